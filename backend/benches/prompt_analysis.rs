@@ -0,0 +1,44 @@
+//! Prompt analysis on long prompts: auto-tier classification and view-hint
+//! detection, for a short instruction and a 100 KB pasted-code prompt.
+//!
+//! Run with `cargo bench --bench prompt_analysis`.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use claudehydra_backend::handlers::prompt::prompt_complexity;
+use claudehydra_backend::handlers::streaming::helpers::detect_view_hints;
+
+const INSTRUCTION: &str = "Refactor the token usage analytics so the cost per model is cached.";
+
+/// `INSTRUCTION` followed by roughly 100 KB of fenced code.
+fn pasted_code() -> String {
+    let line = "    let total = usage.iter().map(|u| u.tokens * price(u.model)).sum::<f64>();\n";
+    let body = line.repeat(100_000 / line.len());
+    format!("{}\n```rust\n{}```\n", INSTRUCTION, body)
+}
+
+/// ~100 KB of log output with no code fence, the worst case for the prefix cap.
+fn pasted_logs() -> String {
+    let line = "2026-01-01T10:00:00Z ERROR swarm: peer probe failed, retrying in 30s\n";
+    format!("{}\n{}", INSTRUCTION, line.repeat(100_000 / line.len()))
+}
+
+fn bench_prompt_analysis(c: &mut Criterion) {
+    let inputs = [
+        ("short", INSTRUCTION.to_string()),
+        ("code_100k", pasted_code()),
+        ("logs_100k", pasted_logs()),
+    ];
+    for (name, prompt) in &inputs {
+        c.bench_function(&format!("prompt_complexity/{}", name), |b| {
+            b.iter(|| prompt_complexity(black_box(prompt)))
+        });
+        c.bench_function(&format!("detect_view_hints/{}", name), |b| {
+            b.iter(|| detect_view_hints(black_box(prompt)))
+        });
+    }
+}
+
+criterion_group!(benches, bench_prompt_analysis);
+criterion_main!(benches);
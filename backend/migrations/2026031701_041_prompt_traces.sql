@@ -0,0 +1,14 @@
+-- Per-prompt lifecycle trace events (see src/prompt_trace.rs).
+-- One row per stage event; written in a single batch when an execution ends.
+
+CREATE TABLE IF NOT EXISTS ch_prompt_traces (
+    execution_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    stage TEXT NOT NULL,
+    elapsed_ms BIGINT NOT NULL DEFAULT 0,
+    detail JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (execution_id, seq)
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_prompt_traces_created_at ON ch_prompt_traces (created_at);
@@ -0,0 +1,17 @@
+-- Post-response hook runs (see src/hooks.rs).
+-- One row per hook execution, linked to the prompt trace by execution_id.
+
+CREATE TABLE IF NOT EXISTS ch_hook_runs (
+    id BIGSERIAL PRIMARY KEY,
+    execution_id TEXT NOT NULL,
+    hook TEXT NOT NULL,
+    event TEXT NOT NULL,
+    exit_code INTEGER,
+    timed_out BOOLEAN NOT NULL DEFAULT FALSE,
+    duration_ms BIGINT NOT NULL DEFAULT 0,
+    stdout TEXT NOT NULL DEFAULT '',
+    stderr TEXT NOT NULL DEFAULT '',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_hook_runs_execution_id ON ch_hook_runs (execution_id);
@@ -0,0 +1,23 @@
+-- Local RAG index over the project directory (see src/rag.rs).
+-- One row per indexed file (content hash drives incremental reindexing) and
+-- one row per embedded chunk.
+
+CREATE TABLE IF NOT EXISTS ch_rag_files (
+    path TEXT PRIMARY KEY,
+    content_hash TEXT NOT NULL,
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    model TEXT NOT NULL DEFAULT '',
+    indexed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS ch_rag_chunks (
+    id BIGSERIAL PRIMARY KEY,
+    path TEXT NOT NULL REFERENCES ch_rag_files (path) ON DELETE CASCADE,
+    chunk_index INTEGER NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    embedding REAL[] NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_rag_chunks_path ON ch_rag_chunks (path);
@@ -0,0 +1,19 @@
+-- Low-priority background prompts executed by the idle scavenger
+-- (see src/idle_scavenger.rs).
+
+CREATE TABLE IF NOT EXISTS ch_background_prompts (
+    id BIGSERIAL PRIMARY KEY,
+    prompt TEXT NOT NULL,
+    model TEXT,
+    priority TEXT NOT NULL DEFAULT 'low' CHECK (priority IN ('low', 'normal')),
+    status TEXT NOT NULL DEFAULT 'queued'
+        CHECK (status IN ('queued', 'running', 'done', 'failed', 'cancelled')),
+    result TEXT,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_background_prompts_queued
+    ON ch_background_prompts (created_at) WHERE status = 'queued';
@@ -0,0 +1,9 @@
+-- Routing rule overlays (see src/rule_updates.rs): the last verified remote
+-- manifest and the user's local overrides. Local always wins.
+
+CREATE TABLE IF NOT EXISTS ch_rule_overlays (
+    source TEXT PRIMARY KEY CHECK (source IN ('remote', 'local')),
+    version BIGINT NOT NULL DEFAULT 0,
+    rules JSONB NOT NULL DEFAULT '{}'::jsonb,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,30 @@
+-- Garbage collection policies per store (see src/gc.rs).
+-- A row is collected when it matches ANY configured limit: older than
+-- max_age_days, beyond the newest max_count rows, or beyond max_bytes of
+-- newest rows. NULL limits are ignored.
+
+CREATE TABLE IF NOT EXISTS ch_gc_policies (
+    store TEXT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    max_age_days INTEGER CHECK (max_age_days IS NULL OR max_age_days > 0),
+    max_count BIGINT CHECK (max_count IS NULL OR max_count > 0),
+    max_bytes BIGINT CHECK (max_bytes IS NULL OR max_bytes > 0),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Every policy ships disabled: nothing is deleted until the user turns it on.
+-- The ages are suggested defaults for when they do.
+INSERT INTO ch_gc_policies (store, enabled, max_age_days) VALUES
+    ('sessions', FALSE, 180),
+    ('prompt_traces', FALSE, 30),
+    ('hook_runs', FALSE, 30),
+    ('background_prompts', FALSE, 30),
+    ('ocr_history', FALSE, 90),
+    ('agent_usage', FALSE, 365),
+    ('tool_interactions', FALSE, 365),
+    ('a2a_tasks', FALSE, 30),
+    ('swarm_tasks', FALSE, 30),
+    ('sandbox_executions', FALSE, 30),
+    ('compression_stats', FALSE, 90),
+    ('web_vitals', FALSE, 30)
+ON CONFLICT (store) DO NOTHING;
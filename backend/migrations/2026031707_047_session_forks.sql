@@ -0,0 +1,12 @@
+-- Conversation branching: a fork remembers the session and message it was
+-- cut from (see handlers::sessions::fork_session). Deleting the original
+-- keeps the fork.
+
+ALTER TABLE ch_sessions
+    ADD COLUMN IF NOT EXISTS forked_from_session_id UUID
+        REFERENCES ch_sessions(id) ON DELETE SET NULL;
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS forked_from_message_id UUID;
+
+CREATE INDEX IF NOT EXISTS idx_ch_sessions_forked_from
+    ON ch_sessions (forked_from_session_id)
+    WHERE forked_from_session_id IS NOT NULL;
@@ -0,0 +1,15 @@
+-- Pinned conversation summaries for context compaction (see src/compaction.rs).
+-- Messages created at or before covers_until are represented by the summary
+-- when building the model context; ch_messages itself is left untouched.
+
+CREATE TABLE IF NOT EXISTS ch_session_summaries (
+    session_id UUID PRIMARY KEY REFERENCES ch_sessions(id) ON DELETE CASCADE,
+    summary TEXT NOT NULL,
+    covers_until TIMESTAMPTZ NOT NULL,
+    summarized_messages INTEGER NOT NULL DEFAULT 0,
+    tokens_before INTEGER NOT NULL DEFAULT 0,
+    tokens_after INTEGER NOT NULL DEFAULT 0,
+    model TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,5 @@
+-- Per-session permission profile (see src/permissions.rs).
+-- NULL inherits CH_PERMISSION_MODE.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS permission_mode TEXT
+    CHECK (permission_mode IS NULL OR permission_mode IN ('yolo', 'ask', 'read_only'));
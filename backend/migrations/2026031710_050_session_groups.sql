@@ -0,0 +1,18 @@
+-- Session groups: named, colored folders in the session sidebar
+-- (see src/handlers/session_groups.rs). Deleting a group ungroups its sessions.
+
+CREATE TABLE IF NOT EXISTS ch_session_groups (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL,
+    color TEXT NOT NULL DEFAULT 'gray',
+    collapsed BOOLEAN NOT NULL DEFAULT FALSE,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS group_id UUID
+    REFERENCES ch_session_groups(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_ch_sessions_group
+    ON ch_sessions (group_id)
+    WHERE group_id IS NOT NULL;
@@ -0,0 +1,4 @@
+-- Per-session read state for unread badges. Assistant messages newer than
+-- last_viewed_at count as unread; existing sessions start out fully read.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS last_viewed_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
@@ -0,0 +1,4 @@
+-- Pinned model per session. Set when a conversation is duplicated onto another
+-- provider; used whenever a chat request for the session names no model.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS model TEXT;
@@ -0,0 +1,6 @@
+-- Preset extras copied onto a session when it is created from a preset
+-- (see src/presets.rs): extra system prompt and pinned files whose contents
+-- are appended to the server-side system prompt on every turn.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS system_prompt TEXT;
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS pinned_files TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,5 @@
+-- Per-session idle timeout for the persistent Claude CLI process
+-- (see src/claude_cli.rs). NULL uses CH_CLAUDE_CLI_IDLE_SECS, 0 never reaps.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS cli_idle_secs INTEGER
+    CHECK (cli_idle_secs IS NULL OR cli_idle_secs >= 0);
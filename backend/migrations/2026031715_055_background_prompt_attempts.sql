@@ -0,0 +1,6 @@
+-- Crash recovery for background prompts (see src/idle_scavenger.rs):
+-- attempts counts claims, interrupted counts runs cut short by a crash or
+-- shutdown. Interrupted prompts are retried until attempts reach the limit.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS interrupted INTEGER NOT NULL DEFAULT 0;
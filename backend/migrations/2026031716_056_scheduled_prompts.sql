@@ -0,0 +1,26 @@
+-- Scheduled and recurring prompts (see src/scheduled_prompts.rs). Due runs
+-- are enqueued into ch_background_prompts; runs bound to a session store
+-- their prompt and reply in it.
+
+CREATE TABLE IF NOT EXISTS ch_scheduled_prompts (
+    id BIGSERIAL PRIMARY KEY,
+    session_id UUID REFERENCES ch_sessions(id) ON DELETE CASCADE,
+    prompt TEXT NOT NULL,
+    model TEXT,
+    -- NULL for one-shot schedules (delay / timestamp)
+    cron TEXT,
+    catch_up TEXT NOT NULL DEFAULT 'once' CHECK (catch_up IN ('skip', 'once', 'all')),
+    next_run_at TIMESTAMPTZ,
+    last_run_at TIMESTAMPTZ,
+    run_count INTEGER NOT NULL DEFAULT 0,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_scheduled_prompts_due
+    ON ch_scheduled_prompts (next_run_at) WHERE enabled;
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS session_id UUID
+    REFERENCES ch_sessions(id) ON DELETE SET NULL;
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS scheduled_prompt_id BIGINT
+    REFERENCES ch_scheduled_prompts(id) ON DELETE SET NULL;
@@ -0,0 +1,7 @@
+-- Per-prompt execution limits for background prompts (see src/idle_scavenger.rs):
+-- timeout_ms bounds a single run, deadline is an absolute cut-off. Either one
+-- being exceeded fails the prompt with a "Timeout" error.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS timeout_ms INTEGER
+    CHECK (timeout_ms IS NULL OR timeout_ms > 0);
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS deadline TIMESTAMPTZ;
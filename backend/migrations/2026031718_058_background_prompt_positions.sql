@@ -0,0 +1,16 @@
+-- Explicit execution order for queued background prompts (see
+-- src/idle_scavenger.rs). New prompts go to the end; reordering rewrites the
+-- positions of the queued prompts.
+
+CREATE SEQUENCE IF NOT EXISTS ch_background_prompts_position_seq;
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS position BIGINT;
+UPDATE ch_background_prompts SET position = id WHERE position IS NULL;
+SELECT setval('ch_background_prompts_position_seq',
+              GREATEST((SELECT MAX(id) FROM ch_background_prompts), 1));
+ALTER TABLE ch_background_prompts
+    ALTER COLUMN position SET DEFAULT nextval('ch_background_prompts_position_seq'),
+    ALTER COLUMN position SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS idx_ch_background_prompts_position
+    ON ch_background_prompts (position) WHERE status = 'queued';
@@ -0,0 +1,19 @@
+-- Retry policy for failed background prompts (see src/idle_scavenger.rs):
+-- retries counts re-enqueues after a failure; every run is recorded in
+-- ch_background_prompt_attempts so the UI can show the retry history.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS retries INTEGER NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS ch_background_prompt_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    prompt_id BIGINT NOT NULL REFERENCES ch_background_prompts(id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    model TEXT,
+    status TEXT NOT NULL CHECK (status IN ('done', 'failed')),
+    error TEXT,
+    duration_ms BIGINT NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_background_prompt_attempts_prompt
+    ON ch_background_prompt_attempts (prompt_id, attempt);
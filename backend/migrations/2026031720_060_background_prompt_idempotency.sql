@@ -0,0 +1,8 @@
+-- Idempotency keys for background prompt enqueue (see src/idle_scavenger.rs):
+-- a repeated key within the window returns the existing prompt.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS idempotency_key TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ch_background_prompts_idempotency
+    ON ch_background_prompts (idempotency_key, created_at DESC)
+    WHERE idempotency_key IS NOT NULL;
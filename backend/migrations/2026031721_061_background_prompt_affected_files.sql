@@ -0,0 +1,4 @@
+-- Files a background prompt is expected to touch (see src/affected_files.rs),
+-- used to flag prompts that would conflict with each other.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS affected_files TEXT[] NOT NULL DEFAULT '{}';
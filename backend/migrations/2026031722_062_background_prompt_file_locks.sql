@@ -0,0 +1,5 @@
+-- Advisory file locks between background prompts (see src/idle_scavenger.rs):
+-- override_lock lets a prompt start even while another running prompt holds
+-- one of its affected files.
+
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS override_lock BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Witcher mode: per-session routing of background prompts to a provider/model
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS witcher_mode BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS route_decision JSONB;
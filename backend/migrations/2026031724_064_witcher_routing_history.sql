@@ -0,0 +1,22 @@
+-- Witcher routing history (see src/witcher_router.rs): one row per routed
+-- background prompt; latency, success and estimated cost are filled in when
+-- its run finishes. Rows outlive the prompt so analytics keep the history.
+
+CREATE TABLE IF NOT EXISTS ch_witcher_routing (
+    id BIGSERIAL PRIMARY KEY,
+    prompt_id BIGINT REFERENCES ch_background_prompts(id) ON DELETE SET NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    task_type TEXT NOT NULL,
+    complexity TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    decided_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    latency_ms BIGINT,
+    success BOOLEAN,
+    cost_usd DOUBLE PRECISION
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_witcher_routing_decided
+    ON ch_witcher_routing (decided_at DESC);
+CREATE INDEX IF NOT EXISTS idx_ch_witcher_routing_prompt
+    ON ch_witcher_routing (prompt_id);
@@ -0,0 +1,7 @@
+-- Token usage per message: prompt/completion tokens as reported by the
+-- provider (Ollama prompt_eval_count/eval_count, API usage), or estimated
+-- (~4 chars per token) for sources that report none, such as CLIs.
+
+ALTER TABLE ch_messages ADD COLUMN IF NOT EXISTS prompt_tokens INTEGER;
+ALTER TABLE ch_messages ADD COLUMN IF NOT EXISTS completion_tokens INTEGER;
+ALTER TABLE ch_messages ADD COLUMN IF NOT EXISTS tokens_estimated BOOLEAN NOT NULL DEFAULT FALSE;
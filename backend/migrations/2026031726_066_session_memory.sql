@@ -0,0 +1,16 @@
+-- Long-term conversation memory (see src/session_memory.rs): one row per
+-- completed exchange with its embedding, recalled by similarity on later
+-- prompts. `model` is the embedding model; vectors of other models are
+-- ignored at recall.
+
+CREATE TABLE IF NOT EXISTS ch_session_memory (
+    id BIGSERIAL PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES ch_sessions(id) ON DELETE CASCADE,
+    prompt TEXT NOT NULL,
+    response TEXT NOT NULL,
+    model TEXT NOT NULL,
+    embedding REAL[] NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_session_memory_session ON ch_session_memory (session_id, created_at DESC);
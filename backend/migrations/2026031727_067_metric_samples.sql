@@ -0,0 +1,17 @@
+-- Historical metrics (see src/metrics_history.rs): one row per metric per
+-- sampling tick, downsampled at query time for charts. Retention is the
+-- `metric_samples` GC policy, which ships disabled like every other one.
+
+CREATE TABLE IF NOT EXISTS ch_metric_samples (
+    id BIGSERIAL PRIMARY KEY,
+    metric TEXT NOT NULL,
+    value DOUBLE PRECISION NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_metric_samples_metric ON ch_metric_samples (metric, created_at);
+CREATE INDEX IF NOT EXISTS idx_ch_metric_samples_created ON ch_metric_samples (created_at);
+
+INSERT INTO ch_gc_policies (store, enabled, max_age_days) VALUES
+    ('metric_samples', FALSE, 30)
+ON CONFLICT (store) DO NOTHING;
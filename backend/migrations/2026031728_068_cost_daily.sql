@@ -0,0 +1,27 @@
+-- Cost accounting (see src/costs.rs): daily aggregates per provider, model
+-- and session (tab). `baseline_cost_usd` is what the same tokens would have
+-- cost on the baseline model, so savings = baseline - actual.
+
+CREATE TABLE IF NOT EXISTS ch_cost_daily (
+    id BIGSERIAL PRIMARY KEY,
+    day DATE NOT NULL,
+    provider TEXT NOT NULL,
+    model TEXT NOT NULL,
+    -- No FK: costs outlive deleted sessions.
+    session_id UUID,
+    requests BIGINT NOT NULL DEFAULT 0,
+    estimated_requests BIGINT NOT NULL DEFAULT 0,
+    input_tokens BIGINT NOT NULL DEFAULT 0,
+    output_tokens BIGINT NOT NULL DEFAULT 0,
+    cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    baseline_cost_usd DOUBLE PRECISION NOT NULL DEFAULT 0,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- One row per (day, provider, model, session); requests without a session
+-- share the all-zero UUID slot.
+CREATE UNIQUE INDEX IF NOT EXISTS idx_ch_cost_daily_key ON ch_cost_daily (
+    day, provider, model,
+    (COALESCE(session_id, '00000000-0000-0000-0000-000000000000'::UUID))
+);
+CREATE INDEX IF NOT EXISTS idx_ch_cost_daily_day ON ch_cost_daily (day);
@@ -0,0 +1,8 @@
+-- Correlation id of the request that enqueued a background prompt (see
+-- src/correlation.rs); prompts enqueued without one (schedules) get their own.
+
+ALTER TABLE ch_background_prompts
+    ADD COLUMN IF NOT EXISTS correlation_id TEXT NOT NULL DEFAULT gen_random_uuid()::TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ch_background_prompts_correlation
+    ON ch_background_prompts (correlation_id);
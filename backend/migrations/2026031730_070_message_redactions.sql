@@ -0,0 +1,4 @@
+-- Secrets replaced in a message (content and tool interactions) before it was
+-- stored (see src/redaction.rs).
+
+ALTER TABLE ch_messages ADD COLUMN IF NOT EXISTS redactions INT NOT NULL DEFAULT 0;
@@ -0,0 +1,50 @@
+-- Project registry: named workspaces the backend can switch between
+-- (see src/projects.rs). Sessions and background prompts remember the
+-- project that was active when they were created.
+
+CREATE TABLE IF NOT EXISTS ch_projects (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name TEXT NOT NULL UNIQUE,
+    path TEXT NOT NULL,
+    default_provider TEXT,
+    presets TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ
+);
+
+ALTER TABLE ch_settings ADD COLUMN IF NOT EXISTS active_project_id UUID
+    REFERENCES ch_projects(id) ON DELETE SET NULL;
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS project_id UUID
+    REFERENCES ch_projects(id) ON DELETE SET NULL;
+ALTER TABLE ch_background_prompts ADD COLUMN IF NOT EXISTS project_id UUID
+    REFERENCES ch_projects(id) ON DELETE SET NULL;
+
+CREATE INDEX IF NOT EXISTS idx_ch_sessions_project
+    ON ch_sessions (project_id)
+    WHERE project_id IS NOT NULL;
+
+-- Rows inserted without a project belong to the active one.
+CREATE OR REPLACE FUNCTION ch_default_project_id() RETURNS trigger AS $$
+BEGIN
+    IF NEW.project_id IS NULL THEN
+        NEW.project_id := (SELECT active_project_id FROM ch_settings WHERE id = 1);
+    END IF;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'trg_ch_sessions_project') THEN
+        CREATE TRIGGER trg_ch_sessions_project
+            BEFORE INSERT ON ch_sessions
+            FOR EACH ROW
+            EXECUTE FUNCTION ch_default_project_id();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'trg_ch_background_prompts_project') THEN
+        CREATE TRIGGER trg_ch_background_prompts_project
+            BEFORE INSERT ON ch_background_prompts
+            FOR EACH ROW
+            EXECUTE FUNCTION ch_default_project_id();
+    END IF;
+END $$;
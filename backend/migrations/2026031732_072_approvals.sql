@@ -0,0 +1,27 @@
+-- Approval requests for dangerous operations outside YOLO mode
+-- (see src/approvals.rs): mutating tool calls on the WebSocket path and
+-- background prompts classified as system operations.
+
+CREATE TABLE IF NOT EXISTS ch_approvals (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    kind TEXT NOT NULL CHECK (kind IN ('tool', 'prompt')),
+    subject TEXT NOT NULL,
+    operation TEXT NOT NULL,
+    detail JSONB NOT NULL DEFAULT '{}',
+    session_id UUID REFERENCES ch_sessions(id) ON DELETE SET NULL,
+    prompt_id BIGINT REFERENCES ch_background_prompts(id) ON DELETE CASCADE,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'denied', 'expired')),
+    reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_approvals_pending
+    ON ch_approvals (created_at)
+    WHERE status = 'pending';
+
+-- Held prompts wait outside the queue until their approval is decided.
+ALTER TABLE ch_background_prompts DROP CONSTRAINT IF EXISTS ch_background_prompts_status_check;
+ALTER TABLE ch_background_prompts ADD CONSTRAINT ch_background_prompts_status_check
+    CHECK (status IN ('queued', 'running', 'done', 'failed', 'cancelled', 'awaiting_approval'));
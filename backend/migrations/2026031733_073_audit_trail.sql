@@ -0,0 +1,41 @@
+-- Append-only, hash-chained trail of executed commands and file changes
+-- (see src/audit_trail.rs). Each row's hash covers the previous row's hash,
+-- so any edit or removal breaks the chain from that row on.
+
+CREATE TABLE IF NOT EXISTS ch_audit_trail (
+    seq BIGSERIAL PRIMARY KEY,
+    recorded_at TIMESTAMPTZ NOT NULL,
+    kind TEXT NOT NULL CHECK (kind IN ('command', 'file_change')),
+    source TEXT NOT NULL,
+    session_ids TEXT[] NOT NULL DEFAULT '{}',
+    prompt_ids BIGINT[] NOT NULL DEFAULT '{}',
+    detail JSONB NOT NULL DEFAULT '{}',
+    prev_hash TEXT NOT NULL,
+    hash TEXT NOT NULL UNIQUE
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_audit_trail_recorded
+    ON ch_audit_trail (recorded_at);
+
+-- Rows can be added, never changed or removed.
+CREATE OR REPLACE FUNCTION ch_audit_trail_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'ch_audit_trail is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'trg_ch_audit_trail_append_only') THEN
+        CREATE TRIGGER trg_ch_audit_trail_append_only
+            BEFORE UPDATE OR DELETE ON ch_audit_trail
+            FOR EACH ROW
+            EXECUTE FUNCTION ch_audit_trail_append_only();
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgname = 'trg_ch_audit_trail_no_truncate') THEN
+        CREATE TRIGGER trg_ch_audit_trail_no_truncate
+            BEFORE TRUNCATE ON ch_audit_trail
+            FOR EACH STATEMENT
+            EXECUTE FUNCTION ch_audit_trail_append_only();
+    END IF;
+END $$;
@@ -0,0 +1,13 @@
+-- Token quota per session (tab), see src/tab_quota.rs. `token_quota` NULL
+-- means unlimited; `quota_used_tokens` counts tokens since the quota was
+-- set. A session with `quota_exceeded_at` set is paused: the queue executor
+-- holds its background prompts until the quota is raised.
+
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS token_quota BIGINT
+    CHECK (token_quota IS NULL OR token_quota > 0);
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS quota_used_tokens BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE ch_sessions ADD COLUMN IF NOT EXISTS quota_exceeded_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_ch_sessions_quota_exceeded
+    ON ch_sessions (id)
+    WHERE quota_exceeded_at IS NOT NULL;
@@ -0,0 +1,10 @@
+-- Embedding model of each RAG chunk vector (see src/rag.rs). Vectors from
+-- different models are not comparable: chunks whose model differs from the
+-- configured one are left out of search and re-embedded on the next pass.
+
+ALTER TABLE ch_rag_chunks ADD COLUMN IF NOT EXISTS model TEXT NOT NULL DEFAULT '';
+
+UPDATE ch_rag_chunks c
+SET model = f.model
+FROM ch_rag_files f
+WHERE f.path = c.path AND c.model = '';
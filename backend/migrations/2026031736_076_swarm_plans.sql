@@ -0,0 +1,20 @@
+-- Swarm dry-run plans (see src/swarm.rs): each `POST /api/swarm/plan` stores
+-- its plan and the delegate body it would submit, so the plan can later be
+-- approved (optionally edited) or rejected by id.
+
+CREATE TABLE IF NOT EXISTS ch_swarm_plans (
+    id UUID PRIMARY KEY,
+    status TEXT NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'approved', 'rejected')),
+    plan JSONB NOT NULL,
+    request JSONB NOT NULL,
+    delegate_response JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    decided_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_ch_swarm_plans_created ON ch_swarm_plans (created_at);
+
+INSERT INTO ch_gc_policies (store, enabled, max_age_days) VALUES
+    ('swarm_plans', FALSE, 30)
+ON CONFLICT (store) DO NOTHING;
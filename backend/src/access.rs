@@ -0,0 +1,273 @@
+//! Network exposure: bind address, LAN access guard and CORS origins.
+//!
+//! The backend listens on `CH_BIND_ADDR` (default `127.0.0.1`, i.e. this
+//! machine only) and `PORT` (default 8082), or `[server]` in the config file
+//! (`hydra_config.rs`) when those are unset. To use it from other devices on
+//! a LAN, bind `0.0.0.0` (or one interface address) and set credentials:
+//!
+//! - `CH_BASIC_AUTH=user:password` — HTTP Basic auth for every request that
+//!   does not come from loopback. `Authorization: Bearer <AUTH_SECRET>` and
+//!   `?token=<AUTH_SECRET>` (WebSockets) are accepted as well. Without it,
+//!   only the routes that check `AUTH_SECRET` themselves are protected.
+//! - `CH_AUTH_LOOPBACK=true` (`access.auth_loopback`) — require credentials
+//!   from loopback clients too (default: the local launcher and browser tabs
+//!   are trusted).
+//!
+//! `/api/health*` stays open for container health checks, and CORS
+//! preflights are answered before the guard. `CH_CORS_ORIGINS`
+//! (comma-separated, e.g. `http://192.168.1.20:5199`; `access.cors_origins`
+//! in the file, applied on restart) replaces the built-in hosted-frontend
+//! origins; local dev origins are always allowed. Credentials are env-only.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{HeaderValue, Method, StatusCode, header};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::Engine as _;
+use subtle::ConstantTimeEq;
+
+use crate::hydra_config::{Derived, HydraConfig, env, flag, text};
+
+/// Hosted frontend origins used when `CH_CORS_ORIGINS` is unset.
+const DEFAULT_EXTRA_ORIGINS: &[&str] = &[
+    "https://claudehydra-v4.vercel.app",
+    "https://claudehydra-v4-pawelserkowskis-projects.vercel.app",
+];
+const DEFAULT_PORT: u16 = 8082;
+
+#[derive(Debug, Clone)]
+pub struct AccessConfig {
+    pub bind: SocketAddr,
+    /// Expected `Authorization: Basic …` value.
+    basic_auth: Option<String>,
+    bearer_secret: Option<String>,
+    pub auth_loopback: bool,
+    pub cors_origins: Vec<String>,
+}
+
+/// `CH_BIND_ADDR` (IP, or `IP:port` overriding `PORT`).
+pub(crate) fn parse_bind(addr: Option<&str>, port: Option<&str>) -> Result<SocketAddr, String> {
+    let port = match port {
+        Some(p) => p
+            .parse::<u16>()
+            .map_err(|_| format!("PORT must be a port number, got '{}'", p))?,
+        None => DEFAULT_PORT,
+    };
+    match addr {
+        None => Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port)),
+        Some(raw) => raw
+            .parse::<SocketAddr>()
+            .or_else(|_| raw.parse::<IpAddr>().map(|ip| SocketAddr::new(ip, port)))
+            .map_err(|_| {
+                format!(
+                    "CH_BIND_ADDR must be an IP address or IP:port, got '{}'",
+                    raw
+                )
+            }),
+    }
+}
+
+fn basic_header(credentials: &str) -> String {
+    format!(
+        "Basic {}",
+        base64::engine::general_purpose::STANDARD.encode(credentials)
+    )
+}
+
+impl AccessConfig {
+    /// Settings from env and the config file.
+    pub fn from_config(config: &HydraConfig) -> Result<Self, String> {
+        let basic_auth = match env("CH_BASIC_AUTH") {
+            Some(creds) if !creds.contains(':') => {
+                return Err("CH_BASIC_AUTH must be 'user:password'".to_string());
+            }
+            creds => creds.map(|c| basic_header(&c)),
+        };
+        let addr = text("CH_BIND_ADDR", config.server.bind.as_deref());
+        let port = env("PORT").or_else(|| config.server.port.map(|p| p.to_string()));
+        let origins: Vec<String> = match env("CH_CORS_ORIGINS") {
+            Some(list) => list.split(',').map(str::to_string).collect(),
+            None => config.access.cors_origins.clone().unwrap_or_else(|| {
+                DEFAULT_EXTRA_ORIGINS
+                    .iter()
+                    .map(|o| o.to_string())
+                    .collect()
+            }),
+        };
+        Ok(Self {
+            bind: parse_bind(addr.as_deref(), port.as_deref())?,
+            basic_auth,
+            bearer_secret: env("AUTH_SECRET"),
+            auth_loopback: flag("CH_AUTH_LOOPBACK", config.access.auth_loopback).unwrap_or(false),
+            cors_origins: origins
+                .iter()
+                .map(|o| o.trim().trim_end_matches('/').to_string())
+                .filter(|o| !o.is_empty())
+                .collect(),
+        })
+    }
+
+    /// Reachable from other machines.
+    pub fn is_exposed(&self) -> bool {
+        !self.bind.ip().is_loopback()
+    }
+
+    /// Whether the guard has any credentials to check.
+    pub fn has_credentials(&self) -> bool {
+        self.basic_auth.is_some() || self.bearer_secret.is_some()
+    }
+
+    /// Whether a request from `peer` with these credentials may pass.
+    /// Without `CH_BASIC_AUTH` the guard is off and routes rely on their
+    /// own `AUTH_SECRET` checks.
+    fn allows(
+        &self,
+        peer: Option<IpAddr>,
+        authorization: Option<&str>,
+        query: Option<&str>,
+    ) -> bool {
+        let Some(expected) = &self.basic_auth else {
+            return true;
+        };
+        if !self.auth_loopback && peer.is_some_and(|ip| ip.is_loopback()) {
+            return true;
+        }
+        let eq = |a: &str, b: &str| bool::from(a.as_bytes().ct_eq(b.as_bytes()));
+        if authorization.is_some_and(|got| eq(got, expected)) {
+            return true;
+        }
+        let Some(secret) = &self.bearer_secret else {
+            return false;
+        };
+        authorization
+            .and_then(|a| a.strip_prefix("Bearer "))
+            .is_some_and(|token| eq(token, secret))
+            || query.is_some_and(|q| {
+                q.split('&')
+                    .filter_map(|pair| pair.strip_prefix("token="))
+                    .any(|token| eq(token, secret))
+            })
+    }
+}
+
+static CONFIG: Derived<Arc<AccessConfig>> = Derived::new(|config| {
+    Arc::new(AccessConfig::from_config(config).unwrap_or_else(|e| {
+        tracing::error!("access: {} — falling back to loopback only", e);
+        AccessConfig {
+            bind: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_PORT),
+            basic_auth: None,
+            bearer_secret: env("AUTH_SECRET"),
+            auth_loopback: false,
+            cors_origins: Vec::new(),
+        }
+    }))
+});
+
+/// Access settings (env overrides the file). Invalid values are reported by
+/// `main` before the server starts.
+pub fn config() -> Arc<AccessConfig> {
+    CONFIG.get()
+}
+
+/// Middleware applied to every route (see module docs).
+pub async fn guard(request: Request, next: Next) -> Response {
+    let cfg = config();
+    let path = request.uri().path();
+    if request.method() == Method::OPTIONS || path.starts_with("/api/health") {
+        return next.run(request).await;
+    }
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip());
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if cfg.allows(peer, authorization, request.uri().query()) {
+        return next.run(request).await;
+    }
+    tracing::warn!(
+        "access: rejected {} {} from {}",
+        request.method(),
+        path,
+        peer.map(|ip| ip.to_string()).unwrap_or_default()
+    );
+    let mut response = StatusCode::UNAUTHORIZED.into_response();
+    if cfg.basic_auth.is_some() {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"ClaudeHydra\", charset=\"UTF-8\""),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(basic: Option<&str>, secret: Option<&str>) -> AccessConfig {
+        AccessConfig {
+            bind: "0.0.0.0:8082".parse().unwrap(),
+            basic_auth: basic.map(basic_header),
+            bearer_secret: secret.map(str::to_string),
+            auth_loopback: false,
+            cors_origins: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bind_address_defaults_to_loopback() {
+        assert_eq!(
+            parse_bind(None, None).unwrap().to_string(),
+            "127.0.0.1:8082"
+        );
+        assert_eq!(
+            parse_bind(Some("0.0.0.0"), Some("9000"))
+                .unwrap()
+                .to_string(),
+            "0.0.0.0:9000"
+        );
+        assert_eq!(
+            parse_bind(Some("192.168.1.5:8100"), Some("9000"))
+                .unwrap()
+                .to_string(),
+            "192.168.1.5:8100"
+        );
+        assert!(parse_bind(Some("localhost"), None).is_err());
+        assert!(parse_bind(None, Some("http")).is_err());
+    }
+
+    #[test]
+    fn lan_requests_need_credentials() {
+        let lan: Option<IpAddr> = Some("192.168.1.20".parse().unwrap());
+        let local: Option<IpAddr> = Some("127.0.0.1".parse().unwrap());
+        let cfg = access(Some("admin:pw"), Some("s3cret"));
+        assert!(cfg.allows(local, None, None));
+        assert!(!cfg.allows(lan, None, None));
+        assert!(cfg.allows(lan, Some("Basic YWRtaW46cHc="), None));
+        assert!(!cfg.allows(lan, Some(&basic_header("admin:nope")), None));
+        assert!(cfg.allows(lan, Some("Bearer s3cret"), None));
+        assert!(cfg.allows(lan, None, Some("topics=logs&token=s3cret")));
+        assert!(!cfg.allows(lan, None, Some("token=wrong")));
+
+        let strict = AccessConfig {
+            auth_loopback: true,
+            ..cfg
+        };
+        assert!(!strict.allows(local, None, None));
+    }
+
+    #[test]
+    fn guard_is_off_without_basic_auth() {
+        let lan: Option<IpAddr> = Some("10.0.0.3".parse().unwrap());
+        assert!(access(None, None).allows(lan, None, None));
+        assert!(access(None, Some("s")).allows(lan, None, None));
+        assert!(!access(Some("a:b"), None).allows(lan, None, None));
+        assert!(!access(Some("a:b"), None).allows(lan, Some("Bearer s"), None));
+    }
+}
@@ -0,0 +1,423 @@
+//! Affected-files inference — which workspace files a background prompt is
+//! likely to touch, so prompts working on the same files can be flagged as
+//! conflicting.
+//!
+//! Inference runs once, before a prompt is enqueued (unless the client sends
+//! `affected_files` itself):
+//! 1. Paths mentioned in the prompt are extracted (`src/main.rs`,
+//!    `./docs/*.md`, ...). URLs are ignored.
+//! 2. Globs are expanded against the workspace (up to `MAX_GLOB_MATCHES`
+//!    files each, visiting at most `MAX_GLOB_VISITS` entries within
+//!    `GLOB_TIMEOUT`, off the async runtime); absolute globs and globs with
+//!    `..` are dropped so a pattern can never walk outside the workspace.
+//!    Plain paths are kept relative to the workspace even if they do not
+//!    exist yet, since the prompt may create them.
+//!    Directories (`src/session/` or an existing directory) and recursive
+//!    globs (`src/session/**`) are kept as patterns covering a whole subtree.
+//! 3. With `CH_AFFECTED_FILES_MODEL` set (an Ollama model, e.g.
+//!    `llama3.1:8b`) the model is additionally asked to predict the files;
+//!    failures or timeouts fall back to the regex result.
+//!
+//! Paths are stored workspace-relative with `/` separators.
+//!
+//! Two prompts conflict when an entry of one overlaps an entry of the other:
+//! equal files, a file matched by a pattern, or two patterns whose subtrees
+//! intersect. `overlaps` reports the broader entry of each overlapping pair.
+
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::state::AppState;
+
+const MAX_AFFECTED_FILES: usize = 100;
+const MAX_GLOB_MATCHES: usize = 50;
+/// Matches (files or not) a single glob may produce before it is cut off.
+const MAX_GLOB_VISITS: usize = 5_000;
+const GLOB_TIMEOUT: Duration = Duration::from_secs(2);
+const PREDICTION_TIMEOUT: Duration = Duration::from_secs(15);
+
+static URL_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[a-zA-Z][a-zA-Z0-9+.-]*://\S+").expect("valid regex"));
+
+/// A path-like token ending in a file extension; globs allowed.
+static PATH_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[\s`'\x22(\[<,])((?:\.{1,2}/|/)?(?:[\w.*\-]+/)*[\w*\-][\w.*\-]*\.[A-Za-z0-9*]{1,10})")
+        .expect("valid regex")
+});
+
+/// Filters out version numbers ("v1.2") and abbreviations ("e.g") that
+/// match the path pattern.
+fn looks_like_file(path: &str) -> bool {
+    let Some((_, ext)) = path.rsplit_once('.') else {
+        return false;
+    };
+    !ext.chars().all(|c| c.is_ascii_digit()) && (ext.len() >= 2 || path.contains('/'))
+}
+
+/// Paths mentioned in `prompt`, in order of appearance, deduplicated.
+fn extract_paths(prompt: &str) -> Vec<String> {
+    let text = URL_RE.replace_all(prompt, " ");
+    let mut paths: Vec<String> = Vec::new();
+    for cap in PATH_RE.captures_iter(&text) {
+        let path = cap[1].trim_end_matches('.').to_string();
+        if !looks_like_file(&path) {
+            continue;
+        }
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// `raw` relative to `workspace` with `/` separators, or `None` when it
+/// points outside of it.
+fn relative_to(workspace: &Path, raw: &str) -> Option<String> {
+    let raw = raw.replace('\\', "/");
+    let path = Path::new(&raw);
+    let rel = if path.is_absolute() {
+        path.strip_prefix(workspace)
+            .ok()?
+            .to_string_lossy()
+            .replace('\\', "/")
+    } else {
+        raw.trim_start_matches("./").to_string()
+    };
+    (!rel.is_empty() && !rel.split('/').any(|part| part == "..")).then_some(rel)
+}
+
+/// Whether `entry` is a pattern rather than a single file.
+pub fn is_pattern(entry: &str) -> bool {
+    entry.contains(['*', '?', '['])
+}
+
+/// Whether a glob stays inside the directory it is expanded in: no
+/// absolute or drive-rooted pattern and no `..` component.
+fn is_contained_glob(pattern: &str) -> bool {
+    let pattern = pattern.replace('\\', "/");
+    !pattern.starts_with('/')
+        && pattern.as_bytes().get(1) != Some(&b':')
+        && !Path::new(&pattern).is_absolute()
+        && !pattern.split('/').any(|part| part == "..")
+}
+
+/// Files matched by a contained glob under `workspace`, bounded in matches,
+/// visited entries and time. The walk runs on the blocking pool.
+async fn expand_glob(workspace: &Path, candidate: &str) -> Vec<String> {
+    let workspace = workspace.to_path_buf();
+    let pattern = workspace.join(candidate.trim_start_matches("./"));
+    let walk = tokio::task::spawn_blocking(move || {
+        let deadline = Instant::now() + GLOB_TIMEOUT;
+        let Ok(entries) = glob::glob(&pattern.to_string_lossy()) else {
+            return Vec::new();
+        };
+        entries
+            .take(MAX_GLOB_VISITS)
+            .take_while(|_| Instant::now() < deadline)
+            .filter_map(Result::ok)
+            .filter(|p| p.is_file())
+            .take(MAX_GLOB_MATCHES)
+            .filter_map(|p| relative_to(&workspace, &p.to_string_lossy()))
+            .collect()
+    });
+    match tokio::time::timeout(GLOB_TIMEOUT, walk).await {
+        Ok(Ok(files)) => files,
+        Ok(Err(e)) => {
+            tracing::warn!("affected_files: glob expansion failed: {}", e);
+            Vec::new()
+        }
+        Err(_) => {
+            tracing::debug!("affected_files: glob '{}' timed out", candidate);
+            Vec::new()
+        }
+    }
+}
+
+/// Expand globs against the workspace and normalize plain paths; keep
+/// directories and recursive globs as subtree patterns.
+async fn resolve(workspace: &Path, candidates: &[String]) -> Vec<String> {
+    let mut files = Vec::new();
+    for candidate in candidates {
+        if is_pattern(candidate) && !is_contained_glob(candidate) {
+            tracing::debug!("affected_files: dropping glob '{}' outside the workspace", candidate);
+            continue;
+        }
+        if candidate.contains("**") {
+            files.extend(relative_to(workspace, candidate));
+        } else if candidate.contains('*') {
+            // Without a workspace there is nothing to expand against.
+            if workspace.as_os_str().is_empty() {
+                continue;
+            }
+            files.extend(expand_glob(workspace, candidate).await);
+        } else if let Some(rel) = relative_to(workspace, candidate) {
+            let is_dir = rel.ends_with('/')
+                || (!workspace.as_os_str().is_empty() && workspace.join(&rel).is_dir());
+            if is_dir {
+                files.push(format!("{}/**", rel.trim_end_matches('/')));
+            } else {
+                files.push(rel);
+            }
+        }
+    }
+    files
+}
+
+/// Lenient parse of a model reply that should contain a JSON array of paths.
+fn parse_prediction(reply: &str) -> Vec<String> {
+    let (Some(start), Some(end)) = (reply.find('['), reply.rfind(']')) else {
+        return Vec::new();
+    };
+    if end < start {
+        return Vec::new();
+    }
+    serde_json::from_str::<Vec<String>>(&reply[start..=end]).unwrap_or_default()
+}
+
+async fn predict(state: &AppState, model: &str, prompt: &str, workspace: &str) -> Vec<String> {
+    let question = format!(
+        "A coding assistant working in `{}` will receive the task below. \
+         List the files it will most likely modify, as a JSON array of paths \
+         relative to that directory. Reply with the JSON array only.\n\nTask:\n{}",
+        workspace, prompt
+    );
+    let call = crate::queue::complete_ollama(state, model, &[], &question);
+    match tokio::time::timeout(PREDICTION_TIMEOUT, call).await {
+        Ok(Ok(reply)) => parse_prediction(&reply),
+        Ok(Err(e)) => {
+            tracing::debug!("affected_files: prediction failed: {}", e);
+            Vec::new()
+        }
+        Err(_) => {
+            tracing::debug!("affected_files: prediction timed out");
+            Vec::new()
+        }
+    }
+}
+
+/// Infer the files `prompt` will touch inside `workspace`.
+pub async fn infer(state: &AppState, prompt: &str, workspace: &str) -> Vec<String> {
+    let root = Path::new(workspace);
+    let mut candidates = extract_paths(prompt);
+    if let Ok(model) = std::env::var("CH_AFFECTED_FILES_MODEL")
+        && !model.trim().is_empty()
+        && !workspace.is_empty()
+    {
+        candidates.extend(predict(state, model.trim(), prompt, workspace).await);
+    }
+    normalize(root, &candidates).await
+}
+
+/// Resolve, deduplicate and cap a list of paths (client-supplied or inferred).
+pub async fn normalize(workspace: &Path, candidates: &[String]) -> Vec<String> {
+    let mut files = resolve(workspace, candidates).await;
+    files.sort();
+    files.dedup();
+    files.truncate(MAX_AFFECTED_FILES);
+    files
+}
+
+/// Leading path components of `pattern` that contain no wildcard.
+fn literal_prefix(pattern: &str) -> Vec<&str> {
+    pattern
+        .split('/')
+        .take_while(|part| !is_pattern(part))
+        .collect()
+}
+
+/// Exact depth of the paths `pattern` can match, `None` when unbounded (`**`).
+fn depth(pattern: &str) -> Option<usize> {
+    (!pattern.contains("**")).then(|| pattern.split('/').count())
+}
+
+fn matches(pattern: &str, file: &str) -> bool {
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..Default::default()
+    };
+    glob::Pattern::new(pattern).is_ok_and(|p| p.matches_with(file, options))
+}
+
+/// Conservative subtree intersection: the literal prefixes must agree and a
+/// depth-bounded pattern must be able to reach below the other's prefix.
+fn patterns_overlap(a: &str, b: &str) -> bool {
+    let (pa, pb) = (literal_prefix(a), literal_prefix(b));
+    if pa.iter().zip(&pb).any(|(x, y)| x != y) {
+        return false;
+    }
+    match (depth(a), depth(b)) {
+        (Some(da), Some(db)) => da == db,
+        (Some(da), None) => pb.len() < da,
+        (None, Some(db)) => pa.len() < db,
+        (None, None) => true,
+    }
+}
+
+/// The entry covering the overlap of `a` and `b`, if they overlap.
+pub fn overlap<'a>(a: &'a str, b: &'a str) -> Option<&'a str> {
+    match (is_pattern(a), is_pattern(b)) {
+        (false, false) => (a == b).then_some(a),
+        (true, false) => matches(a, b).then_some(a),
+        (false, true) => matches(b, a).then_some(b),
+        (true, true) => patterns_overlap(a, b).then(|| {
+            if literal_prefix(b).len() < literal_prefix(a).len() {
+                b
+            } else {
+                a
+            }
+        }),
+    }
+}
+
+/// Overlapping entries between two prompts' affected files, deduplicated.
+pub fn overlaps(a: &[String], b: &[String]) -> Vec<String> {
+    let mut found: Vec<String> = Vec::new();
+    for x in a {
+        for y in b {
+            if let Some(entry) = overlap(x, y)
+                && !found.iter().any(|f| f == entry)
+            {
+                found.push(entry.to_string());
+            }
+        }
+    }
+    found
+}
+
+/// Whether two prompts touch at least one common file or subtree.
+pub fn would_conflict(a: &[String], b: &[String]) -> bool {
+    a.iter().any(|x| b.iter().any(|y| overlap(x, y).is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_paths_but_not_urls_or_versions() {
+        let prompt = "Refactor `src/main.rs` and ./docs/*.md, see https://example.com/a/b.html \
+                      (bump to v1.2 / 3.5, e.g. now). Also README.md.";
+        assert_eq!(
+            extract_paths(prompt),
+            vec!["src/main.rs", "./docs/*.md", "README.md"]
+        );
+    }
+
+    #[test]
+    fn paths_are_made_workspace_relative() {
+        let ws = Path::new("/work/repo");
+        assert_eq!(
+            relative_to(ws, "./src/lib.rs").as_deref(),
+            Some("src/lib.rs")
+        );
+        assert_eq!(
+            relative_to(ws, "/work/repo/src/lib.rs").as_deref(),
+            Some("src/lib.rs")
+        );
+        assert_eq!(relative_to(ws, "/etc/passwd"), None);
+        assert_eq!(relative_to(ws, "../other/x.rs"), None);
+    }
+
+    #[tokio::test]
+    async fn globs_expand_against_the_workspace() {
+        let dir = std::env::temp_dir().join(format!("ch-affected-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/a.md"), "").unwrap();
+        std::fs::write(dir.join("docs/b.md"), "").unwrap();
+        let files = normalize(
+            &dir,
+            &[
+                "docs/*.md".to_string(),
+                "src/new.rs".to_string(),
+                "docs/a.md".to_string(),
+            ],
+        )
+        .await;
+        assert_eq!(files, vec!["docs/a.md", "docs/b.md", "src/new.rs"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn predictions_are_parsed_leniently() {
+        assert_eq!(
+            parse_prediction("Sure:\n[\"src/a.rs\", \"b.toml\"]\nDone"),
+            vec!["src/a.rs", "b.toml"]
+        );
+        assert!(parse_prediction("no idea").is_empty());
+        assert!(parse_prediction("[1, 2]").is_empty());
+    }
+
+    #[test]
+    fn conflicts_need_a_shared_file() {
+        let a = vec!["src/a.rs".to_string(), "src/b.rs".to_string()];
+        assert!(would_conflict(&a, &["src/b.rs".to_string()]));
+        assert!(!would_conflict(&a, &["src/c.rs".to_string()]));
+        assert!(!would_conflict(&a, &[]));
+    }
+
+    #[tokio::test]
+    async fn globs_outside_the_workspace_are_dropped() {
+        let dir = std::env::temp_dir().join(format!("ch-affected-escape-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("docs")).unwrap();
+        std::fs::write(dir.join("docs/a.md"), "").unwrap();
+        let files = normalize(
+            &dir,
+            &[
+                "/etc/*.conf".to_string(),
+                "../*/*.md".to_string(),
+                "docs/../../**".to_string(),
+                "docs/*.md".to_string(),
+            ],
+        )
+        .await;
+        assert_eq!(files, vec!["docs/a.md"]);
+        assert!(!is_contained_glob("C:/Windows/*.dll"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn directories_and_recursive_globs_stay_patterns() {
+        let dir = std::env::temp_dir().join(format!("ch-affected-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/session")).unwrap();
+        let files = normalize(
+            &dir,
+            &[
+                "src/session".to_string(),
+                "docs/".to_string(),
+                "./tests/**/*.rs".to_string(),
+            ],
+        )
+        .await;
+        assert_eq!(files, vec!["docs/**", "src/session/**", "tests/**/*.rs"]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn subtree_overlaps_report_the_broader_entry() {
+        assert_eq!(
+            overlap("src/session/**", "src/session/mod.rs"),
+            Some("src/session/**")
+        );
+        assert_eq!(overlap("src/a.rs", "src/*.rs"), Some("src/*.rs"));
+        assert_eq!(overlap("src/**", "src/session/**"), Some("src/**"));
+        assert_eq!(overlap("src/session/**", "src/*.rs"), None);
+        assert_eq!(
+            overlap("src/*/mod.rs", "src/session/**"),
+            Some("src/*/mod.rs")
+        );
+        assert_eq!(overlap("src/*.rs", "docs/**"), None);
+        assert_eq!(overlap("src/*.rs", "src/*/x.rs"), None);
+
+        let a = vec!["src/session/**".to_string(), "README.md".to_string()];
+        let b = vec![
+            "src/session/a.rs".to_string(),
+            "src/session/b.rs".to_string(),
+        ];
+        assert_eq!(overlaps(&a, &b), vec!["src/session/**"]);
+        assert!(would_conflict(&a, &b));
+        assert!(!would_conflict(&a, &["src/lib.rs".to_string()]));
+    }
+}
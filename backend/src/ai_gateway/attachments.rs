@@ -0,0 +1,383 @@
+// attachments.rs — Image attachments for multimodal prompts.
+//
+// Gateway chat requests (`/api/ai/*`, `/api/ai/compat/*`), Claude chat
+// requests (`/api/claude/chat*`) and WebSocket `execute` messages accept
+// `attachments`: images given either as
+// a server-side `path` (validated against ALLOWED_FILE_DIRS like the
+// filesystem tools) or as inline base64 `data`. They are resolved once per
+// request and appended to the last user message in the provider's format:
+//
+// ```text
+// Anthropic          — {"type":"image","source":{"type":"base64",...}} blocks
+// OpenAI-compatible  — {"type":"image_url","image_url":{"url":"data:..."}} parts
+// Gemini             — {"inline_data":{"mime_type":...,"data":...}} parts
+// Ollama             — the message's `images` array (llava-class models)
+// ```
+//
+// A request with attachments requires the `vision` capability, so it is never
+// dispatched (or failed over) to a text-only provider.
+
+use std::path::Path;
+
+use axum::extract::Json;
+use axum::http::StatusCode;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+
+use crate::ai_gateway::AiProvider;
+use crate::tools::allowed_dirs_from_env;
+use crate::tools::fs_tools::validate_path;
+
+/// Maximum attachments per request.
+const MAX_ATTACHMENTS: usize = 8;
+
+/// Maximum decoded image size (5 MB — the strictest provider limit, Anthropic).
+const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Ollama model families that accept images.
+const OLLAMA_VISION_MODELS: &[&str] = &[
+    "llava",
+    "bakllava",
+    "llama3.2-vision",
+    "minicpm-v",
+    "moondream",
+    "qwen2.5vl",
+    "gemma3",
+];
+
+/// An image attached to a prompt. Exactly one of `path` / `data` is required.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Attachment {
+    /// Server-side image path (must be inside ALLOWED_FILE_DIRS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Base64 image data; a `data:image/...;base64,` prefix is accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+    /// MIME type — inferred from the path extension or data URL when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+}
+
+/// An attachment loaded and validated, ready to embed in a payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedImage {
+    pub mime_type: String,
+    /// Base64 (standard alphabet, no data URL prefix).
+    pub data: String,
+}
+
+/// Whether an Ollama model accepts image input (llava-class models).
+pub fn is_ollama_vision_model(model: &str) -> bool {
+    let name = model.to_lowercase();
+    let family = name.split(':').next().unwrap_or(&name);
+    let family = family.rsplit('/').next().unwrap_or(family);
+    OLLAMA_VISION_MODELS.iter().any(|m| family.starts_with(m))
+}
+
+fn mime_from_extension(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "webp" => Some("image/webp"),
+        "gif" => Some("image/gif"),
+        _ => None,
+    }
+}
+
+fn check_mime(mime_type: &str) -> Result<(), String> {
+    match mime_type {
+        "image/png" | "image/jpeg" | "image/webp" | "image/gif" => Ok(()),
+        other => Err(format!(
+            "Unsupported attachment type '{}' (png, jpeg, webp, gif)",
+            other
+        )),
+    }
+}
+
+fn check_size(len: usize) -> Result<(), String> {
+    if len > MAX_IMAGE_BYTES {
+        return Err(format!(
+            "Attachment too large: {} bytes (max {} MB)",
+            len,
+            MAX_IMAGE_BYTES / (1024 * 1024)
+        ));
+    }
+    Ok(())
+}
+
+/// Load and validate a single attachment.
+async fn resolve_attachment(attachment: &Attachment) -> Result<ResolvedImage, String> {
+    match (&attachment.path, &attachment.data) {
+        (Some(raw_path), None) => {
+            let path = validate_path(raw_path, &allowed_dirs_from_env())?;
+            let mime_type = match &attachment.mime_type {
+                Some(m) => m.clone(),
+                None => mime_from_extension(raw_path)
+                    .ok_or_else(|| format!("Cannot infer image type of '{}'", raw_path))?
+                    .to_string(),
+            };
+            check_mime(&mime_type)?;
+            let bytes = tokio::fs::read(&path)
+                .await
+                .map_err(|e| format!("Cannot read attachment '{}': {}", raw_path, e))?;
+            check_size(bytes.len())?;
+            Ok(ResolvedImage {
+                mime_type,
+                data: base64::engine::general_purpose::STANDARD.encode(&bytes),
+            })
+        }
+        (None, Some(raw_data)) => {
+            // Accept `data:image/png;base64,....` as produced by FileReader.
+            let (url_mime, data) = match raw_data
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((mime, data)) => (Some(mime), data),
+                None => (None, raw_data.as_str()),
+            };
+            let mime_type = attachment
+                .mime_type
+                .as_deref()
+                .or(url_mime)
+                .ok_or("Attachment data needs a mime_type")?
+                .to_string();
+            check_mime(&mime_type)?;
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("Attachment data is not valid base64: {}", e))?;
+            check_size(decoded.len())?;
+            Ok(ResolvedImage {
+                mime_type,
+                data: data.trim().to_string(),
+            })
+        }
+        _ => Err("Each attachment needs exactly one of 'path' or 'data'".to_string()),
+    }
+}
+
+/// Load and validate every attachment of a request.
+pub async fn resolve_attachments(attachments: &[Attachment]) -> Result<Vec<ResolvedImage>, String> {
+    if attachments.len() > MAX_ATTACHMENTS {
+        return Err(format!(
+            "Too many attachments: {} (max {})",
+            attachments.len(),
+            MAX_ATTACHMENTS
+        ));
+    }
+    let mut images = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        images.push(resolve_attachment(attachment).await?);
+    }
+    Ok(images)
+}
+
+/// 400 response for an attachment that could not be resolved.
+pub fn attachment_error(message: String) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "invalid_attachment",
+            "message": message,
+        })),
+    )
+}
+
+/// Attach images to the last user message of a provider-native message list
+/// (`messages` for most providers, `contents` for Gemini).
+pub fn attach_images(provider: &AiProvider, messages: &mut [Value], images: &[ResolvedImage]) {
+    if images.is_empty() {
+        return;
+    }
+    let Some(last_user) = messages
+        .iter_mut()
+        .rev()
+        .find(|m| m.get("role").and_then(|r| r.as_str()) == Some("user"))
+    else {
+        return;
+    };
+
+    // Content that is already a block array (tool results, earlier images)
+    // is kept as is and the images are appended after it.
+    match provider {
+        AiProvider::Anthropic => {
+            let image_blocks = images.iter().map(|img| {
+                json!({
+                    "type": "image",
+                    "source": {
+                        "type": "base64",
+                        "media_type": img.mime_type,
+                        "data": img.data,
+                    },
+                })
+            });
+            let blocks: Vec<Value> = match last_user.get_mut("content").map(Value::take) {
+                Some(Value::Array(mut blocks)) => {
+                    blocks.extend(image_blocks);
+                    blocks
+                }
+                Some(Value::String(text)) => image_blocks
+                    .chain(std::iter::once(json!({ "type": "text", "text": text })))
+                    .collect(),
+                _ => image_blocks.collect(),
+            };
+            last_user["content"] = Value::Array(blocks);
+        }
+        AiProvider::OpenAI | AiProvider::Xai | AiProvider::DeepSeek => {
+            let mut parts = match last_user.get_mut("content").map(Value::take) {
+                Some(Value::Array(parts)) => parts,
+                Some(Value::String(text)) => vec![json!({ "type": "text", "text": text })],
+                _ => Vec::with_capacity(images.len()),
+            };
+            parts.extend(images.iter().map(|img| {
+                json!({
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:{};base64,{}", img.mime_type, img.data) },
+                })
+            }));
+            last_user["content"] = Value::Array(parts);
+        }
+        AiProvider::Google => {
+            if let Some(parts) = last_user.get_mut("parts").and_then(|p| p.as_array_mut()) {
+                parts.extend(images.iter().map(|img| {
+                    json!({ "inline_data": { "mime_type": img.mime_type, "data": img.data } })
+                }));
+            }
+        }
+        AiProvider::Ollama => {
+            let mut data = match last_user.get_mut("images").map(Value::take) {
+                Some(Value::Array(data)) => data,
+                _ => Vec::with_capacity(images.len()),
+            };
+            data.extend(images.iter().map(|img| json!(img.data)));
+            last_user["images"] = Value::Array(data);
+        }
+    }
+}
+
+/// Attach images to a full provider payload built by `build_chat_payload`.
+pub fn apply_images(provider: &AiProvider, payload: &mut Value, images: &[ResolvedImage]) {
+    let key = match provider {
+        AiProvider::Google => "contents",
+        _ => "messages",
+    };
+    if let Some(messages) = payload.get_mut(key).and_then(|m| m.as_array_mut()) {
+        attach_images(provider, messages, images);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png() -> ResolvedImage {
+        ResolvedImage {
+            mime_type: "image/png".to_string(),
+            data: "iVBORw0KGgo=".to_string(),
+        }
+    }
+
+    #[test]
+    fn ollama_vision_models_detected() {
+        assert!(is_ollama_vision_model("llava:13b"));
+        assert!(is_ollama_vision_model("library/llama3.2-vision"));
+        assert!(!is_ollama_vision_model("llama3.1:8b"));
+        assert!(!is_ollama_vision_model("qwen3:14b"));
+    }
+
+    #[tokio::test]
+    async fn data_url_prefix_supplies_mime_type() {
+        let images = resolve_attachments(&[Attachment {
+            data: Some("data:image/png;base64,iVBORw0KGgo=".to_string()),
+            ..Default::default()
+        }])
+        .await
+        .unwrap();
+        assert_eq!(images, vec![png()]);
+    }
+
+    #[tokio::test]
+    async fn invalid_attachments_rejected() {
+        let both = Attachment {
+            path: Some("a.png".to_string()),
+            data: Some("iVBORw0KGgo=".to_string()),
+            mime_type: None,
+        };
+        assert!(resolve_attachments(&[both]).await.is_err());
+
+        let pdf = Attachment {
+            data: Some("JVBERi0=".to_string()),
+            mime_type: Some("application/pdf".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_attachments(&[pdf]).await.is_err());
+    }
+
+    #[test]
+    fn anthropic_images_precede_text() {
+        let mut messages = vec![json!({"role": "user", "content": "what is this?"})];
+        attach_images(&AiProvider::Anthropic, &mut messages, &[png()]);
+        assert_eq!(messages[0]["content"][0]["type"], "image");
+        assert_eq!(messages[0]["content"][0]["source"]["media_type"], "image/png");
+        assert_eq!(messages[0]["content"][1]["text"], "what is this?");
+    }
+
+    #[test]
+    fn images_are_appended_to_existing_content_blocks() {
+        let mut messages = vec![json!({"role": "user", "content": [
+            {"type": "tool_result", "tool_use_id": "t1", "content": "done"},
+            {"type": "text", "text": "and this?"},
+        ]})];
+        attach_images(&AiProvider::Anthropic, &mut messages, &[png()]);
+        let blocks = messages[0]["content"].as_array().unwrap();
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0]["type"], "tool_result");
+        assert_eq!(blocks[1]["text"], "and this?");
+        assert_eq!(blocks[2]["type"], "image");
+
+        let mut messages = vec![json!({"role": "user", "content": [
+            {"type": "text", "text": "compare"},
+            {"type": "image_url", "image_url": {"url": "data:image/png;base64,AAAA"}},
+        ]})];
+        attach_images(&AiProvider::OpenAI, &mut messages, &[png()]);
+        assert_eq!(messages[0]["content"].as_array().unwrap().len(), 3);
+        assert_eq!(messages[0]["content"][0]["text"], "compare");
+    }
+
+    #[test]
+    fn gemini_gets_inline_data_part() {
+        let mut payload = json!({"contents": [{"role": "user", "parts": [{"text": "hi"}]}]});
+        apply_images(&AiProvider::Google, &mut payload, &[png()]);
+        assert_eq!(payload["contents"][0]["parts"][1]["inline_data"]["mime_type"], "image/png");
+    }
+
+    #[test]
+    fn ollama_gets_images_on_last_user_message() {
+        let mut payload = json!({"messages": [
+            {"role": "user", "content": "first"},
+            {"role": "assistant", "content": "ok"},
+            {"role": "user", "content": "describe"},
+        ]});
+        apply_images(&AiProvider::Ollama, &mut payload, &[png()]);
+        assert!(payload["messages"][0].get("images").is_none());
+        assert_eq!(payload["messages"][2]["images"][0], "iVBORw0KGgo=");
+        assert_eq!(payload["messages"][2]["content"], "describe");
+    }
+
+    #[test]
+    fn openai_gets_data_url_part() {
+        let mut payload = json!({"messages": [{"role": "user", "content": "hi"}]});
+        apply_images(&AiProvider::OpenAI, &mut payload, &[png()]);
+        assert_eq!(
+            payload["messages"][0]["content"][1]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+}
@@ -0,0 +1,322 @@
+// capabilities.rs — Provider capability registry.
+//
+// Providers differ in what they accept: streaming, JSON mode (structured
+// output), image input, tool calling and context size. `ProviderCapabilities`
+// records that per provider — gateway providers (`AiProvider`) here, compat
+// providers on their `CompatProviderConfig` — and the proxy / compat
+// handlers check a request's `CapabilityRequirements` before dispatching,
+// so e.g. a `response_format` request is never sent (or failed over) to a
+// provider without JSON mode. Ollama's vision and tool-calling support
+// depend on the model (llava-class / tool-trained families), and so does its
+// context window: it is taken from the model's `num_ctx`
+// (`ollama_models::context_length`) once known, and the context pre-check is
+// skipped until then. See `gateway_capabilities_for_model`.
+//
+// Routes (merged into `ai_gateway_router`):
+// ```text
+// GET /api/ai/capabilities — capabilities of every gateway + compat provider
+// ```
+
+use axum::extract::{Json, State};
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::ai_gateway::{AiProvider, HasAiGateway, vault_bridge::HasVaultBridge};
+
+use super::attachments::is_ollama_vision_model;
+use super::compat_providers::compat_provider_configs;
+use super::gateway_tools::is_ollama_tool_model;
+use super::handlers::GatewayChatRequest;
+
+/// Rough chars-per-token ratio used to estimate prompt size.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Rough prompt tokens per attached image.
+const TOKENS_PER_IMAGE: u32 = 1_600;
+
+/// Context window shown for Ollama models whose `num_ctx` is not known yet
+/// (usage display only; requests are not pre-checked against it).
+const OLLAMA_FALLBACK_CONTEXT_TOKENS: u32 = 8_192;
+
+/// What a provider supports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ProviderCapabilities {
+    pub streaming: bool,
+    /// Native structured output (`response_format` / `responseMimeType` / `format`).
+    pub json_mode: bool,
+    /// Image input.
+    pub vision: bool,
+    pub tool_calling: bool,
+    /// Context window of the provider's largest default-tier model; `None`
+    /// when it depends on a model that is not known yet (not pre-checked).
+    pub max_context_tokens: Option<u32>,
+}
+
+/// What a request needs from a provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CapabilityRequirements {
+    pub streaming: bool,
+    pub json_mode: bool,
+    pub vision: bool,
+    pub tool_calling: bool,
+    /// Estimated prompt tokens + requested completion tokens.
+    pub min_context_tokens: u32,
+}
+
+impl CapabilityRequirements {
+    /// Derive requirements from a gateway chat request.
+    pub fn for_request(request: &GatewayChatRequest, streaming: bool) -> Self {
+        let prompt_chars: usize = request.messages.iter().map(|m| m.content.len()).sum();
+        let image_tokens = request.attachments.len() as u32 * TOKENS_PER_IMAGE;
+        let prompt_tokens = (prompt_chars / CHARS_PER_TOKEN) as u32 + image_tokens;
+        Self {
+            streaming,
+            json_mode: request.response_format.is_some(),
+            vision: !request.attachments.is_empty(),
+            tool_calling: !request.tools.is_empty(),
+            min_context_tokens: prompt_tokens.saturating_add(request.max_tokens.unwrap_or(4096)),
+        }
+    }
+}
+
+impl ProviderCapabilities {
+    /// `Err(capability)` naming the first requirement this provider lacks.
+    pub fn check(&self, req: &CapabilityRequirements) -> Result<(), &'static str> {
+        if req.streaming && !self.streaming {
+            return Err("streaming");
+        }
+        if req.json_mode && !self.json_mode {
+            return Err("json_mode");
+        }
+        if req.vision && !self.vision {
+            return Err("vision");
+        }
+        if req.tool_calling && !self.tool_calling {
+            return Err("tool_calling");
+        }
+        if self
+            .max_context_tokens
+            .is_some_and(|max| req.min_context_tokens > max)
+        {
+            return Err("context_size");
+        }
+        Ok(())
+    }
+}
+
+/// Capabilities of a gateway provider.
+pub fn gateway_capabilities(provider: &AiProvider) -> ProviderCapabilities {
+    let (json_mode, vision, tool_calling, max_context_tokens) = match provider {
+        // Anthropic has no response_format — JSON only via prompting/tools.
+        AiProvider::Anthropic => (false, true, true, Some(200_000)),
+        AiProvider::OpenAI => (true, true, true, Some(128_000)),
+        AiProvider::Google => (true, true, true, Some(1_048_576)),
+        AiProvider::Xai => (true, true, true, Some(131_072)),
+        AiProvider::DeepSeek => (true, false, true, Some(128_000)),
+        // Per model, see `gateway_capabilities_for_model`.
+        AiProvider::Ollama => (true, false, false, None),
+    };
+    ProviderCapabilities {
+        streaming: true,
+        json_mode,
+        vision,
+        tool_calling,
+        max_context_tokens,
+    }
+}
+
+/// Capabilities of a gateway provider when serving `model` (if known).
+/// Ollama only accepts images with a llava-class model and tools with a
+/// tool-trained one, and its context window is the model's `num_ctx`.
+pub fn gateway_capabilities_for_model(provider: &AiProvider, model: Option<&str>) -> ProviderCapabilities {
+    let mut caps = gateway_capabilities(provider);
+    if *provider == AiProvider::Ollama {
+        caps.vision = model.is_some_and(is_ollama_vision_model);
+        caps.tool_calling = model.is_some_and(is_ollama_tool_model);
+        caps.max_context_tokens = model.and_then(crate::ollama_models::cached_context_length);
+    }
+    caps
+}
+
+/// Provider serving a model id: `ollama/<name>` or a tagged name
+/// (`llama3.1:8b`) is Ollama, otherwise the model family decides; unknown
+/// ids fall back to Anthropic, the default provider.
+pub fn provider_for_model_id(model: &str) -> AiProvider {
+    let lower = model.trim().to_lowercase();
+    if lower.starts_with("ollama/") || lower.contains(':') {
+        AiProvider::Ollama
+    } else if lower.starts_with("gpt") || lower.starts_with("o1") || lower.starts_with("o3") || lower.starts_with("o4") {
+        AiProvider::OpenAI
+    } else if lower.starts_with("gemini") {
+        AiProvider::Google
+    } else if lower.starts_with("grok") {
+        AiProvider::Xai
+    } else if lower.starts_with("deepseek") {
+        AiProvider::DeepSeek
+    } else {
+        AiProvider::Anthropic
+    }
+}
+
+/// Context window (tokens) of a model id.
+pub fn context_window(model: &str) -> u32 {
+    gateway_capabilities_for_model(&provider_for_model_id(model), Some(model))
+        .max_context_tokens
+        .unwrap_or(OLLAMA_FALLBACK_CONTEXT_TOKENS)
+}
+
+/// 422 response for a provider that cannot serve the request.
+pub(crate) fn capability_error(provider: &str, missing: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({
+            "error": "capability_unsupported",
+            "provider": provider,
+            "missing_capability": missing,
+            "message": format!("Provider {} does not support {}", provider, missing),
+        })),
+    )
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/ai/capabilities
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub(crate) async fn list_provider_capabilities<S>(State(_state): State<S>) -> Json<Value>
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    let mut providers: Vec<Value> = AiProvider::ALL
+        .iter()
+        .map(|p| {
+            json!({
+                "provider": p.to_string(),
+                "kind": "gateway",
+                "capabilities": gateway_capabilities(p),
+            })
+        })
+        .collect();
+    providers.extend(compat_provider_configs().into_iter().map(|c| {
+        json!({
+            "provider": c.id,
+            "kind": "compat",
+            "capabilities": c.capabilities,
+        })
+    }));
+    Json(json!({ "providers": providers }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai_gateway::handlers::GatewayChatMessage;
+
+    fn request(content: &str, response_format: Option<Value>) -> GatewayChatRequest {
+        GatewayChatRequest {
+            model: None,
+            messages: vec![GatewayChatMessage {
+                role: "user".to_string(),
+                content: content.to_string(),
+            }],
+            temperature: None,
+            max_tokens: Some(1000),
+            stream: None,
+            response_format,
+            attachments: Vec::new(),
+            tools: Vec::new(),
+            session_id: None,
+        }
+    }
+
+    #[test]
+    fn json_mode_request_rejected_by_anthropic() {
+        let req = CapabilityRequirements::for_request(
+            &request("give me json", Some(json!({"type": "json_object"}))),
+            false,
+        );
+        assert!(req.json_mode);
+        assert_eq!(gateway_capabilities(&AiProvider::Anthropic).check(&req), Err("json_mode"));
+        assert!(gateway_capabilities(&AiProvider::OpenAI).check(&req).is_ok());
+    }
+
+    #[test]
+    fn plain_request_accepted_everywhere() {
+        let req = CapabilityRequirements::for_request(&request("hi", None), true);
+        for p in AiProvider::ALL {
+            assert!(gateway_capabilities(&p).check(&req).is_ok(), "{} rejected", p);
+        }
+    }
+
+    #[test]
+    fn context_window_follows_the_model_family() {
+        assert_eq!(context_window("claude-sonnet-4-6"), 200_000);
+        assert_eq!(context_window("gemini-2.5-pro"), 1_048_576);
+        assert_eq!(context_window("gpt-4o"), 128_000);
+        assert_eq!(context_window("ollama/unknown-model:1b"), OLLAMA_FALLBACK_CONTEXT_TOKENS);
+        assert_eq!(provider_for_model_id("qwen2.5-coder:7b"), AiProvider::Ollama);
+        assert_eq!(provider_for_model_id("something-new"), AiProvider::Anthropic);
+    }
+
+    #[test]
+    fn oversized_prompt_exceeds_small_context() {
+        let big = "x".repeat(600_000);
+        let req = CapabilityRequirements::for_request(&request(&big, None), false);
+        assert_eq!(gateway_capabilities(&AiProvider::Anthropic).check(&req), Err("context_size"));
+        assert!(gateway_capabilities(&AiProvider::Google).check(&req).is_ok());
+    }
+
+    #[test]
+    fn ollama_context_is_not_prechecked_until_known() {
+        let big = "x".repeat(40_000);
+        let req = CapabilityRequirements::for_request(&request(&big, None), false);
+        let caps = gateway_capabilities_for_model(&AiProvider::Ollama, Some("ollama/unknown-model:1b"));
+        assert_eq!(caps.max_context_tokens, None);
+        assert!(caps.check(&req).is_ok());
+    }
+
+    #[test]
+    fn image_request_only_reaches_vision_providers() {
+        let mut body = request("what is in this picture?", None);
+        body.attachments = vec![crate::ai_gateway::attachments::Attachment {
+            data: Some("iVBORw0KGgo=".to_string()),
+            mime_type: Some("image/png".to_string()),
+            ..Default::default()
+        }];
+        let req = CapabilityRequirements::for_request(&body, false);
+        assert!(req.vision);
+        assert_eq!(gateway_capabilities(&AiProvider::DeepSeek).check(&req), Err("vision"));
+        assert!(gateway_capabilities(&AiProvider::Google).check(&req).is_ok());
+        assert_eq!(
+            gateway_capabilities_for_model(&AiProvider::Ollama, Some("llama3.1:8b")).check(&req),
+            Err("vision")
+        );
+        assert!(
+            gateway_capabilities_for_model(&AiProvider::Ollama, Some("llava:13b"))
+                .check(&req)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn tool_request_needs_tool_calling() {
+        let mut body = request("list the repo files", None);
+        body.tools = vec!["shell".to_string()];
+        let req = CapabilityRequirements::for_request(&body, false);
+        assert!(req.tool_calling);
+        assert!(gateway_capabilities(&AiProvider::Anthropic).check(&req).is_ok());
+        assert_eq!(
+            gateway_capabilities_for_model(&AiProvider::Ollama, Some("llava:13b")).check(&req),
+            Err("tool_calling")
+        );
+        assert!(
+            gateway_capabilities_for_model(&AiProvider::Ollama, Some("qwen2.5:7b"))
+                .check(&req)
+                .is_ok()
+        );
+    }
+}
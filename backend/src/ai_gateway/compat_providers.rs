@@ -0,0 +1,1057 @@
+// compat_providers.rs — OpenAI-compatible API-key providers outside `AiProvider`.
+//
+// `AiProvider` lives in the shared jaskier-model-router crate and is used by
+// every Hydra, so providers that only ClaudeHydra talks to (OpenRouter, ...)
+// are modelled here instead. Each one speaks the OpenAI chat-completions
+// protocol and is addressed by a string id in the URL.
+//
+// Credentials: `ai_providers/{vault_service}` in Jaskier Vault (Bouncer
+// pattern, preferred) or the provider's env var as a local-dev fallback.
+//
+// Routes (merged into `ai_gateway_router`):
+// ```text
+// GET  /api/ai/compat/providers          — list compat providers + configured flag
+// GET  /api/ai/compat/{provider}/models  — upstream model list with pricing
+// POST /api/ai/compat/{provider}/chat    — non-streaming chat completion
+// POST /api/ai/compat/{provider}/stream  — streaming chat (SSE, same events as /api/ai/{provider}/stream)
+// ```
+//
+// Streamed `token` / `stream_end` events carry the canonical stream event
+// fields (`stream_event.rs`): request id, provider, delta, usage, finish reason.
+//
+// Azure OpenAI (cargo feature `azure`) uses the same wire format but
+// deployment-based URLs (`{endpoint}/openai/deployments/{deployment}/...`),
+// an `api-version` query parameter and an `api-key` header; the "model" in a
+// request is the deployment name (percent-encoded into the path; names with
+// `/`, `?`, `#` or `..` are rejected). Without `AZURE_OPENAI_ENDPOINT` the
+// provider is disabled.
+//
+// `{provider}` may be `auto`: short interactive prompts go to a configured
+// low-latency provider (Groq), everything else to the default (OpenRouter).
+// Only providers with a credential are considered, and the request runs on
+// the picked provider's default model (model ids are provider-specific).
+// Providers whose `capabilities` cannot serve the request (e.g. no JSON mode
+// for a `response_format` request) are skipped by `auto` and rejected with
+// 422 when named explicitly.
+//
+// Chat and stream requests honour the offline profile, the provider's budget
+// cap and the safety guard before dispatch, and book their cost under
+// `<provider>/<model>` (see `handlers::helpers::admit_request`).
+
+use std::convert::Infallible;
+use std::time::Instant;
+
+use axum::extract::{Json, Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::routing::{get, post};
+use axum::Router;
+use jaskier_core::handlers::anthropic_streaming::parse_sse_lines;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tokio_stream::StreamExt;
+
+use crate::ai_gateway::{
+    AiProvider, HasAiGateway,
+    attachments::{apply_images, attachment_error, resolve_attachments},
+    capabilities::{CapabilityRequirements, ProviderCapabilities, capability_error},
+    gateway_tools::{apply_tool_definitions, resolve_tools, run_tool_loop},
+    vault_bridge::HasVaultBridge,
+};
+
+use super::handlers::helpers::{
+    admit_request, build_chat_payload, chunk_text, extract_content_text, record_cost,
+};
+use super::handlers::GatewayChatRequest;
+use crate::stream_event::{self, StreamEvent, Usage};
+
+/// Upstream request timeout for compat providers.
+const COMPAT_TIMEOUT_SECS: u64 = 120;
+
+/// `auto` routing: prompts up to this many chars count as short/interactive
+/// and are sent to a low-latency provider when one is configured.
+const SHORT_PROMPT_MAX_CHARS: usize = 2000;
+
+/// Provider preferred by `auto` routing when no low-latency provider applies.
+const AUTO_DEFAULT_PROVIDER: &str = "openrouter";
+
+/// Azure OpenAI data-plane API version used when `AZURE_OPENAI_API_VERSION` is unset.
+const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+// ── Config ──────────────────────────────────────────────────────────────────
+
+/// URL layout + auth header style of a compat provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompatFlavor {
+    /// `{base}/chat/completions`, `Authorization: Bearer`.
+    OpenAi,
+    /// `{base}/openai/deployments/{deployment}/chat/completions?api-version=`, `api-key` header.
+    AzureOpenAi,
+}
+
+/// Static configuration for a single OpenAI-compatible provider.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompatProviderConfig {
+    /// Provider id used in URLs (e.g. "openrouter").
+    pub id: &'static str,
+    /// Human-readable name.
+    pub display_name: &'static str,
+    /// URL layout + auth header style.
+    pub flavor: CompatFlavor,
+    /// API base URL without trailing slash (e.g. "https://openrouter.ai/api/v1").
+    pub base_url: &'static str,
+    /// Env var overriding `base_url` (required for per-tenant endpoints like Azure).
+    pub base_url_env: Option<&'static str>,
+    /// Vault service name under the `ai_providers` namespace.
+    pub vault_service: &'static str,
+    /// Env var holding the API key (local-dev fallback when Vault is empty).
+    pub api_key_env: &'static str,
+    /// Model used when the request does not specify one.
+    pub default_model: &'static str,
+    /// Extra headers sent with every upstream request.
+    pub extra_headers: &'static [(&'static str, &'static str)],
+    /// Preferred by `auto` routing for short interactive prompts.
+    pub low_latency: bool,
+    /// Feature support, consulted before dispatching a request.
+    pub capabilities: ProviderCapabilities,
+}
+
+/// Compat providers that are usable: per-tenant providers (Azure) are left
+/// out until their endpoint env var is set.
+pub fn compat_provider_configs() -> Vec<CompatProviderConfig> {
+    all_compat_provider_configs()
+        .into_iter()
+        .filter(|c| c.endpoint_configured())
+        .collect()
+}
+
+/// All compat providers known to ClaudeHydra.
+fn all_compat_provider_configs() -> Vec<CompatProviderConfig> {
+    vec![
+        CompatProviderConfig {
+            id: "openrouter",
+            display_name: "OpenRouter",
+            flavor: CompatFlavor::OpenAi,
+            base_url: "https://openrouter.ai/api/v1",
+            base_url_env: None,
+            vault_service: "openrouter",
+            api_key_env: "OPENROUTER_API_KEY",
+            default_model: "anthropic/claude-sonnet-4",
+            // OpenRouter attribution headers (optional, used for their leaderboards).
+            extra_headers: &[
+                ("HTTP-Referer", "https://github.com/EPS-AI-SOLUTIONS/ClaudeHydra"),
+                ("X-Title", "ClaudeHydra"),
+            ],
+            low_latency: false,
+            capabilities: ProviderCapabilities {
+                streaming: true,
+                json_mode: true,
+                vision: true,
+                tool_calling: true,
+                max_context_tokens: Some(200_000),
+            },
+        },
+        CompatProviderConfig {
+            id: "groq",
+            display_name: "Groq",
+            flavor: CompatFlavor::OpenAi,
+            base_url: "https://api.groq.com/openai/v1",
+            base_url_env: None,
+            vault_service: "groq",
+            api_key_env: "GROQ_API_KEY",
+            default_model: "llama-3.3-70b-versatile",
+            extra_headers: &[],
+            low_latency: true,
+            capabilities: ProviderCapabilities {
+                streaming: true,
+                json_mode: true,
+                vision: false,
+                tool_calling: true,
+                max_context_tokens: Some(131_072),
+            },
+        },
+        #[cfg(feature = "azure")]
+        CompatProviderConfig {
+            id: "azure",
+            display_name: "Azure OpenAI",
+            flavor: CompatFlavor::AzureOpenAi,
+            // No default — the endpoint is per resource, see `compat_provider_configs`.
+            base_url: "",
+            base_url_env: Some("AZURE_OPENAI_ENDPOINT"),
+            vault_service: "azure_openai",
+            api_key_env: "AZURE_OPENAI_API_KEY",
+            // Deployment name, not a model id.
+            default_model: "gpt-4o",
+            extra_headers: &[],
+            low_latency: false,
+            capabilities: ProviderCapabilities {
+                streaming: true,
+                json_mode: true,
+                vision: true,
+                tool_calling: true,
+                max_context_tokens: Some(128_000),
+            },
+        },
+    ]
+}
+
+/// Look up a compat provider by id (case-insensitive).
+pub fn find_compat_provider(id: &str) -> Option<CompatProviderConfig> {
+    compat_provider_configs()
+        .into_iter()
+        .find(|c| c.id.eq_ignore_ascii_case(id))
+}
+
+/// Compat provider serving `model` and the model id to send upstream.
+/// `<provider>/<model>` addresses a compat provider directly (e.g.
+/// `groq/llama-3.3-70b-versatile`); bare OpenAI model ids (`gpt-*`, `o1`,
+/// `o3`, `o4`, ...) go to Azure when its endpoint is set, else to OpenRouter
+/// as `openai/<model>`. `None` for models no compat provider serves.
+pub fn route_model(model: &str) -> Option<(CompatProviderConfig, String)> {
+    if let Some((prefix, rest)) = model.split_once('/')
+        && !rest.is_empty()
+        && let Some(cfg) = find_compat_provider(prefix)
+    {
+        return Some((cfg, rest.to_string()));
+    }
+    if !is_openai_model(model) {
+        return None;
+    }
+    if let Some(azure) = find_compat_provider("azure") {
+        return Some((azure, model.to_string()));
+    }
+    find_compat_provider("openrouter").map(|cfg| (cfg, format!("openai/{}", model)))
+}
+
+/// OpenAI model family ids (`gpt-4o`, `o3-mini`, ...).
+fn is_openai_model(model: &str) -> bool {
+    let lower = model.to_lowercase();
+    lower.starts_with("gpt-")
+        || ["o1", "o3", "o4"]
+            .iter()
+            .any(|p| lower == *p || lower.starts_with(&format!("{}-", p)))
+}
+
+impl CompatProviderConfig {
+    /// Locally configured API key: OS keychain first, then the env var.
+    fn env_api_key(&self) -> Option<String> {
+        crate::secrets::get_provider_key(self.id)
+            .or_else(|| std::env::var(self.api_key_env).ok().filter(|k| !k.is_empty()))
+    }
+
+    /// Effective base URL (env override first), without trailing slash.
+    pub fn base_url(&self) -> String {
+        self.base_url_env
+            .and_then(|var| std::env::var(var).ok())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| self.base_url.to_string())
+            .trim_end_matches('/')
+            .to_string()
+    }
+
+    /// Whether the endpoint is usable: per-tenant providers need their
+    /// endpoint env var set.
+    pub fn endpoint_configured(&self) -> bool {
+        self.base_url_env
+            .is_none_or(|var| std::env::var(var).is_ok_and(|v| !v.trim().is_empty()))
+    }
+
+    /// Chat-completions path for `model` (the deployment name on Azure).
+    /// `Err` for deployment names that would escape their path segment.
+    pub fn chat_path(&self, model: &str) -> Result<String, String> {
+        match self.flavor {
+            CompatFlavor::OpenAi => Ok("/chat/completions".to_string()),
+            CompatFlavor::AzureOpenAi => Ok(format!(
+                "/openai/deployments/{}/chat/completions?api-version={}",
+                deployment_segment(model)?,
+                azure_api_version()
+            )),
+        }
+    }
+
+    /// Model listing path.
+    pub fn models_path(&self) -> String {
+        match self.flavor {
+            CompatFlavor::OpenAi => "/models".to_string(),
+            CompatFlavor::AzureOpenAi => format!("/openai/models?api-version={}", azure_api_version()),
+        }
+    }
+
+    /// Attach the API key + extra headers in the provider's auth style.
+    fn authorize(&self, req: reqwest::RequestBuilder, key: &str) -> reqwest::RequestBuilder {
+        let mut req = match self.flavor {
+            CompatFlavor::OpenAi => req.bearer_auth(key),
+            CompatFlavor::AzureOpenAi => req.header("api-key", key),
+        };
+        for (name, value) in self.extra_headers {
+            req = req.header(*name, *value);
+        }
+        req
+    }
+
+    /// Whether a credential is available (Vault connection or env key).
+    async fn is_configured<S: HasVaultBridge>(&self, state: &S) -> bool {
+        self.env_api_key().is_some()
+            || state
+                .vault_client()
+                .get("ai_providers", self.vault_service)
+                .await
+                .map(|c| c.is_connected)
+                .unwrap_or(false)
+    }
+}
+
+/// Percent-encoded path segment for an Azure deployment name.
+fn deployment_segment(deployment: &str) -> Result<String, String> {
+    if deployment.is_empty()
+        || deployment.contains(['/', '\\', '?', '#'])
+        || deployment.contains("..")
+    {
+        return Err(format!("Invalid deployment name: '{}'", deployment));
+    }
+    Ok(deployment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect())
+}
+
+fn invalid_model(provider: &str, message: String) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "invalid_model",
+            "provider": provider,
+            "message": message,
+        })),
+    )
+}
+
+fn azure_api_version() -> String {
+    std::env::var("AZURE_OPENAI_API_VERSION")
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| AZURE_DEFAULT_API_VERSION.to_string())
+}
+
+/// Latency-aware pick for `auto` among `configured` providers (`(id,
+/// low_latency)`): short prompts go to the first low-latency one; anything
+/// else to the default when configured, else to the first other provider.
+/// `None` when nothing is configured.
+pub(crate) fn pick_auto_provider(
+    prompt_chars: usize,
+    configured: &[(&'static str, bool)],
+) -> Option<&'static str> {
+    let low_latency = configured.iter().find(|(_, fast)| *fast).map(|(id, _)| *id);
+    if prompt_chars <= SHORT_PROMPT_MAX_CHARS && low_latency.is_some() {
+        return low_latency;
+    }
+    configured
+        .iter()
+        .find(|(id, _)| *id == AUTO_DEFAULT_PROVIDER)
+        .or_else(|| configured.iter().find(|(_, fast)| !*fast))
+        .or_else(|| configured.first())
+        .map(|(id, _)| *id)
+}
+
+/// Resolve a `{provider}` path segment and the model to run, expanding `auto`
+/// via `pick_auto_provider` (only configured providers that meet `required`
+/// are considered; `auto` always uses the picked provider's default model).
+async fn resolve_provider<S: HasVaultBridge>(
+    state: &S,
+    provider: &str,
+    request: &GatewayChatRequest,
+    required: &CapabilityRequirements,
+) -> Result<(CompatProviderConfig, String), (StatusCode, Json<Value>)> {
+    if !provider.eq_ignore_ascii_case("auto") {
+        let cfg = find_compat_provider(provider).ok_or_else(|| unknown_compat_provider(provider))?;
+        let model = request.model.clone().unwrap_or_else(|| cfg.default_model.to_string());
+        return Ok((cfg, model));
+    }
+    let prompt_chars = request
+        .messages
+        .last()
+        .map(|m| m.content.chars().count())
+        .unwrap_or(0);
+    let mut configured = Vec::new();
+    for cfg in compat_provider_configs()
+        .into_iter()
+        .filter(|c| c.capabilities.check(required).is_ok())
+    {
+        if cfg.is_configured(state).await {
+            configured.push((cfg.id, cfg.low_latency));
+        }
+    }
+    let cfg = pick_auto_provider(prompt_chars, &configured)
+        .and_then(find_compat_provider)
+        .ok_or_else(|| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({
+                    "error": "no_provider_configured",
+                    "message": "No configured compat provider can serve this request",
+                })),
+            )
+        })?;
+    tracing::debug!(provider = cfg.id, prompt_chars, "compat: auto routing decision");
+    let model = cfg.default_model.to_string();
+    Ok((cfg, model))
+}
+
+// ── Model metadata ──────────────────────────────────────────────────────────
+
+/// A single upstream model with normalized pricing.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CompatModelInfo {
+    pub id: String,
+    pub name: String,
+    pub context_length: Option<u64>,
+    /// USD per 1M prompt tokens (None when the provider does not publish it).
+    pub prompt_usd_per_mtok: Option<f64>,
+    /// USD per 1M completion tokens.
+    pub completion_usd_per_mtok: Option<f64>,
+}
+
+/// Parse an OpenAI-style `/models` response. OpenRouter adds `pricing`
+/// (USD per token, as strings) and `context_length`; other providers omit them.
+pub(crate) fn parse_models_response(body: &Value) -> Vec<CompatModelInfo> {
+    let per_mtok = |v: Option<&Value>| -> Option<f64> {
+        let raw = v?;
+        let per_token = match raw {
+            Value::String(s) => s.parse::<f64>().ok()?,
+            Value::Number(n) => n.as_f64()?,
+            _ => return None,
+        };
+        Some((per_token * 1_000_000.0 * 1000.0).round() / 1000.0)
+    };
+
+    body.get("data")
+        .and_then(|d| d.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|m| {
+                    let id = m.get("id")?.as_str()?.to_string();
+                    let pricing = m.get("pricing");
+                    Some(CompatModelInfo {
+                        name: m
+                            .get("name")
+                            .and_then(|n| n.as_str())
+                            .unwrap_or(&id)
+                            .to_string(),
+                        context_length: m.get("context_length").and_then(|c| c.as_u64()),
+                        prompt_usd_per_mtok: per_mtok(pricing.and_then(|p| p.get("prompt"))),
+                        completion_usd_per_mtok: per_mtok(pricing.and_then(|p| p.get("completion"))),
+                        id,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ── Upstream call ───────────────────────────────────────────────────────────
+
+/// Send a request to a compat provider. Uses the env API key directly when
+/// present, otherwise delegates through the Vault Bouncer.
+/// Returns `(status, body)`; transport failures become `Err(message)`.
+pub(crate) async fn send_compat_request<S>(
+    state: &S,
+    cfg: &CompatProviderConfig,
+    method: &str,
+    path: &str,
+    body: Option<Value>,
+) -> Result<(u16, Value), String>
+where
+    S: HasAiGateway + HasVaultBridge,
+{
+    let url = format!("{}{}", cfg.base_url(), path);
+
+    if let Some(key) = cfg.env_api_key() {
+        let client = &state.ai_gateway().http_client;
+        let req = match method {
+            "GET" => client.get(&url),
+            _ => client.post(&url),
+        }
+        .timeout(std::time::Duration::from_secs(COMPAT_TIMEOUT_SECS));
+        let mut req = cfg.authorize(req, &key);
+        if let Some(b) = body {
+            req = req.json(&b);
+        }
+        let resp = req.send().await.map_err(|e| e.to_string())?;
+        let status = resp.status().as_u16();
+        let json_body = resp.json::<Value>().await.unwrap_or(Value::Null);
+        return Ok((status, json_body));
+    }
+
+    state
+        .vault_client()
+        .delegate(&url, method, "ai_providers", cfg.vault_service, body)
+        .await
+        .map(|resp| (resp.status, resp.body))
+        .map_err(|e| e.to_string())
+}
+
+/// One non-streaming chat turn (background prompts, transcripts) on the
+/// compat provider serving `model` (see `route_model`); returns the reply text.
+pub(crate) async fn complete<S: HasAiGateway + HasVaultBridge>(
+    state: &S,
+    model: &str,
+    system: &str,
+    prompt: &str,
+    max_tokens: u32,
+) -> Result<String, String> {
+    let (cfg, upstream_model) =
+        route_model(model).ok_or_else(|| format!("No provider serves model '{}'", model))?;
+    let body = json!({
+        "model": upstream_model,
+        "max_tokens": max_tokens,
+        "messages": [
+            { "role": "system", "content": system },
+            { "role": "user", "content": prompt },
+        ],
+    });
+    let path = cfg.chat_path(&upstream_model)?;
+    match send_compat_request(state, &cfg, "POST", &path, Some(body)).await? {
+        (status, resp) if (200..300).contains(&status) => {
+            Ok(extract_content_text(&AiProvider::OpenAI, &resp))
+        }
+        (status, _) => Err(format!("Provider returned HTTP {}", status)),
+    }
+}
+
+// ── Router ──────────────────────────────────────────────────────────────────
+
+/// Compat provider sub-router (merged into `ai_gateway_router`).
+pub fn compat_router<S>() -> Router<S>
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/api/ai/compat/providers", get(list_compat_providers::<S>))
+        .route("/api/ai/compat/{provider}/models", get(compat_models::<S>))
+        .route("/api/ai/compat/{provider}/chat", post(compat_chat::<S>))
+        .route("/api/ai/compat/{provider}/stream", post(compat_stream::<S>))
+}
+
+fn unknown_compat_provider(provider: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "unknown_provider",
+            "message": format!("Unknown compat provider: {}", provider),
+            "valid_providers": compat_provider_configs().iter().map(|c| c.id).collect::<Vec<_>>(),
+        })),
+    )
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/ai/compat/providers
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub(crate) async fn list_compat_providers<S>(State(state): State<S>) -> Json<Value>
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    let mut providers = Vec::new();
+    for cfg in compat_provider_configs() {
+        let vault_connected = state
+            .vault_client()
+            .get("ai_providers", cfg.vault_service)
+            .await
+            .map(|c| c.is_connected)
+            .unwrap_or(false);
+        providers.push(json!({
+            "provider": cfg.id,
+            "display_name": cfg.display_name,
+            "default_model": cfg.default_model,
+            "low_latency": cfg.low_latency,
+            "capabilities": cfg.capabilities,
+            "is_configured": vault_connected || cfg.env_api_key().is_some(),
+            "credential_source": if vault_connected { "vault" } else if cfg.env_api_key().is_some() { "env" } else { "none" },
+        }));
+    }
+    Json(json!({ "providers": providers }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/ai/compat/{provider}/models
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub(crate) async fn compat_models<S>(
+    State(state): State<S>,
+    Path(provider): Path<String>,
+) -> impl IntoResponse
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    let Some(cfg) = find_compat_provider(&provider) else {
+        return unknown_compat_provider(&provider).into_response();
+    };
+
+    match send_compat_request(&state, &cfg, "GET", &cfg.models_path(), None).await {
+        Ok((status, body)) if (200..300).contains(&status) => {
+            let models = parse_models_response(&body);
+            Json(json!({
+                "provider": cfg.id,
+                "count": models.len(),
+                "models": models,
+            }))
+            .into_response()
+        }
+        Ok((status, body)) => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({
+                "error": "upstream_error",
+                "provider": cfg.id,
+                "upstream_status": status,
+                "upstream_body": body,
+            })),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({
+                "error": "upstream_connection_failed",
+                "provider": cfg.id,
+                "message": e,
+            })),
+        )
+            .into_response(),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/ai/compat/{provider}/chat
+// ═══════════════════════════════════════════════════════════════════════════
+
+pub(crate) async fn compat_chat<S>(
+    State(state): State<S>,
+    Path(provider): Path<String>,
+    Json(mut body): Json<GatewayChatRequest>,
+) -> impl IntoResponse
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    let required = CapabilityRequirements::for_request(&body, false);
+    let (cfg, model) = match resolve_provider(&state, &provider, &body, &required).await {
+        Ok(resolved) => resolved,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(missing) = cfg.capabilities.check(&required) {
+        return capability_error(cfg.id, missing).into_response();
+    }
+    if let Err(e) = admit_request(state.gateway_db(), cfg.id, &mut body).await {
+        return e.into_response();
+    }
+    let images = match resolve_attachments(&body.attachments).await {
+        Ok(images) => images,
+        Err(e) => return attachment_error(e).into_response(),
+    };
+    let tools = match resolve_tools(&body.tools) {
+        Ok(tools) => tools,
+        Err(e) => return e.into_response(),
+    };
+
+    // Compat providers share the OpenAI chat-completions wire format.
+    let mut upstream_body = build_chat_payload(&AiProvider::OpenAI, &model, &body);
+    apply_images(&AiProvider::OpenAI, &mut upstream_body, &images);
+    apply_tool_definitions(&AiProvider::OpenAI, &mut upstream_body, &tools);
+    let tool_payload = (!tools.is_empty()).then(|| upstream_body.clone());
+    let chat_path = match cfg.chat_path(&model) {
+        Ok(path) => path,
+        Err(e) => return invalid_model(cfg.id, e).into_response(),
+    };
+    let started = Instant::now();
+
+    tracing::info!(provider = cfg.id, model = %model, "compat_chat: routing request");
+
+    match send_compat_request(&state, &cfg, "POST", &chat_path, Some(upstream_body)).await {
+        Ok((status, resp)) if (200..300).contains(&status) => {
+            let (resp, tool_calls) = match tool_payload {
+                None => (resp, Vec::new()),
+                Some(payload) => {
+                    let send = |b: Value| send_compat_request(&state, &cfg, "POST", &chat_path, Some(b));
+                    let by = crate::audit_trail::Attribution::session(body.session_id);
+                    let client = &state.ai_gateway().http_client;
+                    match run_tool_loop(&AiProvider::OpenAI, payload, resp, &tools, &by, client, send).await {
+                        Ok(result) => (result.response, result.tool_calls),
+                        Err(e) => {
+                            tracing::warn!(provider = cfg.id, error = %e, "compat_chat: tool loop failed");
+                            return (
+                                StatusCode::BAD_GATEWAY,
+                                Json(json!({
+                                    "error": "tool_loop_failed",
+                                    "provider": cfg.id,
+                                    "message": e,
+                                })),
+                            )
+                                .into_response();
+                        }
+                    }
+                }
+            };
+            let latency_ms = started.elapsed().as_millis() as u64;
+            let content = extract_content_text(&AiProvider::OpenAI, &resp);
+            record_cost(
+                state.gateway_db(),
+                cfg.id,
+                &model,
+                &body,
+                &content,
+                stream_event::response_usage(&resp),
+            );
+            let mut out = json!({
+                "provider": cfg.id,
+                "model": model,
+                "latency_ms": latency_ms,
+                "content": content,
+                "usage": resp.get("usage").cloned().unwrap_or(Value::Null),
+                "response": resp,
+            });
+            if !tool_calls.is_empty() {
+                out["tool_calls"] = json!(tool_calls);
+            }
+            Json(out).into_response()
+        }
+        Ok((status, resp)) => {
+            let latency_ms = started.elapsed().as_millis() as u64;
+            tracing::warn!(provider = cfg.id, status, latency_ms, "compat_chat: upstream error");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": "upstream_error",
+                    "provider": cfg.id,
+                    "upstream_status": status,
+                    "upstream_body": resp,
+                    "latency_ms": latency_ms,
+                })),
+            )
+                .into_response()
+        }
+        Err(e) => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({
+                "error": "upstream_connection_failed",
+                "provider": cfg.id,
+                "message": e,
+            })),
+        )
+            .into_response(),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/ai/compat/{provider}/stream
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Streaming chat via SSE, emitting the same `stream_start` / `token` /
+/// `stream_end` / `error` events as `proxy_stream`. With an env API key the
+/// upstream SSE stream is relayed token by token; Vault-delegated calls are
+/// non-streaming upstream and get chunked like the main proxy does.
+pub(crate) async fn compat_stream<S>(
+    State(state): State<S>,
+    Path(provider): Path<String>,
+    Json(mut body): Json<GatewayChatRequest>,
+) -> impl IntoResponse
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    if !body.tools.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "tools_not_streamable",
+                "message": "Tool calling is only available on the non-streaming /chat endpoint",
+            })),
+        )
+            .into_response();
+    }
+    let required = CapabilityRequirements::for_request(&body, true);
+    let (cfg, model) = match resolve_provider(&state, &provider, &body, &required).await {
+        Ok(resolved) => resolved,
+        Err(e) => return e.into_response(),
+    };
+    if let Err(missing) = cfg.capabilities.check(&required) {
+        return capability_error(cfg.id, missing).into_response();
+    }
+    if let Err(e) = admit_request(state.gateway_db(), cfg.id, &mut body).await {
+        return e.into_response();
+    }
+    let images = match resolve_attachments(&body.attachments).await {
+        Ok(images) => images,
+        Err(e) => return attachment_error(e).into_response(),
+    };
+
+    let chat_path = match cfg.chat_path(&model) {
+        Ok(path) => path,
+        Err(e) => return invalid_model(cfg.id, e).into_response(),
+    };
+    let http_client = state.ai_gateway().http_client.clone();
+    let mut upstream_body = build_chat_payload(&AiProvider::OpenAI, &model, &body);
+    apply_images(&AiProvider::OpenAI, &mut upstream_body, &images);
+    if let Some(obj) = upstream_body.as_object_mut() {
+        obj.insert("stream".to_string(), json!(true));
+    }
+
+    let request_id = stream_event::current_request_id();
+    let stream = async_stream::stream! {
+        let started = Instant::now();
+        yield Ok::<_, Infallible>(Event::default()
+            .event("stream_start")
+            .data(json!({
+                "request_id": &request_id,
+                "provider": cfg.id,
+                "model": model,
+            }).to_string()));
+
+        let token_event = |text: &str| Event::default()
+            .event("token")
+            .data(stream_event::sse_token(&request_id, cfg.id, text).to_string());
+        let end_event = |finish_reason: &str, usage: Option<Usage>| Event::default()
+            .event("stream_end")
+            .data(stream_event::sse_end(
+                &StreamEvent::finish(&request_id, cfg.id, finish_reason, usage),
+                &model,
+                started.elapsed().as_millis() as u64,
+            ).to_string());
+
+        if let Some(key) = cfg.env_api_key() {
+            let req = http_client
+                .post(format!("{}{}", cfg.base_url(), chat_path))
+                .timeout(std::time::Duration::from_secs(COMPAT_TIMEOUT_SECS))
+                .json(&upstream_body);
+            let req = cfg.authorize(req, &key);
+
+            let resp = match req.send().await {
+                Ok(r) if r.status().is_success() => r,
+                Ok(r) => {
+                    yield Ok(Event::default().event("error").data(json!({
+                        "error": "upstream_error",
+                        "provider": cfg.id,
+                        "upstream_status": r.status().as_u16(),
+                    }).to_string()));
+                    return;
+                }
+                Err(e) => {
+                    yield Ok(Event::default().event("error").data(json!({
+                        "error": "upstream_connection_failed",
+                        "provider": cfg.id,
+                        "message": e.to_string(),
+                    }).to_string()));
+                    return;
+                }
+            };
+
+            let mut byte_stream = resp.bytes_stream();
+            let mut raw_buf: Vec<u8> = Vec::new();
+            let mut finish_reason = String::from("stop");
+            let mut usage = None;
+            let mut reply = String::new();
+            while let Some(chunk_result) = byte_stream.next().await {
+                let chunk = match chunk_result {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        // Connection dropped mid-stream: end with the error, not `stop`.
+                        let mut data = stream_event::sse_end(
+                            &StreamEvent::finish(&request_id, cfg.id, "error", usage),
+                            &model,
+                            started.elapsed().as_millis() as u64,
+                        );
+                        data["error"] = json!(e.to_string());
+                        record_cost(state.gateway_db(), cfg.id, &model, &body, &reply, usage);
+                        yield Ok(Event::default().event("stream_end").data(data.to_string()));
+                        return;
+                    }
+                };
+                raw_buf.extend_from_slice(&chunk);
+                for event in parse_sse_lines(&mut raw_buf) {
+                    let choice = event.get("choices").and_then(|c| c.get(0));
+                    if let Some(text) = choice
+                        .and_then(|c| c.get("delta"))
+                        .and_then(|d| d.get("content"))
+                        .and_then(|t| t.as_str())
+                        .filter(|t| !t.is_empty())
+                    {
+                        reply.push_str(text);
+                        yield Ok(token_event(text));
+                    }
+                    if let Some(reason) = choice
+                        .and_then(|c| c.get("finish_reason"))
+                        .and_then(|r| r.as_str())
+                    {
+                        finish_reason = reason.to_string();
+                    }
+                    // Sent on the last chunk by providers that report stream usage
+                    if let Some(reported) = stream_event::response_usage(&event) {
+                        usage = Some(reported);
+                    }
+                }
+            }
+            record_cost(state.gateway_db(), cfg.id, &model, &body, &reply, usage);
+            yield Ok(end_event(&finish_reason, usage));
+            return;
+        }
+
+        // Vault Bouncer path — upstream is called without streaming.
+        if let Some(obj) = upstream_body.as_object_mut() {
+            obj.insert("stream".to_string(), json!(false));
+        }
+        match send_compat_request(&state, &cfg, "POST", &chat_path, Some(upstream_body)).await {
+            Ok((status, resp)) if (200..300).contains(&status) => {
+                let content = extract_content_text(&AiProvider::OpenAI, &resp);
+                let usage = stream_event::response_usage(&resp);
+                record_cost(state.gateway_db(), cfg.id, &model, &body, &content, usage);
+                for chunk in chunk_text(&content, 20) {
+                    yield Ok(token_event(chunk));
+                }
+                let reason = stream_event::response_finish_reason(&resp).unwrap_or("stop");
+                yield Ok(end_event(reason, usage));
+            }
+            Ok((status, _)) => {
+                yield Ok(Event::default().event("error").data(json!({
+                    "error": "upstream_error",
+                    "provider": cfg.id,
+                    "upstream_status": status,
+                }).to_string()));
+            }
+            Err(e) => {
+                yield Ok(Event::default().event("error").data(json!({
+                    "error": "upstream_connection_failed",
+                    "provider": cfg.id,
+                    "message": e,
+                }).to_string()));
+            }
+        }
+    };
+
+    Sse::new(stream)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_compat_provider_case_insensitive() {
+        assert!(find_compat_provider("openrouter").is_some());
+        assert!(find_compat_provider("OpenRouter").is_some());
+        assert!(find_compat_provider("anthropic").is_none());
+    }
+
+    #[test]
+    fn openai_flavor_paths() {
+        let groq = find_compat_provider("groq").unwrap();
+        assert_eq!(groq.chat_path("llama").unwrap(), "/chat/completions");
+        assert_eq!(groq.models_path(), "/models");
+        assert_eq!(groq.base_url(), "https://api.groq.com/openai/v1");
+    }
+
+    #[cfg(feature = "azure")]
+    #[test]
+    fn azure_flavor_uses_deployment_path() {
+        let azure = all_compat_provider_configs()
+            .into_iter()
+            .find(|c| c.id == "azure")
+            .unwrap();
+        let path = azure.chat_path("my-gpt4o").unwrap();
+        assert!(path.starts_with("/openai/deployments/my-gpt4o/chat/completions?api-version="));
+        let path = azure.chat_path("my gpt%4o").unwrap();
+        assert!(path.starts_with("/openai/deployments/my%20gpt%254o/chat/"));
+        for bad in ["", "a/b", "x?api-version=1", "x#y", "..", "a..b", "a\\b"] {
+            assert!(azure.chat_path(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[cfg(feature = "azure")]
+    #[test]
+    fn azure_without_endpoint_is_disabled() {
+        if std::env::var("AZURE_OPENAI_ENDPOINT").is_ok() {
+            return;
+        }
+        assert!(find_compat_provider("azure").is_none());
+        assert!(all_compat_provider_configs().iter().any(|c| c.id == "azure"));
+    }
+
+    #[test]
+    fn route_model_picks_the_serving_provider() {
+        let (cfg, model) = route_model("groq/llama-3.3-70b-versatile").unwrap();
+        assert_eq!((cfg.id, model.as_str()), ("groq", "llama-3.3-70b-versatile"));
+        let (cfg, model) = route_model("openrouter/anthropic/claude-sonnet-4").unwrap();
+        assert_eq!((cfg.id, model.as_str()), ("openrouter", "anthropic/claude-sonnet-4"));
+        assert!(route_model("claude-sonnet-4-6").is_none());
+        assert!(route_model("gemini-2.5-pro").is_none());
+        assert!(route_model("ollama/llama3").is_none());
+        assert!(route_model("groq/").is_none());
+    }
+
+    #[cfg(not(feature = "azure"))]
+    #[test]
+    fn openai_models_go_to_openrouter() {
+        let (cfg, model) = route_model("gpt-4o").unwrap();
+        assert_eq!((cfg.id, model.as_str()), ("openrouter", "openai/gpt-4o"));
+        assert_eq!(route_model("o3-mini").unwrap().1, "openai/o3-mini");
+        assert!(route_model("omni").is_none());
+    }
+
+    #[test]
+    fn groq_is_low_latency() {
+        let groq = find_compat_provider("groq").unwrap();
+        assert!(groq.low_latency);
+        assert!(!find_compat_provider("openrouter").unwrap().low_latency);
+    }
+
+    #[test]
+    fn pick_auto_provider_prefers_low_latency_for_short_prompts() {
+        let both = [("openrouter", false), ("groq", true)];
+        assert_eq!(pick_auto_provider(120, &both), Some("groq"));
+        assert_eq!(pick_auto_provider(SHORT_PROMPT_MAX_CHARS + 1, &both), Some("openrouter"));
+        assert_eq!(pick_auto_provider(120, &[("openrouter", false)]), Some("openrouter"));
+    }
+
+    #[test]
+    fn pick_auto_provider_only_picks_configured_providers() {
+        assert_eq!(pick_auto_provider(SHORT_PROMPT_MAX_CHARS + 1, &[("groq", true)]), Some("groq"));
+        assert_eq!(pick_auto_provider(120, &[("azure", false)]), Some("azure"));
+        assert_eq!(pick_auto_provider(120, &[]), None);
+    }
+
+    #[test]
+    fn parse_models_response_openrouter_pricing() {
+        let body = json!({
+            "data": [
+                {
+                    "id": "anthropic/claude-sonnet-4",
+                    "name": "Anthropic: Claude Sonnet 4",
+                    "context_length": 200000,
+                    "pricing": { "prompt": "0.000003", "completion": "0.000015" }
+                },
+                { "id": "bare-model" },
+                { "name": "missing id is skipped" }
+            ]
+        });
+        let models = parse_models_response(&body);
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].context_length, Some(200_000));
+        assert_eq!(models[0].prompt_usd_per_mtok, Some(3.0));
+        assert_eq!(models[0].completion_usd_per_mtok, Some(15.0));
+        assert_eq!(models[1].name, "bare-model");
+        assert_eq!(models[1].prompt_usd_per_mtok, None);
+    }
+
+    #[test]
+    fn parse_models_response_empty_on_garbage() {
+        assert!(parse_models_response(&json!({"error": "nope"})).is_empty());
+    }
+}
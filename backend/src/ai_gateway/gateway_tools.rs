@@ -0,0 +1,653 @@
+// gateway_tools.rs — Function-calling tools for gateway chat requests.
+//
+// A small registry of local tools the model may call while answering a
+// gateway prompt (`GatewayChatRequest.tools`):
+// - `shell`      — run a shell command in an allowed directory
+// - `read_file`  — read a text file inside ALLOWED_FILE_DIRS (the agent's
+//                  `read_file`, with its size and binary checks)
+// - `http_fetch` — fetch a web page through the agent's SSRF-guarded
+//                  `fetch_webpage`, on the gateway's shared HTTP client
+//
+// Tool output is capped at MAX_OUTPUT_BYTES; shell output beyond that is
+// discarded as it arrives rather than buffered. A shell command runs in its
+// own process group, which is killed as a whole when it times out.
+//
+// Every tool executes local side effects on the user's behalf, so all of
+// them require the `yolo` permission mode to be configured explicitly (the
+// active profile's or `CH_PERMISSION_MODE`, see `permissions.rs`); requests
+// naming tools are rejected with 403 otherwise. Shell commands are recorded in the audit
+// trail (see `audit_trail.rs`).
+//
+// `run_tool_loop` drives the provider's native tool-use protocol (Anthropic
+// `tool_use`, OpenAI-compatible `tool_calls`, Gemini `functionCall`, Ollama
+// `message.tool_calls`) until the model answers without calling a tool, and
+// returns every call as a `ToolCallRecord` for the response trace.
+
+use std::future::Future;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::ai_gateway::AiProvider;
+use crate::audit_trail::Attribution;
+use crate::processes::{kill_process_group, own_process_group};
+use crate::tools::allowed_dirs_from_env;
+use crate::tools::fs_tools::{exec_read_file, validate_path};
+
+/// Tool-use round trips per request before the loop gives up.
+pub const MAX_TOOL_ITERATIONS: usize = 8;
+const TOOL_TIMEOUT: Duration = Duration::from_secs(30);
+/// Tool output returned to the model (and kept in the trace).
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Whether local tools may run unattended: only when `yolo` is the
+/// configured permission mode, not merely the unconfigured default.
+pub fn yolo_enabled() -> bool {
+    crate::permissions::configured_mode() == Some(crate::permissions::PermissionMode::Yolo)
+}
+
+/// Ollama model families trained for function calling.
+pub fn is_ollama_tool_model(model: &str) -> bool {
+    const FAMILIES: &[&str] = &[
+        "llama3.1", "llama3.2", "llama3.3", "llama4", "qwen2.5", "qwen3", "mistral",
+        "mixtral", "command-r", "firefunction", "hermes3", "granite3",
+    ];
+    let lower = model.to_lowercase();
+    FAMILIES.iter().any(|f| lower.starts_with(f))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GatewayTool {
+    Shell,
+    ReadFile,
+    HttpFetch,
+}
+
+impl GatewayTool {
+    pub const ALL: [GatewayTool; 3] = [GatewayTool::Shell, GatewayTool::ReadFile, GatewayTool::HttpFetch];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GatewayTool::Shell => "shell",
+            GatewayTool::ReadFile => "read_file",
+            GatewayTool::HttpFetch => "http_fetch",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.name() == name)
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            GatewayTool::Shell => "Run a shell command on the user's machine and return its exit code, stdout and stderr.",
+            GatewayTool::ReadFile => "Read a UTF-8 text file from the user's project directories.",
+            GatewayTool::HttpFetch => "Fetch a web page or API response with HTTP GET and return the body.",
+        }
+    }
+
+    fn parameters(self) -> Value {
+        match self {
+            GatewayTool::Shell => json!({
+                "type": "object",
+                "properties": {
+                    "command": { "type": "string", "description": "Command line to execute" },
+                    "cwd": { "type": "string", "description": "Working directory (optional)" }
+                },
+                "required": ["command"]
+            }),
+            GatewayTool::ReadFile => json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Absolute file path" }
+                },
+                "required": ["path"]
+            }),
+            GatewayTool::HttpFetch => json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string", "description": "http(s) URL" }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+}
+
+/// Validate requested tool names; `Err` is a ready-to-return response.
+pub(crate) fn resolve_tools(names: &[String]) -> Result<Vec<GatewayTool>, (StatusCode, Json<Value>)> {
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    if !yolo_enabled() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "tools_disabled",
+                "message": "Local tools require the yolo permission mode (CH_PERMISSION_MODE=yolo or the active profile's)",
+            })),
+        ));
+    }
+    let mut tools = Vec::new();
+    for name in names {
+        let tool = GatewayTool::from_name(name).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": "unknown_tool",
+                    "message": format!("Unknown tool '{}'", name),
+                    "available": GatewayTool::ALL.map(|t| t.name()),
+                })),
+            )
+        })?;
+        if !tools.contains(&tool) {
+            tools.push(tool);
+        }
+    }
+    Ok(tools)
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Wire formats
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Tool-calling dialect spoken by a provider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wire {
+    Anthropic,
+    OpenAi,
+    Gemini,
+    Ollama,
+}
+
+impl Wire {
+    fn of(provider: &AiProvider) -> Self {
+        match provider {
+            AiProvider::Anthropic => Wire::Anthropic,
+            AiProvider::Google => Wire::Gemini,
+            AiProvider::Ollama => Wire::Ollama,
+            AiProvider::OpenAI | AiProvider::Xai | AiProvider::DeepSeek => Wire::OpenAi,
+        }
+    }
+}
+
+/// Add tool declarations to an upstream payload in the provider's format.
+pub(crate) fn apply_tool_definitions(provider: &AiProvider, payload: &mut Value, tools: &[GatewayTool]) {
+    if tools.is_empty() {
+        return;
+    }
+    let Some(obj) = payload.as_object_mut() else {
+        return;
+    };
+    let defs: Value = match Wire::of(provider) {
+        Wire::Anthropic => tools
+            .iter()
+            .map(|t| json!({ "name": t.name(), "description": t.description(), "input_schema": t.parameters() }))
+            .collect(),
+        Wire::OpenAi | Wire::Ollama => tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "type": "function",
+                    "function": { "name": t.name(), "description": t.description(), "parameters": t.parameters() }
+                })
+            })
+            .collect(),
+        Wire::Gemini => json!([{
+            "functionDeclarations": tools
+                .iter()
+                .map(|t| json!({ "name": t.name(), "description": t.description(), "parameters": t.parameters() }))
+                .collect::<Vec<_>>()
+        }]),
+    };
+    obj.insert("tools".to_string(), defs);
+}
+
+/// A tool call requested by the model.
+#[derive(Debug, Clone, PartialEq)]
+struct PendingCall {
+    id: String,
+    name: String,
+    input: Value,
+}
+
+/// Tool calls in a provider response (empty = final answer).
+fn extract_tool_calls(wire: Wire, response: &Value) -> Vec<PendingCall> {
+    match wire {
+        Wire::Anthropic => response
+            .get("content")
+            .and_then(|c| c.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("tool_use"))
+            .map(|b| PendingCall {
+                id: b.get("id").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                name: b.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                input: b.get("input").cloned().unwrap_or(json!({})),
+            })
+            .collect(),
+        Wire::OpenAi | Wire::Ollama => {
+            let message = if wire == Wire::OpenAi {
+                response.pointer("/choices/0/message")
+            } else {
+                response.get("message")
+            };
+            message
+                .and_then(|m| m.get("tool_calls"))
+                .and_then(|c| c.as_array())
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .map(|(i, c)| {
+                    // OpenAI sends arguments as a JSON string, Ollama as an object.
+                    let args = c.pointer("/function/arguments").cloned().unwrap_or(json!({}));
+                    let input = match args {
+                        Value::String(s) => serde_json::from_str(&s).unwrap_or(json!({})),
+                        other => other,
+                    };
+                    PendingCall {
+                        id: c.get("id").and_then(|v| v.as_str()).map(str::to_string).unwrap_or_else(|| format!("call_{}", i)),
+                        name: c.pointer("/function/name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                        input,
+                    }
+                })
+                .collect()
+        }
+        Wire::Gemini => response
+            .pointer("/candidates/0/content/parts")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.get("functionCall"))
+            .enumerate()
+            .map(|(i, f)| PendingCall {
+                id: format!("call_{}", i),
+                name: f.get("name").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+                input: f.get("args").cloned().unwrap_or(json!({})),
+            })
+            .collect(),
+    }
+}
+
+/// Append the assistant turn and the tool results to the conversation.
+fn append_tool_turn(wire: Wire, payload: &mut Value, response: &Value, results: &[(PendingCall, ToolOutcome)]) {
+    let key = if wire == Wire::Gemini { "contents" } else { "messages" };
+    let Some(messages) = payload.get_mut(key).and_then(|m| m.as_array_mut()) else {
+        return;
+    };
+    match wire {
+        Wire::Anthropic => {
+            messages.push(json!({ "role": "assistant", "content": response.get("content").cloned().unwrap_or(json!([])) }));
+            messages.push(json!({
+                "role": "user",
+                "content": results.iter().map(|(call, out)| json!({
+                    "type": "tool_result",
+                    "tool_use_id": call.id,
+                    "content": out.output,
+                    "is_error": out.is_error,
+                })).collect::<Vec<_>>(),
+            }));
+        }
+        Wire::OpenAi => {
+            if let Some(msg) = response.pointer("/choices/0/message") {
+                messages.push(msg.clone());
+            }
+            for (call, out) in results {
+                messages.push(json!({ "role": "tool", "tool_call_id": call.id, "content": out.output }));
+            }
+        }
+        Wire::Ollama => {
+            if let Some(msg) = response.get("message") {
+                messages.push(msg.clone());
+            }
+            for (_, out) in results {
+                messages.push(json!({ "role": "tool", "content": out.output }));
+            }
+        }
+        Wire::Gemini => {
+            if let Some(content) = response.pointer("/candidates/0/content") {
+                messages.push(content.clone());
+            }
+            messages.push(json!({
+                "role": "user",
+                "parts": results.iter().map(|(call, out)| json!({
+                    "functionResponse": { "name": call.name, "response": { "content": out.output, "is_error": out.is_error } }
+                })).collect::<Vec<_>>(),
+            }));
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Execution
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+struct ToolOutcome {
+    output: String,
+    is_error: bool,
+}
+
+impl ToolOutcome {
+    fn ok(output: String) -> Self {
+        Self { output: truncate_output(output), is_error: false }
+    }
+
+    fn err(message: impl Into<String>) -> Self {
+        Self { output: message.into(), is_error: true }
+    }
+}
+
+impl From<(String, bool)> for ToolOutcome {
+    /// The `(output, is_error)` pair returned by the agent tools.
+    fn from((output, is_error): (String, bool)) -> Self {
+        Self { output: truncate_output(output), is_error }
+    }
+}
+
+fn truncate_output(mut text: String) -> String {
+    if text.len() > MAX_OUTPUT_BYTES {
+        let cut = text.floor_char_boundary(MAX_OUTPUT_BYTES);
+        text.truncate(cut);
+        text.push_str("\n[output truncated]");
+    }
+    text
+}
+
+/// Read a pipe to its end, keeping only what the model can be shown.
+async fn read_capped(pipe: Option<impl AsyncRead + Unpin>) -> std::io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let Some(mut pipe) = pipe else {
+        return Ok(buf);
+    };
+    (&mut pipe).take(MAX_OUTPUT_BYTES as u64 + 1).read_to_end(&mut buf).await?;
+    // Drain the rest so the command does not stall on a full pipe.
+    tokio::io::copy(&mut pipe, &mut tokio::io::sink()).await?;
+    Ok(buf)
+}
+
+/// One executed tool call, as reported in the response trace.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCallRecord {
+    pub iteration: usize,
+    pub tool: String,
+    pub input: Value,
+    pub output: String,
+    pub is_error: bool,
+    pub duration_ms: u64,
+}
+
+async fn execute_tool(
+    call: &PendingCall,
+    enabled: &[GatewayTool],
+    by: &Attribution,
+    client: &reqwest::Client,
+) -> ToolOutcome {
+    let Some(tool) = GatewayTool::from_name(&call.name).filter(|t| enabled.contains(t)) else {
+        return ToolOutcome::err(format!("Tool '{}' is not available", call.name));
+    };
+    let arg = |key: &str| call.input.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    match tool {
+        GatewayTool::Shell => match arg("command") {
+            Some(command) => run_shell(&command, arg("cwd").as_deref(), by).await,
+            None => ToolOutcome::err("Missing 'command'"),
+        },
+        GatewayTool::ReadFile => match arg("path") {
+            Some(path) => exec_read_file(&json!({ "path": path }), &allowed_dirs_from_env()).await.into(),
+            None => ToolOutcome::err("Missing 'path'"),
+        },
+        GatewayTool::HttpFetch => match arg("url") {
+            Some(url) => http_fetch(&url, client).await,
+            None => ToolOutcome::err("Missing 'url'"),
+        },
+    }
+}
+
+async fn run_shell(command: &str, cwd: Option<&str>, by: &Attribution) -> ToolOutcome {
+    let allowed = allowed_dirs_from_env();
+    let dir = match cwd {
+        Some(raw) => match validate_path(raw, &allowed) {
+            Ok(p) => p,
+            Err(e) => return ToolOutcome::err(e),
+        },
+        None => allowed.first().cloned().unwrap_or_else(|| std::path::PathBuf::from(".")),
+    };
+    let mut cmd = if cfg!(windows) {
+        let mut c = tokio::process::Command::new("cmd");
+        c.arg("/C").arg(command);
+        c
+    } else {
+        let mut c = tokio::process::Command::new("sh");
+        c.arg("-c").arg(command);
+        c
+    };
+    cmd.current_dir(&dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    own_process_group(&mut cmd);
+    let started = Instant::now();
+    let result = match cmd.spawn() {
+        Ok(mut child) => {
+            let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+            let run = async {
+                let (out, err, status) = tokio::join!(read_capped(stdout), read_capped(stderr), child.wait());
+                Ok::<_, std::io::Error>((status?, out?, err?))
+            };
+            let result = tokio::time::timeout(TOOL_TIMEOUT, run).await;
+            if result.is_err() {
+                kill_process_group(&mut child, "gateway_tools").await;
+            }
+            result
+        }
+        Err(e) => Ok(Err(e)),
+    };
+    let exit_code = match &result {
+        Ok(Ok((status, _, _))) => status.code(),
+        _ => None,
+    };
+    let std_cmd = cmd.as_std();
+    let args: Vec<String> = std_cmd.get_args().map(|a| a.to_string_lossy().into_owned()).collect();
+    crate::audit_trail::command(
+        "gateway_shell",
+        crate::audit_trail::CommandRun {
+            binary: &std_cmd.get_program().to_string_lossy(),
+            args: &args,
+            cwd: &dir.to_string_lossy(),
+            exit_code,
+            duration_ms: started.elapsed().as_millis() as u64,
+        },
+        by.clone(),
+    );
+    match result {
+        Err(_) => ToolOutcome::err(format!("Command timed out after {}s", TOOL_TIMEOUT.as_secs())),
+        Ok(Err(e)) => ToolOutcome::err(format!("Failed to start command: {}", e)),
+        Ok(Ok((status, stdout, stderr))) => {
+            let text = format!(
+                "exit code: {}\n--- stdout ---\n{}\n--- stderr ---\n{}",
+                status.code().map(|c| c.to_string()).unwrap_or_else(|| "signal".into()),
+                String::from_utf8_lossy(&stdout),
+                String::from_utf8_lossy(&stderr)
+            );
+            ToolOutcome { is_error: !status.success(), ..ToolOutcome::ok(text) }
+        }
+    }
+}
+
+async fn http_fetch(url: &str, client: &reqwest::Client) -> ToolOutcome {
+    let input = json!({ "url": url });
+    match tokio::time::timeout(TOOL_TIMEOUT, crate::tools::web::fetch_webpage(&input, client)).await {
+        Ok(result) => result.into(),
+        Err(_) => ToolOutcome::err(format!("Fetch timed out after {}s", TOOL_TIMEOUT.as_secs())),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tool-use loop
+// ═══════════════════════════════════════════════════════════════════════════
+
+/// Final provider response plus the calls made along the way.
+#[derive(Debug)]
+pub struct ToolLoopResult {
+    pub response: Value,
+    pub tool_calls: Vec<ToolCallRecord>,
+}
+
+/// Execute requested tools and re-send until the model stops calling them.
+///
+/// `payload` is the request that produced `response` (with tool
+/// definitions applied); `by` attributes tool runs in the audit trail;
+/// `send` performs one upstream round trip and yields `(status, body)`.
+pub(crate) async fn run_tool_loop<F, Fut>(
+    provider: &AiProvider,
+    mut payload: Value,
+    mut response: Value,
+    tools: &[GatewayTool],
+    by: &Attribution,
+    client: &reqwest::Client,
+    send: F,
+) -> Result<ToolLoopResult, String>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = Result<(u16, Value), String>>,
+{
+    let wire = Wire::of(provider);
+    let mut trace = Vec::new();
+    for iteration in 1..=MAX_TOOL_ITERATIONS {
+        let calls = extract_tool_calls(wire, &response);
+        if calls.is_empty() {
+            return Ok(ToolLoopResult { response, tool_calls: trace });
+        }
+        let mut results = Vec::with_capacity(calls.len());
+        for call in calls {
+            let started = Instant::now();
+            let outcome = execute_tool(&call, tools, by, client).await;
+            tracing::info!(tool = %call.name, iteration, is_error = outcome.is_error, "gateway_tools: tool executed");
+            trace.push(ToolCallRecord {
+                iteration,
+                tool: call.name.clone(),
+                input: call.input.clone(),
+                output: outcome.output.clone(),
+                is_error: outcome.is_error,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+            results.push((call, outcome));
+        }
+        append_tool_turn(wire, &mut payload, &response, &results);
+        let (status, body) = send(payload.clone()).await?;
+        if !(200..300).contains(&status) {
+            return Err(format!("Upstream returned HTTP {} during tool loop", status));
+        }
+        response = body;
+    }
+    Err(format!("Tool loop exceeded {} iterations", MAX_TOOL_ITERATIONS))
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  Tests
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_definitions_per_provider() {
+        let tools = [GatewayTool::Shell];
+        let mut anthropic = json!({ "messages": [] });
+        apply_tool_definitions(&AiProvider::Anthropic, &mut anthropic, &tools);
+        assert_eq!(anthropic["tools"][0]["name"], "shell");
+        assert!(anthropic["tools"][0]["input_schema"].is_object());
+
+        let mut openai = json!({ "messages": [] });
+        apply_tool_definitions(&AiProvider::DeepSeek, &mut openai, &tools);
+        assert_eq!(openai["tools"][0]["function"]["name"], "shell");
+
+        let mut gemini = json!({ "contents": [] });
+        apply_tool_definitions(&AiProvider::Google, &mut gemini, &tools);
+        assert_eq!(gemini["tools"][0]["functionDeclarations"][0]["name"], "shell");
+    }
+
+    #[test]
+    fn extracts_calls_from_each_dialect() {
+        let anthropic = json!({ "content": [
+            { "type": "text", "text": "Let me check." },
+            { "type": "tool_use", "id": "tu_1", "name": "read_file", "input": { "path": "/a" } }
+        ]});
+        let calls = extract_tool_calls(Wire::Anthropic, &anthropic);
+        assert_eq!(calls.len(), 1);
+        assert_eq!((calls[0].id.as_str(), calls[0].name.as_str()), ("tu_1", "read_file"));
+
+        let openai = json!({ "choices": [{ "message": { "tool_calls": [
+            { "id": "c1", "type": "function", "function": { "name": "shell", "arguments": "{\"command\":\"ls\"}" } }
+        ]}}]});
+        assert_eq!(extract_tool_calls(Wire::OpenAi, &openai)[0].input["command"], "ls");
+
+        let ollama = json!({ "message": { "tool_calls": [
+            { "function": { "name": "http_fetch", "arguments": { "url": "https://x" } } }
+        ]}});
+        let calls = extract_tool_calls(Wire::Ollama, &ollama);
+        assert_eq!((calls[0].id.as_str(), calls[0].input["url"].as_str()), ("call_0", Some("https://x")));
+
+        assert!(extract_tool_calls(Wire::Anthropic, &json!({ "content": [{ "type": "text", "text": "done" }] })).is_empty());
+    }
+
+    #[test]
+    fn anthropic_tool_turn_pairs_results_with_ids() {
+        let response = json!({ "content": [{ "type": "tool_use", "id": "tu_1", "name": "shell", "input": {} }] });
+        let mut payload = json!({ "messages": [{ "role": "user", "content": "hi" }] });
+        let call = extract_tool_calls(Wire::Anthropic, &response).remove(0);
+        append_tool_turn(Wire::Anthropic, &mut payload, &response, &[(call, ToolOutcome::err("boom"))]);
+        let messages = payload["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "tu_1");
+        assert_eq!(messages[2]["content"][0]["is_error"], true);
+    }
+
+    #[tokio::test]
+    async fn disabled_tools_are_refused() {
+        let call = PendingCall { id: "1".into(), name: "shell".into(), input: json!({ "command": "echo hi" }) };
+        let client = reqwest::Client::new();
+        let out = execute_tool(&call, &[GatewayTool::ReadFile], &Attribution::default(), &client).await;
+        assert!(out.is_error);
+    }
+
+    #[tokio::test]
+    async fn loop_stops_when_model_answers() {
+        let first = json!({ "content": [{ "type": "tool_use", "id": "t", "name": "nope", "input": {} }] });
+        let result = run_tool_loop(
+            &AiProvider::Anthropic,
+            json!({ "messages": [] }),
+            first,
+            &[GatewayTool::ReadFile],
+            &Attribution::default(),
+            &reqwest::Client::new(),
+            |_| async { Ok((200, json!({ "content": [{ "type": "text", "text": "done" }] }))) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.tool_calls.len(), 1);
+        assert!(result.tool_calls[0].is_error);
+        assert_eq!(result.response["content"][0]["text"], "done");
+    }
+
+    #[test]
+    fn output_is_truncated() {
+        let out = truncate_output("x".repeat(MAX_OUTPUT_BYTES + 10));
+        assert!(out.ends_with("[output truncated]"));
+    }
+
+    #[tokio::test]
+    async fn pipes_are_drained_but_not_buffered() {
+        let flood = vec![b'x'; MAX_OUTPUT_BYTES * 4];
+        let kept = read_capped(Some(flood.as_slice())).await.unwrap();
+        assert_eq!(kept.len(), MAX_OUTPUT_BYTES + 1);
+        assert!(read_capped(None::<&[u8]>).await.unwrap().is_empty());
+    }
+}
@@ -1,8 +1,11 @@
 // helpers.rs — Private utility functions for AI Gateway handlers.
 
+use axum::extract::Json;
+use axum::http::StatusCode;
 use serde_json::{json, Value};
 
 use crate::ai_gateway::AiProvider;
+use crate::stream_event::Usage;
 use super::types::GatewayChatRequest;
 
 /// Resolve the upstream URL, replacing `{model}` placeholder if present.
@@ -54,7 +57,7 @@ pub(crate) fn build_chat_payload(
     let temperature = request.temperature.unwrap_or(0.7);
     let max_tokens = request.max_tokens.unwrap_or(4096);
 
-    match provider {
+    let mut payload = match provider {
         AiProvider::Anthropic => {
             let messages: Vec<Value> = request
                 .messages
@@ -147,6 +150,43 @@ pub(crate) fn build_chat_payload(
                 "stream": false,
             })
         }
+    };
+
+    if let Some(format) = &request.response_format {
+        apply_response_format(provider, &mut payload, format);
+    }
+    if *provider == AiProvider::Ollama {
+        crate::ollama::apply_keep_alive(&mut payload);
+    }
+    payload
+}
+
+/// Translate an OpenAI-style `response_format` into the provider's JSON mode.
+/// Providers without one (Anthropic) are filtered out by the capability check.
+fn apply_response_format(provider: &AiProvider, payload: &mut Value, format: &Value) {
+    let Some(obj) = payload.as_object_mut() else {
+        return;
+    };
+    match provider {
+        AiProvider::OpenAI | AiProvider::Xai | AiProvider::DeepSeek => {
+            obj.insert("response_format".to_string(), format.clone());
+        }
+        AiProvider::Google => {
+            if let Some(cfg) = obj.get_mut("generationConfig").and_then(|c| c.as_object_mut()) {
+                cfg.insert("responseMimeType".to_string(), json!("application/json"));
+                if let Some(schema) = format.pointer("/json_schema/schema") {
+                    cfg.insert("responseSchema".to_string(), schema.clone());
+                }
+            }
+        }
+        AiProvider::Ollama => {
+            let schema = format
+                .pointer("/json_schema/schema")
+                .cloned()
+                .unwrap_or_else(|| json!("json"));
+            obj.insert("format".to_string(), schema);
+        }
+        AiProvider::Anthropic => {}
     }
 }
 
@@ -236,3 +276,75 @@ pub(crate) fn chunk_text(text: &str, chunk_size: usize) -> Vec<&str> {
     }
     chunks
 }
+
+// ── Request policy ──────────────────────────────────────────────────────────
+
+/// Provider id used for budgets and cost accounting (`budget::PROVIDERS`).
+pub(crate) fn provider_id(provider: &AiProvider) -> &'static str {
+    match provider {
+        AiProvider::Anthropic => "anthropic",
+        AiProvider::OpenAI => "openai",
+        AiProvider::Google => "google",
+        AiProvider::Xai => "xai",
+        AiProvider::DeepSeek => "deepseek",
+        AiProvider::Ollama => "ollama",
+    }
+}
+
+/// Checks a gateway request passes before it leaves the machine: the offline
+/// profile, the provider's budget cap and the safety guard, which redacts the
+/// last user message in place. Local Ollama requests skip all three.
+pub(crate) async fn admit_request(
+    db: &sqlx::PgPool,
+    provider: &str,
+    body: &mut GatewayChatRequest,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    if provider == "ollama" {
+        return Ok(());
+    }
+    crate::profiles::ensure_cloud()?;
+    crate::budget::ensure_within(db, provider).await?;
+    if let Some(last) = body.messages.iter_mut().rev().find(|m| m.role == "user") {
+        crate::safety::screen_prompt(&mut last.content, "gateway")
+            .map_err(|e| crate::safety::rejection(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    }
+    Ok(())
+}
+
+/// Model id a gateway cost is booked under: prefixed with the provider so
+/// `costs::provider_of` bills the provider that actually served it.
+pub(crate) fn cost_model(provider: &str, model: &str) -> String {
+    if model.starts_with(&format!("{}/", provider)) {
+        model.to_string()
+    } else {
+        format!("{}/{}", provider, model)
+    }
+}
+
+/// Record the cost of a served gateway request: the provider's usage when
+/// reported, else estimated from the prompt and reply text.
+pub(crate) fn record_cost(
+    db: &sqlx::PgPool,
+    provider: &str,
+    model: &str,
+    body: &GatewayChatRequest,
+    reply: &str,
+    usage: Option<Usage>,
+) {
+    let model = cost_model(provider, model);
+    let event = match usage {
+        Some(usage) => crate::costs::CostEvent {
+            model,
+            session_id: body.session_id,
+            requests: 1,
+            input_tokens: usage.input_tokens as i64,
+            output_tokens: usage.output_tokens as i64,
+            estimated: false,
+        },
+        None => {
+            let prompt: String = body.messages.iter().map(|m| m.content.as_str()).collect();
+            crate::costs::CostEvent::estimate(&model, body.session_id, &prompt, reply)
+        }
+    };
+    crate::costs::schedule(db, event);
+}
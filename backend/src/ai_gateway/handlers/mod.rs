@@ -5,7 +5,8 @@
 // - `router` — Sub-router builder + shared error helpers
 // - `providers` — Provider management handlers (list, status, connect, etc.)
 // - `proxy` — Chat proxy handlers (non-streaming + SSE streaming)
-// - `helpers` — Private utility functions (payload builders, content extractors)
+// - `helpers` — Private utility functions (payload builders, content extractors,
+//   request policy: offline profile, budget cap, safety guard, cost recording)
 
 mod types;
 pub mod router;
@@ -92,6 +93,10 @@ mod tests {
             temperature: Some(0.5),
             max_tokens: Some(1024),
             stream: None,
+            response_format: None,
+            attachments: Vec::new(),
+            tools: Vec::new(),
+            session_id: None,
         };
         let payload = build_chat_payload(&AiProvider::OpenAI, "gpt-4o", &request);
         assert_eq!(payload["model"], "gpt-4o");
@@ -117,6 +122,10 @@ mod tests {
             temperature: None,
             max_tokens: None,
             stream: None,
+            response_format: None,
+            attachments: Vec::new(),
+            tools: Vec::new(),
+            session_id: None,
         };
         let payload = build_chat_payload(&AiProvider::Google, "gemini-2.5-pro", &request);
         // Google maps "assistant" -> "model"
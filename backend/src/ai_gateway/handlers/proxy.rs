@@ -10,12 +10,23 @@ use axum::response::sse::{Event, KeepAlive, Sse};
 use serde_json::{json, Value};
 
 use crate::ai_gateway::{
-    AuthType, HasAiGateway,
-    vault_bridge::HasVaultBridge,
+    AiProvider, AuthType, HasAiGateway, ProviderConfig,
+    vault_bridge::{HasVaultBridge, VaultClient},
 };
 
+use crate::ai_gateway::attachments::{apply_images, attachment_error, resolve_attachments};
+use crate::ai_gateway::capabilities::{
+    CapabilityRequirements, capability_error, gateway_capabilities_for_model,
+};
+use crate::ai_gateway::gateway_tools::{
+    GatewayTool, apply_tool_definitions, resolve_tools, run_tool_loop,
+};
+use crate::audit_trail::Attribution;
+use crate::stream_event::{self, StreamEvent};
+
 use super::helpers::{
-    build_chat_payload, chunk_text, extract_content_text, resolve_upstream_url,
+    admit_request, build_chat_payload, chunk_text, extract_content_text, provider_id,
+    record_cost, resolve_upstream_url,
 };
 use super::router::{parse_provider, vault_error_response};
 use super::types::GatewayChatRequest;
@@ -34,7 +45,7 @@ use super::types::GatewayChatRequest;
 pub(crate) async fn proxy_chat<S>(
     State(state): State<S>,
     Path(provider): Path<String>,
-    Json(body): Json<GatewayChatRequest>,
+    Json(mut body): Json<GatewayChatRequest>,
 ) -> impl IntoResponse
 where
     S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
@@ -44,8 +55,40 @@ where
         Err(e) => return e.into_response(),
     };
 
+    // Offline profile, budget cap and safety guard (screens the prompt once).
+    let db = state.gateway_db();
+    if let Err(e) = admit_request(db, provider_id(&current_provider), &mut body).await {
+        return e.into_response();
+    }
+
+    // Never dispatch (or fail over) to a provider that cannot serve the request.
+    // Image prompts only reach vision-capable providers (Ollama: llava-class models).
+    let required = CapabilityRequirements::for_request(&body, false);
+    let requested_model = body.model.as_deref();
+    if let Err(missing) =
+        gateway_capabilities_for_model(&current_provider, requested_model).check(&required)
+    {
+        return capability_error(&current_provider.to_string(), missing).into_response();
+    }
+    let images = match resolve_attachments(&body.attachments).await {
+        Ok(images) => images,
+        Err(e) => return attachment_error(e).into_response(),
+    };
+    let tools = match resolve_tools(&body.tools) {
+        Ok(tools) => tools,
+        Err(e) => return e.into_response(),
+    };
+    let by = Attribution::session(body.session_id);
+
     let router = crate::ai_gateway::model_router::ModelRouter::new();
-    let fallback_chain = router.fallback_chain(current_provider);
+    let fallback_chain: Vec<_> = router
+        .fallback_chain(current_provider)
+        .into_iter()
+        .filter(|p| {
+            let model = if *p == current_provider { requested_model } else { None };
+            gateway_capabilities_for_model(p, model).check(&required).is_ok()
+        })
+        .collect();
 
     let original_model = body.model.clone();
     let gateway = state.ai_gateway();
@@ -59,6 +102,13 @@ where
             Some(cfg) => cfg,
             None => continue,
         };
+        // Fallbacks must pass the same gates (offline profile, budget cap).
+        if attempt > 0
+            && let Err(e) = admit_request(db, provider_id(provider_enum), &mut body).await
+        {
+            last_error_response = Some(e.into_response());
+            continue;
+        }
 
         // If fallback, we need to map to the new provider's model for the same tier
         let model = if attempt == 0 {
@@ -82,7 +132,11 @@ where
             "proxy_chat: routing request",
         );
 
-        let upstream_body = build_chat_payload(provider_enum, &model, &body);
+        let mut upstream_body = build_chat_payload(provider_enum, &model, &body);
+        apply_images(provider_enum, &mut upstream_body, &images);
+        apply_tool_definitions(provider_enum, &mut upstream_body, &tools);
+        // Kept for follow-up tool-use round trips.
+        let tool_payload = (!tools.is_empty()).then(|| upstream_body.clone());
         let upstream_url = resolve_upstream_url(&config.upstream_url, &model);
         let started = Instant::now();
 
@@ -95,13 +149,11 @@ where
                     last_latency = started.elapsed().as_millis() as u64;
                     if let Ok(json_body) = resp.json::<Value>().await {
                         if (200..300).contains(&(status as usize)) {
-                            return Json(json!({
-                                "provider": provider_enum.to_string(),
-                                "model": model,
-                                "latency_ms": last_latency,
-                                "response": json_body,
-                                "fallback_attempts": attempt,
-                            })).into_response();
+                            return finish_chat(
+                                vault, db, &body, config, &upstream_url, provider_enum, &model,
+                                attempt, started, json_body, tool_payload, &tools, &by,
+                                &gateway.http_client,
+                            ).await;
                         } else {
                             last_error_response = Some((
                                 StatusCode::BAD_GATEWAY,
@@ -152,13 +204,11 @@ where
                         latency_ms = last_latency,
                         "proxy_chat: upstream success",
                     );
-                    return Json(json!({
-                        "provider": provider_enum.to_string(),
-                        "model": model,
-                        "latency_ms": last_latency,
-                        "response": resp.body,
-                        "fallback_attempts": attempt,
-                    })).into_response();
+                    return finish_chat(
+                        vault, db, &body, config, &upstream_url, provider_enum, &model,
+                        attempt, started, resp.body, tool_payload, &tools, &by,
+                        &gateway.http_client,
+                    ).await;
                 } else {
                     tracing::warn!(
                         provider = %provider_enum,
@@ -210,6 +260,105 @@ where
     })
 }
 
+/// One upstream round trip via the provider's transport (direct for Ollama,
+/// Vault Bouncer otherwise). Returns `(status, body)`.
+async fn send_upstream(
+    vault: &VaultClient,
+    config: &ProviderConfig,
+    upstream_url: &str,
+    body: Value,
+) -> Result<(u16, Value), String> {
+    if config.auth_type == AuthType::None {
+        let resp = reqwest::Client::new()
+            .post(upstream_url)
+            .json(&body)
+            .timeout(std::time::Duration::from_secs(120))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = resp.status().as_u16();
+        return Ok((status, resp.json::<Value>().await.unwrap_or(Value::Null)));
+    }
+    vault
+        .delegate(upstream_url, "POST", &config.vault_namespace, &config.vault_service, Some(body))
+        .await
+        .map(|resp| (resp.status, resp.body))
+        .map_err(|e| e.to_string())
+}
+
+/// Build the success response, first completing any tool-use round trips
+/// when the request enabled tools (`tool_payload` is set), and record its cost.
+#[allow(clippy::too_many_arguments)]
+async fn finish_chat(
+    vault: &VaultClient,
+    db: &sqlx::PgPool,
+    body: &GatewayChatRequest,
+    config: &ProviderConfig,
+    upstream_url: &str,
+    provider: &AiProvider,
+    model: &str,
+    attempt: usize,
+    started: Instant,
+    response: Value,
+    tool_payload: Option<Value>,
+    tools: &[GatewayTool],
+    by: &Attribution,
+    client: &reqwest::Client,
+) -> axum::response::Response {
+    let Some(payload) = tool_payload else {
+        record_cost(
+            db,
+            provider_id(provider),
+            model,
+            body,
+            &extract_content_text(provider, &response),
+            stream_event::response_usage(&response),
+        );
+        return Json(json!({
+            "provider": provider.to_string(),
+            "model": model,
+            "latency_ms": started.elapsed().as_millis() as u64,
+            "response": response,
+            "fallback_attempts": attempt,
+        })).into_response();
+    };
+
+    let send = |b: Value| send_upstream(vault, config, upstream_url, b);
+    match run_tool_loop(provider, payload, response, tools, by, client, send).await {
+        Ok(result) => {
+            let content = extract_content_text(provider, &result.response);
+            record_cost(
+                db,
+                provider_id(provider),
+                model,
+                body,
+                &content,
+                stream_event::response_usage(&result.response),
+            );
+            Json(json!({
+                "provider": provider.to_string(),
+                "model": model,
+                "latency_ms": started.elapsed().as_millis() as u64,
+                "content": content,
+                "response": result.response,
+                "tool_calls": result.tool_calls,
+                "fallback_attempts": attempt,
+            })).into_response()
+        }
+        Err(e) => {
+            tracing::warn!(provider = %provider, error = %e, "proxy_chat: tool loop failed");
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": "tool_loop_failed",
+                    "provider": provider.to_string(),
+                    "message": e,
+                })),
+            ).into_response()
+        }
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 //  POST /api/ai/{provider}/stream — proxied streaming (SSE)
 // ═══════════════════════════════════════════════════════════════════════════
@@ -222,7 +371,7 @@ where
 pub(crate) async fn proxy_stream<S>(
     State(state): State<S>,
     Path(provider): Path<String>,
-    Json(body): Json<GatewayChatRequest>,
+    Json(mut body): Json<GatewayChatRequest>,
 ) -> impl IntoResponse
 where
     S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
@@ -232,12 +381,49 @@ where
         Err(e) => return e.into_response(),
     };
 
+    // Offline profile, budget cap and safety guard (screens the prompt once).
+    let db = state.gateway_db();
+    if let Err(e) = admit_request(db, provider_id(&current_provider), &mut body).await {
+        return e.into_response();
+    }
+
+    // Never dispatch (or fail over) to a provider that cannot serve the request.
+    // Image prompts only reach vision-capable providers (Ollama: llava-class models).
+    if !body.tools.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "tools_not_streamable",
+                "message": "Tool calling is only available on the non-streaming /chat endpoint",
+            })),
+        ).into_response();
+    }
+    let required = CapabilityRequirements::for_request(&body, true);
+    let requested_model = body.model.as_deref();
+    if let Err(missing) =
+        gateway_capabilities_for_model(&current_provider, requested_model).check(&required)
+    {
+        return capability_error(&current_provider.to_string(), missing).into_response();
+    }
+    let images = match resolve_attachments(&body.attachments).await {
+        Ok(images) => images,
+        Err(e) => return attachment_error(e).into_response(),
+    };
+
     let router = crate::ai_gateway::model_router::ModelRouter::new();
-    let fallback_chain = router.fallback_chain(current_provider);
+    let fallback_chain: Vec<_> = router
+        .fallback_chain(current_provider)
+        .into_iter()
+        .filter(|p| {
+            let model = if *p == current_provider { requested_model } else { None };
+            gateway_capabilities_for_model(p, model).check(&required).is_ok()
+        })
+        .collect();
 
     let original_model = body.model.clone();
     let cloned_state = state.clone();
     let vault_client = state.vault_client().clone();
+    let request_id = stream_event::current_request_id();
 
     let stream = async_stream::stream! {
         let mut last_error_response = None;
@@ -247,6 +433,14 @@ where
                 Some(cfg) => cfg.clone(),
                 None => continue,
             };
+            // Fallbacks must pass the same gates (offline profile, budget cap).
+            if attempt > 0
+                && let Err((_, Json(err))) =
+                    admit_request(cloned_state.gateway_db(), provider_id(&provider_enum), &mut body).await
+            {
+                last_error_response = Some(err["error"].as_str().unwrap_or("refused").to_string());
+                continue;
+            }
 
             let model = if attempt == 0 {
                 original_model.clone().unwrap_or_else(|| config.model_tiers.coordinator.clone())
@@ -270,17 +464,20 @@ where
             );
 
             let mut upstream_body = build_chat_payload(&provider_enum, &model, &body);
+            apply_images(&provider_enum, &mut upstream_body, &images);
             if let Some(obj) = upstream_body.as_object_mut() {
                 obj.insert("stream".to_string(), json!(true));
             }
 
             let upstream_url = resolve_upstream_url(&config.upstream_url, &model);
             let started = Instant::now();
+            let provider_name = provider_enum.to_string();
 
             if attempt == 0 {
                 yield Ok::<_, Infallible>(Event::default()
                     .event("stream_start")
                     .data(json!({
+                        "request_id": &request_id,
                         "provider": provider_enum.to_string(),
                         "model": model,
                     }).to_string()));
@@ -305,18 +502,27 @@ where
                             if (200..300).contains(&(status as usize)) {
                                 let content = extract_content_text(&provider_enum, &json_body);
                                 for chunk in chunk_text(&content, 20) {
-                                    yield Ok(Event::default()
-                                        .event("token")
-                                        .data(json!({ "text": chunk }).to_string()));
+                                    let data = stream_event::sse_token(&request_id, &provider_name, chunk);
+                                    yield Ok(Event::default().event("token").data(data.to_string()));
                                 }
-                                yield Ok(Event::default()
-                                    .event("stream_end")
-                                    .data(json!({
-                                        "provider": provider_enum.to_string(),
-                                        "model": model,
-                                        "latency_ms": latency_ms,
-                                        "finish_reason": "end_turn",
-                                    }).to_string()));
+                                let reason = stream_event::response_finish_reason(&json_body);
+                                let usage = stream_event::response_usage(&json_body);
+                                record_cost(
+                                    cloned_state.gateway_db(),
+                                    provider_id(&provider_enum),
+                                    &model,
+                                    &body,
+                                    &content,
+                                    usage,
+                                );
+                                let event = StreamEvent::finish(
+                                    &request_id,
+                                    &provider_name,
+                                    reason.unwrap_or("stop"),
+                                    usage,
+                                );
+                                let data = stream_event::sse_end(&event, &model, latency_ms);
+                                yield Ok(Event::default().event("stream_end").data(data.to_string()));
                                 return;
                             } else {
                                 last_error_response = Some(format!("Upstream returned HTTP {} (direct)", status));
@@ -345,19 +551,27 @@ where
                         let content = extract_content_text(&provider_enum, &resp.body);
 
                         for chunk in chunk_text(&content, 20) {
-                            yield Ok(Event::default()
-                                .event("token")
-                                .data(json!({ "text": chunk }).to_string()));
+                            let data = stream_event::sse_token(&request_id, &provider_name, chunk);
+                            yield Ok(Event::default().event("token").data(data.to_string()));
                         }
 
-                        yield Ok(Event::default()
-                            .event("stream_end")
-                            .data(json!({
-                                "provider": provider_enum.to_string(),
-                                "model": model,
-                                "latency_ms": latency_ms,
-                                "finish_reason": "end_turn",
-                            }).to_string()));
+                        let usage = stream_event::response_usage(&resp.body);
+                        record_cost(
+                            cloned_state.gateway_db(),
+                            provider_id(&provider_enum),
+                            &model,
+                            &body,
+                            &content,
+                            usage,
+                        );
+                        let event = StreamEvent::finish(
+                            &request_id,
+                            &provider_name,
+                            stream_event::response_finish_reason(&resp.body).unwrap_or("stop"),
+                            usage,
+                        );
+                        let data = stream_event::sse_end(&event, &model, latency_ms);
+                        yield Ok(Event::default().event("stream_end").data(data.to_string()));
                         return; // Successfully completed
                     } else {
                         last_error_response = Some(format!("Upstream returned HTTP {}", resp.status));
@@ -29,6 +29,9 @@ use super::proxy::*;
 /// POST /api/ai/providers/{provider}/disconnect — revoke + delete
 /// POST /api/ai/providers/{provider}/refresh  — force token refresh
 /// POST /api/ai/providers/{provider}/test     — test connection
+/// GET  /api/ai/capabilities                 — provider capability registry
+/// GET  /api/ai/compat/...                    — OpenAI-compatible extras (see `compat_providers`)
+/// POST /api/ai/bedrock/chat                  — AWS Bedrock (feature `bedrock`)
 /// ```
 pub fn ai_gateway_router<S>() -> Router<S>
 where
@@ -64,6 +67,30 @@ where
             "/api/ai/providers/{provider}/test",
             post(test_provider::<S>),
         )
+        .route(
+            "/api/ai/capabilities",
+            get(crate::ai_gateway::capabilities::list_provider_capabilities::<S>),
+        )
+        // ── OpenAI-compatible providers outside AiProvider ──────────────
+        .merge(crate::ai_gateway::compat_providers::compat_router::<S>())
+        // ── AWS Bedrock (SigV4 via AWS SDK, opt-in) ─────────────────────
+        .merge(bedrock_routes::<S>())
+}
+
+#[cfg(feature = "bedrock")]
+fn bedrock_routes<S>() -> Router<S>
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    crate::ai_gateway::bedrock::bedrock_router::<S>()
+}
+
+#[cfg(not(feature = "bedrock"))]
+fn bedrock_routes<S>() -> Router<S>
+where
+    S: HasAiGateway + HasVaultBridge + Clone + Send + Sync + 'static,
+{
+    Router::new()
 }
 
 // ── Helper: parse provider from path ────────────────────────────────────────
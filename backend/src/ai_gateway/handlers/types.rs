@@ -1,6 +1,9 @@
 // types.rs — Request / Response types for the AI Gateway HTTP handlers.
 
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ai_gateway::attachments::Attachment;
 
 // ── Request / Response Types ────────────────────────────────────────────────
 
@@ -26,6 +29,22 @@ pub struct GatewayChatRequest {
     /// Whether to stream (only relevant for the non-stream endpoint as a hint;
     /// the /stream endpoint always streams).
     pub stream: Option<bool>,
+    /// OpenAI-style structured output (`{"type": "json_object"}` or
+    /// `json_schema`). Only routed to providers with JSON mode.
+    #[serde(default)]
+    pub response_format: Option<Value>,
+    /// Images for the last user message. Only routed to vision-capable providers.
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    /// Local tools the model may call (`shell`, `read_file`, `http_fetch`).
+    /// Requires the `yolo` permission mode to be configured; non-streaming
+    /// endpoints only.
+    #[serde(default)]
+    pub tools: Vec<String>,
+    /// Session (tab) the request is made for; tool runs are attributed to it
+    /// in the audit trail.
+    #[serde(default)]
+    pub session_id: Option<uuid::Uuid>,
 }
 
 /// A single chat message (role + content).
@@ -3,10 +3,16 @@
 
 pub mod vault_bridge;
 pub mod vault_handlers;
+pub mod attachments;
 pub mod handlers;
 pub mod oauth_flows;
 pub mod session_manager;
 pub mod model_router;
+pub mod capabilities;
+pub mod compat_providers;
+pub mod gateway_tools;
+#[cfg(feature = "bedrock")]
+pub mod bedrock;
 
 use std::collections::HashMap;
 use std::fmt;
@@ -100,6 +106,8 @@ pub struct AiGatewayState {
     pub vault_client: vault_bridge::VaultClient,
     /// Unified OAuth PKCE flow manager for all providers.
     pub oauth_manager: oauth_flows::OAuthFlowManager,
+    /// Shared (pooled) client for direct upstream calls.
+    pub http_client: reqwest::Client,
 }
 
 // ── HasAiGateway trait ────────────────────────────────────────────────────────
@@ -110,6 +118,9 @@ pub trait HasAiGateway: Send + Sync + 'static {
     /// Returns a reference to the AI Gateway state.
     fn ai_gateway(&self) -> &AiGatewayState;
 
+    /// Database for budget checks and cost accounting of gateway requests.
+    fn gateway_db(&self) -> &sqlx::PgPool;
+
     /// Convenience: look up a single provider's config.
     fn provider_config(&self, provider: AiProvider) -> Option<&ProviderConfig> {
         self.ai_gateway().providers.get(&provider)
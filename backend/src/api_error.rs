@@ -0,0 +1,50 @@
+//! Shared JSON error responses for the REST handlers.
+//!
+//! Handlers fail with `(StatusCode, Json<{ "error": ... }>)`. Database errors
+//! are logged under the caller's target and reported without detail.
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde_json::{Value, json};
+
+pub(crate) type ApiError = (StatusCode, Json<Value>);
+
+/// Maps a database error to a 500, logging it under `target`:
+/// `.map_err(db_error("tab_quota"))`.
+pub(crate) fn db_error(target: &'static str) -> impl Fn(sqlx::Error) -> ApiError {
+    move |e| {
+        tracing::error!("{}: {}", target, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Database error" })),
+        )
+    }
+}
+
+pub(crate) fn bad_request(message: impl Into<String>) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": message.into() })),
+    )
+}
+
+pub(crate) fn parse_session_id(id: &str) -> Result<uuid::Uuid, ApiError> {
+    id.parse().map_err(|_| bad_request("Invalid session id"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_carry_status_and_message() {
+        let (status, Json(body)) = db_error("test")(sqlx::Error::RowNotFound);
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(body["error"], "Database error");
+
+        let (status, Json(body)) = parse_session_id("not-a-uuid").unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "Invalid session id");
+        assert!(parse_session_id("8f2c1a4e-6b0d-4c1e-9a57-3d2f1e0b9c88").is_ok());
+    }
+}
@@ -0,0 +1,604 @@
+//! Structured application log — JSONL entries for the dashboard.
+//!
+//! Components record entries with `record(level, source, message, fields)`;
+//! each becomes one JSON line in `<data dir>/logs/app.jsonl` (the data
+//! directory is absolute — see `crate::metrics_snapshot` — so the log does
+//! not depend on the process's working directory). The file rotates into
+//! `app.1.jsonl` … when it reaches `CH_APP_LOG_MAX_MB` (default 10) and, with
+//! `CH_APP_LOG_DAILY` (default `true`), on the first write of a new (UTC)
+//! day. At most `CH_APP_LOG_FILES` files are kept (default 7), and rotated
+//! files older than `CH_APP_LOG_RETENTION_DAYS` (default 14, `0` = no age
+//! limit) are deleted.
+//!
+//! Entries carry the tab (chat session) and correlation id they belong to
+//! when known: `record` takes them from the `session_id` / `tab_id` and
+//! `correlation_id` / `execution_id` / `request_id` fields, falling back to
+//! the correlation id in scope (see `crate::correlation`). They also carry
+//! the project active when they were written (see `crate::projects`).
+//!
+//! Messages and fields are redacted before they are written (see
+//! `crate::redaction`).
+//!
+//! New entries are also broadcast to `subscribe()` (the dashboard's `/ws`).
+//!
+//! Sources: `audit` (audit log entries), `background` (background prompt
+//! lifecycle), `chat` (failed WebSocket executions), `ollama` (service
+//! watchdog, batches), and whatever clients (launcher, GUI windows) post.
+//!
+//! - `GET  /api/logs?level=&source=&since=&tab_id=&correlation_id=&project=&q=&limit=&offset=`
+//!   — newest first; `level` is a minimum (`warn` = warn + error), `since` an
+//!   RFC 3339 timestamp or a relative age (`15m`, `2h`, `7d`), `q` a
+//!   case-insensitive substring of the message
+//! - `POST /api/logs` — record client entries: one
+//!   `{ level, source, message, fields?, tab_id?, correlation_id? }` object
+//!   or an array of up to 100
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, OnceLock};
+
+use axum::Json;
+use axum::extract::Query;
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast;
+
+use crate::api_error::bad_request;
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "app.jsonl";
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1_000;
+const MAX_POSTED_ENTRIES: usize = 100;
+const MAX_MESSAGE_CHARS: usize = 8_192;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "debug" | "trace" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" | "warning" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub ts: DateTime<Utc>,
+    pub level: Level,
+    pub source: String,
+    pub message: String,
+    /// Chat session (tab) the entry belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_id: Option<String>,
+    /// Ties together the entries of one request, prompt or job.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Project active when the entry was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub fields: Value,
+}
+
+/// First string (or number) among `keys` in `fields`.
+fn field_id(fields: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| match fields.get(*key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    })
+}
+
+struct LogConfig {
+    dir: PathBuf,
+    max_bytes: u64,
+    files: usize,
+    daily: bool,
+    /// Rotated files older than this are deleted.
+    retention: Option<Duration>,
+}
+
+static CONFIG: OnceLock<LogConfig> = OnceLock::new();
+static WRITER: Mutex<Option<File>> = Mutex::new(None);
+static ENTRIES: LazyLock<broadcast::Sender<LogRecord>> =
+    LazyLock::new(|| broadcast::channel(256).0);
+
+/// Entries recorded from now on.
+pub fn subscribe() -> broadcast::Receiver<LogRecord> {
+    ENTRIES.subscribe()
+}
+
+fn config() -> &'static LogConfig {
+    CONFIG.get_or_init(|| {
+        let env_num = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        let retention_days = env_num("CH_APP_LOG_RETENTION_DAYS").unwrap_or(14);
+        LogConfig {
+            dir: crate::metrics_snapshot::data_dir().join(LOG_DIR),
+            max_bytes: env_num("CH_APP_LOG_MAX_MB").unwrap_or(10).max(1) * 1024 * 1024,
+            files: env_num("CH_APP_LOG_FILES").unwrap_or(7).clamp(1, 60) as usize,
+            daily: std::env::var("CH_APP_LOG_DAILY")
+                .map(|v| v.trim() != "false" && v.trim() != "0")
+                .unwrap_or(true),
+            retention: (retention_days > 0).then(|| Duration::days(retention_days as i64)),
+        }
+    })
+}
+
+/// `app.jsonl`, `app.1.jsonl`, … — newest first.
+fn log_files(dir: &Path, files: usize) -> Vec<PathBuf> {
+    (0..files)
+        .map(|n| match n {
+            0 => dir.join(LOG_FILE),
+            n => dir.join(format!("app.{}.jsonl", n)),
+        })
+        .collect()
+}
+
+/// Shift `app.jsonl` → `app.1.jsonl` → … dropping the oldest, then delete
+/// rotated files last written before `cutoff`.
+fn rotate(dir: &Path, files: usize, cutoff: Option<DateTime<Utc>>) -> std::io::Result<()> {
+    let paths = log_files(dir, files);
+    if let Some(oldest) = paths.last()
+        && oldest.exists()
+    {
+        std::fs::remove_file(oldest)?;
+    }
+    for pair in paths.windows(2).rev() {
+        if pair[0].exists() {
+            std::fs::rename(&pair[0], &pair[1])?;
+        }
+    }
+    let Some(cutoff) = cutoff else {
+        return Ok(());
+    };
+    for path in paths.iter().skip(1) {
+        if modified_at(path).is_some_and(|at| at < cutoff) {
+            std::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+fn modified_at(path: &Path) -> Option<DateTime<Utc>> {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(DateTime::<Utc>::from)
+}
+
+/// Whether the current file (`size` bytes, last written at `modified`)
+/// must be rotated before writing at `now`.
+fn needs_rotation(
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    max_bytes: u64,
+    daily: bool,
+) -> bool {
+    size >= max_bytes
+        || (daily && size > 0 && modified.is_some_and(|at| at.date_naive() < now.date_naive()))
+}
+
+fn append(line: &str) -> std::io::Result<()> {
+    let cfg = config();
+    let mut writer = WRITER.lock().unwrap_or_else(|e| e.into_inner());
+    let current = log_files(&cfg.dir, 1).remove(0);
+    let now = Utc::now();
+    let size = std::fs::metadata(&current).map(|m| m.len()).unwrap_or(0);
+    if needs_rotation(size, modified_at(&current), now, cfg.max_bytes, cfg.daily) && cfg.files > 1 {
+        *writer = None;
+        rotate(&cfg.dir, cfg.files, cfg.retention.map(|r| now - r))?;
+    }
+    if writer.is_none() {
+        std::fs::create_dir_all(&cfg.dir)?;
+        *writer = Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&current)?,
+        );
+    }
+    let file = writer.as_mut().expect("log file opened above");
+    writeln!(file, "{}", line)
+}
+
+/// Record one entry. Failures are reported via `tracing` and otherwise ignored.
+pub fn record(level: Level, source: &str, message: &str, fields: Value) {
+    write(LogRecord {
+        ts: Utc::now(),
+        level,
+        source: source.to_string(),
+        message: message.to_string(),
+        tab_id: field_id(&fields, &["tab_id", "session_id"]),
+        correlation_id: field_id(&fields, &["correlation_id", "execution_id", "request_id"])
+            .or_else(crate::correlation::current),
+        project: crate::projects::active_name(),
+        fields,
+    });
+}
+
+fn write(mut entry: LogRecord) {
+    crate::redaction::redact_string(&mut entry.message);
+    crate::redaction::redact_value(&mut entry.fields);
+    let result = serde_json::to_string(&entry)
+        .map_err(std::io::Error::other)
+        .and_then(|line| append(&line));
+    if let Err(e) = result {
+        tracing::warn!("app_log: cannot write entry: {}", e);
+    }
+    // No subscribers is fine.
+    let _ = ENTRIES.send(entry);
+}
+
+/// `since` as an RFC 3339 timestamp or an age (`30s`, `15m`, `2h`, `7d`).
+fn parse_since(raw: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(raw) {
+        return Some(ts.with_timezone(&Utc));
+    }
+    let unit = raw.chars().last()?;
+    let amount: i64 = raw[..raw.len() - unit.len_utf8()].parse().ok()?;
+    let age = match unit {
+        's' => Duration::seconds(amount),
+        'm' => Duration::minutes(amount),
+        'h' => Duration::hours(amount),
+        'd' => Duration::days(amount),
+        _ => return None,
+    };
+    Some(now - age)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub min_level: Option<Level>,
+    pub source: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub tab_id: Option<String>,
+    pub correlation_id: Option<String>,
+    pub project: Option<String>,
+    /// Lowercased message substring.
+    pub text: Option<String>,
+}
+
+impl LogFilter {
+    fn matches(&self, entry: &LogRecord) -> bool {
+        let same = |wanted: &Option<String>, got: &Option<String>| {
+            wanted.is_none() || wanted.as_deref() == got.as_deref()
+        };
+        self.min_level.is_none_or(|min| entry.level >= min)
+            && self
+                .source
+                .as_deref()
+                .is_none_or(|s| entry.source.eq_ignore_ascii_case(s))
+            && self.since.is_none_or(|since| entry.ts >= since)
+            && same(&self.tab_id, &entry.tab_id)
+            && same(&self.correlation_id, &entry.correlation_id)
+            && same(&self.project, &entry.project)
+            && self
+                .text
+                .as_deref()
+                .is_none_or(|t| entry.message.to_lowercase().contains(t))
+    }
+}
+
+/// Matching entries from `files` (newest file first), newest entry first.
+/// Unparseable lines are skipped.
+fn read_entries(files: &[PathBuf], filter: &LogFilter) -> Vec<LogRecord> {
+    let mut entries = Vec::new();
+    for path in files {
+        let Ok(file) = File::open(path) else {
+            continue;
+        };
+        let mut chunk: Vec<LogRecord> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<LogRecord>(&line).ok())
+            .filter(|entry| filter.matches(entry))
+            .collect();
+        chunk.reverse();
+        entries.extend(chunk);
+    }
+    entries
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct LogQuery {
+    #[serde(default)]
+    pub level: Option<String>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub since: Option<String>,
+    #[serde(default)]
+    pub tab_id: Option<String>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub q: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+/// `GET /api/logs`
+pub async fn list_logs(
+    Query(query): Query<LogQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let min_level = match query.level.as_deref().filter(|l| !l.trim().is_empty()) {
+        Some(raw) => Some(
+            Level::parse(raw)
+                .ok_or_else(|| bad_request("level must be debug, info, warn or error"))?,
+        ),
+        None => None,
+    };
+    let since = match query.since.as_deref().filter(|s| !s.trim().is_empty()) {
+        Some(raw) => Some(
+            parse_since(raw, Utc::now())
+                .ok_or_else(|| bad_request("since must be RFC 3339 or an age like 15m, 2h, 7d"))?,
+        ),
+        None => None,
+    };
+    let non_empty = |v: Option<String>| v.filter(|s| !s.trim().is_empty());
+    let filter = LogFilter {
+        min_level,
+        source: non_empty(query.source),
+        since,
+        tab_id: non_empty(query.tab_id),
+        correlation_id: non_empty(query.correlation_id),
+        project: non_empty(query.project),
+        text: non_empty(query.q).map(|q| q.to_lowercase()),
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+
+    let cfg = config();
+    let files = log_files(&cfg.dir, cfg.files);
+    let entries = tokio::task::spawn_blocking(move || read_entries(&files, &filter))
+        .await
+        .map_err(|e| {
+            tracing::error!("app_log: reader failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to read logs" })),
+            )
+        })?;
+    let total = entries.len();
+    let page: Vec<LogRecord> = entries.into_iter().skip(offset).take(limit).collect();
+    let next_offset = (offset + page.len() < total).then_some(offset + page.len());
+    Ok(Json(json!({
+        "entries": page,
+        "total": total,
+        "limit": limit,
+        "offset": offset,
+        "next_offset": next_offset,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PostedEntry {
+    pub level: String,
+    pub source: String,
+    pub message: String,
+    #[serde(default)]
+    pub fields: Value,
+    #[serde(default)]
+    pub tab_id: Option<String>,
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum PostedEntries {
+    One(PostedEntry),
+    Many(Vec<PostedEntry>),
+}
+
+fn valid_source(source: &str) -> bool {
+    !source.is_empty()
+        && source.len() <= 64
+        && source
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// `POST /api/logs`
+pub async fn post_logs(
+    Json(body): Json<PostedEntries>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let posted = match body {
+        PostedEntries::One(entry) => vec![entry],
+        PostedEntries::Many(entries) => entries,
+    };
+    if posted.len() > MAX_POSTED_ENTRIES {
+        return Err(bad_request(format!(
+            "at most {} entries per request",
+            MAX_POSTED_ENTRIES
+        )));
+    }
+    let mut entries = Vec::with_capacity(posted.len());
+    for (i, p) in posted.into_iter().enumerate() {
+        let level = Level::parse(&p.level)
+            .ok_or_else(|| bad_request(format!("entry {}: unknown level '{}'", i, p.level)))?;
+        if !valid_source(&p.source) {
+            return Err(bad_request(format!(
+                "entry {}: source must be 1-64 characters of letters, digits, '-', '_' or '.'",
+                i
+            )));
+        }
+        entries.push(LogRecord {
+            ts: Utc::now(),
+            level,
+            source: p.source,
+            message: p.message.chars().take(MAX_MESSAGE_CHARS).collect(),
+            tab_id: p
+                .tab_id
+                .or_else(|| field_id(&p.fields, &["tab_id", "session_id"])),
+            correlation_id: p.correlation_id,
+            project: crate::projects::active_name(),
+            fields: p.fields,
+        });
+    }
+    let recorded = entries.len();
+    tokio::task::spawn_blocking(move || entries.into_iter().for_each(write))
+        .await
+        .map_err(|e| {
+            tracing::error!("app_log: writer failed: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Failed to write logs" })),
+            )
+        })?;
+    Ok(Json(json!({ "recorded": recorded })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(level: Level, source: &str, minutes_ago: i64) -> LogRecord {
+        LogRecord {
+            ts: Utc::now() - Duration::minutes(minutes_ago),
+            level,
+            source: source.to_string(),
+            message: "m".to_string(),
+            tab_id: None,
+            correlation_id: None,
+            project: None,
+            fields: Value::Null,
+        }
+    }
+
+    #[test]
+    fn filter_by_level_source_and_age() {
+        let filter = LogFilter {
+            min_level: Some(Level::Warn),
+            source: Some("ollama".to_string()),
+            since: Some(Utc::now() - Duration::hours(1)),
+            ..LogFilter::default()
+        };
+        assert!(filter.matches(&entry(Level::Error, "ollama", 5)));
+        assert!(filter.matches(&entry(Level::Warn, "Ollama", 5)));
+        assert!(!filter.matches(&entry(Level::Info, "ollama", 5)));
+        assert!(!filter.matches(&entry(Level::Error, "audit", 5)));
+        assert!(!filter.matches(&entry(Level::Error, "ollama", 120)));
+        assert!(LogFilter::default().matches(&entry(Level::Debug, "x", 9999)));
+    }
+
+    #[test]
+    fn entries_carry_tab_and_correlation_ids() {
+        let fields = json!({ "session_id": "tab-1", "execution_id": 42 });
+        assert_eq!(
+            field_id(&fields, &["tab_id", "session_id"]).as_deref(),
+            Some("tab-1")
+        );
+        assert_eq!(
+            field_id(&fields, &["correlation_id", "execution_id"]).as_deref(),
+            Some("42")
+        );
+        assert_eq!(field_id(&Value::Null, &["session_id"]), None);
+
+        let mut tagged = entry(Level::Info, "gui", 1);
+        tagged.tab_id = Some("tab-1".to_string());
+        tagged.message = "Model download FAILED".to_string();
+        let filter = LogFilter {
+            tab_id: Some("tab-1".to_string()),
+            text: Some("failed".to_string()),
+            ..LogFilter::default()
+        };
+        assert!(filter.matches(&tagged));
+        assert!(!filter.matches(&entry(Level::Info, "gui", 1)));
+
+        tagged.project = Some("hydra".to_string());
+        let by_project = LogFilter {
+            project: Some("hydra".to_string()),
+            ..LogFilter::default()
+        };
+        assert!(by_project.matches(&tagged));
+        assert!(!by_project.matches(&entry(Level::Info, "gui", 1)));
+    }
+
+    #[test]
+    fn rotation_by_size_and_day() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T08:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let today = Some(now - Duration::hours(1));
+        let yesterday = Some(now - Duration::hours(9));
+        assert!(!needs_rotation(10, today, now, 100, true));
+        assert!(needs_rotation(100, today, now, 100, true));
+        assert!(needs_rotation(10, yesterday, now, 100, true));
+        assert!(!needs_rotation(10, yesterday, now, 100, false));
+        assert!(!needs_rotation(0, yesterday, now, 100, true));
+    }
+
+    #[test]
+    fn since_accepts_timestamps_and_ages() {
+        let now = Utc::now();
+        assert_eq!(parse_since("15m", now), Some(now - Duration::minutes(15)));
+        assert_eq!(parse_since("7d", now), Some(now - Duration::days(7)));
+        let ts = parse_since("2026-10-01T12:00:00Z", now).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2026-10-01T12:00:00+00:00");
+        assert_eq!(parse_since("yesterday", now), None);
+        assert_eq!(parse_since("5w", now), None);
+    }
+
+    #[test]
+    fn entries_are_read_newest_first_across_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("ch-app-log-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = log_files(&dir, 2);
+        let write = |path: &Path, records: &[LogRecord]| {
+            let lines: Vec<String> = records
+                .iter()
+                .map(|r| serde_json::to_string(r).unwrap())
+                .collect();
+            std::fs::write(path, lines.join("\n") + "\nnot json\n").unwrap();
+        };
+        let mut old = entry(Level::Info, "audit", 30);
+        old.message = "old".into();
+        let mut mid = entry(Level::Info, "audit", 20);
+        mid.message = "mid".into();
+        let mut new = entry(Level::Warn, "background", 10);
+        new.message = "new".into();
+        write(&files[1], &[old, mid]);
+        write(&files[0], &[new]);
+
+        let all = read_entries(&files, &LogFilter::default());
+        let messages: Vec<&str> = all.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages, ["new", "mid", "old"]);
+
+        rotate(&dir, 2, None).unwrap();
+        assert!(!files[0].exists());
+        let kept = read_entries(&files, &LogFilter::default());
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].message, "new");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,527 @@
+//! Approval workflow for dangerous operations outside YOLO mode.
+//!
+//! Two things wait for a human decision:
+//! - **tool calls** — in `ask` mode (see `permissions.rs`) every mutating
+//!   tool the model calls on the WebSocket path is announced to the client
+//!   (`approval_required`) and runs only once approved. Denied or expired
+//!   calls return an error result to the model, which carries on without them.
+//! - **background prompts** — prompts classified as a system operation
+//!   (`system_operation`: destructive shell commands, privilege escalation,
+//!   package installs, force pushes, dropping databases, deployments, ...)
+//!   are queued as `awaiting_approval` unless the effective mode is `yolo`.
+//!   Approving moves them to `queued`, denying cancels them.
+//!
+//! Tool approvals expire after `CH_APPROVAL_TIMEOUT_SECS` (default 600) or
+//! when the WebSocket execution is cancelled; pending ones left by a
+//! previous run are expired on startup. Prompt approvals wait until decided
+//! or until the prompt is cancelled.
+//!
+//! Decisions come in through `POST /api/approvals/{id}` or, on the
+//! WebSocket that announced the tool call, an `approval_decision` message.
+//!
+//! - `GET  /api/approvals`        — recent approvals, newest first (`?status=`)
+//! - `GET  /api/approvals/events` — SSE: `approval-requested`, `approval-decided`
+//! - `POST /api/approvals/{id}`   — `{ decision: approve | deny, reason? }`
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::{DateTime, Utc};
+use futures_util::stream::Stream;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::{broadcast, oneshot};
+use uuid::Uuid;
+
+use crate::api_error::db_error;
+use crate::app_log::Level;
+use crate::permissions::PermissionMode;
+use crate::state::AppState;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 600;
+const MAX_SUBJECT_CHARS: usize = 200;
+const MAX_INPUT_CHARS: usize = 2_000;
+const LIST_LIMIT: i64 = 100;
+
+/// `(operation, pattern)`, matched case-insensitively; the first match wins.
+const SYSTEM_OPERATIONS: &[(&str, &str)] = &[
+    (
+        "destructive_command",
+        r"\brm\s+-[a-z]*[rf]|\brmdir\b|\bdel\s+/[sq]|\bformat\s+[a-z]:|\bmkfs\b|\bdd\s+if=|\b(?:delete|remove|wipe|erase|purge)\s+(?:all|every|the\s+entire|the\s+whole)\b",
+    ),
+    (
+        "privilege_escalation",
+        r"\bsudo\b|\brunas\b|\bchmod\s+(?:-R\s+)?[0-7]{3,4}\b|\bchown\b",
+    ),
+    (
+        "process_control",
+        r"\b(?:kill|pkill|killall|taskkill)\s+-?\w|\b(?:shutdown|reboot)\b|\bsystemctl\s+(?:stop|restart|disable)\b",
+    ),
+    (
+        "package_install",
+        r"\b(?:apt(?:-get)?|brew|choco|winget|yum|dnf|pacman)\s+(?:install|remove|uninstall)\b|\bnpm\s+(?:install|i)\s+(?:-g|--global)\b|\bpip3?\s+install\b|\bcargo\s+install\b",
+    ),
+    (
+        "history_rewrite",
+        r"\bgit\s+push\b|\bgit\s+reset\s+--hard\b|\bgit\s+clean\s+-[a-z]*f|\bgit\s+branch\s+-D\b",
+    ),
+    (
+        "database_drop",
+        r"\bdrop\s+(?:table|database|schema)\b|\btruncate\s+table\b",
+    ),
+    (
+        "deployment",
+        r"\b(?:deploy|publish|release)\s+(?:it\s+)?(?:to\s+)?prod(?:uction)?\b|\b(?:npm|cargo)\s+publish\b|\b(?:vercel|fly)\s+deploy\b|\bterraform\s+(?:apply|destroy)\b|\bkubectl\s+(?:apply|delete)\b",
+    ),
+    (
+        "system_config",
+        r"\bregedit\b|\breg\s+(?:add|delete)\b|\bcrontab\s+-[er]\b|\b(?:iptables|ufw|netsh)\b",
+    ),
+];
+
+static SYSTEM_OPERATION_RES: LazyLock<Vec<(&'static str, Regex)>> = LazyLock::new(|| {
+    SYSTEM_OPERATIONS
+        .iter()
+        .map(|(name, pattern)| {
+            let regex = Regex::new(&format!("(?i){}", pattern)).expect("valid operation pattern");
+            (*name, regex)
+        })
+        .collect()
+});
+
+/// The system operation `prompt` asks for, if any.
+pub fn system_operation(prompt: &str) -> Option<&'static str> {
+    SYSTEM_OPERATION_RES
+        .iter()
+        .find(|(_, regex)| regex.is_match(prompt))
+        .map(|(name, _)| *name)
+}
+
+fn timeout() -> Duration {
+    let secs = std::env::var("CH_APPROVAL_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&s| s > 0)
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct Approval {
+    pub id: Uuid,
+    pub kind: String,
+    pub subject: String,
+    pub operation: String,
+    pub detail: Value,
+    pub session_id: Option<Uuid>,
+    pub prompt_id: Option<i64>,
+    pub status: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub decided_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Approved,
+    Denied(Option<String>),
+    Expired,
+}
+
+impl Decision {
+    /// Tool result reported instead of running `tool`, if it may not run.
+    pub fn refusal(&self, tool: &str) -> Option<String> {
+        match self {
+            Decision::Approved => None,
+            Decision::Denied(Some(reason)) => Some(format!(
+                "Tool '{}' was denied by the user: {}",
+                tool, reason
+            )),
+            Decision::Denied(None) => Some(format!("Tool '{}' was denied by the user", tool)),
+            Decision::Expired => Some(format!("Tool '{}' was not approved in time", tool)),
+        }
+    }
+}
+
+/// Tool executions blocked on a decision, by approval id.
+static WAITERS: LazyLock<Mutex<HashMap<Uuid, oneshot::Sender<Decision>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// `approval-requested` / `approval-decided` events for `/api/approvals/events`.
+static EVENTS: LazyLock<broadcast::Sender<Value>> = LazyLock::new(|| broadcast::channel(64).0);
+
+fn emit(event: &str, approval: &Approval) {
+    let data = json!(approval);
+    crate::app_log::record(Level::Info, "approvals", event, data.clone());
+    // No subscribers is fine — events are best effort.
+    let _ = EVENTS.send(json!({ "event": event, "data": data }));
+}
+
+fn excerpt(text: &str, max: usize) -> String {
+    let mut out: String = text.chars().take(max).collect();
+    if out.len() < text.len() {
+        out.push('…');
+    }
+    out
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Tool calls
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Record a pending approval for `tool` and register its waiter.
+pub async fn request_tool(
+    db: &sqlx::PgPool,
+    session_id: Option<Uuid>,
+    tool: &str,
+    input: &Value,
+) -> Result<(Approval, oneshot::Receiver<Decision>), sqlx::Error> {
+    let input = excerpt(&input.to_string(), MAX_INPUT_CHARS);
+    let approval = sqlx::query_as::<_, Approval>(
+        "INSERT INTO ch_approvals (kind, subject, operation, detail, session_id) \
+         VALUES ('tool', $1, 'tool_call', $2, $3) RETURNING *",
+    )
+    .bind(tool)
+    .bind(json!({ "input": input }))
+    .bind(session_id)
+    .fetch_one(db)
+    .await?;
+    let rx = register(approval.id);
+    emit("approval-requested", &approval);
+    Ok((approval, rx))
+}
+
+/// Register a waiter for the decision on approval `id`.
+fn register(id: Uuid) -> oneshot::Receiver<Decision> {
+    let (tx, rx) = oneshot::channel();
+    WAITERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id, tx);
+    rx
+}
+
+/// Hand `decision` to the execution waiting on approval `id`, if any.
+fn resolve(id: Uuid, decision: Decision) -> bool {
+    let waiter = WAITERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    waiter.is_some_and(|waiter| waiter.send(decision).is_ok())
+}
+
+/// Wait for the decision on a tool approval; expires it after the timeout.
+pub async fn await_decision(
+    db: &sqlx::PgPool,
+    id: Uuid,
+    rx: oneshot::Receiver<Decision>,
+) -> Decision {
+    match tokio::time::timeout(timeout(), rx).await {
+        Ok(Ok(decision)) => decision,
+        _ => expire(db, id).await,
+    }
+}
+
+/// Give up on a pending tool approval (timeout, cancelled execution).
+pub async fn expire(db: &sqlx::PgPool, id: Uuid) -> Decision {
+    WAITERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&id);
+    let expired = sqlx::query_as::<_, Approval>(
+        "UPDATE ch_approvals SET status = 'expired', decided_at = NOW() \
+         WHERE id = $1 AND status = 'pending' RETURNING *",
+    )
+    .bind(id)
+    .fetch_optional(db)
+    .await;
+    match expired {
+        Ok(Some(approval)) => emit("approval-decided", &approval),
+        Ok(None) => {}
+        Err(e) => tracing::error!("approvals: failed to expire {}: {}", id, e),
+    }
+    Decision::Expired
+}
+
+/// Expire tool approvals left pending by a previous run — nothing waits for them.
+pub async fn expire_orphaned(db: &sqlx::PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE ch_approvals SET status = 'expired', decided_at = NOW() \
+         WHERE kind = 'tool' AND status = 'pending'",
+    )
+    .execute(db)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Background prompts
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Hold a just-inserted background prompt for approval when it asks for a
+/// system operation and `mode` is not `yolo`. Call inside the inserting
+/// transaction and `announce` the returned approval after committing.
+pub async fn hold_prompt(
+    conn: &mut sqlx::PgConnection,
+    mode: PermissionMode,
+    prompt_id: i64,
+    prompt: &str,
+) -> Result<Option<Approval>, sqlx::Error> {
+    if mode == PermissionMode::Yolo {
+        return Ok(None);
+    }
+    let Some(operation) = system_operation(prompt) else {
+        return Ok(None);
+    };
+    sqlx::query("UPDATE ch_background_prompts SET status = 'awaiting_approval' WHERE id = $1")
+        .bind(prompt_id)
+        .execute(&mut *conn)
+        .await?;
+    let approval = sqlx::query_as::<_, Approval>(
+        "INSERT INTO ch_approvals (kind, subject, operation, detail, prompt_id) \
+         VALUES ('prompt', $1, $2, $3, $4) RETURNING *",
+    )
+    .bind(excerpt(prompt, MAX_SUBJECT_CHARS))
+    .bind(operation)
+    .bind(json!({ "mode": mode }))
+    .bind(prompt_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(Some(approval))
+}
+
+/// Publish a prompt approval created by `hold_prompt`.
+pub fn announce(approval: &Approval) {
+    tracing::info!(
+        "approvals: prompt #{} held ({})",
+        approval.prompt_id.unwrap_or_default(),
+        approval.operation
+    );
+    emit("approval-requested", approval);
+}
+
+/// Expire the pending approval of a cancelled prompt.
+pub async fn withdraw_prompt(db: &sqlx::PgPool, prompt_id: i64) {
+    let withdrawn = sqlx::query_as::<_, Approval>(
+        "UPDATE ch_approvals SET status = 'expired', decided_at = NOW() \
+         WHERE prompt_id = $1 AND status = 'pending' RETURNING *",
+    )
+    .bind(prompt_id)
+    .fetch_optional(db)
+    .await;
+    match withdrawn {
+        Ok(Some(approval)) => emit("approval-decided", &approval),
+        Ok(None) => {}
+        Err(e) => tracing::error!("approvals: failed to withdraw prompt #{}: {}", prompt_id, e),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct ApprovalQuery {
+    pub status: Option<String>,
+}
+
+/// `GET /api/approvals`
+pub async fn list_approvals(
+    State(state): State<AppState>,
+    Query(q): Query<ApprovalQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let approvals = sqlx::query_as::<_, Approval>(
+        "SELECT * FROM ch_approvals WHERE ($1::TEXT IS NULL OR status = $1) \
+         ORDER BY created_at DESC LIMIT $2",
+    )
+    .bind(&q.status)
+    .bind(LIST_LIMIT)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("approvals"))?;
+    Ok(Json(json!({ "approvals": approvals })))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Approve,
+    Deny,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RespondRequest {
+    pub decision: Verdict,
+    pub reason: Option<String>,
+}
+
+/// Decide the pending approval `id`; `None` when there is no pending
+/// approval with this id.
+pub async fn decide(
+    db: &sqlx::PgPool,
+    id: Uuid,
+    verdict: Verdict,
+    reason: Option<&str>,
+) -> Result<Option<Approval>, sqlx::Error> {
+    let reason = reason
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(|r| excerpt(r, MAX_SUBJECT_CHARS));
+    let status = match verdict {
+        Verdict::Approve => "approved",
+        Verdict::Deny => "denied",
+    };
+
+    let mut tx = db.begin().await?;
+    let Some(approval) = sqlx::query_as::<_, Approval>(
+        "UPDATE ch_approvals SET status = $2, reason = $3, decided_at = NOW() \
+         WHERE id = $1 AND status = 'pending' RETURNING *",
+    )
+    .bind(id)
+    .bind(status)
+    .bind(&reason)
+    .fetch_optional(&mut *tx)
+    .await?
+    else {
+        return Ok(None);
+    };
+    if let Some(prompt_id) = approval.prompt_id {
+        let error = reason
+            .as_deref()
+            .map_or_else(|| "Denied".to_string(), |r| format!("Denied: {}", r));
+        sqlx::query(
+            "UPDATE ch_background_prompts SET \
+                 status = CASE WHEN $2 THEN 'queued' ELSE 'cancelled' END, \
+                 error = CASE WHEN $2 THEN error ELSE $3 END, \
+                 finished_at = CASE WHEN $2 THEN finished_at ELSE NOW() END \
+             WHERE id = $1 AND status = 'awaiting_approval'",
+        )
+        .bind(prompt_id)
+        .bind(status == "approved")
+        .bind(error)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    let decision = match verdict {
+        Verdict::Approve => Decision::Approved,
+        Verdict::Deny => Decision::Denied(reason),
+    };
+    if approval.prompt_id.is_some() && decision == Decision::Approved {
+        crate::queue::wake();
+    }
+    resolve(id, decision);
+    tracing::info!("approvals: {} {} ({})", approval.subject, status, id);
+    emit("approval-decided", &approval);
+    Ok(Some(approval))
+}
+
+/// `POST /api/approvals/{id}` — decide a pending approval.
+pub async fn respond_to_approval(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<RespondRequest>,
+) -> Result<Json<Approval>, (StatusCode, Json<Value>)> {
+    let id: Uuid = id.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid approval id" })),
+        )
+    })?;
+    let approval = decide(&state.db, id, req.decision, req.reason.as_deref())
+        .await
+        .map_err(db_error("approvals"))?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No pending approval with this id" })),
+        ))?;
+    Ok(Json(approval))
+}
+
+/// `GET /api/approvals/events` — SSE stream of approval requests and decisions.
+pub async fn events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut rx = EVENTS.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => {
+                    let name = msg["event"].as_str().unwrap_or("message").to_string();
+                    if let Ok(event) = Event::default().event(name).json_data(&msg["data"]) {
+                        yield Ok(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn system_operations_are_classified() {
+        assert_eq!(
+            system_operation("Run rm -rf ./build and rebuild"),
+            Some("destructive_command")
+        );
+        assert_eq!(
+            system_operation("use sudo to fix the permissions"),
+            Some("privilege_escalation")
+        );
+        assert_eq!(
+            system_operation("Commit the fix and git push to origin"),
+            Some("history_rewrite")
+        );
+        assert_eq!(system_operation("DROP TABLE users;"), Some("database_drop"));
+        assert_eq!(
+            system_operation("then deploy to production"),
+            Some("deployment")
+        );
+        assert_eq!(
+            system_operation("pip install requests"),
+            Some("package_install")
+        );
+        assert_eq!(system_operation("Summarize the README"), None);
+        assert_eq!(system_operation("Explain how the git log works"), None);
+        assert_eq!(system_operation("Remove the unused import"), None);
+    }
+
+    #[tokio::test]
+    async fn decision_reaches_the_waiting_tool_call() {
+        let id = Uuid::new_v4();
+        let rx = register(id);
+        assert!(resolve(id, Decision::Denied(Some("not now".to_string()))));
+        assert_eq!(
+            rx.await.ok(),
+            Some(Decision::Denied(Some("not now".to_string())))
+        );
+        // Decided once — nothing waits any more.
+        assert!(!resolve(id, Decision::Approved));
+    }
+
+    #[test]
+    fn decisions_map_to_tool_refusals() {
+        assert_eq!(Decision::Approved.refusal("write_file"), None);
+        assert_eq!(
+            Decision::Denied(Some("wrong file".to_string())).refusal("write_file"),
+            Some("Tool 'write_file' was denied by the user: wrong file".to_string())
+        );
+        assert!(
+            Decision::Expired
+                .refusal("delete_file")
+                .is_some_and(|r| r.contains("not approved in time"))
+        );
+    }
+}
@@ -3,12 +3,19 @@
 
 pub use jaskier_core::audit::extract_ip;
 
-/// Insert an audit log entry into `ch_audit_log`.
+/// Insert an audit log entry into `ch_audit_log` (mirrored to the structured
+/// application log, source `audit`).
 pub async fn log_audit(
     pool: &sqlx::PgPool,
     action: &str,
     details: serde_json::Value,
     ip: Option<&str>,
 ) {
+    crate::app_log::record(
+        crate::app_log::Level::Info,
+        "audit",
+        action,
+        serde_json::json!({ "details": details, "ip": ip }),
+    );
     jaskier_core::audit::log_audit(pool, "ch_audit_log", action, details, ip).await;
 }
@@ -0,0 +1,665 @@
+//! Audit trail — an append-only, hash-chained record of the commands the
+//! backend ran and the file changes it made or saw, for compliance reviews.
+//!
+//! Entries:
+//! - `command` — binary, arguments, working directory, exit code (`null`
+//!   when killed, timed out or not started) and duration. Recorded for
+//!   persistent Claude CLI processes (when they end), the gateway `shell`
+//!   tool and hooks. Diagnostics the backend polls on its own (GPU, Ollama,
+//!   speech) are not recorded.
+//! - `file_change` — path and change: files written by WebSocket tools and
+//!   external changes detected by the file watcher (see `file_watcher.rs`).
+//!
+//! Each entry is attributed to the sessions (tabs) and background prompts it
+//! belongs to, when known. Secrets in the detail are redacted first (see
+//! `redaction.rs`).
+//!
+//! Entries are queued in memory (bounded) and appended by one writer task.
+//! An entry that still fails after retries, or finds the queue full, is
+//! spilled to `audit-trail-spill.jsonl` in the data directory and appended
+//! on a later pass (its `recorded_at` keeps the original time). Each row
+//! stores the previous row's hash and `hash = sha256(prev_hash + "\n" +
+//! canonical JSON of the entry)`, starting from `GENESIS`; the table rejects
+//! updates, deletes and truncation. Verifying replays the chain and reports
+//! the first row whose link or content does not match.
+//!
+//! Filters: `kind`, `source`, `session_id`, `prompt_id`, `since`, `until`
+//! (RFC 3339), `limit`.
+//!
+//! - `GET /api/audit-trail`        — entries matching the filter, newest first
+//! - `GET /api/audit-trail/export` — oldest first with hashes, `format=jsonl|csv`
+//! - `GET /api/audit-trail/verify` — `{ valid, checked, broken_at?, problem? }`
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+
+use crate::api_error::db_error;
+use crate::handlers::routing_dataset::csv_field;
+use crate::state::AppState;
+
+/// `prev_hash` of the first entry.
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1_000;
+const MAX_EXPORT_ROWS: i64 = 100_000;
+const VERIFY_BATCH: i64 = 5_000;
+/// Entries queued for the writer; more are spilled to disk.
+const QUEUE_CAPACITY: usize = 10_000;
+/// Tries per entry before the writer spills it.
+const APPEND_ATTEMPTS: u32 = 3;
+/// How often spilled entries are appended again.
+const SPILL_RETRY: Duration = Duration::from_secs(60);
+/// Spill file in the data directory (`CH_DATA_DIR`), one JSON entry per line.
+const SPILL_FILE: &str = "audit-trail-spill.jsonl";
+
+/// Sessions (tabs) and background prompts an entry belongs to.
+#[derive(Debug, Clone, Default)]
+pub struct Attribution {
+    pub session_ids: Vec<String>,
+    pub prompt_ids: Vec<i64>,
+}
+
+impl Attribution {
+    pub fn session(session_id: Option<uuid::Uuid>) -> Self {
+        Self {
+            session_ids: session_id
+                .map(|id| vec![id.to_string()])
+                .unwrap_or_default(),
+            prompt_ids: Vec::new(),
+        }
+    }
+}
+
+/// One external program run.
+#[derive(Debug, Clone)]
+pub struct CommandRun<'a> {
+    pub binary: &'a str,
+    pub args: &'a [String],
+    pub cwd: &'a str,
+    /// `None` when the program was killed, timed out or could not start.
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Entry {
+    recorded_at: DateTime<Utc>,
+    kind: String,
+    source: String,
+    session_ids: Vec<String>,
+    prompt_ids: Vec<i64>,
+    detail: Value,
+}
+
+impl Entry {
+    fn new(kind: &str, source: &str, by: Attribution, mut detail: Value) -> Self {
+        crate::redaction::redact_value(&mut detail);
+        Self {
+            // Stored as TIMESTAMPTZ: keep what survives the round trip.
+            recorded_at: Utc::now().trunc_subsecs(6),
+            kind: kind.to_string(),
+            source: source.to_string(),
+            session_ids: by.session_ids,
+            prompt_ids: by.prompt_ids,
+            detail,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TrailRow {
+    pub seq: i64,
+    pub recorded_at: DateTime<Utc>,
+    pub kind: String,
+    pub source: String,
+    pub session_ids: Vec<String>,
+    pub prompt_ids: Vec<i64>,
+    pub detail: Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl TrailRow {
+    fn entry(&self) -> Entry {
+        Entry {
+            recorded_at: self.recorded_at,
+            kind: self.kind.clone(),
+            source: self.source.clone(),
+            session_ids: self.session_ids.clone(),
+            prompt_ids: self.prompt_ids.clone(),
+            detail: self.detail.clone(),
+        }
+    }
+
+    fn csv_line(&self) -> String {
+        let prompt_ids: Vec<String> = self.prompt_ids.iter().map(i64::to_string).collect();
+        [
+            self.seq.to_string(),
+            self.recorded_at
+                .to_rfc3339_opts(SecondsFormat::Micros, true),
+            csv_field(&self.kind),
+            csv_field(&self.source),
+            csv_field(&self.session_ids.join(";")),
+            prompt_ids.join(";"),
+            csv_field(&self.detail.to_string()),
+            self.prev_hash.clone(),
+            self.hash.clone(),
+        ]
+        .join(",")
+    }
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "seq",
+    "recorded_at",
+    "kind",
+    "source",
+    "session_ids",
+    "prompt_ids",
+    "detail",
+    "prev_hash",
+    "hash",
+];
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Hash chain
+// ═══════════════════════════════════════════════════════════════════════
+
+/// JSON with object keys sorted at every level, so a row read back from
+/// JSONB hashes like the value that was written.
+fn canonical(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|k| format!("{}:{}", Value::String(k.clone()), canonical(&map[k])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(canonical).collect();
+            format!("[{}]", items.join(","))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn chain_hash(prev_hash: &str, entry: &Entry) -> String {
+    let body = json!({
+        "recorded_at": entry.recorded_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        "kind": entry.kind,
+        "source": entry.source,
+        "session_ids": entry.session_ids,
+        "prompt_ids": entry.prompt_ids,
+        "detail": entry.detail,
+    });
+    Sha256::digest(format!("{}\n{}", prev_hash, canonical(&body)).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Why `row` does not continue a chain ending in `prev_hash`, if it doesn't.
+fn broken_link(prev_hash: &str, row: &TrailRow) -> Option<&'static str> {
+    if row.prev_hash != prev_hash {
+        Some("prev_hash does not match the previous entry")
+    } else if chain_hash(prev_hash, &row.entry()) != row.hash {
+        Some("hash does not match the entry")
+    } else {
+        None
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Recording
+// ═══════════════════════════════════════════════════════════════════════
+
+type Queue = (mpsc::Sender<Entry>, Mutex<Option<mpsc::Receiver<Entry>>>);
+
+/// Entries waiting for the writer; the receiver is taken by `spawn`.
+static QUEUE: LazyLock<Queue> = LazyLock::new(|| {
+    let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+    (tx, Mutex::new(Some(rx)))
+});
+
+/// Serializes access to the spill file.
+static SPILL_LOCK: Mutex<()> = Mutex::new(());
+
+fn spill_path() -> PathBuf {
+    crate::metrics_snapshot::data_dir().join(SPILL_FILE)
+}
+
+fn push(entry: Entry) {
+    // Full (writer behind or database down) or no writer: keep it on disk.
+    if let Err(e) = QUEUE.0.try_send(entry) {
+        spill(&spill_path(), &[e.into_inner()]);
+    }
+}
+
+/// Append `entries` to the spill file, one JSON line each.
+fn spill(path: &Path, entries: &[Entry]) {
+    let _guard = SPILL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut lines = String::new();
+    for entry in entries {
+        lines.push_str(&serde_json::to_string(entry).unwrap_or_default());
+        lines.push('\n');
+    }
+    let written = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+        })
+        .and_then(|mut file| file.write_all(lines.as_bytes()));
+    if let Err(e) = written {
+        tracing::error!(
+            "audit_trail: lost {} entries, cannot spill to {}: {}",
+            entries.len(),
+            path.display(),
+            e
+        );
+    }
+}
+
+/// Remove and return the spilled entries; unreadable lines are dropped.
+fn take_spilled(path: &Path) -> Vec<Entry> {
+    let _guard = SPILL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    if let Err(e) = std::fs::remove_file(path) {
+        tracing::warn!("audit_trail: cannot remove {}: {}", path.display(), e);
+        return Vec::new();
+    }
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::error!("audit_trail: dropping unreadable spilled entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Record a program run by `source` (`claude_cli`, `gateway_shell`, `hook`, ...).
+pub fn command(source: &str, run: CommandRun<'_>, by: Attribution) {
+    let detail = json!({
+        "binary": run.binary,
+        "args": run.args,
+        "cwd": run.cwd,
+        "exit_code": run.exit_code,
+        "duration_ms": run.duration_ms,
+    });
+    push(Entry::new("command", source, by, detail));
+}
+
+/// Record a change (`created`, `modified`, `removed`, ...) to `path`.
+pub fn file_change(source: &str, path: &str, change: &str, by: Attribution) {
+    let detail = json!({ "path": path, "change": change });
+    push(Entry::new("file_change", source, by, detail));
+}
+
+async fn append(db: &sqlx::PgPool, entry: &Entry) -> Result<(), sqlx::Error> {
+    let mut tx = db.begin().await?;
+    // Serialize appends from every backend instance sharing the database.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('ch_audit_trail'))")
+        .execute(&mut *tx)
+        .await?;
+    let prev_hash: String =
+        sqlx::query_scalar("SELECT hash FROM ch_audit_trail ORDER BY seq DESC LIMIT 1")
+            .fetch_optional(&mut *tx)
+            .await?
+            .unwrap_or_else(|| GENESIS.to_string());
+    let hash = chain_hash(&prev_hash, entry);
+    sqlx::query(
+        "INSERT INTO ch_audit_trail \
+             (recorded_at, kind, source, session_ids, prompt_ids, detail, prev_hash, hash) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(entry.recorded_at)
+    .bind(&entry.kind)
+    .bind(&entry.source)
+    .bind(&entry.session_ids)
+    .bind(&entry.prompt_ids)
+    .bind(&entry.detail)
+    .bind(&prev_hash)
+    .bind(&hash)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// `append`, retried with backoff; the last error when every attempt failed.
+async fn append_with_retry(db: &sqlx::PgPool, entry: &Entry) -> Result<(), sqlx::Error> {
+    let mut attempt = 1;
+    loop {
+        match append(db, entry).await {
+            Err(e) if attempt < APPEND_ATTEMPTS => {
+                tracing::warn!("audit_trail: append attempt {} failed: {}", attempt, e);
+                tokio::time::sleep(Duration::from_millis(250 << attempt)).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Append spilled entries; stops at the first failure and spills the rest
+/// back for the next pass.
+async fn replay_spilled(db: &sqlx::PgPool, path: &Path) {
+    let taken = path.to_path_buf();
+    let entries = tokio::task::spawn_blocking(move || take_spilled(&taken))
+        .await
+        .unwrap_or_default();
+    for (i, entry) in entries.iter().enumerate() {
+        if let Err(e) = append(db, entry).await {
+            tracing::warn!("audit_trail: spilled entries not appended yet: {}", e);
+            let rest = entries[i..].to_vec();
+            let path = path.to_path_buf();
+            let _ = tokio::task::spawn_blocking(move || spill(&path, &rest)).await;
+            return;
+        }
+    }
+    if !entries.is_empty() {
+        tracing::info!("audit_trail: appended {} spilled entries", entries.len());
+    }
+}
+
+/// Spawn the writer that appends queued entries in order. Entries that
+/// cannot be appended are spilled to disk and retried every `SPILL_RETRY`.
+/// Started once per process by `startup::spawn_background_writers`.
+pub fn spawn(state: AppState) {
+    let Some(mut rx) = QUEUE.1.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    let path = spill_path();
+    tokio::spawn(async move {
+        // The first tick fires at once: entries spilled by an earlier run.
+        let mut retry = tokio::time::interval(SPILL_RETRY);
+        loop {
+            tokio::select! {
+                entry = rx.recv() => {
+                    let Some(entry) = entry else { break };
+                    if let Err(e) = append_with_retry(&state.db, &entry).await {
+                        tracing::error!(
+                            "audit_trail: failed to append {} from {}, spilled to {}: {}",
+                            entry.kind,
+                            entry.source,
+                            path.display(),
+                            e
+                        );
+                        let spilled = path.clone();
+                        let _ = tokio::task::spawn_blocking(move || spill(&spilled, &[entry]))
+                            .await;
+                    }
+                }
+                _ = retry.tick() => replay_spilled(&state.db, &path).await,
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Queries
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TrailFilter {
+    pub kind: Option<String>,
+    pub source: Option<String>,
+    pub session_id: Option<String>,
+    pub prompt_id: Option<i64>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    /// Export only: `jsonl` (default) or `csv`.
+    pub format: Option<String>,
+}
+
+/// Entries matching `filter`, oldest or newest first, at most `limit`.
+pub async fn get_audit_trail(
+    db: &sqlx::PgPool,
+    filter: &TrailFilter,
+    newest_first: bool,
+    limit: i64,
+) -> Result<Vec<TrailRow>, sqlx::Error> {
+    sqlx::query_as::<_, TrailRow>(&format!(
+        "SELECT * FROM ch_audit_trail \
+         WHERE ($1::TEXT IS NULL OR kind = $1) \
+           AND ($2::TEXT IS NULL OR source = $2) \
+           AND ($3::TEXT IS NULL OR $3 = ANY(session_ids)) \
+           AND ($4::BIGINT IS NULL OR $4 = ANY(prompt_ids)) \
+           AND ($5::TIMESTAMPTZ IS NULL OR recorded_at >= $5) \
+           AND ($6::TIMESTAMPTZ IS NULL OR recorded_at < $6) \
+         ORDER BY seq {} LIMIT $7",
+        if newest_first { "DESC" } else { "ASC" }
+    ))
+    .bind(&filter.kind)
+    .bind(&filter.source)
+    .bind(&filter.session_id)
+    .bind(filter.prompt_id)
+    .bind(filter.since)
+    .bind(filter.until)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/audit-trail`
+pub async fn list_audit_trail(
+    State(state): State<AppState>,
+    Query(filter): Query<TrailFilter>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let entries = get_audit_trail(&state.db, &filter, true, limit)
+        .await
+        .map_err(db_error("audit_trail"))?;
+    Ok(Json(json!({ "entries": entries })))
+}
+
+/// `GET /api/audit-trail/export` — matching entries as a JSONL or CSV download.
+pub async fn export_audit_trail(
+    State(state): State<AppState>,
+    Query(filter): Query<TrailFilter>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let csv = match filter.format.as_deref() {
+        None | Some("jsonl") => false,
+        Some("csv") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Unsupported format '{}' (jsonl, csv)", other) })),
+            ));
+        }
+    };
+    let limit = filter
+        .limit
+        .unwrap_or(MAX_EXPORT_ROWS)
+        .clamp(1, MAX_EXPORT_ROWS);
+    let rows = get_audit_trail(&state.db, &filter, false, limit)
+        .await
+        .map_err(db_error("audit_trail"))?;
+
+    let (body, content_type, ext) = if csv {
+        let mut out = CSV_COLUMNS.join(",");
+        out.push('\n');
+        for row in &rows {
+            out.push_str(&row.csv_line());
+            out.push('\n');
+        }
+        (out, "text/csv; charset=utf-8", "csv")
+    } else {
+        let mut out = String::new();
+        for row in &rows {
+            out.push_str(&serde_json::to_string(row).unwrap_or_default());
+            out.push('\n');
+        }
+        (out, "application/x-ndjson", "jsonl")
+    };
+    let disposition = format!(
+        "attachment; filename=\"claudehydra-audit-trail-{}.{}\"",
+        Utc::now().format("%Y%m%d"),
+        ext
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// `GET /api/audit-trail/verify` — replay the whole hash chain.
+pub async fn verify_audit_trail(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut prev_hash = GENESIS.to_string();
+    let mut after = 0_i64;
+    let mut checked = 0_u64;
+    loop {
+        let rows = sqlx::query_as::<_, TrailRow>(
+            "SELECT * FROM ch_audit_trail WHERE seq > $1 ORDER BY seq ASC LIMIT $2",
+        )
+        .bind(after)
+        .bind(VERIFY_BATCH)
+        .fetch_all(&state.db)
+        .await
+        .map_err(db_error("audit_trail"))?;
+        if rows.is_empty() {
+            break;
+        }
+        for row in rows {
+            if let Some(problem) = broken_link(&prev_hash, &row) {
+                tracing::warn!("audit_trail: chain broken at #{}: {}", row.seq, problem);
+                return Ok(Json(json!({
+                    "valid": false,
+                    "checked": checked,
+                    "broken_at": row.seq,
+                    "problem": problem,
+                })));
+            }
+            prev_hash = row.hash;
+            after = row.seq;
+            checked += 1;
+        }
+    }
+    Ok(Json(json!({
+        "valid": true,
+        "checked": checked,
+        "head": prev_hash,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(seq: i64, prev_hash: &str, path: &str) -> TrailRow {
+        let entry = Entry::new(
+            "file_change",
+            "file_watcher",
+            Attribution {
+                session_ids: vec!["s1".to_string()],
+                prompt_ids: vec![7],
+            },
+            json!({ "path": path, "change": "modified" }),
+        );
+        TrailRow {
+            seq,
+            hash: chain_hash(prev_hash, &entry),
+            recorded_at: entry.recorded_at,
+            kind: entry.kind,
+            source: entry.source,
+            session_ids: entry.session_ids,
+            prompt_ids: entry.prompt_ids,
+            detail: entry.detail,
+            prev_hash: prev_hash.to_string(),
+        }
+    }
+
+    #[test]
+    fn canonical_json_ignores_key_order() {
+        let a = json!({ "b": 1, "a": { "y": [1, "x"], "x": null } });
+        let b = json!({ "a": { "x": null, "y": [1, "x"] }, "b": 1 });
+        assert_eq!(canonical(&a), canonical(&b));
+        assert_eq!(canonical(&a), r#"{"a":{"x":null,"y":[1,"x"]},"b":1}"#);
+    }
+
+    #[test]
+    fn chain_detects_edits_and_removed_rows() {
+        let first = row(1, GENESIS, "a.rs");
+        let second = row(2, &first.hash, "b.rs");
+        let third = row(3, &second.hash, "c.rs");
+        assert_eq!(broken_link(GENESIS, &first), None);
+        assert_eq!(broken_link(&first.hash, &second), None);
+
+        let mut edited = second.clone();
+        edited.detail["path"] = json!("other.rs");
+        assert_eq!(
+            broken_link(&first.hash, &edited),
+            Some("hash does not match the entry")
+        );
+        // `second` removed: `third` no longer links to its predecessor.
+        assert_eq!(
+            broken_link(&first.hash, &third),
+            Some("prev_hash does not match the previous entry")
+        );
+    }
+
+    #[test]
+    fn commands_are_recorded_with_redacted_arguments() {
+        let args = vec![
+            "-c".to_string(),
+            "curl -H 'Authorization: Bearer sk-ant-REDACTED'"
+                .to_string(),
+        ];
+        let entry = Entry::new(
+            "command",
+            "gateway_shell",
+            Attribution::session(None),
+            json!({ "binary": "sh", "args": args, "exit_code": 0 }),
+        );
+        assert!(entry.session_ids.is_empty());
+        assert!(!entry.detail.to_string().contains("abcdefghijklmnop"));
+        assert_eq!(entry.detail["exit_code"], 0);
+    }
+
+    #[test]
+    fn spilled_entries_are_taken_back_once() {
+        let path = std::env::temp_dir()
+            .join(format!("ch-audit-{}", uuid::Uuid::new_v4()))
+            .join(SPILL_FILE);
+        let first = row(1, GENESIS, "a.rs").entry();
+        let second = row(2, GENESIS, "b.rs").entry();
+        spill(&path, std::slice::from_ref(&first));
+        spill(&path, std::slice::from_ref(&second));
+        assert_eq!(take_spilled(&path), vec![first, second]);
+        assert!(take_spilled(&path).is_empty());
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
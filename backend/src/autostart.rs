@@ -0,0 +1,242 @@
+//! Start-at-login integration and headless background mode.
+//!
+//! `set_autostart(true)` registers the backend to launch at login with
+//! `--background`:
+//! - Windows — `HKCU\Software\Microsoft\Windows\CurrentVersion\Run`
+//!   value `ClaudeHydra`, launched through a hidden PowerShell window so no
+//!   console appears.
+//! - Linux — XDG autostart entry `~/.config/autostart/claudehydra.desktop`.
+//!
+//! In background mode (`--background` or `CH_BACKGROUND=1`) the server runs
+//! exactly as usual — watchdog, scheduler, pruning and swarm loops included —
+//! but skips the console banner. Opening the frontend attaches to the
+//! already-running backend.
+//!
+//! - `GET /api/system/autostart` — current registration + background flag
+//! - `PUT /api/system/autostart` — `{ enabled }`
+
+use std::path::{Path, PathBuf};
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+pub const BACKGROUND_FLAG: &str = "--background";
+
+#[cfg(windows)]
+const RUN_KEY: &str = r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run";
+#[cfg(windows)]
+const RUN_VALUE: &str = "ClaudeHydra";
+
+/// Whether this process was started headless (login autostart).
+pub fn is_background() -> bool {
+    std::env::args().any(|a| a == BACKGROUND_FLAG)
+        || std::env::var("CH_BACKGROUND").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutostartStatus {
+    pub supported: bool,
+    pub enabled: bool,
+    /// Command registered to run at login, if any.
+    pub command: Option<String>,
+    pub background_mode: bool,
+}
+
+/// Command line that starts this executable headless from its current
+/// working directory (so `.env` is found).
+#[cfg_attr(not(any(windows, target_os = "linux")), allow(dead_code))]
+fn launch_command(exe: &Path, workdir: &Path) -> String {
+    if cfg!(windows) {
+        format!(
+            "powershell.exe -NoProfile -WindowStyle Hidden -Command \"Start-Process -FilePath '{}' -ArgumentList '{}' -WorkingDirectory '{}' -WindowStyle Hidden\"",
+            exe.display(),
+            BACKGROUND_FLAG,
+            workdir.display()
+        )
+    } else {
+        format!("\"{}\" {}", exe.display(), BACKGROUND_FLAG)
+    }
+}
+
+/// XDG autostart entry for `exe`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn desktop_entry(exe: &Path, workdir: &Path) -> String {
+    format!(
+        "[Desktop Entry]\nType=Application\nName=ClaudeHydra\nComment=ClaudeHydra backend (background)\nExec={}\nPath={}\nTerminal=false\nX-GNOME-Autostart-enabled=true\n",
+        launch_command(exe, workdir),
+        workdir.display()
+    )
+}
+
+#[cfg_attr(not(any(windows, target_os = "linux")), allow(dead_code))]
+fn current_exe_and_dir() -> Result<(PathBuf, PathBuf), String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Cannot resolve executable: {}", e))?;
+    let dir = std::env::current_dir().map_err(|e| format!("Cannot resolve working directory: {}", e))?;
+    Ok((exe, dir))
+}
+
+#[cfg(target_os = "linux")]
+fn desktop_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("autostart").join("claudehydra.desktop"))
+}
+
+/// Current autostart registration.
+pub async fn autostart_status() -> AutostartStatus {
+    let background_mode = is_background();
+
+    #[cfg(windows)]
+    {
+        let output = tokio::process::Command::new("reg")
+            .args(["query", RUN_KEY, "/v", RUN_VALUE])
+            .output()
+            .await;
+        let command = output.ok().filter(|o| o.status.success()).and_then(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .find(|l| l.trim_start().starts_with(RUN_VALUE))
+                .and_then(|l| l.split("REG_SZ").nth(1))
+                .map(|c| c.trim().to_string())
+        });
+        AutostartStatus {
+            supported: true,
+            enabled: command.is_some(),
+            command,
+            background_mode,
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let command = match desktop_path() {
+            Some(path) => tokio::fs::read_to_string(path).await.ok().and_then(|s| {
+                s.lines()
+                    .find_map(|l| l.strip_prefix("Exec="))
+                    .map(str::to_string)
+            }),
+            None => None,
+        };
+        AutostartStatus {
+            supported: true,
+            enabled: command.is_some(),
+            command,
+            background_mode,
+        }
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        AutostartStatus {
+            supported: false,
+            enabled: false,
+            command: None,
+            background_mode,
+        }
+    }
+}
+
+/// Register (or remove) this executable as a login item started with
+/// `--background`.
+pub async fn set_autostart(enabled: bool) -> Result<AutostartStatus, String> {
+    register(enabled).await?;
+    tracing::info!("autostart: {}", if enabled { "enabled" } else { "disabled" });
+    Ok(autostart_status().await)
+}
+
+#[cfg(windows)]
+async fn register(enabled: bool) -> Result<(), String> {
+    let output = if enabled {
+        let (exe, dir) = current_exe_and_dir()?;
+        tokio::process::Command::new("reg")
+            .args(["add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d"])
+            .arg(launch_command(&exe, &dir))
+            .arg("/f")
+            .output()
+            .await
+    } else {
+        tokio::process::Command::new("reg")
+            .args(["delete", RUN_KEY, "/v", RUN_VALUE, "/f"])
+            .output()
+            .await
+    }
+    .map_err(|e| format!("Failed to run reg.exe: {}", e))?;
+    // Deleting a value that does not exist is not an error for us.
+    if !output.status.success() && enabled {
+        return Err(format!(
+            "reg.exe failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+async fn register(enabled: bool) -> Result<(), String> {
+    let path = desktop_path().ok_or("Cannot resolve the XDG config directory")?;
+    if enabled {
+        let (exe, dir) = current_exe_and_dir()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Cannot create {}: {}", parent.display(), e))?;
+        }
+        tokio::fs::write(&path, desktop_entry(&exe, &dir))
+            .await
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    } else if let Err(e) = tokio::fs::remove_file(&path).await
+        && e.kind() != std::io::ErrorKind::NotFound
+    {
+        return Err(format!("Cannot remove {}: {}", path.display(), e));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+async fn register(_enabled: bool) -> Result<(), String> {
+    Err("Autostart is not supported on this platform".to_string())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct SetAutostartRequest {
+    pub enabled: bool,
+}
+
+/// `GET /api/system/autostart`
+pub async fn get_autostart() -> Json<AutostartStatus> {
+    Json(autostart_status().await)
+}
+
+/// `PUT /api/system/autostart`
+pub async fn put_autostart(
+    Json(req): Json<SetAutostartRequest>,
+) -> Result<Json<AutostartStatus>, (StatusCode, Json<Value>)> {
+    set_autostart(req.enabled).await.map(Json).map_err(|e| {
+        tracing::warn!("autostart: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e })))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_command_passes_background_flag() {
+        let cmd = launch_command(Path::new("/opt/ch/claudehydra-backend"), Path::new("/opt/ch"));
+        assert!(cmd.contains("claudehydra-backend"));
+        assert!(cmd.contains(BACKGROUND_FLAG));
+    }
+
+    #[test]
+    fn desktop_entry_runs_from_workdir() {
+        let entry = desktop_entry(Path::new("/opt/ch/claudehydra-backend"), Path::new("/opt/ch"));
+        assert!(entry.starts_with("[Desktop Entry]\n"));
+        assert!(entry.contains("\nPath=/opt/ch\n"));
+        assert!(entry.contains("Terminal=false"));
+    }
+}
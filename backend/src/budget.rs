@@ -0,0 +1,408 @@
+//! Budget caps — daily and weekly spending limits per cloud provider, checked
+//! against the cost accounting aggregates in `ch_cost_daily` (see `costs.rs`).
+//!
+//! Caps are USD amounts per provider (`anthropic`, `google`, `openai`,
+//! `openrouter`, `groq`, `azure`, `xai`, `deepseek`, `other`) from `[budget]` in the config file or `CH_BUDGET_DAILY` /
+//! `CH_BUDGET_WEEKLY` (e.g. `anthropic=5,google=2`). Days are local calendar
+//! days and weeks start on Monday. Providers without a cap are unlimited;
+//! local Ollama models are free and never capped.
+//!
+//! Once a provider reaches a cap:
+//! - the Witcher router and the background queue shift prompts bound for it
+//!   to a free local model (`CH_WITCHER_LOCAL_MODEL`, or the best installed
+//!   one when that is unset or `auto`);
+//! - requests that still reach the provider (chat, streaming, WebSocket,
+//!   AI gateway, background prompts with no local model available) are rejected with
+//!   `402 Payment Required` and code `BUDGET_EXCEEDED`.
+//!
+//! Spending is an estimate (see `costs.rs`), so a cap may be overshot by the
+//! request that crosses it. Reaching a cap is logged once per provider and
+//! period as `budget-exceeded`.
+//!
+//! - `GET /api/budget` — caps, spending and remaining budget per provider
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{Datelike, Local, NaiveDate};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::app_log::Level;
+use crate::state::AppState;
+
+/// Providers a cap can be set for (`costs::provider_of`, minus `ollama`).
+pub const PROVIDERS: &[&str] = &[
+    "anthropic",
+    "google",
+    "openai",
+    "openrouter",
+    "groq",
+    "azure",
+    "xai",
+    "deepseek",
+    "other",
+];
+
+/// How long spending totals are reused; recording a cost refreshes them.
+const SPEND_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Period {
+    Daily,
+    Weekly,
+}
+
+impl Period {
+    fn as_str(self) -> &'static str {
+        match self {
+            Period::Daily => "daily",
+            Period::Weekly => "weekly",
+        }
+    }
+
+    fn window(self) -> &'static str {
+        match self {
+            Period::Daily => "today",
+            Period::Weekly => "this week",
+        }
+    }
+}
+
+/// A cap that has been reached.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Exceeded {
+    pub provider: String,
+    pub period: Period,
+    pub cap: f64,
+    pub spent: f64,
+}
+
+impl Exceeded {
+    pub fn message(&self) -> String {
+        format!(
+            "The {} {} budget of ${:.2} is used up (${:.2} spent {})",
+            self.provider,
+            self.period.as_str(),
+            self.cap,
+            self.spent,
+            self.period.window()
+        )
+    }
+}
+
+/// Daily and weekly caps per provider.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Caps {
+    pub daily: BTreeMap<String, f64>,
+    pub weekly: BTreeMap<String, f64>,
+}
+
+/// Parse `provider=usd` pairs separated by commas; invalid pairs are skipped.
+pub fn parse_caps(raw: &str) -> BTreeMap<String, f64> {
+    raw.split(',')
+        .filter_map(|pair| {
+            let (provider, cap) = pair.split_once('=')?;
+            let provider = provider.trim().to_lowercase();
+            let cap: f64 = cap.trim().trim_start_matches('$').parse().ok()?;
+            (PROVIDERS.contains(&provider.as_str()) && cap.is_finite() && cap > 0.0)
+                .then_some((provider, cap))
+        })
+        .collect()
+}
+
+/// Current caps: env vars win over the config file.
+pub fn caps() -> Caps {
+    let file = crate::hydra_config::current();
+    let pick = |env: &str, configured: &BTreeMap<String, f64>| match std::env::var(env) {
+        Ok(v) if !v.trim().is_empty() => parse_caps(&v),
+        _ => configured
+            .iter()
+            .map(|(p, cap)| (p.trim().to_lowercase(), *cap))
+            .collect(),
+    };
+    Caps {
+        daily: pick("CH_BUDGET_DAILY", &file.budget.daily),
+        weekly: pick("CH_BUDGET_WEEKLY", &file.budget.weekly),
+    }
+}
+
+/// Monday of the week containing `day`.
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
+
+/// Spending of one provider, in USD.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Spend {
+    day: f64,
+    week: f64,
+}
+
+/// The first cap `provider` has reached, daily before weekly.
+fn over_cap(caps: &Caps, provider: &str, spend: Spend) -> Option<Exceeded> {
+    [
+        (Period::Daily, &caps.daily, spend.day),
+        (Period::Weekly, &caps.weekly, spend.week),
+    ]
+    .into_iter()
+    .find_map(|(period, caps, spent)| {
+        let cap = *caps.get(provider)?;
+        (spent >= cap).then(|| Exceeded {
+            provider: provider.to_string(),
+            period,
+            cap,
+            spent,
+        })
+    })
+}
+
+struct SpendCache {
+    loaded: Instant,
+    today: NaiveDate,
+    by_provider: HashMap<String, Spend>,
+}
+
+static SPEND: Mutex<Option<SpendCache>> = Mutex::new(None);
+
+/// `(provider, period, period start)` caps already reported as reached.
+static WARNED: LazyLock<Mutex<HashSet<(String, Period, NaiveDate)>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Drop the cached totals so the next check sees a just-recorded cost.
+pub fn invalidate() {
+    *SPEND.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Today's and this week's spending per provider.
+async fn spending(db: &sqlx::PgPool) -> Result<HashMap<String, Spend>, sqlx::Error> {
+    let today = Local::now().date_naive();
+    {
+        let cache = SPEND.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(c) = cache.as_ref()
+            && c.today == today
+            && c.loaded.elapsed() < SPEND_TTL
+        {
+            return Ok(c.by_provider.clone());
+        }
+    }
+    let rows: Vec<(String, f64, f64)> = sqlx::query_as(
+        "SELECT provider, \
+                COALESCE(SUM(cost_usd) FILTER (WHERE day = $1), 0), \
+                COALESCE(SUM(cost_usd), 0) \
+         FROM ch_cost_daily WHERE day >= $2 AND day <= $1 \
+         GROUP BY provider",
+    )
+    .bind(today)
+    .bind(week_start(today))
+    .fetch_all(db)
+    .await?;
+    let by_provider: HashMap<String, Spend> = rows
+        .into_iter()
+        .map(|(provider, day, week)| (provider, Spend { day, week }))
+        .collect();
+    *SPEND.lock().unwrap_or_else(|e| e.into_inner()) = Some(SpendCache {
+        loaded: Instant::now(),
+        today,
+        by_provider: by_provider.clone(),
+    });
+    Ok(by_provider)
+}
+
+/// The cap `provider` has reached, if any. Spending that cannot be read
+/// counts as within budget.
+pub async fn exceeded(db: &sqlx::PgPool, provider: &str) -> Option<Exceeded> {
+    let caps = caps();
+    if !caps.daily.contains_key(provider) && !caps.weekly.contains_key(provider) {
+        return None;
+    }
+    let spend = match spending(db).await {
+        Ok(by_provider) => by_provider.get(provider).copied().unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("budget: failed to read spending: {}", e);
+            return None;
+        }
+    };
+    let over = over_cap(&caps, provider, spend)?;
+    let today = Local::now().date_naive();
+    let since = match over.period {
+        Period::Daily => today,
+        Period::Weekly => week_start(today),
+    };
+    let first = WARNED.lock().unwrap_or_else(|e| e.into_inner()).insert((
+        over.provider.clone(),
+        over.period,
+        since,
+    ));
+    if first {
+        tracing::warn!("budget: {}", over.message());
+        crate::app_log::record(Level::Warn, "budget", "budget-exceeded", json!(over));
+    }
+    Some(over)
+}
+
+/// Error for requests to `provider` once it has reached a cap.
+pub async fn ensure_within(
+    db: &sqlx::PgPool,
+    provider: &str,
+) -> Result<(), (StatusCode, Json<Value>)> {
+    match exceeded(db, provider).await {
+        None => Ok(()),
+        Some(over) => Err((
+            StatusCode::PAYMENT_REQUIRED,
+            Json(json!({
+                "error": format!("{} — use a local model or raise the cap", over.message()),
+                "code": "BUDGET_EXCEEDED",
+                "provider": over.provider,
+                "period": over.period,
+                "cap_usd": over.cap,
+                "spent_usd": over.spent,
+            })),
+        )),
+    }
+}
+
+/// Free local model (`ollama/<name>`) to run `prompt` on instead of a capped
+/// provider, or `None` when none is configured or installed.
+pub async fn local_model(state: &AppState, prompt: &str) -> Option<String> {
+    let name = match crate::witcher_router::local_model() {
+        Some(m) if m != "auto" => m,
+        _ => {
+            let kind = crate::ollama_models::task_kind(prompt);
+            crate::ollama_models::best_model(state, kind).await?
+        }
+    };
+    Some(format!("{}{}", crate::queue::OLLAMA_PREFIX, name))
+}
+
+fn round_usd(usd: f64) -> f64 {
+    (usd * 10_000.0).round() / 10_000.0
+}
+
+fn window(cap: Option<f64>, spent: f64) -> Value {
+    json!({
+        "cap_usd": cap,
+        "spent_usd": round_usd(spent),
+        "remaining_usd": cap.map(|cap| round_usd((cap - spent).max(0.0))),
+        "exceeded": cap.is_some_and(|cap| spent >= cap),
+    })
+}
+
+/// Caps and spending per cloud provider with a cap or spending this week.
+pub async fn budget_status(db: &sqlx::PgPool) -> Result<Value, sqlx::Error> {
+    let caps = caps();
+    let by_provider = spending(db).await?;
+    let today = Local::now().date_naive();
+    let providers: Vec<Value> = PROVIDERS
+        .iter()
+        .filter(|p| {
+            caps.daily.contains_key(**p)
+                || caps.weekly.contains_key(**p)
+                || by_provider.contains_key(**p)
+        })
+        .map(|provider| {
+            let spend = by_provider.get(*provider).copied().unwrap_or_default();
+            let over = over_cap(&caps, provider, spend);
+            json!({
+                "provider": provider,
+                "daily": window(caps.daily.get(*provider).copied(), spend.day),
+                "weekly": window(caps.weekly.get(*provider).copied(), spend.week),
+                "exceeded": over.is_some(),
+                "message": over.map(|o| o.message()),
+            })
+        })
+        .collect();
+    Ok(json!({
+        "day": today,
+        "week_start": week_start(today),
+        "providers": providers,
+    }))
+}
+
+/// GET /api/budget
+pub async fn get_budget_status(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    budget_status(&state.db).await.map(Json).map_err(|e| {
+        tracing::error!("budget: failed to load status: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to load budget status" })),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caps_parse_known_providers_with_positive_amounts() {
+        let caps = parse_caps(" Anthropic=5, google=$2.5,ollama=1,openai=0,other=x,broken");
+        assert_eq!(caps.len(), 2);
+        assert_eq!(caps.get("anthropic"), Some(&5.0));
+        assert_eq!(caps.get("google"), Some(&2.5));
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        assert_eq!(week_start(day(18)), day(16));
+        assert_eq!(week_start(day(16)), day(16));
+        assert_eq!(week_start(day(22)), day(16));
+    }
+
+    #[test]
+    fn daily_cap_is_reported_before_weekly() {
+        let caps = Caps {
+            daily: BTreeMap::from([("anthropic".to_string(), 5.0)]),
+            weekly: BTreeMap::from([("anthropic".to_string(), 20.0)]),
+        };
+        let within = Spend {
+            day: 4.99,
+            week: 12.0,
+        };
+        assert_eq!(over_cap(&caps, "anthropic", within), None);
+        let over = over_cap(
+            &caps,
+            "anthropic",
+            Spend {
+                day: 5.0,
+                week: 25.0,
+            },
+        )
+        .unwrap();
+        assert_eq!(over.period, Period::Daily);
+        let weekly = over_cap(
+            &caps,
+            "anthropic",
+            Spend {
+                day: 1.0,
+                week: 20.5,
+            },
+        )
+        .unwrap();
+        assert_eq!(weekly.period, Period::Weekly);
+        assert!(
+            weekly.message().contains("weekly budget of $20.00"),
+            "{}",
+            weekly.message()
+        );
+        assert_eq!(
+            over_cap(
+                &caps,
+                "google",
+                Spend {
+                    day: 100.0,
+                    week: 100.0
+                }
+            ),
+            None
+        );
+    }
+}
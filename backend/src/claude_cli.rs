@@ -0,0 +1,864 @@
+//! Persistent Claude CLI sessions over stdio.
+//!
+//! Instead of spawning a fresh `claude -p` per prompt (which loses all
+//! CLI-side context), each ClaudeHydra session keeps one long-lived process
+//! started with `--input-format stream-json --output-format stream-json`.
+//! Prompts are written to stdin as `user` events; the reply is read frame by
+//! frame (one JSON event per line) until the turn's `result` event. If the
+//! process exits, the next prompt respawns it with `--resume <cli session>`
+//! so the multi-turn context survives. Permission flags follow the session's
+//! permission mode when one is set (see `permissions`).
+//!
+//! Each process is recorded in the audit trail when it ends (see
+//! `audit_trail.rs`).
+//!
+//! Idle processes are reaped (the conversation and CLI session id are kept,
+//! so the next prompt respawns transparently). Sessions can override the idle
+//! timeout; the number of live processes is capped, evicting the least
+//! recently used idle one when a new process is needed.
+//!
+//! Environment (or `[claude_cli]` in the config file, `hydra_config.rs`):
+//! - `CH_CLAUDE_CLI_BIN` — CLI executable (`paths.claude`; default: `claude`
+//!   found via `paths.rs`, which also knows npm `.cmd` shims and the native
+//!   installer)
+//! - `CH_CLAUDE_CLI_ARGS` — extra arguments, whitespace-separated (a list
+//!   in the file)
+//! - `CH_CLAUDE_CLI_IDLE_SECS` — idle processes are stopped after this many
+//!   seconds (default 900, `0` keeps them until shutdown)
+//! - `CH_CLAUDE_CLI_TURN_TIMEOUT_SECS` — per-turn limit (default 600)
+//! - `CH_CLAUDE_CLI_MAX_PROCESSES` — live process cap (default 8, `0` = unlimited)
+//!
+//! - `GET    /api/claude-cli/sessions`                   — live processes
+//! - `GET    /api/claude-cli/processes`                  — child PIDs, memory, uptime
+//! - `POST   /api/claude-cli/sessions/{id}/prompt`       — `{ prompt, model? }`, one turn
+//! - `PUT    /api/claude-cli/sessions/{id}/idle-timeout` — `{ idle_secs }` (`0` = never,
+//!   `null` = default)
+//! - `DELETE /api/claude-cli/sessions/{id}`              — stop the process
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+
+use crate::api_error::bad_request;
+use crate::hydra_config::{Derived, HydraConfig, env, number};
+use crate::permissions::PermissionMode;
+use crate::state::AppState;
+
+const MAX_PROMPT_CHARS: usize = 100_000;
+pub const REAPER_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct CliConfig {
+    pub extra_args: Vec<String>,
+    pub idle_timeout: Option<Duration>,
+    pub turn_timeout: Duration,
+    pub max_processes: usize,
+}
+
+static CONFIG: Derived<Arc<CliConfig>> = Derived::new(build_config);
+
+fn build_config(config: &HydraConfig) -> Arc<CliConfig> {
+    let file = &config.claude_cli;
+    let idle = number("CH_CLAUDE_CLI_IDLE_SECS", file.idle_secs).unwrap_or(900);
+    Arc::new(CliConfig {
+        extra_args: env("CH_CLAUDE_CLI_ARGS")
+            .map(|a| a.split_whitespace().map(str::to_string).collect())
+            .or_else(|| file.args.clone())
+            .unwrap_or_default(),
+        idle_timeout: (idle > 0).then(|| Duration::from_secs(idle)),
+        turn_timeout: Duration::from_secs(
+            number("CH_CLAUDE_CLI_TURN_TIMEOUT_SECS", file.turn_timeout_secs)
+                .unwrap_or(600)
+                .max(10),
+        ),
+        max_processes: number("CH_CLAUDE_CLI_MAX_PROCESSES", file.max_processes).unwrap_or(8),
+    })
+}
+
+/// CLI settings (env overrides the file).
+pub fn config() -> Arc<CliConfig> {
+    CONFIG.get()
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  stream-json framing
+// ═══════════════════════════════════════════════════════════════════════
+
+/// One parsed stdout frame.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliEvent {
+    /// `system/init` — carries the CLI's own session id.
+    Init { cli_session_id: String },
+    /// Assistant text produced during the turn.
+    Text(String),
+    /// A tool call the CLI is running on its side.
+    ToolUse { name: String },
+    /// End of turn.
+    Result {
+        text: String,
+        is_error: bool,
+        cost_usd: Option<f64>,
+        cli_session_id: Option<String>,
+    },
+    /// Anything else (tool results, partial frames, unknown types).
+    Other,
+}
+
+/// stdin frame for one user prompt.
+fn user_frame(prompt: &str) -> String {
+    json!({
+        "type": "user",
+        "message": { "role": "user", "content": [{ "type": "text", "text": prompt }] },
+    })
+    .to_string()
+}
+
+/// Parse a stdout line. Returns `None` for non-JSON noise.
+fn parse_event(line: &str) -> Option<Vec<CliEvent>> {
+    let v: Value = serde_json::from_str(line.trim()).ok()?;
+    let str_field = |v: &Value, key: &str| v.get(key).and_then(|s| s.as_str()).map(str::to_string);
+    let events = match v.get("type").and_then(|t| t.as_str()) {
+        Some("system") if v.get("subtype").and_then(|s| s.as_str()) == Some("init") => {
+            str_field(&v, "session_id")
+                .map(|id| vec![CliEvent::Init { cli_session_id: id }])
+                .unwrap_or_default()
+        }
+        Some("assistant") => v
+            .pointer("/message/content")
+            .and_then(|c| c.as_array())
+            .map(|blocks| {
+                blocks
+                    .iter()
+                    .map(|b| match b.get("type").and_then(|t| t.as_str()) {
+                        Some("text") => CliEvent::Text(str_field(b, "text").unwrap_or_default()),
+                        Some("tool_use") => CliEvent::ToolUse {
+                            name: str_field(b, "name").unwrap_or_default(),
+                        },
+                        _ => CliEvent::Other,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        Some("result") => vec![CliEvent::Result {
+            text: str_field(&v, "result").unwrap_or_default(),
+            is_error: v.get("is_error").and_then(|e| e.as_bool()).unwrap_or(false),
+            cost_usd: v.get("total_cost_usd").and_then(|c| c.as_f64()),
+            cli_session_id: str_field(&v, "session_id"),
+        }],
+        _ => vec![CliEvent::Other],
+    };
+    Some(events)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Process registry
+// ═══════════════════════════════════════════════════════════════════════
+
+struct CliProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: Lines<BufReader<ChildStdout>>,
+    session_id: uuid::Uuid,
+    binary: String,
+    args: Vec<String>,
+    cwd: String,
+    model: Option<String>,
+    mode: Option<PermissionMode>,
+    started_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Drop for CliProcess {
+    /// Records the invocation in the audit trail once it ends — exited, or
+    /// about to be killed (`kill_on_drop`), which leaves no exit code.
+    fn drop(&mut self) {
+        let exit_code = self.child.try_wait().ok().flatten().and_then(|s| s.code());
+        let duration = chrono::Utc::now() - self.started_at;
+        crate::audit_trail::command(
+            "claude_cli",
+            crate::audit_trail::CommandRun {
+                binary: &self.binary,
+                args: &self.args,
+                cwd: &self.cwd,
+                exit_code,
+                duration_ms: duration.num_milliseconds().max(0) as u64,
+            },
+            crate::audit_trail::Attribution::session(Some(self.session_id)),
+        );
+    }
+}
+
+struct CliSlot {
+    process: Option<CliProcess>,
+    /// CLI-side session id, kept across respawns for `--resume`.
+    cli_session_id: Option<String>,
+    last_used: Instant,
+    turns: u64,
+    /// Per-session idle timeout in seconds (`0` = never); `None` uses the default.
+    idle_override: Option<u64>,
+}
+
+impl CliSlot {
+    fn idle_timeout(&self) -> Option<Duration> {
+        effective_idle_timeout(self.idle_override, config().idle_timeout)
+    }
+}
+
+fn effective_idle_timeout(
+    override_secs: Option<u64>,
+    default: Option<Duration>,
+) -> Option<Duration> {
+    match override_secs {
+        Some(0) => None,
+        Some(secs) => Some(Duration::from_secs(secs)),
+        None => default,
+    }
+}
+
+type Registry = HashMap<uuid::Uuid, Arc<Mutex<CliSlot>>>;
+
+static SESSIONS: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+async fn slot(session_id: uuid::Uuid) -> Arc<Mutex<CliSlot>> {
+    SESSIONS
+        .lock()
+        .await
+        .entry(session_id)
+        .or_insert_with(|| {
+            Arc::new(Mutex::new(CliSlot {
+                process: None,
+                cli_session_id: None,
+                last_used: Instant::now(),
+                turns: 0,
+                idle_override: None,
+            }))
+        })
+        .clone()
+}
+
+fn spawn_process(
+    session_id: uuid::Uuid,
+    cwd: &str,
+    model: Option<&str>,
+    mode: Option<PermissionMode>,
+    resume: Option<&str>,
+) -> Result<CliProcess, String> {
+    let cfg = config();
+    let bin = crate::paths::program(&crate::paths::CLAUDE);
+    let mut cmd = tokio::process::Command::new(&bin);
+    cmd.args([
+        "-p",
+        "--input-format",
+        "stream-json",
+        "--output-format",
+        "stream-json",
+        "--verbose",
+    ]);
+    if let Some(model) = model {
+        cmd.args(["--model", model]);
+    }
+    if let Some(id) = resume {
+        cmd.args(["--resume", id]);
+    }
+    if let Some(mode) = mode {
+        cmd.args(mode.claude_cli_args());
+    }
+    cmd.args(&cfg.extra_args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true);
+    if !cwd.is_empty() {
+        cmd.current_dir(cwd);
+    }
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", bin.display(), e))?;
+    let stdin = child.stdin.take().ok_or("CLI stdin unavailable")?;
+    let stdout = child.stdout.take().ok_or("CLI stdout unavailable")?;
+    tracing::info!(
+        "claude_cli: started {} (pid {:?}, cwd {:?}, resume {:?})",
+        bin.display(),
+        child.id(),
+        cwd,
+        resume
+    );
+    let args = cmd
+        .as_std()
+        .get_args()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    Ok(CliProcess {
+        child,
+        stdin,
+        stdout: BufReader::new(stdout).lines(),
+        session_id,
+        binary: bin.to_string_lossy().into_owned(),
+        args,
+        cwd: cwd.to_string(),
+        model: model.map(str::to_string),
+        mode,
+        started_at: chrono::Utc::now(),
+    })
+}
+
+fn registry_slots(
+    registry: &Registry,
+    except: Option<uuid::Uuid>,
+) -> Vec<(uuid::Uuid, Arc<Mutex<CliSlot>>)> {
+    registry
+        .iter()
+        .filter(|(id, _)| Some(**id) != except)
+        .map(|(id, s)| (*id, s.clone()))
+        .collect()
+}
+
+/// Make room for one more process under `CH_CLAUDE_CLI_MAX_PROCESSES` by
+/// stopping the least recently used idle process of another session.
+async fn ensure_capacity(session_id: uuid::Uuid) -> Result<(), String> {
+    let max = config().max_processes;
+    if max == 0 {
+        return Ok(());
+    }
+    let others = registry_slots(&*SESSIONS.lock().await, Some(session_id));
+    let mut running = 0;
+    let mut lru: Option<(Instant, uuid::Uuid, Arc<Mutex<CliSlot>>)> = None;
+    for (id, slot) in others {
+        let Ok(guard) = slot.try_lock() else {
+            // Mid-turn: running, but not evictable.
+            running += 1;
+            continue;
+        };
+        if guard.process.is_none() {
+            continue;
+        }
+        running += 1;
+        if lru
+            .as_ref()
+            .is_none_or(|(used, ..)| guard.last_used < *used)
+        {
+            let used = guard.last_used;
+            drop(guard);
+            lru = Some((used, id, slot));
+        }
+    }
+    if running < max {
+        return Ok(());
+    }
+    let Some((_, id, slot)) = lru else {
+        return Err(format!(
+            "CLI process limit reached ({} busy); try again shortly",
+            max
+        ));
+    };
+    tracing::info!(
+        "claude_cli: process limit {} reached, stopping session {}",
+        max,
+        id
+    );
+    slot.lock().await.process = None;
+    Ok(())
+}
+
+/// Outcome of one prompt turn.
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnResult {
+    pub text: String,
+    pub is_error: bool,
+    pub cost_usd: Option<f64>,
+    pub cli_session_id: Option<String>,
+    pub tools_used: Vec<String>,
+}
+
+async fn read_turn(
+    process: &mut CliProcess,
+    slot_id: &mut Option<String>,
+) -> Result<TurnResult, String> {
+    let mut streamed = String::new();
+    let mut tools_used = Vec::new();
+    loop {
+        let line = process
+            .stdout
+            .next_line()
+            .await
+            .map_err(|e| format!("CLI read failed: {}", e))?
+            .ok_or("CLI process exited mid-turn")?;
+        for event in parse_event(&line).unwrap_or_default() {
+            match event {
+                CliEvent::Init { cli_session_id } => *slot_id = Some(cli_session_id),
+                CliEvent::Text(t) => streamed.push_str(&t),
+                CliEvent::ToolUse { name } => tools_used.push(name),
+                CliEvent::Result {
+                    text,
+                    is_error,
+                    cost_usd,
+                    cli_session_id,
+                } => {
+                    if cli_session_id.is_some() {
+                        *slot_id = cli_session_id;
+                    }
+                    return Ok(TurnResult {
+                        text: if text.is_empty() { streamed } else { text },
+                        is_error,
+                        cost_usd,
+                        cli_session_id: slot_id.clone(),
+                        tools_used,
+                    });
+                }
+                CliEvent::Other => {}
+            }
+        }
+    }
+}
+
+/// Send one prompt to the session's CLI process (spawning or resuming it
+/// as needed) and wait for the turn to finish. Turns are serialised per session.
+pub async fn run_turn(
+    session_id: uuid::Uuid,
+    cwd: &str,
+    prompt: &str,
+    model: Option<&str>,
+    mode: Option<PermissionMode>,
+) -> Result<TurnResult, String> {
+    let _in_flight = crate::shutdown::track().ok_or("Server is shutting down")?;
+    let slot = slot(session_id).await;
+    let mut slot = slot.lock().await;
+    slot.last_used = Instant::now();
+
+    // A different model, directory or permission mode needs a new process
+    // (context is kept via --resume).
+    if slot.process.as_ref().is_some_and(|p| {
+        p.cwd != cwd || p.mode != mode || (model.is_some() && p.model.as_deref() != model)
+    }) {
+        slot.process = None;
+    }
+    if slot.process.is_none() {
+        ensure_capacity(session_id).await?;
+        let process = spawn_process(session_id, cwd, model, mode, slot.cli_session_id.as_deref())?;
+        slot.process = Some(process);
+    }
+
+    let CliSlot {
+        process,
+        cli_session_id,
+        ..
+    } = &mut *slot;
+    let proc_ref = process.as_mut().expect("process spawned above");
+    let written = async {
+        proc_ref
+            .stdin
+            .write_all(user_frame(prompt).as_bytes())
+            .await?;
+        proc_ref.stdin.write_all(b"\n").await?;
+        proc_ref.stdin.flush().await
+    }
+    .await;
+    let result = match written {
+        Ok(()) => tokio::time::timeout(config().turn_timeout, read_turn(proc_ref, cli_session_id))
+            .await
+            .unwrap_or_else(|_| Err("CLI turn timed out".to_string())),
+        Err(e) => Err(format!("CLI write failed: {}", e)),
+    };
+    match result {
+        Ok(turn) => {
+            slot.turns += 1;
+            Ok(turn)
+        }
+        Err(e) => {
+            // Drop the broken process; the next turn resumes from the CLI session id.
+            slot.process = None;
+            Err(e)
+        }
+    }
+}
+
+/// Stop a session's process. Returns whether one was running.
+pub async fn stop(session_id: uuid::Uuid) -> bool {
+    let Some(slot) = SESSIONS.lock().await.remove(&session_id) else {
+        return false;
+    };
+    let mut slot = slot.lock().await;
+    match slot.process.take() {
+        Some(mut p) => {
+            let _ = p.child.kill().await;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Kill a session's process but keep its CLI session id, so the next prompt
+/// respawns it with `--resume`. Waits for a running turn to finish. Returns
+/// whether one was running.
+pub async fn recycle(session_id: uuid::Uuid) -> bool {
+    let Some(slot) = SESSIONS.lock().await.get(&session_id).cloned() else {
+        return false;
+    };
+    let mut slot = slot.lock().await;
+    match slot.process.take() {
+        Some(mut p) => {
+            let _ = p.child.kill().await;
+            true
+        }
+        None => false,
+    }
+}
+
+/// PIDs of live CLI processes by session. Sessions in the middle of a turn
+/// are skipped.
+pub(crate) async fn child_pids() -> Vec<(uuid::Uuid, u32)> {
+    let slots = registry_slots(&*SESSIONS.lock().await, None);
+    slots
+        .into_iter()
+        .filter_map(|(id, slot)| {
+            let guard = slot.try_lock().ok()?;
+            let pid = guard.process.as_ref()?.child.id()?;
+            Some((id, pid))
+        })
+        .collect()
+}
+
+/// Kill every persistent CLI process (shutdown). Returns how many ran.
+pub async fn stop_all() -> usize {
+    let slots: Vec<Arc<Mutex<CliSlot>>> = SESSIONS.lock().await.drain().map(|(_, s)| s).collect();
+    let mut stopped = 0;
+    for slot in slots {
+        if let Some(mut p) = slot.lock().await.process.take() {
+            let _ = p.child.kill().await;
+            stopped += 1;
+        }
+    }
+    stopped
+}
+
+/// Set a session's idle timeout override (`Some(0)` = never reap,
+/// `None` = `CH_CLAUDE_CLI_IDLE_SECS`).
+pub async fn set_idle_timeout(session_id: uuid::Uuid, idle_secs: Option<u64>) {
+    slot(session_id).await.lock().await.idle_override = idle_secs;
+}
+
+/// Spawn the idle reaper. Runs even when the default timeout is disabled,
+/// since sessions may set their own.
+pub fn spawn_reaper() {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(REAPER_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let slots: Vec<(uuid::Uuid, Arc<Mutex<CliSlot>>)> = SESSIONS
+                .lock()
+                .await
+                .iter()
+                .map(|(id, s)| (*id, s.clone()))
+                .collect();
+            for (id, slot) in slots {
+                // Busy slots are mid-turn, not idle.
+                let Ok(mut slot) = slot.try_lock() else {
+                    continue;
+                };
+                if slot.process.is_some()
+                    && let Some(idle) = slot.idle_timeout()
+                    && slot.last_used.elapsed() >= idle
+                {
+                    tracing::info!("claude_cli: stopping idle process for session {}", id);
+                    slot.process = None;
+                }
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/claude-cli/sessions`
+pub async fn list_sessions() -> Json<Value> {
+    let slots: Vec<(uuid::Uuid, Arc<Mutex<CliSlot>>)> = SESSIONS
+        .lock()
+        .await
+        .iter()
+        .map(|(id, s)| (*id, s.clone()))
+        .collect();
+    let mut sessions = Vec::with_capacity(slots.len());
+    for (id, slot) in slots {
+        let (running, busy, entry) = match slot.try_lock() {
+            Ok(s) => (s.process.is_some(), false, Some(s)),
+            Err(_) => (true, true, None),
+        };
+        sessions.push(json!({
+            "session_id": id.to_string(),
+            "running": running,
+            "busy": busy,
+            "cli_session_id": entry.as_ref().and_then(|s| s.cli_session_id.clone()),
+            "turns": entry.as_ref().map(|s| s.turns),
+            "idle_secs": entry.as_ref().map(|s| s.last_used.elapsed().as_secs()),
+            "started_at": entry.as_ref().and_then(|s| s.process.as_ref().map(|p| p.started_at.to_rfc3339())),
+            "idle_timeout_secs": entry.as_ref().map(|s| s.idle_timeout().map(|d| d.as_secs())),
+        }));
+    }
+    let bin = crate::paths::program(&crate::paths::CLAUDE);
+    Json(json!({ "bin": bin.display().to_string(), "sessions": sessions }))
+}
+
+/// Resident memory of a process in bytes (Linux `/proc`; `None` elsewhere).
+fn resident_bytes(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    parse_vm_rss(&status)
+}
+
+fn parse_vm_rss(status: &str) -> Option<u64> {
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// `GET /api/claude-cli/processes` — live child processes with PID and memory.
+pub async fn get_process_stats() -> Json<Value> {
+    let slots = registry_slots(&*SESSIONS.lock().await, None);
+    let mut processes = Vec::new();
+    let mut busy = 0;
+    for (id, slot) in slots {
+        let Ok(mut guard) = slot.try_lock() else {
+            busy += 1;
+            continue;
+        };
+        let idle_timeout = guard.idle_timeout();
+        let idle_secs = guard.last_used.elapsed().as_secs();
+        let Some(process) = guard.process.as_mut() else {
+            continue;
+        };
+        // A child that already exited is cleaned up on the next turn.
+        if !matches!(process.child.try_wait(), Ok(None)) {
+            continue;
+        }
+        let pid = process.child.id();
+        processes.push(json!({
+            "session_id": id.to_string(),
+            "pid": pid,
+            "memory_bytes": pid.and_then(resident_bytes),
+            "uptime_secs": (chrono::Utc::now() - process.started_at).num_seconds(),
+            "idle_secs": idle_secs,
+            "idle_timeout_secs": idle_timeout.map(|d| d.as_secs()),
+            "cwd": process.cwd,
+            "model": process.model,
+            "permission_mode": process.mode,
+        }));
+    }
+    let total_memory: u64 = processes
+        .iter()
+        .filter_map(|p| p["memory_bytes"].as_u64())
+        .sum();
+    Json(json!({
+        "processes": processes,
+        "busy": busy,
+        "max_processes": config().max_processes,
+        "total_memory_bytes": total_memory,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IdleTimeoutRequest {
+    /// Seconds of inactivity before the process is stopped; `0` = never,
+    /// `null` = the server default.
+    pub idle_secs: Option<u64>,
+}
+
+/// `PUT /api/claude-cli/sessions/{id}/idle-timeout`
+pub async fn set_session_idle_timeout(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<IdleTimeoutRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| bad_request("Invalid session id"))?;
+    let idle_secs = req.idle_secs.map(|s| s.min(i32::MAX as u64));
+    let updated = sqlx::query("UPDATE ch_sessions SET cli_idle_secs = $1 WHERE id = $2")
+        .bind(idle_secs.map(|s| s as i32))
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("claude_cli: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Database error" })),
+            )
+        })?
+        .rows_affected();
+    if updated == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Session not found" })),
+        ));
+    }
+    set_idle_timeout(session_id, idle_secs).await;
+    let effective = effective_idle_timeout(idle_secs, config().idle_timeout);
+    Ok(Json(json!({
+        "session_id": id,
+        "idle_secs": idle_secs,
+        "effective_idle_secs": effective.map(|d| d.as_secs()),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CliPromptRequest {
+    pub prompt: String,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// `POST /api/claude-cli/sessions/{id}/prompt`
+pub async fn prompt(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(mut req): Json<CliPromptRequest>,
+) -> Result<Json<TurnResult>, (StatusCode, Json<Value>)> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| bad_request("Invalid session id"))?;
+    if req.prompt.trim().is_empty() {
+        return Err(bad_request("Prompt is empty"));
+    }
+    if req.prompt.len() > MAX_PROMPT_CHARS {
+        return Err(bad_request("Prompt is too long"));
+    }
+    // The CLI talks to Anthropic, so the prompt passes the safety guard first.
+    crate::safety::screen_prompt(&mut req.prompt, "claude_cli")
+        .map_err(|e| crate::safety::rejection(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    // Session workspace, falling back to the global working directory.
+    let cwd = crate::handlers::sessions::session_working_directory(&state.db, &session_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("claude_cli: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Database error" })),
+            )
+        })?
+        .ok_or((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Session not found" })),
+        ))?;
+
+    let mode = crate::permissions::session_mode_setting(&state.db, &session_id).await;
+    let idle_secs: Option<i32> =
+        sqlx::query_scalar("SELECT cli_idle_secs FROM ch_sessions WHERE id = $1")
+            .bind(session_id)
+            .fetch_optional(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .flatten();
+    set_idle_timeout(session_id, idle_secs.map(|s| s.max(0) as u64)).await;
+
+    crate::idle_scavenger::mark_interactive();
+    let turn = run_turn(session_id, &cwd, &req.prompt, req.model.as_deref(), mode)
+        .await
+        .map_err(|e| {
+            tracing::warn!("claude_cli: session {}: {}", session_id, e);
+            (StatusCode::BAD_GATEWAY, Json(json!({ "error": e })))
+        })?;
+
+    if let Err(e) = crate::handlers::streaming::helpers::store_ws_messages(
+        &state,
+        &session_id,
+        Some(req.model.as_deref().unwrap_or("claude-cli")),
+        &req.prompt,
+        &turn.text,
+    )
+    .await
+    {
+        tracing::warn!("claude_cli: failed to store messages: {}", e);
+    }
+    Ok(Json(turn))
+}
+
+/// `DELETE /api/claude-cli/sessions/{id}`
+pub async fn stop_session(
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| bad_request("Invalid session id"))?;
+    Ok(Json(
+        json!({ "session_id": id, "stopped": stop(session_id).await }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_frame_is_single_line_stream_json() {
+        let frame = user_frame("line one\nline two");
+        assert!(!frame.contains('\n'));
+        let v: Value = serde_json::from_str(&frame).unwrap();
+        assert_eq!(v["type"], "user");
+        assert_eq!(v["message"]["content"][0]["text"], "line one\nline two");
+    }
+
+    #[test]
+    fn parses_init_assistant_and_result_frames() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"abc","tools":[]}"#;
+        assert_eq!(
+            parse_event(init).unwrap(),
+            vec![CliEvent::Init {
+                cli_session_id: "abc".to_string()
+            }]
+        );
+
+        let assistant = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hi"},{"type":"tool_use","name":"Read","input":{}}]}}"#;
+        assert_eq!(
+            parse_event(assistant).unwrap(),
+            vec![
+                CliEvent::Text("Hi".to_string()),
+                CliEvent::ToolUse {
+                    name: "Read".to_string()
+                }
+            ]
+        );
+
+        let result = r#"{"type":"result","subtype":"success","is_error":false,"result":"Done","session_id":"abc","total_cost_usd":0.01}"#;
+        assert_eq!(
+            parse_event(result).unwrap(),
+            vec![CliEvent::Result {
+                text: "Done".to_string(),
+                is_error: false,
+                cost_usd: Some(0.01),
+                cli_session_id: Some("abc".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn idle_override_wins_over_default() {
+        let default = Some(Duration::from_secs(900));
+        assert_eq!(effective_idle_timeout(None, default), default);
+        assert_eq!(effective_idle_timeout(Some(0), default), None);
+        assert_eq!(
+            effective_idle_timeout(Some(60), None),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn vm_rss_is_read_from_proc_status() {
+        let status = "Name:\tclaude\nVmPeak:\t  900 kB\nVmRSS:\t  2048 kB\nThreads:\t12\n";
+        assert_eq!(parse_vm_rss(status), Some(2048 * 1024));
+        assert_eq!(parse_vm_rss("Name:\tclaude\n"), None);
+    }
+
+    #[test]
+    fn non_json_lines_are_ignored() {
+        assert!(parse_event("Loading...").is_none());
+        assert_eq!(
+            parse_event(r#"{"type":"user"}"#).unwrap(),
+            vec![CliEvent::Other]
+        );
+    }
+}
@@ -0,0 +1,604 @@
+//! Conversation compaction — keeps long sessions inside the provider context
+//! window by folding older messages into a pinned summary.
+//!
+//! When the messages after the current summary exceed the token budget, all
+//! but the most recent `CH_COMPACTION_KEEP_RECENT` are summarized (together
+//! with the previous summary) by a cheap local Ollama model. The summary is
+//! stored in `ch_session_summaries`; the messages themselves stay in
+//! `ch_messages` so the UI still shows the full thread. Session history sent
+//! to the model is then the summary followed by the uncovered messages.
+//!
+//! Environment (or `[compaction]` in the config file, `hydra_config.rs`):
+//! - `CH_COMPACTION_URL` — Ollama base URL (default: the `[ollama]` URL, else
+//!   `http://localhost:11434`)
+//! - `CH_COMPACTION_MODEL` — summarizer model (default `llama3.2:3b`)
+//! - `CH_COMPACTION_TOKEN_BUDGET` — estimated tokens that trigger compaction
+//!   (default 24000)
+//! - `CH_COMPACTION_KEEP_RECENT` — messages always kept verbatim (default 6)
+//! - `CH_COMPACTION_AUTO` — compact automatically after each stored
+//!   exchange (default `false`)
+//!
+//! - `GET    /api/sessions/{id}/compaction` — pinned summary + token estimate
+//! - `POST   /api/sessions/{id}/compact`    — compact now (`{ force? }`)
+//! - `DELETE /api/sessions/{id}/compaction` — drop the summary (full history again)
+//! - `GET    /api/sessions/{id}/context-usage?model=` — tokens in use vs. the
+//!   model's context window (last reported usage + newer messages, or the estimate)
+
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::parse_session_id;
+use crate::hydra_config::{Derived, HydraConfig, flag, number, text};
+use crate::state::AppState;
+
+const SUMMARY_TIMEOUT_SECS: u64 = 180;
+/// Per-message cap on what is fed to the summarizer.
+const MAX_MESSAGE_CHARS_FOR_SUMMARY: usize = 6_000;
+const SUMMARY_HEADER: &str = "[Summary of the earlier conversation]";
+const SUMMARY_ACK: &str = "Understood — continuing from the summary above.";
+
+#[derive(Debug, Clone)]
+pub struct CompactionConfig {
+    pub base_url: String,
+    pub model: String,
+    pub token_budget: usize,
+    pub keep_recent: usize,
+    pub auto: bool,
+}
+
+static CONFIG: Derived<Arc<CompactionConfig>> = Derived::new(build_config);
+
+fn build_config(config: &HydraConfig) -> Arc<CompactionConfig> {
+    let file = &config.compaction;
+    let cfg = CompactionConfig {
+        base_url: text("CH_COMPACTION_URL", file.url.as_deref())
+            .or_else(crate::hydra_config::ollama_url)
+            .unwrap_or_else(|| "http://localhost:11434".to_string())
+            .trim_end_matches('/')
+            .to_string(),
+        model: text("CH_COMPACTION_MODEL", file.model.as_deref())
+            .unwrap_or_else(|| "llama3.2:3b".to_string()),
+        token_budget: number("CH_COMPACTION_TOKEN_BUDGET", file.token_budget)
+            .unwrap_or(24_000)
+            .max(2_000),
+        keep_recent: number("CH_COMPACTION_KEEP_RECENT", file.keep_recent)
+            .unwrap_or(6)
+            .max(2),
+        auto: flag("CH_COMPACTION_AUTO", file.auto).unwrap_or(false),
+    };
+    if cfg.auto {
+        tracing::info!(
+            "compaction: auto mode, budget {} tokens, model {}",
+            cfg.token_budget,
+            cfg.model
+        );
+    }
+    Arc::new(cfg)
+}
+
+/// Compaction settings (env overrides the file).
+pub fn config() -> Arc<CompactionConfig> {
+    CONFIG.get()
+}
+
+/// Rough token estimate (~4 characters per token).
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SessionSummary {
+    pub summary: String,
+    /// Messages created at or before this instant are covered by the summary.
+    pub covers_until: DateTime<Utc>,
+    pub summarized_messages: i32,
+    pub tokens_before: i32,
+    pub tokens_after: i32,
+    pub model: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub async fn load_summary(db: &sqlx::PgPool, session_id: &uuid::Uuid) -> Option<SessionSummary> {
+    sqlx::query_as::<_, SessionSummary>(
+        "SELECT summary, covers_until, summarized_messages, tokens_before, tokens_after, \
+            model, updated_at \
+         FROM ch_session_summaries WHERE session_id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(db)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::warn!("compaction: failed to load summary: {}", e);
+        None
+    })
+}
+
+/// Estimated tokens of the messages stored after `since` (all when `None`).
+async fn tokens_since(
+    db: &sqlx::PgPool,
+    session_id: &uuid::Uuid,
+    since: Option<DateTime<Utc>>,
+) -> Result<usize, sqlx::Error> {
+    let tokens: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(SUM(CEIL(char_length(content) / 4.0)), 0)::BIGINT FROM ch_messages \
+         WHERE session_id = $1 AND ($2::TIMESTAMPTZ IS NULL OR created_at > $2)",
+    )
+    .bind(session_id)
+    .bind(since)
+    .fetch_one(db)
+    .await?;
+    Ok(tokens as usize)
+}
+
+/// Estimated tokens of the history sent with the next request: the pinned
+/// summary plus the messages it does not cover.
+pub async fn estimate_context_tokens(
+    db: &sqlx::PgPool,
+    session_id: &uuid::Uuid,
+) -> Result<usize, sqlx::Error> {
+    let summary = load_summary(db, session_id).await;
+    let uncovered = tokens_since(db, session_id, summary.as_ref().map(|s| s.covers_until)).await?;
+    Ok(uncovered + summary.as_ref().map_or(0, |s| estimate_tokens(&s.summary)))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContextUsage {
+    pub model: Option<String>,
+    pub used_tokens: usize,
+    pub context_window: u32,
+    pub remaining_tokens: usize,
+    pub percent: f64,
+    /// No provider-reported usage to build on.
+    pub estimated: bool,
+}
+
+fn context_usage_of(
+    model: Option<String>,
+    used_tokens: usize,
+    context_window: u32,
+    estimated: bool,
+) -> ContextUsage {
+    let percent = used_tokens as f64 * 100.0 / context_window.max(1) as f64;
+    ContextUsage {
+        model,
+        used_tokens,
+        context_window,
+        remaining_tokens: (context_window as usize).saturating_sub(used_tokens),
+        percent: (percent * 10.0).round() / 10.0,
+        estimated,
+    }
+}
+
+/// Context in use: the last reported request (prompt + completion) plus the
+/// messages stored after it; the estimate when nothing was reported or the
+/// session has been compacted since. `model` defaults to the session's latest.
+pub async fn context_usage(
+    db: &sqlx::PgPool,
+    session_id: &uuid::Uuid,
+    model: Option<String>,
+) -> Result<ContextUsage, sqlx::Error> {
+    let last: Option<(i32, Option<i32>, bool, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT prompt_tokens, completion_tokens, tokens_estimated, created_at FROM ch_messages \
+         WHERE session_id = $1 AND role = 'assistant' AND prompt_tokens IS NOT NULL \
+         ORDER BY created_at DESC LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(db)
+    .await?;
+    let model = match model.filter(|m| !m.trim().is_empty()) {
+        Some(model) => Some(model),
+        None => {
+            sqlx::query_scalar(
+                "SELECT model FROM ch_messages WHERE session_id = $1 AND model IS NOT NULL \
+             ORDER BY created_at DESC LIMIT 1",
+            )
+            .bind(session_id)
+            .fetch_optional(db)
+            .await?
+        }
+    };
+    let summary = load_summary(db, session_id).await;
+    let (used, estimated) = match last {
+        Some((prompt, completion, estimated, at))
+            if summary.as_ref().is_none_or(|s| s.updated_at < at) =>
+        {
+            let newer = tokens_since(db, session_id, Some(at)).await?;
+            (
+                prompt.max(0) as usize + completion.unwrap_or(0).max(0) as usize + newer,
+                estimated,
+            )
+        }
+        _ => (estimate_context_tokens(db, session_id).await?, true),
+    };
+    let window =
+        crate::ai_gateway::capabilities::context_window(model.as_deref().unwrap_or_default());
+    Ok(context_usage_of(model, used, window, estimated))
+}
+
+/// History prefix for a compacted session: the summary as a user turn plus
+/// an assistant acknowledgement, so roles keep alternating.
+pub fn summary_messages(summary: &str) -> [Value; 2] {
+    [
+        json!({ "role": "user", "content": format!("{}\n\n{}", SUMMARY_HEADER, summary) }),
+        json!({ "role": "assistant", "content": SUMMARY_ACK }),
+    ]
+}
+
+/// Index splitting `messages` into (to summarize, keep verbatim), or `None`
+/// when the conversation fits the budget or nothing old enough exists.
+fn split_point(
+    token_counts: &[usize],
+    budget: usize,
+    keep_recent: usize,
+    force: bool,
+) -> Option<usize> {
+    let total: usize = token_counts.iter().sum();
+    if !force && total <= budget {
+        return None;
+    }
+    let split = token_counts.len().saturating_sub(keep_recent);
+    (split > 0).then_some(split)
+}
+
+fn build_summary_prompt(previous: Option<&str>, messages: &[(String, String)]) -> String {
+    let mut prompt = String::from(
+        "Summarize the conversation below so it can replace the original messages \
+         in an AI assistant's context. Keep: the user's goals and constraints, decisions \
+         made, file paths, commands, code identifiers, errors and their fixes, and open \
+         tasks. Drop pleasantries and verbatim code unless essential. Write concise \
+         bullet points, at most 400 words, in the conversation's language.\n\n",
+    );
+    if let Some(previous) = previous {
+        prompt.push_str("Earlier summary:\n");
+        prompt.push_str(previous);
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str("Conversation:\n");
+    for (role, content) in messages {
+        let clipped: String = content
+            .chars()
+            .take(MAX_MESSAGE_CHARS_FOR_SUMMARY)
+            .collect();
+        prompt.push_str(&format!("\n[{}]\n{}\n", role, clipped));
+    }
+    prompt
+}
+
+async fn summarize(client: &reqwest::Client, prompt: &str) -> Result<String, String> {
+    let cfg = config();
+    let mut body = json!({
+        "model": cfg.model,
+        "stream": false,
+        "options": { "temperature": 0.2 },
+        "messages": [{ "role": "user", "content": prompt }],
+    });
+    crate::ollama::apply_keep_alive(&mut body);
+    let resp = client
+        .post(format!("{}/api/chat", cfg.base_url))
+        .timeout(Duration::from_secs(SUMMARY_TIMEOUT_SECS))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Summarizer request failed: {}", e))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!(
+            "Summarizer returned {}: {}",
+            status,
+            body.chars().take(300).collect::<String>()
+        ));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid summarizer response: {}", e))?;
+    body.get("message")
+        .and_then(|m| m.get("content"))
+        .and_then(|c| c.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| "Summarizer returned an empty summary".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompactionOutcome {
+    pub compacted: bool,
+    pub summarized_messages: usize,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+/// Compact `session_id` if it exceeds the budget (always when `force`).
+pub async fn compact_session(
+    state: &AppState,
+    session_id: uuid::Uuid,
+    force: bool,
+) -> Result<CompactionOutcome, String> {
+    let cfg = config();
+    let previous = load_summary(&state.db, &session_id).await;
+    let since = previous.as_ref().map(|s| s.covers_until);
+
+    let rows: Vec<(String, String, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT role, content, created_at FROM ch_messages \
+         WHERE session_id = $1 AND ($2::TIMESTAMPTZ IS NULL OR created_at > $2) \
+         ORDER BY created_at ASC",
+    )
+    .bind(session_id)
+    .bind(since)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load messages: {}", e))?;
+
+    let summary_tokens = previous.as_ref().map_or(0, |s| estimate_tokens(&s.summary));
+    let counts: Vec<usize> = rows.iter().map(|(_, c, _)| estimate_tokens(c)).collect();
+    let tokens_before = summary_tokens + counts.iter().sum::<usize>();
+    let budget = cfg.token_budget.saturating_sub(summary_tokens);
+    // The verbatim tail must start with a user turn so roles keep
+    // alternating after the summary / acknowledgement pair.
+    let split = split_point(&counts, budget, cfg.keep_recent, force).and_then(|mut split| {
+        while split > 0 && rows[split].0 != "user" {
+            split -= 1;
+        }
+        (split > 0).then_some(split)
+    });
+    let Some(split) = split else {
+        return Ok(CompactionOutcome {
+            compacted: false,
+            summarized_messages: 0,
+            tokens_before,
+            tokens_after: tokens_before,
+        });
+    };
+
+    let old: Vec<(String, String)> = rows[..split]
+        .iter()
+        .map(|(r, c, _)| (r.clone(), c.clone()))
+        .collect();
+    let covers_until = rows[split - 1].2;
+    let prompt = build_summary_prompt(previous.as_ref().map(|s| s.summary.as_str()), &old);
+    let summary = summarize(&state.http_client, &prompt).await?;
+    let tokens_after = estimate_tokens(&summary) + counts[split..].iter().sum::<usize>();
+    let summarized = split
+        + previous
+            .as_ref()
+            .map_or(0, |s| s.summarized_messages as usize);
+
+    sqlx::query(
+        "INSERT INTO ch_session_summaries \
+            (session_id, summary, covers_until, summarized_messages, tokens_before, tokens_after, model) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7) \
+         ON CONFLICT (session_id) DO UPDATE SET summary = EXCLUDED.summary, \
+            covers_until = EXCLUDED.covers_until, \
+            summarized_messages = EXCLUDED.summarized_messages, \
+            tokens_before = EXCLUDED.tokens_before, tokens_after = EXCLUDED.tokens_after, \
+            model = EXCLUDED.model, updated_at = NOW()",
+    )
+    .bind(session_id)
+    .bind(&summary)
+    .bind(covers_until)
+    .bind(summarized as i32)
+    .bind(tokens_before as i32)
+    .bind(tokens_after as i32)
+    .bind(&cfg.model)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store summary: {}", e))?;
+
+    tracing::info!(
+        "compaction: session {} — {} message(s) summarized, ~{} → ~{} tokens",
+        session_id,
+        split,
+        tokens_before,
+        tokens_after
+    );
+    Ok(CompactionOutcome {
+        compacted: true,
+        summarized_messages: split,
+        tokens_before,
+        tokens_after,
+    })
+}
+
+/// Sessions with a compaction in flight (auto mode must not stack runs).
+static IN_FLIGHT: LazyLock<Mutex<HashSet<uuid::Uuid>>> = LazyLock::new(Default::default);
+
+/// Auto mode hook — call after storing messages. No-op unless
+/// `CH_COMPACTION_AUTO` is set; runs in the background.
+pub fn schedule_auto(state: &AppState, session_id: uuid::Uuid) {
+    if !config().auto {
+        return;
+    }
+    if !IN_FLIGHT
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(session_id)
+    {
+        return;
+    }
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = compact_session(&state, session_id, false).await {
+            tracing::warn!("compaction: auto run for {} failed: {}", session_id, e);
+        }
+        IN_FLIGHT
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&session_id);
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/sessions/{id}/compaction`
+pub async fn get_compaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let summary = load_summary(&state.db, &session_id).await;
+    let since = summary.as_ref().map(|s| s.covers_until);
+    let uncovered = tokens_since(&state.db, &session_id, since)
+        .await
+        .map_err(|e| {
+            tracing::error!("compaction: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Database error" })),
+            )
+        })?;
+    let cfg = config();
+    Ok(Json(json!({
+        "session_id": id,
+        "summary": summary,
+        "estimated_tokens": uncovered + summary.as_ref().map_or(0, |s| estimate_tokens(&s.summary)),
+        "token_budget": cfg.token_budget,
+        "auto": cfg.auto,
+        "model": cfg.model,
+    })))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ContextUsageQuery {
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// `GET /api/sessions/{id}/context-usage`
+pub async fn get_context_usage(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<ContextUsageQuery>,
+) -> Result<Json<ContextUsage>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let mut usage = context_usage(&state.db, &session_id, query.model)
+        .await
+        .map_err(|e| {
+            tracing::error!("compaction: context usage: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Database error" })),
+            )
+        })?;
+    // Ollama windows come from the model's num_ctx, looked up on first use.
+    if let Some(model) = usage.model.clone().filter(|m| {
+        crate::ai_gateway::capabilities::provider_for_model_id(m)
+            == crate::ai_gateway::AiProvider::Ollama
+    }) && let Some(window) = crate::ollama_models::context_length(&state, &model).await
+    {
+        usage = context_usage_of(usage.model, usage.used_tokens, window, usage.estimated);
+    }
+    Ok(Json(usage))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CompactRequest {
+    /// Summarize even when under budget (everything but the recent messages).
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// `POST /api/sessions/{id}/compact`
+pub async fn compact(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    body: Option<Json<CompactRequest>>,
+) -> Result<Json<CompactionOutcome>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let force = body.map(|Json(b)| b.force).unwrap_or(false);
+    compact_session(&state, session_id, force)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))
+}
+
+/// `DELETE /api/sessions/{id}/compaction`
+pub async fn clear_compaction(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let result = sqlx::query("DELETE FROM ch_session_summaries WHERE session_id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("compaction: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": "Database error" })),
+            )
+        })?;
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No summary for this session" })),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_usage_reports_share_of_the_window() {
+        let usage = context_usage_of(Some("ollama/llama3.1:8b".into()), 6_144, 8_192, false);
+        assert_eq!(usage.percent, 75.0);
+        assert_eq!(usage.remaining_tokens, 2_048);
+
+        let over = context_usage_of(None, 9_000, 8_192, true);
+        assert_eq!(over.remaining_tokens, 0);
+        assert_eq!(over.percent, 109.9);
+    }
+
+    #[test]
+    fn split_only_over_budget_unless_forced() {
+        let counts = [1_000, 1_000, 1_000, 1_000];
+        assert_eq!(split_point(&counts, 5_000, 2, false), None);
+        assert_eq!(split_point(&counts, 3_000, 2, false), Some(2));
+        assert_eq!(split_point(&counts, 5_000, 2, true), Some(2));
+        // Nothing older than the kept tail.
+        assert_eq!(split_point(&counts, 100, 4, true), None);
+    }
+
+    #[test]
+    fn summary_prompt_includes_previous_summary_and_clips_messages() {
+        let long = "x".repeat(MAX_MESSAGE_CHARS_FOR_SUMMARY + 100);
+        let prompt = build_summary_prompt(
+            Some("- goal: port parser"),
+            &[
+                ("user".to_string(), long),
+                ("assistant".to_string(), "ok".to_string()),
+            ],
+        );
+        assert!(prompt.contains("Earlier summary:\n- goal: port parser"));
+        assert!(prompt.contains("[assistant]\nok"));
+        assert!(!prompt.contains(&"x".repeat(MAX_MESSAGE_CHARS_FOR_SUMMARY + 1)));
+    }
+
+    #[test]
+    fn summary_prefix_alternates_roles() {
+        let [user, assistant] = summary_messages("- done");
+        assert_eq!(user["role"], "user");
+        assert!(
+            user["content"]
+                .as_str()
+                .unwrap()
+                .starts_with(SUMMARY_HEADER)
+        );
+        assert_eq!(assistant["role"], "assistant");
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+    }
+}
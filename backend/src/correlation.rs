@@ -0,0 +1,98 @@
+//! Correlation ids — one id per prompt, carried through routing, queueing,
+//! execution and logs so a failure can be traced end to end.
+//!
+//! - HTTP: every request gets an id, taken from `X-Correlation-Id` when the
+//!   client sent a valid one (1-128 characters of `A-Z a-z 0-9 . _ : -`),
+//!   otherwise a new UUID. It is echoed in the response header (streaming
+//!   responses included), set on the request's tracing span, and returned in
+//!   `ChatResponse.correlation_id`.
+//! - Background prompts store the id of the request that enqueued them
+//!   (`correlation_id`, or the body field of the same name) and run under it;
+//!   their lifecycle events carry it.
+//! - WebSocket executions use their `execution_id` (sent in `start`), which
+//!   is also the prompt trace id.
+//!
+//! Application log entries (`app_log::record`) written while an id is in
+//! scope get it as `correlation_id`, so `GET /api/logs?correlation_id=<id>`
+//! lists everything one prompt did.
+
+use std::future::Future;
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+
+pub const HEADER: &str = "x-correlation-id";
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: String;
+}
+
+/// Id of the request or job the current task works for, if any.
+pub fn current() -> Option<String> {
+    CURRENT.try_with(Clone::clone).ok()
+}
+
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// `raw` trimmed, if it is usable as an id.
+pub fn accept(raw: &str) -> Option<&str> {
+    let id = raw.trim();
+    let valid = !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b':' | b'-'));
+    valid.then_some(id)
+}
+
+/// Run `fut` with `id` as the current correlation id.
+pub async fn scope<F: Future>(id: String, fut: F) -> F::Output {
+    let span = tracing::info_span!("correlation", correlation_id = %id);
+    CURRENT.scope(id, fut.instrument(span)).await
+}
+
+/// Middleware: assign the request's id and echo it in the response.
+pub async fn propagate(req: Request, next: Next) -> Response {
+    let id = req
+        .headers()
+        .get(HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(accept)
+        .map(str::to_string)
+        .unwrap_or_else(new_id);
+    let header = HeaderValue::from_str(&id).ok();
+    let mut response = scope(id, next.run(req)).await;
+    if let Some(value) = header {
+        response.headers_mut().insert(HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_ids_are_validated() {
+        assert_eq!(accept("  req-42:retry.1_a "), Some("req-42:retry.1_a"));
+        assert_eq!(accept(""), None);
+        assert_eq!(accept("has space"), None);
+        assert_eq!(accept("line\nbreak"), None);
+        assert_eq!(accept(&"x".repeat(MAX_LEN + 1)), None);
+        assert!(accept(&new_id()).is_some());
+    }
+
+    #[tokio::test]
+    async fn id_is_visible_inside_its_scope_only() {
+        assert_eq!(current(), None);
+        let inner = scope("abc".to_string(), async { current() }).await;
+        assert_eq!(inner.as_deref(), Some("abc"));
+        assert_eq!(current(), None);
+    }
+}
@@ -0,0 +1,339 @@
+//! Cost accounting — estimated tokens and cost per request, kept as daily
+//! aggregates per provider, model and session (tab) in `ch_cost_daily`.
+//!
+//! Every completed generation records one `CostEvent`: WebSocket chat and
+//! Claude CLI turns and session-bound background prompts (via
+//! `store_ws_messages`), unbound background prompts, HTTP streaming chat,
+//! the AI gateway proxy and compat endpoints (booked as `<provider>/<model>`),
+//! and the Ollama chat / batch endpoints. Token counts are the provider's
+//! when reported, otherwise ~4 characters per token (`estimated_requests`).
+//!
+//! Prices are per million tokens by model tier (`analytics::tier_pricing`,
+//! overridable through the rule update channel); local Ollama models cost
+//! nothing. Savings compare against the baseline model
+//! (`CH_COST_BASELINE_MODEL`, default `claude-sonnet-4-6`): what the same
+//! tokens would have cost there minus what they did cost.
+//!
+//! Daily and weekly caps per provider are enforced on these totals (see
+//! `budget.rs`); session-bound events also count towards the session's token
+//! quota (`tab_quota.rs`).
+//!
+//! - `GET /api/costs?range=30d` — `range` is `today` or `<n>d` (1-365):
+//!   totals, per-provider, per-model, per-session and daily breakdowns
+
+use std::sync::OnceLock;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use chrono::{Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::handlers::analytics::{model_tier, tier_pricing};
+use crate::queue::OLLAMA_PREFIX;
+use crate::state::AppState;
+
+const MAX_RANGE_DAYS: i64 = 365;
+const TOP_SESSIONS: i64 = 20;
+
+/// One completed request (or a batch of them).
+#[derive(Debug, Clone)]
+pub struct CostEvent {
+    /// Model id; local models carry the `ollama/` prefix.
+    pub model: String,
+    pub session_id: Option<uuid::Uuid>,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Token counts are estimates rather than provider-reported usage.
+    pub estimated: bool,
+}
+
+impl CostEvent {
+    /// A single request with tokens estimated from the prompt and output text.
+    pub fn estimate(
+        model: &str,
+        session_id: Option<uuid::Uuid>,
+        prompt: &str,
+        output: &str,
+    ) -> Self {
+        Self {
+            model: model.to_string(),
+            session_id,
+            requests: 1,
+            input_tokens: crate::compaction::estimate_tokens(prompt) as i64,
+            output_tokens: crate::compaction::estimate_tokens(output) as i64,
+            estimated: true,
+        }
+    }
+}
+
+fn baseline_model() -> &'static str {
+    static MODEL: OnceLock<String> = OnceLock::new();
+    MODEL.get_or_init(|| {
+        std::env::var("CH_COST_BASELINE_MODEL")
+            .ok()
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "claude-sonnet-4-6".to_string())
+    })
+}
+
+fn is_local(model: &str) -> bool {
+    model.starts_with(OLLAMA_PREFIX)
+}
+
+/// Provider a model is billed by: the `<provider>/` prefix of gateway
+/// bookings, else the provider the model id belongs to.
+pub fn provider_of(model: &str) -> &'static str {
+    if is_local(model) {
+        return "ollama";
+    }
+    let prefixed = model
+        .split_once('/')
+        .and_then(|(prefix, _)| crate::budget::PROVIDERS.iter().find(|p| **p == prefix));
+    match prefixed {
+        Some(provider) => provider,
+        None => crate::model_registry::provider_for_model(model),
+    }
+}
+
+/// Estimated cost in USD of `input` + `output` tokens on `model`.
+pub fn cost_usd(model: &str, input: i64, output: i64) -> f64 {
+    if is_local(model) {
+        return 0.0;
+    }
+    let (input_price, output_price) = tier_pricing(model_tier(model));
+    (input as f64 * input_price + output as f64 * output_price) / 1_000_000.0
+}
+
+/// Add `event` to today's aggregate row.
+pub async fn record(db: &sqlx::PgPool, event: &CostEvent) -> Result<(), sqlx::Error> {
+    let cost = cost_usd(&event.model, event.input_tokens, event.output_tokens);
+    let baseline = cost_usd(baseline_model(), event.input_tokens, event.output_tokens);
+    sqlx::query(
+        "INSERT INTO ch_cost_daily \
+             (day, provider, model, session_id, requests, estimated_requests, \
+              input_tokens, output_tokens, cost_usd, baseline_cost_usd) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10) \
+         ON CONFLICT (day, provider, model, \
+             (COALESCE(session_id, '00000000-0000-0000-0000-000000000000'::UUID))) \
+         DO UPDATE SET \
+             requests = ch_cost_daily.requests + EXCLUDED.requests, \
+             estimated_requests = ch_cost_daily.estimated_requests + EXCLUDED.estimated_requests, \
+             input_tokens = ch_cost_daily.input_tokens + EXCLUDED.input_tokens, \
+             output_tokens = ch_cost_daily.output_tokens + EXCLUDED.output_tokens, \
+             cost_usd = ch_cost_daily.cost_usd + EXCLUDED.cost_usd, \
+             baseline_cost_usd = ch_cost_daily.baseline_cost_usd + EXCLUDED.baseline_cost_usd, \
+             updated_at = NOW()",
+    )
+    .bind(Local::now().date_naive())
+    .bind(provider_of(&event.model))
+    .bind(&event.model)
+    .bind(event.session_id)
+    .bind(event.requests)
+    .bind(if event.estimated { event.requests } else { 0 })
+    .bind(event.input_tokens)
+    .bind(event.output_tokens)
+    .bind(cost)
+    .bind(baseline)
+    .execute(db)
+    .await?;
+    crate::budget::invalidate();
+    if let Some(session_id) = event.session_id {
+        crate::tab_quota::consume(db, session_id, event.input_tokens + event.output_tokens).await?;
+    }
+    Ok(())
+}
+
+/// Record `event` in the background; failures are logged.
+pub fn schedule(db: &sqlx::PgPool, event: CostEvent) {
+    if event.requests <= 0 {
+        return;
+    }
+    let db = db.clone();
+    tokio::spawn(async move {
+        if let Err(e) = record(&db, &event).await {
+            tracing::warn!("costs: failed to record {}: {}", event.model, e);
+        }
+    });
+}
+
+/// First day of `range` ending `today`: `today` or `<n>d`.
+fn range_start(range: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let days = match range.trim() {
+        "today" => 1,
+        raw => raw.strip_suffix('d')?.parse::<i64>().ok()?,
+    };
+    (1..=MAX_RANGE_DAYS)
+        .contains(&days)
+        .then(|| today - Duration::days(days - 1))
+}
+
+#[derive(Debug, Default, Serialize, sqlx::FromRow)]
+pub struct CostLine {
+    /// Provider, model, session id or day, depending on the breakdown.
+    pub key: String,
+    #[sqlx(default)]
+    pub title: Option<String>,
+    pub requests: i64,
+    pub estimated_requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost_usd: f64,
+    pub baseline_cost_usd: f64,
+    #[sqlx(skip)]
+    pub saved_usd: f64,
+}
+
+fn with_savings(mut lines: Vec<CostLine>) -> Vec<CostLine> {
+    for line in &mut lines {
+        line.saved_usd = line.baseline_cost_usd - line.cost_usd;
+    }
+    lines
+}
+
+const SUMS: &str = "SUM(requests)::BIGINT AS requests, \
+     SUM(estimated_requests)::BIGINT AS estimated_requests, \
+     SUM(input_tokens)::BIGINT AS input_tokens, \
+     SUM(output_tokens)::BIGINT AS output_tokens, \
+     SUM(cost_usd)::FLOAT8 AS cost_usd, \
+     SUM(baseline_cost_usd)::FLOAT8 AS baseline_cost_usd";
+
+async fn breakdown(
+    db: &sqlx::PgPool,
+    key: &str,
+    start: NaiveDate,
+    order: &str,
+) -> Result<Vec<CostLine>, sqlx::Error> {
+    let sql = format!(
+        "SELECT {key} AS key, {SUMS} FROM ch_cost_daily WHERE day >= $1 \
+         GROUP BY 1 ORDER BY {order}"
+    );
+    sqlx::query_as::<_, CostLine>(&sql)
+        .bind(start)
+        .fetch_all(db)
+        .await
+        .map(with_savings)
+}
+
+/// Cost report for `range` (see module docs).
+pub async fn cost_report(db: &sqlx::PgPool, range: &str) -> Result<Value, String> {
+    let today = Local::now().date_naive();
+    let start = range_start(range, today)
+        .ok_or_else(|| format!("range must be 'today' or 1-{}d", MAX_RANGE_DAYS))?;
+    let db_error = |e: sqlx::Error| format!("Cost query failed: {}", e);
+
+    let providers = breakdown(db, "provider", start, "cost_usd DESC, key")
+        .await
+        .map_err(db_error)?;
+    let models = breakdown(db, "model", start, "cost_usd DESC, key")
+        .await
+        .map_err(db_error)?;
+    let daily = breakdown(db, "day::TEXT", start, "key")
+        .await
+        .map_err(db_error)?;
+    let sessions = sqlx::query_as::<_, CostLine>(&format!(
+        "SELECT c.session_id::TEXT AS key, MAX(s.title) AS title, {SUMS} \
+         FROM ch_cost_daily c LEFT JOIN ch_sessions s ON s.id = c.session_id \
+         WHERE c.day >= $1 AND c.session_id IS NOT NULL \
+         GROUP BY c.session_id ORDER BY cost_usd DESC, requests DESC LIMIT $2"
+    ))
+    .bind(start)
+    .bind(TOP_SESSIONS)
+    .fetch_all(db)
+    .await
+    .map(with_savings)
+    .map_err(db_error)?;
+
+    let total = |f: fn(&CostLine) -> f64| providers.iter().map(f).sum::<f64>();
+    let requests: i64 = providers.iter().map(|p| p.requests).sum();
+    Ok(json!({
+        "range": range,
+        "from": start,
+        "to": today,
+        "baseline_model": baseline_model(),
+        "total": {
+            "requests": requests,
+            "cost_usd": total(|p| p.cost_usd),
+            "baseline_cost_usd": total(|p| p.baseline_cost_usd),
+            "saved_usd": total(|p| p.saved_usd),
+        },
+        "providers": providers,
+        "models": models,
+        "sessions": sessions,
+        "daily": daily,
+    }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct CostQuery {
+    #[serde(default)]
+    pub range: Option<String>,
+}
+
+/// `GET /api/costs`
+pub async fn get_costs(
+    State(state): State<AppState>,
+    Query(q): Query<CostQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let range = q.range.as_deref().unwrap_or("30d");
+    if range_start(range, Local::now().date_naive()).is_none() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "range must be 'today' or 1-365d" })),
+        ));
+    }
+    cost_report(&state.db, range).await.map(Json).map_err(|e| {
+        tracing::error!("costs: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to build cost report" })),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_models_are_free_and_cloud_models_priced_by_tier() {
+        assert_eq!(cost_usd("ollama/llama3.1:8b", 1_000_000, 1_000_000), 0.0);
+        assert_eq!(provider_of("ollama/qwen2.5-coder"), "ollama");
+        assert_eq!(provider_of("claude-opus-4-6"), "anthropic");
+        assert_eq!(provider_of("gemini-3.1-pro-preview"), "google");
+        assert_eq!(provider_of("groq/llama-3.3-70b-versatile"), "groq");
+        assert_eq!(provider_of("xai/grok-4"), "xai");
+        let (input, output) = tier_pricing("opus");
+        let cost = cost_usd("claude-opus-4-6", 2_000_000, 1_000_000);
+        assert!((cost - (2.0 * input + output)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranges_are_inclusive_day_windows() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        assert_eq!(range_start("today", today), Some(today));
+        assert_eq!(range_start("1d", today), Some(today));
+        assert_eq!(
+            range_start("7d", today),
+            NaiveDate::from_ymd_opt(2026, 10, 10)
+        );
+        assert_eq!(range_start("0d", today), None);
+        assert_eq!(range_start("366d", today), None);
+        assert_eq!(range_start("24h", today), None);
+    }
+
+    #[test]
+    fn estimated_events_count_characters() {
+        let event = CostEvent::estimate("claude-sonnet-4-6", None, "abcdefgh", "abcd");
+        assert_eq!((event.input_tokens, event.output_tokens), (2, 1));
+        assert!(event.estimated);
+        assert_eq!(event.requests, 1);
+    }
+}
@@ -0,0 +1,321 @@
+//! Text embeddings with pluggable backends — the foundation for semantic
+//! caching, RAG and similarity features that should not depend on a
+//! particular vendor.
+//!
+//! Backend selection via environment or `[embeddings]` in the config file
+//! (`hydra_config.rs`):
+//! - `CH_EMBEDDINGS_BACKEND` — `ollama` (default) or `openai` (any
+//!   OpenAI-compatible `/embeddings` endpoint).
+//! - `CH_EMBEDDINGS_URL` — base URL; defaults to the `[ollama]` URL
+//!   (`http://localhost:11434` when unset) for Ollama and
+//!   `https://api.openai.com/v1` for OpenAI-compatible.
+//! - `CH_EMBEDDINGS_MODEL` — default model (`nomic-embed-text` /
+//!   `text-embedding-3-small`); callers may override per request.
+//! - `CH_EMBEDDINGS_API_KEY` — bearer key for OpenAI-compatible backends
+//!   (falls back to `OPENAI_API_KEY`; env only).
+//!
+//! - `POST /api/embeddings` — `{ texts, model? }` → vectors + dimensionality
+
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::hydra_config::{Derived, HydraConfig, env, text};
+use crate::state::AppState;
+
+/// Upper bound on texts per call (Ollama embeds one text per request).
+const MAX_TEXTS: usize = 256;
+
+const EMBEDDINGS_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingBackendKind {
+    Ollama,
+    OpenAiCompatible,
+}
+
+#[derive(Debug, Clone)]
+pub struct EmbeddingBackend {
+    pub kind: EmbeddingBackendKind,
+    pub base_url: String,
+    pub default_model: String,
+    pub api_key: Option<String>,
+}
+
+impl EmbeddingBackend {
+    pub fn ollama(base_url: &str) -> Self {
+        Self {
+            kind: EmbeddingBackendKind::Ollama,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            default_model: "nomic-embed-text".to_string(),
+            api_key: None,
+        }
+    }
+
+    pub fn openai_compatible(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            kind: EmbeddingBackendKind::OpenAiCompatible,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            default_model: "text-embedding-3-small".to_string(),
+            api_key,
+        }
+    }
+
+    fn from_config(config: &HydraConfig) -> Self {
+        let file = &config.embeddings;
+        let url = text("CH_EMBEDDINGS_URL", file.url.as_deref());
+        let mut backend = match text("CH_EMBEDDINGS_BACKEND", file.backend.as_deref()).as_deref() {
+            Some("openai") => EmbeddingBackend::openai_compatible(
+                url.as_deref().unwrap_or("https://api.openai.com/v1"),
+                env("CH_EMBEDDINGS_API_KEY").or_else(|| env("OPENAI_API_KEY")),
+            ),
+            _ => EmbeddingBackend::ollama(
+                &url.or_else(crate::hydra_config::ollama_url)
+                    .unwrap_or_else(|| "http://localhost:11434".to_string()),
+            ),
+        };
+        if let Some(model) = text("CH_EMBEDDINGS_MODEL", file.model.as_deref()) {
+            backend.default_model = model;
+        }
+        tracing::info!(
+            "embeddings: {:?} backend at {} (model {})",
+            backend.kind,
+            backend.base_url,
+            backend.default_model
+        );
+        backend
+    }
+}
+
+static BACKEND: Derived<Arc<EmbeddingBackend>> =
+    Derived::new(|config| Arc::new(EmbeddingBackend::from_config(config)));
+
+/// Embedding backend from the config (env overrides the file).
+pub fn backend() -> Arc<EmbeddingBackend> {
+    BACKEND.get()
+}
+
+/// Vectors for a batch of texts, in input order.
+#[derive(Debug, Clone, Serialize)]
+pub struct Embeddings {
+    pub backend: EmbeddingBackendKind,
+    pub model: String,
+    pub dimensions: usize,
+    pub vectors: Vec<Vec<f32>>,
+}
+
+/// Embed `texts` with the configured backend (`model` overrides its default).
+pub async fn embed_texts(
+    client: &reqwest::Client,
+    texts: &[String],
+    model: Option<&str>,
+) -> Result<Embeddings, String> {
+    embed_texts_with(&backend(), client, texts, model).await
+}
+
+/// Embed `texts` with an explicit backend.
+pub async fn embed_texts_with(
+    backend: &EmbeddingBackend,
+    client: &reqwest::Client,
+    texts: &[String],
+    model: Option<&str>,
+) -> Result<Embeddings, String> {
+    if texts.is_empty() {
+        return Err("No texts to embed".to_string());
+    }
+    if texts.len() > MAX_TEXTS {
+        return Err(format!("Too many texts: {} (max {})", texts.len(), MAX_TEXTS));
+    }
+    let model = model.unwrap_or(&backend.default_model).to_string();
+
+    let vectors = match backend.kind {
+        EmbeddingBackendKind::Ollama => {
+            let mut vectors = Vec::with_capacity(texts.len());
+            for text in texts {
+                let body = post_json(
+                    client,
+                    backend,
+                    "/api/embeddings",
+                    json!({ "model": model, "prompt": text }),
+                )
+                .await?;
+                vectors.push(parse_ollama_embedding(&body)?);
+            }
+            vectors
+        }
+        EmbeddingBackendKind::OpenAiCompatible => {
+            let body = post_json(
+                client,
+                backend,
+                "/embeddings",
+                json!({ "model": model, "input": texts }),
+            )
+            .await?;
+            parse_openai_embeddings(&body, texts.len())?
+        }
+    };
+
+    let dimensions = vectors.first().map(|v| v.len()).unwrap_or(0);
+    if vectors.iter().any(|v| v.len() != dimensions) {
+        return Err("Backend returned vectors of differing dimensionality".to_string());
+    }
+
+    Ok(Embeddings {
+        backend: backend.kind,
+        model,
+        dimensions,
+        vectors,
+    })
+}
+
+async fn post_json(
+    client: &reqwest::Client,
+    backend: &EmbeddingBackend,
+    path: &str,
+    body: Value,
+) -> Result<Value, String> {
+    let mut req = client
+        .post(format!("{}{}", backend.base_url, path))
+        .timeout(std::time::Duration::from_secs(EMBEDDINGS_TIMEOUT_SECS))
+        .json(&body);
+    if let Some(key) = &backend.api_key {
+        req = req.bearer_auth(key);
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| format!("Embedding backend unreachable: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Embedding backend returned invalid JSON: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Embedding backend returned HTTP {}: {}", status.as_u16(), body));
+    }
+    Ok(body)
+}
+
+fn to_vector(value: &Value) -> Option<Vec<f32>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|x| x.as_f64().map(|f| f as f32))
+        .collect()
+}
+
+/// Ollama `/api/embeddings`: `{ "embedding": [...] }`.
+fn parse_ollama_embedding(body: &Value) -> Result<Vec<f32>, String> {
+    body.get("embedding")
+        .and_then(to_vector)
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| "Ollama response has no embedding".to_string())
+}
+
+/// OpenAI `/embeddings`: `{ "data": [{ "index": n, "embedding": [...] }] }`,
+/// reordered by `index`.
+fn parse_openai_embeddings(body: &Value, expected: usize) -> Result<Vec<Vec<f32>>, String> {
+    let data = body
+        .get("data")
+        .and_then(|d| d.as_array())
+        .ok_or("Embedding response has no data")?;
+    let mut indexed: Vec<(u64, Vec<f32>)> = data
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let index = item.get("index").and_then(|n| n.as_u64()).unwrap_or(i as u64);
+            item.get("embedding")
+                .and_then(to_vector)
+                .map(|v| (index, v))
+                .ok_or_else(|| format!("Embedding {} is missing or malformed", i))
+        })
+        .collect::<Result<_, _>>()?;
+    if indexed.len() != expected {
+        return Err(format!("Expected {} embeddings, got {}", expected, indexed.len()));
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+    Ok(indexed.into_iter().map(|(_, v)| v).collect())
+}
+
+/// Cosine similarity in `[-1, 1]`; `0.0` for mismatched or zero vectors.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  POST /api/embeddings
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct EmbedRequest {
+    pub texts: Vec<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+pub async fn embed(
+    State(state): State<AppState>,
+    Json(req): Json<EmbedRequest>,
+) -> Result<Json<Embeddings>, (StatusCode, Json<Value>)> {
+    if req.texts.is_empty() || req.texts.len() > MAX_TEXTS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("texts must contain 1..={} items", MAX_TEXTS) })),
+        ));
+    }
+    embed_texts(&state.http_client, &req.texts, req.model.as_deref())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            tracing::warn!("embeddings: {}", e);
+            (StatusCode::BAD_GATEWAY, Json(json!({ "error": e })))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ollama_embedding() {
+        let body = json!({ "embedding": [0.5, -0.25, 1.0] });
+        assert_eq!(parse_ollama_embedding(&body).unwrap(), vec![0.5, -0.25, 1.0]);
+        assert!(parse_ollama_embedding(&json!({ "embedding": [] })).is_err());
+    }
+
+    #[test]
+    fn openai_embeddings_reordered_by_index() {
+        let body = json!({ "data": [
+            { "index": 1, "embedding": [0.0, 1.0] },
+            { "index": 0, "embedding": [1.0, 0.0] },
+        ]});
+        let vectors = parse_openai_embeddings(&body, 2).unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(parse_openai_embeddings(&body, 3).is_err());
+    }
+
+    #[test]
+    fn cosine_similarity_bounds() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
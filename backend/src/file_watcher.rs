@@ -0,0 +1,689 @@
+//! File watcher — detects on-disk changes to files that sessions or queued
+//! prompts depend on and records them as external-change conflicts.
+//!
+//! Watched files are refreshed every `REFRESH_INTERVAL` from:
+//! - each session's `pinned_files` (relative to its working directory) that
+//!   lie inside the allowed directories (see `presets::pinned_path`)
+//! - `affected_files` of queued and running background prompts (relative to
+//!   the global working directory); directory and glob entries are expanded
+//!   to the files they currently match (up to `MAX_PATTERN_MATCHES` each)
+//!
+//! The parent directories of those files are watched non-recursively (so
+//! editors that save via rename are caught) and events for other files are
+//! ignored. Changes to the same file within `DEBOUNCE` are reported once.
+//! Each change becomes an `ExternalChange` in an in-memory log (last
+//! `MAX_CONFLICTS`), is pushed as a `conflict-detected` SSE event and is
+//! recorded in the audit trail (see `audit_trail.rs`).
+//!
+//! When a file is first watched its content (text files up to
+//! `MAX_BASELINE_BYTES`) is kept as the baseline — the version the session or
+//! prompt was working from. A conflict can then be resolved with:
+//! - `show_diff`   — unified diff from the baseline to the on-disk content
+//! - `keep_mine`   — write the baseline back to disk
+//! - `keep_theirs` — accept the on-disk content as the new baseline
+//!
+//! Resolutions are recorded on the conflict and stay in the log.
+//!
+//! `watch_tree` lends the same machinery to other modules: a recursive watch
+//! whose changed paths go to the caller (RAG reindexing, see `rag.rs`).
+//!
+//! Set `CH_FILE_WATCHER=0` to disable.
+//!
+//! - `GET    /api/conflicts`              — recent external changes (newest first)
+//! - `GET    /api/conflicts/events`       — SSE: `conflict-detected`
+//! - `POST   /api/conflicts/{id}/resolve` — `{ "strategy": "keep_mine" | "keep_theirs" | "show_diff" }`
+//! - `DELETE /api/conflicts/{id}`         — dismiss one
+//! - `DELETE /api/conflicts`              — dismiss all
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::Path as UrlPath;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use chrono::{DateTime, Utc};
+use futures_util::stream::Stream;
+use notify::{EventKind, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::{broadcast, mpsc};
+
+use crate::state::AppState;
+
+pub const JOB_FILE_WATCHER: &str = "file_watcher";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const DEBOUNCE: Duration = Duration::from_secs(1);
+const MAX_CONFLICTS: usize = 200;
+const MAX_WATCHED_FILES: usize = 2_000;
+const MAX_PATTERN_MATCHES: usize = 200;
+const MAX_BASELINE_BYTES: u64 = 512 * 1024;
+/// Events for a file we just wrote ourselves (`keep_mine`) are ignored this long.
+const SELF_WRITE_GRACE: Duration = Duration::from_secs(2);
+
+/// A watched file changed on disk.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalChange {
+    pub id: u64,
+    pub path: String,
+    /// `modified`, `created` or `removed`
+    pub kind: &'static str,
+    pub detected_at: DateTime<Utc>,
+    /// Sessions that pinned the file.
+    pub session_ids: Vec<String>,
+    /// Queued or running background prompts expected to touch the file.
+    pub prompt_ids: Vec<i64>,
+    /// Whether a baseline exists, i.e. `keep_mine` / `show_diff` are possible.
+    pub has_baseline: bool,
+    pub resolution: Option<Resolution>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Resolution {
+    /// `keep_mine` or `keep_theirs`
+    pub strategy: &'static str,
+    pub resolved_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Watchers {
+    session_ids: Vec<String>,
+    prompt_ids: Vec<i64>,
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static CONFLICTS: LazyLock<Mutex<VecDeque<ExternalChange>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+static EVENTS: LazyLock<broadcast::Sender<ExternalChange>> =
+    LazyLock::new(|| broadcast::channel(64).0);
+/// Content of each watched file when it was first watched (or last accepted).
+static BASELINES: LazyLock<Mutex<HashMap<PathBuf, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static SELF_WRITES: LazyLock<Mutex<HashMap<PathBuf, Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn enabled() -> bool {
+    std::env::var("CH_FILE_WATCHER")
+        .map(|v| !matches!(v.trim(), "0" | "false" | "off"))
+        .unwrap_or(true)
+}
+
+/// Absolute path for a file listed relative to `base` (absolute paths pass through).
+fn resolve(base: &str, file: &str) -> Option<PathBuf> {
+    let path = Path::new(file);
+    if path.is_absolute() {
+        Some(path.to_path_buf())
+    } else if base.is_empty() {
+        None
+    } else {
+        Some(Path::new(base).join(file))
+    }
+}
+
+/// Files matched by an `affected_files` entry, which may be a pattern.
+fn expand(base: &str, entry: &str) -> Vec<PathBuf> {
+    let Some(path) = resolve(base, entry) else {
+        return Vec::new();
+    };
+    if !crate::affected_files::is_pattern(entry) {
+        return vec![path];
+    }
+    match glob::glob(&path.to_string_lossy()) {
+        Ok(paths) => paths
+            .filter_map(Result::ok)
+            .filter(|p| p.is_file())
+            .take(MAX_PATTERN_MATCHES)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Files to watch, with who depends on each.
+async fn watched_files(state: &AppState) -> Result<HashMap<PathBuf, Watchers>, sqlx::Error> {
+    let mut files: HashMap<PathBuf, Watchers> = HashMap::new();
+
+    let sessions: Vec<(String, String, Vec<String>)> = sqlx::query_as(
+        "SELECT s.id::TEXT, COALESCE(NULLIF(s.working_directory, ''), g.working_directory, ''), \
+                s.pinned_files \
+         FROM ch_sessions s LEFT JOIN ch_settings g ON g.id = 1 \
+         WHERE cardinality(s.pinned_files) > 0",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    for (session_id, wd, pinned) in sessions {
+        // Only pinned files a session may read get watched (and baselined).
+        for path in pinned
+            .iter()
+            .filter_map(|f| crate::presets::pinned_path(&wd, f).ok())
+        {
+            files
+                .entry(path)
+                .or_default()
+                .session_ids
+                .push(session_id.clone());
+        }
+    }
+
+    let global_wd: String =
+        sqlx::query_scalar("SELECT COALESCE(working_directory, '') FROM ch_settings WHERE id = 1")
+            .fetch_optional(&state.db)
+            .await?
+            .unwrap_or_default();
+    let prompts: Vec<(i64, Vec<String>)> = sqlx::query_as(
+        "SELECT id, affected_files FROM ch_background_prompts \
+         WHERE status IN ('queued', 'running') AND cardinality(affected_files) > 0",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    for (prompt_id, affected) in prompts {
+        for path in affected.iter().flat_map(|f| expand(&global_wd, f)) {
+            files.entry(path).or_default().prompt_ids.push(prompt_id);
+        }
+    }
+
+    if files.len() > MAX_WATCHED_FILES {
+        tracing::warn!(
+            "file_watcher: {} files requested, watching the first {}",
+            files.len(),
+            MAX_WATCHED_FILES
+        );
+        files = files.into_iter().take(MAX_WATCHED_FILES).collect();
+    }
+    Ok(files)
+}
+
+fn change_kind(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("created"),
+        EventKind::Modify(_) => Some("modified"),
+        EventKind::Remove(_) => Some("removed"),
+        _ => None,
+    }
+}
+
+/// Text content of `path`, or `None` for missing, large or binary files.
+fn read_baseline(path: &Path) -> Option<String> {
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_BASELINE_BYTES {
+        return None;
+    }
+    String::from_utf8(std::fs::read(path).ok()?).ok()
+}
+
+/// Take baselines for newly watched files and drop those no longer watched.
+fn sync_baselines(files: &HashMap<PathBuf, Watchers>) {
+    let known: HashSet<PathBuf> = {
+        let mut baselines = BASELINES.lock().unwrap_or_else(|e| e.into_inner());
+        baselines.retain(|path, _| files.contains_key(path));
+        baselines.keys().cloned().collect()
+    };
+    let fresh: Vec<(PathBuf, String)> = files
+        .keys()
+        .filter(|path| !known.contains(*path))
+        .filter_map(|path| read_baseline(path).map(|text| (path.clone(), text)))
+        .collect();
+    BASELINES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .extend(fresh);
+}
+
+fn is_self_write(path: &Path) -> bool {
+    let mut writes = SELF_WRITES.lock().unwrap_or_else(|e| e.into_inner());
+    writes.retain(|_, t| t.elapsed() < SELF_WRITE_GRACE);
+    writes.contains_key(path)
+}
+
+/// Unified diff from `mine` to `theirs`, labelled with `path`.
+fn unified_diff(path: &str, mine: &str, theirs: &str) -> String {
+    similar::TextDiff::from_lines(mine, theirs)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{} (mine)", path), &format!("{} (on disk)", path))
+        .to_string()
+}
+
+fn record(path: &Path, kind: &'static str, watchers: &Watchers) {
+    let has_baseline = BASELINES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(path);
+    let change = ExternalChange {
+        id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+        path: path.to_string_lossy().into_owned(),
+        kind,
+        detected_at: Utc::now(),
+        session_ids: watchers.session_ids.clone(),
+        prompt_ids: watchers.prompt_ids.clone(),
+        has_baseline,
+        resolution: None,
+    };
+    tracing::info!(path = %change.path, kind, "file_watcher: external change");
+    crate::audit_trail::file_change(
+        "file_watcher",
+        &change.path,
+        kind,
+        crate::audit_trail::Attribution {
+            session_ids: change.session_ids.clone(),
+            prompt_ids: change.prompt_ids.clone(),
+        },
+    );
+    let mut log = CONFLICTS.lock().unwrap_or_else(|e| e.into_inner());
+    log.push_front(change.clone());
+    log.truncate(MAX_CONFLICTS);
+    drop(log);
+    // No subscribers is fine — the log keeps it.
+    let _ = EVENTS.send(change);
+}
+
+/// Spawn the watcher loop (no-op when `CH_FILE_WATCHER=0`).
+pub fn spawn(state: AppState) {
+    if !enabled() {
+        tracing::info!("file_watcher: disabled");
+        return;
+    }
+    let (tx, mut rx) = mpsc::unbounded_channel::<notify::Event>();
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                let _ = tx.send(event);
+            }
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                tracing::warn!("file_watcher: cannot start: {}", e);
+                return;
+            }
+        };
+
+    state.job_schedule.start(JOB_FILE_WATCHER, REFRESH_INTERVAL);
+    tokio::spawn(async move {
+        let mut files: HashMap<PathBuf, Watchers> = HashMap::new();
+        let mut dirs: HashSet<PathBuf> = HashSet::new();
+        let mut last_seen: HashMap<PathBuf, Instant> = HashMap::new();
+        let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = refresh.tick() => {
+                    state.job_schedule.record_run(JOB_FILE_WATCHER);
+                    match watched_files(&state).await {
+                        Ok(next) => files = next,
+                        Err(e) => {
+                            tracing::warn!("file_watcher: refresh failed: {}", e);
+                            continue;
+                        }
+                    }
+                    let wanted: HashSet<PathBuf> = files
+                        .keys()
+                        .filter_map(|f| f.parent().map(Path::to_path_buf))
+                        .filter(|d| d.is_dir())
+                        .collect();
+                    for dir in dirs.difference(&wanted) {
+                        let _ = watcher.unwatch(dir);
+                    }
+                    for dir in wanted.difference(&dirs) {
+                        if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                            tracing::debug!("file_watcher: cannot watch {}: {}", dir.display(), e);
+                        }
+                    }
+                    dirs = wanted;
+                    sync_baselines(&files);
+                    last_seen.retain(|_, t| t.elapsed() < DEBOUNCE);
+                }
+                Some(event) = rx.recv() => {
+                    let Some(kind) = change_kind(&event.kind) else {
+                        continue;
+                    };
+                    for path in &event.paths {
+                        let Some(watchers) = files.get(path) else {
+                            continue;
+                        };
+                        if last_seen.get(path).is_some_and(|t| t.elapsed() < DEBOUNCE)
+                            || is_self_write(path)
+                        {
+                            continue;
+                        }
+                        last_seen.insert(path.clone(), Instant::now());
+                        record(path, kind, watchers);
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// A recursive watch on a directory tree; changed paths arrive on `changes`.
+/// Dropping it stops the watch.
+pub struct TreeWatch {
+    _watcher: notify::RecommendedWatcher,
+    pub changes: mpsc::UnboundedReceiver<PathBuf>,
+}
+
+/// Watch `root` recursively. `None` when `CH_FILE_WATCHER=0` or the watch
+/// cannot be set up — callers fall back to polling.
+pub fn watch_tree(root: &Path) -> Option<TreeWatch> {
+    if !enabled() {
+        return None;
+    }
+    let (tx, changes) = mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res
+                && change_kind(&event.kind).is_some()
+            {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                tracing::warn!("file_watcher: cannot watch {}: {}", root.display(), e);
+                return None;
+            }
+        };
+    if let Err(e) = watcher.watch(root, RecursiveMode::Recursive) {
+        tracing::warn!("file_watcher: cannot watch {}: {}", root.display(), e);
+        return None;
+    }
+    Some(TreeWatch {
+        _watcher: watcher,
+        changes,
+    })
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/conflicts`
+pub async fn list_conflicts() -> Json<Value> {
+    let log = CONFLICTS.lock().unwrap_or_else(|e| e.into_inner());
+    Json(json!({ "conflicts": log.iter().collect::<Vec<_>>() }))
+}
+
+/// `GET /api/conflicts/events` — SSE stream of `conflict-detected`.
+pub async fn conflict_events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut rx = EVENTS.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(change) => {
+                    if let Ok(event) = Event::default().event("conflict-detected").json_data(&change) {
+                        yield Ok(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::new())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResolveRequest {
+    pub strategy: String,
+}
+
+/// `POST /api/conflicts/{id}/resolve`
+pub async fn resolve_conflict(
+    UrlPath(id): UrlPath<u64>,
+    Json(body): Json<ResolveRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let strategy: &'static str = match body.strategy.as_str() {
+        "keep_mine" => "keep_mine",
+        "keep_theirs" => "keep_theirs",
+        "show_diff" => "show_diff",
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!(
+                        "Unknown strategy '{}' (expected keep_mine, keep_theirs or show_diff)",
+                        other
+                    )
+                })),
+            ));
+        }
+    };
+    let path = CONFLICTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|c| c.id == id)
+        .map(|c| c.path.clone())
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Conflict not found" })),
+            )
+        })?;
+    let file = PathBuf::from(&path);
+    let mine = BASELINES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&file)
+        .cloned();
+    // A removed file diffs against empty content.
+    let theirs = match tokio::fs::read(&file).await {
+        Ok(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": format!("Cannot read {}: {}", path, e) })),
+            ));
+        }
+    };
+    let no_baseline = || {
+        (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "No baseline content for this file" })),
+        )
+    };
+
+    match strategy {
+        "show_diff" => {
+            let mine = mine.ok_or_else(no_baseline)?;
+            let diff = unified_diff(&path, &mine, theirs.as_deref().unwrap_or(""));
+            return Ok(Json(
+                json!({ "id": id, "strategy": strategy, "diff": diff }),
+            ));
+        }
+        "keep_mine" => {
+            let mine = mine.ok_or_else(no_baseline)?;
+            if crate::tools::is_blocked_for_write(
+                &file,
+                crate::tools::DEFAULT_BLOCKED_WRITE_PREFIXES,
+            ) {
+                return Err((
+                    StatusCode::FORBIDDEN,
+                    Json(json!({ "error": format!("Writing {} is not allowed", path) })),
+                ));
+            }
+            SELF_WRITES
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(file.clone(), Instant::now());
+            if let Err(e) = tokio::fs::write(&file, mine).await {
+                return Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": format!("Cannot write {}: {}", path, e) })),
+                ));
+            }
+        }
+        _ => {
+            let mut baselines = BASELINES.lock().unwrap_or_else(|e| e.into_inner());
+            match theirs {
+                Some(text) => baselines.insert(file.clone(), text),
+                None => baselines.remove(&file),
+            };
+        }
+    }
+
+    let resolution = Resolution {
+        strategy,
+        resolved_at: Utc::now(),
+    };
+    let mut log = CONFLICTS.lock().unwrap_or_else(|e| e.into_inner());
+    // Every open change to this file is settled by the same decision.
+    for change in log
+        .iter_mut()
+        .filter(|c| c.path == path && c.resolution.is_none())
+    {
+        change.resolution = Some(resolution.clone());
+    }
+    tracing::info!(path = %path, strategy, "file_watcher: conflict resolved");
+    Ok(Json(
+        json!({ "id": id, "strategy": strategy, "resolution": resolution }),
+    ))
+}
+
+/// `DELETE /api/conflicts/{id}`
+pub async fn dismiss_conflict(UrlPath(id): UrlPath<u64>) -> StatusCode {
+    let mut log = CONFLICTS.lock().unwrap_or_else(|e| e.into_inner());
+    let before = log.len();
+    log.retain(|c| c.id != id);
+    if log.len() < before {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// `DELETE /api/conflicts`
+pub async fn clear_conflicts() -> StatusCode {
+    CONFLICTS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    StatusCode::NO_CONTENT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_files_resolve_against_their_base() {
+        assert_eq!(
+            resolve("/work/repo", "src/main.rs"),
+            Some(PathBuf::from("/work/repo/src/main.rs"))
+        );
+        assert_eq!(
+            resolve("/work/repo", "/etc/hosts"),
+            Some(PathBuf::from("/etc/hosts"))
+        );
+        assert_eq!(resolve("", "src/main.rs"), None);
+    }
+
+    #[test]
+    fn only_content_changes_are_reported() {
+        use notify::event::{AccessKind, CreateKind, ModifyKind, RemoveKind};
+        assert_eq!(
+            change_kind(&EventKind::Modify(ModifyKind::Any)),
+            Some("modified")
+        );
+        assert_eq!(
+            change_kind(&EventKind::Create(CreateKind::File)),
+            Some("created")
+        );
+        assert_eq!(
+            change_kind(&EventKind::Remove(RemoveKind::File)),
+            Some("removed")
+        );
+        assert_eq!(change_kind(&EventKind::Access(AccessKind::Any)), None);
+    }
+
+    #[tokio::test]
+    async fn recorded_changes_are_logged_and_dismissable() {
+        let watchers = Watchers {
+            session_ids: vec!["s1".to_string()],
+            prompt_ids: vec![7],
+        };
+        record(Path::new("/tmp/ch-watch-test.rs"), "modified", &watchers);
+        let id = {
+            let log = CONFLICTS.lock().unwrap();
+            let change = log
+                .iter()
+                .find(|c| c.path == "/tmp/ch-watch-test.rs")
+                .expect("logged");
+            assert_eq!(change.prompt_ids, vec![7]);
+            change.id
+        };
+        assert_eq!(dismiss_conflict(UrlPath(id)).await, StatusCode::NO_CONTENT);
+        assert_eq!(dismiss_conflict(UrlPath(id)).await, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn diff_goes_from_baseline_to_disk() {
+        let diff = unified_diff("a.rs", "one\ntwo\nthree\n", "one\n2\nthree\n");
+        assert!(diff.starts_with("--- a.rs (mine)\n+++ a.rs (on disk)\n"));
+        assert!(diff.contains("-two\n+2\n"));
+        assert!(unified_diff("a.rs", "same\n", "same\n").is_empty());
+    }
+
+    #[tokio::test]
+    async fn keep_mine_restores_the_baseline() {
+        let file = std::env::temp_dir().join(format!("ch-resolve-{}.txt", std::process::id()));
+        std::fs::write(&file, "mine\n").unwrap();
+        let files = HashMap::from([(file.clone(), Watchers::default())]);
+        sync_baselines(&files);
+        std::fs::write(&file, "theirs\n").unwrap();
+        record(&file, "modified", &Watchers::default());
+        let id = CONFLICTS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.path == file.to_string_lossy())
+            .map(|c| c.id)
+            .expect("logged");
+
+        let request = |strategy: &str| {
+            Json(ResolveRequest {
+                strategy: strategy.to_string(),
+            })
+        };
+        let Json(diff) = resolve_conflict(UrlPath(id), request("show_diff"))
+            .await
+            .unwrap();
+        assert!(diff["diff"].as_str().unwrap().contains("-mine\n+theirs\n"));
+        assert!(
+            resolve_conflict(UrlPath(id), request("merge"))
+                .await
+                .is_err()
+        );
+
+        resolve_conflict(UrlPath(id), request("keep_mine"))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "mine\n");
+        assert!(is_self_write(&file));
+        let resolved = CONFLICTS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|c| c.id == id)
+            .and_then(|c| c.resolution.clone())
+            .expect("resolution recorded");
+        assert_eq!(resolved.strategy, "keep_mine");
+        std::fs::remove_file(&file).ok();
+    }
+
+    #[test]
+    fn pattern_entries_expand_to_files() {
+        let dir = std::env::temp_dir().join(format!("ch-watch-glob-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src/session")).unwrap();
+        std::fs::write(dir.join("src/session/a.rs"), "").unwrap();
+        let base = dir.to_string_lossy();
+        assert_eq!(
+            expand(&base, "src/session/**"),
+            vec![dir.join("src/session/a.rs")]
+        );
+        assert_eq!(expand(&base, "src/new.rs"), vec![dir.join("src/new.rs")]);
+        assert!(expand("", "src/**").is_empty());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
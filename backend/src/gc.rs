@@ -0,0 +1,351 @@
+//! Garbage collection for persisted stores (sessions, traces, caches, job
+//! records) that would otherwise grow forever.
+//!
+//! Each store has a policy in `ch_gc_policies` with up to three limits; a row
+//! is collected when it breaks ANY of them:
+//! - `max_age_days` — older than N days
+//! - `max_count` — beyond the newest N rows
+//! - `max_bytes` — beyond the newest rows totalling N bytes (row size as
+//!   reported by `pg_column_size`)
+//!
+//! Job-like stores only ever collect finished rows. Deleting a session
+//! cascades to its messages and tags.
+//!
+//! Every policy ships disabled, including ones added by later migrations. The scheduled
+//! maintenance job runs every enabled policy every 6 hours.
+//! `run_gc` defaults to a dry run that only reports what would be deleted.
+//!
+//! - `GET  /api/gc/policies` — all stores with their policy
+//! - `PUT  /api/gc/policies/{store}` — `{ enabled, max_age_days?, max_count?, max_bytes? }`
+//! - `POST /api/gc/run` — `{ dry_run? = true, stores? }` → per-store report
+
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::state::AppState;
+
+pub const JOB_GC: &str = "gc";
+pub const GC_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// A collectable store. Table and column names are static, never user input.
+struct StoreDef {
+    id: &'static str,
+    table: &'static str,
+    timestamp: &'static str,
+    /// Rows that may be collected at all (e.g. only finished jobs).
+    filter: &'static str,
+}
+
+const STORES: &[StoreDef] = &[
+    StoreDef { id: "sessions", table: "ch_sessions", timestamp: "updated_at", filter: "TRUE" },
+    StoreDef { id: "prompt_traces", table: "ch_prompt_traces", timestamp: "created_at", filter: "TRUE" },
+    StoreDef { id: "hook_runs", table: "ch_hook_runs", timestamp: "created_at", filter: "TRUE" },
+    StoreDef {
+        id: "background_prompts",
+        table: "ch_background_prompts",
+        timestamp: "created_at",
+        filter: "status IN ('done', 'failed', 'cancelled')",
+    },
+    StoreDef { id: "ocr_history", table: "ch_ocr_history", timestamp: "created_at", filter: "TRUE" },
+    StoreDef { id: "agent_usage", table: "ch_agent_usage", timestamp: "created_at", filter: "TRUE" },
+    StoreDef { id: "tool_interactions", table: "ch_tool_interactions", timestamp: "executed_at", filter: "TRUE" },
+    StoreDef { id: "a2a_tasks", table: "ch_a2a_tasks", timestamp: "created_at", filter: "completed_at IS NOT NULL" },
+    StoreDef { id: "swarm_tasks", table: "ch_swarm_tasks", timestamp: "created_at", filter: "completed_at IS NOT NULL" },
+    StoreDef { id: "sandbox_executions", table: "ch_sandbox_executions", timestamp: "executed_at", filter: "TRUE" },
+    StoreDef { id: "compression_stats", table: "ch_compression_stats", timestamp: "compressed_at", filter: "TRUE" },
+    StoreDef { id: "web_vitals", table: "ch_web_vitals", timestamp: "created_at", filter: "TRUE" },
+    StoreDef { id: "metric_samples", table: "ch_metric_samples", timestamp: "created_at", filter: "TRUE" },
+    StoreDef { id: "swarm_plans", table: "ch_swarm_plans", timestamp: "created_at", filter: "status <> 'pending'" },
+];
+
+fn store_def(id: &str) -> Option<&'static StoreDef> {
+    STORES.iter().find(|s| s.id == id)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct GcPolicy {
+    #[serde(default)]
+    pub store: String,
+    pub enabled: bool,
+    #[serde(default)]
+    pub max_age_days: Option<i32>,
+    #[serde(default)]
+    pub max_count: Option<i64>,
+    #[serde(default)]
+    pub max_bytes: Option<i64>,
+}
+
+impl GcPolicy {
+    fn has_limits(&self) -> bool {
+        self.max_age_days.is_some() || self.max_count.is_some() || self.max_bytes.is_some()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.max_age_days.is_some_and(|v| v <= 0)
+            || self.max_count.is_some_and(|v| v <= 0)
+            || self.max_bytes.is_some_and(|v| v <= 0)
+        {
+            return Err("Limits must be positive (omit a limit to disable it)".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// What a policy matched (dry run) or removed.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreReport {
+    pub store: String,
+    pub rows: i64,
+    pub bytes: i64,
+    pub oldest: Option<DateTime<Utc>>,
+    pub newest: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GcReport {
+    pub dry_run: bool,
+    pub stores: Vec<StoreReport>,
+    pub total_rows: i64,
+    pub total_bytes: i64,
+    pub duration_ms: u64,
+}
+
+/// Rows of `store` that break `$1` age / `$2` count / `$3` bytes, newest first.
+fn candidates_sql(store: &StoreDef) -> String {
+    format!(
+        "WITH ranked AS ( \
+            SELECT ctid AS rid, {ts} AS ts, pg_column_size(t.*)::BIGINT AS bytes, \
+                ROW_NUMBER() OVER (ORDER BY {ts} DESC) AS rn, \
+                SUM(pg_column_size(t.*)) OVER (ORDER BY {ts} DESC ROWS UNBOUNDED PRECEDING) AS cum_bytes \
+            FROM {table} t WHERE {filter} \
+         ), doomed AS ( \
+            SELECT rid, ts, bytes FROM ranked \
+            WHERE ($1::INT IS NOT NULL AND ts < NOW() - make_interval(days => $1::INT)) \
+               OR ($2::BIGINT IS NOT NULL AND rn > $2::BIGINT) \
+               OR ($3::BIGINT IS NOT NULL AND cum_bytes > $3::BIGINT) \
+         )",
+        ts = store.timestamp,
+        table = store.table,
+        filter = store.filter,
+    )
+}
+
+async fn collect_store(
+    state: &AppState,
+    store: &StoreDef,
+    policy: &GcPolicy,
+    dry_run: bool,
+) -> Result<StoreReport, String> {
+    // A data-modifying CTE always runs to completion, and `doomed` is read
+    // from the same snapshot, so the report matches what was deleted.
+    let delete = if dry_run {
+        String::new()
+    } else {
+        format!(
+            ", deleted AS (DELETE FROM {} WHERE ctid IN (SELECT rid FROM doomed))",
+            store.table
+        )
+    };
+    let sql = format!(
+        "{}{} SELECT COUNT(*), COALESCE(SUM(bytes), 0)::BIGINT, MIN(ts), MAX(ts) FROM doomed",
+        candidates_sql(store),
+        delete,
+    );
+    let (rows, bytes, oldest, newest): (i64, i64, Option<DateTime<Utc>>, Option<DateTime<Utc>>) =
+        sqlx::query_as(&sql)
+            .bind(policy.max_age_days)
+            .bind(policy.max_count)
+            .bind(policy.max_bytes)
+            .fetch_one(&state.db)
+            .await
+            .map_err(|e| format!("{}: {}", store.id, e))?;
+    Ok(StoreReport {
+        store: store.id.to_string(),
+        rows,
+        bytes,
+        oldest,
+        newest,
+    })
+}
+
+async fn load_policies(state: &AppState) -> Result<Vec<GcPolicy>, String> {
+    sqlx::query_as::<_, GcPolicy>(
+        "SELECT store, enabled, max_age_days, max_count, max_bytes FROM ch_gc_policies ORDER BY store",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load GC policies: {}", e))
+}
+
+/// Apply policies. `stores = None` runs every enabled policy; an explicit
+/// list runs those stores even if their policy is disabled.
+pub async fn run_gc(state: &AppState, dry_run: bool, stores: Option<&[String]>) -> Result<GcReport, String> {
+    let started = Instant::now();
+    if let Some(unknown) = stores.and_then(|s| s.iter().find(|id| store_def(id).is_none())) {
+        return Err(format!("Unknown store '{}'", unknown));
+    }
+    let policies = load_policies(state).await?;
+
+    let mut reports = Vec::new();
+    for policy in &policies {
+        let selected = match stores {
+            Some(list) => list.iter().any(|s| s == &policy.store),
+            None => policy.enabled,
+        };
+        let Some(store) = store_def(&policy.store) else {
+            continue;
+        };
+        if !selected || !policy.has_limits() {
+            continue;
+        }
+        reports.push(collect_store(state, store, policy, dry_run).await?);
+    }
+
+    let report = GcReport {
+        dry_run,
+        total_rows: reports.iter().map(|r| r.rows).sum(),
+        total_bytes: reports.iter().map(|r| r.bytes).sum(),
+        stores: reports,
+        duration_ms: started.elapsed().as_millis() as u64,
+    };
+    if !dry_run && report.total_rows > 0 {
+        tracing::info!(
+            "gc: removed {} row(s), {} bytes in {}ms",
+            report.total_rows,
+            report.total_bytes,
+            report.duration_ms
+        );
+    }
+    Ok(report)
+}
+
+/// Scheduled maintenance: run every enabled policy every `GC_INTERVAL`.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(GC_INTERVAL);
+        // The first tick fires immediately; let startup settle first.
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            if let Err(e) = run_gc(&state, false, None).await {
+                tracing::warn!("gc: {}", e);
+            }
+            state.job_schedule.record_run(JOB_GC);
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn internal(e: String) -> (StatusCode, Json<Value>) {
+    tracing::warn!("gc: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e })))
+}
+
+/// `GET /api/gc/policies`
+pub async fn list_policies(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let policies = load_policies(&state).await.map_err(internal)?;
+    Ok(Json(json!({
+        "stores": STORES.iter().map(|s| s.id).collect::<Vec<_>>(),
+        "policies": policies,
+        "interval_secs": GC_INTERVAL.as_secs(),
+    })))
+}
+
+/// `PUT /api/gc/policies/{store}`
+pub async fn put_policy(
+    State(state): State<AppState>,
+    Path(store): Path<String>,
+    Json(mut policy): Json<GcPolicy>,
+) -> Result<Json<GcPolicy>, (StatusCode, Json<Value>)> {
+    if store_def(&store).is_none() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Unknown store '{}'", store) })),
+        ));
+    }
+    policy
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+    policy.store = store;
+    sqlx::query(
+        "INSERT INTO ch_gc_policies (store, enabled, max_age_days, max_count, max_bytes, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, NOW()) \
+         ON CONFLICT (store) DO UPDATE SET enabled = EXCLUDED.enabled, max_age_days = EXCLUDED.max_age_days, \
+         max_count = EXCLUDED.max_count, max_bytes = EXCLUDED.max_bytes, updated_at = NOW()",
+    )
+    .bind(&policy.store)
+    .bind(policy.enabled)
+    .bind(policy.max_age_days)
+    .bind(policy.max_count)
+    .bind(policy.max_bytes)
+    .execute(&state.db)
+    .await
+    .map_err(|e| internal(e.to_string()))?;
+    Ok(Json(policy))
+}
+
+fn default_dry_run() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunGcRequest {
+    #[serde(default = "default_dry_run")]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub stores: Option<Vec<String>>,
+}
+
+/// `POST /api/gc/run`
+pub async fn run(
+    State(state): State<AppState>,
+    Json(req): Json<RunGcRequest>,
+) -> Result<Json<GcReport>, (StatusCode, Json<Value>)> {
+    run_gc(&state, req.dry_run, req.stores.as_deref()).await.map(Json).map_err(|e| {
+        if e.starts_with("Unknown store") {
+            (StatusCode::BAD_REQUEST, Json(json!({ "error": e })))
+        } else {
+            internal(e)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_ids_are_unique() {
+        let mut ids: Vec<_> = STORES.iter().map(|s| s.id).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), STORES.len());
+    }
+
+    #[test]
+    fn candidates_sql_scopes_to_filter() {
+        let sql = candidates_sql(store_def("background_prompts").unwrap());
+        assert!(sql.contains("FROM ch_background_prompts t WHERE status IN ('done', 'failed', 'cancelled')"));
+        assert!(sql.contains("ORDER BY created_at DESC"));
+    }
+
+    #[test]
+    fn policy_validation() {
+        let ok = GcPolicy { enabled: true, max_age_days: Some(30), ..Default::default() };
+        assert!(ok.validate().is_ok() && ok.has_limits());
+        let bad = GcPolicy { max_count: Some(0), ..Default::default() };
+        assert!(bad.validate().is_err());
+        assert!(!GcPolicy::default().has_limits());
+    }
+}
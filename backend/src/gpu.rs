@@ -0,0 +1,162 @@
+//! GPU / VRAM telemetry for the Ollama host, so VRAM pressure is visible
+//! next to the CPU and RAM metrics.
+//!
+//! Sources, in order:
+//! - NVIDIA — `nvidia-smi` (NVML) per-GPU total / used VRAM and utilization
+//!   (located via `paths.rs`; `CH_NVIDIA_SMI` overrides the binary)
+//! - otherwise the VRAM Ollama reports for its loaded models (`/api/ps`);
+//!   the total is unknown then
+//!
+//! The models occupying VRAM always come from Ollama's `/api/ps`.
+//!
+//! - `GET /api/system/gpu` — `{ source, gpus, vram_total_mb, vram_used_mb, models }`
+
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::state::AppState;
+
+const NVIDIA_SMI_TIMEOUT: Duration = Duration::from_secs(5);
+const MB: f64 = 1_048_576.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GpuDevice {
+    pub index: u32,
+    pub name: String,
+    pub vram_total_mb: f64,
+    pub vram_used_mb: f64,
+    pub utilization_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelVram {
+    pub name: String,
+    pub vram_mb: f64,
+    /// Part of the model Ollama keeps in system RAM instead.
+    pub ram_mb: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuInfo {
+    /// `nvml`, `ollama` or `none`
+    pub source: &'static str,
+    pub gpus: Vec<GpuDevice>,
+    pub vram_total_mb: Option<f64>,
+    pub vram_used_mb: Option<f64>,
+    pub models: Vec<ModelVram>,
+    /// Why the Ollama model list is missing, if it is.
+    pub ollama_error: Option<String>,
+}
+
+/// Rows of `nvidia-smi --query-gpu=index,name,memory.total,memory.used,utilization.gpu
+/// --format=csv,noheader,nounits` (memory in MiB).
+fn parse_nvidia_smi(output: &str) -> Vec<GpuDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let [index, name, total, used, util] = fields.as_slice() else {
+                return None;
+            };
+            Some(GpuDevice {
+                index: index.parse().ok()?,
+                name: name.to_string(),
+                vram_total_mb: total.parse().ok()?,
+                vram_used_mb: used.parse().ok()?,
+                // `[N/A]` on GPUs without utilization reporting.
+                utilization_percent: util.parse().ok(),
+            })
+        })
+        .collect()
+}
+
+async fn nvidia_gpus() -> Option<Vec<GpuDevice>> {
+    let bin = crate::paths::program(&crate::paths::NVIDIA_SMI);
+    let output = tokio::process::Command::new(bin)
+        .args([
+            "--query-gpu=index,name,memory.total,memory.used,utilization.gpu",
+            "--format=csv,noheader,nounits",
+        ])
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(NVIDIA_SMI_TIMEOUT, output)
+        .await
+        .ok()?
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let gpus = parse_nvidia_smi(&String::from_utf8_lossy(&output.stdout));
+    (!gpus.is_empty()).then_some(gpus)
+}
+
+/// VRAM totals and the models occupying it.
+pub async fn get_gpu_info(state: &AppState) -> GpuInfo {
+    let (gpus, loaded) = tokio::join!(nvidia_gpus(), crate::ollama::ollama_loaded_models(state));
+    let (models, ollama_error) = match loaded {
+        Ok(loaded) => (
+            loaded
+                .into_iter()
+                .map(|m| ModelVram {
+                    name: m.name,
+                    vram_mb: m.vram_bytes as f64 / MB,
+                    ram_mb: m.size_bytes.saturating_sub(m.vram_bytes) as f64 / MB,
+                })
+                .collect::<Vec<_>>(),
+            None,
+        ),
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    match gpus {
+        Some(gpus) => GpuInfo {
+            source: "nvml",
+            vram_total_mb: Some(gpus.iter().map(|g| g.vram_total_mb).sum()),
+            vram_used_mb: Some(gpus.iter().map(|g| g.vram_used_mb).sum()),
+            gpus,
+            models,
+            ollama_error,
+        },
+        None => GpuInfo {
+            source: if ollama_error.is_none() {
+                "ollama"
+            } else {
+                "none"
+            },
+            gpus: Vec::new(),
+            vram_total_mb: None,
+            vram_used_mb: ollama_error
+                .is_none()
+                .then(|| models.iter().map(|m| m.vram_mb).sum()),
+            models,
+            ollama_error,
+        },
+    }
+}
+
+/// `GET /api/system/gpu`
+pub async fn gpu_info(State(state): State<AppState>) -> Json<GpuInfo> {
+    Json(get_gpu_info(&state).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nvidia_smi_rows_are_parsed() {
+        let gpus = parse_nvidia_smi(
+            "0, NVIDIA GeForce RTX 4090, 24564, 8123, 37\n1, Tesla T4, 15360, 0, [N/A]\nbogus line\n",
+        );
+        assert_eq!(gpus.len(), 2);
+        assert_eq!(gpus[0].name, "NVIDIA GeForce RTX 4090");
+        assert_eq!(gpus[0].vram_total_mb, 24564.0);
+        assert_eq!(gpus[0].vram_used_mb, 8123.0);
+        assert_eq!(gpus[0].utilization_percent, Some(37.0));
+        assert_eq!(gpus[1].index, 1);
+        assert_eq!(gpus[1].utilization_percent, None);
+        assert!(parse_nvidia_smi("").is_empty());
+    }
+}
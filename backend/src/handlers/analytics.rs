@@ -163,7 +163,7 @@ fn clamp_days(days: Option<i32>) -> i32 {
 }
 
 /// Determine pricing tier from model name.
-fn model_tier(model: &str) -> &'static str {
+pub(crate) fn model_tier(model: &str) -> &'static str {
     let m = model.to_lowercase();
     if m.contains("opus") {
         "opus"
@@ -177,7 +177,10 @@ fn model_tier(model: &str) -> &'static str {
 }
 
 /// Per-million-token pricing: (input, output).
-fn tier_pricing(tier: &str) -> (f64, f64) {
+pub(crate) fn tier_pricing(tier: &str) -> (f64, f64) {
+    if let Some(price) = crate::rule_updates::price(tier) {
+        return price;
+    }
     match tier {
         "opus" => (15.0, 75.0),
         "sonnet" => (3.0, 15.0),
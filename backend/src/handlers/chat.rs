@@ -1,16 +1,24 @@
 //! Non-streaming Claude chat endpoints.
 //!
 //! - `claude_models` — list resolved Claude models per tier
-//! - `claude_chat` — non-streaming chat completion
+//! - `claude_chat` — non-streaming chat completion (Gemini models go to the
+//!   Google API; other non-Anthropic models are rejected), followed by the
+//!   post-response hooks (see `hooks.rs`)
 
 use axum::Json;
 use axum::extract::State;
 use axum::http::StatusCode;
 use serde_json::{Value, json};
 
+use crate::ai_gateway::AiProvider;
+use crate::ai_gateway::attachments::{attach_images, attachment_error, resolve_attachments};
+use crate::handlers::prompt::Backend;
+use crate::hooks::{self, HookEvent, HookPayload};
 use crate::models::*;
 use crate::state::AppState;
+use crate::web_search;
 
+use super::streaming::google_chat;
 use super::{sanitize_json_strings, send_to_anthropic};
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -84,17 +92,87 @@ pub async fn claude_models(State(state): State<AppState>) -> Json<Value> {
     responses((status = 200, description = "Chat completion response")))]
 pub async fn claude_chat(
     State(state): State<AppState>,
-    Json(req): Json<ChatRequest>,
+    Json(mut req): Json<ChatRequest>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    crate::idle_scavenger::mark_interactive();
+    // Safety guard — secrets in the new user turn
+    if let Some(last) = req.messages.iter_mut().rev().find(|m| m.role == "user") {
+        crate::safety::screen_prompt(&mut last.content, "chat")
+            .map_err(|e| crate::safety::rejection(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    }
     let default_model = crate::model_registry::get_model_id(&state, "coordinator").await;
-    let model = req.model.unwrap_or(default_model);
+    let model = req.model.clone().unwrap_or(default_model);
+    let prompt = req
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.clone())
+        .unwrap_or_default();
+    let session_id = req.session_id.as_deref().and_then(|id| id.parse().ok());
+
+    let result = chat(&state, req, model.clone()).await;
+
+    // Post-response hooks, keyed by the request's correlation id.
+    let (event, response, detail) = match &result {
+        Ok(Json(body)) => (
+            HookEvent::Completed,
+            body["message"]["content"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            Value::Null,
+        ),
+        Err((status, Json(body))) => (
+            HookEvent::Failed,
+            String::new(),
+            json!({ "status": status.as_u16(), "error": body["error"] }),
+        ),
+    };
+    hooks::dispatch(
+        state.db.clone(),
+        HookPayload {
+            event,
+            execution_id: crate::correlation::current()
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            session_id,
+            model,
+            working_directory: String::new(),
+            prompt,
+            response,
+            detail,
+        },
+    );
+    result
+}
+
+async fn chat(
+    state: &AppState,
+    req: ChatRequest,
+    model: String,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let max_tokens = req.max_tokens.unwrap_or(4096);
+    let backend = Backend::for_model(&model);
+    if !matches!(backend, Backend::Anthropic | Backend::Google) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("Model '{}' is not available for chat", model) })),
+        ));
+    }
+
+    let images = resolve_attachments(&req.attachments)
+        .await
+        .map_err(attachment_error)?;
 
-    let messages: Vec<Value> = req
+    let mut messages: Vec<Value> = req
         .messages
         .iter()
         .map(|m| json!({ "role": m.role, "content": m.content }))
         .collect();
+    // Gemini takes the images as `inline_data` parts (see `google_chat`).
+    if backend == Backend::Anthropic {
+        attach_images(&AiProvider::Anthropic, &mut messages, &images);
+    }
 
     let mut body = json!({
         "model": model,
@@ -106,9 +184,60 @@ pub async fn claude_chat(
         body["temperature"] = json!(temp);
     }
 
+    let last_user = req
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .map(|m| m.content.as_str())
+        .unwrap_or("");
+    let mut search_context = String::new();
+    let sources = web_search::augment_system_prompt(
+        &state.http_client,
+        &mut search_context,
+        last_user,
+        req.web_search,
+    )
+    .await;
+    if !sources.is_empty() {
+        body["system"] = json!(search_context.trim_start());
+    }
+
+    if backend == Backend::Google {
+        let content = google_chat(
+            state,
+            &model,
+            search_context.trim_start(),
+            max_tokens,
+            &messages,
+            &images,
+            120,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("google chat: {}", e);
+            (StatusCode::BAD_GATEWAY, Json(json!({ "error": e })))
+        })?;
+        crate::safety::screen_response(&content, "chat")
+            .map_err(|e| crate::safety::rejection(StatusCode::BAD_GATEWAY, e))?;
+        return chat_response(ChatResponse {
+            id: uuid::Uuid::new_v4().to_string(),
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content,
+                model: Some(model.clone()),
+                timestamp: Some(chrono::Utc::now().to_rfc3339()),
+            },
+            model,
+            usage: None,
+            sources,
+            correlation_id: crate::correlation::current(),
+        });
+    }
+
     sanitize_json_strings(&mut body);
 
-    let resp = send_to_anthropic(&state, &body, 120).await?;
+    let resp = send_to_anthropic(state, &body, 120).await?;
 
     if !resp.status().is_success() {
         let status = resp.status();
@@ -139,6 +268,8 @@ pub async fn claude_chat(
                 .join("")
         })
         .unwrap_or_default();
+    crate::safety::screen_response(&content, "chat")
+        .map_err(|e| crate::safety::rejection(StatusCode::BAD_GATEWAY, e))?;
 
     let response_model = resp_body
         .get("model")
@@ -168,8 +299,14 @@ pub async fn claude_chat(
         },
         model: response_model,
         usage,
+        sources,
+        correlation_id: crate::correlation::current(),
     };
 
+    chat_response(chat_resp)
+}
+
+fn chat_response(chat_resp: ChatResponse) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     Ok(Json(serde_json::to_value(chat_resp).map_err(|_| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -93,6 +93,37 @@ pub async fn readiness(State(state): State<AppState>) -> Result<Json<Value>, Sta
     }
 }
 
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/health/startup
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Warm-start readiness report. Returns 503 with `{"ready": false}` until
+/// the startup warm-up has finished.
+#[utoipa::path(
+    get,
+    path = "/api/health/startup",
+    tag = "health",
+    responses(
+        (status = 200, description = "Startup warm-up report", body = StartupReport),
+        (status = 503, description = "Warm-up still in progress")
+    )
+)]
+pub async fn startup_report(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    match state.startup_report.read().await.as_ref() {
+        Some(report) => (
+            StatusCode::OK,
+            Json(serde_json::to_value(report).unwrap_or_else(|_| json!({"error": "serialization failed"}))),
+        ),
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "ready": false,
+                "uptime_seconds": state.start_time.elapsed().as_secs(),
+            })),
+        ),
+    }
+}
+
 // ═══════════════════════════════════════════════════════════════════════
 //  GET /api/auth/mode
 // ═══════════════════════════════════════════════════════════════════════
@@ -123,12 +154,22 @@ pub async fn auth_mode(State(state): State<AppState>) -> Json<Value> {
     responses((status = 200, description = "System statistics", body = SystemStats))
 )]
 pub async fn system_stats(State(state): State<AppState>) -> Json<Value> {
+    let queues = InternalQueueStats {
+        swarm_tasks: state.swarm.task_count().await,
+        a2a_cancel_tokens: state.a2a_cancel_tokens.read().await.len(),
+        pending_oauth_states: state.ai_gateway.oauth_manager.pending_states_count().await,
+    };
+    let telemetry = crate::system_telemetry::collect().await;
     let snapshot = state.system_monitor.read().await;
     let stats = SystemStats {
         cpu_usage_percent: snapshot.cpu_usage_percent,
         memory_used_mb: snapshot.memory_used_mb,
         memory_total_mb: snapshot.memory_total_mb,
         platform: snapshot.platform.clone(),
+        queues,
+        disk: telemetry.disk,
+        network: telemetry.network,
+        load_average: telemetry.load_average,
     };
     Json(serde_json::to_value(stats).unwrap_or_else(|_| json!({"error": "serialization failed"})))
 }
@@ -144,6 +185,7 @@ pub async fn system_stats(State(state): State<AppState>) -> Json<Value> {
     responses((status = 200, description = "System metrics for dashboard", body = SystemMetricsResponse))
 )]
 pub async fn system_metrics(State(state): State<AppState>) -> Json<Value> {
+    let telemetry = crate::system_telemetry::collect().await;
     let snapshot = state.system_monitor.read().await;
 
     // Status can be determined based on connection or simple mock, here we use online
@@ -151,6 +193,19 @@ pub async fn system_metrics(State(state): State<AppState>) -> Json<Value> {
     let status = "online".to_string();
     let rx_mb = snapshot.network_rx_bytes as f64 / 1_048_576.0;
     let tx_mb = snapshot.network_tx_bytes as f64 / 1_048_576.0;
+    let network_label = match (
+        telemetry.network.rx_bytes_per_sec,
+        telemetry.network.tx_bytes_per_sec,
+    ) {
+        (Some(rx), Some(tx)) => format!(
+            "Rx: {:.1} MB ({:.0} KB/s) | Tx: {:.1} MB ({:.0} KB/s)",
+            rx_mb,
+            rx / 1024.0,
+            tx_mb,
+            tx / 1024.0
+        ),
+        _ => format!("Rx: {:.1} MB | Tx: {:.1} MB", rx_mb, tx_mb),
+    };
 
     let metrics = SystemMetricsResponse {
         cpu: MetricItem {
@@ -166,11 +221,26 @@ pub async fn system_metrics(State(state): State<AppState>) -> Json<Value> {
             unit: Some("MB".to_string()),
         },
         network: NetworkMetric {
-            label: Some(format!("Rx: {:.1} MB | Tx: {:.1} MB", rx_mb, tx_mb)),
+            label: Some(network_label),
             status,
             // Derive ping estimate from system load: under low load ~1ms, scales with CPU usage
             ping: Some(1 + (snapshot.cpu_usage_percent * 0.5) as u64),
         },
+        disk: telemetry.disk.map(|disk| {
+            let gb = |bytes: u64| (bytes as f64 / 1_073_741_824.0 * 10.0).round() / 10.0;
+            MetricItem {
+                label: format!("Disk ({})", disk.mount_point),
+                value: gb(disk.total_bytes.saturating_sub(disk.available_bytes)),
+                max: Some(gb(disk.total_bytes)),
+                unit: Some("GB".to_string()),
+            }
+        }),
+        load: telemetry.load_average.map(|load| MetricItem {
+            label: "Load (1m)".to_string(),
+            value: (load.one * 100.0).round() / 100.0,
+            max: None,
+            unit: None,
+        }),
     };
     Json(serde_json::to_value(metrics).unwrap_or_else(|_| json!({"error": "serialization failed"})))
 }
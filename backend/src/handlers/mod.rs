@@ -11,6 +11,9 @@
 //! - `files` — file listing and native folder browser
 //! - `prompt_history` — bash-like prompt recall
 //! - `analytics` — agent performance dashboard aggregation endpoints
+//! - `routing_dataset` — anonymized routing decision export (CSV / JSONL)
+//! - `session_groups` — named, colored session groups for the sidebar
+//! - `session_transfer` — session export / import (Markdown, JSON, Claude CLI JSONL)
 
 pub mod agents;
 pub mod analytics;
@@ -19,6 +22,9 @@ pub mod files;
 pub mod health;
 pub mod prompt;
 pub mod prompt_history;
+pub mod routing_dataset;
+pub mod session_groups;
+pub mod session_transfer;
 pub mod sessions;
 pub mod settings;
 pub mod streaming;
@@ -32,6 +38,12 @@ pub use files::*;
 pub use health::*;
 pub use prompt::warm_prompt_cache;
 pub use prompt_history::*;
+pub use routing_dataset::export_routing_dataset;
+pub use session_groups::{
+    create_session_group, delete_session_group, list_session_groups, move_session_to_group,
+    update_session_group,
+};
+pub use session_transfer::{export_session, import_session};
 pub use sessions::*;
 pub use settings::*;
 pub use streaming::*;
@@ -41,6 +53,11 @@ pub use tags::*;
 
 pub(crate) const TOOL_TIMEOUT_SECS: u64 = 60;
 pub(crate) const MAX_MESSAGE_LENGTH: usize = 100_000;
+/// Bytes of a prompt inspected by keyword heuristics (view hints, complexity
+/// routing). Intent is stated up front; past this point the text is nearly
+/// always pasted code or logs, which only adds scan cost on 100 KB prompts.
+/// Benchmarked in `benches/prompt_analysis.rs`.
+pub(crate) const PROMPT_ANALYSIS_PREFIX_BYTES: usize = 4096;
 
 // ── Shared helpers ────────────────────────────────────────────────────────
 
@@ -74,6 +91,19 @@ pub(crate) fn truncate_for_context_with_limit(text: &str, max_chars: usize) -> S
     )
 }
 
+/// Leading slice of `text` used for keyword analysis — at most `max_bytes`,
+/// cut on a UTF-8 char boundary. Borrows, so callers avoid copying the whole prompt.
+pub(crate) fn analysis_prefix(text: &str, max_bytes: usize) -> &str {
+    if text.len() <= max_bytes {
+        return text;
+    }
+    let mut end = max_bytes;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
 /// Sanitize JSON strings — remove null bytes and BOM that break API calls.
 pub(crate) fn sanitize_json_strings(value: &mut Value) {
     match value {
@@ -364,6 +394,12 @@ pub(crate) async fn send_to_anthropic(
     body: &Value,
     timeout_secs: u64,
 ) -> Result<reqwest::Response, (StatusCode, Json<Value>)> {
+    // Offline profile gate
+    crate::profiles::ensure_cloud()?;
+
+    // Budget cap gate
+    crate::budget::ensure_within(&state.db, "anthropic").await?;
+
     // Circuit breaker gate
     if let Err(msg) = state.circuit_breaker.check().await {
         return Err((
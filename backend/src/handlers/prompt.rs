@@ -4,7 +4,10 @@
 //! - `resolve_chat_context` — model selection, session WD, generation params
 //! - `warm_prompt_cache` — pre-warm system prompt cache at startup
 //! - `tier_token_budget` — per-model max_tokens budget
-//! - `classify_complexity` — auto-tier routing (re-exported from model_registry)
+//! - `prompt_complexity` — auto-tier routing (wraps `model_registry::classify_complexity`,
+//!   applied to the prompt's instructions rather than pasted code)
+//! - `auto_tier_model` — model for a complexity class (rule overlay, then tier)
+//! - `complete_prompt` — one-shot, non-streaming completion of a single prompt
 
 use crate::state::AppState;
 
@@ -39,6 +42,7 @@ pub(crate) struct ChatContext {
     pub working_directory: String,
     pub session_id: Option<uuid::Uuid>,
     pub system_prompt: String,
+    pub permission_mode: crate::permissions::PermissionMode,
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -122,27 +126,81 @@ fn build_system_prompt(working_directory: &str, language: &str, custom_instructi
 //  Chat context resolution (model, tokens, WD, system prompt)
 // ═══════════════════════════════════════════════════════════════════════
 
+/// Complexity class used for auto-tier routing: `simple`, `complex`, ...
+/// Classified from what the prompt asks for (`task_text`), so pasting a large
+/// file under "fix the typo" does not by itself pick the commander tier.
+pub fn prompt_complexity(prompt_text: &str) -> &'static str {
+    let task = task_text(prompt_text, super::PROMPT_ANALYSIS_PREFIX_BYTES);
+    crate::rule_updates::complexity_override(&task)
+        .unwrap_or_else(|| crate::model_registry::classify_complexity(&task))
+}
+
+/// The instructions of a prompt: its text outside fenced code blocks, up to
+/// `max_bytes`. Pasted code and logs carry no task signal, only length.
+fn task_text(prompt: &str, max_bytes: usize) -> String {
+    let mut task = String::new();
+    let mut in_fence = false;
+    for line in prompt.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if task.len() + line.len() >= max_bytes {
+            task.push_str(super::analysis_prefix(line, max_bytes - task.len()));
+            break;
+        }
+        task.push_str(line);
+        task.push('\n');
+    }
+    task
+}
+
+/// Model for a complexity class when none was chosen explicitly.
+pub(crate) async fn auto_tier_model(state: &AppState, class: &str) -> String {
+    // Rule overlay recommendation: a tier name or an explicit model id.
+    match crate::rule_updates::recommended_model(class) {
+        Some(rec) if crate::rule_updates::MODEL_TIERS.contains(&rec.as_str()) => {
+            crate::model_registry::get_model_id(state, &rec).await
+        }
+        Some(model_id) => model_id,
+        None => match class {
+            "simple" => crate::model_registry::get_model_id(state, "coordinator").await,
+            "complex" => crate::model_registry::get_model_id(state, "commander").await,
+            _ => crate::model_registry::get_model_id(state, "commander").await,
+        },
+    }
+}
+
 /// Resolves model, max_tokens, session WD (session → global fallback).
 pub(crate) async fn resolve_chat_context(
     state: &AppState,
     req: &crate::models::ChatRequest,
 ) -> ChatContext {
-    let model = if let Some(ref m) = req.model {
-        m.clone()
+    let session_uuid = req
+        .session_id
+        .as_deref()
+        .and_then(|s| uuid::Uuid::parse_str(s).ok());
+
+    // Sessions duplicated onto another provider pin their model.
+    let pinned_model: Option<String> = match (&req.model, session_uuid) {
+        (None, Some(sid)) => sqlx::query_scalar("SELECT model FROM ch_sessions WHERE id = $1")
+            .bind(sid)
+            .fetch_optional(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .flatten(),
+        _ => None,
+    };
+
+    let model = if let Some(m) = req.model.clone().or(pinned_model) {
+        m
     } else {
-        let prompt_text: String = req
-            .messages
-            .iter()
-            .rev()
-            .take(1)
-            .map(|m| m.content.as_str())
-            .collect();
-        let complexity = crate::model_registry::classify_complexity(&prompt_text);
-        match complexity {
-            "simple" => crate::model_registry::get_model_id(state, "coordinator").await,
-            "complex" => crate::model_registry::get_model_id(state, "commander").await,
-            _ => crate::model_registry::get_model_id(state, "commander").await,
-        }
+        let prompt_text = req.messages.last().map(|m| m.content.as_str()).unwrap_or("");
+        auto_tier_model(state, prompt_complexity(prompt_text)).await
     };
 
     // A/B testing: read ab_model_b + ab_split from settings
@@ -169,22 +227,22 @@ pub(crate) async fn resolve_chat_context(
         }
     };
 
-    let session_uuid = req
-        .session_id
-        .as_deref()
-        .and_then(|s| uuid::Uuid::parse_str(s).ok());
-
-    // Single query: fetch session WD, global WD, language, generation params, and custom instructions
-    let (working_directory, language, db_temperature, db_max_tokens, db_max_iterations, custom_instructions) =
+    // Single query: fetch session WD, global WD, language, generation params,
+    // custom instructions, the session permission mode and preset extras
+    let (working_directory, language, db_temperature, db_max_tokens, db_max_iterations, custom_instructions, session_mode, session_prompt, pinned_files) =
         if let Some(ref sid) = session_uuid {
-            let row: Option<(String, String, String, f64, i32, i32, String)> = sqlx::query_as(
+            #[allow(clippy::type_complexity)]
+            let row: Option<(String, String, String, f64, i32, i32, String, Option<String>, String, Vec<String>)> = sqlx::query_as(
                 "SELECT COALESCE(s.working_directory, '') AS session_wd, \
              COALESCE(g.working_directory, '') AS global_wd, \
              COALESCE(g.language, 'en') AS language, \
              COALESCE(g.temperature, 0.7) AS temperature, \
              COALESCE(g.max_tokens, 4096) AS max_tokens, \
              COALESCE(g.max_iterations, 10) AS max_iterations, \
-             COALESCE(g.custom_instructions, '') AS custom_instructions \
+             COALESCE(g.custom_instructions, '') AS custom_instructions, \
+             s.permission_mode, \
+             COALESCE(s.system_prompt, '') AS system_prompt, \
+             s.pinned_files \
              FROM ch_sessions s \
              CROSS JOIN ch_settings g \
              WHERE s.id = $1 AND g.id = 1",
@@ -195,15 +253,15 @@ pub(crate) async fn resolve_chat_context(
             .ok()
             .flatten();
             match row {
-                Some((session_wd, global_wd, lang, temp, mtok, miter, ci)) => {
+                Some((session_wd, global_wd, lang, temp, mtok, miter, ci, mode, sp, pinned)) => {
                     let wd = if !session_wd.is_empty() {
                         session_wd
                     } else {
                         global_wd
                     };
-                    (wd, lang, temp, mtok, miter, ci)
+                    (wd, lang, temp, mtok, miter, ci, mode, sp, pinned)
                 }
-                None => (String::new(), "en".to_string(), 0.7, 4096, 10, String::new()),
+                None => (String::new(), "en".to_string(), 0.7, 4096, 10, String::new(), None, String::new(), Vec::new()),
             }
         } else {
             let row: Option<(String, String, f64, i32, i32, String)> = sqlx::query_as(
@@ -216,7 +274,9 @@ pub(crate) async fn resolve_chat_context(
             .await
             .ok()
             .flatten();
-            row.unwrap_or(("".to_string(), "en".to_string(), 0.7, 4096, 10, String::new()))
+            let (wd, lang, temp, mtok, miter, ci) =
+                row.unwrap_or(("".to_string(), "en".to_string(), 0.7, 4096, 10, String::new()));
+            (wd, lang, temp, mtok, miter, ci, None, String::new(), Vec::new())
         };
 
     let budget = tier_token_budget(&model);
@@ -245,6 +305,14 @@ pub(crate) async fn resolve_chat_context(
         });
         prompt
     });
+    // Preset extras are per session and read pinned files fresh — never cached.
+    let system_prompt = system_prompt
+        + &crate::presets::session_prompt_section(
+            &session_prompt,
+            &pinned_files,
+            &working_directory,
+        )
+        .await;
 
     ChatContext {
         model,
@@ -254,6 +322,173 @@ pub(crate) async fn resolve_chat_context(
         working_directory,
         session_id: session_uuid,
         system_prompt,
+        permission_mode: session_mode
+            .as_deref()
+            .and_then(crate::permissions::PermissionMode::parse)
+            .unwrap_or_else(crate::permissions::default_mode),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  One-shot completion (background prompts, transcripts)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Where a one-shot prompt for a model is sent; named like the provider
+/// lanes of the background queue (`model_registry::provider_for_model`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Backend {
+    Anthropic,
+    Google,
+    Ollama,
+    /// OpenAI-compatible provider, by compat provider id.
+    Compat(&'static str),
+    Unserved,
+}
+
+impl Backend {
+    pub(crate) fn for_model(model: &str) -> Self {
+        match crate::model_registry::provider_for_model(model) {
+            "anthropic" => Backend::Anthropic,
+            "google" => Backend::Google,
+            "ollama" => Backend::Ollama,
+            "other" => Backend::Unserved,
+            compat => Backend::Compat(compat),
+        }
+    }
+
+    /// Provider name, as used for lanes, budgets and costs.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Backend::Anthropic => "anthropic",
+            Backend::Google => "google",
+            Backend::Ollama => "ollama",
+            Backend::Compat(id) => id,
+            Backend::Unserved => "other",
+        }
+    }
+}
+
+/// Runs a single user prompt with the server-side system prompt and
+/// auto-tier routing on the provider serving the model (see
+/// `model_registry::provider_for_model`): Anthropic, Google, an
+/// OpenAI-compatible provider or local Ollama. Returns the reply text.
+/// Prompts leaving the machine pass the safety guard first (logged under
+/// `origin`); a blocked prompt fails with a `BLOCKED_BY_GUARD: ...` error.
+pub(crate) async fn complete_prompt(
+    state: &AppState,
+    prompt: &str,
+    model: Option<String>,
+    timeout_secs: u64,
+    origin: &str,
+) -> Result<String, String> {
+    if let Some(name) = model
+        .as_deref()
+        .and_then(|m| m.strip_prefix(crate::queue::OLLAMA_PREFIX))
+    {
+        return crate::queue::complete_ollama(state, name, &[], prompt).await;
+    }
+    let req = crate::models::ChatRequest {
+        messages: vec![crate::models::ChatMessage {
+            role: "user".to_string(),
+            content: prompt.to_string(),
+            model: None,
+            timestamp: None,
+        }],
+        model,
+        temperature: None,
+        max_tokens: None,
+        stream: Some(false),
+        tools_enabled: Some(false),
+        session_id: None,
+        attachments: Vec::new(),
+        web_search: None,
+    };
+    let ctx = resolve_chat_context(state, &req).await;
+    let backend = Backend::for_model(&ctx.model);
+    let mut screened = prompt.to_string();
+    if !matches!(backend, Backend::Ollama) {
+        crate::safety::screen_prompt(&mut screened, origin)
+            .map_err(|e| format!("{}: {}", crate::safety::BLOCKED_CODE, e))?;
+    }
+    let prompt = screened.as_str();
+    match backend {
+        Backend::Anthropic => complete_anthropic(state, &ctx, prompt, timeout_secs).await,
+        Backend::Google => {
+            super::streaming::google_complete(state, &ctx, prompt, timeout_secs).await
+        }
+        Backend::Ollama => {
+            let name = ctx
+                .model
+                .strip_prefix(crate::queue::OLLAMA_PREFIX)
+                .unwrap_or(&ctx.model);
+            crate::queue::complete_ollama(state, name, &[], prompt).await
+        }
+        Backend::Unserved => Err(format!("No provider serves model '{}'", ctx.model)),
+        Backend::Compat(provider) => {
+            let gate = match crate::profiles::ensure_cloud() {
+                Ok(()) => crate::budget::ensure_within(&state.db, provider).await,
+                Err(e) => Err(e),
+            };
+            gate.map_err(|(_, axum::Json(err))| error_text(&err))?;
+            crate::ai_gateway::compat_providers::complete(
+                state,
+                &ctx.model,
+                &ctx.system_prompt,
+                prompt,
+                ctx.max_tokens,
+            )
+            .await
+        }
+    }
+}
+
+async fn complete_anthropic(
+    state: &AppState,
+    ctx: &ChatContext,
+    prompt: &str,
+    timeout_secs: u64,
+) -> Result<String, String> {
+    let body = serde_json::json!({
+        "model": ctx.model,
+        "max_tokens": ctx.max_tokens,
+        "system": ctx.system_prompt,
+        "messages": [{ "role": "user", "content": prompt }],
+    });
+
+    let resp = super::send_to_anthropic(state, &body, timeout_secs)
+        .await
+        .map_err(|(_, axum::Json(err))| error_text(&err))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid provider response: {}", e))?;
+    if !status.is_success() {
+        return Err(format!("Provider returned HTTP {}", status.as_u16()));
+    }
+    Ok(body
+        .get("content")
+        .and_then(|c| c.as_array())
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default())
+}
+
+/// User-facing message of a gate / provider error body, prefixed with its
+/// `code` (e.g. `BUDGET_EXCEEDED: ...`) when it has one.
+pub(crate) fn error_text(err: &serde_json::Value) -> String {
+    let message = err
+        .get("error")
+        .and_then(|e| e.as_str())
+        .unwrap_or("AI provider request failed");
+    match err.get("code").and_then(|c| c.as_str()) {
+        Some(code) => format!("{}: {}", code, message),
+        None => message.to_string(),
     }
 }
 
@@ -293,3 +528,36 @@ pub async fn warm_prompt_cache(state: &AppState) {
     }
     tracing::info!("prompt_cache: pre-warmed {} system prompt variants", count);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_text_skips_fenced_code() {
+        let prompt = "Fix the typo in this function:\n```rust\nfn mian() {}\n```\nThanks";
+        assert_eq!(
+            task_text(prompt, 4096),
+            "Fix the typo in this function:\nThanks\n"
+        );
+        // An unclosed fence hides the rest of the prompt.
+        assert_eq!(task_text("Explain\n```\nlog line", 4096), "Explain\n");
+    }
+
+    #[test]
+    fn task_text_is_capped_on_a_char_boundary() {
+        let prompt = "ż".repeat(5_000);
+        let task = task_text(&prompt, 4096);
+        assert!(task.len() <= 4096 && task.len() >= 4094);
+        assert!(task.chars().all(|c| c == 'ż'));
+    }
+
+    #[test]
+    fn pasted_code_does_not_decide_the_tier() {
+        let short = "Rename the variable `x` to `count`.";
+        let code = format!("```\n{}```", "let x = compute(x);\n".repeat(5_000));
+        let pasted = format!("{}\n{}", short, code);
+        assert!(pasted.len() > 100_000);
+        assert_eq!(prompt_complexity(&pasted), prompt_complexity(short));
+    }
+}
@@ -0,0 +1,306 @@
+//! Anonymized routing dataset export for offline analysis.
+//!
+//! One row per WebSocket prompt execution, newest first (capped at
+//! `MAX_ROWS`), built from `ch_prompt_traces`:
+//! the routing inputs (complexity, task type, whether the model was chosen
+//! explicitly, tools flag, prompt length) and outcome (provider, model, tier,
+//! latency, time to first token, success, failure reason, user rating).
+//! Provider and tier come from the model registry
+//! (`model_registry::provider_for_model` / `tier_for_model`).
+//!
+//! Anonymization: no prompt or response text, session ids or working
+//! directories are exported; execution ids are replaced by a truncated
+//! SHA-256 and timestamps are reduced to the day.
+//!
+//! - `GET /api/analytics/routing-dataset?days=30&format=csv|jsonl`
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::model_registry::{provider_for_model, tier_for_model};
+use crate::state::AppState;
+
+/// Hard cap on exported rows.
+const MAX_ROWS: i64 = 50_000;
+
+#[derive(Debug, Deserialize)]
+pub struct RoutingDatasetQuery {
+    /// Number of days to look back (default: 30, max: 365)
+    pub days: Option<i32>,
+    /// `csv` (default) or `jsonl`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoutingRecord {
+    pub id: String,
+    pub day: String,
+    pub complexity: String,
+    pub task_type: String,
+    pub model_explicit: bool,
+    pub tools_enabled: bool,
+    pub prompt_len: i64,
+    pub provider: String,
+    pub model: String,
+    pub tier: String,
+    pub latency_ms: Option<i64>,
+    pub first_token_ms: Option<i64>,
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    pub rating: Option<i64>,
+}
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "day",
+    "complexity",
+    "task_type",
+    "model_explicit",
+    "tools_enabled",
+    "prompt_len",
+    "provider",
+    "model",
+    "tier",
+    "latency_ms",
+    "first_token_ms",
+    "success",
+    "failure_reason",
+    "rating",
+];
+
+#[derive(sqlx::FromRow)]
+struct ExecutionRow {
+    execution_id: String,
+    started_at: Option<DateTime<Utc>>,
+    received: Option<Value>,
+    context: Option<Value>,
+    outcome: Option<String>,
+    latency_ms: Option<i64>,
+    first_token_ms: Option<i64>,
+    failure: Option<Value>,
+    rating: Option<Value>,
+}
+
+fn anonymize_id(execution_id: &str) -> String {
+    let digest = Sha256::digest(execution_id.as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl RoutingRecord {
+    fn from_row(row: ExecutionRow) -> Self {
+        let str_field = |v: &Option<Value>, key: &str| {
+            v.as_ref()
+                .and_then(|d| d.get(key))
+                .and_then(|x| x.as_str())
+                .map(str::to_string)
+        };
+        let bool_field = |v: &Option<Value>, key: &str| {
+            v.as_ref()
+                .and_then(|d| d.get(key))
+                .and_then(|x| x.as_bool())
+                .unwrap_or(false)
+        };
+        let model = str_field(&row.context, "model").unwrap_or_default();
+        Self {
+            id: anonymize_id(&row.execution_id),
+            day: row
+                .started_at
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            complexity: str_field(&row.context, "complexity").unwrap_or_else(|| "unknown".into()),
+            task_type: str_field(&row.context, "task_type").unwrap_or_else(|| "unknown".into()),
+            model_explicit: bool_field(&row.context, "model_explicit"),
+            tools_enabled: bool_field(&row.received, "tools_enabled"),
+            prompt_len: row
+                .received
+                .as_ref()
+                .and_then(|d| d.get("prompt_len"))
+                .and_then(|x| x.as_i64())
+                .unwrap_or(0),
+            provider: provider_for_model(&model).to_string(),
+            tier: tier_for_model(&model).to_string(),
+            latency_ms: row.latency_ms,
+            first_token_ms: row.first_token_ms,
+            success: row.outcome.as_deref() == Some("completed"),
+            // Only the reason code — error messages may quote prompt content.
+            failure_reason: str_field(&row.failure, "reason"),
+            rating: row
+                .rating
+                .as_ref()
+                .and_then(|d| d.get("rating"))
+                .and_then(|x| x.as_i64()),
+            model,
+        }
+    }
+
+    fn csv_line(&self) -> String {
+        let opt = |v: Option<i64>| v.map(|n| n.to_string()).unwrap_or_default();
+        [
+            csv_field(&self.id),
+            csv_field(&self.day),
+            csv_field(&self.complexity),
+            csv_field(&self.task_type),
+            self.model_explicit.to_string(),
+            self.tools_enabled.to_string(),
+            self.prompt_len.to_string(),
+            csv_field(&self.provider),
+            csv_field(&self.model),
+            csv_field(&self.tier),
+            opt(self.latency_ms),
+            opt(self.first_token_ms),
+            self.success.to_string(),
+            csv_field(self.failure_reason.as_deref().unwrap_or("")),
+            opt(self.rating),
+        ]
+        .join(",")
+    }
+}
+
+/// RFC 4180 field quoting.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// `GET /api/analytics/routing-dataset` — anonymized routing decisions as a
+/// CSV or JSONL download.
+pub async fn export_routing_dataset(
+    State(state): State<AppState>,
+    Query(q): Query<RoutingDatasetQuery>,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let days = q.days.unwrap_or(30).clamp(1, 365);
+    let jsonl = match q.format.as_deref() {
+        None | Some("csv") => false,
+        Some("jsonl") => true,
+        Some(other) => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": format!("Unsupported format '{}' (csv, jsonl)", other) })),
+            ));
+        }
+    };
+
+    let rows = sqlx::query_as::<_, ExecutionRow>(
+        r#"
+        SELECT
+            execution_id,
+            MIN(created_at) AS started_at,
+            (ARRAY_AGG(detail ORDER BY seq) FILTER (WHERE stage = 'received'))[1] AS received,
+            (ARRAY_AGG(detail ORDER BY seq) FILTER (WHERE stage = 'context_resolved'))[1] AS context,
+            (ARRAY_AGG(stage ORDER BY seq DESC)
+                FILTER (WHERE stage IN ('completed', 'failed', 'cancelled')))[1] AS outcome,
+            MAX(elapsed_ms) FILTER (WHERE stage IN ('completed', 'failed', 'cancelled')) AS latency_ms,
+            MIN(elapsed_ms) FILTER (WHERE stage = 'first_token') AS first_token_ms,
+            (ARRAY_AGG(detail ORDER BY seq DESC) FILTER (WHERE stage = 'failed'))[1] AS failure,
+            (ARRAY_AGG(detail ORDER BY seq DESC) FILTER (WHERE stage = 'rated'))[1] AS rating
+        FROM ch_prompt_traces
+        WHERE created_at >= NOW() - make_interval(days => $1)
+        GROUP BY execution_id
+        HAVING BOOL_OR(stage = 'context_resolved')
+        ORDER BY MIN(created_at) DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(days)
+    .bind(MAX_ROWS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("analytics/routing-dataset query failed: {e}");
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to export routing dataset" })),
+        )
+    })?;
+
+    let records = rows.into_iter().map(RoutingRecord::from_row);
+    let (body, content_type, ext) = if jsonl {
+        let mut out = String::new();
+        for r in records {
+            out.push_str(&serde_json::to_string(&r).unwrap_or_default());
+            out.push('\n');
+        }
+        (out, "application/x-ndjson", "jsonl")
+    } else {
+        let mut out = CSV_COLUMNS.join(",");
+        out.push('\n');
+        for r in records {
+            out.push_str(&r.csv_line());
+            out.push('\n');
+        }
+        (out, "text/csv; charset=utf-8", "csv")
+    };
+
+    let disposition = format!(
+        "attachment; filename=\"claudehydra-routing-{}.{}\"",
+        Utc::now().format("%Y%m%d"),
+        ext
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row() -> ExecutionRow {
+        ExecutionRow {
+            execution_id: "8d1f6c1e-0000-4000-8000-000000000000".to_string(),
+            started_at: "2026-10-01T12:34:56Z".parse().ok(),
+            received: Some(json!({ "prompt_len": 120, "tools_enabled": true, "session_id": "secret" })),
+            context: Some(json!({
+                "model": "claude-opus-4-6", "complexity": "complex",
+                "task_type": "code", "model_explicit": false,
+            })),
+            outcome: Some("failed".to_string()),
+            latency_ms: Some(4200),
+            first_token_ms: None,
+            failure: Some(json!({ "reason": "upstream_status", "error": "prompt text here" })),
+            rating: Some(json!({ "rating": 2 })),
+        }
+    }
+
+    #[test]
+    fn record_is_anonymized() {
+        let r = RoutingRecord::from_row(row());
+        assert_eq!(r.id.len(), 16);
+        assert!(!r.id.contains("8d1f6c1e"));
+        assert_eq!(r.day, "2026-10-01");
+        assert_eq!(r.provider, "anthropic");
+        assert_eq!(r.tier, "commander");
+        assert!(!r.success);
+        assert_eq!(r.failure_reason.as_deref(), Some("upstream_status"));
+        let json = serde_json::to_string(&r).unwrap();
+        assert!(!json.contains("secret") && !json.contains("prompt text"));
+    }
+
+    #[test]
+    fn csv_line_matches_header() {
+        let line = RoutingRecord::from_row(row()).csv_line();
+        assert_eq!(line.split(',').count(), CSV_COLUMNS.len());
+        assert!(line.ends_with(",2"));
+    }
+
+    #[test]
+    fn csv_field_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,276 @@
+//! Session groups — named, colored folders for the session sidebar.
+//!
+//! Endpoints:
+//! - `GET    /api/session-groups`          — groups (in order) with member session ids
+//! - `POST   /api/session-groups`          — create `{ name, color? }`
+//! - `PATCH  /api/session-groups/{id}`     — update `{ name?, color?, collapsed?, position? }`
+//! - `DELETE /api/session-groups/{id}`     — delete (sessions become ungrouped)
+//! - `PUT    /api/sessions/{id}/group`     — move a session `{ group_id }` (`null` = ungroup)
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error};
+use crate::state::AppState;
+
+/// Colors offered by the sidebar picker; `#rrggbb` is accepted as well.
+const NAMED_COLORS: &[&str] = &[
+    "gray", "red", "orange", "yellow", "green", "teal", "blue", "purple", "pink",
+];
+const DEFAULT_COLOR: &str = "gray";
+const MAX_NAME_LEN: usize = 60;
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct SessionGroupRow {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub color: String,
+    pub collapsed: bool,
+    pub position: i32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Normalize a color label: a palette name or `#rrggbb` (lowercased).
+fn normalize_color(raw: &str) -> Option<String> {
+    let color = raw.trim().to_lowercase();
+    if NAMED_COLORS.contains(&color.as_str()) {
+        return Some(color);
+    }
+    let hex = color.strip_prefix('#')?;
+    (hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit())).then_some(color)
+}
+
+fn normalize_name(raw: &str) -> Result<String, (StatusCode, Json<Value>)> {
+    let name = raw.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+        return Err(bad_request(&format!(
+            "Group name must be 1-{} characters",
+            MAX_NAME_LEN
+        )));
+    }
+    Ok(name.to_string())
+}
+
+fn not_found(what: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": format!("{} not found", what) })),
+    )
+}
+
+fn parse_uuid(raw: &str, what: &str) -> Result<uuid::Uuid, (StatusCode, Json<Value>)> {
+    raw.parse()
+        .map_err(|_| bad_request(&format!("Invalid {} id", what)))
+}
+
+// ── GET /api/session-groups ─────────────────────────────────────────────────
+
+pub async fn list_session_groups(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let groups = sqlx::query_as::<_, SessionGroupRow>(
+        "SELECT id, name, color, collapsed, position, created_at \
+         FROM ch_session_groups ORDER BY position ASC, created_at ASC",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("session_groups"))?;
+
+    let members: Vec<(uuid::Uuid, uuid::Uuid)> = sqlx::query_as(
+        "SELECT group_id, id FROM ch_sessions WHERE group_id IS NOT NULL ORDER BY updated_at DESC",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("session_groups"))?;
+    let mut by_group: std::collections::HashMap<uuid::Uuid, Vec<String>> =
+        std::collections::HashMap::new();
+    for (group_id, session_id) in members {
+        by_group
+            .entry(group_id)
+            .or_default()
+            .push(session_id.to_string());
+    }
+
+    let groups: Vec<Value> = groups
+        .into_iter()
+        .map(|g| {
+            let sessions = by_group.remove(&g.id).unwrap_or_default();
+            json!({
+                "id": g.id.to_string(),
+                "name": g.name,
+                "color": g.color,
+                "collapsed": g.collapsed,
+                "position": g.position,
+                "created_at": g.created_at.to_rfc3339(),
+                "session_ids": sessions,
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "groups": groups })))
+}
+
+// ── POST /api/session-groups ────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+pub async fn create_session_group(
+    State(state): State<AppState>,
+    Json(req): Json<CreateGroupRequest>,
+) -> Result<(StatusCode, Json<SessionGroupRow>), (StatusCode, Json<Value>)> {
+    let name = normalize_name(&req.name)?;
+    let color = match req.color.as_deref() {
+        Some(c) => normalize_color(c).ok_or_else(|| bad_request("Invalid color"))?,
+        None => DEFAULT_COLOR.to_string(),
+    };
+    // New groups go to the end of the sidebar.
+    let row = sqlx::query_as::<_, SessionGroupRow>(
+        "INSERT INTO ch_session_groups (name, color, position) \
+         VALUES ($1, $2, (SELECT COALESCE(MAX(position) + 1, 0) FROM ch_session_groups)) \
+         RETURNING id, name, color, collapsed, position, created_at",
+    )
+    .bind(&name)
+    .bind(&color)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error("session_groups"))?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+// ── PATCH /api/session-groups/{id} ──────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateGroupRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub collapsed: Option<bool>,
+    #[serde(default)]
+    pub position: Option<i32>,
+}
+
+pub async fn update_session_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateGroupRequest>,
+) -> Result<Json<SessionGroupRow>, (StatusCode, Json<Value>)> {
+    let group_id = parse_uuid(&id, "group")?;
+    let name = req.name.as_deref().map(normalize_name).transpose()?;
+    let color = req
+        .color
+        .as_deref()
+        .map(|c| normalize_color(c).ok_or_else(|| bad_request("Invalid color")))
+        .transpose()?;
+
+    sqlx::query_as::<_, SessionGroupRow>(
+        "UPDATE ch_session_groups SET \
+            name = COALESCE($2, name), \
+            color = COALESCE($3, color), \
+            collapsed = COALESCE($4, collapsed), \
+            position = COALESCE($5, position) \
+         WHERE id = $1 \
+         RETURNING id, name, color, collapsed, position, created_at",
+    )
+    .bind(group_id)
+    .bind(name)
+    .bind(color)
+    .bind(req.collapsed)
+    .bind(req.position.map(|p| p.max(0)))
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("session_groups"))?
+    .map(Json)
+    .ok_or_else(|| not_found("Group"))
+}
+
+// ── DELETE /api/session-groups/{id} ─────────────────────────────────────────
+
+pub async fn delete_session_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let group_id = parse_uuid(&id, "group")?;
+    // Member sessions are kept; the FK clears their group_id.
+    let result = sqlx::query("DELETE FROM ch_session_groups WHERE id = $1")
+        .bind(group_id)
+        .execute(&state.db)
+        .await
+        .map_err(db_error("session_groups"))?;
+    if result.rows_affected() == 0 {
+        return Err(not_found("Group"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// ── PUT /api/sessions/{id}/group ────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct MoveSessionRequest {
+    pub group_id: Option<String>,
+}
+
+pub async fn move_session_to_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<MoveSessionRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_uuid(&id, "session")?;
+    let group_id = req
+        .group_id
+        .as_deref()
+        .map(|g| parse_uuid(g, "group"))
+        .transpose()?;
+
+    if let Some(gid) = group_id {
+        sqlx::query("SELECT 1 FROM ch_session_groups WHERE id = $1")
+            .bind(gid)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(db_error("session_groups"))?
+            .ok_or_else(|| not_found("Group"))?;
+    }
+
+    let result = sqlx::query("UPDATE ch_sessions SET group_id = $1 WHERE id = $2")
+        .bind(group_id)
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(db_error("session_groups"))?;
+    if result.rows_affected() == 0 {
+        return Err(not_found("Session"));
+    }
+    Ok(Json(json!({
+        "session_id": id,
+        "group_id": group_id.map(|g| g.to_string()),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_accept_palette_names_and_hex() {
+        assert_eq!(normalize_color(" Blue ").as_deref(), Some("blue"));
+        assert_eq!(normalize_color("#A1B2C3").as_deref(), Some("#a1b2c3"));
+        assert!(normalize_color("#abc").is_none());
+        assert!(normalize_color("#ggg000").is_none());
+        assert!(normalize_color("chartreuse").is_none());
+    }
+
+    #[test]
+    fn names_are_trimmed_and_bounded() {
+        assert_eq!(normalize_name("  Backend  ").unwrap(), "Backend");
+        assert!(normalize_name("   ").is_err());
+        assert!(normalize_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+    }
+}
@@ -0,0 +1,734 @@
+//! Conversation export / import — archive a session or move it between machines.
+//!
+//! Formats:
+//! - `markdown` — human-readable transcript (`## User` / `## Assistant` sections)
+//! - `json` — lossless ClaudeHydra archive, including tool interactions
+//! - `claude-cli` — Claude CLI session JSONL (one `user` / `assistant` event per line)
+//!
+//! Endpoints:
+//! - `GET  /api/sessions/{id}/export?format=` — download (default `json`)
+//! - `POST /api/sessions/import` — `{ path? | content?, format?, title? }`; the
+//!   format is detected from the content when omitted. Paths must be inside
+//!   the allowed directories.
+//!
+//! Exports and imported messages are redacted (see `redaction.rs`).
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error};
+use crate::models::ToolInteractionInfo;
+use crate::state::AppState;
+
+use super::{MAX_MESSAGE_LENGTH, truncate_for_context_with_limit};
+
+const ARCHIVE_FORMAT: &str = "claudehydra.session";
+const ARCHIVE_VERSION: u32 = 1;
+const MAX_IMPORT_MESSAGES: usize = 10_000;
+/// Upper bound on an import file / body.
+pub const MAX_IMPORT_BYTES: u64 = 50 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransferFormat {
+    Markdown,
+    Json,
+    ClaudeCli,
+}
+
+impl TransferFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::ClaudeCli => "jsonl",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Self::Markdown => "text/markdown; charset=utf-8",
+            Self::Json => "application/json",
+            Self::ClaudeCli => "application/x-ndjson",
+        }
+    }
+}
+
+/// Lossless archive (`json` format).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionArchive {
+    pub format: String,
+    pub version: u32,
+    pub title: String,
+    #[serde(default)]
+    pub working_directory: String,
+    pub created_at: DateTime<Utc>,
+    pub messages: Vec<ArchivedMessage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_interactions: Vec<ToolInteractionInfo>,
+}
+
+impl ArchivedMessage {
+    /// Redact content and tool interactions; returns the number of replacements.
+    fn redact(&mut self) -> usize {
+        crate::redaction::redact_string(&mut self.content)
+            + crate::redaction::redact_interactions(&mut self.tool_interactions)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Rendering
+// ═══════════════════════════════════════════════════════════════════════
+
+fn role_heading(role: &str) -> &'static str {
+    match role {
+        "assistant" => "Assistant",
+        "system" => "System",
+        _ => "User",
+    }
+}
+
+fn render_markdown(archive: &SessionArchive) -> String {
+    let mut out = format!("# {}\n\n", archive.title);
+    out.push_str(&format!("_Created {}", archive.created_at.to_rfc3339()));
+    if !archive.working_directory.is_empty() {
+        out.push_str(&format!(" · `{}`", archive.working_directory));
+    }
+    out.push_str("_\n");
+    for m in &archive.messages {
+        out.push_str(&format!("\n## {}", role_heading(&m.role)));
+        if let Some(ref model) = m.model {
+            out.push_str(&format!(" ({})", model));
+        }
+        out.push_str(&format!(" — {}\n\n", m.timestamp.to_rfc3339()));
+        out.push_str(m.content.trim_end());
+        out.push('\n');
+        for ti in &m.tool_interactions {
+            out.push_str(&format!(
+                "\n> Tool `{}`{}\n",
+                ti.tool_name,
+                if ti.is_error { " (error)" } else { "" }
+            ));
+        }
+    }
+    out
+}
+
+/// Claude CLI session JSONL: linked `user` / `assistant` events.
+fn render_claude_cli(archive: &SessionArchive, session_id: &str) -> String {
+    let mut parent: Option<String> = None;
+    let mut lines = Vec::with_capacity(archive.messages.len());
+    for m in archive
+        .messages
+        .iter()
+        .filter(|m| m.role == "user" || m.role == "assistant")
+    {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let message = if m.role == "assistant" {
+            json!({
+                "role": "assistant",
+                "model": m.model,
+                "content": [{ "type": "text", "text": m.content }],
+            })
+        } else {
+            json!({ "role": "user", "content": m.content })
+        };
+        lines.push(
+            json!({
+                "type": m.role,
+                "uuid": uuid,
+                "parentUuid": parent,
+                "sessionId": session_id,
+                "timestamp": m.timestamp.to_rfc3339(),
+                "cwd": archive.working_directory,
+                "message": message,
+            })
+            .to_string(),
+        );
+        parent = Some(uuid);
+    }
+    let mut out = lines.join("\n");
+    out.push('\n');
+    out
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Parsing
+// ═══════════════════════════════════════════════════════════════════════
+
+fn detect_format(content: &str) -> TransferFormat {
+    let trimmed = content.trim_start();
+    if trimmed.starts_with('{') {
+        // A single JSON document is an archive; one object per line is CLI JSONL.
+        if serde_json::from_str::<Value>(trimmed).is_ok() {
+            TransferFormat::Json
+        } else {
+            TransferFormat::ClaudeCli
+        }
+    } else {
+        TransferFormat::Markdown
+    }
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw.trim())
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
+}
+
+fn parse_json(content: &str) -> Result<SessionArchive, String> {
+    let archive: SessionArchive =
+        serde_json::from_str(content).map_err(|e| format!("Invalid session archive: {}", e))?;
+    if archive.format != ARCHIVE_FORMAT {
+        return Err(format!("Unsupported archive format '{}'", archive.format));
+    }
+    if archive.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than supported",
+            archive.version
+        ));
+    }
+    Ok(archive)
+}
+
+/// Text of a CLI message `content` (string or block array). Tool-result-only
+/// user turns yield an empty string and are skipped.
+fn cli_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|b| b.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|b| b.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn parse_claude_cli(content: &str) -> Result<SessionArchive, String> {
+    let mut messages = Vec::new();
+    let mut working_directory = String::new();
+    for (n, line) in content
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+    {
+        let event: Value = serde_json::from_str(line)
+            .map_err(|e| format!("Line {}: invalid JSON: {}", n + 1, e))?;
+        let kind = event.get("type").and_then(|t| t.as_str()).unwrap_or("");
+        if kind != "user" && kind != "assistant" {
+            continue;
+        }
+        let Some(message) = event.get("message") else {
+            continue;
+        };
+        let text = cli_text(message.get("content").unwrap_or(&Value::Null));
+        if text.trim().is_empty() {
+            continue;
+        }
+        if working_directory.is_empty()
+            && let Some(cwd) = event.get("cwd").and_then(|c| c.as_str())
+        {
+            working_directory = cwd.to_string();
+        }
+        messages.push(ArchivedMessage {
+            role: kind.to_string(),
+            content: text,
+            model: message
+                .get("model")
+                .and_then(|m| m.as_str())
+                .map(str::to_string),
+            agent: None,
+            timestamp: event
+                .get("timestamp")
+                .and_then(|t| t.as_str())
+                .and_then(parse_timestamp)
+                .unwrap_or_else(Utc::now),
+            tool_interactions: Vec::new(),
+        });
+    }
+    Ok(SessionArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        title: String::new(),
+        working_directory,
+        created_at: messages
+            .first()
+            .map(|m| m.timestamp)
+            .unwrap_or_else(Utc::now),
+        messages,
+    })
+}
+
+/// Parses transcripts written by `render_markdown`: `# Title`, then one
+/// `## User|Assistant|System [(...)] [— timestamp]` section per message.
+fn parse_markdown(content: &str) -> Result<SessionArchive, String> {
+    let mut title = String::new();
+    let mut messages: Vec<ArchivedMessage> = Vec::new();
+    let mut current: Option<(ArchivedMessage, Vec<&str>)> = None;
+
+    let flush = |current: &mut Option<(ArchivedMessage, Vec<&str>)>,
+                 out: &mut Vec<ArchivedMessage>| {
+        if let Some((mut msg, body)) = current.take() {
+            let text = body
+                .into_iter()
+                .filter(|l| !l.starts_with("> Tool `"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            msg.content = text.trim().to_string();
+            if !msg.content.is_empty() {
+                out.push(msg);
+            }
+        }
+    };
+
+    for line in content.lines() {
+        if let Some(rest) = line.strip_prefix("## ") {
+            let (head, stamp) = match rest.split_once(" — ") {
+                Some((h, s)) => (h, parse_timestamp(s)),
+                None => (rest, None),
+            };
+            let (role_word, model) = match head.split_once(" (") {
+                Some((r, m)) => (r, Some(m.trim_end_matches(')').to_string())),
+                None => (head, None),
+            };
+            let role = match role_word.trim() {
+                "User" => "user",
+                "Assistant" => "assistant",
+                "System" => "system",
+                _ => {
+                    if let Some((_, body)) = current.as_mut() {
+                        body.push(line);
+                    }
+                    continue;
+                }
+            };
+            flush(&mut current, &mut messages);
+            current = Some((
+                ArchivedMessage {
+                    role: role.to_string(),
+                    content: String::new(),
+                    model,
+                    agent: None,
+                    timestamp: stamp.unwrap_or_else(Utc::now),
+                    tool_interactions: Vec::new(),
+                },
+                Vec::new(),
+            ));
+        } else if let Some((_, body)) = current.as_mut() {
+            body.push(line);
+        } else if let Some(t) = line.strip_prefix("# ")
+            && title.is_empty()
+        {
+            title = t.trim().to_string();
+        }
+    }
+    flush(&mut current, &mut messages);
+
+    if messages.is_empty() {
+        return Err("No `## User` / `## Assistant` sections found".to_string());
+    }
+    Ok(SessionArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        title,
+        working_directory: String::new(),
+        created_at: messages[0].timestamp,
+        messages,
+    })
+}
+
+fn parse(content: &str, format: TransferFormat) -> Result<SessionArchive, String> {
+    let archive = match format {
+        TransferFormat::Json => parse_json(content)?,
+        TransferFormat::ClaudeCli => parse_claude_cli(content)?,
+        TransferFormat::Markdown => parse_markdown(content)?,
+    };
+    if archive.messages.is_empty() {
+        return Err("The conversation has no messages".to_string());
+    }
+    if archive.messages.len() > MAX_IMPORT_MESSAGES {
+        return Err(format!("Too many messages (max {})", MAX_IMPORT_MESSAGES));
+    }
+    Ok(archive)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Database
+// ═══════════════════════════════════════════════════════════════════════
+
+async fn load_archive(
+    state: &AppState,
+    session_id: uuid::Uuid,
+) -> Result<SessionArchive, (StatusCode, Json<Value>)> {
+    let (title, working_directory, created_at): (String, String, DateTime<Utc>) = sqlx::query_as(
+        "SELECT title, COALESCE(working_directory, ''), created_at FROM ch_sessions WHERE id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("session_transfer"))?
+    .ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Session not found" })),
+        )
+    })?;
+
+    let rows: Vec<(
+        uuid::Uuid,
+        String,
+        String,
+        Option<String>,
+        Option<String>,
+        DateTime<Utc>,
+    )> = sqlx::query_as(
+        "SELECT id, role, content, model, agent, created_at FROM ch_messages \
+         WHERE session_id = $1 ORDER BY created_at ASC",
+    )
+    .bind(session_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("session_transfer"))?;
+
+    let ids: Vec<uuid::Uuid> = rows.iter().map(|r| r.0).collect();
+    let interactions: Vec<(uuid::Uuid, String, String, Value, Option<String>, bool)> =
+        sqlx::query_as(
+            "SELECT message_id, tool_use_id, tool_name, tool_input, result, is_error \
+         FROM ch_tool_interactions WHERE message_id = ANY($1) ORDER BY executed_at ASC",
+        )
+        .bind(&ids)
+        .fetch_all(&state.db)
+        .await
+        .map_err(db_error("session_transfer"))?;
+    let mut by_message: std::collections::HashMap<uuid::Uuid, Vec<ToolInteractionInfo>> =
+        std::collections::HashMap::new();
+    for (message_id, tool_use_id, tool_name, tool_input, result, is_error) in interactions {
+        by_message
+            .entry(message_id)
+            .or_default()
+            .push(ToolInteractionInfo {
+                tool_use_id,
+                tool_name,
+                tool_input,
+                result,
+                is_error,
+            });
+    }
+
+    Ok(SessionArchive {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        title,
+        working_directory,
+        created_at,
+        messages: rows
+            .into_iter()
+            .map(
+                |(id, role, content, model, agent, timestamp)| ArchivedMessage {
+                    role,
+                    content,
+                    model,
+                    agent,
+                    timestamp,
+                    tool_interactions: by_message.remove(&id).unwrap_or_default(),
+                },
+            )
+            .collect(),
+    })
+}
+
+async fn store_archive(
+    state: &AppState,
+    archive: &SessionArchive,
+) -> Result<uuid::Uuid, sqlx::Error> {
+    let session_id = uuid::Uuid::new_v4();
+    let mut tx = state.db.begin().await?;
+    sqlx::query(
+        "INSERT INTO ch_sessions (id, title, working_directory, created_at, updated_at) \
+         VALUES ($1, $2, $3, $4, NOW())",
+    )
+    .bind(session_id)
+    .bind(&archive.title)
+    .bind(&archive.working_directory)
+    .bind(archive.created_at)
+    .execute(&mut *tx)
+    .await?;
+
+    // Messages are ordered by created_at; keep timestamps strictly increasing
+    // for sources that lack them (Markdown without stamps, CLI meta lines).
+    let mut last: Option<DateTime<Utc>> = None;
+    for m in &archive.messages {
+        let mut m = m.clone();
+        let redactions = m.redact();
+        let message_id = uuid::Uuid::new_v4();
+        let created_at = match last {
+            Some(prev) if m.timestamp <= prev => prev + chrono::Duration::milliseconds(1),
+            _ => m.timestamp,
+        };
+        last = Some(created_at);
+        sqlx::query(
+            "INSERT INTO ch_messages \
+                (id, session_id, role, content, model, agent, created_at, redactions) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(message_id)
+        .bind(session_id)
+        .bind(&m.role)
+        .bind(truncate_for_context_with_limit(
+            &m.content,
+            MAX_MESSAGE_LENGTH,
+        ))
+        .bind(&m.model)
+        .bind(&m.agent)
+        .bind(created_at)
+        .bind(redactions as i32)
+        .execute(&mut *tx)
+        .await?;
+        for ti in &m.tool_interactions {
+            sqlx::query(
+                "INSERT INTO ch_tool_interactions \
+                 (message_id, tool_use_id, tool_name, tool_input, result, is_error) \
+                 VALUES ($1, $2, $3, $4, $5, $6)",
+            )
+            .bind(message_id)
+            .bind(&ti.tool_use_id)
+            .bind(&ti.tool_name)
+            .bind(&ti.tool_input)
+            .bind(&ti.result)
+            .bind(ti.is_error)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(session_id)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<TransferFormat>,
+}
+
+/// `GET /api/sessions/{id}/export?format=markdown|json|claude-cli`
+pub async fn export_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(q): Query<ExportQuery>,
+) -> Result<impl IntoResponse, (StatusCode, Json<Value>)> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid session id" })),
+        )
+    })?;
+    let format = q.format.unwrap_or(TransferFormat::Json);
+    let mut archive = load_archive(&state, session_id).await?;
+    // Also covers messages stored before redaction was enabled.
+    for message in &mut archive.messages {
+        message.redact();
+    }
+
+    let body = match format {
+        TransferFormat::Markdown => render_markdown(&archive),
+        TransferFormat::Json => serde_json::to_string_pretty(&archive).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string() })),
+            )
+        })?,
+        TransferFormat::ClaudeCli => render_claude_cli(&archive, &id),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"session-{}.{}\"",
+                    session_id,
+                    format.extension()
+                ),
+            ),
+        ],
+        body,
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportRequest {
+    /// File to import (must be inside the allowed directories).
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Inline file content (alternative to `path`).
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Detected from the content when omitted.
+    #[serde(default)]
+    pub format: Option<TransferFormat>,
+    /// Overrides the title stored in the file.
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// `POST /api/sessions/import`
+pub async fn import_session(
+    State(state): State<AppState>,
+    Json(req): Json<ImportRequest>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let content = match (req.path, req.content) {
+        (Some(raw), None) => {
+            let path =
+                crate::tools::fs_tools::validate_path(&raw, &crate::tools::allowed_dirs_from_env())
+                    .map_err(bad_request)?;
+            let len = tokio::fs::metadata(&path)
+                .await
+                .map_err(|e| bad_request(format!("Cannot read {}: {}", path.display(), e)))?
+                .len();
+            if len > MAX_IMPORT_BYTES {
+                return Err(bad_request(format!(
+                    "File is too large (max {} bytes)",
+                    MAX_IMPORT_BYTES
+                )));
+            }
+            tokio::fs::read_to_string(&path)
+                .await
+                .map_err(|e| bad_request(format!("Cannot read {}: {}", path.display(), e)))?
+        }
+        (None, Some(content)) => content,
+        _ => {
+            return Err(bad_request(
+                "Provide exactly one of path or content".to_string(),
+            ));
+        }
+    };
+
+    let format = req.format.unwrap_or_else(|| detect_format(&content));
+    let mut archive = parse(&content, format).map_err(bad_request)?;
+    if let Some(title) = req.title.filter(|t| !t.trim().is_empty()) {
+        archive.title = title;
+    }
+    if archive.title.trim().is_empty() {
+        archive.title = "Imported session".to_string();
+    }
+
+    let session_id = store_archive(&state, &archive)
+        .await
+        .map_err(db_error("session_transfer"))?;
+    tracing::info!(
+        "session_transfer: imported {} message(s) into session {}",
+        archive.messages.len(),
+        session_id
+    );
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "id": session_id.to_string(),
+            "title": archive.title,
+            "messages": archive.messages.len(),
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> SessionArchive {
+        let t = parse_timestamp("2026-03-01T10:00:00Z").unwrap();
+        SessionArchive {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            title: "Refactor plan".to_string(),
+            working_directory: "/work/app".to_string(),
+            created_at: t,
+            messages: vec![
+                ArchivedMessage {
+                    role: "user".to_string(),
+                    content: "Split the module.".to_string(),
+                    model: None,
+                    agent: None,
+                    timestamp: t,
+                    tool_interactions: Vec::new(),
+                },
+                ArchivedMessage {
+                    role: "assistant".to_string(),
+                    content: "## Plan\n1. Move types.".to_string(),
+                    model: Some("claude-sonnet-4-6".to_string()),
+                    agent: None,
+                    timestamp: t + chrono::Duration::seconds(5),
+                    tool_interactions: Vec::new(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn markdown_round_trip() {
+        let md = render_markdown(&sample());
+        let back = parse(&md, detect_format(&md)).unwrap();
+        assert_eq!(back.title, "Refactor plan");
+        assert_eq!(back.messages.len(), 2);
+        assert_eq!(back.messages[1].model.as_deref(), Some("claude-sonnet-4-6"));
+        // `## Plan` inside a reply is content, not a new message.
+        assert_eq!(back.messages[1].content, "## Plan\n1. Move types.");
+        assert_eq!(back.messages[1].timestamp, sample().messages[1].timestamp);
+    }
+
+    #[test]
+    fn claude_cli_round_trip() {
+        let jsonl = render_claude_cli(&sample(), "abc");
+        assert_eq!(detect_format(&jsonl), TransferFormat::ClaudeCli);
+        let back = parse(&jsonl, TransferFormat::ClaudeCli).unwrap();
+        assert_eq!(back.working_directory, "/work/app");
+        assert_eq!(back.messages[0].content, "Split the module.");
+        assert_eq!(back.messages[1].role, "assistant");
+    }
+
+    #[test]
+    fn claude_cli_skips_tool_results_and_meta_events() {
+        let jsonl = concat!(
+            r#"{"type":"summary","summary":"x"}"#,
+            "\n",
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","content":"ok"}]}}"#,
+            "\n",
+            r#"{"type":"user","message":{"role":"user","content":"hi"}}"#,
+            "\n",
+        );
+        let back = parse(jsonl, TransferFormat::ClaudeCli).unwrap();
+        assert_eq!(back.messages.len(), 1);
+    }
+
+    #[test]
+    fn json_archive_round_trip() {
+        let json = serde_json::to_string(&sample()).unwrap();
+        assert_eq!(detect_format(&json), TransferFormat::Json);
+        assert_eq!(
+            parse(&json, TransferFormat::Json).unwrap().messages.len(),
+            2
+        );
+        let foreign = json.replace(ARCHIVE_FORMAT, "other.app");
+        assert!(parse(&foreign, TransferFormat::Json).is_err());
+    }
+}
@@ -4,6 +4,11 @@
 //! ClaudeHydra keeps local overrides for `get_session` and `add_session_message`
 //! because they include `ch_tool_interactions` joins and inserts — a feature
 //! specific to Claude's tool-use protocol that other Hydras don't have.
+//! `fork_session` is CH-only: it branches a conversation at a given message.
+//! `duplicate_session` copies a whole conversation onto another provider.
+//! The workspace endpoints are CH-only validated variants of the shared
+//! working-directory update: paths must exist and are stored canonicalized.
+//! Read state (`last_viewed_at`) backs the per-session unread badges.
 
 use axum::Json;
 use axum::extract::{Path, Query, State};
@@ -65,7 +70,8 @@ pub async fn get_session(
 
     let message_rows = sqlx::query_as::<_, MessageRow>(
         "SELECT * FROM (\
-            SELECT id, session_id, role, content, model, agent, created_at \
+            SELECT id, session_id, role, content, model, agent, created_at, \
+            prompt_tokens, completion_tokens, tokens_estimated, redactions \
             FROM ch_messages WHERE session_id = $1 \
             ORDER BY created_at DESC LIMIT $2 OFFSET $3\
         ) sub ORDER BY created_at ASC",
@@ -116,6 +122,7 @@ pub async fn get_session(
         .into_iter()
         .map(|m| {
             let interactions = ti_map.remove(&m.id);
+            let usage = m.usage();
             HistoryEntry {
                 id: m.id.to_string(),
                 role: m.role,
@@ -124,6 +131,8 @@ pub async fn get_session(
                 agent: m.agent,
                 timestamp: m.created_at.to_rfc3339(),
                 tool_interactions: interactions,
+                usage,
+                redactions: m.redactions.max(0) as u32,
             }
         })
         .collect();
@@ -154,7 +163,7 @@ pub async fn get_session(
 pub async fn add_session_message(
     State(state): State<AppState>,
     Path(id): Path<String>,
-    Json(req): Json<AddMessageRequest>,
+    Json(mut req): Json<AddMessageRequest>,
 ) -> Result<(StatusCode, Json<Value>), StatusCode> {
     if req.content.len() > MAX_MESSAGE_LENGTH {
         tracing::warn!(
@@ -180,16 +189,46 @@ pub async fn add_session_message(
         return Err(StatusCode::NOT_FOUND);
     }
 
+    let redactions = crate::redaction::redact_string(&mut req.content)
+        + req
+            .tool_interactions
+            .as_deref_mut()
+            .map_or(0, crate::redaction::redact_interactions);
+
+    // Assistant messages without reported usage get an estimate, so the
+    // context meter also works for sources that report none (CLIs).
+    let usage = match req.usage {
+        Some(usage) => Some(usage),
+        None if req.role == "assistant" => {
+            let context = crate::compaction::estimate_context_tokens(&state.db, &session_id)
+                .await
+                .unwrap_or(0);
+            Some(MessageUsage {
+                prompt_tokens: context as u32,
+                completion_tokens: crate::compaction::estimate_tokens(&req.content) as u32,
+                estimated: true,
+            })
+        }
+        None => None,
+    };
+
     let row = sqlx::query_as::<_, MessageRow>(
-        "INSERT INTO ch_messages (session_id, role, content, model, agent) \
-         VALUES ($1, $2, $3, $4, $5) \
-         RETURNING id, session_id, role, content, model, agent, created_at",
+        "INSERT INTO ch_messages \
+         (session_id, role, content, model, agent, prompt_tokens, completion_tokens, \
+          tokens_estimated, redactions) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) \
+         RETURNING id, session_id, role, content, model, agent, created_at, \
+         prompt_tokens, completion_tokens, tokens_estimated, redactions",
     )
     .bind(session_id)
     .bind(&req.role)
     .bind(&req.content)
     .bind(&req.model)
     .bind(&req.agent)
+    .bind(usage.map(|u| u.prompt_tokens as i32))
+    .bind(usage.map(|u| u.completion_tokens as i32))
+    .bind(usage.is_some_and(|u| u.estimated))
+    .bind(redactions as i32)
     .fetch_one(&state.db)
     .await
     .map_err(|e| {
@@ -224,7 +263,9 @@ pub async fn add_session_message(
         .execute(&state.db)
         .await
         .ok();
+    crate::compaction::schedule_auto(&state, session_id);
 
+    let usage = row.usage();
     let entry = HistoryEntry {
         id: row.id.to_string(),
         role: row.role,
@@ -233,6 +274,8 @@ pub async fn add_session_message(
         agent: row.agent,
         timestamp: row.created_at.to_rfc3339(),
         tool_interactions: req.tool_interactions,
+        usage,
+        redactions: redactions as u32,
     };
 
     Ok((
@@ -240,3 +283,770 @@ pub async fn add_session_message(
         Json(serde_json::to_value(entry).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?),
     ))
 }
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Fork session at a message
+//  CH-ONLY — branch a conversation without touching the original
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Fork `session_id` at `message_id` into a new session: the settings of the
+/// original and its messages up to and including the cut-off (ordered by
+/// `(created_at, id)`, so messages stored in the same instant split
+/// correctly), with their tool interactions and tags. `None` when the
+/// message is not part of the session. Returns `(fork id, title, messages)`.
+pub(crate) async fn fork(
+    db: &sqlx::PgPool,
+    session_id: uuid::Uuid,
+    message_id: uuid::Uuid,
+    title: Option<&str>,
+) -> Result<Option<(uuid::Uuid, String, u64)>, sqlx::Error> {
+    // The cut-off message must belong to the session being forked.
+    let cutoff: Option<chrono::DateTime<chrono::Utc>> =
+        sqlx::query_scalar("SELECT created_at FROM ch_messages WHERE id = $1 AND session_id = $2")
+            .bind(message_id)
+            .bind(session_id)
+            .fetch_optional(db)
+            .await?;
+    let Some(cutoff) = cutoff else {
+        return Ok(None);
+    };
+
+    let fork_id = uuid::Uuid::new_v4();
+    let mut tx = db.begin().await?;
+
+    // Same working directory, agent, permission mode, preset extras, group,
+    // routing and tab quota (usage so far included), so the fork resumes
+    // with the original context and budget. Copied rows get ids derived from
+    // (fork id, source id) so tool interactions can be re-pointed without a
+    // round trip.
+    let title: Option<String> = sqlx::query_scalar(
+        "INSERT INTO ch_sessions \
+            (id, title, working_directory, agent_id, permission_mode, model, \
+             system_prompt, pinned_files, witcher_mode, cli_idle_secs, group_id, \
+             token_quota, quota_used_tokens, quota_exceeded_at, \
+             forked_from_session_id, forked_from_message_id) \
+         SELECT $1, COALESCE(NULLIF(TRIM($2), ''), 'Fork of ' || title), \
+            working_directory, agent_id, permission_mode, model, \
+            system_prompt, pinned_files, witcher_mode, cli_idle_secs, group_id, \
+            token_quota, quota_used_tokens, quota_exceeded_at, id, $3 \
+         FROM ch_sessions WHERE id = $4 \
+         RETURNING title",
+    )
+    .bind(fork_id)
+    .bind(title)
+    .bind(message_id)
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let Some(title) = title else {
+        return Ok(None);
+    };
+
+    let copied = sqlx::query(
+        "INSERT INTO ch_messages \
+            (id, session_id, role, content, model, agent, created_at, redactions, \
+             prompt_tokens, completion_tokens, tokens_estimated) \
+         SELECT md5($1::TEXT || id::TEXT)::UUID, $1, role, content, model, agent, created_at, \
+            redactions, prompt_tokens, completion_tokens, tokens_estimated \
+         FROM ch_messages WHERE session_id = $2 AND (created_at, id) <= ($3, $4)",
+    )
+    .bind(fork_id)
+    .bind(session_id)
+    .bind(cutoff)
+    .bind(message_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    sqlx::query(
+        "INSERT INTO ch_tool_interactions \
+            (message_id, tool_use_id, tool_name, tool_input, result, is_error, executed_at) \
+         SELECT md5($1::TEXT || m.id::TEXT)::UUID, ti.tool_use_id, ti.tool_name, \
+            ti.tool_input, ti.result, ti.is_error, ti.executed_at \
+         FROM ch_tool_interactions ti \
+         JOIN ch_messages m ON m.id = ti.message_id \
+         WHERE m.session_id = $2 AND (m.created_at, m.id) <= ($3, $4)",
+    )
+    .bind(fork_id)
+    .bind(session_id)
+    .bind(cutoff)
+    .bind(message_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO ch_session_tags (session_id, tag) \
+         SELECT $1, tag FROM ch_session_tags WHERE session_id = $2",
+    )
+    .bind(fork_id)
+    .bind(session_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(Some((fork_id, title, copied)))
+}
+
+#[utoipa::path(post, path = "/api/sessions/{id}/fork", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    request_body = ForkSessionRequest,
+    responses((status = 201, description = "Forked session created")))]
+pub async fn fork_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<ForkSessionRequest>,
+) -> Result<(StatusCode, Json<Value>), StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let message_id: uuid::Uuid = req
+        .message_id
+        .parse()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let (fork_id, title, copied) = fork(&state.db, session_id, message_id, req.title.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to fork session: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    tracing::info!(
+        "Forked session {} at message {} into {} ({} messages)",
+        session_id,
+        message_id,
+        fork_id,
+        copied
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "id": fork_id.to_string(),
+            "title": title,
+            "forked_from": { "session_id": id, "message_id": req.message_id },
+            "message_count": copied,
+        })),
+    ))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Duplicate session onto another provider
+//  CH-ONLY — replay the same context against a different backend
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Longest tool input / result kept when tool calls are inlined as text.
+const INLINED_TOOL_CHARS: usize = 2000;
+
+#[derive(sqlx::FromRow)]
+struct DuplicatedMessageRow {
+    id: uuid::Uuid,
+    role: String,
+    content: String,
+    model: Option<String>,
+    agent: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    redactions: i32,
+    prompt_tokens: Option<i32>,
+    completion_tokens: Option<i32>,
+    tokens_estimated: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct InlinedToolRow {
+    message_id: uuid::Uuid,
+    tool_name: String,
+    tool_input: Value,
+    result: Option<String>,
+    is_error: bool,
+}
+
+/// Anthropic `tool_use` ids mean nothing to other providers, so their
+/// copies get the tool calls folded into the assistant text instead.
+fn inline_tool_calls(content: &str, calls: &[&InlinedToolRow]) -> String {
+    if calls.is_empty() {
+        return content.to_string();
+    }
+    let mut out = content.to_string();
+    out.push_str("\n\n[Tool calls]");
+    for call in calls {
+        out.push_str(&format!(
+            "\n- {}({}) {} {}",
+            call.tool_name,
+            super::truncate_for_context_with_limit(
+                &call.tool_input.to_string(),
+                INLINED_TOOL_CHARS
+            ),
+            if call.is_error { "failed:" } else { "→" },
+            super::truncate_for_context_with_limit(
+                call.result.as_deref().unwrap_or("").trim(),
+                INLINED_TOOL_CHARS
+            ),
+        ));
+    }
+    out
+}
+
+#[utoipa::path(post, path = "/api/sessions/{id}/duplicate", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    request_body = DuplicateSessionRequest,
+    responses((status = 201, description = "Duplicated session created")))]
+pub async fn duplicate_session(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<DuplicateSessionRequest>,
+) -> Result<(StatusCode, Json<Value>), StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    let model = req.model.trim();
+    if model.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Only providers the chat paths serve (WebSocket, `/api/claude/chat`).
+    let backend = super::prompt::Backend::for_model(model);
+    if !matches!(
+        backend,
+        super::prompt::Backend::Anthropic | super::prompt::Backend::Google
+    ) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let provider = backend.name();
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("Failed to duplicate session: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let copy_id = uuid::Uuid::new_v4();
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+
+    // Everything a fork keeps (group, routing, tab quota), on the new model.
+    let title: String = sqlx::query_scalar(
+        "INSERT INTO ch_sessions \
+            (id, title, working_directory, agent_id, permission_mode, model, \
+             system_prompt, pinned_files, witcher_mode, cli_idle_secs, group_id, \
+             token_quota, quota_used_tokens, quota_exceeded_at, forked_from_session_id) \
+         SELECT $1, COALESCE(NULLIF(TRIM($2), ''), title || ' (' || $3 || ')'), \
+            working_directory, agent_id, permission_mode, $4, \
+            system_prompt, pinned_files, witcher_mode, cli_idle_secs, group_id, \
+            token_quota, quota_used_tokens, quota_exceeded_at, id \
+         FROM ch_sessions WHERE id = $5 \
+         RETURNING title",
+    )
+    .bind(copy_id)
+    .bind(&req.title)
+    .bind(provider)
+    .bind(model)
+    .bind(session_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let copied = if provider == "anthropic" {
+        // Same wire format: copy rows verbatim, as a fork does.
+        let copied = sqlx::query(
+            "INSERT INTO ch_messages \
+                (id, session_id, role, content, model, agent, created_at, redactions, \
+                 prompt_tokens, completion_tokens, tokens_estimated) \
+             SELECT md5($1::TEXT || id::TEXT)::UUID, $1, role, content, model, agent, created_at, \
+                redactions, prompt_tokens, completion_tokens, tokens_estimated \
+             FROM ch_messages WHERE session_id = $2",
+        )
+        .bind(copy_id)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?
+        .rows_affected();
+
+        sqlx::query(
+            "INSERT INTO ch_tool_interactions \
+                (message_id, tool_use_id, tool_name, tool_input, result, is_error, executed_at) \
+             SELECT md5($1::TEXT || m.id::TEXT)::UUID, ti.tool_use_id, ti.tool_name, \
+                ti.tool_input, ti.result, ti.is_error, ti.executed_at \
+             FROM ch_tool_interactions ti \
+             JOIN ch_messages m ON m.id = ti.message_id \
+             WHERE m.session_id = $2",
+        )
+        .bind(copy_id)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        copied
+    } else {
+        let messages = sqlx::query_as::<_, DuplicatedMessageRow>(
+            "SELECT id, role, content, model, agent, created_at, redactions, \
+                prompt_tokens, completion_tokens, tokens_estimated \
+             FROM ch_messages WHERE session_id = $1 ORDER BY created_at ASC, id ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+        let tools = sqlx::query_as::<_, InlinedToolRow>(
+            "SELECT ti.message_id, ti.tool_name, ti.tool_input, ti.result, ti.is_error \
+             FROM ch_tool_interactions ti \
+             JOIN ch_messages m ON m.id = ti.message_id \
+             WHERE m.session_id = $1 ORDER BY ti.executed_at ASC",
+        )
+        .bind(session_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error)?;
+
+        for msg in &messages {
+            let calls: Vec<&InlinedToolRow> =
+                tools.iter().filter(|t| t.message_id == msg.id).collect();
+            sqlx::query(
+                "INSERT INTO ch_messages \
+                    (session_id, role, content, model, agent, created_at, redactions, \
+                     prompt_tokens, completion_tokens, tokens_estimated) \
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+            )
+            .bind(copy_id)
+            .bind(&msg.role)
+            .bind(inline_tool_calls(&msg.content, &calls))
+            .bind(&msg.model)
+            .bind(&msg.agent)
+            .bind(msg.created_at)
+            .bind(msg.redactions)
+            .bind(msg.prompt_tokens)
+            .bind(msg.completion_tokens)
+            .bind(msg.tokens_estimated)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        }
+        messages.len() as u64
+    };
+
+    sqlx::query(
+        "INSERT INTO ch_session_tags (session_id, tag) \
+         SELECT $1, tag FROM ch_session_tags WHERE session_id = $2",
+    )
+    .bind(copy_id)
+    .bind(session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    // The compaction summary is provider-neutral text; keep it pinned.
+    sqlx::query(
+        "INSERT INTO ch_session_summaries \
+            (session_id, summary, covers_until, summarized_messages, tokens_before, \
+             tokens_after, model) \
+         SELECT $1, summary, covers_until, summarized_messages, tokens_before, \
+            tokens_after, model \
+         FROM ch_session_summaries WHERE session_id = $2",
+    )
+    .bind(copy_id)
+    .bind(session_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    tx.commit().await.map_err(db_error)?;
+    tracing::info!(
+        "Duplicated session {} into {} on {} ({}, {} messages)",
+        session_id,
+        copy_id,
+        model,
+        provider,
+        copied
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "id": copy_id.to_string(),
+            "title": title,
+            "model": model,
+            "provider": provider,
+            "duplicated_from": id,
+            "message_count": copied,
+        })),
+    ))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Per-session workspace
+//  CH-ONLY — validated working directory at creation / on change
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Canonicalize a workspace path. Empty means "inherit the global working
+/// directory"; anything else must be an existing directory.
+pub(crate) fn normalize_workspace(raw: &str) -> Result<String, String> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return Ok(String::new());
+    }
+    let path = std::fs::canonicalize(raw).map_err(|e| format!("{}: {}", raw, e))?;
+    if !path.is_dir() {
+        return Err(format!("{} is not a directory", raw));
+    }
+    let path = path.to_string_lossy();
+    // Windows canonical paths carry a verbatim prefix the CLIs don't accept.
+    Ok(path.strip_prefix(r"\\?\").unwrap_or(&path).to_string())
+}
+
+/// Effective working directory for a session: its own, else the global one.
+/// `None` when the session does not exist.
+pub(crate) async fn session_working_directory(
+    db: &sqlx::PgPool,
+    session_id: &uuid::Uuid,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(NULLIF(s.working_directory, ''), g.working_directory, '') \
+         FROM ch_sessions s LEFT JOIN ch_settings g ON g.id = 1 WHERE s.id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(db)
+    .await
+}
+
+#[utoipa::path(post, path = "/api/sessions/with-workspace", tag = "sessions",
+    request_body = CreateSessionWithWorkspaceRequest,
+    responses((status = 201, description = "Session created"),
+        (status = 400, description = "Invalid working directory")))]
+pub async fn create_session_with_workspace(
+    State(state): State<AppState>,
+    Json(req): Json<CreateSessionWithWorkspaceRequest>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let title = req.title.trim();
+    if title.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Title is required" })),
+        ));
+    }
+    let wd = normalize_workspace(&req.working_directory)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+    let row = sqlx::query_as::<_, SessionRow>(
+        "INSERT INTO ch_sessions (title, working_directory) VALUES ($1, $2) \
+         RETURNING id, title, created_at, updated_at, working_directory",
+    )
+    .bind(title)
+    .bind(&wd)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to create session: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Database error" })),
+        )
+    })?;
+
+    let summary = SessionSummary {
+        id: row.id.to_string(),
+        title: row.title,
+        created_at: row.created_at.to_rfc3339(),
+        message_count: 0,
+        working_directory: row.working_directory,
+    };
+    Ok((StatusCode::CREATED, Json(json!(summary))))
+}
+
+#[utoipa::path(put, path = "/api/sessions/{id}/workspace", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    request_body = UpdateWorkingDirectoryRequest,
+    responses((status = 200, description = "Workspace updated"),
+        (status = 400, description = "Invalid working directory")))]
+pub async fn set_session_workspace(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateWorkingDirectoryRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid session id" })),
+        )
+    })?;
+    let wd = normalize_workspace(&req.working_directory)
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+
+    let updated = sqlx::query(
+        "UPDATE ch_sessions SET working_directory = $1, updated_at = NOW() WHERE id = $2",
+    )
+    .bind(&wd)
+    .bind(session_id)
+    .execute(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to set session workspace: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Database error" })),
+        )
+    })?
+    .rows_affected();
+    if updated == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Session not found" })),
+        ));
+    }
+
+    Ok(Json(json!({
+        "id": id,
+        "working_directory": wd,
+        "effective_working_directory": session_working_directory(&state.db, &session_id)
+            .await
+            .ok()
+            .flatten(),
+    })))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Read state
+//  CH-ONLY — assistant replies newer than `last_viewed_at` are unread
+// ═══════════════════════════════════════════════════════════════════════
+
+#[utoipa::path(get, path = "/api/sessions/unread", tag = "sessions",
+    responses((status = 200, description = "Unread assistant message counts per session")))]
+pub async fn list_unread_sessions(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let rows: Vec<(uuid::Uuid, i64, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
+        "SELECT s.id, COUNT(m.id), s.last_viewed_at \
+         FROM ch_sessions s \
+         JOIN ch_messages m ON m.session_id = s.id \
+              AND m.role = 'assistant' AND m.created_at > s.last_viewed_at \
+         GROUP BY s.id, s.last_viewed_at \
+         ORDER BY MAX(m.created_at) DESC",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to count unread messages: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let total: i64 = rows.iter().map(|(_, n, _)| n).sum();
+    let sessions: Vec<Value> = rows
+        .into_iter()
+        .map(|(id, unread, viewed)| {
+            json!({
+                "session_id": id.to_string(),
+                "unread_count": unread,
+                "last_viewed_at": viewed.to_rfc3339(),
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "sessions": sessions, "total_unread": total })))
+}
+
+#[utoipa::path(post, path = "/api/sessions/{id}/read", tag = "sessions",
+    params(("id" = String, Path, description = "Session UUID")),
+    responses((status = 200, description = "Session marked as read"),
+        (status = 404, description = "Session not found")))]
+pub async fn mark_session_read(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let session_id: uuid::Uuid = id.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+    // Deliberately leaves `updated_at` alone — viewing is not an edit.
+    let viewed: chrono::DateTime<chrono::Utc> = sqlx::query_scalar(
+        "UPDATE ch_sessions SET last_viewed_at = NOW() WHERE id = $1 RETURNING last_viewed_at",
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to mark session read: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(json!({
+        "session_id": id,
+        "unread_count": 0,
+        "last_viewed_at": viewed.to_rfc3339(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tool_calls_are_inlined_for_other_providers() {
+        let call = InlinedToolRow {
+            message_id: uuid::Uuid::nil(),
+            tool_name: "read_file".into(),
+            tool_input: json!({ "path": "src/main.rs" }),
+            result: Some("fn main() {}\n".into()),
+            is_error: false,
+        };
+        let failed = InlinedToolRow {
+            message_id: uuid::Uuid::nil(),
+            tool_name: "git_diff".into(),
+            tool_input: json!({}),
+            result: Some("not a repository".into()),
+            is_error: true,
+        };
+        assert_eq!(inline_tool_calls("Plain answer", &[]), "Plain answer");
+        let text = inline_tool_calls("Looked it up.", &[&call, &failed]);
+        assert!(text.starts_with("Looked it up.\n\n[Tool calls]"));
+        assert!(text.contains(r#"- read_file({"path":"src/main.rs"}) → fn main() {}"#));
+        assert!(text.contains("- git_diff("));
+        assert!(text.ends_with("failed: not a repository"));
+    }
+
+    #[test]
+    fn workspace_is_canonicalized_and_must_be_a_directory() {
+        assert_eq!(normalize_workspace("  ").unwrap(), "");
+
+        let dir = std::env::temp_dir();
+        let canonical = normalize_workspace(&dir.join(".").to_string_lossy()).unwrap();
+        assert!(!canonical.ends_with('.'));
+        assert!(std::path::Path::new(&canonical).is_dir());
+
+        let file = dir.join(format!("ch-workspace-{}.txt", uuid::Uuid::new_v4()));
+        std::fs::write(&file, b"x").unwrap();
+        assert!(normalize_workspace(&file.to_string_lossy()).is_err());
+        std::fs::remove_file(&file).unwrap();
+
+        assert!(normalize_workspace("/definitely/not/a/real/dir").is_err());
+    }
+
+    /// Migrated pool for the database-backed tests; `None` (test skipped)
+    /// unless `TEST_DATABASE_URL` points at a scratch Postgres.
+    async fn test_db() -> Option<sqlx::PgPool> {
+        let url = std::env::var("TEST_DATABASE_URL").ok()?;
+        let db = sqlx::PgPool::connect(&url)
+            .await
+            .expect("connect TEST_DATABASE_URL");
+        sqlx::migrate!("./migrations")
+            .run(&db)
+            .await
+            .expect("migrations");
+        Some(db)
+    }
+
+    async fn insert_message(
+        db: &sqlx::PgPool,
+        session_id: uuid::Uuid,
+        id: uuid::Uuid,
+        created_at: chrono::DateTime<chrono::Utc>,
+    ) {
+        sqlx::query(
+            "INSERT INTO ch_messages \
+                (id, session_id, role, content, created_at, \
+                 prompt_tokens, completion_tokens, tokens_estimated) \
+             VALUES ($1, $2, 'assistant', 'hi', $3, 12, 34, TRUE)",
+        )
+        .bind(id)
+        .bind(session_id)
+        .bind(created_at)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn fork_copies_messages_up_to_the_cut_off_only() {
+        let Some(db) = test_db().await else { return };
+        let session_id: uuid::Uuid =
+            sqlx::query_scalar("INSERT INTO ch_sessions (title) VALUES ('Original') RETURNING id")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        let t0 = chrono::Utc::now() - chrono::Duration::seconds(10);
+        let first = uuid::Uuid::new_v4();
+        // Two messages stored in the same instant: the cut-off is the lower id.
+        let mut tied = [uuid::Uuid::new_v4(), uuid::Uuid::new_v4()];
+        tied.sort();
+        let [cut, after] = tied;
+        insert_message(&db, session_id, first, t0).await;
+        insert_message(&db, session_id, cut, t0 + chrono::Duration::seconds(1)).await;
+        insert_message(&db, session_id, after, t0 + chrono::Duration::seconds(1)).await;
+        for message_id in [cut, after] {
+            sqlx::query(
+                "INSERT INTO ch_tool_interactions (message_id, tool_use_id, tool_name) \
+                 VALUES ($1, 'toolu_1', 'read_file')",
+            )
+            .bind(message_id)
+            .execute(&db)
+            .await
+            .unwrap();
+        }
+
+        let (fork_id, title, copied) = fork(&db, session_id, cut, None).await.unwrap().unwrap();
+        assert_eq!(title, "Fork of Original");
+        assert_eq!(copied, 2);
+        let tools: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM ch_tool_interactions ti \
+             JOIN ch_messages m ON m.id = ti.message_id WHERE m.session_id = $1",
+        )
+        .bind(fork_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(tools, 1);
+
+        // A message from another session is not a valid cut-off.
+        assert!(fork(&db, fork_id, after, None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn fork_inherits_quota_routing_and_token_counts() {
+        let Some(db) = test_db().await else { return };
+        let group_id: uuid::Uuid =
+            sqlx::query_scalar("INSERT INTO ch_session_groups (name) VALUES ('g') RETURNING id")
+                .fetch_one(&db)
+                .await
+                .unwrap();
+        let session_id: uuid::Uuid = sqlx::query_scalar(
+            "INSERT INTO ch_sessions \
+                (title, token_quota, quota_used_tokens, quota_exceeded_at, \
+                 witcher_mode, cli_idle_secs, group_id) \
+             VALUES ('Budgeted', 1000, 1000, NOW(), TRUE, 90, $1) RETURNING id",
+        )
+        .bind(group_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        let message_id = uuid::Uuid::new_v4();
+        insert_message(&db, session_id, message_id, chrono::Utc::now()).await;
+
+        let (fork_id, title, _) = fork(&db, session_id, message_id, Some("Branch"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(title, "Branch");
+
+        let (quota, used, exceeded, witcher, idle, group): (
+            Option<i64>,
+            i64,
+            bool,
+            bool,
+            Option<i32>,
+            Option<uuid::Uuid>,
+        ) = sqlx::query_as(
+            "SELECT token_quota, quota_used_tokens, quota_exceeded_at IS NOT NULL, \
+                witcher_mode, cli_idle_secs, group_id \
+             FROM ch_sessions WHERE id = $1",
+        )
+        .bind(fork_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(quota, Some(1000));
+        assert_eq!(used, 1000);
+        assert!(exceeded);
+        assert!(witcher);
+        assert_eq!(idle, Some(90));
+        assert_eq!(group, Some(group_id));
+
+        let tokens: (Option<i32>, Option<i32>, bool) = sqlx::query_as(
+            "SELECT prompt_tokens, completion_tokens, tokens_estimated \
+             FROM ch_messages WHERE session_id = $1",
+        )
+        .bind(fork_id)
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(tokens, (Some(12), Some(34), true));
+    }
+}
@@ -0,0 +1,375 @@
+//! Anthropic streaming without tools — Messages API SSE → NDJSON translation.
+//!
+//! `text_delta` events become `token` lines. Usage is read from
+//! `message_start` (input tokens) and the final `message_delta` (output
+//! tokens), the stop reason from `message_delta.delta.stop_reason`. The final
+//! line carries both as the canonical stream event fields (`stream_event.rs`)
+//! next to the legacy `token` / `done` / `model` / `total_tokens`, and the
+//! reported token counts are recorded instead of estimates.
+//!
+//! When the request cannot be sent or Anthropic answers with a retryable
+//! status, the shared `jaskier_core` handler takes over, which adds the
+//! fallback models.
+
+use std::time::Instant;
+
+use axum::Json;
+use axum::body::Body;
+use axum::http::StatusCode;
+use axum::response::Response;
+use serde_json::{Value, json};
+
+use jaskier_core::handlers::anthropic_streaming::{
+    self, AnthropicChatContext, build_ndjson_response, sanitize_api_error,
+};
+
+use crate::state::AppState;
+use crate::stream_event::{self, StreamEvent, Usage};
+
+use super::super::{is_retryable_status, sanitize_json_strings, send_to_anthropic};
+use super::helpers::send_task_complete_notification;
+
+const STREAM_TIMEOUT_SECS: u64 = 300;
+
+/// What the events of one response have added up to so far.
+#[derive(Debug, Default)]
+struct Progress {
+    usage: Usage,
+    /// Anthropic `stop_reason`; empty until `message_delta` reports it.
+    stop_reason: String,
+    output_chars: usize,
+    /// Message of an `error` event; ends the stream.
+    error: Option<String>,
+}
+
+/// Fold one SSE event into `progress`; the text it adds, if any.
+fn apply_event(event: &Value, progress: &mut Progress) -> Option<String> {
+    stream_event::anthropic_usage(event, &mut progress.usage);
+    match event.get("type").and_then(Value::as_str) {
+        Some("content_block_delta") => {
+            let text = event
+                .pointer("/delta/text")
+                .and_then(Value::as_str)
+                .filter(|t| !t.is_empty())?;
+            progress.output_chars += text.chars().count();
+            Some(text.to_string())
+        }
+        Some("message_delta") => {
+            if let Some(reason) = event.pointer("/delta/stop_reason").and_then(Value::as_str) {
+                progress.stop_reason = reason.to_string();
+            }
+            None
+        }
+        Some("error") => {
+            let message = event
+                .pointer("/error/message")
+                .and_then(Value::as_str)
+                .unwrap_or("stream error");
+            progress.error = Some(message.to_string());
+            None
+        }
+        _ => None,
+    }
+}
+
+fn ndjson_line(frame: &Value) -> axum::body::Bytes {
+    axum::body::Bytes::from(format!("{}\n", frame))
+}
+
+/// `POST /api/claude/chat/stream` without tools, for Anthropic models.
+pub(crate) async fn anthropic_chat_stream(
+    state: AppState,
+    ctx: AnthropicChatContext,
+    messages: Vec<Value>,
+    prompt_len: usize,
+) -> Result<Response, (StatusCode, Json<Value>)> {
+    let mut body = json!({
+        "model": ctx.model,
+        "max_tokens": ctx.max_tokens,
+        "temperature": ctx.temperature,
+        "system": ctx.system_prompt,
+        "messages": &messages,
+        "stream": true,
+    });
+    sanitize_json_strings(&mut body);
+
+    let resp = match send_to_anthropic(&state, &body, STREAM_TIMEOUT_SECS).await {
+        Ok(resp) if resp.status().is_success() => resp,
+        Ok(resp) if !is_retryable_status(resp.status().as_u16()) => {
+            let status = resp.status();
+            let err = resp.text().await.unwrap_or_default();
+            tracing::error!("Anthropic API error (status={}): {}", status, err);
+            return Err((
+                StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+                Json(json!({ "error": sanitize_api_error(&err) })),
+            ));
+        }
+        _ => {
+            tracing::warn!("anthropic stream: request failed, using the shared fallback handler");
+            return anthropic_streaming::anthropic_ndjson_stream_no_tools(
+                &state, &ctx, messages, prompt_len,
+            )
+            .await
+            .map(with_stream_events);
+        }
+    };
+
+    let request_id = stream_event::current_request_id();
+    let started = Instant::now();
+    let mut byte_stream = resp.bytes_stream();
+
+    let ndjson_stream = async_stream::stream! {
+        let mut sse_buffer = String::new();
+        let mut progress = Progress::default();
+
+        'read: while let Some(chunk) = futures_util::StreamExt::next(&mut byte_stream).await {
+            let chunk = match chunk {
+                Ok(b) => b,
+                Err(e) => {
+                    tracing::error!("Anthropic SSE stream error: {}", e);
+                    progress.error = Some(e.to_string());
+                    break;
+                }
+            };
+            sse_buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+            while let Some(nl) = sse_buffer.find('\n') {
+                // '\n' is ASCII — find() returns a char boundary
+                let line = sse_buffer[..nl].trim().to_string();
+                sse_buffer.drain(..=nl);
+                let Some(data) = line.strip_prefix("data:") else {
+                    continue;
+                };
+                let Ok(event) = serde_json::from_str::<Value>(data.trim()) else {
+                    continue;
+                };
+                if let Some(text) = apply_event(&event, &mut progress) {
+                    let frame = stream_event::ndjson_token(&request_id, "anthropic", &text);
+                    yield Ok::<_, std::io::Error>(ndjson_line(&frame));
+                }
+                if progress.error.is_some() {
+                    break 'read;
+                }
+            }
+        }
+
+        let usage = progress.usage;
+        let reported = usage != Usage::default();
+        let (reason, token) = match &progress.error {
+            Some(e) => {
+                tracing::error!("Anthropic stream ended with an error: {}", e);
+                ("error", "\n[Stream interrupted]")
+            }
+            None => (progress.stop_reason.as_str(), ""),
+        };
+        let final_usage = reported.then_some(usage);
+        let event = StreamEvent::finish(&request_id, "anthropic", reason, final_usage);
+        let frame = stream_event::ndjson_done(&event, token, json!({
+            "model": &ctx.model,
+            "total_tokens": usage.input_tokens + usage.output_tokens,
+        }));
+        yield Ok::<_, std::io::Error>(ndjson_line(&frame));
+
+        let usage = if reported {
+            usage
+        } else {
+            // No usage events (interrupted early): estimate like the shared handler.
+            Usage {
+                input_tokens: (prompt_len / 4) as u64,
+                output_tokens: (progress.output_chars / 4) as u64,
+            }
+        };
+        record_stream_usage(
+            &state,
+            &ctx.model,
+            usage,
+            !reported,
+            started.elapsed().as_millis(),
+        );
+        let (state, model) = (state.clone(), ctx.model.clone());
+        tokio::spawn(async move {
+            send_task_complete_notification(&state, &model).await;
+        });
+    };
+
+    Ok(build_ndjson_response(Body::from_stream(ndjson_stream)))
+}
+
+/// A response of the shared handler with the canonical stream event fields
+/// added to each NDJSON line (`stream_event::from_ndjson_line`).
+pub(super) fn with_stream_events(response: Response) -> Response {
+    if !response.status().is_success() {
+        return response;
+    }
+    let request_id = stream_event::current_request_id();
+    let (mut parts, body) = response.into_parts();
+    parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+    let mut chunks = body.into_data_stream();
+
+    let lines = async_stream::stream! {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut provider = "anthropic".to_string();
+        while let Some(chunk) = futures_util::StreamExt::next(&mut chunks).await {
+            match chunk {
+                Ok(bytes) => buffer.extend_from_slice(&bytes),
+                Err(e) => {
+                    yield Err(std::io::Error::other(e));
+                    return;
+                }
+            }
+            while let Some(nl) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=nl).collect();
+                yield Ok(canonical_line(&line, &request_id, &mut provider));
+            }
+        }
+        if !buffer.is_empty() {
+            yield Ok(canonical_line(&buffer, &request_id, &mut provider));
+        }
+    };
+    Response::from_parts(parts, Body::from_stream(lines))
+}
+
+/// One NDJSON line with the canonical fields added; a `fallback` event
+/// switches `provider` for the lines after it. Lines that are not JSON
+/// objects pass unchanged.
+fn canonical_line(line: &[u8], request_id: &str, provider: &mut String) -> axum::body::Bytes {
+    match serde_json::from_slice::<Value>(line) {
+        Ok(frame) if frame.is_object() => {
+            if frame.get("type").and_then(Value::as_str) == Some("fallback")
+                && let Some(to) = frame.get("to").and_then(Value::as_str)
+            {
+                *provider = to.to_string();
+            }
+            let event = stream_event::from_ndjson_line(&frame, request_id, provider);
+            ndjson_line(&event.merged(frame))
+        }
+        _ => axum::body::Bytes::copy_from_slice(line),
+    }
+}
+
+/// Usage tier of a Claude / Gemini model for `ch_agent_usage`.
+fn usage_tier(model: &str) -> &'static str {
+    if model.contains("opus") {
+        "commander"
+    } else if model.contains("sonnet") {
+        "coordinator"
+    } else if model.contains("haiku") {
+        "executor"
+    } else if model.contains("flash") {
+        "flash"
+    } else {
+        "coordinator"
+    }
+}
+
+/// Record one finished stream in `ch_agent_usage` and the cost ledger
+/// (fire-and-forget). `estimated` when the provider reported no usage.
+pub(super) fn record_stream_usage(
+    state: &AppState,
+    model: &str,
+    usage: Usage,
+    estimated: bool,
+    latency_ms: u128,
+) {
+    let latency = latency_ms.min(i32::MAX as u128) as i32;
+    let input = usage.input_tokens.min(i32::MAX as u64) as i32;
+    let output = usage.output_tokens.min(i32::MAX as u64) as i32;
+    let db = state.db.clone();
+    let m = model.to_string();
+    tokio::spawn(async move {
+        let _ = sqlx::query(
+            "INSERT INTO ch_agent_usage (agent_id, model, input_tokens, output_tokens, total_tokens, latency_ms, success, tier) \
+             VALUES (NULL, $1, $2, $3, $4, $5, TRUE, $6)",
+        )
+        .bind(&m)
+        .bind(input)
+        .bind(output)
+        .bind(input.saturating_add(output))
+        .bind(latency)
+        .bind(usage_tier(&m))
+        .execute(&db)
+        .await;
+    });
+
+    crate::costs::schedule(
+        &state.db,
+        crate::costs::CostEvent {
+            model: model.to_string(),
+            session_id: None,
+            requests: 1,
+            input_tokens: input as i64,
+            output_tokens: output as i64,
+            estimated,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_usage_and_stop_reason_are_read_from_the_events() {
+        let events = [
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 25, "output_tokens": 1 } } }),
+            json!({ "type": "content_block_start", "index": 0, "content_block": { "type": "text", "text": "" } }),
+            json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "Cześć" } }),
+            json!({ "type": "content_block_delta", "index": 0, "delta": { "type": "text_delta", "text": "!" } }),
+            json!({ "type": "content_block_stop", "index": 0 }),
+            json!({ "type": "message_delta", "delta": { "stop_reason": "max_tokens" }, "usage": { "output_tokens": 12 } }),
+            json!({ "type": "message_stop" }),
+        ];
+        let mut progress = Progress::default();
+        let text: Vec<String> = events
+            .iter()
+            .filter_map(|e| apply_event(e, &mut progress))
+            .collect();
+        assert_eq!(text, ["Cześć", "!"]);
+        assert_eq!(progress.output_chars, 6);
+        assert_eq!(
+            progress.usage,
+            Usage {
+                input_tokens: 25,
+                output_tokens: 12
+            }
+        );
+        assert_eq!(stream_event::finish_reason(&progress.stop_reason), "length");
+        assert_eq!(progress.error, None);
+    }
+
+    #[test]
+    fn error_events_end_the_stream() {
+        let mut progress = Progress::default();
+        let overloaded = json!({ "type": "error", "error": { "message": "Overloaded" } });
+        assert_eq!(apply_event(&overloaded, &mut progress), None);
+        assert_eq!(progress.error.as_deref(), Some("Overloaded"));
+    }
+
+    #[test]
+    fn shared_handler_lines_are_converted() {
+        let mut provider = "anthropic".to_string();
+        let token = br#"{"token":"Hi","done":false}"#;
+        let frame: Value =
+            serde_json::from_slice(&canonical_line(token, "req-1", &mut provider)).unwrap();
+        assert_eq!(frame["token"], "Hi");
+        assert_eq!(frame["delta"], "Hi");
+        assert_eq!(frame["provider"], "anthropic");
+
+        let fallback = br#"{"type":"fallback","from":"anthropic","to":"deepseek"}"#;
+        canonical_line(fallback, "req-1", &mut provider);
+        let done = br#"{"token":"","done":true}"#;
+        let frame: Value =
+            serde_json::from_slice(&canonical_line(done, "req-1", &mut provider)).unwrap();
+        assert_eq!(frame["provider"], "deepseek");
+        assert_eq!(frame["finish_reason"], "stop");
+        let raw = canonical_line(b"not json\n", "req-1", &mut provider);
+        assert_eq!(&raw[..], b"not json\n");
+    }
+
+    #[test]
+    fn usage_tiers_follow_the_model_family() {
+        assert_eq!(usage_tier("claude-opus-4-6"), "commander");
+        assert_eq!(usage_tier("claude-haiku-4-5-20251001"), "executor");
+        assert_eq!(usage_tier("claude-sonnet-4-6"), "coordinator");
+    }
+}
@@ -1,4 +1,15 @@
 //! Gemini hybrid streaming — Google API SSE → NDJSON translation.
+//!
+//! Requests for `gemini-*` models go straight to the Generative Language API
+//! with the configured Google credential (API key or OAuth). The aliases
+//! `gemini-flash` / `gemini-pro` resolve to the newest Flash / Pro model from
+//! the registry (pins respected). Client `system` messages are folded into
+//! `systemInstruction`, and safety settings come from `GEMINI_SAFETY_THRESHOLD`.
+//! Requests are refused once the Google budget cap is reached (`budget.rs`).
+//! Lines carry the canonical stream event fields (`stream_event.rs`) next to
+//! the legacy `token` / `done`.
+//!
+//! - `GET /api/gemini/models` — text-generation models available to the key
 
 use axum::Json;
 use axum::body::Body;
@@ -10,8 +21,12 @@ use jaskier_core::handlers::anthropic_streaming::{
     build_ndjson_response, sanitize_api_error,
 };
 
+use crate::ai_gateway::AiProvider;
+use crate::ai_gateway::attachments::{ResolvedImage, attach_images};
+use crate::model_registry;
 use crate::models::*;
 use crate::state::AppState;
+use crate::stream_event::{self, StreamEvent};
 
 use crate::handlers::prompt::ChatContext;
 
@@ -19,7 +34,9 @@ pub(crate) async fn google_chat_stream(
     state: AppState,
     req: ChatRequest,
     ctx: ChatContext,
+    images: &[ResolvedImage],
 ) -> Result<Response, (StatusCode, Json<Value>)> {
+    crate::budget::ensure_within(&state.db, "google").await?;
     let credential = jaskier_oauth::google::get_google_credential(&state).await;
     let (api_key, is_oauth) = match credential {
         Some(c) => c,
@@ -31,15 +48,18 @@ pub(crate) async fn google_chat_stream(
         }
     };
 
-    let model = &ctx.model;
+    let model = resolve_gemini_model(&state, &ctx.model).await;
     let url = format!(
-        "https://generativelanguage.googleapis.com/v1beta/models/{}:streamGenerateContent?alt=sse",
-        model
+        "{}/models/{}:streamGenerateContent?alt=sse",
+        GEMINI_API_BASE, model
     );
 
-    let contents: Vec<Value> = req
+    let system_instruction = system_instruction(&ctx.system_prompt, &req.messages);
+
+    let mut contents: Vec<Value> = req
         .messages
         .iter()
+        .filter(|m| m.role != "system")
         .map(|m| {
             let role = if m.role == "assistant" {
                 "model"
@@ -49,10 +69,12 @@ pub(crate) async fn google_chat_stream(
             json!({ "role": role, "parts": [{ "text": m.content }] })
         })
         .collect();
+    attach_images(&AiProvider::Google, &mut contents, images);
 
     let body = json!({
-        "systemInstruction": { "parts": [{ "text": ctx.system_prompt }] },
+        "systemInstruction": { "parts": [{ "text": system_instruction }] },
         "contents": contents,
+        "safetySettings": safety_settings(),
         "generationConfig": {
             "temperature": req.temperature.unwrap_or(1.0),
             "maxOutputTokens": ctx.max_tokens,
@@ -83,12 +105,15 @@ pub(crate) async fn google_chat_stream(
         ));
     }
 
-    let model_for_done = ctx.model.clone();
+    let model_for_done = model;
     let byte_stream = resp.bytes_stream();
+    let request_id = stream_event::current_request_id();
 
     let ndjson_stream = async_stream::stream! {
         let mut sse_buffer = String::new();
         let mut total_tokens: u32 = 0;
+        let mut usage = None;
+        let mut finish_reason = String::new();
         let mut stream = byte_stream;
 
         while let Some(chunk_result) = futures_util::StreamExt::next(&mut stream).await {
@@ -96,7 +121,8 @@ pub(crate) async fn google_chat_stream(
                 Ok(b) => b,
                 Err(e) => {
                     tracing::error!("Google SSE stream error: {}", e);
-                    let err_line = serde_json::to_string(&json!({ "token": "\n[Stream interrupted]", "done": true, "model": &model_for_done })).unwrap_or_default();
+                    let event = StreamEvent::finish(&request_id, "google", "error", usage);
+                    let err_line = stream_event::ndjson_done(&event, "\n[Stream interrupted]", json!({ "model": &model_for_done })).to_string();
                     yield Ok::<_, std::io::Error>(axum::body::Bytes::from(format!("{}\n", err_line)));
                     break;
                 }
@@ -112,18 +138,370 @@ pub(crate) async fn google_chat_stream(
                     && let Ok(event) = serde_json::from_str::<Value>(data) {
                         if let Some(text) = event.pointer("/candidates/0/content/parts/0/text").and_then(|t| t.as_str())
                             && !text.is_empty() {
-                                let ndjson_line = serde_json::to_string(&json!({ "token": text, "done": false })).unwrap_or_default();
+                                let ndjson_line = stream_event::ndjson_token(&request_id, "google", text).to_string();
                                 yield Ok::<_, std::io::Error>(axum::body::Bytes::from(format!("{}\n", ndjson_line)));
                             }
-                        if let Some(usage) = event.get("usageMetadata") {
-                            total_tokens = usage.get("totalTokenCount").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                        if let Some(meta) = event.get("usageMetadata") {
+                            total_tokens = meta.get("totalTokenCount").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+                            usage = stream_event::response_usage(&event);
+                        }
+                        if let Some(reason) = stream_event::response_finish_reason(&event) {
+                            finish_reason = reason.to_string();
                         }
                     }
             }
         }
-        let done_line = serde_json::to_string(&json!({ "token": "", "done": true, "model": &model_for_done, "total_tokens": total_tokens })).unwrap_or_default();
+        let event = StreamEvent::finish(&request_id, "google", &finish_reason, usage);
+        let done_line = stream_event::ndjson_done(&event, "", json!({ "model": &model_for_done, "total_tokens": total_tokens })).to_string();
         yield Ok::<_, std::io::Error>(axum::body::Bytes::from(format!("{}\n", done_line)));
     };
 
     Ok(build_ndjson_response(Body::from_stream(ndjson_stream)))
 }
+
+/// One non-streaming `generateContent` turn (background prompts, transcripts):
+/// `prompt` under the server-side system prompt; returns the reply text.
+pub(crate) async fn google_complete(
+    state: &AppState,
+    ctx: &ChatContext,
+    prompt: &str,
+    timeout_secs: u64,
+) -> Result<String, String> {
+    let messages = [json!({ "role": "user", "content": prompt })];
+    google_chat(
+        state,
+        &ctx.model,
+        &ctx.system_prompt,
+        ctx.max_tokens,
+        &messages,
+        &[],
+        timeout_secs,
+    )
+    .await
+}
+
+/// One non-streaming `generateContent` turn over a conversation of
+/// `{"role", "content"}` messages (as loaded from `ch_messages`), with
+/// `images` attached to the last user turn — the WebSocket and
+/// `/api/claude/chat` path for Gemini models.
+pub(crate) async fn google_chat(
+    state: &AppState,
+    model: &str,
+    system_prompt: &str,
+    max_tokens: u32,
+    messages: &[Value],
+    images: &[ResolvedImage],
+    timeout_secs: u64,
+) -> Result<String, String> {
+    crate::profiles::ensure_cloud().map_err(|(_, Json(err))| crate::handlers::prompt::error_text(&err))?;
+    crate::budget::ensure_within(&state.db, "google")
+        .await
+        .map_err(|(_, Json(err))| crate::handlers::prompt::error_text(&err))?;
+    let Some((api_key, is_oauth)) = jaskier_oauth::google::get_google_credential(state).await
+    else {
+        return Err("No Google API credential configured".to_string());
+    };
+    let model = resolve_gemini_model(state, model).await;
+    let url = format!("{}/models/{}:generateContent", GEMINI_API_BASE, model);
+    let mut contents = gemini_contents(messages);
+    attach_images(&AiProvider::Google, &mut contents, images);
+    let body = json!({
+        "systemInstruction": { "parts": [{ "text": system_prompt }] },
+        "contents": contents,
+        "safetySettings": safety_settings(),
+        "generationConfig": { "maxOutputTokens": max_tokens },
+    });
+    let resp =
+        jaskier_oauth::google::apply_google_auth(state.http_client.post(&url), &api_key, is_oauth)
+            .json(&body)
+            .timeout(std::time::Duration::from_secs(timeout_secs))
+            .send()
+            .await
+            .map_err(|e| {
+                tracing::error!("Google API request failed: {}", e);
+                "AI provider request failed".to_string()
+            })?;
+    let status = resp.status();
+    if !status.is_success() {
+        let err = resp.text().await.unwrap_or_default();
+        tracing::error!("Google API error (status={}): {}", status, err);
+        return Err(format!(
+            "Provider returned HTTP {}: {}",
+            status.as_u16(),
+            sanitize_api_error(&err)
+        ));
+    }
+    let reply: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid provider response: {}", e))?;
+    Ok(reply_text(&reply))
+}
+
+/// The server-built system prompt extended with the client's `system` messages.
+fn system_instruction(system_prompt: &str, messages: &[ChatMessage]) -> String {
+    let mut instruction = system_prompt.to_string();
+    for m in messages.iter().filter(|m| m.role == "system") {
+        if !m.content.trim().is_empty() {
+            instruction.push_str("\n\n");
+            instruction.push_str(&m.content);
+        }
+    }
+    instruction
+}
+
+/// `{"role", "content"}` messages as Gemini `contents`; `system` messages go
+/// to `systemInstruction` instead.
+fn gemini_contents(messages: &[Value]) -> Vec<Value> {
+    messages
+        .iter()
+        .filter(|m| m["role"] != "system")
+        .map(|m| {
+            let role = if m["role"] == "assistant" { "model" } else { "user" };
+            json!({ "role": role, "parts": [{ "text": m["content"].as_str().unwrap_or_default() }] })
+        })
+        .collect()
+}
+
+/// Text of a `generateContent` reply: every text part of the first candidate.
+fn reply_text(reply: &Value) -> String {
+    reply
+        .pointer("/candidates/0/content/parts")
+        .and_then(|p| p.as_array())
+        .map(|parts| {
+            parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default()
+}
+
+const GEMINI_API_BASE: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Harm categories covered by `safetySettings`.
+const SAFETY_CATEGORIES: [&str; 4] = [
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Map the `gemini-flash` / `gemini-pro` aliases to concrete model IDs.
+async fn resolve_gemini_model(state: &AppState, requested: &str) -> String {
+    match requested {
+        "gemini-flash" => model_registry::get_model_id(state, "flash").await,
+        "gemini-pro" => model_registry::get_model_id(state, "gemini_pro").await,
+        other => other.to_string(),
+    }
+}
+
+/// Safety settings for every category at `GEMINI_SAFETY_THRESHOLD`
+/// (default `BLOCK_ONLY_HIGH`; unknown values fall back to the default).
+fn safety_settings() -> Value {
+    safety_settings_at(std::env::var("GEMINI_SAFETY_THRESHOLD").ok().as_deref())
+}
+
+fn safety_settings_at(threshold: Option<&str>) -> Value {
+    let threshold = match threshold {
+        Some(t @ ("BLOCK_NONE" | "BLOCK_ONLY_HIGH" | "BLOCK_MEDIUM_AND_ABOVE" | "BLOCK_LOW_AND_ABOVE")) => t,
+        _ => "BLOCK_ONLY_HIGH",
+    };
+    Value::Array(
+        SAFETY_CATEGORIES
+            .iter()
+            .map(|c| json!({ "category": c, "threshold": threshold }))
+            .collect(),
+    )
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/gemini/models
+// ═══════════════════════════════════════════════════════════════════════
+
+/// List Gemini models that support `generateContent`, with the IDs the
+/// `gemini-flash` / `gemini-pro` aliases currently resolve to.
+pub async fn gemini_list_models(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let Some((api_key, is_oauth)) = jaskier_oauth::google::get_google_credential(&state).await
+    else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "No Google API credential configured" })),
+        ));
+    };
+
+    let url = format!("{}/models?pageSize=200", GEMINI_API_BASE);
+    let resp = jaskier_oauth::google::apply_google_auth(state.http_client.get(&url), &api_key, is_oauth)
+        .timeout(std::time::Duration::from_secs(15))
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!("Gemini list models failed: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "AI provider request failed" })),
+            )
+        })?;
+
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let err = resp.text().await.unwrap_or_default();
+        tracing::error!("Gemini list models error (status={}): {}", status, err);
+        return Err((
+            StatusCode::from_u16(status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY),
+            Json(json!({ "error": sanitize_api_error(&err) })),
+        ));
+    }
+
+    let body: Value = resp.json().await.unwrap_or_default();
+    Ok(Json(json!({
+        "models": text_models(&body),
+        "aliases": {
+            "gemini-flash": resolve_gemini_model(&state, "gemini-flash").await,
+            "gemini-pro": resolve_gemini_model(&state, "gemini-pro").await,
+        },
+    })))
+}
+
+/// Models of a `models.list` response that support `generateContent`.
+fn text_models(body: &Value) -> Vec<Value> {
+    body.get("models")
+        .and_then(|m| m.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter(|m| {
+                    m.get("supportedGenerationMethods")
+                        .and_then(|s| s.as_array())
+                        .is_some_and(|s| s.iter().any(|v| v == "generateContent"))
+                })
+                .map(|m| {
+                    let id = m
+                        .get("name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or("")
+                        .trim_start_matches("models/");
+                    json!({
+                        "id": id,
+                        "display_name": m.get("displayName"),
+                        "tier": model_tier(id),
+                        "input_token_limit": m.get("inputTokenLimit"),
+                        "output_token_limit": m.get("outputTokenLimit"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Picker tier of a Gemini model ID.
+fn model_tier(id: &str) -> &'static str {
+    if id.contains("-pro") {
+        "pro"
+    } else if id.contains("flash") {
+        "flash"
+    } else {
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: &str) -> ChatMessage {
+        ChatMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            model: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn client_system_messages_extend_the_system_instruction() {
+        let messages = [
+            message("system", "Answer in Polish."),
+            message("system", "  "),
+            message("user", "Hi"),
+        ];
+        assert_eq!(
+            system_instruction("You are ClaudeHydra.", &messages),
+            "You are ClaudeHydra.\n\nAnswer in Polish."
+        );
+        assert_eq!(system_instruction("Base", &[message("user", "Hi")]), "Base");
+    }
+
+    #[test]
+    fn contents_use_gemini_roles_without_system_turns() {
+        let contents = gemini_contents(&[
+            json!({ "role": "system", "content": "ignored" }),
+            json!({ "role": "user", "content": "Hi" }),
+            json!({ "role": "assistant", "content": "Hello" }),
+        ]);
+        assert_eq!(
+            contents,
+            vec![
+                json!({ "role": "user", "parts": [{ "text": "Hi" }] }),
+                json!({ "role": "model", "parts": [{ "text": "Hello" }] }),
+            ]
+        );
+    }
+
+    #[test]
+    fn safety_threshold_applies_to_every_category() {
+        let settings = safety_settings_at(Some("BLOCK_NONE"));
+        let settings = settings.as_array().unwrap();
+        assert_eq!(settings.len(), SAFETY_CATEGORIES.len());
+        assert!(settings.iter().all(|s| s["threshold"] == "BLOCK_NONE"));
+        // Unknown or missing thresholds use the default.
+        assert_eq!(safety_settings_at(Some("block_none"))[0]["threshold"], "BLOCK_ONLY_HIGH");
+        assert_eq!(safety_settings_at(None)[0]["threshold"], "BLOCK_ONLY_HIGH");
+    }
+
+    #[test]
+    fn reply_text_joins_the_first_candidate_parts() {
+        let reply = json!({
+            "candidates": [
+                { "content": { "parts": [{ "text": "Cześć" }, { "inlineData": {} }, { "text": "!" }] } },
+                { "content": { "parts": [{ "text": "other" }] } },
+            ]
+        });
+        assert_eq!(reply_text(&reply), "Cześć!");
+        assert_eq!(reply_text(&json!({ "candidates": [] })), "");
+    }
+
+    #[test]
+    fn model_list_keeps_text_models_with_their_tier() {
+        let body = json!({
+            "models": [
+                { "name": "models/gemini-2.5-pro", "displayName": "Gemini 2.5 Pro",
+                  "supportedGenerationMethods": ["generateContent", "countTokens"],
+                  "inputTokenLimit": 1048576, "outputTokenLimit": 65536 },
+                { "name": "models/gemini-2.5-flash",
+                  "supportedGenerationMethods": ["generateContent"] },
+                { "name": "models/text-embedding-004",
+                  "supportedGenerationMethods": ["embedContent"] },
+                { "name": "models/gemma-3-27b-it",
+                  "supportedGenerationMethods": ["generateContent"] },
+            ]
+        });
+        let models = text_models(&body);
+        let ids: Vec<(&str, &str)> = models
+            .iter()
+            .map(|m| (m["id"].as_str().unwrap(), m["tier"].as_str().unwrap()))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("gemini-2.5-pro", "pro"),
+                ("gemini-2.5-flash", "flash"),
+                ("gemma-3-27b-it", "other"),
+            ]
+        );
+        assert_eq!(models[0]["output_token_limit"], 65536);
+        assert!(text_models(&json!({})).is_empty());
+    }
+}
@@ -33,11 +33,17 @@ pub(crate) async fn send_task_complete_notification(state: &AppState, model: &st
 //  Session history helpers
 // ═══════════════════════════════════════════════════════════════════════
 
+/// Recent session messages for the model context. In a compacted session
+/// only messages newer than the pinned summary are loaded, behind the summary.
 pub(crate) async fn load_session_history(db: &sqlx::PgPool, sid: &uuid::Uuid) -> Vec<Value> {
+    let summary = crate::compaction::load_summary(db, sid).await;
     let mut messages: Vec<Value> = sqlx::query_as::<_, (String, String)>(
-        "SELECT role, content FROM ch_messages WHERE session_id = $1 ORDER BY created_at DESC LIMIT 20",
+        "SELECT role, content FROM ch_messages \
+         WHERE session_id = $1 AND ($2::TIMESTAMPTZ IS NULL OR created_at > $2) \
+         ORDER BY created_at DESC LIMIT 20",
     )
     .bind(sid)
+    .bind(summary.as_ref().map(|s| s.covers_until))
     .fetch_all(db)
     .await
     .unwrap_or_default()
@@ -66,6 +72,10 @@ pub(crate) async fn load_session_history(db: &sqlx::PgPool, sid: &uuid::Uuid) ->
         }
     }
 
+    if let Some(summary) = summary {
+        messages.splice(0..0, crate::compaction::summary_messages(&summary.summary));
+    }
+
     messages
 }
 
@@ -92,31 +102,50 @@ pub(crate) fn filter_client_system_prompt(messages: &[ChatMessage]) -> Vec<Value
 //  Predictive UI Pre-fetching — view hint detection from prompt text
 // ═══════════════════════════════════════════════════════════════════════
 
+/// Maximum number of view hints returned — avoids over-fetching.
+const MAX_VIEW_HINTS: usize = 3;
+
 /// Keyword-to-view mapping for predictive pre-fetching.
+const VIEW_HINT_RULES: &[(&[&str], &str)] = &[
+    (&["statystyk", "analytics", "zużyci", "token", "koszt", "cost", "usage", "billing"], "analytics"),
+    (&["ustawieni", "settings", "konfiguracj", "model", "api key", "provider"], "settings"),
+    (&["log", "błęd", "error", "debug", "tracing"], "logs"),
+    (&["agent", "narzędzi", "tool", "executor"], "agents"),
+    (&["delegacj", "delegation", "przekaz", "a2a"], "delegations"),
+    (&["rój", "swarm", "orkiestracj", "multi-agent", "peer"], "swarm"),
+    (&["cache", "semantyczn", "semantic", "embedding", "qdrant"], "semantic-cache"),
+    (&["kolaboracj", "collab", "współprac", "edytor", "crdt", "yjs"], "collab"),
+];
+
 /// Analyzes the user prompt and returns view IDs that the user likely wants next.
-pub(crate) fn detect_view_hints(prompt: &str) -> Vec<String> {
-    let lower = prompt.to_lowercase();
-    let mut hints = Vec::new();
-
-    let rules: &[(&[&str], &str)] = &[
-        (&["statystyk", "analytics", "zużyci", "token", "koszt", "cost", "usage", "billing"], "analytics"),
-        (&["ustawieni", "settings", "konfiguracj", "model", "api key", "provider"], "settings"),
-        (&["log", "błęd", "error", "debug", "tracing"], "logs"),
-        (&["agent", "narzędzi", "tool", "executor"], "agents"),
-        (&["delegacj", "delegation", "przekaz", "a2a"], "delegations"),
-        (&["rój", "swarm", "orkiestracj", "multi-agent", "peer"], "swarm"),
-        (&["cache", "semantyczn", "semantic", "embedding", "qdrant"], "semantic-cache"),
-        (&["kolaboracj", "collab", "współprac", "edytor", "crdt", "yjs"], "collab"),
-    ];
-
-    for (keywords, view) in rules {
+///
+/// Only the first `PROMPT_ANALYSIS_PREFIX_BYTES` are lowercased and scanned,
+/// so a 100 KB pasted-code prompt costs the same as a short one. Scanning
+/// stops as soon as `MAX_VIEW_HINTS` views have matched.
+pub fn detect_view_hints(prompt: &str) -> Vec<String> {
+    let lower = crate::handlers::analysis_prefix(prompt, crate::handlers::PROMPT_ANALYSIS_PREFIX_BYTES)
+        .to_lowercase();
+    let mut hints = Vec::with_capacity(MAX_VIEW_HINTS);
+
+    for (keywords, view) in VIEW_HINT_RULES {
         if keywords.iter().any(|kw| lower.contains(kw)) {
             hints.push((*view).to_string());
+            if hints.len() == MAX_VIEW_HINTS {
+                return hints;
+            }
+        }
+    }
+
+    // Extra keywords from the rule update channel (remote manifest / local overrides).
+    for view in crate::rule_updates::matching_views(&lower) {
+        if !hints.contains(&view) {
+            hints.push(view);
+            if hints.len() == MAX_VIEW_HINTS {
+                break;
+            }
         }
     }
 
-    // Cap at 3 hints to avoid over-fetching
-    hints.truncate(3);
     hints
 }
 
@@ -124,32 +153,62 @@ pub(crate) fn detect_view_hints(prompt: &str) -> Vec<String> {
 //  DB persistence helpers
 // ═══════════════════════════════════════════════════════════════════════
 
-/// Store user prompt + assistant response to DB for a WebSocket session.
+/// Store user prompt + assistant response to DB for a WebSocket session,
+/// redacted, and record the exchange's estimated cost against the session.
 pub(crate) async fn store_ws_messages(
     state: &AppState,
     session_id: &uuid::Uuid,
+    model: Option<&str>,
     user_prompt: &str,
     assistant_text: &str,
 ) -> Result<(), sqlx::Error> {
+    let mut user_prompt = user_prompt.to_string();
+    let user_redactions = crate::redaction::redact_string(&mut user_prompt);
+    let mut assistant_text = assistant_text.to_string();
+    let assistant_redactions = crate::redaction::redact_string(&mut assistant_text);
+    let (user_prompt, assistant_text) = (user_prompt.as_str(), assistant_text.as_str());
+
     sqlx::query(
-        "INSERT INTO ch_messages (id, session_id, role, content, created_at) VALUES ($1, $2, 'user', $3, NOW())",
+        "INSERT INTO ch_messages (id, session_id, role, content, created_at, redactions) \
+         VALUES ($1, $2, 'user', $3, NOW(), $4)",
     )
     .bind(uuid::Uuid::new_v4())
     .bind(session_id)
     .bind(user_prompt)
+    .bind(user_redactions as i32)
     .execute(&state.db)
     .await?;
 
     if !assistant_text.is_empty() {
+        // The stream reports no usage here — store an estimate for the context meter.
+        let context = crate::compaction::estimate_context_tokens(&state.db, session_id).await?;
         sqlx::query(
-            "INSERT INTO ch_messages (id, session_id, role, content, created_at) VALUES ($1, $2, 'assistant', $3, NOW())",
+            "INSERT INTO ch_messages (id, session_id, role, content, model, created_at, prompt_tokens, completion_tokens, tokens_estimated, redactions) \
+             VALUES ($1, $2, 'assistant', $3, $4, NOW(), $5, $6, TRUE, $7)",
         )
         .bind(uuid::Uuid::new_v4())
         .bind(session_id)
         .bind(assistant_text)
+        .bind(model)
+        .bind(context as i32)
+        .bind(crate::compaction::estimate_tokens(assistant_text) as i32)
+        .bind(assistant_redactions as i32)
         .execute(&state.db)
         .await?;
+        crate::costs::schedule(
+            &state.db,
+            crate::costs::CostEvent {
+                model: model.unwrap_or("unknown").to_string(),
+                session_id: Some(*session_id),
+                requests: 1,
+                input_tokens: context as i64,
+                output_tokens: crate::compaction::estimate_tokens(assistant_text) as i64,
+                estimated: true,
+            },
+        );
+        crate::session_memory::schedule_remember(state, *session_id, user_prompt, assistant_text);
     }
 
+    crate::compaction::schedule_auto(state, *session_id);
     Ok(())
 }
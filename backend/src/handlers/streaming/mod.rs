@@ -4,16 +4,19 @@
 //! Split into focused submodules:
 //! - `trait_impl` — `HasAnthropicStreamingState` implementation for CH AppState
 //! - `helpers` — session history, predictive prefetch, MCP notifications, DB persistence
+//! - `anthropic` — Anthropic streaming without tools (SSE -> NDJSON, usage + stop reason)
 //! - `gemini` — Gemini hybrid streaming (Google API SSE -> NDJSON)
 //! - `websocket` — WebSocket streaming with rich protocol
 //! - `agent_call` — Agent-to-Agent delegation (call_agent tool)
 //!
-//! BE-CH-003: NDJSON streaming uses `jaskier_core::handlers::anthropic_streaming`
-//! shared handler with `HasAnthropicStreamingState` trait. WebSocket + A2A delegation
-//! remain CH-specific (different protocol / deeply coupled to CH state).
+//! BE-CH-003: NDJSON streaming with tools uses `jaskier_core::handlers::anthropic_streaming`
+//! shared handler with `HasAnthropicStreamingState` trait (also the fallback of the
+//! no-tools path). WebSocket + A2A delegation remain CH-specific (different protocol /
+//! deeply coupled to CH state).
 
 mod trait_impl;
 pub mod helpers;
+mod anthropic;
 mod gemini;
 pub mod websocket;
 pub mod agent_call;
@@ -27,6 +30,10 @@ use jaskier_core::handlers::anthropic_streaming::{
     self, AnthropicChatContext, dynamic_max_iterations,
 };
 
+use crate::ai_gateway::AiProvider;
+use crate::ai_gateway::attachments::{
+    ResolvedImage, attach_images, attachment_error, resolve_attachments,
+};
 use crate::models::*;
 use crate::state::AppState;
 
@@ -39,6 +46,8 @@ use helpers::{detect_view_hints, filter_client_system_prompt, load_session_histo
 
 // ── Public re-exports ────────────────────────────────────────────────────
 
+pub use gemini::gemini_list_models;
+pub(crate) use gemini::{google_chat, google_complete};
 pub use websocket::ws_chat;
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -60,7 +69,7 @@ pub async fn prefetch_hints(
 
 // ═══════════════════════════════════════════════════════════════════════
 //  Claude Streaming (SSE from Anthropic -> NDJSON to frontend)
-//  BE-CH-003: Delegates to shared anthropic_streaming handler
+//  Anthropic models: `anthropic.rs` (shared handler as fallback)
 // ═══════════════════════════════════════════════════════════════════════
 
 /// POST /api/claude/chat/stream
@@ -69,11 +78,22 @@ pub async fn prefetch_hints(
     responses((status = 200, description = "Streaming NDJSON response")))]
 pub async fn claude_chat_stream(
     axum::extract::State(state): axum::extract::State<AppState>,
-    Json(req): Json<ChatRequest>,
+    Json(mut req): Json<ChatRequest>,
 ) -> Result<Response, (StatusCode, Json<Value>)> {
+    crate::idle_scavenger::mark_interactive();
+    crate::profiles::ensure_cloud()?;
+    // Safety guard — secrets in the new user turn
+    if let Some(last) = req.messages.iter_mut().rev().find(|m| m.role == "user") {
+        crate::safety::screen_prompt(&mut last.content, "stream")
+            .map_err(|e| crate::safety::rejection(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    }
+    let images = resolve_attachments(&req.attachments)
+        .await
+        .map_err(attachment_error)?;
+
     // Gate: if tools_enabled, route to agentic handler
     if req.tools_enabled.unwrap_or(false) {
-        return claude_chat_stream_with_tools(state, req).await;
+        return claude_chat_stream_with_tools(state, req, images).await;
     }
 
     let ctx = resolve_chat_context(&state, &req).await;
@@ -86,12 +106,12 @@ pub async fn claude_chat_stream(
 
     // Hybrid routing: Gemini models -> Google API
     if ctx.model.starts_with("gemini-") {
-        return gemini::google_chat_stream(state, req, ctx).await;
+        return gemini::google_chat_stream(state, req, ctx, &images).await;
     }
 
-    // ── Delegate to shared handler ──────────────────────────────────────
     let prompt_len = req.messages.iter().map(|m| m.content.len()).sum::<usize>();
-    let messages = filter_client_system_prompt(&req.messages);
+    let mut messages = filter_client_system_prompt(&req.messages);
+    attach_images(&AiProvider::Anthropic, &mut messages, &images);
 
     let shared_ctx = AnthropicChatContext {
         model: ctx.model,
@@ -103,8 +123,7 @@ pub async fn claude_chat_stream(
         system_prompt: ctx.system_prompt,
     };
 
-    anthropic_streaming::anthropic_ndjson_stream_no_tools(&state, &shared_ctx, messages, prompt_len)
-        .await
+    anthropic::anthropic_chat_stream(state, shared_ctx, messages, prompt_len).await
 }
 
 // ═══════════════════════════════════════════════════════════════════════
@@ -115,6 +134,7 @@ pub async fn claude_chat_stream(
 async fn claude_chat_stream_with_tools(
     state: AppState,
     req: ChatRequest,
+    images: Vec<ResolvedImage>,
 ) -> Result<Response, (StatusCode, Json<Value>)> {
     let ctx = resolve_chat_context(&state, &req).await;
 
@@ -124,7 +144,7 @@ async fn claude_chat_stream_with_tools(
         dynamic_max_iterations(prompt_len).min(ctx.max_iterations.max(1) as usize);
 
     // Build initial messages — prefer DB history when session_id present
-    let initial_messages: Vec<Value> = if let Some(ref sid) = ctx.session_id {
+    let mut initial_messages: Vec<Value> = if let Some(ref sid) = ctx.session_id {
         let mut history = load_session_history(&state.db, sid).await;
         if let Some(last) = req.messages.last() {
             history.push(json!({ "role": "user", "content": &last.content }));
@@ -133,6 +153,7 @@ async fn claude_chat_stream_with_tools(
     } else {
         filter_client_system_prompt(&req.messages)
     };
+    attach_images(&AiProvider::Anthropic, &mut initial_messages, &images);
 
     let shared_ctx = AnthropicChatContext {
         model: ctx.model,
@@ -147,4 +168,5 @@ async fn claude_chat_stream_with_tools(
     // ── Delegate to shared handler ──────────────────────────────────────
     anthropic_streaming::anthropic_ndjson_stream_with_tools(&state, shared_ctx, initial_messages)
         .await
+        .map(anthropic::with_stream_events)
 }
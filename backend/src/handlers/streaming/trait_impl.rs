@@ -16,6 +16,7 @@ use super::{
     TOOL_TIMEOUT_SECS, is_retryable_status, sanitize_json_strings, send_to_anthropic,
 };
 use super::agent_call::execute_agent_call;
+use super::anthropic::record_stream_usage;
 use super::helpers::{load_session_history, send_task_complete_notification};
 
 impl HasAnthropicStreamingState for AppState {
@@ -257,41 +258,15 @@ impl HasAnthropicStreamingState for AppState {
         prompt_len: usize,
         latency_ms: u128,
     ) -> impl std::future::Future<Output = ()> + Send {
-        let db = self.db.clone();
         let state = self.clone();
         let model = model.to_string();
         async move {
-            // Token usage tracking — fire-and-forget
-            let latency = latency_ms.min(i32::MAX as u128) as i32;
-            let input_est = (prompt_len / 4) as i32;
-            let output_est = (output_chars / 4) as i32;
-            let tier = if model.contains("opus") {
-                "commander"
-            } else if model.contains("sonnet") {
-                "coordinator"
-            } else if model.contains("haiku") {
-                "executor"
-            } else if model.contains("flash") {
-                "flash"
-            } else {
-                "coordinator"
+            // Token usage tracking — fire-and-forget, estimated from lengths
+            let usage = crate::stream_event::Usage {
+                input_tokens: (prompt_len / 4) as u64,
+                output_tokens: (output_chars / 4) as u64,
             };
-            let m = model.clone();
-            let db_clone = db.clone();
-            tokio::spawn(async move {
-                let _ = sqlx::query(
-                    "INSERT INTO ch_agent_usage (agent_id, model, input_tokens, output_tokens, total_tokens, latency_ms, success, tier) \
-                     VALUES (NULL, $1, $2, $3, $4, $5, TRUE, $6)",
-                )
-                .bind(&m)
-                .bind(input_est)
-                .bind(output_est)
-                .bind(input_est + output_est)
-                .bind(latency)
-                .bind(tier)
-                .execute(&db_clone)
-                .await;
-            });
+            record_stream_usage(&state, &model, usage, true, latency_ms);
 
             // Fire-and-forget: task completion notification
             tokio::spawn(async move {
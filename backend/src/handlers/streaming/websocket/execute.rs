@@ -29,9 +29,21 @@ use crate::handlers::streaming::{
     TOOL_TIMEOUT_SECS, is_retryable_status, sanitize_json_strings,
     send_to_anthropic, truncate_for_context_with_limit,
 };
+use crate::ai_gateway::AiProvider;
+use crate::ai_gateway::attachments::{Attachment, ResolvedImage, attach_images, resolve_attachments};
 use crate::handlers::streaming::agent_call::execute_agent_call;
 use crate::handlers::streaming::helpers::{detect_view_hints, load_session_history, store_ws_messages};
-use crate::handlers::prompt::resolve_chat_context;
+use crate::handlers::prompt::{Backend, prompt_complexity, resolve_chat_context};
+use crate::hooks::{self, HookEvent, HookPayload};
+use crate::idle_scavenger;
+use crate::markdown_vault::{Exchange, mirror_exchange};
+use crate::permissions::PermissionMode;
+use crate::prompt_trace::PromptTrace;
+use crate::rag;
+use crate::session_memory;
+use crate::stream_event::{self, StreamEvent, Usage};
+use crate::tts::StreamingSpeaker;
+use crate::web_search;
 
 use super::ws_send;
 
@@ -43,10 +55,45 @@ pub(crate) async fn execute_streaming_ws(
     model_override: Option<String>,
     tools_enabled: bool,
     session_id: Option<String>,
+    web_search: Option<bool>,
+    speak: bool,
+    attachments: Vec<Attachment>,
     cancel: CancellationToken,
 ) {
+    let execution_id = crate::correlation::current().unwrap_or_else(crate::correlation::new_id);
+    let mut trace = PromptTrace::new(state.db.clone(), &execution_id);
+    run_streaming_ws(
+        sender, state, prompt, model_override, tools_enabled, session_id, web_search, speak,
+        attachments, cancel, &mut trace,
+    ).await;
+    trace.finish().await;
+}
+
+async fn run_streaming_ws(
+    sender: &mut SplitSink<WebSocket, WsMessage>,
+    state: &AppState,
+    mut prompt: String,
+    model_override: Option<String>,
+    tools_enabled: bool,
+    session_id: Option<String>,
+    web_search: Option<bool>,
+    speak: bool,
+    attachments: Vec<Attachment>,
+    cancel: CancellationToken,
+    trace: &mut PromptTrace,
+) {
+    idle_scavenger::mark_interactive();
     let execution_start = std::time::Instant::now();
-    let execution_id = uuid::Uuid::new_v4().to_string();
+    let execution_id = trace.execution_id().to_string();
+    trace.record(
+        "received",
+        json!({
+            "prompt_len": prompt.len(),
+            "tools_enabled": tools_enabled,
+            "session_id": &session_id,
+            "attachments": attachments.len(),
+        }),
+    );
 
     // Build a ChatRequest for resolve_chat_context
     let chat_req = ChatRequest {
@@ -62,6 +109,8 @@ pub(crate) async fn execute_streaming_ws(
         stream: Some(true),
         tools_enabled: Some(tools_enabled),
         session_id: session_id.clone(),
+        attachments,
+        web_search,
     };
 
     let ctx = resolve_chat_context(state, &chat_req).await;
@@ -69,7 +118,128 @@ pub(crate) async fn execute_streaming_ws(
     let max_tokens = ctx.max_tokens;
     let effective_temperature = ctx.temperature;
     let wd = ctx.working_directory;
-    let system_prompt = ctx.system_prompt;
+    let mut system_prompt = ctx.system_prompt;
+    // Predictive UI pre-fetching
+    let view_hints = detect_view_hints(&prompt);
+    trace.record(
+        "context_resolved",
+        json!({
+            "model": &model,
+            "max_tokens": max_tokens,
+            "temperature": effective_temperature,
+            "model_explicit": chat_req.model.is_some(),
+            "complexity": prompt_complexity(&prompt),
+            "task_type": crate::ollama_models::task_kind(&prompt).as_str(),
+        }),
+    );
+
+    // Provider routing — Anthropic streams, Gemini takes one non-streamed
+    // turn; other providers have no chat path here.
+    let backend = Backend::for_model(&model);
+    if !matches!(backend, Backend::Anthropic | Backend::Google) {
+        trace.record("failed", json!({ "reason": "unsupported_model" }));
+        ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message: format!("Model '{}' is not available for chat", model),
+                code: Some("UNSUPPORTED_MODEL".to_string()),
+            },
+        )
+        .await;
+        return;
+    }
+
+    // Offline profile — cloud providers are disabled
+    if let Err((_, Json(err))) = crate::profiles::ensure_cloud() {
+        trace.record("failed", json!({ "reason": "offline" }));
+        ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message: err["error"].as_str().unwrap_or("Offline").to_string(),
+                code: Some("OFFLINE".to_string()),
+            },
+        )
+        .await;
+        return;
+    }
+
+    // Budget cap reached — the provider's requests are refused until it resets
+    if let Err((_, Json(err))) = crate::budget::ensure_within(&state.db, backend.name()).await {
+        trace.record("failed", json!({ "reason": "budget_exceeded" }));
+        ws_send(
+            sender,
+            &WsServerMessage::Error {
+                message: err["error"].as_str().unwrap_or("Budget exceeded").to_string(),
+                code: Some("BUDGET_EXCEEDED".to_string()),
+            },
+        )
+        .await;
+        return;
+    }
+
+    // Safety guard — secrets in the prompt are reported, redacted or blocked
+    let verdict = crate::safety::screen_prompt(&mut prompt, "ws");
+    if !report_guard(sender, trace, verdict).await {
+        return;
+    }
+
+    // Image attachments — resolved once, attached to the new user turn below
+    let images = match resolve_attachments(&chat_req.attachments).await {
+        Ok(images) => images,
+        Err(message) => {
+            trace.record("failed", json!({ "reason": "invalid_attachment" }));
+            ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message,
+                    code: Some("INVALID_ATTACHMENT".to_string()),
+                },
+            )
+            .await;
+            return;
+        }
+    };
+
+    // Local RAG — inject retrieved project chunks before the request is routed
+    let rag_hits = rag::augment_system_prompt(state, &mut system_prompt, &prompt).await;
+    if !rag_hits.is_empty() {
+        trace.record(
+            "rag_retrieved",
+            json!({
+                "chunks": rag_hits
+                    .iter()
+                    .map(|h| json!({ "path": &h.path, "lines": [h.start_line, h.end_line], "score": h.score }))
+                    .collect::<Vec<_>>(),
+            }),
+        );
+    }
+
+    // Session memory — relevant exchanges that fell out of the history window
+    if let Some(sid) = ctx.session_id {
+        let recalled = session_memory::augment_system_prompt(state, sid, &mut system_prompt, &prompt).await;
+        if !recalled.is_empty() {
+            trace.record(
+                "memory_recalled",
+                json!({
+                    "exchanges": recalled
+                        .iter()
+                        .map(|h| json!({ "id": h.entry.id, "session_id": h.entry.session_id, "score": h.score }))
+                        .collect::<Vec<_>>(),
+                }),
+            );
+        }
+    }
+
+    // Web search augmentation for prompts that need fresh information
+    let sources =
+        web_search::augment_system_prompt(&state.http_client, &mut system_prompt, &prompt, web_search)
+            .await;
+    if !sources.is_empty() {
+        trace.record(
+            "web_search",
+            json!({ "urls": sources.iter().map(|s| s.url.as_str()).collect::<Vec<_>>() }),
+        );
+    }
 
     // Dynamic iteration cap
     let prompt_len = prompt.len();
@@ -86,38 +256,168 @@ pub(crate) async fn execute_streaming_ws(
         },
     )
     .await;
+    ws_send(
+        sender,
+        &WsServerMessage::Phase {
+            progress: Progress::indeterminate("awaiting_model", format!("Waiting for {}", model)),
+        },
+    )
+    .await;
+
+    if !sources.is_empty() {
+        ws_send(sender, &WsServerMessage::Sources { sources }).await;
+    }
 
     // Predictive UI pre-fetching — emit view hints based on prompt keywords
-    let view_hints = detect_view_hints(&prompt);
     if !view_hints.is_empty() {
         ws_send(sender, &WsServerMessage::ViewHint { views: view_hints }).await;
     }
 
     // Build initial messages — prefer DB history when session_id present
-    let initial_messages: Vec<Value> = if let Some(ref sid) = ctx.session_id {
+    let mut initial_messages: Vec<Value> = if let Some(ref sid) = ctx.session_id {
         let mut history = load_session_history(&state.db, sid).await;
         history.push(json!({ "role": "user", "content": &prompt }));
         history
     } else {
         vec![json!({ "role": "user", "content": &prompt })]
     };
+    // Gemini takes the images as `inline_data` parts (see `execute_google`).
+    if backend == Backend::Anthropic {
+        attach_images(&AiProvider::Anthropic, &mut initial_messages, &images);
+    }
+
+    // Read the response aloud sentence by sentence while it streams (CH_TTS_BACKEND)
+    let mut speaker = if speak { StreamingSpeaker::start(None) } else { None };
 
-    // Non-tools path: simple streaming without tool loop
-    if !tools_enabled {
+    if backend == Backend::Google {
+        // Gemini path: one generateContent turn, no tool loop
+        execute_google(
+            sender, state, &model, max_tokens, &system_prompt, &initial_messages,
+            &images, &prompt, &ctx.session_id, &wd, execution_start, trace, &mut speaker,
+        ).await;
+    } else if !tools_enabled {
+        // Non-tools path: simple streaming without tool loop
         execute_no_tools(
             sender, state, &model, max_tokens, effective_temperature,
             &system_prompt, &initial_messages, &prompt, &ctx.session_id,
-            execution_start, &cancel,
+            &wd, execution_start, &cancel, trace, &mut speaker,
+        ).await;
+    } else {
+        // ── Tools-enabled path: agentic tool_use loop ───────────────────
+        execute_with_tools(
+            sender, state, &model, max_tokens, effective_temperature,
+            &system_prompt, initial_messages, &prompt, &ctx.session_id,
+            &wd, ctx.permission_mode, max_tool_iterations, execution_start, &cancel, trace,
+            &mut speaker,
         ).await;
-        return;
     }
 
-    // ── Tools-enabled path: agentic tool_use loop ───────────────────────
-    execute_with_tools(
-        sender, state, &model, max_tokens, effective_temperature,
-        &system_prompt, initial_messages, &prompt, &ctx.session_id,
-        &wd, max_tool_iterations, execution_start, &cancel,
-    ).await;
+    // Cancelled runs drop the speaker, which stops playback immediately.
+    if let Some(speaker) = speaker.take()
+        && !cancel.is_cancelled()
+    {
+        speaker.finish();
+    }
+
+    // Completed runs dispatch their hooks where the response is assembled.
+    if let Some(detail) = trace.failure() {
+        crate::app_log::record(
+            crate::app_log::Level::Warn,
+            "chat",
+            "execution-failed",
+            json!({
+                "execution_id": &execution_id,
+                "session_id": ctx.session_id,
+                "model": &model,
+                "detail": &detail,
+            }),
+        );
+        hooks::dispatch(state.db.clone(), HookPayload {
+            event: HookEvent::Failed,
+            execution_id: execution_id.clone(),
+            session_id: ctx.session_id,
+            model: model.clone(),
+            working_directory: wd.clone(),
+            prompt: prompt.clone(),
+            response: String::new(),
+            detail: detail.clone(),
+        });
+    }
+}
+
+/// Sends a safety guard verdict to the client: an alert for findings that
+/// were let through, an error when the text was blocked (returns `false`).
+async fn report_guard(
+    sender: &mut SplitSink<WebSocket, WsMessage>,
+    trace: &mut PromptTrace,
+    verdict: Result<Option<String>, String>,
+) -> bool {
+    match verdict {
+        Ok(None) => true,
+        Ok(Some(message)) => {
+            ws_send(sender, &WsServerMessage::SafetyAlert { message }).await;
+            true
+        }
+        Err(message) => {
+            trace.record("failed", json!({ "reason": "blocked_by_guard" }));
+            ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message,
+                    code: Some(crate::safety::BLOCKED_CODE.to_string()),
+                },
+            )
+            .await;
+            false
+        }
+    }
+}
+
+/// Asks the client to approve a mutating tool call (`ask` mode) and waits
+/// for the decision, sending heartbeats meanwhile. Returns the error result
+/// to report instead of running the tool, if it may not run.
+async fn approve_tool(
+    sender: &mut SplitSink<WebSocket, WsMessage>,
+    state: &AppState,
+    session_id: Option<uuid::Uuid>,
+    cancel: &CancellationToken,
+    tool_name: &str,
+    tool_input: &Value,
+) -> Option<String> {
+    let request = crate::approvals::request_tool(&state.db, session_id, tool_name, tool_input);
+    let (approval, rx) = match request.await {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::error!("approvals: request for {} failed: {}", tool_name, e);
+            return Some(format!(
+                "Tool '{}' needs approval, but the request could not be recorded",
+                tool_name
+            ));
+        }
+    };
+    ws_send(
+        sender,
+        &WsServerMessage::ApprovalRequired {
+            id: approval.id.to_string(),
+            tool: tool_name.to_string(),
+            input: tool_input.clone(),
+        },
+    )
+    .await;
+
+    let decision = crate::approvals::await_decision(&state.db, approval.id, rx);
+    tokio::pin!(decision);
+    let heartbeat_dur = std::time::Duration::from_secs(15);
+    let decision = loop {
+        tokio::select! {
+            decision = &mut decision => break decision,
+            _ = cancel.cancelled() => break crate::approvals::expire(&state.db, approval.id).await,
+            _ = tokio::time::sleep(heartbeat_dur) => {
+                ws_send(sender, &WsServerMessage::Heartbeat).await;
+            }
+        }
+    };
+    decision.refusal(tool_name)
 }
 
 /// Non-tools path: simple streaming without tool loop.
@@ -131,9 +431,13 @@ async fn execute_no_tools(
     initial_messages: &[Value],
     prompt: &str,
     session_id: &Option<uuid::Uuid>,
+    wd: &str,
     execution_start: std::time::Instant,
     cancel: &CancellationToken,
+    trace: &mut PromptTrace,
+    speaker: &mut Option<StreamingSpeaker>,
 ) {
+    let request_id = stream_event::current_request_id();
     let mut body = json!({
         "model": model,
         "max_tokens": max_tokens,
@@ -146,6 +450,7 @@ async fn execute_no_tools(
     }
     sanitize_json_strings(&mut body);
 
+    trace.record("provider_called", json!({ "model": model, "attempt": 1 }));
     let resp = match send_to_anthropic(state, &body, 300).await {
         Ok(r) => r,
         Err((_, Json(err_val))) => {
@@ -154,6 +459,7 @@ async fn execute_no_tools(
                 .and_then(|e| e.as_str())
                 .unwrap_or("Unknown error");
             tracing::error!("WS: send_to_anthropic failed (no-tools): {}", raw_msg);
+            trace.record("failed", json!({ "reason": "request_failed", "error": raw_msg }));
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -184,6 +490,10 @@ async fn execute_no_tools(
                 fb_model
             );
             body["model"] = json!(fb_model);
+            trace.record(
+                "fallback",
+                json!({ "from": model, "to": fb_model, "status": original_status.as_u16() }),
+            );
             if let Ok(fb) = send_to_anthropic(state, &body, 300).await
                 && fb.status().is_success()
             {
@@ -218,6 +528,7 @@ async fn execute_no_tools(
             status,
             &truncate_for_context_with_limit(&err_text, 500)
         );
+        trace.record("failed", json!({ "reason": "upstream_status", "status": status.as_u16() }));
         let safe_error = sanitize_api_error(&err_text);
         ws_send(
             sender,
@@ -234,9 +545,12 @@ async fn execute_no_tools(
     let mut byte_stream = resp.bytes_stream();
     let mut raw_buf: Vec<u8> = Vec::new();
     let mut full_text = String::new();
+    let mut usage = Usage::default();
+    let mut stop_reason = String::new();
 
     while let Some(chunk_result) = byte_stream.next().await {
         if cancel.is_cancelled() {
+            trace.record("cancelled", Value::Null);
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -255,7 +569,11 @@ async fn execute_no_tools(
 
         let events = parse_sse_lines(&mut raw_buf);
         for event in events {
+            stream_event::anthropic_usage(&event, &mut usage);
             let event_type = event.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            if let Some(reason) = event.pointer("/delta/stop_reason").and_then(|r| r.as_str()) {
+                stop_reason = reason.to_string();
+            }
             if event_type == "content_block_delta" {
                 let text = event
                     .get("delta")
@@ -264,10 +582,15 @@ async fn execute_no_tools(
                     .unwrap_or("");
                 if !text.is_empty() {
                     full_text.push_str(text);
+                    trace.token();
+                    if let Some(speaker) = speaker.as_mut() {
+                        speaker.push(text);
+                    }
                     ws_send(
                         sender,
                         &WsServerMessage::Token {
                             content: text.to_string(),
+                            stream: StreamEvent::delta(&request_id, "anthropic", text),
                         },
                     )
                     .await;
@@ -276,15 +599,140 @@ async fn execute_no_tools(
         }
     }
 
+    // Safety guard — a blocked response is not stored
+    let verdict = crate::safety::screen_response(&full_text, "ws");
+    if !report_guard(sender, trace, verdict).await {
+        return;
+    }
+
     // Store message to DB if session present
     if let Some(sid) = session_id {
-        let _ = store_ws_messages(state, sid, prompt, &full_text).await;
+        let _ = store_ws_messages(state, sid, Some(model), prompt, &full_text).await;
     }
+    mirror_exchange(Exchange {
+        session_id: *session_id,
+        working_directory: wd.to_string(),
+        model: model.to_string(),
+        prompt: prompt.to_string(),
+        response: full_text.clone(),
+        completed_at: chrono::Utc::now(),
+    });
+    hooks::dispatch(state.db.clone(), HookPayload {
+        event: HookEvent::Completed,
+        execution_id: trace.execution_id().to_string(),
+        session_id: *session_id,
+        model: model.to_string(),
+        working_directory: wd.to_string(),
+        prompt: prompt.to_string(),
+        response: full_text.clone(),
+        detail: Value::Null,
+    });
 
+    trace.record("completed", json!({ "response_chars": full_text.len() }));
     ws_send(
         sender,
         &WsServerMessage::Complete {
             duration_ms: execution_start.elapsed().as_millis() as u64,
+            stream: StreamEvent::finish(
+                &request_id,
+                "anthropic",
+                &stop_reason,
+                Some(usage),
+            ),
+        },
+    )
+    .await;
+}
+
+/// Gemini path: the whole reply from one `generateContent` turn, sent as a
+/// single token.
+async fn execute_google(
+    sender: &mut SplitSink<WebSocket, WsMessage>,
+    state: &AppState,
+    model: &str,
+    max_tokens: u32,
+    system_prompt: &str,
+    initial_messages: &[Value],
+    images: &[ResolvedImage],
+    prompt: &str,
+    session_id: &Option<uuid::Uuid>,
+    wd: &str,
+    execution_start: std::time::Instant,
+    trace: &mut PromptTrace,
+    speaker: &mut Option<StreamingSpeaker>,
+) {
+    let request_id = stream_event::current_request_id();
+    trace.record("provider_called", json!({ "model": model, "attempt": 1 }));
+    let full_text = match crate::handlers::streaming::google_chat(
+        state, model, system_prompt, max_tokens, initial_messages, images, 300,
+    )
+    .await
+    {
+        Ok(text) => text,
+        Err(e) => {
+            tracing::error!("WS: google_chat failed: {}", e);
+            trace.record("failed", json!({ "reason": "request_failed", "error": &e }));
+            ws_send(
+                sender,
+                &WsServerMessage::Error {
+                    message: e,
+                    code: Some("API_ERROR".to_string()),
+                },
+            )
+            .await;
+            return;
+        }
+    };
+
+    // Safety guard — a blocked response is not stored
+    let verdict = crate::safety::screen_response(&full_text, "ws");
+    if !report_guard(sender, trace, verdict).await {
+        return;
+    }
+
+    if !full_text.is_empty() {
+        trace.token();
+        if let Some(speaker) = speaker.as_mut() {
+            speaker.push(&full_text);
+        }
+        ws_send(
+            sender,
+            &WsServerMessage::Token {
+                content: full_text.clone(),
+                stream: StreamEvent::delta(&request_id, "google", &full_text),
+            },
+        )
+        .await;
+    }
+
+    if let Some(sid) = session_id {
+        let _ = store_ws_messages(state, sid, Some(model), prompt, &full_text).await;
+    }
+    mirror_exchange(Exchange {
+        session_id: *session_id,
+        working_directory: wd.to_string(),
+        model: model.to_string(),
+        prompt: prompt.to_string(),
+        response: full_text.clone(),
+        completed_at: chrono::Utc::now(),
+    });
+    hooks::dispatch(state.db.clone(), HookPayload {
+        event: HookEvent::Completed,
+        execution_id: trace.execution_id().to_string(),
+        session_id: *session_id,
+        model: model.to_string(),
+        working_directory: wd.to_string(),
+        prompt: prompt.to_string(),
+        response: full_text.clone(),
+        detail: Value::Null,
+    });
+
+    trace.record("completed", json!({ "response_chars": full_text.len() }));
+    ws_send(
+        sender,
+        &WsServerMessage::Complete {
+            duration_ms: execution_start.elapsed().as_millis() as u64,
+            stream: StreamEvent::finish(&request_id, "google", "stop", None),
         },
     )
     .await;
@@ -303,15 +751,21 @@ async fn execute_with_tools(
     prompt: &str,
     session_id: &Option<uuid::Uuid>,
     wd: &str,
+    permission_mode: PermissionMode,
     max_tool_iterations: usize,
     execution_start: std::time::Instant,
     cancel: &CancellationToken,
+    trace: &mut PromptTrace,
+    speaker: &mut Option<StreamingSpeaker>,
 ) {
+    let request_id = stream_event::current_request_id();
     let tool_defs: Vec<Value> = state
         .tool_executor
         .tool_definitions_with_mcp(state, Some(model))
         .await
         .into_iter()
+        // Read-only sessions never see mutating tools (this also disables auto-fix).
+        .filter(|td| permission_mode.allows_tool(&td.name))
         .map(|td| {
             json!({
                 "name": td.name,
@@ -326,12 +780,15 @@ async fn execute_with_tools(
     let mut has_written_file = false;
     let mut agent_text_len: usize = 0;
     let mut full_text = String::new();
+    // Summed over every model turn of the loop
+    let mut usage = Usage::default();
     let execution_timeout = std::time::Duration::from_secs(300);
 
     loop {
         iteration += 1;
 
         if cancel.is_cancelled() {
+            trace.record("cancelled", json!({ "iteration": iteration }));
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -348,6 +805,7 @@ async fn execute_with_tools(
                 "WS: Global execution timeout (300s) at iteration {}",
                 iteration
             );
+            trace.record("failed", json!({ "reason": "timeout", "iteration": iteration }));
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -360,6 +818,7 @@ async fn execute_with_tools(
         }
 
         if iteration > max_tool_iterations as u32 {
+            trace.record("failed", json!({ "reason": "max_iterations", "iteration": iteration }));
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -377,6 +836,12 @@ async fn execute_with_tools(
             &WsServerMessage::Iteration {
                 number: iteration,
                 max: max_tool_iterations as u32,
+                progress: Progress::determinate(
+                    "tool_loop",
+                    iteration,
+                    max_tool_iterations as u32,
+                    format!("Step {} of at most {}", iteration, max_tool_iterations),
+                ),
             },
         )
         .await;
@@ -392,6 +857,7 @@ async fn execute_with_tools(
         });
         sanitize_json_strings(&mut body);
 
+        trace.record("provider_called", json!({ "model": model, "iteration": iteration }));
         let resp = match send_to_anthropic(state, &body, 300).await {
             Ok(r) => r,
             Err((_, Json(err_val))) => {
@@ -404,6 +870,10 @@ async fn execute_with_tools(
                     iteration,
                     raw_msg
                 );
+                trace.record(
+                    "failed",
+                    json!({ "reason": "request_failed", "iteration": iteration, "error": raw_msg }),
+                );
                 ws_send(
                     sender,
                     &WsServerMessage::Error {
@@ -425,6 +895,10 @@ async fn execute_with_tools(
                 iteration,
                 &truncate_for_context_with_limit(&err_text, 500)
             );
+            trace.record(
+                "failed",
+                json!({ "reason": "upstream_status", "status": status.as_u16(), "iteration": iteration }),
+            );
             let safe_error = sanitize_api_error(&err_text);
             ws_send(
                 sender,
@@ -443,6 +917,7 @@ async fn execute_with_tools(
         let mut tool_uses: Vec<Value> = Vec::new();
         let mut stop_reason = String::new();
         let mut _total_tokens: u32 = 0;
+        let mut turn_usage = Usage::default();
 
         let mut byte_stream = resp.bytes_stream();
         let mut raw_buf: Vec<u8> = Vec::new();
@@ -460,22 +935,33 @@ async fn execute_with_tools(
 
             let sse_events = parse_sse_lines(&mut raw_buf);
             for sse_json in sse_events {
+                stream_event::anthropic_usage(&sse_json, &mut turn_usage);
                 let parsed = parser.parse_event(&sse_json);
                 for ev in parsed {
                     match ev {
                         AnthropicSseEvent::TextToken(text) => {
+                            trace.token();
                             text_content.push_str(&text);
                             full_text.push_str(&text);
                             agent_text_len += text.len();
+                            if let Some(speaker) = speaker.as_mut() {
+                                speaker.push(&text);
+                            }
                             ws_send(
                                 sender,
                                 &WsServerMessage::Token {
+                                    stream: StreamEvent::delta(
+                                        &request_id,
+                                        "anthropic",
+                                        &text,
+                                    ),
                                     content: text,
                                 },
                             )
                             .await;
                         }
                         AnthropicSseEvent::ToolUse { id, name, input } => {
+                            trace.record("tool_call", json!({ "name": &name, "iteration": iteration }));
                             ws_send(
                                 sender,
                                 &WsServerMessage::ToolCall {
@@ -503,8 +989,11 @@ async fn execute_with_tools(
                 }
             }
         }
+        usage.input_tokens += turn_usage.input_tokens;
+        usage.output_tokens += turn_usage.output_tokens;
 
         if cancel.is_cancelled() {
+            trace.record("cancelled", json!({ "iteration": iteration }));
             ws_send(
                 sender,
                 &WsServerMessage::Error {
@@ -528,6 +1017,18 @@ async fn execute_with_tools(
             let tools_total = tool_uses.len() as u32;
             let mut tool_results: Vec<Value> = Vec::new();
             let mut tools_completed: u32 = 0;
+            ws_send(
+                sender,
+                &WsServerMessage::Phase {
+                    progress: Progress::determinate(
+                        "running_tools",
+                        0,
+                        tools_total,
+                        format!("Running {} tools", tools_total),
+                    ),
+                },
+            )
+            .await;
 
             // Execute tools in parallel via tokio::spawn
             let mut handles = Vec::new();
@@ -548,10 +1049,27 @@ async fn execute_with_tools(
                 let executor = state.tool_executor.with_working_directory(wd);
                 let state_ref = state.clone();
                 let wd_ref = wd.to_string();
+                let refusal = if permission_mode.needs_approval(&tool_name) {
+                    trace.record("approval_requested", json!({ "name": &tool_name }));
+                    approve_tool(sender, state, *session_id, cancel, &tool_name, &tool_input).await
+                } else {
+                    None
+                };
 
                 let semaphore = state.a2a_semaphore.clone();
                 let handle = tokio::spawn(async move {
-                    let (result, is_error) = if tool_name == "call_agent" {
+                    let (result, is_error) = if let Some(refusal) = refusal {
+                        (refusal, true)
+                    } else if !permission_mode.allows_tool(&tool_name) {
+                        (
+                            format!(
+                                "Tool '{}' is not allowed: this session is in {} mode",
+                                tool_name,
+                                permission_mode.as_str()
+                            ),
+                            true,
+                        )
+                    } else if tool_name == "call_agent" {
                         // Acquire A2A concurrency permit
                         match semaphore.acquire_owned().await {
                             Err(_) => (
@@ -611,8 +1129,24 @@ async fn execute_with_tools(
                 match result {
                     Ok((tool_name, tool_id, result, is_error)) => {
                         tools_completed += 1;
+                        trace.record(
+                            "tool_result",
+                            json!({ "name": &tool_name, "success": !is_error, "iteration": iteration }),
+                        );
                         if !is_error && (tool_name == "write_file" || tool_name == "edit_file") {
                             has_written_file = true;
+                            let path = tool_uses
+                                .iter()
+                                .find(|tu| tu["id"] == tool_id.as_str())
+                                .and_then(|tu| tu["input"]["path"].as_str());
+                            if let Some(path) = path {
+                                crate::audit_trail::file_change(
+                                    &format!("tool:{}", tool_name),
+                                    &std::path::Path::new(wd).join(path).to_string_lossy(),
+                                    "modified",
+                                    crate::audit_trail::Attribution::session(*session_id),
+                                );
+                            }
                         }
 
                         let summary: String = result.chars().take(200).collect();
@@ -633,6 +1167,12 @@ async fn execute_with_tools(
                                 iteration,
                                 tools_completed,
                                 tools_total,
+                                progress: Progress::determinate(
+                                    "running_tools",
+                                    tools_completed,
+                                    tools_total,
+                                    format!("{} of {} tools finished", tools_completed, tools_total),
+                                ),
                             },
                         )
                         .await;
@@ -648,6 +1188,7 @@ async fn execute_with_tools(
                     }
                     Err(e) => {
                         tracing::error!("Tool task panicked: {}", e);
+                        trace.record("tool_result", json!({ "success": false, "panicked": true, "iteration": iteration }));
                         tools_completed += 1;
                         tool_results.push(json!({
                             "type": "tool_result",
@@ -679,19 +1220,73 @@ async fn execute_with_tools(
 
         // Auto-fix phase
         if !has_written_file && !full_text.is_empty() && agent_text_len > 50 {
-            execute_auto_fix(sender, state, model, max_tokens, system_prompt, &conversation, &tool_defs, wd, iteration).await;
+            trace.record("auto_fix", json!({ "iteration": iteration }));
+            ws_send(
+                sender,
+                &WsServerMessage::Phase {
+                    progress: Progress::indeterminate("auto_fix", "Applying described changes"),
+                },
+            )
+            .await;
+            execute_auto_fix(
+                sender,
+                state,
+                model,
+                max_tokens,
+                system_prompt,
+                &conversation,
+                &tool_defs,
+                wd,
+                iteration,
+                &request_id,
+            )
+            .await;
+        }
+
+        // Safety guard — a blocked response is not stored
+        let verdict = crate::safety::screen_response(&full_text, "ws");
+        if !report_guard(sender, trace, verdict).await {
+            break;
         }
 
         // Store messages if session present
         if let Some(sid) = session_id {
-            let _ = store_ws_messages(state, sid, prompt, &full_text).await;
+            let _ = store_ws_messages(state, sid, Some(model), prompt, &full_text).await;
         }
+        mirror_exchange(Exchange {
+            session_id: *session_id,
+            working_directory: wd.to_string(),
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            response: full_text.clone(),
+            completed_at: chrono::Utc::now(),
+        });
+        hooks::dispatch(state.db.clone(), HookPayload {
+            event: HookEvent::Completed,
+            execution_id: trace.execution_id().to_string(),
+            session_id: *session_id,
+            model: model.to_string(),
+            working_directory: wd.to_string(),
+            prompt: prompt.to_string(),
+            response: full_text.clone(),
+            detail: Value::Null,
+        });
 
         // Complete
+        trace.record(
+            "completed",
+            json!({ "iterations": iteration, "response_chars": full_text.len(), "stop_reason": &stop_reason }),
+        );
         ws_send(
             sender,
             &WsServerMessage::Complete {
                 duration_ms: execution_start.elapsed().as_millis() as u64,
+                stream: StreamEvent::finish(
+                    &request_id,
+                    "anthropic",
+                    &stop_reason,
+                    Some(usage),
+                ),
             },
         )
         .await;
@@ -710,6 +1305,7 @@ async fn execute_auto_fix(
     tool_defs: &[Value],
     wd: &str,
     iteration: u32,
+    request_id: &str,
 ) {
     // Check if the full text mentions fix/edit keywords
     let full_text: String = conversation.iter()
@@ -814,6 +1410,7 @@ async fn execute_auto_fix(
                     sender,
                     &WsServerMessage::Token {
                         content: text.to_string(),
+                        stream: StreamEvent::delta(request_id, "anthropic", text),
                     },
                 )
                 .await;
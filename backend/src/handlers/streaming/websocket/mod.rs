@@ -5,7 +5,11 @@
 //! - `execute` — core streaming execution (no-tools + tools-enabled paths)
 //!
 //! Message types: Start/Token/Iteration/ToolCall/ToolResult/ToolProgress/
-//! ViewHint/Fallback/Heartbeat/Complete/Error.
+//! Phase/ViewHint/Fallback/Heartbeat/Complete/Error. Iteration, ToolProgress
+//! and Phase carry a structured `Progress` (determinate vs indeterminate).
+//!
+//! While an execution runs, `cancel` and `approval_decision` are handled
+//! right away; other client messages wait until it finishes.
 //!
 //! Remains CH-specific because:
 //! - CH uses its own WsClientMessage/WsServerMessage types
@@ -15,7 +19,8 @@
 
 mod execute;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use axum::extract::State;
 use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
@@ -29,6 +34,30 @@ use jaskier_core::auth::validate_ws_token;
 use crate::models::*;
 use crate::state::AppState;
 
+/// Open `/ws/chat` connections (one per frontend tab).
+static ACTIVE_CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of currently connected WebSocket clients.
+pub fn active_connections() -> usize {
+    ACTIVE_CONNECTIONS.load(Ordering::Relaxed)
+}
+
+/// Decrements the connection count however `handle_ws` exits.
+struct ConnectionGuard;
+
+impl ConnectionGuard {
+    fn new() -> Self {
+        ACTIVE_CONNECTIONS.fetch_add(1, Ordering::Relaxed);
+        Self
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        ACTIVE_CONNECTIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
 /// Send a `WsServerMessage` through the WebSocket sink.
 pub(crate) async fn ws_send(sender: &mut SplitSink<WebSocket, WsMessage>, msg: &WsServerMessage) {
     let json = match serde_json::to_string(msg) {
@@ -68,16 +97,27 @@ pub async fn ws_chat(
 async fn handle_ws(socket: WebSocket, state: AppState) {
     let (mut sender, mut receiver) = futures_util::StreamExt::split(socket);
     let cancel = CancellationToken::new();
+    let _connection = ConnectionGuard::new();
 
     tracing::info!("WebSocket client connected");
 
+    // Messages received during an execution that wait for it to finish.
+    let mut deferred: VecDeque<String> = VecDeque::new();
+    let mut disconnected = false;
+
     loop {
-        let msg = tokio::select! {
-            msg = futures_util::StreamExt::next(&mut receiver) => msg,
-            // Send heartbeat every 30s when idle
-            _ = tokio::time::sleep(std::time::Duration::from_secs(30)) => {
-                ws_send(&mut sender, &WsServerMessage::Heartbeat).await;
-                continue;
+        let msg = if let Some(text) = deferred.pop_front() {
+            Some(Ok(WsMessage::Text(text.into())))
+        } else if disconnected {
+            None
+        } else {
+            tokio::select! {
+                msg = futures_util::StreamExt::next(&mut receiver) => msg,
+                // Send heartbeat every 30s when idle
+                _ = tokio::time::sleep(std::time::Duration::from_secs(30)) => {
+                    ws_send(&mut sender, &WsServerMessage::Heartbeat).await;
+                    continue;
+                }
             }
         };
 
@@ -107,23 +147,57 @@ async fn handle_ws(socket: WebSocket, state: AppState) {
                         tracing::info!("Cancel requested");
                         cancel.cancel();
                     }
+                    WsClientMessage::ApprovalDecision {
+                        id,
+                        decision,
+                        reason,
+                    } => {
+                        decide_approval(&state, &id, decision, reason.as_deref()).await;
+                    }
                     WsClientMessage::Execute {
                         prompt,
                         model,
                         tools_enabled,
                         session_id,
+                        web_search,
+                        speak,
+                        attachments,
                     } => {
                         let child_cancel = cancel.child_token();
-                        execute::execute_streaming_ws(
+                        // The execution id doubles as the correlation id.
+                        let execution_id = crate::correlation::new_id();
+                        let execution = execute::execute_streaming_ws(
                             &mut sender,
                             &state,
                             prompt,
                             model,
                             tools_enabled.unwrap_or(false),
                             session_id,
-                            child_cancel,
-                        )
-                        .await;
+                            web_search,
+                            speak.unwrap_or(false),
+                            attachments,
+                            child_cancel.clone(),
+                        );
+                        let execution = crate::correlation::scope(execution_id, execution);
+                        tokio::pin!(execution);
+                        // Keep reading: cancellation and approval decisions
+                        // must reach the running execution.
+                        loop {
+                            let incoming = futures_util::StreamExt::next(&mut receiver);
+                            let msg = tokio::select! {
+                                _ = &mut execution => break,
+                                msg = incoming, if !disconnected => msg,
+                            };
+                            match msg {
+                                Some(Ok(WsMessage::Text(text))) => {
+                                    if !handle_mid_execution(&state, &child_cancel, &text).await {
+                                        deferred.push_back(text.to_string());
+                                    }
+                                }
+                                Some(Ok(WsMessage::Close(_))) | None => disconnected = true,
+                                _ => {}
+                            }
+                        }
                     }
                 }
             }
@@ -138,3 +212,60 @@ async fn handle_ws(socket: WebSocket, state: AppState) {
         }
     }
 }
+
+/// Handle a client message received while an execution runs; `false` when
+/// it has to wait until the execution finishes.
+async fn handle_mid_execution(state: &AppState, cancel: &CancellationToken, text: &str) -> bool {
+    match serde_json::from_str::<WsClientMessage>(text) {
+        Ok(WsClientMessage::Cancel) => {
+            tracing::info!("Cancel requested");
+            cancel.cancel();
+            true
+        }
+        Ok(WsClientMessage::ApprovalDecision {
+            id,
+            decision,
+            reason,
+        }) => {
+            decide_approval(state, &id, decision, reason.as_deref()).await;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Apply an `approval_decision` sent by the client (see `approvals.rs`).
+async fn decide_approval(
+    state: &AppState,
+    id: &str,
+    decision: crate::approvals::Verdict,
+    reason: Option<&str>,
+) {
+    let Ok(id) = id.parse::<uuid::Uuid>() else {
+        tracing::warn!("WS approval decision with invalid id '{}'", id);
+        return;
+    };
+    match crate::approvals::decide(&state.db, id, decision, reason).await {
+        Ok(Some(_)) => {}
+        Ok(None) => tracing::warn!("WS approval decision for {}: not pending", id),
+        Err(e) => tracing::error!("WS approval decision for {} failed: {}", id, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn cancel_and_approval_decisions_are_handled_mid_execution() {
+        let state = AppState::new_test();
+        let cancel = CancellationToken::new();
+        let decision = r#"{"type":"approval_decision","id":"not-a-uuid","decision":"deny"}"#;
+        assert!(handle_mid_execution(&state, &cancel, decision).await);
+        assert!(!cancel.is_cancelled());
+        assert!(handle_mid_execution(&state, &cancel, r#"{"type":"cancel"}"#).await);
+        assert!(cancel.is_cancelled());
+        assert!(!handle_mid_execution(&state, &cancel, r#"{"type":"ping"}"#).await);
+        assert!(!handle_mid_execution(&state, &cancel, "not json").await);
+    }
+}
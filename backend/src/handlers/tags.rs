@@ -5,6 +5,8 @@
 //! - `POST /api/sessions/{id}/tags`          — add tag(s) to a session
 //! - `DELETE /api/sessions/{id}/tags/{tag}`  — remove a tag from a session
 //! - `GET  /api/sessions/search`             — full-text search + tag filter
+//! - `GET  /api/conversations/search`        — ranked message hits with
+//!   highlighted snippets and neighbouring messages (search palette)
 
 use axum::Json;
 use axum::extract::{Path, Query, State};
@@ -68,6 +70,72 @@ struct SearchRow {
     rank: Option<f32>,
 }
 
+/// Query parameters for the conversation search palette.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConversationSearchParams {
+    /// Web-search style query: `"exact phrase"`, `or`, `-exclude`.
+    pub q: String,
+    /// Comma-separated list of tags (session must have at least one).
+    pub tags: Option<String>,
+    /// Restrict to one session.
+    pub session_id: Option<String>,
+    /// `user` or `assistant`.
+    pub role: Option<String>,
+    /// Model id prefix, e.g. `claude-opus`.
+    pub model: Option<String>,
+    /// Lower bound on message time (RFC 3339 or `YYYY-MM-DD`).
+    pub since: Option<String>,
+    /// Upper bound on message time, exclusive (RFC 3339 or `YYYY-MM-DD`).
+    pub until: Option<String>,
+    /// Maximum number of hits (default 20, max 100).
+    pub limit: Option<i64>,
+    /// Offset for pagination (default 0).
+    pub offset: Option<i64>,
+}
+
+/// A neighbouring message shown around a hit.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ContextMessage {
+    pub role: String,
+    pub preview: String,
+}
+
+/// A ranked message hit.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConversationHit {
+    pub session_id: String,
+    pub session_title: String,
+    pub message_id: String,
+    pub role: String,
+    pub model: Option<String>,
+    pub timestamp: String,
+    pub rank: f32,
+    /// Matching fragments, terms wrapped in `«` / `»`.
+    pub snippet: String,
+    pub before: Option<ContextMessage>,
+    pub after: Option<ContextMessage>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct ConversationHitRow {
+    session_id: uuid::Uuid,
+    session_title: String,
+    message_id: uuid::Uuid,
+    role: String,
+    model: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    rank: f32,
+    total: i64,
+    snippet: String,
+    before_role: Option<String>,
+    before_preview: Option<String>,
+    after_role: Option<String>,
+    after_preview: Option<String>,
+}
+
+/// Characters of each neighbouring message returned as context.
+const CONTEXT_PREVIEW_CHARS: i32 = 160;
+
 // ── GET /api/sessions/{id}/tags ─────────────────────────────────────────────
 
 #[utoipa::path(get, path = "/api/sessions/{id}/tags", tag = "tags",
@@ -381,6 +449,168 @@ pub async fn search_sessions(
     })))
 }
 
+// ── GET /api/conversations/search ───────────────────────────────────────────
+
+/// Parse a `since` / `until` bound: RFC 3339, or a bare date (midnight UTC).
+fn parse_time_bound(raw: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let raw = raw.trim();
+    chrono::DateTime::parse_from_rfc3339(raw)
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|t| t.and_utc())
+        })
+}
+
+#[utoipa::path(get, path = "/api/conversations/search", tag = "tags",
+    params(
+        ("q" = String, Query, description = "Web-search style query"),
+        ("tags" = Option<String>, Query, description = "Comma-separated tag filter"),
+        ("session_id" = Option<String>, Query, description = "Restrict to one session"),
+        ("role" = Option<String>, Query, description = "user | assistant"),
+        ("model" = Option<String>, Query, description = "Model id prefix"),
+        ("since" = Option<String>, Query, description = "RFC 3339 or YYYY-MM-DD"),
+        ("until" = Option<String>, Query, description = "RFC 3339 or YYYY-MM-DD (exclusive)"),
+        ("limit" = Option<i64>, Query, description = "Max hits (default 20)"),
+        ("offset" = Option<i64>, Query, description = "Pagination offset"),
+    ),
+    responses((status = 200, description = "Ranked message hits")))]
+pub async fn search_conversations(
+    State(state): State<AppState>,
+    Query(params): Query<ConversationSearchParams>,
+) -> Result<Json<Value>, StatusCode> {
+    let query_text = params.q.trim();
+    if query_text.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let limit = params.limit.unwrap_or(20).clamp(1, 100);
+    let offset = params.offset.unwrap_or(0).max(0);
+
+    let tag_filter: Vec<String> = params
+        .tags
+        .as_deref()
+        .unwrap_or("")
+        .split(',')
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+    let session_id: Option<uuid::Uuid> = match params.session_id.as_deref() {
+        Some(id) if !id.trim().is_empty() => {
+            Some(id.trim().parse().map_err(|_| StatusCode::BAD_REQUEST)?)
+        }
+        _ => None,
+    };
+    let bound = |raw: &Option<String>| match raw.as_deref() {
+        Some(r) if !r.trim().is_empty() => {
+            parse_time_bound(r).map(Some).ok_or(StatusCode::BAD_REQUEST)
+        }
+        _ => Ok(None),
+    };
+    let since = bound(&params.since)?;
+    let until = bound(&params.until)?;
+    let role = params
+        .role
+        .as_deref()
+        .map(str::trim)
+        .filter(|r| !r.is_empty());
+    let model = params
+        .model
+        .as_deref()
+        .map(str::trim)
+        .filter(|m| !m.is_empty());
+
+    // Rank + filter on the GIN index first, then decorate only the page of
+    // hits with headlines and neighbouring messages.
+    let rows = sqlx::query_as::<_, ConversationHitRow>(
+        "WITH q AS (SELECT websearch_to_tsquery('english', $1) AS tsq), \
+        hits AS ( \
+            SELECT m.id, m.session_id, m.role, m.model, m.content, m.created_at, \
+                ts_rank_cd(m.search_vector, q.tsq) AS rank, \
+                COUNT(*) OVER () AS total \
+            FROM ch_messages m, q \
+            WHERE m.search_vector @@ q.tsq \
+                AND ($2::UUID IS NULL OR m.session_id = $2) \
+                AND ($3::TEXT IS NULL OR m.role = $3) \
+                AND ($4::TEXT IS NULL OR m.model ILIKE ($4 || '%')) \
+                AND ($5::TIMESTAMPTZ IS NULL OR m.created_at >= $5) \
+                AND ($6::TIMESTAMPTZ IS NULL OR m.created_at < $6) \
+                AND (cardinality($7::TEXT[]) = 0 OR EXISTS ( \
+                    SELECT 1 FROM ch_session_tags t \
+                    WHERE t.session_id = m.session_id AND t.tag = ANY($7))) \
+            ORDER BY rank DESC, m.created_at DESC \
+            LIMIT $8 OFFSET $9 \
+        ) \
+        SELECT h.session_id, s.title AS session_title, h.id AS message_id, \
+            h.role, h.model, h.created_at, h.rank, h.total, \
+            ts_headline('english', h.content, q.tsq, \
+                'StartSel=«, StopSel=», MaxWords=35, MinWords=12, \
+                 MaxFragments=2, FragmentDelimiter=\" … \"') AS snippet, \
+            b.role AS before_role, LEFT(b.content, $10) AS before_preview, \
+            a.role AS after_role, LEFT(a.content, $10) AS after_preview \
+        FROM hits h \
+        CROSS JOIN q \
+        JOIN ch_sessions s ON s.id = h.session_id \
+        LEFT JOIN LATERAL ( \
+            SELECT role, content FROM ch_messages p \
+            WHERE p.session_id = h.session_id AND p.created_at < h.created_at \
+            ORDER BY p.created_at DESC LIMIT 1 \
+        ) b ON TRUE \
+        LEFT JOIN LATERAL ( \
+            SELECT role, content FROM ch_messages n \
+            WHERE n.session_id = h.session_id AND n.created_at > h.created_at \
+            ORDER BY n.created_at ASC LIMIT 1 \
+        ) a ON TRUE \
+        ORDER BY h.rank DESC, h.created_at DESC",
+    )
+    .bind(query_text)
+    .bind(session_id)
+    .bind(role)
+    .bind(model)
+    .bind(since)
+    .bind(until)
+    .bind(&tag_filter)
+    .bind(limit)
+    .bind(offset)
+    .bind(CONTEXT_PREVIEW_CHARS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("Conversation search failed: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let total = rows.first().map(|r| r.total).unwrap_or(0);
+    let context = |role: Option<String>, preview: Option<String>| {
+        role.zip(preview)
+            .map(|(role, preview)| ContextMessage { role, preview })
+    };
+    let hits: Vec<ConversationHit> = rows
+        .into_iter()
+        .map(|r| ConversationHit {
+            session_id: r.session_id.to_string(),
+            session_title: r.session_title,
+            message_id: r.message_id.to_string(),
+            role: r.role,
+            model: r.model,
+            timestamp: r.created_at.to_rfc3339(),
+            rank: r.rank,
+            snippet: r.snippet,
+            before: context(r.before_role, r.before_preview),
+            after: context(r.after_role, r.after_preview),
+        })
+        .collect();
+
+    Ok(Json(json!({
+        "results": hits,
+        "total": total,
+        "query": query_text,
+        "tags": tag_filter,
+    })))
+}
+
 // ── GET /api/tags — list all unique tags with counts ────────────────────────
 
 #[utoipa::path(get, path = "/api/tags", tag = "tags",
@@ -407,3 +637,17 @@ pub async fn list_all_tags(
         "tags": tag_list,
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_bounds_accept_rfc3339_and_dates() {
+        let t = parse_time_bound("2026-03-01T12:30:00+02:00").unwrap();
+        assert_eq!(t.to_rfc3339(), "2026-03-01T10:30:00+00:00");
+        let d = parse_time_bound(" 2026-03-01 ").unwrap();
+        assert_eq!(d.to_rfc3339(), "2026-03-01T00:00:00+00:00");
+        assert!(parse_time_bound("yesterday").is_none());
+    }
+}
@@ -0,0 +1,462 @@
+//! Post-response hooks — user scripts run after a prompt finishes, e.g. to
+//! lint generated code or post the answer to a wiki.
+//!
+//! Opt-in via `[[hooks]]` entries in the config file (`hydra_config.rs`,
+//! applied without a restart):
+//!
+//! ```toml
+//! [[hooks]]
+//! name = "lint"
+//! command = "C:/scripts/lint.cmd"
+//! args = ["--fix"]
+//! events = ["completed"]        # completed | failed
+//! model_prefix = "claude-"
+//! timeout_secs = 30             # 1-300
+//! ```
+//!
+//! Hooks fire for WebSocket chats, `/api/claude/chat` and background queue
+//! runs. Each matching hook gets the prompt record as JSON on stdin
+//! (`event`, `execution_id`, `session_id`, `model`, `working_directory`,
+//! `prompt`, `response`, `detail`). It runs in a fresh temporary directory
+//! with a cleared environment (only `PATH` / system variables plus
+//! `CH_HOOK_*`), in its own process group, and when its timeout expires the
+//! whole group is killed — scripts it started included — keeping the output
+//! read so far. Exit code and truncated stdout/stderr are stored in
+//! `ch_hook_runs` and returned with the prompt trace, and each run is
+//! recorded in the audit trail (see `audit_trail.rs`). Hooks never block or
+//! fail the chat.
+//!
+//! - `GET /api/hooks` — configured hooks
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use crate::hydra_config::Derived;
+use crate::processes::{kill_process_group, own_process_group};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 300;
+
+/// Captured stdout/stderr are cut to this many bytes each.
+const MAX_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Environment variables passed through to hook processes.
+const PASSTHROUGH_ENV: &[&str] = &["PATH", "SYSTEMROOT", "SystemRoot", "COMSPEC", "PATHEXT", "LANG"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Completed,
+    Failed,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HookEvent::Completed => "completed",
+            HookEvent::Failed => "failed",
+        }
+    }
+}
+
+fn default_events() -> Vec<HookEvent> {
+    vec![HookEvent::Completed]
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// One user-registered hook.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Events that trigger the hook (default: `completed`).
+    #[serde(default = "default_events")]
+    pub events: Vec<HookEvent>,
+    /// Only run for models starting with this prefix.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl HookConfig {
+    pub(crate) fn validate(&self, prefix: &str, errors: &mut Vec<String>) {
+        if self.name.trim().is_empty() {
+            errors.push(format!("{}.name must not be empty", prefix));
+        }
+        if self.command.trim().is_empty() {
+            errors.push(format!("{}.command must not be empty", prefix));
+        }
+        if self.events.is_empty() {
+            errors.push(format!("{}.events must name at least one event", prefix));
+        }
+        if let Some(secs) = self.timeout_secs
+            && !(1..=MAX_TIMEOUT_SECS).contains(&secs)
+        {
+            errors.push(format!(
+                "{}.timeout_secs must be between 1 and {}, got {}",
+                prefix, MAX_TIMEOUT_SECS, secs
+            ));
+        }
+    }
+
+    fn matches(&self, event: HookEvent, model: &str) -> bool {
+        self.enabled
+            && self.events.contains(&event)
+            && self
+                .model_prefix
+                .as_deref()
+                .is_none_or(|prefix| model.starts_with(prefix))
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(
+            self.timeout_secs
+                .unwrap_or(DEFAULT_TIMEOUT_SECS)
+                .clamp(1, MAX_TIMEOUT_SECS),
+        )
+    }
+}
+
+static HOOKS: Derived<Arc<[HookConfig]>> = Derived::new(|config| config.hooks.clone().into());
+
+/// Hooks from the config file's `[[hooks]]`; empty when none are set.
+pub fn hooks() -> Arc<[HookConfig]> {
+    HOOKS.get()
+}
+
+/// The prompt record handed to hooks on stdin.
+#[derive(Debug, Clone, Serialize)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    pub execution_id: String,
+    pub session_id: Option<uuid::Uuid>,
+    pub model: String,
+    /// Empty = the session's (or the global) working directory, looked up
+    /// when a hook matches.
+    pub working_directory: String,
+    pub prompt: String,
+    pub response: String,
+    /// Failure reason / extra context from the prompt trace.
+    pub detail: Value,
+}
+
+/// Outcome of one hook run.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct HookRun {
+    pub hook: String,
+    pub event: String,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: i64,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Run every matching hook in the background. No-op when none match.
+pub fn dispatch(db: PgPool, mut payload: HookPayload) {
+    let matching: Vec<HookConfig> = hooks()
+        .iter()
+        .filter(|h| h.matches(payload.event, &payload.model))
+        .cloned()
+        .collect();
+    if matching.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        if payload.working_directory.is_empty() {
+            payload.working_directory = working_directory(&db, payload.session_id).await;
+        }
+        let stdin = serde_json::to_vec(&payload).unwrap_or_default();
+        for hook in &matching {
+            let run = run_hook(hook, &payload, &stdin).await;
+            if run.exit_code != Some(0) {
+                tracing::warn!(
+                    hook = %hook.name,
+                    exit_code = ?run.exit_code,
+                    timed_out = run.timed_out,
+                    "hooks: hook did not succeed"
+                );
+            }
+            if let Err(e) = persist(&db, &payload.execution_id, &run).await {
+                tracing::warn!("hooks: failed to store run of {}: {}", hook.name, e);
+            }
+        }
+    });
+}
+
+/// The session's working directory, else the global one ("" when unset).
+async fn working_directory(db: &PgPool, session_id: Option<uuid::Uuid>) -> String {
+    if let Some(id) = session_id
+        && let Ok(Some(wd)) = crate::handlers::sessions::session_working_directory(db, &id).await
+    {
+        return wd;
+    }
+    sqlx::query_scalar("SELECT COALESCE(working_directory, '') FROM ch_settings WHERE id = 1")
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+async fn run_hook(hook: &HookConfig, payload: &HookPayload, stdin: &[u8]) -> HookRun {
+    let started = Instant::now();
+    let finish = |exit_code, timed_out, stdout: &[u8], stderr: &[u8]| HookRun {
+        hook: hook.name.clone(),
+        event: payload.event.as_str().to_string(),
+        exit_code,
+        timed_out,
+        duration_ms: started.elapsed().as_millis() as i64,
+        stdout: truncate_output(stdout),
+        stderr: truncate_output(stderr),
+    };
+
+    let sandbox = sandbox_dir(&payload.execution_id, &hook.name);
+    if let Err(e) = tokio::fs::create_dir_all(&sandbox).await {
+        return finish(None, false, b"", format!("cannot create sandbox dir: {}", e).as_bytes());
+    }
+
+    let mut cmd = tokio::process::Command::new(&hook.command);
+    cmd.args(&hook.args)
+        .current_dir(&sandbox)
+        .env_clear()
+        .envs(
+            PASSTHROUGH_ENV
+                .iter()
+                .filter_map(|k| std::env::var(k).ok().map(|v| (*k, v))),
+        )
+        .env("TEMP", &sandbox)
+        .env("TMP", &sandbox)
+        .env("CH_HOOK_EVENT", payload.event.as_str())
+        .env("CH_HOOK_EXECUTION_ID", &payload.execution_id)
+        .env("CH_HOOK_WORKING_DIRECTORY", &payload.working_directory)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    own_process_group(&mut cmd);
+
+    let run = match cmd.spawn() {
+        Ok(mut child) => {
+            // Feed stdin concurrently so a hook that doesn't read it can't stall us.
+            if let Some(mut pipe) = child.stdin.take() {
+                let input = stdin.to_vec();
+                tokio::spawn(async move {
+                    let _ = pipe.write_all(&input).await;
+                });
+            }
+            let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+            let (mut out, mut err) = (Vec::new(), Vec::new());
+            let waited = tokio::time::timeout(hook.timeout(), async {
+                let (_, _, status) = tokio::join!(
+                    read_into(stdout, &mut out),
+                    read_into(stderr, &mut err),
+                    child.wait()
+                );
+                status
+            })
+            .await;
+            match waited {
+                Ok(Ok(status)) => finish(status.code(), false, &out, &err),
+                Ok(Err(e)) => finish(None, false, &out, e.to_string().as_bytes()),
+                // Output read before the deadline is kept.
+                Err(_) => {
+                    kill_process_group(&mut child, "hooks").await;
+                    finish(None, true, &out, &err)
+                }
+            }
+        }
+        Err(e) => finish(None, false, b"", format!("cannot start hook: {}", e).as_bytes()),
+    };
+
+    let _ = tokio::fs::remove_dir_all(&sandbox).await;
+    crate::audit_trail::command(
+        "hook",
+        crate::audit_trail::CommandRun {
+            binary: &hook.command,
+            args: &hook.args,
+            cwd: &sandbox.to_string_lossy(),
+            exit_code: run.exit_code,
+            duration_ms: run.duration_ms as u64,
+        },
+        crate::audit_trail::Attribution::session(payload.session_id),
+    );
+    run
+}
+
+/// Append everything read from `pipe` to `buf`, chunk by chunk, so that what
+/// arrived before a timeout is not lost.
+async fn read_into(pipe: Option<impl AsyncRead + Unpin>, buf: &mut Vec<u8>) {
+    let Some(mut pipe) = pipe else {
+        return;
+    };
+    let mut chunk = [0u8; 4096];
+    while let Ok(n @ 1..) = pipe.read(&mut chunk).await {
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Per-run scratch directory under the system temp dir.
+fn sandbox_dir(execution_id: &str, hook_name: &str) -> PathBuf {
+    let safe: String = hook_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    std::env::temp_dir()
+        .join("claudehydra-hooks")
+        .join(format!("{}-{}", execution_id, safe))
+}
+
+fn truncate_output(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+    format!(
+        "{}\n... [truncated, {} bytes total]",
+        &text[..text.floor_char_boundary(MAX_OUTPUT_BYTES)],
+        text.len()
+    )
+}
+
+async fn persist(db: &PgPool, execution_id: &str, run: &HookRun) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO ch_hook_runs \
+         (execution_id, hook, event, exit_code, timed_out, duration_ms, stdout, stderr) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+    )
+    .bind(execution_id)
+    .bind(&run.hook)
+    .bind(&run.event)
+    .bind(run.exit_code)
+    .bind(run.timed_out)
+    .bind(run.duration_ms)
+    .bind(&run.stdout)
+    .bind(&run.stderr)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Hook runs recorded for one prompt execution, oldest first.
+pub async fn runs_for_execution(db: &PgPool, execution_id: &str) -> Result<Vec<HookRun>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT hook, event, exit_code, timed_out, duration_ms, stdout, stderr \
+         FROM ch_hook_runs WHERE execution_id = $1 ORDER BY id",
+    )
+    .bind(execution_id)
+    .fetch_all(db)
+    .await
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/hooks
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn list_hooks() -> Json<Value> {
+    Json(json!({ "hooks": &*hooks() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(json: Value) -> HookConfig {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn defaults_to_completed_event() {
+        let h = hook(json!({ "name": "lint", "command": "lint" }));
+        assert!(h.enabled);
+        assert!(h.matches(HookEvent::Completed, "claude-sonnet-4-6"));
+        assert!(!h.matches(HookEvent::Failed, "claude-sonnet-4-6"));
+        assert_eq!(h.timeout(), Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+    }
+
+    #[test]
+    fn model_prefix_and_disabled_filter() {
+        let h = hook(json!({
+            "name": "wiki", "command": "post", "events": ["completed", "failed"],
+            "model_prefix": "gemini-", "timeout_secs": 9999,
+        }));
+        assert!(h.matches(HookEvent::Failed, "gemini-2.5-pro"));
+        assert!(!h.matches(HookEvent::Completed, "claude-opus-4-6"));
+        assert_eq!(h.timeout(), Duration::from_secs(MAX_TIMEOUT_SECS));
+
+        let off = hook(json!({ "name": "off", "command": "x", "enabled": false }));
+        assert!(!off.matches(HookEvent::Completed, "any"));
+    }
+
+    #[test]
+    fn output_truncated_on_char_boundary() {
+        let long = "ż".repeat(MAX_OUTPUT_BYTES);
+        let out = truncate_output(long.as_bytes());
+        assert!(out.contains("[truncated"));
+        assert!(out.len() < long.len());
+        assert_eq!(truncate_output(b"ok"), "ok");
+    }
+
+    #[test]
+    fn invalid_hooks_are_reported() {
+        let mut errors = Vec::new();
+        hook(json!({ "name": " ", "command": "", "events": [], "timeout_secs": 0 }))
+            .validate("hooks[0]", &mut errors);
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        let mut errors = Vec::new();
+        hook(json!({ "name": "lint", "command": "lint" })).validate("hooks[1]", &mut errors);
+        assert!(errors.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn timeouts_keep_partial_output_and_kill_the_group() {
+        let marker = std::env::temp_dir().join(format!("ch-hook-{}", uuid::Uuid::new_v4()));
+        let script = format!(
+            "echo started; (sleep 2; touch {}) & sleep 30",
+            marker.display()
+        );
+        let h = hook(json!({
+            "name": "slow", "command": "sh", "args": ["-c", script], "timeout_secs": 1,
+        }));
+        let payload = HookPayload {
+            event: HookEvent::Completed,
+            execution_id: "test-timeout".to_string(),
+            session_id: None,
+            model: "claude-sonnet-4-6".to_string(),
+            working_directory: String::new(),
+            prompt: String::new(),
+            response: String::new(),
+            detail: Value::Null,
+        };
+        let run = run_hook(&h, &payload, b"{}").await;
+        assert!(run.timed_out);
+        assert_eq!(run.stdout.trim(), "started");
+        // The background `sleep 2; touch` was killed with the group.
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        assert!(!marker.exists());
+    }
+
+    #[test]
+    fn sandbox_dir_sanitises_hook_name() {
+        let dir = sandbox_dir("exec-1", "../evil hook");
+        assert!(dir.ends_with("exec-1-___evil_hook"));
+    }
+}
@@ -0,0 +1,1281 @@
+//! Hydra config file — the backend's tunables (ports, queue limits, timeouts,
+//! models, the Ollama endpoint, optional features) in one TOML file,
+//! validated on load and applied without a restart where possible.
+//!
+//! The file is `CH_CONFIG_FILE`, default `<data dir>/hydra.toml` (see
+//! `metrics_snapshot` for the data dir). Every key is optional; for each one
+//! the env var named in `ENV_OVERRIDES` wins over the file, and the file over
+//! the built-in default.
+//!
+//! ```toml
+//! [server]                      # applied on restart
+//! bind = "127.0.0.1"            # CH_BIND_ADDR
+//! port = 8082                   # PORT
+//!
+//! [queue]                       # background prompts (queue/)
+//! max_concurrent = 2            # 1-16
+//! max_attempts = 3              # 1-100
+//! max_retries = 1               # 0-10
+//! ollama_batch = 4              # 0-32
+//! cpu_threshold = 30            # percent, 1-100
+//! idle_minutes = 10
+//! file_locks = false
+//! lanes = { anthropic = 2, ollama = 1 }
+//!
+//! [ollama]
+//! url = "http://localhost:11434"
+//! max_generations = 2           # 1-64
+//! keep_alive = "30m"            # seconds or Go duration, -1 = forever
+//!
+//! [routing]
+//! witcher_local_model = "auto"
+//! fallback_models = ["claude-sonnet-4-6", "ollama/llama3.1:8b"]
+//!
+//! [timeouts]
+//! background_prompt_secs = 300  # 1-3600
+//!
+//! [budget]                      # USD caps per provider (budget.rs)
+//! daily = { anthropic = 5.0 }
+//! weekly = { anthropic = 20.0, google = 10.0 }
+//!
+//! [paths]                       # executables (paths.rs)
+//! claude = "C:/Users/me/AppData/Roaming/npm/claude.cmd"
+//! ollama = "/opt/ollama/bin/ollama"
+//!
+//! [embeddings]                  # url defaults to the [ollama] url
+//! backend = "ollama"            # ollama | openai
+//! model = "nomic-embed-text"
+//!
+//! [rag]                         # dir and reindex_secs on restart
+//! dir = "C:/Users/me/hydra"
+//! top_k = 4                     # 0-20
+//! min_score = 0.35              # 0-1
+//!
+//! [compaction]                  # url defaults to the [ollama] url
+//! model = "llama3.2:3b"
+//! token_budget = 24000
+//! keep_recent = 6
+//! auto = true
+//!
+//! [web_search]
+//! provider = "searxng"          # searxng | brave | serper
+//! url = "http://localhost:8888"
+//! max_results = 5               # 1-10
+//!
+//! [tts]
+//! backend = "piper"             # sapi | edge_tts | piper
+//! voice = "C:/voices/en_US-amy-medium.onnx"
+//!
+//! [transcription]
+//! backend = "whisper_cpp"       # openai | whisper_cpp
+//! whisper_model = "C:/models/ggml-base.en.bin"
+//!
+//! [claude_cli]
+//! args = ["--add-dir", "C:/work"]
+//! idle_secs = 900               # 0 = until shutdown
+//! max_processes = 8             # 0 = unlimited
+//!
+//! [session_memory]
+//! scope = "session"             # session | all
+//!
+//! [safety]
+//! secrets = "redact"            # off | warn | redact | block
+//! injection = "warn"            # off | warn | block
+//!
+//! [permissions]
+//! mode = "ask"                  # yolo | ask | read_only
+//!
+//! [markdown_vault]
+//! dir = "C:/Users/me/Obsidian/hydra"
+//! layout = "daily"              # daily | session
+//!
+//! [metrics]                     # applied on restart
+//! snapshot_secs = 15            # 0 disables
+//! history_secs = 60             # 0 disables
+//!
+//! [access]                      # cors_origins on restart
+//! auth_loopback = false
+//! cors_origins = ["http://192.168.1.20:5199"]
+//!
+//! [rules]                       # check_secs on restart
+//! manifest_url = "https://example.com/rules.json"
+//! manifest_pubkey = "<hex Ed25519 key>"
+//! check_secs = 86400
+//!
+//! [[hooks]]                     # post-response scripts (hooks.rs)
+//! name = "lint"
+//! command = "C:/scripts/lint.cmd"
+//! ```
+//!
+//! Credentials stay in the environment only: API keys (`CH_EMBEDDINGS_API_KEY`,
+//! `CH_WEB_SEARCH_API_KEY`, `CH_STT_API_KEY`), `CH_BASIC_AUTH` and
+//! `AUTH_SECRET`.
+//!
+//! Named profiles (`[profiles.<name>]`, see `profiles.rs`) can override
+//! `[ollama]` and `[routing]` while they are active.
+//!
+//! The file is loaded at startup and re-read whenever it changes (checked
+//! every `RELOAD_INTERVAL`). Settings take effect immediately — modules
+//! read them through `current()` or a `Derived` cache — except the keys in
+//! `RESTART_REQUIRED`. A file that fails to parse or validate keeps the
+//! previous settings.
+//!
+//! - `GET /api/config` — file path, file values, env overrides and the last
+//!   load error
+//! - `PUT /api/config` — validate and save a new config (same shape as the
+//!   file, as JSON), then apply it. Settings that run programs (`FILE_ONLY`:
+//!   hooks, executable paths, the TTS player) must match the file; they are
+//!   changed by editing it and applied on reload.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+const CONFIG_FILE: &str = "hydra.toml";
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// `(key, env var)` pairs; a set env var overrides the file value.
+const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("server.bind", "CH_BIND_ADDR"),
+    ("server.port", "PORT"),
+    ("queue.max_concurrent", "CH_BACKGROUND_MAX_CONCURRENT"),
+    ("queue.max_attempts", "CH_BACKGROUND_MAX_ATTEMPTS"),
+    ("queue.max_retries", "CH_BACKGROUND_MAX_RETRIES"),
+    ("queue.ollama_batch", "CH_BACKGROUND_OLLAMA_BATCH"),
+    ("queue.cpu_threshold", "CH_IDLE_CPU_THRESHOLD"),
+    ("queue.idle_minutes", "CH_IDLE_MINUTES"),
+    ("queue.file_locks", "CH_BACKGROUND_FILE_LOCKS"),
+    ("queue.lanes", "CH_BACKGROUND_LANES"),
+    ("ollama.url", "CH_OLLAMA_URL"),
+    ("ollama.max_generations", "CH_OLLAMA_MAX_GENERATIONS"),
+    ("ollama.keep_alive", "CH_OLLAMA_KEEP_ALIVE"),
+    ("routing.witcher_local_model", "CH_WITCHER_LOCAL_MODEL"),
+    ("routing.fallback_models", "CH_BACKGROUND_FALLBACK_MODELS"),
+    (
+        "timeouts.background_prompt_secs",
+        "CH_BACKGROUND_TIMEOUT_SECS",
+    ),
+    ("budget.daily", "CH_BUDGET_DAILY"),
+    ("budget.weekly", "CH_BUDGET_WEEKLY"),
+    ("paths.claude", "CH_CLAUDE_CLI_BIN"),
+    ("paths.ollama", "CH_OLLAMA_BIN"),
+    ("paths.nvidia_smi", "CH_NVIDIA_SMI"),
+    ("paths.piper", "CH_PIPER_BIN"),
+    ("paths.edge_tts", "CH_EDGE_TTS_BIN"),
+    ("paths.whisper", "CH_WHISPER_CPP_BIN"),
+    ("embeddings.backend", "CH_EMBEDDINGS_BACKEND"),
+    ("embeddings.url", "CH_EMBEDDINGS_URL"),
+    ("embeddings.model", "CH_EMBEDDINGS_MODEL"),
+    ("rag.dir", "CH_RAG_DIR"),
+    ("rag.top_k", "CH_RAG_TOP_K"),
+    ("rag.min_score", "CH_RAG_MIN_SCORE"),
+    ("rag.reindex_secs", "CH_RAG_REINDEX_SECS"),
+    ("compaction.url", "CH_COMPACTION_URL"),
+    ("compaction.model", "CH_COMPACTION_MODEL"),
+    ("compaction.token_budget", "CH_COMPACTION_TOKEN_BUDGET"),
+    ("compaction.keep_recent", "CH_COMPACTION_KEEP_RECENT"),
+    ("compaction.auto", "CH_COMPACTION_AUTO"),
+    ("web_search.provider", "CH_WEB_SEARCH_PROVIDER"),
+    ("web_search.url", "CH_WEB_SEARCH_URL"),
+    ("web_search.max_results", "CH_WEB_SEARCH_MAX_RESULTS"),
+    ("web_search.auto", "CH_WEB_SEARCH_AUTO"),
+    ("tts.backend", "CH_TTS_BACKEND"),
+    ("tts.voice", "CH_TTS_VOICE"),
+    ("tts.player", "CH_TTS_PLAYER"),
+    ("transcription.backend", "CH_STT_BACKEND"),
+    ("transcription.url", "CH_STT_URL"),
+    ("transcription.model", "CH_STT_MODEL"),
+    ("transcription.whisper_model", "CH_WHISPER_CPP_MODEL"),
+    ("claude_cli.args", "CH_CLAUDE_CLI_ARGS"),
+    ("claude_cli.idle_secs", "CH_CLAUDE_CLI_IDLE_SECS"),
+    (
+        "claude_cli.turn_timeout_secs",
+        "CH_CLAUDE_CLI_TURN_TIMEOUT_SECS",
+    ),
+    ("claude_cli.max_processes", "CH_CLAUDE_CLI_MAX_PROCESSES"),
+    ("session_memory.scope", "CH_SESSION_MEMORY"),
+    ("session_memory.top_k", "CH_SESSION_MEMORY_TOP_K"),
+    ("session_memory.min_score", "CH_SESSION_MEMORY_MIN_SCORE"),
+    ("safety.secrets", "CH_SAFETY_SECRETS"),
+    ("safety.injection", "CH_SAFETY_INJECTION"),
+    ("permissions.mode", "CH_PERMISSION_MODE"),
+    ("markdown_vault.dir", "CH_MARKDOWN_VAULT_DIR"),
+    ("markdown_vault.layout", "CH_MARKDOWN_VAULT_LAYOUT"),
+    ("metrics.snapshot_secs", "CH_METRICS_SNAPSHOT_SECS"),
+    ("metrics.history_secs", "CH_METRICS_HISTORY_SECS"),
+    ("access.auth_loopback", "CH_AUTH_LOOPBACK"),
+    ("access.cors_origins", "CH_CORS_ORIGINS"),
+    ("rules.manifest_url", "CH_RULES_MANIFEST_URL"),
+    ("rules.manifest_pubkey", "CH_RULES_MANIFEST_PUBKEY"),
+    ("rules.check_secs", "CH_RULES_CHECK_SECS"),
+];
+
+/// Keys that only take effect on the next start.
+const RESTART_REQUIRED: &[&str] = &[
+    "server.bind",
+    "server.port",
+    "rag.dir",
+    "rag.reindex_secs",
+    "metrics.snapshot_secs",
+    "metrics.history_secs",
+    "access.cors_origins",
+    "rules.check_secs",
+];
+
+/// Keys that name programs to run; `PUT /api/config` cannot change them.
+const FILE_ONLY: &[&str] = &["hooks", "paths", "tts.player"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HydraConfig {
+    pub server: ServerConfig,
+    pub queue: QueueConfig,
+    pub ollama: OllamaConfig,
+    pub routing: RoutingConfig,
+    pub timeouts: TimeoutConfig,
+    pub budget: BudgetConfig,
+    /// Tool → executable path or name (see `paths.rs`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub paths: BTreeMap<String, String>,
+    /// Named profiles (see `profiles.rs`).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, crate::profiles::Profile>,
+    pub embeddings: EmbeddingsConfig,
+    pub rag: RagConfig,
+    pub compaction: CompactionConfig,
+    pub web_search: WebSearchConfig,
+    pub tts: TtsConfig,
+    pub transcription: TranscriptionConfig,
+    pub claude_cli: ClaudeCliConfig,
+    pub session_memory: SessionMemoryConfig,
+    pub safety: SafetyConfig,
+    pub permissions: PermissionsConfig,
+    pub markdown_vault: MarkdownVaultConfig,
+    pub metrics: MetricsConfig,
+    pub access: AccessConfig,
+    pub rules: RulesConfig,
+    /// Post-response hooks (see `hooks.rs`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<crate::hooks::HookConfig>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ollama_batch: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_threshold: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_minutes: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_locks: Option<bool>,
+    /// Per-provider concurrency limits.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub lanes: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OllamaConfig {
+    /// Server base URL used by the local-model features.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_generations: Option<usize>,
+    /// How long Ollama keeps models loaded (see `ollama.rs`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_alive: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoutingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witcher_local_model: Option<String>,
+    /// Models tried in turn when a background prompt is retried.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_models: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_prompt_secs: Option<u64>,
+}
+
+/// Spending caps in USD per provider (see `budget.rs`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BudgetConfig {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub daily: BTreeMap<String, f64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub weekly: BTreeMap<String, f64>,
+}
+
+/// See `embeddings.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmbeddingsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// See `rag.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RagConfig {
+    /// Project root to index; RAG is disabled without it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reindex_secs: Option<u64>,
+}
+
+/// See `compaction.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompactionConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_budget: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_recent: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto: Option<bool>,
+}
+
+/// See `web_search.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSearchConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_results: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto: Option<bool>,
+}
+
+/// See `tts.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TtsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voice: Option<String>,
+    /// Player command line; the audio file path is appended.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub player: Option<String>,
+}
+
+/// See `transcription.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscriptionConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// ggml model file for `whisper_cpp`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub whisper_model: Option<String>,
+}
+
+/// See `claude_cli.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClaudeCliConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turn_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_processes: Option<usize>,
+}
+
+/// See `session_memory.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SessionMemoryConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_score: Option<f32>,
+}
+
+/// See `safety.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SafetyConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub injection: Option<String>,
+}
+
+/// See `permissions.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PermissionsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+}
+
+/// See `markdown_vault.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MarkdownVaultConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+}
+
+/// See `metrics_snapshot.rs` and `metrics_history.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snapshot_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_secs: Option<u64>,
+}
+
+/// See `access.rs`; bind address and port are in `[server]`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AccessConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_loopback: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cors_origins: Option<Vec<String>>,
+}
+
+/// See `rule_updates.rs`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RulesConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_pubkey: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub check_secs: Option<u64>,
+}
+
+fn check<T: PartialOrd + Copy + std::fmt::Display>(
+    errors: &mut Vec<String>,
+    key: &str,
+    value: Option<T>,
+    min: T,
+    max: T,
+) {
+    if let Some(v) = value
+        && !(min..=max).contains(&v)
+    {
+        errors.push(format!(
+            "{} must be between {} and {}, got {}",
+            key, min, max, v
+        ));
+    }
+}
+
+fn check_url(errors: &mut Vec<String>, key: &str, value: Option<&str>) {
+    if let Some(url) = value
+        && !(url.starts_with("http://") || url.starts_with("https://"))
+    {
+        errors.push(format!("{} must be an http(s) URL, got '{}'", key, url));
+    }
+}
+
+/// Reports `value` when `parse` rejects it.
+fn check_with<T>(
+    errors: &mut Vec<String>,
+    key: &str,
+    value: Option<&str>,
+    parse: impl Fn(&str) -> Option<T>,
+) {
+    if let Some(raw) = value
+        && parse(raw).is_none()
+    {
+        errors.push(format!("{}: unknown value '{}'", key, raw));
+    }
+}
+
+impl HydraConfig {
+    /// `FILE_ONLY` keys whose value differs from `current`.
+    fn file_only_changes(&self, current: &HydraConfig) -> Vec<&'static str> {
+        let changed = [
+            self.hooks != current.hooks,
+            self.paths != current.paths,
+            self.tts.player != current.tts.player,
+        ];
+        FILE_ONLY
+            .iter()
+            .zip(changed)
+            .filter(|(_, changed)| *changed)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// All problems with the config, empty when it is valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if let Some(bind) = &self.server.bind
+            && let Err(e) = crate::access::parse_bind(
+                Some(bind),
+                Some(&self.server.port.unwrap_or(0).to_string()),
+            )
+        {
+            errors.push(format!("server.bind: {}", e));
+        }
+        let q = &self.queue;
+        check(&mut errors, "queue.max_concurrent", q.max_concurrent, 1, 16);
+        check(&mut errors, "queue.max_attempts", q.max_attempts, 1, 100);
+        check(&mut errors, "queue.max_retries", q.max_retries, 0, 10);
+        check(&mut errors, "queue.ollama_batch", q.ollama_batch, 0, 32);
+        check(
+            &mut errors,
+            "queue.cpu_threshold",
+            q.cpu_threshold,
+            1.0,
+            100.0,
+        );
+        check(
+            &mut errors,
+            "queue.idle_minutes",
+            q.idle_minutes,
+            0.0,
+            1440.0,
+        );
+        for (lane, limit) in &q.lanes {
+            if lane.trim().is_empty() || *limit == 0 {
+                errors.push(format!("queue.lanes.{}: limit must be at least 1", lane));
+            }
+        }
+        self.ollama.validate("ollama", &mut errors);
+        self.routing.validate("routing", &mut errors);
+        check(
+            &mut errors,
+            "timeouts.background_prompt_secs",
+            self.timeouts.background_prompt_secs,
+            1,
+            3600,
+        );
+        for (period, caps) in [
+            ("daily", &self.budget.daily),
+            ("weekly", &self.budget.weekly),
+        ] {
+            for (provider, cap) in caps {
+                if !crate::budget::PROVIDERS.contains(&provider.trim().to_lowercase().as_str()) {
+                    errors.push(format!("budget.{}.{}: unknown provider", period, provider));
+                } else if !(cap.is_finite() && *cap > 0.0) {
+                    errors.push(format!("budget.{}.{} must be above 0", period, provider));
+                }
+            }
+        }
+        for (tool, value) in &self.paths {
+            if !crate::paths::TOOLS.iter().any(|t| t.key == tool) {
+                errors.push(format!("paths.{}: unknown tool", tool));
+            } else if value.trim().is_empty() {
+                errors.push(format!("paths.{} must not be empty", tool));
+            }
+        }
+        for (name, profile) in &self.profiles {
+            profile.validate(name, &mut errors);
+        }
+        self.validate_features(&mut errors);
+        errors
+    }
+
+    /// The optional feature sections.
+    fn validate_features(&self, errors: &mut Vec<String>) {
+        let e = &self.embeddings;
+        check_with(errors, "embeddings.backend", e.backend.as_deref(), |b| {
+            matches!(b, "ollama" | "openai").then_some(())
+        });
+        check_url(errors, "embeddings.url", e.url.as_deref());
+        let r = &self.rag;
+        check(errors, "rag.top_k", r.top_k, 0, 20);
+        check(errors, "rag.min_score", r.min_score, 0.0, 1.0);
+        check(errors, "rag.reindex_secs", r.reindex_secs, 30, 86_400);
+        let c = &self.compaction;
+        check_url(errors, "compaction.url", c.url.as_deref());
+        check(
+            errors,
+            "compaction.token_budget",
+            c.token_budget,
+            2_000,
+            1_000_000,
+        );
+        check(errors, "compaction.keep_recent", c.keep_recent, 2, 200);
+        let w = &self.web_search;
+        check_with(
+            errors,
+            "web_search.provider",
+            w.provider.as_deref(),
+            crate::web_search::parse_provider,
+        );
+        check_url(errors, "web_search.url", w.url.as_deref());
+        check(errors, "web_search.max_results", w.max_results, 1, 10);
+        check_with(
+            errors,
+            "tts.backend",
+            self.tts.backend.as_deref(),
+            crate::tts::parse_backend,
+        );
+        let t = &self.transcription;
+        check_with(
+            errors,
+            "transcription.backend",
+            t.backend.as_deref(),
+            crate::transcription::parse_backend,
+        );
+        check_url(errors, "transcription.url", t.url.as_deref());
+        let cli = &self.claude_cli;
+        check(errors, "claude_cli.idle_secs", cli.idle_secs, 0, 7 * 86_400);
+        check(
+            errors,
+            "claude_cli.turn_timeout_secs",
+            cli.turn_timeout_secs,
+            10,
+            86_400,
+        );
+        check(errors, "claude_cli.max_processes", cli.max_processes, 0, 64);
+        let m = &self.session_memory;
+        check_with(
+            errors,
+            "session_memory.scope",
+            m.scope.as_deref(),
+            crate::session_memory::parse_scope,
+        );
+        check(errors, "session_memory.top_k", m.top_k, 0, 10);
+        check(errors, "session_memory.min_score", m.min_score, 0.0, 1.0);
+        check_with(
+            errors,
+            "safety.secrets",
+            self.safety.secrets.as_deref(),
+            crate::safety::Policy::parse,
+        );
+        check_with(
+            errors,
+            "safety.injection",
+            self.safety.injection.as_deref(),
+            |raw| {
+                use crate::safety::Policy;
+                Policy::parse(raw).filter(|p| *p != Policy::Redact)
+            },
+        );
+        check_with(
+            errors,
+            "permissions.mode",
+            self.permissions.mode.as_deref(),
+            crate::permissions::PermissionMode::parse,
+        );
+        check_with(
+            errors,
+            "markdown_vault.layout",
+            self.markdown_vault.layout.as_deref(),
+            crate::markdown_vault::parse_layout,
+        );
+        check(
+            errors,
+            "metrics.snapshot_secs",
+            self.metrics.snapshot_secs,
+            0,
+            86_400,
+        );
+        check(
+            errors,
+            "metrics.history_secs",
+            self.metrics.history_secs,
+            0,
+            86_400,
+        );
+        if self
+            .access
+            .cors_origins
+            .as_ref()
+            .is_some_and(|origins| origins.iter().any(|o| o.trim().is_empty()))
+        {
+            errors.push("access.cors_origins must not contain empty origins".to_string());
+        }
+        let rules = &self.rules;
+        check_url(errors, "rules.manifest_url", rules.manifest_url.as_deref());
+        if let Some(key) = &rules.manifest_pubkey
+            && let Err(e) = crate::rule_updates::parse_public_key(key)
+        {
+            errors.push(format!("rules.manifest_pubkey: {}", e));
+        }
+        check(errors, "rules.check_secs", rules.check_secs, 0, 30 * 86_400);
+        for (i, hook) in self.hooks.iter().enumerate() {
+            hook.validate(&format!("hooks[{}]", i), errors);
+        }
+    }
+}
+
+impl OllamaConfig {
+    pub(crate) fn validate(&self, prefix: &str, errors: &mut Vec<String>) {
+        check_url(errors, &format!("{}.url", prefix), self.url.as_deref());
+        check(
+            errors,
+            &format!("{}.max_generations", prefix),
+            self.max_generations,
+            1,
+            64,
+        );
+        check_with(
+            errors,
+            &format!("{}.keep_alive", prefix),
+            self.keep_alive.as_deref(),
+            crate::ollama::parse_keep_alive,
+        );
+    }
+
+    /// `other`'s values where it sets them.
+    fn overlay(&mut self, other: &OllamaConfig) {
+        self.url = other.url.clone().or(self.url.take());
+        self.max_generations = other.max_generations.or(self.max_generations);
+        self.keep_alive = other.keep_alive.clone().or(self.keep_alive.take());
+    }
+}
+
+impl RoutingConfig {
+    pub(crate) fn validate(&self, prefix: &str, errors: &mut Vec<String>) {
+        if self
+            .witcher_local_model
+            .as_deref()
+            .is_some_and(|m| m.trim().is_empty())
+        {
+            errors.push(format!("{}.witcher_local_model must not be empty", prefix));
+        }
+        if self
+            .fallback_models
+            .as_ref()
+            .is_some_and(|models| models.iter().any(|m| m.trim().is_empty()))
+        {
+            errors.push(format!(
+                "{}.fallback_models must not contain empty names",
+                prefix
+            ));
+        }
+    }
+
+    /// `other`'s values where it sets them.
+    fn overlay(&mut self, other: &RoutingConfig) {
+        self.witcher_local_model = other
+            .witcher_local_model
+            .clone()
+            .or(self.witcher_local_model.take());
+        self.fallback_models = other
+            .fallback_models
+            .clone()
+            .or(self.fallback_models.take());
+    }
+}
+
+/// `file` with the active profile's overrides applied.
+fn effective(file: &HydraConfig) -> HydraConfig {
+    let mut config = file.clone();
+    if let Some(profile) = crate::profiles::active(file) {
+        config.ollama.overlay(&profile.ollama);
+        config.routing.overlay(&profile.routing);
+    }
+    config
+}
+
+/// Parse and validate a config file's content.
+pub fn parse(text: &str) -> Result<HydraConfig, String> {
+    let config: HydraConfig = toml::from_str(text).map_err(|e| e.to_string())?;
+    match config.validate() {
+        errors if errors.is_empty() => Ok(config),
+        errors => Err(errors.join("; ")),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Loaded {
+    /// As in the file.
+    config: Arc<HydraConfig>,
+    /// With the active profile applied.
+    effective: Arc<HydraConfig>,
+    /// Modification time of the file the config was loaded from.
+    modified: Option<SystemTime>,
+    error: Option<String>,
+}
+
+pub fn path() -> PathBuf {
+    std::env::var("CH_CONFIG_FILE")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| crate::metrics_snapshot::data_dir().join(CONFIG_FILE))
+}
+
+fn read(path: &Path) -> (Option<SystemTime>, Result<HydraConfig, String>) {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let parsed = match modified {
+        None => Ok(HydraConfig::default()),
+        Some(_) => std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse(&text)),
+    };
+    (modified, parsed)
+}
+
+static LOADED: LazyLock<RwLock<Loaded>> = LazyLock::new(|| {
+    let path = path();
+    let (modified, parsed) = read(&path);
+    let (config, error) = match parsed {
+        Ok(config) => (config, None),
+        Err(e) => {
+            tracing::error!("hydra_config: {}: {} — using defaults", path.display(), e);
+            (HydraConfig::default(), Some(e))
+        }
+    };
+    RwLock::new(Loaded {
+        effective: Arc::new(effective(&config)),
+        config: Arc::new(config),
+        modified,
+        error,
+    })
+});
+
+/// Current settings: the config file (defaults when there is none) with the
+/// active profile applied.
+pub fn current() -> Arc<HydraConfig> {
+    LOADED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .effective
+        .clone()
+}
+
+/// The config file's values, without the active profile.
+pub fn file() -> Arc<HydraConfig> {
+    LOADED
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .config
+        .clone()
+}
+
+/// A module's settings built from `current()`; rebuilt whenever the
+/// config changes (file edit, save, profile switch).
+pub struct Derived<T> {
+    build: fn(&HydraConfig) -> T,
+    cache: RwLock<Option<(Arc<HydraConfig>, T)>>,
+}
+
+impl<T: Clone> Derived<T> {
+    pub const fn new(build: fn(&HydraConfig) -> T) -> Self {
+        Self {
+            build,
+            cache: RwLock::new(None),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        let config = current();
+        if let Some((built_from, value)) = &*self.cache.read().unwrap_or_else(|e| e.into_inner())
+            && Arc::ptr_eq(built_from, &config)
+        {
+            return value.clone();
+        }
+        let value = (self.build)(&config);
+        *self.cache.write().unwrap_or_else(|e| e.into_inner()) = Some((config, value.clone()));
+        value
+    }
+}
+
+/// Env var `name`, trimmed; `None` when unset or empty.
+pub fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn env_set(name: &str) -> bool {
+    env(name).is_some()
+}
+
+/// Env var `name`, else the file's `value`.
+pub fn text(name: &str, value: Option<&str>) -> Option<String> {
+    env(name).or_else(|| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// Env var `name` when it parses, else the file's `value`.
+pub fn number<T: FromStr>(name: &str, value: Option<T>) -> Option<T> {
+    env(name).and_then(|v| v.parse().ok()).or(value)
+}
+
+/// Env var `name` (`1`, `true`, `yes`, `on` = true), else the file's `value`.
+pub fn flag(name: &str, value: Option<bool>) -> Option<bool> {
+    env(name)
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .or(value)
+}
+
+/// Ollama base URL from `CH_OLLAMA_URL` or the file, if set.
+pub fn ollama_url() -> Option<String> {
+    text("CH_OLLAMA_URL", current().ollama.url.as_deref())
+        .map(|url| url.trim_end_matches('/').to_string())
+}
+
+/// Push `config` to the modules that read it.
+fn apply(previous: &HydraConfig, config: &HydraConfig) {
+    crate::queue::reload_config();
+    crate::queue::wake();
+    crate::ollama_queue::apply_config();
+    crate::paths::clear_cache();
+    if previous.server != config.server {
+        tracing::warn!("hydra_config: [server] changes take effect after a restart");
+    }
+}
+
+/// Install `config` loaded from a file with `modified`; returns whether the
+/// settings changed.
+fn install(config: HydraConfig, modified: Option<SystemTime>) -> bool {
+    let next = effective(&config);
+    let previous = {
+        let mut loaded = LOADED.write().unwrap_or_else(|e| e.into_inner());
+        loaded.modified = modified;
+        loaded.error = None;
+        loaded.config = Arc::new(config);
+        std::mem::replace(&mut loaded.effective, Arc::new(next.clone()))
+    };
+    let changed = *previous != next;
+    if changed {
+        apply(&previous, &next);
+        crate::app_log::record(
+            crate::app_log::Level::Info,
+            "config",
+            "config-applied",
+            json!({ "path": path().display().to_string() }),
+        );
+    }
+    changed
+}
+
+/// Re-apply the file after the active profile changed.
+pub(crate) fn refresh() {
+    let previous = {
+        let mut loaded = LOADED.write().unwrap_or_else(|e| e.into_inner());
+        let next = Arc::new(effective(&loaded.config));
+        std::mem::replace(&mut loaded.effective, next)
+    };
+    apply(&previous, &current());
+}
+
+/// Re-read the file when it changed since the last load.
+async fn reload() {
+    let path = path();
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok());
+    if LOADED.read().unwrap_or_else(|e| e.into_inner()).modified == modified {
+        return;
+    }
+    let read_path = path.clone();
+    let (modified, parsed) = tokio::task::spawn_blocking(move || read(&read_path))
+        .await
+        .unwrap_or_else(|e| (modified, Err(e.to_string())));
+    match parsed {
+        Ok(config) => {
+            if install(config, modified) {
+                tracing::info!("hydra_config: applied {}", path.display());
+            }
+        }
+        Err(e) => {
+            tracing::warn!(
+                "hydra_config: {}: {} — keeping previous settings",
+                path.display(),
+                e
+            );
+            let mut loaded = LOADED.write().unwrap_or_else(|e| e.into_inner());
+            loaded.modified = modified;
+            loaded.error = Some(e);
+        }
+    }
+}
+
+/// Keep the config in sync with the file.
+pub fn spawn() {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(RELOAD_INTERVAL);
+        loop {
+            ticker.tick().await;
+            reload().await;
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn snapshot() -> Value {
+    let loaded = LOADED.read().unwrap_or_else(|e| e.into_inner());
+    let overrides: Vec<Value> = ENV_OVERRIDES
+        .iter()
+        .filter(|(_, var)| env_set(var))
+        .map(|(key, var)| json!({ "key": key, "env": var }))
+        .collect();
+    json!({
+        "path": path().display().to_string(),
+        "exists": loaded.modified.is_some(),
+        "config": *loaded.config,
+        "active_profile": crate::profiles::active_name(),
+        "env_overrides": overrides,
+        "restart_required": RESTART_REQUIRED,
+        "file_only": FILE_ONLY,
+        "error": loaded.error,
+    })
+}
+
+/// `GET /api/config`
+pub async fn get_config() -> Json<Value> {
+    Json(snapshot())
+}
+
+/// `PUT /api/config`
+pub async fn save_hydra_config(
+    Json(config): Json<HydraConfig>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let errors = config.validate();
+    if !errors.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid config", "details": errors })),
+        ));
+    }
+    let file_only = config.file_only_changes(&file());
+    if !file_only.is_empty() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(json!({
+                "error": "These settings can only be changed in the config file",
+                "details": file_only,
+            })),
+        ));
+    }
+    let internal = |e: String| {
+        tracing::error!("hydra_config: cannot save: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to save config" })),
+        )
+    };
+    let text = toml::to_string_pretty(&config).map_err(|e| internal(e.to_string()))?;
+    let path = path();
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .map_err(|e| internal(e.to_string()))?;
+    }
+    // Write beside the file and rename, so the watcher never sees half a file.
+    let tmp = path.with_extension("toml.tmp");
+    tokio::fs::write(
+        &tmp,
+        format!("# ClaudeHydra config (see /api/config)\n\n{}", text),
+    )
+    .await
+    .map_err(|e| internal(e.to_string()))?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .map_err(|e| internal(e.to_string()))?;
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok());
+    install(config, modified);
+    tracing::info!("hydra_config: saved {}", path.display());
+    Ok(Json(snapshot()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_config_parses() {
+        let config = parse(
+            r#"
+            [server]
+            bind = "0.0.0.0"
+            port = 9000
+
+            [queue]
+            max_concurrent = 4
+            cpu_threshold = 50
+            lanes = { anthropic = 2, ollama = 1 }
+
+            [ollama]
+            url = "http://gpu-box:11434"
+
+            [routing]
+            fallback_models = ["claude-sonnet-4-6"]
+
+            [timeouts]
+            background_prompt_secs = 600
+
+            [budget]
+            daily = { anthropic = 5.0 }
+
+            [rag]
+            dir = "/home/me/hydra"
+            top_k = 6
+
+            [claude_cli]
+            args = ["--add-dir", "/work"]
+
+            [safety]
+            secrets = "redact"
+
+            [[hooks]]
+            name = "lint"
+            command = "lint.cmd"
+            events = ["completed", "failed"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.server.port, Some(9000));
+        assert_eq!(config.queue.max_concurrent, Some(4));
+        assert_eq!(config.queue.lanes.get("ollama"), Some(&1));
+        assert_eq!(config.queue.max_retries, None);
+        assert_eq!(config.timeouts.background_prompt_secs, Some(600));
+        assert_eq!(config.budget.daily.get("anthropic"), Some(&5.0));
+        assert_eq!(config.rag.top_k, Some(6));
+        assert_eq!(config.claude_cli.args.as_ref().map(Vec::len), Some(2));
+        assert_eq!(config.safety.secrets.as_deref(), Some("redact"));
+        assert_eq!(config.hooks.len(), 1);
+        assert_eq!(config.hooks[0].events.len(), 2);
+        assert_eq!(parse("").unwrap(), HydraConfig::default());
+    }
+
+    #[test]
+    fn invalid_values_and_unknown_keys_are_rejected() {
+        let err = parse("[queue]\nmax_concurrent = 0\ncpu_threshold = 150\n").unwrap_err();
+        assert!(err.contains("queue.max_concurrent"), "{}", err);
+        assert!(err.contains("queue.cpu_threshold"), "{}", err);
+        assert!(parse("[queue]\nmax_concurent = 2\n").is_err());
+        assert!(parse("[ollama]\nurl = \"gpu-box:11434\"\n").is_err());
+        assert!(parse("[server]\nbind = \"not an ip\"\n").is_err());
+        assert!(parse("[queue.lanes]\nollama = 0\n").is_err());
+        assert!(parse("[budget.daily]\nollama = 1.0\n").is_err());
+        assert!(parse("[budget.weekly]\nanthropic = -2.0\n").is_err());
+        assert!(parse("[ollama]\nkeep_alive = \"soon\"\n").is_err());
+        assert!(parse("[web_search]\nprovider = \"bing\"\n").is_err());
+        assert!(parse("[safety]\ninjection = \"redact\"\n").is_err());
+        assert!(parse("[permissions]\nmode = \"root\"\n").is_err());
+        assert!(parse("[rules]\nmanifest_pubkey = \"abc\"\n").is_err());
+        assert!(parse("[rag]\nmin_score = 2.0\n").is_err());
+        assert!(parse("[[hooks]]\nname = \"lint\"\ncommand = \"\"\n").is_err());
+        assert!(
+            parse("[[hooks]]\nname = \"lint\"\ncommand = \"x\"\nevent = \"failed\"\n").is_err()
+        );
+    }
+
+    #[test]
+    fn saved_config_round_trips() {
+        let mut config = HydraConfig::default();
+        config.queue.max_concurrent = Some(3);
+        config.routing.witcher_local_model = Some("auto".to_string());
+        config.hooks.push(crate::hooks::HookConfig {
+            name: "lint".to_string(),
+            command: "lint.cmd".to_string(),
+            args: vec!["--fix".to_string()],
+            events: vec![crate::hooks::HookEvent::Completed],
+            model_prefix: None,
+            timeout_secs: Some(30),
+            enabled: true,
+        });
+        let text = toml::to_string_pretty(&config).unwrap();
+        assert_eq!(parse(&text).unwrap(), config);
+    }
+
+    #[test]
+    fn programs_to_run_are_file_only() {
+        let mut current = HydraConfig::default();
+        current
+            .paths
+            .insert("ollama".to_string(), "/usr/bin/ollama".to_string());
+
+        let mut config = current.clone();
+        config.queue.max_concurrent = Some(3);
+        assert!(config.file_only_changes(&current).is_empty());
+
+        config
+            .paths
+            .insert("ollama".to_string(), "/tmp/x".to_string());
+        config.tts.player = Some("sh -c".to_string());
+        assert_eq!(
+            config.file_only_changes(&current),
+            vec!["paths", "tts.player"]
+        );
+
+        let mut config = current.clone();
+        config.hooks.push(crate::hooks::HookConfig {
+            name: "lint".to_string(),
+            command: "lint.cmd".to_string(),
+            args: Vec::new(),
+            events: vec![crate::hooks::HookEvent::Completed],
+            model_prefix: None,
+            timeout_secs: None,
+            enabled: true,
+        });
+        assert_eq!(config.file_only_changes(&current), vec!["hooks"]);
+    }
+}
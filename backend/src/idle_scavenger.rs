@@ -0,0 +1,143 @@
+//! Idle scavenger — decides when the background prompt queue (see `queue/`)
+//! makes a pass, so that background work never competes with the user's
+//! active session.
+//!
+//! The worker runs inside the backend — no client polling is involved. It
+//! wakes every 30 seconds, on enqueue and whenever a prompt finishes, and
+//! hands the queue its free slots together with the current idle state:
+//! `low` prompts only run while the machine is idle, i.e. system CPU below
+//! `CH_IDLE_CPU_THRESHOLD` percent (default 30) and no interactive prompt for
+//! `CH_IDLE_MINUTES` (default 10). Interactive entry points call
+//! `mark_interactive()`.
+//!
+//! On startup, prompts left `running` by a previous process are recovered
+//! first (see `queue::recover_interrupted`).
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::queue::{self, QueueConfig, config};
+use crate::state::AppState;
+
+pub const JOB_IDLE_SCAVENGER: &str = "idle_scavenger";
+pub const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Unix millis of the last interactive prompt (0 = none since start).
+static LAST_INTERACTIVE_MS: AtomicI64 = AtomicI64::new(0);
+
+/// Record user activity — call from every interactive prompt entry point.
+pub fn mark_interactive() {
+    LAST_INTERACTIVE_MS.store(Utc::now().timestamp_millis(), Ordering::Relaxed);
+}
+
+fn last_interactive() -> Option<DateTime<Utc>> {
+    match LAST_INTERACTIVE_MS.load(Ordering::Relaxed) {
+        0 => None,
+        ms => DateTime::from_timestamp_millis(ms),
+    }
+}
+
+/// Idle decision with the reason it was reached, for the status endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IdleStatus {
+    pub idle: bool,
+    pub cpu_usage_percent: f32,
+    pub cpu_threshold: f32,
+    pub last_interactive: Option<String>,
+    pub idle_after_secs: u64,
+}
+
+fn evaluate_idle(
+    cfg: &QueueConfig,
+    cpu: f32,
+    last_interactive: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> IdleStatus {
+    let quiet = last_interactive.is_none_or(|t| {
+        (now - t).to_std().unwrap_or_default() >= cfg.idle_after
+    });
+    IdleStatus {
+        idle: quiet && cpu < cfg.cpu_threshold,
+        cpu_usage_percent: cpu,
+        cpu_threshold: cfg.cpu_threshold,
+        last_interactive: last_interactive.map(|t| t.to_rfc3339()),
+        idle_after_secs: cfg.idle_after.as_secs(),
+    }
+}
+
+pub async fn idle_status(state: &AppState) -> IdleStatus {
+    let cpu = state.system_monitor.read().await.cpu_usage_percent;
+    evaluate_idle(&config(), cpu, last_interactive(), Utc::now())
+}
+
+/// Spawn the queue worker: hands eligible prompts to free slots (up to
+/// `CH_BACKGROUND_MAX_CONCURRENT`) on every tick, on enqueue and whenever a
+/// running prompt finishes.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        // Prompts left `running` by a previous process will never finish.
+        match queue::recover_interrupted(&state.db).await {
+            Ok((0, 0)) => {}
+            Ok((requeued, failed)) => tracing::info!(
+                "idle_scavenger: recovered interrupted prompts ({} requeued, {} failed)",
+                requeued,
+                failed
+            ),
+            Err(e) => tracing::warn!("idle_scavenger: recovery failed: {}", e),
+        }
+
+        let mut dispatcher = queue::Dispatcher::new();
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => state.job_schedule.record_run(JOB_IDLE_SCAVENGER),
+                _ = queue::woken() => {}
+            }
+            let idle = idle_status(&state).await.idle;
+            dispatcher.run(&state, idle).await;
+            crate::queue_stats::publish_etas(&state, idle).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn cfg() -> QueueConfig {
+        QueueConfig {
+            cpu_threshold: 30.0,
+            idle_after: Duration::from_secs(600),
+            max_concurrent: 2,
+            max_attempts: 3,
+            lanes: HashMap::new(),
+            ollama_batch: 4,
+            max_retries: 1,
+            fallback_models: Vec::new(),
+            file_locks: false,
+            execution_timeout: Duration::from_secs(300),
+        }
+    }
+
+    #[test]
+    fn idle_requires_low_cpu_and_quiet_period() {
+        let now = Utc::now();
+        let recent = Some(now - chrono::Duration::minutes(2));
+        let old = Some(now - chrono::Duration::minutes(20));
+        assert!(evaluate_idle(&cfg(), 10.0, None, now).idle);
+        assert!(evaluate_idle(&cfg(), 10.0, old, now).idle);
+        assert!(!evaluate_idle(&cfg(), 10.0, recent, now).idle);
+        assert!(!evaluate_idle(&cfg(), 45.0, old, now).idle);
+    }
+
+    #[test]
+    fn mark_interactive_updates_timestamp() {
+        mark_interactive();
+        let t = last_interactive().unwrap();
+        assert!((Utc::now() - t).num_seconds() < 5);
+    }
+}
@@ -1,26 +1,82 @@
+pub mod access;
+pub mod affected_files;
 pub mod ai_gateway;
+pub(crate) mod api_error;
+pub mod app_log;
+pub mod approvals;
 pub mod audit;
+pub mod audit_trail;
 pub mod auth;
 pub mod auto_qa;
+pub mod autostart;
 pub mod browser_proxy;
+pub mod budget;
+pub mod claude_cli;
 pub mod collab;
+pub mod compaction;
+pub mod correlation;
+pub mod costs;
+pub mod embeddings;
+pub mod file_watcher;
+pub mod gc;
+pub mod gpu;
 pub mod handlers;
+pub mod hooks;
+pub mod hydra_config;
+pub mod idle_scavenger;
+pub mod live_updates;
+pub mod markdown_vault;
 pub mod mcp;
 pub mod memory_pruning;
+pub mod metrics_history;
+pub mod metrics_snapshot;
 pub mod model_registry;
 pub mod models;
 pub mod ocr;
+pub mod ollama;
+pub mod ollama_models;
+pub mod ollama_queue;
+pub mod ollama_service;
+pub mod overview;
+pub mod paths;
+pub mod permissions;
+pub mod presets;
+pub mod processes;
+pub mod profiles;
+pub mod projects;
+pub mod prompt_trace;
+pub mod queue;
+pub mod queue_stats;
+pub mod rag;
 pub mod rate_limits;
+pub mod redaction;
+pub mod rule_updates;
+pub mod run_history;
+pub mod safety;
 pub mod sandbox;
+pub mod schedule;
+pub mod scheduled_prompts;
+pub mod secrets;
 pub mod semantic_cache;
+pub mod session_memory;
+pub mod shutdown;
+pub mod startup;
 pub mod state;
+pub mod stream_event;
 pub mod swarm;
 pub mod system_monitor;
+pub mod system_telemetry;
+pub mod tab_quota;
 pub mod tools;
+pub mod transcription;
+pub mod tts;
 pub mod watchdog;
+pub mod web_search;
+pub mod witcher_router;
 
 use axum::Router;
-use axum::routing::{delete, get, patch, post};
+use axum::extract::DefaultBodyLimit;
+use axum::routing::{delete, get, patch, post, put};
 use jaskier_core::router_builder::{HydraRouterConfig, build_hydra_router, build_hydra_test_router};
 use utoipa::OpenApi;
 
@@ -42,6 +98,7 @@ use state::AppState;
         handlers::readiness,
         handlers::auth_mode,
         handlers::system_stats,
+        handlers::startup_report,
         handlers::system_metrics,
         handlers::system_audit,
         // Agents
@@ -63,11 +120,18 @@ use state::AppState;
         // Sessions (local overrides with utoipa annotations)
         handlers::get_session,
         handlers::add_session_message,
+        handlers::fork_session,
+        handlers::duplicate_session,
+        handlers::create_session_with_workspace,
+        handlers::set_session_workspace,
+        handlers::list_unread_sessions,
+        handlers::mark_session_read,
         // Tags & search
         handlers::get_session_tags,
         handlers::add_session_tags,
         handlers::delete_session_tag,
         handlers::search_sessions,
+        handlers::search_conversations,
         handlers::list_all_tags,
         // Model registry
         model_registry::list_models,
@@ -81,9 +145,14 @@ use state::AppState;
         models::HealthResponse,
         models::ProviderInfo,
         models::SystemStats,
+        models::InternalQueueStats,
+        models::StartupReport,
         models::SystemMetricsResponse,
         models::MetricItem,
         models::NetworkMetric,
+        system_telemetry::DiskUsage,
+        system_telemetry::NetworkThroughput,
+        system_telemetry::LoadAverage,
         // Agents
         models::WitcherAgent,
         models::CreateAgentRequest,
@@ -91,6 +160,8 @@ use state::AppState;
         // Chat
         models::ChatRequest,
         models::ChatMessage,
+        ai_gateway::attachments::Attachment,
+        web_search::WebSource,
         models::ChatResponse,
         models::UsageInfo,
         models::ClaudeModelInfo,
@@ -103,6 +174,9 @@ use state::AppState;
         models::HistoryEntry,
         models::ToolInteractionInfo,
         models::CreateSessionRequest,
+        models::ForkSessionRequest,
+        models::DuplicateSessionRequest,
+        models::CreateSessionWithWorkspaceRequest,
         models::UpdateSessionRequest,
         models::AddMessageRequest,
         // Model registry
@@ -114,6 +188,8 @@ use state::AppState;
         // Tags
         handlers::tags::AddTagsRequest,
         handlers::tags::SearchResult,
+        handlers::tags::ConversationHit,
+        handlers::tags::ContextMessage,
     )),
     tags(
         (name = "health", description = "Health & readiness endpoints"),
@@ -149,9 +225,11 @@ fn ch_primary_auth_routes() -> Router<AppState> {
         .route("/api/auth/logout", post(jaskier_oauth::anthropic::anthropic_auth_logout::<AppState>))
 }
 
-/// CH WebSocket chat route (maps to `ws_route` config slot).
+/// CH WebSocket routes (map to `ws_route` config slot): chat + dashboard live updates.
 fn ch_ws_route() -> Router<AppState> {
-    Router::new().route("/ws/chat", get(handlers::ws_chat))
+    Router::new()
+        .route("/ws/chat", get(handlers::ws_chat))
+        .route("/ws", get(live_updates::live_updates))
 }
 
 /// CH streaming + non-streaming chat routes (maps to `execute_routes` config slot).
@@ -206,9 +284,17 @@ fn ch_files_router(state: AppState) -> Router<AppState> {
 /// `/api/auth/mode` are provided by `build_hydra_router` via `HasHealthState`
 /// handlers, so they are NOT registered here to avoid duplicate-route panics.
 fn ch_system_router(state: AppState) -> Router<AppState> {
+    // Public warm-start report (complements shared /api/health/ready)
+    let public = Router::new().route("/api/health/startup", get(handlers::startup_report));
+
     // Protected system endpoints (require auth)
     let protected = Router::new()
         .route("/api/system/stats", get(handlers::system_stats))
+        .route("/api/system/gpu", get(gpu::gpu_info))
+        .route(
+            "/api/system/autostart",
+            get(autostart::get_autostart).put(autostart::put_autostart),
+        )
         .route("/api/admin/rotate-key", post(handlers::rotate_key))
         .route(
             "/api/admin/rate-limits",
@@ -232,7 +318,7 @@ fn ch_system_router(state: AppState) -> Router<AppState> {
             auth::require_api_key_auth,
         ));
 
-    protected.merge(api_key_auth)
+    public.merge(protected).merge(api_key_auth)
 }
 
 /// CH browser proxy routes (public, no auth).
@@ -284,18 +370,42 @@ fn ch_ocr_routes() -> Router<AppState> {
 /// - `/api/sessions*`      — shared `session_routes::<S>()` (list, CRUD, messages,
 ///   working-directory, generate-title, prompt-history)
 /// - `/mcp`                — shared MCP server endpoint
-/// - `/api/mcp/*`          — shared MCP config endpoints
+/// - `/api/mcp/*`          — shared MCP config endpoints (except the CH
+///   `/api/mcp/registry*`, `/api/mcp/health`, `/api/mcp/processes*` and
+///   `/api/mcp/call` routes registered here)
 ///
 /// CH-specific session extensions that ARE safe to add here (not in `session_routes`):
 /// - `/api/sessions/search`         — CH full-text search (not in shared session_routes)
 /// - `/api/sessions/{id}/tags*`     — CH session tagging (not in shared session_routes)
+/// - `/api/sessions/{id}/export`    — CH conversation export (Markdown / JSON / Claude CLI)
+/// - `/api/sessions/import`         — CH conversation import
+/// - `/api/sessions/{id}/fork`      — CH conversation branching
+/// - `/api/sessions/{id}/duplicate` — CH copy onto another provider / model
+/// - `/api/sessions/unread`, `/api/sessions/{id}/read` — CH unread tracking
+/// - `/api/sessions/{id}/permission-mode` — CH per-session permission profile
+/// - `/api/sessions/{id}/witcher-mode` — CH per-session Witcher routing toggle
+/// - `/api/sessions/{id}/quota*`    — CH per-session token quota (pause / raise)
+/// - `/api/sessions/with-workspace`, `/api/sessions/{id}/workspace`
+///                                  — CH validated per-session working directory
+/// - `/api/sessions/{id}/compact*`  — CH context compaction (pinned summary)
 /// - `/api/tags`                    — CH global tag listing
+/// - `/api/session-groups*`, `/api/sessions/{id}/group` — CH session groups
+/// - `/api/presets*`                — CH session presets (`.hydra/presets/`)
+/// - `/api/projects*`               — CH project registry (switching, project sessions)
+/// - `/api/conversations/search`    — CH message-level search palette
 fn ch_app_protected_routes() -> Router<AppState> {
     Router::new()
         // Claude model list (CH-specific — Anthropic models, not Google)
         .route("/api/claude/models", get(handlers::claude_models))
+        // Gemini model picker (direct Google API)
+        .route("/api/gemini/models", get(handlers::gemini_list_models))
         // Session search (literal path, NOT in shared session_routes)
         .route("/api/sessions/search", get(handlers::search_sessions))
+        // Conversation search palette (ranked message hits with context)
+        .route(
+            "/api/conversations/search",
+            get(handlers::search_conversations),
+        )
         // Session tags (NOT in shared session_routes)
         .route(
             "/api/sessions/{id}/tags",
@@ -305,11 +415,107 @@ fn ch_app_protected_routes() -> Router<AppState> {
             "/api/sessions/{id}/tags/{tag}",
             delete(handlers::delete_session_tag),
         )
+        // Validated per-session workspace (NOT in shared session_routes)
+        .route(
+            "/api/sessions/with-workspace",
+            post(handlers::create_session_with_workspace),
+        )
+        .route(
+            "/api/sessions/{id}/workspace",
+            put(handlers::set_session_workspace),
+        )
+        // Per-session permission profile (yolo / ask / read_only)
+        .route(
+            "/api/sessions/{id}/permission-mode",
+            get(permissions::get_permission_mode).put(permissions::set_permission_mode),
+        )
+        // Per-session Witcher mode (routed background prompts)
+        .route(
+            "/api/sessions/{id}/witcher-mode",
+            get(witcher_router::get_witcher_mode).put(witcher_router::set_witcher_mode),
+        )
+        // Per-session token quota (paused tabs resume only on an explicit raise)
+        .route(
+            "/api/sessions/{id}/quota",
+            get(tab_quota::get_tab_quota).put(tab_quota::set_tab_quota),
+        )
+        .route("/api/sessions/{id}/quota/raise", post(tab_quota::raise_tab_quota))
+        .route(
+            "/api/witcher/routing-stats",
+            get(witcher_router::routing_stats),
+        )
+        .route(
+            "/api/witcher/routing-analytics",
+            get(witcher_router::get_routing_analytics),
+        )
+        .route("/api/witcher/signs", get(witcher_router::list_signs))
+        // Unread tracking (NOT in shared session_routes)
+        .route("/api/sessions/unread", get(handlers::list_unread_sessions))
+        .route("/api/sessions/{id}/read", post(handlers::mark_session_read))
+        // Conversation branching (NOT in shared session_routes)
+        .route("/api/sessions/{id}/fork", post(handlers::fork_session))
+        .route(
+            "/api/sessions/{id}/duplicate",
+            post(handlers::duplicate_session),
+        )
+        // Context compaction — pinned summary of older messages
+        .route(
+            "/api/sessions/{id}/compaction",
+            get(compaction::get_compaction).delete(compaction::clear_compaction),
+        )
+        .route("/api/sessions/{id}/compact", post(compaction::compact))
+        // Embeddings-backed long-term memory per session
+        .route(
+            "/api/sessions/{id}/memory",
+            get(session_memory::list_memory).delete(session_memory::clear_memory),
+        )
+        .route("/api/sessions/{id}/memory/search", post(session_memory::search_memory))
+        // Context window meter: tokens in use vs. the model's limit
+        .route("/api/sessions/{id}/context-usage", get(compaction::get_context_usage))
+        // Conversation export / import (NOT in shared session_routes)
+        .route("/api/sessions/{id}/export", get(handlers::export_session))
+        .route(
+            "/api/sessions/import",
+            post(handlers::import_session).layer(DefaultBodyLimit::max(
+                handlers::session_transfer::MAX_IMPORT_BYTES as usize,
+            )),
+        )
+        // Session groups (sidebar folders with color labels)
+        .route(
+            "/api/session-groups",
+            get(handlers::list_session_groups).post(handlers::create_session_group),
+        )
+        .route(
+            "/api/session-groups/{id}",
+            patch(handlers::update_session_group).delete(handlers::delete_session_group),
+        )
+        .route(
+            "/api/sessions/{id}/group",
+            put(handlers::move_session_to_group),
+        )
+        // Session presets (project configurations under .hydra/presets/)
+        .route("/api/presets", get(presets::list_presets))
+        .route(
+            "/api/presets/{name}",
+            get(presets::get_preset)
+                .put(presets::put_preset)
+                .delete(presets::delete_preset),
+        )
+        .route(
+            "/api/presets/{name}/session",
+            post(presets::create_session_from_preset),
+        )
         // Global tags listing (NOT in shared session_routes)
         .route("/api/tags", get(handlers::list_all_tags))
         // Settings API key endpoint (CH-specific Anthropic key storage,
         // not in shared session_routes which only has /api/settings GET+PATCH)
         .route("/api/settings/api-key", post(handlers::set_api_key))
+        // Provider API keys in the OS credential store (feature `keychain`)
+        .route("/api/secrets/providers", get(secrets::get_provider_key_status))
+        .route(
+            "/api/secrets/providers/{provider}",
+            put(secrets::set_provider_key_handler).delete(secrets::delete_provider_key_handler),
+        )
         // Analytics — agent performance dashboard (CH-specific)
         .route("/api/analytics/tokens", get(handlers::analytics_tokens))
         .route("/api/analytics/latency", get(handlers::analytics_latency))
@@ -319,6 +525,232 @@ fn ch_app_protected_routes() -> Router<AppState> {
         )
         .route("/api/analytics/top-tools", get(handlers::analytics_top_tools))
         .route("/api/analytics/cost", get(handlers::analytics_cost))
+        .route(
+            "/api/analytics/routing-dataset",
+            get(handlers::export_routing_dataset),
+        )
+        // Text embeddings (Ollama / OpenAI-compatible backend)
+        .route("/api/embeddings", post(embeddings::embed))
+        // Ollama model warm-up + resident models (keep-alive policy: CH_OLLAMA_KEEP_ALIVE)
+        .route("/api/ollama/warmup", post(ollama::warmup))
+        .route("/api/ollama/loaded", get(ollama::loaded))
+        .route("/api/ollama/chat", post(ollama::chat))
+        .route("/api/ollama/batch", post(ollama::batch))
+        .route("/api/ollama/events", get(ollama::events))
+        // Generation dispatcher: priority queue + cancel by request_id
+        .route("/api/ollama/queue", get(ollama_queue::queue))
+        .route("/api/ollama/requests/{request_id}", delete(ollama_queue::cancel_request))
+        // Installed-model matcher by task + free RAM/VRAM, consented downloads
+        .route("/api/ollama/select", get(ollama_models::select_model))
+        .route("/api/ollama/pull", post(ollama_models::pull_model))
+        .route("/api/ollama/pulls", get(ollama_models::list_pulls))
+        // Ollama server lifecycle (systemd unit or detached `ollama serve`) + health watchdog
+        .route("/api/ollama/service", get(ollama_service::service_status))
+        .route("/api/ollama/service/start", post(ollama_service::start_service))
+        .route("/api/ollama/service/stop", post(ollama_service::stop_service))
+        // Structured application log (JSONL in <data dir>/logs): query + client entries
+        .route("/api/logs", get(app_log::list_logs).post(app_log::post_logs))
+        // Background job schedule (JSON + iCalendar export)
+        .route("/api/schedule", get(schedule::get_schedule))
+        .route("/api/schedule.ics", get(schedule::export_schedule_ics))
+        // Garbage collection policies + manual (dry-run) runs
+        .route("/api/gc/policies", get(gc::list_policies))
+        .route("/api/gc/policies/{store}", put(gc::put_policy))
+        .route("/api/gc/run", post(gc::run))
+        // Dashboard overview: tabs, queues, provider health, recent completions
+        .route("/api/overview", get(overview::get_overview))
+        .route(
+            "/api/overview/clients/{client}",
+            put(overview::report_client).delete(overview::remove_client),
+        )
+        // Metrics snapshot (also written to <data dir>/metrics.json)
+        .route("/api/metrics/snapshot", get(metrics_snapshot::get_snapshot))
+        // Cost accounting: per-provider / per-session costs and savings vs the baseline model
+        .route("/api/costs", get(costs::get_costs))
+        // Budget caps per provider: spending, remaining budget and exceeded caps
+        .route("/api/budget", get(budget::get_budget_status))
+        // Sensitive-data redaction — detectors and a dry run
+        .route("/api/redaction", get(redaction::get_redaction))
+        .route("/api/redaction/preview", post(redaction::preview))
+        // Safety guard — secret-leak / prompt-injection policies and recent events
+        .route("/api/safety", get(safety::get_safety))
+        .route("/api/safety/events", get(safety::list_events))
+        // Approvals — dangerous tool calls / background prompts outside YOLO mode
+        .route("/api/approvals", get(approvals::list_approvals))
+        .route("/api/approvals/events", get(approvals::events))
+        .route("/api/approvals/{id}", post(approvals::respond_to_approval))
+        // Audit trail — hash-chained commands / file changes, export and verification
+        .route("/api/audit-trail", get(audit_trail::list_audit_trail))
+        .route(
+            "/api/audit-trail/export",
+            get(audit_trail::export_audit_trail),
+        )
+        .route(
+            "/api/audit-trail/verify",
+            get(audit_trail::verify_audit_trail),
+        )
+        // Config file (hydra.toml): current values + validated save
+        .route(
+            "/api/config",
+            get(hydra_config::get_config).put(hydra_config::save_hydra_config),
+        )
+        // Config profiles (work / home / offline) and switching
+        .route("/api/profiles", get(profiles::list_profiles))
+        .route("/api/profiles/switch", post(profiles::switch))
+        // Project registry and switching (working directory, default provider, presets)
+        .route(
+            "/api/projects",
+            get(projects::list_projects).post(projects::add_project),
+        )
+        .route("/api/projects/switch", post(projects::switch_project))
+        .route(
+            "/api/projects/{name}/sessions",
+            get(projects::list_project_sessions),
+        )
+        // External executables (claude, ollama, nvidia-smi, ...) as resolved per OS
+        .route("/api/paths", get(paths::get_paths))
+        .route("/api/paths/refresh", post(paths::refresh))
+        // Historical metrics, downsampled for charts (sampler: CH_METRICS_HISTORY_SECS)
+        .route("/api/history", get(metrics_history::get_history))
+        .route("/api/history/metrics", get(metrics_history::list_metrics))
+        // Past swarm and background queue runs with failure rates / durations
+        .route("/api/swarm/runs", get(run_history::swarm_runs))
+        .route("/api/queue/history", get(run_history::queue_history))
+        // Local RAG over the project directory (CH_RAG_DIR)
+        .route("/api/rag/query", post(rag::query))
+        .route("/api/rag/reindex", post(rag::trigger_reindex))
+        .route("/api/rag/status", get(rag::status))
+        // Speech-to-text (push-to-talk), optionally answered as a prompt
+        .route(
+            "/api/transcribe",
+            post(transcription::transcribe)
+                .layer(DefaultBodyLimit::max(transcription::MAX_REQUEST_BYTES)),
+        )
+        // Text-to-speech playback on the local machine (CH_TTS_BACKEND)
+        .route("/api/tts/speak", post(tts::speak))
+        // Routing rules / model recommendations / prices update channel
+        .route("/api/rules", get(rule_updates::get_rules))
+        .route("/api/rules/check-updates", post(rule_updates::check_updates))
+        .route("/api/rules/overrides", put(rule_updates::put_overrides))
+        // Background prompts run by the queue worker (see queue/)
+        .route(
+            "/api/background-prompts",
+            get(queue::list).post(queue::enqueue),
+        )
+        .route(
+            "/api/background-prompts/events",
+            get(queue::events),
+        )
+        .route(
+            "/api/background-prompts/queue",
+            get(queue::queue_snapshot),
+        )
+        .route(
+            "/api/background-prompts/stats",
+            get(queue_stats::queue_stats),
+        )
+        .route(
+            "/api/background-prompts/history",
+            get(queue_stats::queue_history),
+        )
+        .route(
+            "/api/background-prompts/{id}",
+            delete(queue::cancel),
+        )
+        .route(
+            "/api/background-prompts/{id}/position",
+            put(queue::move_prompt),
+        )
+        .route(
+            "/api/background-prompts/{id}/override-lock",
+            put(queue::set_override_lock),
+        )
+        .route(
+            "/api/background-prompts/{id}/attempts",
+            get(queue::list_attempts),
+        )
+        .route(
+            "/api/background-prompts/{id}/eta",
+            get(queue_stats::prompt_eta),
+        )
+        // Scheduled / recurring prompts (enqueued as background prompts)
+        .route(
+            "/api/scheduled-prompts",
+            get(scheduled_prompts::list_scheduled).post(scheduled_prompts::schedule_prompt),
+        )
+        .route(
+            "/api/scheduled-prompts/{id}",
+            delete(scheduled_prompts::cancel_scheduled),
+        )
+        // External file changes (pinned / affected files)
+        .route(
+            "/api/conflicts",
+            get(file_watcher::list_conflicts).delete(file_watcher::clear_conflicts),
+        )
+        .route("/api/conflicts/events", get(file_watcher::conflict_events))
+        .route("/api/conflicts/{id}", delete(file_watcher::dismiss_conflict))
+        .route(
+            "/api/conflicts/{id}/resolve",
+            post(file_watcher::resolve_conflict),
+        )
+        // MCP registry, health checks, process supervision, tool bridge
+        .route("/api/mcp/registry", get(mcp::registry::list_registry_handler))
+        .route(
+            "/api/mcp/registry/import",
+            post(mcp::registry::import_registry_handler),
+        )
+        .route(
+            "/api/mcp/registry/{id}/enabled",
+            put(mcp::registry::set_enabled_handler),
+        )
+        .route("/api/mcp/health", get(mcp::health::health_handler))
+        .route("/api/mcp/call", post(mcp::bridge::call_tool_handler))
+        .route(
+            "/api/mcp/processes",
+            get(mcp::supervisor::list_processes_handler),
+        )
+        .route(
+            "/api/mcp/processes/{id}/start",
+            post(mcp::supervisor::start_process_handler),
+        )
+        .route(
+            "/api/mcp/processes/{id}/stop",
+            post(mcp::supervisor::stop_process_handler),
+        )
+        .route(
+            "/api/mcp/processes/{id}/restart",
+            post(mcp::supervisor::restart_process_handler),
+        )
+        // Persistent Claude CLI sessions (stream-json over stdio)
+        .route("/api/claude-cli/sessions", get(claude_cli::list_sessions))
+        .route(
+            "/api/claude-cli/sessions/{id}/prompt",
+            post(claude_cli::prompt),
+        )
+        .route(
+            "/api/claude-cli/sessions/{id}",
+            delete(claude_cli::stop_session),
+        )
+        .route(
+            "/api/claude-cli/sessions/{id}/idle-timeout",
+            put(claude_cli::set_session_idle_timeout),
+        )
+        .route("/api/claude-cli/processes", get(claude_cli::get_process_stats))
+        // Process inventory (Ollama, Claude CLI, MCP servers) with kill / restart
+        .route("/api/processes", get(processes::list_processes))
+        .route("/api/processes/{pid}/kill", post(processes::kill_process))
+        .route("/api/processes/{pid}/restart", post(processes::restart_process))
+        // Post-response hooks (user scripts, CH_HOOKS_CONFIG)
+        .route("/api/hooks", get(hooks::list_hooks))
+        // Prompt lifecycle trace (time-travel debugging)
+        .route(
+            "/api/prompts/{execution_id}/trace",
+            get(prompt_trace::get_prompt_trace),
+        )
+        .route(
+            "/api/prompts/{execution_id}/rating",
+            post(prompt_trace::rate_prompt),
+        )
 }
 
 /// Prometheus metrics endpoint (public, no auth).
@@ -537,6 +969,7 @@ pub fn create_router(state: AppState) -> Router {
         .merge(ch_profiling_routes())
         // Swarm IPC: Cross-Agent Communication Protocol endpoints
         .merge(jaskier_swarm::swarm_router::<AppState>())
+        .merge(swarm::swarm_results_router())
         // CRDT Real-time Collaboration: WebSocket sync + stats endpoints
         .merge(jaskier_collab::collab_router::<AppState>())
         // Semantic Cache: Qdrant-backed semantic router + AST compression
@@ -573,6 +1006,8 @@ pub fn create_test_router(state: AppState) -> Router {
         // Webhooks: Grafana incidents
         .merge(ch_auto_qa_routes())
         .merge(ch_profiling_routes())
+        // Swarm result paging + dry-run planning
+        .merge(swarm::swarm_results_router())
         // CRDT Real-time Collaboration
         .merge(jaskier_collab::collab_router::<AppState>())
         // Semantic Cache
@@ -0,0 +1,277 @@
+//! Dashboard live updates over WebSocket — replaces polling of the stats,
+//! logs, queue and health endpoints.
+//!
+//! `GET /ws?token=<secret>&topics=system,logs` upgrades to a WebSocket that
+//! pushes `{"type": "update", "topic": …, "data": …}` frames:
+//!
+//! - `system` — CPU / memory / internal queues (as `/api/system/stats`)
+//! - `logs`   — each new structured log entry (as `/api/logs`), as it is recorded
+//! - `queue`  — background prompt stats + the Ollama generation queue
+//! - `health` — provider availability, database and Ollama status
+//!
+//! Polled topics are sampled every `CH_LIVE_UPDATES_SECS` (default 2, health
+//! every 5th tick) and only sent when they changed. `topics` defaults to all.
+//! Clients change their set with `{"type": "subscribe" | "unsubscribe",
+//! "topics": [...]}` (the current data of newly subscribed topics is sent
+//! right away) and may send `{"type": "ping"}`. The server sends a
+//! `{"type": "heartbeat"}` every 30 s.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::extract::ws::{Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast;
+
+use jaskier_core::auth::validate_ws_token;
+
+use crate::state::AppState;
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+/// Health checks hit the database and Ollama — sample them less often.
+const HEALTH_EVERY_TICKS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Topic {
+    System,
+    Logs,
+    Queue,
+    Health,
+}
+
+impl Topic {
+    const ALL: [Topic; 4] = [Topic::System, Topic::Logs, Topic::Queue, Topic::Health];
+
+    fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "system" => Some(Topic::System),
+            "logs" => Some(Topic::Logs),
+            "queue" => Some(Topic::Queue),
+            "health" => Some(Topic::Health),
+            _ => None,
+        }
+    }
+}
+
+/// `topics` query value — comma separated; missing or empty means all.
+/// Unknown names are ignored.
+fn parse_topics(raw: Option<&str>) -> HashSet<Topic> {
+    let topics: HashSet<Topic> = raw
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(Topic::parse)
+        .collect();
+    if topics.is_empty() {
+        Topic::ALL.into_iter().collect()
+    } else {
+        topics
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+    Ping,
+}
+
+fn tick_interval() -> Duration {
+    static SECS: OnceLock<u64> = OnceLock::new();
+    Duration::from_secs(*SECS.get_or_init(|| {
+        std::env::var("CH_LIVE_UPDATES_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse::<u64>().ok())
+            .unwrap_or(2)
+            .clamp(1, 300)
+    }))
+}
+
+/// Current data of a polled topic (`None` for `logs`, which is pushed).
+pub(crate) async fn sample(state: &AppState, topic: Topic) -> Option<Value> {
+    match topic {
+        Topic::System => Some(crate::handlers::system_stats(State(state.clone())).await.0),
+        Topic::Queue => {
+            let background = crate::queue_stats::queue_stats(State(state.clone()))
+                .await
+                .map(|json| json.0)
+                .unwrap_or(Value::Null);
+            Some(json!({
+                "background": background,
+                "ollama": crate::ollama_queue::snapshot(),
+            }))
+        }
+        Topic::Health => {
+            let mut health = crate::handlers::health_check(State(state.clone())).await.0;
+            let ollama = crate::ollama_service::ollama_version(state).await;
+            health["ollama"] = json!({ "running": ollama.is_some(), "version": ollama });
+            Some(health)
+        }
+        Topic::Logs => None,
+    }
+}
+
+/// `GET /ws` — auth via `?token=<secret>` like `/ws/chat`.
+pub async fn live_updates(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let query_string: String = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&");
+    if !validate_ws_token(&query_string, state.auth_secret.as_deref()) {
+        return (StatusCode::UNAUTHORIZED, "Invalid or missing auth token").into_response();
+    }
+    let topics = parse_topics(params.get("topics").map(String::as_str));
+    ws.on_upgrade(move |socket| handle_socket(socket, state, topics))
+}
+
+struct Connection {
+    sender: SplitSink<WebSocket, WsMessage>,
+    topics: HashSet<Topic>,
+    /// Last data sent per polled topic — unchanged samples are skipped.
+    last: HashMap<Topic, Value>,
+}
+
+impl Connection {
+    /// `false` once the client is gone.
+    async fn send(&mut self, frame: Value) -> bool {
+        self.sender
+            .send(WsMessage::Text(frame.to_string().into()))
+            .await
+            .is_ok()
+    }
+
+    async fn push(&mut self, state: &AppState, topic: Topic) -> bool {
+        let Some(data) = sample(state, topic).await else {
+            return true;
+        };
+        if self.last.get(&topic) == Some(&data) {
+            return true;
+        }
+        self.last.insert(topic, data.clone());
+        self.send(json!({ "type": "update", "topic": topic, "data": data }))
+            .await
+    }
+
+    async fn handle(&mut self, state: &AppState, text: &str) -> bool {
+        match serde_json::from_str::<ClientMessage>(text) {
+            Ok(ClientMessage::Subscribe { topics }) => {
+                let added: Vec<Topic> = topics
+                    .iter()
+                    .filter_map(|t| Topic::parse(t))
+                    .filter(|t| self.topics.insert(*t))
+                    .collect();
+                for topic in added {
+                    self.last.remove(&topic);
+                    if !self.push(state, topic).await {
+                        return false;
+                    }
+                }
+                self.send(json!({ "type": "subscribed", "topics": self.topics }))
+                    .await
+            }
+            Ok(ClientMessage::Unsubscribe { topics }) => {
+                for topic in topics.iter().filter_map(|t| Topic::parse(t)) {
+                    self.topics.remove(&topic);
+                }
+                self.send(json!({ "type": "subscribed", "topics": self.topics }))
+                    .await
+            }
+            Ok(ClientMessage::Ping) => self.send(json!({ "type": "pong" })).await,
+            Err(e) => {
+                tracing::debug!("live_updates: invalid client message: {}", e);
+                self.send(json!({ "type": "error", "message": "Invalid message format" }))
+                    .await
+            }
+        }
+    }
+}
+
+async fn handle_socket(socket: WebSocket, state: AppState, topics: HashSet<Topic>) {
+    let (sender, mut receiver) = socket.split();
+    let mut conn = Connection {
+        sender,
+        topics,
+        last: HashMap::new(),
+    };
+    let mut logs = crate::app_log::subscribe();
+    let mut ticker = tokio::time::interval(tick_interval());
+    let mut heartbeat =
+        tokio::time::interval_at(tokio::time::Instant::now() + HEARTBEAT, HEARTBEAT);
+    let mut ticks: u64 = 0;
+    tracing::debug!("live_updates: client connected");
+
+    loop {
+        let open = tokio::select! {
+            msg = receiver.next() => match msg {
+                Some(Ok(WsMessage::Text(text))) => conn.handle(&state, &text).await,
+                Some(Ok(WsMessage::Close(_))) | None | Some(Err(_)) => false,
+                Some(Ok(_)) => true,
+            },
+            entry = logs.recv() => match entry {
+                Ok(entry) if conn.topics.contains(&Topic::Logs) => {
+                    conn.send(json!({ "type": "update", "topic": Topic::Logs, "data": entry }))
+                        .await
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => true,
+                Err(broadcast::error::RecvError::Closed) => false,
+            },
+            _ = ticker.tick() => {
+                let health_due = ticks % HEALTH_EVERY_TICKS == 0;
+                ticks += 1;
+                let mut open = true;
+                for topic in [Topic::System, Topic::Queue, Topic::Health] {
+                    if !conn.topics.contains(&topic) || (topic == Topic::Health && !health_due) {
+                        continue;
+                    }
+                    if !conn.push(&state, topic).await {
+                        open = false;
+                        break;
+                    }
+                }
+                open
+            }
+            _ = heartbeat.tick() => conn.send(json!({ "type": "heartbeat" })).await,
+        };
+        if !open {
+            break;
+        }
+    }
+    tracing::debug!("live_updates: client disconnected");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topics_default_to_all_and_ignore_unknown_names() {
+        assert_eq!(parse_topics(None).len(), 4);
+        assert_eq!(parse_topics(Some("")).len(), 4);
+        assert_eq!(parse_topics(Some("bogus")).len(), 4);
+        let topics = parse_topics(Some("logs, Health,bogus"));
+        assert_eq!(topics, HashSet::from([Topic::Logs, Topic::Health]));
+    }
+
+    #[test]
+    fn client_messages_parse() {
+        let msg: ClientMessage =
+            serde_json::from_str(r#"{"type":"subscribe","topics":["queue"]}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Subscribe { topics } if topics == ["queue"]));
+        let msg: ClientMessage = serde_json::from_str(r#"{"type":"ping"}"#).unwrap();
+        assert!(matches!(msg, ClientMessage::Ping));
+        assert!(serde_json::from_str::<ClientMessage>(r#"{"type":"execute"}"#).is_err());
+    }
+}
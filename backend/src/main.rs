@@ -4,8 +4,8 @@ use tower_http::compression::predicate::{DefaultPredicate, NotForContentType, Pr
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
 
-use claudehydra_backend::handlers;
-use claudehydra_backend::model_registry;
+use claudehydra_backend::access;
+use claudehydra_backend::correlation;
 use claudehydra_backend::state::AppState;
 #[cfg(feature = "shuttle")]
 use claudehydra_backend::state::LogRingBuffer;
@@ -14,14 +14,18 @@ use claudehydra_backend::watchdog;
 use jaskier_core::app_builder;
 
 fn build_app(state: AppState) -> axum::Router {
-    // CORS — centralized via jaskier-auth, Vercel production origins as extras
-    let cors = jaskier_auth::build_cors_layer(&[
-        "https://claudehydra-v4.vercel.app",
-        "https://claudehydra-v4-pawelserkowskis-projects.vercel.app",
-    ]);
+    // CORS — centralized via jaskier-auth; extras are the hosted frontend
+    // origins, or exactly CH_CORS_ORIGINS when set (see access.rs)
+    let access = access::config();
+    let origins: Vec<&str> = access.cors_origins.iter().map(String::as_str).collect();
+    let cors = jaskier_auth::build_cors_layer(&origins);
 
     // Rate limiting: per-endpoint governors configured in lib.rs (#21)
     claudehydra_backend::create_router(state)
+        // LAN access guard (CH_BASIC_AUTH) — inside CORS so preflights pass
+        .layer(axum::middleware::from_fn(access::guard))
+        // Correlation id per request (X-Correlation-Id), also on rejected ones
+        .layer(axum::middleware::from_fn(correlation::propagate))
         .layer(cors)
         // ── #11 Security headers ────────────────────────────────────────
         .layer(SetResponseHeaderLayer::overriding(
@@ -118,9 +122,15 @@ async fn main() -> shuttle_axum::ShuttleAxum {
     // ── Spawn system monitor (CPU/memory stats, refreshed every 5s) ──
     claudehydra_backend::system_monitor::spawn(state.system_monitor.clone());
 
-    model_registry::startup_sync(&state).await;
-    handlers::warm_prompt_cache(&state).await;
-    state.mark_ready();
+    // ── Audit trail writer (shared with the local entry point) ──
+    claudehydra_backend::startup::spawn_background_writers(&state);
+
+    claudehydra_backend::projects::load(&state.db).await;
+    if let Err(e) = claudehydra_backend::approvals::expire_orphaned(&state.db).await {
+        tracing::warn!("approvals: failed to expire orphaned tool approvals: {}", e);
+    }
+    // ── Warm start: model sync, prompt cache, probes → readiness report ──
+    claudehydra_backend::startup::spawn_warm_start(state.clone());
     Ok(build_app(state).into())
 }
 
@@ -152,74 +162,21 @@ async fn main() -> anyhow::Result<()> {
     // ── Spawn system monitor (CPU/memory stats, refreshed every 5s) ──
     claudehydra_backend::system_monitor::spawn(state.system_monitor.clone());
 
-    // ── Non-blocking startup: model sync in background with retry (#8) ──
-    let startup_state = state.clone();
-    tokio::spawn(async move {
-        let retry_delays = [
-            std::time::Duration::from_secs(5),
-            std::time::Duration::from_secs(15),
-            std::time::Duration::from_secs(30),
-        ];
-        let sync_timeout = std::time::Duration::from_secs(90);
-        let mut synced = false;
-
-        for (attempt, delay) in std::iter::once(&std::time::Duration::ZERO)
-            .chain(retry_delays.iter())
-            .enumerate()
-        {
-            if attempt > 0 {
-                tracing::warn!(
-                    "startup: model registry sync retry {}/{} after {}s",
-                    attempt,
-                    retry_delays.len(),
-                    delay.as_secs()
-                );
-                tokio::time::sleep(*delay).await;
-            }
-
-            match tokio::time::timeout(sync_timeout, model_registry::startup_sync(&startup_state))
-                .await
-            {
-                Ok(()) => {
-                    tracing::info!(
-                        "startup: model registry sync complete (attempt {})",
-                        attempt + 1
-                    );
-                    synced = true;
-                    break;
-                }
-                Err(_) => {
-                    tracing::error!(
-                        "startup: model registry sync timed out after {}s (attempt {})",
-                        sync_timeout.as_secs(),
-                        attempt + 1
-                    );
-                }
-            }
-        }
-
-        if !synced {
-            tracing::error!(
-                "startup: model registry sync failed after {} attempts — using fallback models",
-                retry_delays.len() + 1
-            );
-        }
-
-        handlers::warm_prompt_cache(&startup_state).await;
-        startup_state.mark_ready();
-    });
+    // ── Active project (tags log entries; see projects.rs) ──
+    claudehydra_backend::projects::load(&state.db).await;
+
+    // ── Approvals: tool calls waiting in a previous run can no longer resume ──
+    if let Err(e) = claudehydra_backend::approvals::expire_orphaned(&state.db).await {
+        tracing::warn!("approvals: failed to expire orphaned tool approvals: {}", e);
+    }
+
+    // ── Non-blocking warm start: model sync (with retry), prompt cache,
+    //    Anthropic/Vault health, MCP connections → readiness report (#8) ──
+    claudehydra_backend::startup::spawn_warm_start(state.clone());
 
     // ── Spawn background watchdog ──
     let _watchdog = watchdog::spawn(state.clone());
 
-    // ── Spawn MCP client startup (connect to enabled MCP servers) ──
-    let mcp_state = state.clone();
-    tokio::spawn(async move {
-        if let Err(e) = mcp_state.mcp_client.startup_connect().await {
-            tracing::error!("MCP startup_connect failed: {}", e);
-        }
-    });
-
     // ── Spawn Swarm IPC discovery loop (probes peers every 30s) ──
     state.swarm.start_discovery();
 
@@ -233,6 +190,51 @@ async fn main() -> anyhow::Result<()> {
     // ── Spawn Memory Pruning watchdog (configurable interval, default 1h) ──
     claudehydra_backend::memory_pruning::spawn_pruning_watchdog(state.clone());
 
+    // ── Audit trail writer: appends recorded commands / file changes in order ──
+    claudehydra_backend::startup::spawn_background_writers(&state);
+
+    // ── Idle scavenger: low-priority background prompts (every 30s) ──
+    claudehydra_backend::idle_scavenger::spawn(state.clone());
+
+    // ── Scheduled / recurring prompts → background queue (every 30s) ──
+    claudehydra_backend::scheduled_prompts::spawn(state.clone());
+
+    // ── File watcher → external-change conflicts (pinned / affected files) ──
+    claudehydra_backend::file_watcher::spawn(state.clone());
+
+    // ── Local RAG: load persisted index + incremental reindex loop (CH_RAG_DIR) ──
+    claudehydra_backend::rag::spawn_reindex_loop(state.clone());
+
+    // ── Ollama warm-up of CH_OLLAMA_WARMUP_MODELS (avoids cold first requests) ──
+    claudehydra_backend::ollama::spawn(state.clone());
+
+    // ── Ollama health watchdog: status events + optional auto-restart ──
+    claudehydra_backend::ollama_service::spawn(state.clone());
+
+    // ── Custom Witcher signs: load + hot reload (.hydra/witcher-signs.toml) ──
+    claudehydra_backend::witcher_router::spawn(state.clone());
+
+    // ── Config file: hot reload of queue, Ollama and routing settings (hydra.toml) ──
+    claudehydra_backend::hydra_config::spawn();
+
+    // ── Config profiles: provider keys of the active one + automatic offline switch ──
+    claudehydra_backend::profiles::spawn(state.clone());
+
+    // ── Routing rule overlays: load + signed manifest checks (CH_RULES_MANIFEST_URL) ──
+    claudehydra_backend::rule_updates::spawn(state.clone());
+
+    // ── Garbage collection of persisted stores (every 6h, per-store policies) ──
+    claudehydra_backend::gc::spawn(state.clone());
+
+    // ── Metrics snapshot file for dashboards (<data dir>/metrics.json) ──
+    claudehydra_backend::metrics_snapshot::spawn(state.clone());
+
+    // ── Historical metrics sampler for dashboard charts ──
+    claudehydra_backend::metrics_history::spawn(state.clone());
+
+    // ── Claude CLI: stop idle persistent processes (CH_CLAUDE_CLI_IDLE_SECS) ──
+    claudehydra_backend::claude_cli::spawn_reaper();
+
     // ── Browser proxy mode logging ──
     if claudehydra_backend::browser_proxy::is_enabled() {
         let auto_restart = claudehydra_backend::browser_proxy::proxy_dir().is_some();
@@ -243,14 +245,28 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    let shutdown_state = state.clone();
     let app = build_app(state);
 
-    let port: u16 = std::env::var("PORT")
-        .unwrap_or_else(|_| "8082".to_string())
-        .parse()?;
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    // Bind address: CH_BIND_ADDR (default loopback) + PORT
+    let access_config =
+        access::AccessConfig::from_config(&claudehydra_backend::hydra_config::current())
+            .map_err(anyhow::Error::msg)?;
+    let addr = access_config.bind;
+    let port = addr.port();
+    if access_config.is_exposed() && !access_config.has_credentials() {
+        tracing::warn!(
+            "Listening on {} without CH_BASIC_AUTH or AUTH_SECRET — the API is open to the network",
+            addr
+        );
+    }
 
-    app_builder::print_banner("CLAUDEHYDRA v4", "AI Swarm Control Center", "33", port);
+    // Login autostart runs headless — no console to print a banner to.
+    if claudehydra_backend::autostart::is_background() {
+        tracing::info!("Background mode (started at login)");
+    } else {
+        app_builder::print_banner("CLAUDEHYDRA v4", "AI Swarm Control Center", "33", port);
+    }
     tracing::info!("ClaudeHydra v4 backend listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -258,8 +274,14 @@ async fn main() -> anyhow::Result<()> {
         listener,
         app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
     )
-    .with_graceful_shutdown(app_builder::shutdown_signal())
+    .with_graceful_shutdown(async {
+        app_builder::shutdown_signal().await;
+        claudehydra_backend::shutdown::begin_drain();
+    })
     .await?;
 
+    // ── Drain background work, stop CLI children, requeue interrupted prompts ──
+    claudehydra_backend::shutdown::finish(&shutdown_state).await;
+
     Ok(())
 }
@@ -0,0 +1,263 @@
+//! Markdown vault mirror — copies every completed chat exchange into a plain
+//! Markdown folder structure that Obsidian (or any notes tool) can index.
+//!
+//! Opt-in via environment or `[markdown_vault]` in the config file
+//! (`hydra_config.rs`):
+//! - `CH_MARKDOWN_VAULT_DIR` — vault root; mirroring is disabled when unset.
+//! - `CH_MARKDOWN_VAULT_LAYOUT` — `daily` (default, one file per day) or
+//!   `session` (one file per chat session; session-less exchanges fall back
+//!   to the daily file).
+//!
+//! Layout: `{root}/{workspace}/{YYYY-MM-DD | session-<uuid>}.md`, where
+//! `workspace` is the last component of the resolved working directory
+//! (`_default` when none is set). New files start with YAML frontmatter;
+//! exchanges are appended as `##` sections, redacted (see `redaction.rs`).
+//! Writes to one file are serialized, so concurrent exchanges neither write
+//! the frontmatter twice nor interleave. Writes never block or fail the
+//! chat — errors are logged and dropped.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use chrono::{DateTime, Utc};
+use tokio::io::AsyncWriteExt;
+
+use crate::hydra_config::{Derived, HydraConfig, text};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VaultLayout {
+    Daily,
+    Session,
+}
+
+#[derive(Debug, Clone)]
+pub struct MarkdownVaultConfig {
+    pub root: PathBuf,
+    pub layout: VaultLayout,
+}
+
+static CONFIG: Derived<Option<Arc<MarkdownVaultConfig>>> = Derived::new(build_config);
+
+/// One write lock per vault file.
+static FILE_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Arc<tokio::sync::Mutex<()>>>>> =
+    LazyLock::new(Default::default);
+
+/// Write lock for `path`; locks no writer holds any more are dropped.
+fn file_lock(path: &Path) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = FILE_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(path.to_path_buf()).or_default().clone()
+}
+
+pub(crate) fn parse_layout(raw: &str) -> Option<VaultLayout> {
+    match raw.trim() {
+        "daily" => Some(VaultLayout::Daily),
+        "session" => Some(VaultLayout::Session),
+        _ => None,
+    }
+}
+
+fn build_config(config: &HydraConfig) -> Option<Arc<MarkdownVaultConfig>> {
+    let file = &config.markdown_vault;
+    let root = text("CH_MARKDOWN_VAULT_DIR", file.dir.as_deref())?;
+    let layout = text("CH_MARKDOWN_VAULT_LAYOUT", file.layout.as_deref())
+        .and_then(|raw| parse_layout(&raw))
+        .unwrap_or(VaultLayout::Daily);
+    tracing::info!("markdown_vault: mirroring exchanges to {} ({:?})", root, layout);
+    Some(Arc::new(MarkdownVaultConfig {
+        root: PathBuf::from(root),
+        layout,
+    }))
+}
+
+/// Vault configuration (env overrides the file). `None` = mirroring disabled.
+pub fn config() -> Option<Arc<MarkdownVaultConfig>> {
+    CONFIG.get()
+}
+
+/// One completed prompt/response pair.
+#[derive(Debug, Clone)]
+pub struct Exchange {
+    pub session_id: Option<uuid::Uuid>,
+    pub working_directory: String,
+    pub model: String,
+    pub prompt: String,
+    pub response: String,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Mirror an exchange in the background. No-op when the vault is not configured.
+pub fn mirror_exchange(mut exchange: Exchange) {
+    let Some(cfg) = config() else {
+        return;
+    };
+    if exchange.response.is_empty() {
+        return;
+    }
+    crate::redaction::redact_string(&mut exchange.prompt);
+    crate::redaction::redact_string(&mut exchange.response);
+    tokio::spawn(async move {
+        if let Err(e) = append_exchange(&cfg, &exchange).await {
+            tracing::warn!("markdown_vault: failed to write exchange: {}", e);
+        }
+    });
+}
+
+async fn append_exchange(cfg: &MarkdownVaultConfig, ex: &Exchange) -> std::io::Result<()> {
+    let workspace = workspace_name(&ex.working_directory);
+    let dir = cfg.root.join(&workspace);
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let date = ex.completed_at.format("%Y-%m-%d").to_string();
+    let (file_name, session_file) = match (cfg.layout, ex.session_id) {
+        (VaultLayout::Session, Some(sid)) => (format!("session-{}.md", sid), true),
+        _ => (format!("{}.md", date), false),
+    };
+    let path = dir.join(file_name);
+
+    // Held from the existence check to the end of the append.
+    let lock = file_lock(&path);
+    let _guard = lock.lock().await;
+    let mut out = String::new();
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        out.push_str(&frontmatter(ex, &workspace, &date, session_file));
+    }
+    out.push_str(&exchange_section(ex));
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await?;
+    file.write_all(out.as_bytes()).await?;
+    file.flush().await
+}
+
+/// Folder name for a working directory — its last path component, sanitised.
+fn workspace_name(working_directory: &str) -> String {
+    let name = Path::new(working_directory.trim())
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("");
+    let clean: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.' | ' ') { c } else { '_' })
+        .collect();
+    let clean = clean.trim_matches(|c| c == '.' || c == ' ');
+    if clean.is_empty() {
+        "_default".to_string()
+    } else {
+        clean.to_string()
+    }
+}
+
+fn frontmatter(ex: &Exchange, workspace: &str, date: &str, session_file: bool) -> String {
+    let mut fm = String::from("---\n");
+    fm.push_str(&format!("workspace: {}\n", yaml_str(workspace)));
+    if !ex.working_directory.is_empty() {
+        fm.push_str(&format!("workspace_path: {}\n", yaml_str(&ex.working_directory)));
+    }
+    if let (true, Some(sid)) = (session_file, ex.session_id) {
+        fm.push_str(&format!("session_id: {}\n", sid));
+    }
+    fm.push_str(&format!("date: {}\n", date));
+    fm.push_str(&format!("created: {}\n", ex.completed_at.to_rfc3339()));
+    fm.push_str("source: ClaudeHydra\n");
+    fm.push_str("tags:\n  - claudehydra\n  - ai-chat\n");
+    fm.push_str("---\n\n");
+    fm
+}
+
+fn exchange_section(ex: &Exchange) -> String {
+    let session = ex
+        .session_id
+        .map(|s| format!(" · session `{}`", s))
+        .unwrap_or_default();
+    format!(
+        "## {} — {}{}\n\n### Prompt\n\n{}\n\n### Response\n\n{}\n\n---\n\n",
+        ex.completed_at.format("%H:%M:%S"),
+        ex.model,
+        session,
+        ex.prompt.trim_end(),
+        ex.response.trim_end(),
+    )
+}
+
+/// Double-quoted YAML scalar (handles `:`/`#`/Windows paths).
+fn yaml_str(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exchange(prompt: &str) -> Exchange {
+        Exchange {
+            session_id: None,
+            working_directory: "C:/work/my-project".to_string(),
+            model: "claude-sonnet-4-6".to_string(),
+            prompt: prompt.to_string(),
+            response: format!("answer to {}", prompt),
+            completed_at: Utc::now(),
+        }
+    }
+
+    fn vault(layout: VaultLayout) -> MarkdownVaultConfig {
+        MarkdownVaultConfig {
+            root: std::env::temp_dir().join(format!("ch-vault-{}", uuid::Uuid::new_v4())),
+            layout,
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_appends_write_frontmatter_once_and_never_interleave() {
+        let cfg = Arc::new(vault(VaultLayout::Daily));
+        let writers: Vec<_> = (0..16)
+            .map(|i| {
+                let cfg = cfg.clone();
+                tokio::spawn(async move {
+                    append_exchange(&cfg, &exchange(&format!("prompt {}", i))).await
+                })
+            })
+            .collect();
+        for writer in writers {
+            writer.await.unwrap().unwrap();
+        }
+        let date = Utc::now().format("%Y-%m-%d").to_string();
+        let path = cfg.root.join("my-project").join(format!("{}.md", date));
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("---\n"));
+        assert_eq!(text.matches("source: ClaudeHydra").count(), 1);
+        for i in 0..16 {
+            let section = format!(
+                "### Prompt\n\nprompt {}\n\n### Response\n\nanswer to prompt {}\n",
+                i, i
+            );
+            assert!(text.contains(&section), "section {} is not intact", i);
+        }
+        std::fs::remove_dir_all(&cfg.root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn session_layout_uses_one_file_per_session() {
+        let cfg = vault(VaultLayout::Session);
+        let sid = uuid::Uuid::new_v4();
+        let mut ex = exchange("hello");
+        ex.session_id = Some(sid);
+        append_exchange(&cfg, &ex).await.unwrap();
+        let path = cfg.root.join("my-project").join(format!("session-{}.md", sid));
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains(&format!("session_id: {}", sid)));
+        std::fs::remove_dir_all(&cfg.root).unwrap();
+    }
+
+    #[test]
+    fn workspace_names_are_sanitised() {
+        assert_eq!(workspace_name("C:/work/my-project"), "my-project");
+        assert_eq!(workspace_name(""), "_default");
+        assert_eq!(workspace_name("/srv/a:b*c"), "a_b_c");
+        assert_eq!(yaml_str(r#"C:\dir "x""#), r#""C:\\dir \"x\"""#);
+    }
+}
@@ -0,0 +1,187 @@
+//! MCP tool bridge — lets the frontend call a tool on a connected MCP server
+//! directly (e.g. a Desktop Commander or Playwright action) without going
+//! through a model turn.
+//!
+//! The server is matched by name (or id) and the tool by its unprefixed name;
+//! the call goes through the shared `McpClientManager::call_tool`, i.e. the
+//! same connection the models use. Calls are bounded by `CALL_TIMEOUT`.
+//!
+//! Calls obey the permission mode of `session_id` (else the default mode, see
+//! `permissions.rs`) like model tool calls do: MCP tools count as mutating, so
+//! `read_only` refuses them and `ask` waits for an approval (`approvals.rs`).
+//! Every call that runs is recorded in the audit trail.
+//!
+//! - `POST /api/mcp/call` — `{ server, tool, arguments?, session_id? }` → `{ server, tool, result, is_error }`
+
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::audit_trail::{Attribution, CommandRun};
+use crate::permissions::{self, PermissionMode};
+use crate::state::AppState;
+
+const CALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Deserialize)]
+pub struct CallToolRequest {
+    /// Server name or id.
+    pub server: String,
+    /// Tool name as advertised by the server (without the `mcp_` prefix).
+    pub tool: String,
+    #[serde(default)]
+    pub arguments: Option<Value>,
+    /// Session the call is made for: its permission mode applies and the
+    /// audit entry is attributed to it.
+    #[serde(default)]
+    pub session_id: Option<String>,
+}
+
+/// Structured result when the tool returned JSON, the raw text otherwise.
+fn result_value(raw: String) -> Value {
+    serde_json::from_str(&raw).unwrap_or(Value::String(raw))
+}
+
+/// POST /api/mcp/call — invoke a tool on a connected MCP server
+pub async fn call_tool_handler(
+    State(state): State<AppState>,
+    Json(req): Json<CallToolRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let arguments = req.arguments.unwrap_or_else(|| json!({}));
+    if !arguments.is_object() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "arguments must be an object" })),
+        ));
+    }
+
+    let session_id = match req.session_id.as_deref().filter(|s| !s.is_empty()) {
+        Some(raw) => Some(raw.parse::<uuid::Uuid>().map_err(|_| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "Invalid session id" })),
+            )
+        })?),
+        None => None,
+    };
+
+    let server_name = match super::config::get_by_id(&state.db, &req.server).await {
+        Ok(Some(server)) => server.name,
+        _ => req.server.clone(),
+    };
+    let Some(tool) = state
+        .mcp_client
+        .list_all_tools()
+        .await
+        .into_iter()
+        .find(|t| t.server_name == server_name && t.name == req.tool)
+    else {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": format!(
+                    "Tool '{}' not found on server '{}' (is the server connected?)",
+                    req.tool, req.server
+                )
+            })),
+        ));
+    };
+
+    let mode = match session_id {
+        Some(sid) => permissions::session_mode(&state.db, &sid).await,
+        None => permissions::default_mode(),
+    };
+    if let Some(refusal) = authorize(&state, mode, session_id, &tool.prefixed_name, &arguments).await {
+        return Err((StatusCode::FORBIDDEN, Json(json!({ "error": refusal }))));
+    }
+
+    let started = Instant::now();
+    let call = state.mcp_client.call_tool(&tool.prefixed_name, &arguments);
+    let outcome = tokio::time::timeout(CALL_TIMEOUT, call).await;
+    crate::audit_trail::command(
+        "mcp_bridge",
+        CommandRun {
+            binary: &tool.prefixed_name,
+            args: &[arguments.to_string()],
+            cwd: "",
+            exit_code: match &outcome {
+                Ok(Ok(_)) => Some(0),
+                Ok(Err(_)) => Some(1),
+                Err(_) => None,
+            },
+            duration_ms: started.elapsed().as_millis() as u64,
+        },
+        Attribution::session(session_id),
+    );
+    let (result, is_error) = match outcome {
+        Ok(Ok(out)) => (result_value(out), false),
+        Ok(Err(e)) => (Value::String(e), true),
+        Err(_) => {
+            return Err((
+                StatusCode::GATEWAY_TIMEOUT,
+                Json(json!({
+                    "error": format!("Tool call timed out after {}s", CALL_TIMEOUT.as_secs())
+                })),
+            ));
+        }
+    };
+    tracing::info!(
+        server = %server_name,
+        tool = %req.tool,
+        is_error,
+        "mcp bridge: tool called"
+    );
+    Ok(Json(json!({
+        "server": server_name,
+        "tool": req.tool,
+        "result": result,
+        "is_error": is_error,
+    })))
+}
+
+/// Refusal when `mode` does not let the tool run: `read_only` never does,
+/// `ask` waits for the user's decision on an approval request.
+async fn authorize(
+    state: &AppState,
+    mode: PermissionMode,
+    session_id: Option<uuid::Uuid>,
+    tool: &str,
+    arguments: &Value,
+) -> Option<String> {
+    if !mode.allows_tool(tool) {
+        return Some(format!("Tool '{}' is not available in read-only mode", tool));
+    }
+    if !mode.needs_approval(tool) {
+        return None;
+    }
+    match crate::approvals::request_tool(&state.db, session_id, tool, arguments).await {
+        Ok((approval, rx)) => crate::approvals::await_decision(&state.db, approval.id, rx)
+            .await
+            .refusal(tool),
+        Err(e) => {
+            tracing::error!("approvals: request for {} failed: {}", tool, e);
+            Some(format!(
+                "Tool '{}' needs approval, but the request could not be recorded",
+                tool
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_results_are_structured() {
+        assert_eq!(
+            result_value(r#"{"ok":true}"#.to_string()),
+            json!({ "ok": true })
+        );
+        assert_eq!(result_value("plain text".to_string()), json!("plain text"));
+    }
+}
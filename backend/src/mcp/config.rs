@@ -167,6 +167,7 @@ pub async fn create_server_handler(
         StatusCode::INTERNAL_SERVER_ERROR
     })?;
 
+    super::health::invalidate().await;
     Ok((StatusCode::CREATED, Json(redact_server(&server))))
 }
 
@@ -219,6 +220,7 @@ pub async fn update_server_handler(
         })?
         .ok_or(StatusCode::NOT_FOUND)?;
 
+    super::health::invalidate().await;
     Ok(Json(redact_server(&server)))
 }
 
@@ -236,6 +238,7 @@ pub async fn delete_server_handler(
     })?;
 
     if deleted {
+        super::health::invalidate().await;
         Ok(StatusCode::NO_CONTENT)
     } else {
         Err(StatusCode::NOT_FOUND)
@@ -0,0 +1,442 @@
+//! MCP health checks — probes every server in the registry (`ch_mcp_servers`,
+//! see `registry.rs`) rather than a fixed list of well-known servers.
+//!
+//! Each enabled server gets a real MCP handshake, bounded by `PROBE_TIMEOUT`:
+//! - stdio: the command is spawned with its args/env and sent newline-delimited
+//!   JSON-RPC over stdin/stdout; the process is killed afterwards
+//! - http: JSON-RPC is POSTed to the URL (streamable HTTP; JSON or SSE
+//!   replies, `Mcp-Session-Id` carried over)
+//!
+//! The probe sends `initialize`, then `notifications/initialized` and
+//! `tools/list`. A server is healthy when `initialize` succeeds; the result
+//! reports the negotiated protocol version, the server name, the advertised
+//! tools and the `initialize` round-trip time. Disabled servers are listed
+//! but not probed.
+//!
+//! All servers are probed concurrently. Results are shared across callers
+//! for `CACHE_TTL`; concurrent requests while a check runs wait for it
+//! instead of starting their own. Registry changes invalidate the cache.
+//!
+//! - `GET /api/mcp/health` — one `McpHealthResult` per registered server
+//!   (`?refresh=true` bypasses the cache)
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{ChildStdin, ChildStdout};
+use tokio::sync::Mutex;
+
+use super::config::McpServerConfig;
+use crate::state::AppState;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+const PROTOCOL_VERSION: &str = "2025-03-26";
+const INITIALIZE_ID: i64 = 1;
+const TOOLS_LIST_ID: i64 = 2;
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+struct CachedResults {
+    taken_at: Instant,
+    results: Vec<McpHealthResult>,
+}
+
+static CACHE: LazyLock<Mutex<Option<CachedResults>>> = LazyLock::new(|| Mutex::new(None));
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpHealthResult {
+    pub server_id: String,
+    pub name: String,
+    pub transport: String,
+    pub enabled: bool,
+    pub healthy: bool,
+    pub detail: String,
+    /// Protocol version the server answered `initialize` with.
+    pub protocol_version: Option<String>,
+    /// `serverInfo.name` from `initialize`.
+    pub server_name: Option<String>,
+    /// Tool names from `tools/list` (empty when the server lists none or fails it).
+    pub tools: Vec<String>,
+    /// `initialize` round trip, or time until the probe failed.
+    pub response_time_ms: u64,
+    pub checked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Handshake {
+    protocol_version: Option<String>,
+    server_name: Option<String>,
+    tools: Vec<String>,
+    response_time: Duration,
+}
+
+fn request(id: i64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn initialize_request() -> Value {
+    request(
+        INITIALIZE_ID,
+        "initialize",
+        json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": {},
+            "clientInfo": { "name": "ClaudeHydra", "version": env!("CARGO_PKG_VERSION") },
+        }),
+    )
+}
+
+fn initialized_notification() -> Value {
+    json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })
+}
+
+/// `(protocolVersion, serverInfo.name)` of an `initialize` response.
+fn parse_initialize(resp: &Value) -> Result<(Option<String>, Option<String>), String> {
+    if let Some(err) = resp.get("error") {
+        return Err(format!(
+            "initialize failed: {}",
+            err["message"].as_str().unwrap_or("unknown error")
+        ));
+    }
+    let result = resp
+        .get("result")
+        .ok_or_else(|| "initialize returned no result".to_string())?;
+    Ok((
+        result["protocolVersion"].as_str().map(str::to_string),
+        result["serverInfo"]["name"].as_str().map(str::to_string),
+    ))
+}
+
+fn parse_tools(resp: &Value) -> Vec<String> {
+    resp["result"]["tools"]
+        .as_array()
+        .map(|tools| {
+            tools
+                .iter()
+                .filter_map(|t| t["name"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The JSON-RPC response with `id` in an HTTP body — plain JSON or an SSE
+/// stream of `data:` lines.
+fn parse_http_body(body: &str, id: i64) -> Option<Value> {
+    let matches = |v: &Value| v["id"].as_i64() == Some(id);
+    if let Ok(value) = serde_json::from_str::<Value>(body.trim()) {
+        return match value {
+            Value::Array(batch) => batch.into_iter().find(matches),
+            single => matches(&single).then_some(single),
+        };
+    }
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .filter_map(|data| serde_json::from_str::<Value>(data.trim()).ok())
+        .find(matches)
+}
+
+async fn send_line(stdin: &mut ChildStdin, msg: &Value) -> Result<(), String> {
+    let line = format!("{}\n", msg);
+    stdin
+        .write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("write failed: {}", e))
+}
+
+/// Next message with `id`; servers may log or notify on stdout before answering.
+async fn read_response(
+    lines: &mut Lines<BufReader<ChildStdout>>,
+    id: i64,
+) -> Result<Value, String> {
+    loop {
+        let Some(line) = lines
+            .next_line()
+            .await
+            .map_err(|e| format!("read failed: {}", e))?
+        else {
+            return Err("server exited before answering".to_string());
+        };
+        if let Ok(msg) = serde_json::from_str::<Value>(&line)
+            && msg["id"].as_i64() == Some(id)
+        {
+            return Ok(msg);
+        }
+    }
+}
+
+async fn stdio_handshake(
+    command: &str,
+    args: &[String],
+    env: &HashMap<String, String>,
+) -> Result<Handshake, String> {
+    let mut child = tokio::process::Command::new(command)
+        .args(args)
+        .envs(env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("cannot start {}: {}", command, e))?;
+    let mut stdin = child.stdin.take().ok_or("no stdin")?;
+    let mut lines = BufReader::new(child.stdout.take().ok_or("no stdout")?).lines();
+
+    let started = Instant::now();
+    send_line(&mut stdin, &initialize_request()).await?;
+    let init = read_response(&mut lines, INITIALIZE_ID).await?;
+    let response_time = started.elapsed();
+    let (protocol_version, server_name) = parse_initialize(&init)?;
+
+    send_line(&mut stdin, &initialized_notification()).await?;
+    send_line(&mut stdin, &request(TOOLS_LIST_ID, "tools/list", json!({}))).await?;
+    let tools = read_response(&mut lines, TOOLS_LIST_ID)
+        .await
+        .map(|resp| parse_tools(&resp))
+        .unwrap_or_default();
+
+    let _ = child.kill().await;
+    Ok(Handshake {
+        protocol_version,
+        server_name,
+        tools,
+        response_time,
+    })
+}
+
+async fn http_handshake(state: &AppState, server: &McpServerConfig) -> Result<Handshake, String> {
+    let url = server
+        .url
+        .as_deref()
+        .ok_or_else(|| "no url configured".to_string())?;
+    let post = |body: &Value, session: Option<&str>| {
+        let mut req = state
+            .http_client
+            .post(url)
+            .header(
+                reqwest::header::ACCEPT,
+                "application/json, text/event-stream",
+            )
+            .json(body);
+        if let Some(token) = server.auth_token.as_deref() {
+            req = req.bearer_auth(token);
+        }
+        if let Some(session) = session {
+            req = req.header("Mcp-Session-Id", session);
+        }
+        req.send()
+    };
+
+    let started = Instant::now();
+    let resp = post(&initialize_request(), None)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("initialize returned HTTP {}", status.as_u16()));
+    }
+    let session = resp
+        .headers()
+        .get("mcp-session-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    let response_time = started.elapsed();
+    let init = parse_http_body(&body, INITIALIZE_ID)
+        .ok_or_else(|| "no initialize response in reply".to_string())?;
+    let (protocol_version, server_name) = parse_initialize(&init)?;
+
+    let _ = post(&initialized_notification(), session.as_deref()).await;
+    let tools = match post(
+        &request(TOOLS_LIST_ID, "tools/list", json!({})),
+        session.as_deref(),
+    )
+    .await
+    {
+        Ok(resp) => resp
+            .text()
+            .await
+            .ok()
+            .and_then(|body| parse_http_body(&body, TOOLS_LIST_ID))
+            .map(|resp| parse_tools(&resp))
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    Ok(Handshake {
+        protocol_version,
+        server_name,
+        tools,
+        response_time,
+    })
+}
+
+async fn check(state: &AppState, server: &McpServerConfig) -> McpHealthResult {
+    let started = Instant::now();
+    let outcome = if !server.enabled {
+        Err("disabled".to_string())
+    } else {
+        let handshake = async {
+            match server.transport.as_str() {
+                "stdio" => {
+                    let Some(command) = server.command.as_deref() else {
+                        return Err("no command configured".to_string());
+                    };
+                    let args: Vec<String> = serde_json::from_str(&server.args).unwrap_or_default();
+                    let env: HashMap<String, String> =
+                        serde_json::from_str(&server.env_vars).unwrap_or_default();
+                    stdio_handshake(command, &args, &env).await
+                }
+                _ => http_handshake(state, server).await,
+            }
+        };
+        tokio::time::timeout(PROBE_TIMEOUT, handshake)
+            .await
+            .unwrap_or_else(|_| Err(format!("no answer within {}s", PROBE_TIMEOUT.as_secs())))
+    };
+    let (healthy, detail, handshake) = match outcome {
+        Ok(h) => (true, "ok".to_string(), h),
+        Err(e) => (
+            false,
+            e,
+            Handshake {
+                response_time: started.elapsed(),
+                ..Default::default()
+            },
+        ),
+    };
+    McpHealthResult {
+        server_id: server.id.clone(),
+        name: server.name.clone(),
+        transport: server.transport.clone(),
+        enabled: server.enabled,
+        healthy,
+        detail,
+        protocol_version: handshake.protocol_version,
+        server_name: handshake.server_name,
+        tools: handshake.tools,
+        response_time_ms: handshake.response_time.as_millis() as u64,
+        checked_at: Utc::now(),
+    }
+}
+
+/// Probe every registered server concurrently.
+pub async fn check_all(state: &AppState) -> Result<Vec<McpHealthResult>, sqlx::Error> {
+    let servers = super::config::list_all(&state.db).await?;
+    Ok(futures_util::future::join_all(servers.iter().map(|s| check(state, s))).await)
+}
+
+/// `check_all`, served from the shared cache while it is younger than
+/// `CACHE_TTL` (unless `refresh`). Returns the results and their age.
+pub async fn cached_check_all(
+    state: &AppState,
+    refresh: bool,
+) -> Result<(Vec<McpHealthResult>, Duration), sqlx::Error> {
+    // Held across the check so concurrent callers share one run.
+    let mut cache = CACHE.lock().await;
+    if !refresh
+        && let Some(cached) = cache.as_ref()
+        && cached.taken_at.elapsed() < CACHE_TTL
+    {
+        return Ok((cached.results.clone(), cached.taken_at.elapsed()));
+    }
+    let results = check_all(state).await?;
+    *cache = Some(CachedResults {
+        taken_at: Instant::now(),
+        results: results.clone(),
+    });
+    Ok((results, Duration::ZERO))
+}
+
+/// Drop cached results, e.g. after servers were added, removed or toggled.
+pub async fn invalidate() {
+    *CACHE.lock().await = None;
+}
+
+// ── HTTP Handlers ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HealthQuery {
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+/// GET /api/mcp/health — one result per registered server
+pub async fn health_handler(
+    State(state): State<AppState>,
+    Query(query): Query<HealthQuery>,
+) -> Result<Json<Value>, StatusCode> {
+    let (results, age) = cached_check_all(&state, query.refresh).await.map_err(|e| {
+        tracing::error!("mcp: health: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let healthy = results.iter().filter(|r| r.healthy).count();
+    Ok(Json(json!({
+        "healthy": healthy,
+        "total": results.len(),
+        "cache_age_ms": age.as_millis() as u64,
+        "servers": results,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn initialize_results_are_parsed() {
+        let ok = json!({
+            "jsonrpc": "2.0", "id": 1,
+            "result": {
+                "protocolVersion": "2024-11-05",
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "serena", "version": "1.0" }
+            }
+        });
+        assert_eq!(
+            parse_initialize(&ok),
+            Ok((Some("2024-11-05".to_string()), Some("serena".to_string())))
+        );
+        let err = json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "bad version" } });
+        assert_eq!(
+            parse_initialize(&err),
+            Err("initialize failed: bad version".to_string())
+        );
+        assert!(parse_initialize(&json!({ "id": 1 })).is_err());
+    }
+
+    #[test]
+    fn tools_are_listed_by_name() {
+        let resp = json!({ "id": 2, "result": { "tools": [{ "name": "read_file" }, { "name": "grep" }, {}] } });
+        assert_eq!(parse_tools(&resp), vec!["read_file", "grep"]);
+        assert!(parse_tools(&json!({ "id": 2, "error": {} })).is_empty());
+    }
+
+    #[test]
+    fn http_bodies_may_be_json_or_sse() {
+        let json_body = r#"{"jsonrpc":"2.0","id":1,"result":{}}"#;
+        assert!(parse_http_body(json_body, 1).is_some());
+        assert!(parse_http_body(json_body, 2).is_none());
+        let sse = "event: message\ndata: {\"jsonrpc\":\"2.0\",\"method\":\"notifications/message\"}\n\n\
+                   event: message\ndata: {\"jsonrpc\":\"2.0\",\"id\":2,\"result\":{\"tools\":[]}}\n\n";
+        assert_eq!(parse_http_body(sse, 2).unwrap()["id"], 2);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stdio_handshake_talks_json_rpc() {
+        // A fake server: ignore input, print a log line, then both responses.
+        let script = r#"read l; echo 'starting'; echo '{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2025-03-26","serverInfo":{"name":"fake"}}}'; read l; read l; echo '{"jsonrpc":"2.0","id":2,"result":{"tools":[{"name":"ping"}]}}'"#;
+        let args = vec!["-c".to_string(), script.to_string()];
+        let handshake = stdio_handshake("sh", &args, &HashMap::new()).await.unwrap();
+        assert_eq!(handshake.protocol_version.as_deref(), Some("2025-03-26"));
+        assert_eq!(handshake.server_name.as_deref(), Some("fake"));
+        assert_eq!(handshake.tools, vec!["ping"]);
+    }
+}
@@ -3,6 +3,8 @@
 //!
 //! ## Architecture (ClaudeHydra)
 //!
+//! - **bridge**: ClaudeHydra-only — `POST /api/mcp/call` invokes a server's tool
+//!   directly (frontend-initiated, no model turn).
 //! - **client**: Re-exports `jaskier_core::mcp::client::*` — shared `McpClientManager` with
 //!   `call_tool(prefixed_name, args)` API used by `tools/mod.rs` and `handlers/streaming.rs`.
 //! - **config**: Shared types + DB functions from `jaskier_core::mcp::config`, with local
@@ -10,7 +12,16 @@
 //! - **server**: Re-exports shared `mcp_handler` from `jaskier_core::mcp::server`.
 //!   ClaudeHydra overrides `mcp_tool_definitions()` and `mcp_execute_tool()` via
 //!   `HasMcpServerState` impl in `state.rs` to use its `ToolExecutor` pattern.
+//! - **registry**: ClaudeHydra-only — servers declared in `.hydra/mcp.toml` /
+//!   `.claude/settings.json`, imported into `ch_mcp_servers`, plus enable/disable.
+//! - **health**: ClaudeHydra-only — `check_all` probes every registered server.
+//! - **supervisor**: ClaudeHydra-only — start/stop/restart stdio servers as managed
+//!   child processes with auto-restart and backoff.
 
+pub mod bridge;
 pub mod client;
 pub mod config;
+pub mod health;
+pub mod registry;
 pub mod server;
+pub mod supervisor;
@@ -0,0 +1,345 @@
+//! MCP registry — MCP servers declared in config files, imported into
+//! `ch_mcp_servers` so they are managed like servers added through the API.
+//!
+//! Sources, in order (the first declaration of a name wins):
+//! - `<working dir>/.hydra/mcp.toml`       — `[servers.<name>]` tables
+//! - `<working dir>/.claude/settings.json` — `mcpServers` object
+//! - `<working dir>/.mcp.json`             — `mcpServers` object
+//! - `~/.claude/settings.json`             — `mcpServers` object
+//!
+//! Each entry has either `command` (+ `args`, `env`) for stdio or `url` for
+//! HTTP, plus optional `enabled` (TOML) / `disabled` (JSON). Importing skips
+//! names already registered and applies the same validation as
+//! `POST /api/mcp/servers`.
+//!
+//! - `GET  /api/mcp/registry`              — declared servers and whether they are registered
+//! - `POST /api/mcp/registry/import`       — register declared servers not yet in the DB
+//! - `PUT  /api/mcp/registry/{id}/enabled` — `{ enabled }`: enable (and connect) or disable (and disconnect)
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path as FsPath, PathBuf};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::config::{
+    CreateMcpServerRequest, UpdateMcpServerRequest, validate_mcp_url, validate_stdio_config,
+};
+use crate::state::AppState;
+
+#[derive(Debug, Default, Deserialize)]
+struct RawServer {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    transport: Option<String>,
+    command: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    url: Option<String>,
+    enabled: Option<bool>,
+    disabled: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClaudeSettings {
+    #[serde(default, rename = "mcpServers")]
+    mcp_servers: BTreeMap<String, RawServer>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct HydraMcpToml {
+    #[serde(default)]
+    servers: BTreeMap<String, RawServer>,
+}
+
+/// A server declared in one of the registry sources.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RegistryEntry {
+    pub name: String,
+    /// `stdio` or `http`
+    pub transport: &'static str,
+    pub command: Option<String>,
+    pub args: Vec<String>,
+    #[serde(skip)]
+    pub env: BTreeMap<String, String>,
+    pub url: Option<String>,
+    pub enabled: bool,
+    /// File the entry was read from.
+    pub source: String,
+}
+
+fn entry(name: String, raw: RawServer, source: &FsPath) -> Option<RegistryEntry> {
+    let declared = raw.transport.or(raw.kind).unwrap_or_default();
+    let transport = match (&raw.command, &raw.url) {
+        (Some(_), _) if declared != "http" && declared != "sse" => "stdio",
+        (_, Some(_)) => "http",
+        _ => return None,
+    };
+    Some(RegistryEntry {
+        name,
+        transport,
+        command: raw.command.filter(|_| transport == "stdio"),
+        args: raw.args,
+        env: raw.env,
+        url: raw.url.filter(|_| transport == "http"),
+        enabled: raw.enabled.unwrap_or(true) && !raw.disabled.unwrap_or(false),
+        source: source.to_string_lossy().into_owned(),
+    })
+}
+
+/// Entries of a `.claude/settings.json`-style file.
+fn parse_settings_json(text: &str, source: &FsPath) -> Result<Vec<RegistryEntry>, String> {
+    let settings: ClaudeSettings = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    Ok(settings
+        .mcp_servers
+        .into_iter()
+        .filter_map(|(name, raw)| entry(name, raw, source))
+        .collect())
+}
+
+/// Entries of a `.hydra/mcp.toml` file.
+fn parse_mcp_toml(text: &str, source: &FsPath) -> Result<Vec<RegistryEntry>, String> {
+    let config: HydraMcpToml = toml::from_str(text).map_err(|e| e.to_string())?;
+    Ok(config
+        .servers
+        .into_iter()
+        .filter_map(|(name, raw)| entry(name, raw, source))
+        .collect())
+}
+
+fn sources(workspace: &str) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if !workspace.is_empty() {
+        let root = FsPath::new(workspace);
+        files.push(root.join(".hydra").join("mcp.toml"));
+        files.push(root.join(".claude").join("settings.json"));
+        files.push(root.join(".mcp.json"));
+    }
+    if let Some(home) = dirs::home_dir() {
+        files.push(home.join(".claude").join("settings.json"));
+    }
+    files
+}
+
+/// All declared servers, deduplicated by name in source order.
+pub async fn discover(workspace: &str) -> Vec<RegistryEntry> {
+    let mut entries: Vec<RegistryEntry> = Vec::new();
+    let mut seen = HashSet::new();
+    for path in sources(workspace) {
+        let Ok(text) = tokio::fs::read_to_string(&path).await else {
+            continue;
+        };
+        let parsed = if path.extension().is_some_and(|e| e == "toml") {
+            parse_mcp_toml(&text, &path)
+        } else {
+            parse_settings_json(&text, &path)
+        };
+        match parsed {
+            Ok(found) => entries.extend(found.into_iter().filter(|e| seen.insert(e.name.clone()))),
+            Err(e) => tracing::warn!("mcp registry: cannot parse {}: {}", path.display(), e),
+        }
+    }
+    entries
+}
+
+async fn global_workspace(db: &sqlx::PgPool) -> String {
+    sqlx::query_scalar("SELECT COALESCE(working_directory, '') FROM ch_settings WHERE id = 1")
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+}
+
+/// Turn a declared server into a create request, validated like
+/// `create_server_handler` (stdio allowlist, SSRF).
+fn create_request(entry: &RegistryEntry, is_prod: bool) -> Result<CreateMcpServerRequest, String> {
+    let req: CreateMcpServerRequest = serde_json::from_value(json!({
+        "name": entry.name,
+        "transport": entry.transport,
+        "command": entry.command,
+        "args": entry.args,
+        "env_vars": entry.env,
+        "url": entry.url,
+    }))
+    .map_err(|e| e.to_string())?;
+    if let Some(ref cmd) = req.command {
+        validate_stdio_config(cmd, req.env_vars.as_ref()).map_err(|e| e.to_string())?;
+    }
+    if let Some(ref url) = req.url {
+        validate_mcp_url(url, is_prod).map_err(|e| e.to_string())?;
+    }
+    Ok(req)
+}
+
+// ── HTTP Handlers ──────────────────────────────────────────────────────────
+
+/// GET /api/mcp/registry — declared servers and whether they are registered
+pub async fn list_registry_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let registered = super::config::list_all(&state.db).await.map_err(|e| {
+        tracing::error!("mcp registry: list: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let entries: Vec<Value> = discover(&global_workspace(&state.db).await)
+        .await
+        .into_iter()
+        .map(|e| {
+            let server = registered.iter().find(|s| s.name == e.name);
+            let mut value = json!(e);
+            value["env_keys"] = json!(e.env.keys().collect::<Vec<_>>());
+            value["server_id"] = json!(server.map(|s| &s.id));
+            value
+        })
+        .collect();
+    Ok(Json(json!(entries)))
+}
+
+/// POST /api/mcp/registry/import — register declared servers not yet in the DB
+pub async fn import_registry_handler(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, StatusCode> {
+    let registered: HashSet<String> = super::config::list_all(&state.db)
+        .await
+        .map_err(|e| {
+            tracing::error!("mcp registry: import: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    let is_prod = state.auth_secret.is_some();
+
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    for entry in discover(&global_workspace(&state.db).await).await {
+        if registered.contains(&entry.name) {
+            skipped.push(json!({ "name": entry.name, "reason": "already registered" }));
+            continue;
+        }
+        let req = match create_request(&entry, is_prod) {
+            Ok(req) => req,
+            Err(reason) => {
+                tracing::warn!("mcp registry: {} rejected: {}", entry.name, reason);
+                skipped.push(json!({ "name": entry.name, "reason": reason }));
+                continue;
+            }
+        };
+        let server = super::config::insert(&state.db, &req).await.map_err(|e| {
+            tracing::error!("mcp registry: import {}: {}", entry.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        if !entry.enabled
+            && let Ok(update) =
+                serde_json::from_value::<UpdateMcpServerRequest>(json!({ "enabled": false }))
+        {
+            let _ = super::config::update(&state.db, &server.id, &update).await;
+        }
+        imported.push(json!({ "id": server.id, "name": entry.name, "source": entry.source }));
+    }
+    if !imported.is_empty() {
+        super::health::invalidate().await;
+    }
+    Ok(Json(json!({ "imported": imported, "skipped": skipped })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetEnabledRequest {
+    pub enabled: bool,
+}
+
+/// PUT /api/mcp/registry/{id}/enabled — enable (and connect) or disable (and disconnect)
+pub async fn set_enabled_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SetEnabledRequest>,
+) -> Result<Json<Value>, StatusCode> {
+    let update: UpdateMcpServerRequest = serde_json::from_value(json!({ "enabled": req.enabled }))
+        .map_err(|e| {
+            tracing::error!("mcp registry: set_enabled: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let server = super::config::update(&state.db, &id, &update)
+        .await
+        .map_err(|e| {
+            tracing::error!("mcp registry: set_enabled: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    super::health::invalidate().await;
+    let connected = if server.enabled {
+        match state.mcp_client.connect_server(&server).await {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::warn!("mcp registry: connect {}: {}", server.name, e);
+                false
+            }
+        }
+    } else {
+        state.mcp_client.disconnect_server(&id).await;
+        false
+    };
+    Ok(Json(json!({
+        "id": server.id,
+        "name": server.name,
+        "enabled": server.enabled,
+        "connected": connected,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_json_declares_stdio_and_http_servers() {
+        let text = r#"{
+            "permissions": {},
+            "mcpServers": {
+                "serena": { "command": "uvx", "args": ["serena"], "env": { "LOG": "1" } },
+                "docs": { "type": "http", "url": "https://docs.example.com/mcp" },
+                "old": { "command": "node", "disabled": true },
+                "broken": { "args": ["x"] }
+            }
+        }"#;
+        let entries = parse_settings_json(text, FsPath::new("settings.json")).unwrap();
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["docs", "old", "serena"]);
+        assert_eq!(entries[0].transport, "http");
+        assert!(!entries[1].enabled);
+        assert_eq!(entries[2].transport, "stdio");
+        assert_eq!(entries[2].args, vec!["serena"]);
+        assert_eq!(entries[2].env.get("LOG").map(String::as_str), Some("1"));
+        assert!(
+            parse_settings_json("{}", FsPath::new("x"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn hydra_toml_declares_servers() {
+        let text = r#"
+            [servers.playwright]
+            command = "npx"
+            args = ["@playwright/mcp"]
+            enabled = false
+
+            [servers.remote]
+            url = "https://mcp.example.com"
+        "#;
+        let entries = parse_mcp_toml(text, FsPath::new("mcp.toml")).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "playwright");
+        assert!(!entries[0].enabled);
+        assert_eq!(entries[1].transport, "http");
+        assert!(parse_mcp_toml("servers = 1", FsPath::new("x")).is_err());
+    }
+}
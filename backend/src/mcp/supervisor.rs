@@ -0,0 +1,324 @@
+//! MCP process supervision — runs registered stdio MCP servers as managed
+//! child processes, independent of the MCP client connections.
+//!
+//! A supervised server is spawned with its configured command, args and env.
+//! Its stdin is held open (many stdio servers exit on EOF) and its output is
+//! discarded. When the process exits without being stopped it is restarted
+//! after a backoff that doubles from `INITIAL_BACKOFF` up to `MAX_BACKOFF`;
+//! a run lasting `STABLE_AFTER` resets the backoff. Supervised processes are
+//! killed on shutdown.
+//!
+//! - `GET  /api/mcp/processes`              — supervised servers (status, PID, restarts, last exit)
+//! - `POST /api/mcp/processes/{id}/start`   — start supervising a registered stdio server
+//! - `POST /api/mcp/processes/{id}/stop`    — stop it (no restart)
+//! - `POST /api/mcp/processes/{id}/restart` — stop, then start with a fresh backoff
+
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::state::AppState;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+const STABLE_AFTER: Duration = Duration::from_secs(60);
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+struct Spec {
+    command: String,
+    args: Vec<String>,
+    env: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct McpProcess {
+    pub server_id: String,
+    pub name: String,
+    /// `running`, `backoff` (waiting to restart) or `stopped`
+    pub status: &'static str,
+    pub pid: Option<u32>,
+    pub restarts: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Exit status or spawn error of the last run.
+    pub last_exit: Option<String>,
+}
+
+struct Supervised {
+    info: McpProcess,
+    stop: Arc<Notify>,
+    task: Option<JoinHandle<()>>,
+}
+
+static PROCESSES: LazyLock<Mutex<HashMap<String, Supervised>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn next_backoff(current: Duration) -> Duration {
+    (current * 2).min(MAX_BACKOFF)
+}
+
+fn update(id: &str, f: impl FnOnce(&mut McpProcess)) {
+    if let Some(entry) = PROCESSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(id)
+    {
+        f(&mut entry.info);
+    }
+}
+
+async fn supervise(id: String, spec: Spec, stop: Arc<Notify>) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let spawned = tokio::process::Command::new(&spec.command)
+            .args(&spec.args)
+            .envs(&spec.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                let started = Instant::now();
+                // Held until the child exits so it never sees EOF.
+                let _stdin = child.stdin.take();
+                update(&id, |p| {
+                    p.status = "running";
+                    p.pid = child.id();
+                    p.started_at = Some(Utc::now());
+                });
+                tokio::select! {
+                    status = child.wait() => {
+                        let exit = match status {
+                            Ok(status) => status.to_string(),
+                            Err(e) => e.to_string(),
+                        };
+                        tracing::warn!("mcp supervisor: {} exited ({})", id, exit);
+                        update(&id, |p| p.last_exit = Some(exit));
+                        if started.elapsed() >= STABLE_AFTER {
+                            backoff = INITIAL_BACKOFF;
+                        }
+                    }
+                    _ = stop.notified() => {
+                        let _ = child.kill().await;
+                        update(&id, |p| {
+                            p.status = "stopped";
+                            p.pid = None;
+                            p.last_exit = Some("stopped".to_string());
+                        });
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::warn!("mcp supervisor: cannot start {}: {}", id, e);
+                update(&id, |p| p.last_exit = Some(format!("spawn failed: {}", e)));
+            }
+        }
+
+        update(&id, |p| {
+            p.status = "backoff";
+            p.pid = None;
+        });
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = stop.notified() => {
+                update(&id, |p| p.status = "stopped");
+                return;
+            }
+        }
+        update(&id, |p| p.restarts += 1);
+        backoff = next_backoff(backoff);
+    }
+}
+
+/// Start supervising `spec` under `id`; `false` when it already runs.
+fn start_process(id: &str, name: &str, spec: Spec) -> bool {
+    let mut processes = PROCESSES.lock().unwrap_or_else(|e| e.into_inner());
+    if processes
+        .get(id)
+        .is_some_and(|p| p.task.as_ref().is_some_and(|t| !t.is_finished()))
+    {
+        return false;
+    }
+    let stop = Arc::new(Notify::new());
+    let restarts = processes.get(id).map_or(0, |p| p.info.restarts);
+    let info = McpProcess {
+        server_id: id.to_string(),
+        name: name.to_string(),
+        status: "running",
+        pid: None,
+        restarts,
+        started_at: None,
+        last_exit: None,
+    };
+    let task = tokio::spawn(supervise(id.to_string(), spec, stop.clone()));
+    processes.insert(
+        id.to_string(),
+        Supervised {
+            info,
+            stop,
+            task: Some(task),
+        },
+    );
+    true
+}
+
+/// Stop supervising `id` and wait for the child to be killed; `false` when
+/// it was not running.
+pub async fn stop_process(id: &str) -> bool {
+    let task = {
+        let mut processes = PROCESSES.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(entry) = processes.get_mut(id) else {
+            return false;
+        };
+        entry.stop.notify_one();
+        entry.task.take()
+    };
+    match task {
+        Some(task) => {
+            let _ = tokio::time::timeout(STOP_TIMEOUT, task).await;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stop every supervised process (graceful shutdown). Returns how many were stopped.
+pub async fn stop_all() -> usize {
+    let ids: Vec<String> = PROCESSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys()
+        .cloned()
+        .collect();
+    let mut stopped = 0;
+    for id in ids {
+        if stop_process(&id).await {
+            stopped += 1;
+        }
+    }
+    stopped
+}
+
+pub(crate) fn processes_snapshot() -> Vec<McpProcess> {
+    let mut list: Vec<McpProcess> = PROCESSES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .map(|p| p.info.clone())
+        .collect();
+    list.sort_by(|a, b| a.name.cmp(&b.name));
+    list
+}
+
+// ── HTTP Handlers ──────────────────────────────────────────────────────────
+
+/// GET /api/mcp/processes — supervised servers
+pub async fn list_processes_handler() -> Json<Value> {
+    Json(json!(processes_snapshot()))
+}
+
+/// POST /api/mcp/processes/{id}/start — start supervising a registered stdio server
+pub async fn start_process_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let server = super::config::get_by_id(&state.db, &id)
+        .await
+        .map_err(|e| {
+            tracing::error!("mcp supervisor: start: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if !server.enabled {
+        return Err(StatusCode::CONFLICT);
+    }
+    let Some(command) = server
+        .command
+        .clone()
+        .filter(|_| server.transport == "stdio")
+    else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    let spec = Spec {
+        command,
+        args: serde_json::from_str(&server.args).unwrap_or_default(),
+        env: serde_json::from_str(&server.env_vars).unwrap_or_default(),
+    };
+    if !start_process(&id, &server.name, spec) {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(Json(
+        json!({ "server_id": id, "name": server.name, "status": "running" }),
+    ))
+}
+
+/// POST /api/mcp/processes/{id}/stop — stop a supervised server
+pub async fn stop_process_handler(Path(id): Path<String>) -> StatusCode {
+    if stop_process(&id).await {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::NOT_FOUND
+    }
+}
+
+/// POST /api/mcp/processes/{id}/restart — stop (if running), then start again
+pub async fn restart_process_handler(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    stop_process(&id).await;
+    start_process_handler(State(state), Path(id)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(next_backoff(INITIAL_BACKOFF), Duration::from_secs(2));
+        assert_eq!(next_backoff(Duration::from_secs(40)), MAX_BACKOFF);
+        assert_eq!(next_backoff(MAX_BACKOFF), MAX_BACKOFF);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn supervised_processes_start_and_stop() {
+        let spec = Spec {
+            command: "sleep".to_string(),
+            args: vec!["30".to_string()],
+            env: HashMap::new(),
+        };
+        assert!(start_process("test-sleep", "sleep", spec.clone()));
+        assert!(!start_process("test-sleep", "sleep", spec));
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let running = processes_snapshot()
+            .into_iter()
+            .find(|p| p.server_id == "test-sleep")
+            .unwrap();
+        assert_eq!(running.status, "running");
+        assert!(running.pid.is_some());
+
+        assert!(stop_process("test-sleep").await);
+        let stopped = processes_snapshot()
+            .into_iter()
+            .find(|p| p.server_id == "test-sleep")
+            .unwrap();
+        assert_eq!(stopped.status, "stopped");
+        assert_eq!(stopped.pid, None);
+        assert!(!stop_process("test-sleep").await);
+    }
+}
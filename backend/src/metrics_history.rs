@@ -0,0 +1,319 @@
+//! Historical metrics for dashboard charts.
+//!
+//! Every `CH_METRICS_HISTORY_SECS` seconds (`metrics.history_secs` in the
+//! config file, default 60, `0` disables; read at startup) a sampler
+//! records one row per metric into `ch_metric_samples`:
+//!
+//! - `system.cpu_percent`, `system.memory_used_mb`, `system.memory_percent`
+//! - `queue.background_queued`, `queue.background_running`,
+//!   `queue.ollama_running`, `queue.ollama_queued`, `queue.a2a_in_flight`
+//! - `tabs.websocket`
+//! - `provider.<name>.requests_24h`, `provider.<name>.failures_24h`
+//!
+//! Series are downsampled at query time into at most `points` buckets
+//! (avg / min / max per bucket). Old samples are removed by the
+//! `metric_samples` GC policy (30 days once enabled; see `gc.rs`).
+//!
+//! - `GET /api/history?metric=system.cpu_percent,queue.ollama_queued&range=24h&points=120`
+//!   — `range` is `<n>m`, `<n>h` or `<n>d` (up to 90 days, default `24h`)
+//! - `GET /api/history/metrics` — recorded metric names with their last sample time
+
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error};
+use crate::metrics_snapshot::MetricsSnapshot;
+use crate::ollama_queue::QueueSnapshot;
+use crate::state::AppState;
+
+pub const JOB_METRICS_HISTORY: &str = "metrics_history";
+const MAX_RANGE_SECS: i64 = 90 * 24 * 60 * 60;
+const DEFAULT_POINTS: i64 = 120;
+const MAX_POINTS: i64 = 1_000;
+const MAX_METRICS_PER_QUERY: usize = 16;
+
+/// Sampling interval; `None` when disabled.
+fn interval() -> Option<Duration> {
+    let secs = crate::hydra_config::number(
+        "CH_METRICS_HISTORY_SECS",
+        crate::hydra_config::current().metrics.history_secs,
+    )
+    .unwrap_or(60);
+    (secs > 0).then(|| Duration::from_secs(secs.max(5)))
+}
+
+/// Metric name/value pairs of one sampling tick.
+fn samples_of(
+    cpu_percent: f64,
+    memory_used_mb: f64,
+    memory_total_mb: f64,
+    metrics: &MetricsSnapshot,
+    ollama: &QueueSnapshot,
+) -> Vec<(String, f64)> {
+    let mut samples = vec![
+        ("system.cpu_percent".to_string(), cpu_percent),
+        ("system.memory_used_mb".to_string(), memory_used_mb),
+        (
+            "queue.background_queued".to_string(),
+            metrics.queue.background_queued as f64,
+        ),
+        (
+            "queue.background_running".to_string(),
+            metrics.queue.background_running as f64,
+        ),
+        (
+            "queue.ollama_running".to_string(),
+            ollama.running.len() as f64,
+        ),
+        (
+            "queue.ollama_queued".to_string(),
+            ollama.queued.len() as f64,
+        ),
+        (
+            "queue.a2a_in_flight".to_string(),
+            metrics.queue.a2a_in_flight as f64,
+        ),
+        ("tabs.websocket".to_string(), metrics.active_sessions as f64),
+    ];
+    if memory_total_mb > 0.0 {
+        samples.push((
+            "system.memory_percent".to_string(),
+            memory_used_mb / memory_total_mb * 100.0,
+        ));
+    }
+    for provider in &metrics.providers {
+        samples.push((
+            format!("provider.{}.requests_24h", provider.provider),
+            provider.requests_24h as f64,
+        ));
+        samples.push((
+            format!("provider.{}.failures_24h", provider.provider),
+            provider.failures_24h as f64,
+        ));
+    }
+    samples
+}
+
+async fn record_samples(state: &AppState) -> Result<usize, String> {
+    let metrics = crate::metrics_snapshot::collect(state).await?;
+    let (cpu, used, total) = {
+        let snapshot = state.system_monitor.read().await;
+        (
+            snapshot.cpu_usage_percent as f64,
+            snapshot.memory_used_mb,
+            snapshot.memory_total_mb,
+        )
+    };
+    let samples = samples_of(cpu, used, total, &metrics, &crate::ollama_queue::snapshot());
+    let (names, values): (Vec<String>, Vec<f64>) = samples.into_iter().unzip();
+    sqlx::query(
+        "INSERT INTO ch_metric_samples (metric, value) \
+         SELECT * FROM UNNEST($1::TEXT[], $2::FLOAT8[])",
+    )
+    .bind(&names)
+    .bind(&values)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store metric samples: {}", e))?;
+    Ok(names.len())
+}
+
+/// Spawn the sampler (no-op when `CH_METRICS_HISTORY_SECS=0`).
+pub fn spawn(state: AppState) {
+    let Some(interval) = interval() else {
+        return;
+    };
+    tracing::info!("metrics_history: sampling every {}s", interval.as_secs());
+    state.job_schedule.start(JOB_METRICS_HISTORY, interval);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_METRICS_HISTORY);
+            if let Err(e) = record_samples(&state).await {
+                tracing::warn!("metrics_history: {}", e);
+            }
+        }
+    });
+}
+
+/// `range` as seconds: `<n>m`, `<n>h` or `<n>d`, at most `MAX_RANGE_SECS`.
+pub(crate) fn parse_range(raw: &str) -> Option<i64> {
+    let raw = raw.trim();
+    let unit = raw.chars().last()?;
+    let amount: i64 = raw[..raw.len() - unit.len_utf8()].parse().ok()?;
+    let secs = match unit {
+        'm' => amount.checked_mul(60)?,
+        'h' => amount.checked_mul(60 * 60)?,
+        'd' => amount.checked_mul(24 * 60 * 60)?,
+        _ => return None,
+    };
+    (secs > 0 && secs <= MAX_RANGE_SECS).then_some(secs)
+}
+
+/// Bucket width for `points` buckets over `range_secs` — never finer than
+/// the sampling interval, so buckets are not mostly empty.
+fn step_secs(range_secs: i64, points: i64, sample_secs: i64) -> i64 {
+    let points = points.clamp(1, MAX_POINTS);
+    ((range_secs + points - 1) / points).max(sample_secs).max(1)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub metric: String,
+    #[serde(default)]
+    pub range: Option<String>,
+    #[serde(default)]
+    pub points: Option<i64>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SeriesPoint {
+    #[serde(skip)]
+    pub metric: String,
+    pub t: DateTime<Utc>,
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+/// `GET /api/history`
+pub async fn get_history(
+    State(state): State<AppState>,
+    Query(q): Query<HistoryQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut metrics: Vec<String> = q
+        .metric
+        .split(',')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    metrics.dedup();
+    if metrics.is_empty() || metrics.len() > MAX_METRICS_PER_QUERY {
+        return Err(bad_request(
+            "metric must list 1-16 comma-separated metric names",
+        ));
+    }
+    let range = q.range.as_deref().unwrap_or("24h");
+    let range_secs = parse_range(range)
+        .ok_or_else(|| bad_request("range must be like 30m, 6h or 7d (at most 90d)"))?;
+    let sample_secs = interval().map(|i| i.as_secs() as i64).unwrap_or(1);
+    let step = step_secs(range_secs, q.points.unwrap_or(DEFAULT_POINTS), sample_secs);
+
+    let rows = sqlx::query_as::<_, SeriesPoint>(
+        "SELECT metric, \
+             TO_TIMESTAMP(FLOOR(EXTRACT(EPOCH FROM created_at) / $3) * $3) AS t, \
+             AVG(value) AS avg, MIN(value) AS min, MAX(value) AS max \
+         FROM ch_metric_samples \
+         WHERE metric = ANY($1) AND created_at >= NOW() - make_interval(secs => $2) \
+         GROUP BY metric, t ORDER BY metric, t",
+    )
+    .bind(&metrics)
+    .bind(range_secs as f64)
+    .bind(step as f64)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("metrics_history"))?;
+
+    let series: Vec<Value> = metrics
+        .iter()
+        .map(|metric| {
+            let points: Vec<&SeriesPoint> = rows.iter().filter(|p| &p.metric == metric).collect();
+            json!({ "metric": metric, "points": points })
+        })
+        .collect();
+    Ok(Json(json!({
+        "range": range,
+        "step_secs": step,
+        "series": series,
+    })))
+}
+
+/// `GET /api/history/metrics`
+pub async fn list_metrics(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let rows: Vec<(String, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT metric, MAX(created_at) FROM ch_metric_samples GROUP BY metric ORDER BY metric",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("metrics_history"))?;
+    Ok(Json(json!({
+        "sampling_secs": interval().map(|i| i.as_secs()),
+        "metrics": rows
+            .into_iter()
+            .map(|(metric, last)| json!({ "metric": metric, "last_sample_at": last }))
+            .collect::<Vec<_>>(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics_snapshot::{ProviderCounters, QueueStats};
+
+    #[test]
+    fn ranges_parse_with_units_and_limit() {
+        assert_eq!(parse_range("30m"), Some(1_800));
+        assert_eq!(parse_range("24h"), Some(86_400));
+        assert_eq!(parse_range("7d"), Some(604_800));
+        assert_eq!(parse_range("90d"), Some(MAX_RANGE_SECS));
+        assert_eq!(parse_range("91d"), None);
+        assert_eq!(parse_range("0h"), None);
+        assert_eq!(parse_range("1w"), None);
+        assert_eq!(parse_range("h"), None);
+    }
+
+    #[test]
+    fn step_covers_range_but_not_below_sampling() {
+        assert_eq!(step_secs(86_400, 120, 60), 720);
+        assert_eq!(step_secs(3_600, 1_000, 60), 60);
+        assert_eq!(step_secs(100, 0, 1), 100);
+        assert_eq!(step_secs(86_400, 1_000_000, 5), 87);
+    }
+
+    #[test]
+    fn samples_include_providers_and_memory_share() {
+        let metrics = MetricsSnapshot {
+            generated_at: Utc::now(),
+            queue: QueueStats {
+                background_queued: 3,
+                ..QueueStats::default()
+            },
+            providers: vec![ProviderCounters {
+                provider: "anthropic".to_string(),
+                requests_24h: 12,
+                failures_24h: 1,
+                tokens_24h: 0,
+            }],
+            active_sessions: 2,
+        };
+        let ollama = QueueSnapshot {
+            max_concurrent: 2,
+            running: Vec::new(),
+            queued: Vec::new(),
+        };
+        let samples = samples_of(25.0, 4_096.0, 16_384.0, &metrics, &ollama);
+        let value = |name: &str| samples.iter().find(|(n, _)| n == name).map(|(_, v)| *v);
+        assert_eq!(value("system.memory_percent"), Some(25.0));
+        assert_eq!(value("queue.background_queued"), Some(3.0));
+        assert_eq!(value("tabs.websocket"), Some(2.0));
+        assert_eq!(value("provider.anthropic.requests_24h"), Some(12.0));
+        assert_eq!(value("provider.anthropic.failures_24h"), Some(1.0));
+        assert_eq!(
+            samples_of(0.0, 0.0, 0.0, &metrics, &ollama).len(),
+            samples.len() - 1
+        );
+    }
+}
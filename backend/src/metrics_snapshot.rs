@@ -0,0 +1,271 @@
+//! Periodic metrics snapshot file for dashboards.
+//!
+//! Every `CH_METRICS_SNAPSHOT_SECS` seconds (`metrics.snapshot_secs` in the
+//! config file, default 15, `0` disables; read at startup) the backend writes a compact JSON snapshot to `<data dir>/metrics.json`:
+//! background prompt queue, in-flight A2A tasks, per-provider request and
+//! token counters for the last 24 hours, and open WebSocket connections
+//! (frontend tabs). The file is replaced atomically (write + rename), so any
+//! process can read it without IPC or database access.
+//!
+//! The data dir is `CH_DATA_DIR`, defaulting to the platform local data
+//! directory + `claudehydra` (e.g. `~/.local/share/claudehydra`). It holds
+//! the config file itself, so it is env-only.
+//!
+//! - `GET /api/metrics/snapshot` — the last written snapshot; a fresh one
+//!   when the file is missing, older than `STALE_AFTER_INTERVALS` intervals
+//!   (writer stopped or failing) or snapshots are disabled
+
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::model_registry::provider_for_model;
+use crate::state::AppState;
+
+pub const JOB_METRICS_SNAPSHOT: &str = "metrics_snapshot";
+pub const SNAPSHOT_FILE: &str = "metrics.json";
+/// Snapshot age, in intervals, past which the file is no longer served.
+const STALE_AFTER_INTERVALS: u32 = 2;
+
+/// Data dir from `CH_DATA_DIR` (read once).
+pub fn data_dir() -> &'static PathBuf {
+    static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+    DATA_DIR.get_or_init(|| {
+        crate::hydra_config::env("CH_DATA_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| {
+                dirs::data_local_dir()
+                    .unwrap_or_else(std::env::temp_dir)
+                    .join("claudehydra")
+            })
+    })
+}
+
+/// Snapshot interval; `None` when disabled.
+pub fn interval() -> Option<Duration> {
+    let secs = crate::hydra_config::number(
+        "CH_METRICS_SNAPSHOT_SECS",
+        crate::hydra_config::current().metrics.snapshot_secs,
+    )
+    .unwrap_or(15);
+    (secs > 0).then(|| Duration::from_secs(secs.max(5)))
+}
+
+/// Path of the snapshot file.
+pub fn snapshot_path() -> PathBuf {
+    data_dir().join(SNAPSHOT_FILE)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub background_queued: i64,
+    pub background_running: i64,
+    pub background_done_24h: i64,
+    pub background_failed_24h: i64,
+    /// A2A delegations with a live cancellation token.
+    pub a2a_in_flight: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderCounters {
+    pub provider: String,
+    pub requests_24h: i64,
+    pub failures_24h: i64,
+    pub tokens_24h: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub generated_at: DateTime<Utc>,
+    pub queue: QueueStats,
+    pub providers: Vec<ProviderCounters>,
+    /// Open `/ws/chat` connections — one per frontend tab.
+    pub active_sessions: usize,
+}
+
+/// Fold per-model usage rows into per-provider counters, sorted by provider.
+fn aggregate_providers(rows: Vec<(String, i64, i64, i64)>) -> Vec<ProviderCounters> {
+    let mut by_provider: std::collections::BTreeMap<&'static str, ProviderCounters> =
+        std::collections::BTreeMap::new();
+    for (model, requests, failures, tokens) in rows {
+        let provider = provider_for_model(&model);
+        let entry = by_provider.entry(provider).or_insert_with(|| ProviderCounters {
+            provider: provider.to_string(),
+            requests_24h: 0,
+            failures_24h: 0,
+            tokens_24h: 0,
+        });
+        entry.requests_24h += requests;
+        entry.failures_24h += failures;
+        entry.tokens_24h += tokens;
+    }
+    by_provider.into_values().collect()
+}
+
+/// Collect a fresh snapshot.
+pub async fn collect(state: &AppState) -> Result<MetricsSnapshot, String> {
+    let (queued, running, done, failed): (i64, i64, i64, i64) = sqlx::query_as(
+        "SELECT \
+            COUNT(*) FILTER (WHERE status = 'queued'), \
+            COUNT(*) FILTER (WHERE status = 'running'), \
+            COUNT(*) FILTER (WHERE status = 'done' AND finished_at >= NOW() - INTERVAL '24 hours'), \
+            COUNT(*) FILTER (WHERE status = 'failed' AND finished_at >= NOW() - INTERVAL '24 hours') \
+         FROM ch_background_prompts",
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| format!("Queue stats query failed: {}", e))?;
+
+    let rows: Vec<(String, i64, i64, i64)> = sqlx::query_as(
+        "SELECT model, COUNT(*), COUNT(*) FILTER (WHERE success IS FALSE), \
+            COALESCE(SUM(total_tokens), 0)::BIGINT \
+         FROM ch_agent_usage \
+         WHERE created_at >= NOW() - INTERVAL '24 hours' \
+         GROUP BY model",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Provider counters query failed: {}", e))?;
+
+    let a2a_in_flight = state.a2a_cancel_tokens.read().await.len();
+
+    Ok(MetricsSnapshot {
+        generated_at: Utc::now(),
+        queue: QueueStats {
+            background_queued: queued,
+            background_running: running,
+            background_done_24h: done,
+            background_failed_24h: failed,
+            a2a_in_flight,
+        },
+        providers: aggregate_providers(rows),
+        active_sessions: crate::handlers::streaming::websocket::active_connections(),
+    })
+}
+
+/// Write `snapshot` atomically to the snapshot file.
+pub async fn write_snapshot(snapshot: &MetricsSnapshot) -> Result<(), String> {
+    let dir = data_dir();
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let bytes = serde_json::to_vec(snapshot).map_err(|e| e.to_string())?;
+    let tmp = dir.join(format!("{}.tmp", SNAPSHOT_FILE));
+    tokio::fs::write(&tmp, bytes)
+        .await
+        .map_err(|e| format!("Cannot write {}: {}", tmp.display(), e))?;
+    let path = snapshot_path();
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .map_err(|e| format!("Cannot replace {}: {}", path.display(), e))
+}
+
+/// Spawn the snapshot writer (no-op when `CH_METRICS_SNAPSHOT_SECS=0`).
+pub fn spawn(state: AppState) {
+    let Some(interval) = interval() else {
+        return;
+    };
+    tracing::info!(
+        "metrics_snapshot: writing {} every {}s",
+        snapshot_path().display(),
+        interval.as_secs()
+    );
+    state.job_schedule.start(JOB_METRICS_SNAPSHOT, interval);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_METRICS_SNAPSHOT);
+            let result = match collect(&state).await {
+                Ok(snapshot) => write_snapshot(&snapshot).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                tracing::warn!("metrics_snapshot: {}", e);
+            }
+        }
+    });
+}
+
+/// Whether a snapshot written every `interval` is recent enough to serve.
+fn is_fresh(snapshot: &MetricsSnapshot, interval: Duration, now: DateTime<Utc>) -> bool {
+    let max_age = chrono::Duration::from_std(interval * STALE_AFTER_INTERVALS)
+        .unwrap_or(chrono::Duration::MAX);
+    now - snapshot.generated_at <= max_age
+}
+
+/// `GET /api/metrics/snapshot`
+pub async fn get_snapshot(
+    State(state): State<AppState>,
+) -> Result<Json<MetricsSnapshot>, (StatusCode, Json<Value>)> {
+    if let Some(interval) = interval()
+        && let Ok(bytes) = tokio::fs::read(snapshot_path()).await
+        && let Ok(snapshot) = serde_json::from_slice::<MetricsSnapshot>(&bytes)
+        && is_fresh(&snapshot, interval, Utc::now())
+    {
+        return Ok(Json(snapshot));
+    }
+    collect(&state).await.map(Json).map_err(|e| {
+        tracing::warn!("metrics_snapshot: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to collect metrics" })),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn providers_are_folded_by_model_family() {
+        let providers = aggregate_providers(vec![
+            ("claude-sonnet-4-6".to_string(), 10, 1, 5_000),
+            ("claude-haiku-4-5".to_string(), 4, 0, 800),
+            ("gemini-3.1-flash-preview".to_string(), 2, 2, 100),
+        ]);
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].provider, "anthropic");
+        assert_eq!(providers[0].requests_24h, 14);
+        assert_eq!(providers[0].failures_24h, 1);
+        assert_eq!(providers[0].tokens_24h, 5_800);
+        assert_eq!(providers[1].provider, "google");
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let snapshot = MetricsSnapshot {
+            generated_at: Utc::now(),
+            queue: QueueStats::default(),
+            providers: Vec::new(),
+            active_sessions: 2,
+        };
+        let bytes = serde_json::to_vec(&snapshot).unwrap();
+        let back: MetricsSnapshot = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(back.active_sessions, 2);
+    }
+
+    #[test]
+    fn stale_snapshots_are_not_served() {
+        let now = Utc::now();
+        let written = |secs_ago: i64| MetricsSnapshot {
+            generated_at: now - chrono::Duration::seconds(secs_ago),
+            queue: QueueStats::default(),
+            providers: Vec::new(),
+            active_sessions: 0,
+        };
+        let interval = Duration::from_secs(15);
+        assert!(is_fresh(&written(0), interval, now));
+        assert!(is_fresh(&written(30), interval, now));
+        assert!(!is_fresh(&written(31), interval, now));
+        assert!(!is_fresh(&written(3_600), interval, now));
+    }
+}
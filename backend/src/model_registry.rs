@@ -34,6 +34,7 @@ pub struct ResolvedModels {
     pub coordinator: Option<ModelInfo>, // sonnet
     pub executor: Option<ModelInfo>,    // haiku
     pub flash: Option<ModelInfo>,       // gemini flash (fast tasks)
+    pub gemini_pro: Option<ModelInfo>,  // gemini pro (direct Gemini chat)
 }
 
 /// Extract a sortable version key from a model ID.
@@ -123,21 +124,20 @@ pub async fn resolve_models(state: &AppState) -> ResolvedModels {
     let executor = select_best(&anthropic, &["haiku"], &["20"])
         .or_else(|| select_best(&anthropic, &["haiku"], &[]));
 
-    // Flash: latest Google Flash model for fast simple tasks
+    // Flash / Pro: latest Google text models (fast tasks / direct Gemini chat)
     let google = cache.models.get("google").cloned().unwrap_or_default();
-    let flash = select_best(
-        &google,
-        &["flash"],
-        &[
-            "lite", "latest", "image", "tts", "computer", "robotics", "audio", "thinking",
-        ],
-    );
+    let google_exclude = [
+        "lite", "latest", "image", "tts", "computer", "robotics", "audio", "thinking",
+    ];
+    let flash = select_best(&google, &["flash"], &google_exclude);
+    let gemini_pro = select_best(&google, &["pro"], &google_exclude);
 
     ResolvedModels {
         commander,
         coordinator,
         executor,
         flash,
+        gemini_pro,
     }
 }
 
@@ -170,6 +170,7 @@ pub async fn get_model_id(state: &AppState, use_case: &str) -> String {
         "coordinator" | "Coordinator" => (resolved.coordinator, "claude-sonnet-4-6"),
         "executor" | "Executor" => (resolved.executor, "claude-haiku-4-5-20251001"),
         "flash" | "Flash" => (resolved.flash, "gemini-3.1-flash-preview"),
+        "gemini_pro" | "gemini-pro" => (resolved.gemini_pro, "gemini-3.1-pro-preview"),
         _ => (resolved.coordinator, "claude-sonnet-4-6"),
     };
 
@@ -194,6 +195,50 @@ pub async fn model_for_tier(state: &AppState, tier: &str) -> String {
     get_model_id(state, tier).await
 }
 
+/// Whether `model_id` is in the cached model lists of any provider.
+pub async fn is_known_model(state: &AppState, model_id: &str) -> bool {
+    use jaskier_core::model_registry::HasModelRegistryState;
+
+    let cache = state.model_cache().read().await;
+    cache
+        .models
+        .values()
+        .any(|models| models.iter().any(|m| m.id == model_id))
+}
+
+/// Provider that serves a model id — the key for dispatch, queue lanes,
+/// budgets and cost accounting: `ollama` (`ollama/<name>`), `anthropic`
+/// (`claude-*`), `google` (`gemini-*`), the compat provider that
+/// `compat_providers::route_model` sends it to (`openrouter`, `groq`,
+/// `azure`), or `other` when nothing can serve it.
+pub fn provider_for_model(model: &str) -> &'static str {
+    let lower = model.to_lowercase();
+    if lower.starts_with(crate::queue::OLLAMA_PREFIX) {
+        "ollama"
+    } else if lower.starts_with("claude") {
+        "anthropic"
+    } else if lower.starts_with("gemini") {
+        "google"
+    } else if let Some((cfg, _)) = crate::ai_gateway::compat_providers::route_model(model) {
+        cfg.id
+    } else {
+        "other"
+    }
+}
+
+/// Registry tier a model id belongs to, as `resolve_models` assigns them:
+/// `commander` (opus), `executor` (haiku), otherwise `coordinator`.
+pub fn tier_for_model(model: &str) -> &'static str {
+    let lower = model.to_lowercase();
+    if lower.contains("opus") {
+        "commander"
+    } else if lower.contains("haiku") {
+        "executor"
+    } else {
+        "coordinator"
+    }
+}
+
 // ── HTTP handlers ────────────────────────────────────────────────────────────
 
 /// Read all pins from DB as a HashMap.
@@ -298,6 +343,7 @@ pub async fn list_models(State(state): State<AppState>) -> impl IntoResponse {
             "coordinator": resolved.coordinator,
             "executor": resolved.executor,
             "flash": resolved.flash,
+            "gemini_pro": resolved.gemini_pro,
         },
         "providers": {
             "anthropic": cache.models.get("anthropic").cloned().unwrap_or_default(),
@@ -329,6 +375,7 @@ pub async fn refresh_models(State(state): State<AppState>) -> Json<Value> {
             "coordinator": resolved.coordinator,
             "executor": resolved.executor,
             "flash": resolved.flash,
+            "gemini_pro": resolved.gemini_pro,
         }
     });
     if !errors.is_empty() {
@@ -448,6 +495,25 @@ mod tests {
         }
     }
 
+    // ── provider_for_model ───────────────────────────────────────────────
+
+    #[test]
+    fn provider_for_model_names_the_serving_provider() {
+        assert_eq!(provider_for_model("claude-opus-4-6"), "anthropic");
+        assert_eq!(provider_for_model("gemini-2.5-pro"), "google");
+        assert_eq!(provider_for_model("ollama/llama3.1:8b"), "ollama");
+        assert_eq!(provider_for_model("groq/llama-3.3-70b-versatile"), "groq");
+        assert_eq!(provider_for_model("llama3"), "other");
+    }
+
+    #[test]
+    fn tier_for_model_follows_the_registry_tiers() {
+        assert_eq!(tier_for_model("claude-opus-4-6"), "commander");
+        assert_eq!(tier_for_model("claude-haiku-4-5-20251001"), "executor");
+        assert_eq!(tier_for_model("claude-sonnet-4-6"), "coordinator");
+        assert_eq!(tier_for_model("gemini-2.5-pro"), "coordinator");
+    }
+
     // ── version_key ──────────────────────────────────────────────────────
 
     #[test]
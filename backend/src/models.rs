@@ -2,6 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use utoipa::ToSchema;
 
+use crate::stream_event::StreamEvent;
+
 // ── DB row types ────────────────────────────────────────────────────────
 
 #[derive(sqlx::FromRow)]
@@ -69,6 +71,26 @@ pub struct MessageRow {
     pub model: Option<String>,
     pub agent: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    #[sqlx(default)]
+    pub prompt_tokens: Option<i32>,
+    #[sqlx(default)]
+    pub completion_tokens: Option<i32>,
+    #[sqlx(default)]
+    pub tokens_estimated: bool,
+    /// Secrets replaced before the message was stored.
+    #[sqlx(default)]
+    pub redactions: i32,
+}
+
+impl MessageRow {
+    /// Recorded token usage, if any.
+    pub fn usage(&self) -> Option<MessageUsage> {
+        Some(MessageUsage {
+            prompt_tokens: self.prompt_tokens? as u32,
+            completion_tokens: self.completion_tokens.unwrap_or(0) as u32,
+            estimated: self.tokens_estimated,
+        })
+    }
 }
 
 // ── Agent ───────────────────────────────────────────────────────────────
@@ -115,6 +137,13 @@ pub struct ChatRequest {
     pub tools_enabled: Option<bool>,
     #[serde(default)]
     pub session_id: Option<String>,
+    /// Images attached to the last user message (vision models only).
+    #[serde(default)]
+    pub attachments: Vec<crate::ai_gateway::attachments::Attachment>,
+    /// Force (`true`) or suppress (`false`) web search augmentation;
+    /// unset = decided from the prompt.
+    #[serde(default)]
+    pub web_search: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -133,6 +162,12 @@ pub struct ChatResponse {
     pub message: ChatMessage,
     pub model: String,
     pub usage: Option<UsageInfo>,
+    /// Web search results injected into the prompt, for citation display.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<crate::web_search::WebSource>,
+    /// Id of the request in logs and traces (also the `X-Correlation-Id` header).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -235,6 +270,21 @@ pub struct HistoryEntry {
     pub timestamp: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_interactions: Option<Vec<ToolInteractionInfo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MessageUsage>,
+    /// Secrets replaced before the message was stored (see `redaction.rs`).
+    #[serde(default)]
+    pub redactions: u32,
+}
+
+/// Tokens of the request that produced a message. `estimated` when the
+/// source reported none (~4 characters per token), e.g. CLI providers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct MessageUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    #[serde(default)]
+    pub estimated: bool,
 }
 
 // ── Session ─────────────────────────────────────────────────────────────
@@ -273,6 +323,33 @@ pub struct UpdateWorkingDirectoryRequest {
     pub working_directory: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CreateSessionWithWorkspaceRequest {
+    pub title: String,
+    /// Empty = inherit the global working directory.
+    #[serde(default)]
+    pub working_directory: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ForkSessionRequest {
+    /// Last message (inclusive) copied into the fork.
+    pub message_id: String,
+    /// Defaults to "Fork of <original title>".
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DuplicateSessionRequest {
+    /// Model the copy continues on (Anthropic or Gemini — the providers the chat
+    /// paths serve); its provider decides how tool calls are carried over.
+    pub model: String,
+    /// Defaults to "<original title> (<provider>)".
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
 // ── Prompt History ─────────────────────────────────────────────────────
 
 #[derive(sqlx::FromRow)]
@@ -297,6 +374,9 @@ pub struct AddMessageRequest {
     pub agent: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_interactions: Option<Vec<ToolInteractionInfo>>,
+    /// Reported token usage; estimated for assistant messages when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MessageUsage>,
 }
 
 // ── System ──────────────────────────────────────────────────────────────
@@ -307,6 +387,39 @@ pub struct SystemStats {
     pub memory_used_mb: f64,
     pub memory_total_mb: f64,
     pub platform: String,
+    pub queues: InternalQueueStats,
+    /// Filesystem holding the data directory.
+    pub disk: Option<crate::system_telemetry::DiskUsage>,
+    pub network: crate::system_telemetry::NetworkThroughput,
+    /// `None` on Windows.
+    pub load_average: Option<crate::system_telemetry::LoadAverage>,
+}
+
+/// Result of the eager startup warm-up (`startup::spawn_warm_start`).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StartupReport {
+    pub ready: bool,
+    pub model_sync_ok: bool,
+    pub model_sync_attempts: u32,
+    pub prompt_cache_warmed: bool,
+    pub anthropic_reachable: bool,
+    /// `None` while the probe is still running.
+    pub vault_reachable: Option<bool>,
+    /// `None` while the servers are still connecting.
+    pub mcp_connected: Option<bool>,
+    pub agents_loaded: usize,
+    pub tools_available: usize,
+    pub warmup_ms: u64,
+    pub completed_at: String,
+}
+
+/// Sizes of in-memory task/state maps — lets monitoring spot leaks when
+/// cancelled or finished entries are not being compacted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct InternalQueueStats {
+    pub swarm_tasks: usize,
+    pub a2a_cancel_tokens: usize,
+    pub pending_oauth_states: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -336,6 +449,10 @@ pub struct SystemMetricsResponse {
     pub cpu: MetricItem,
     pub ram: MetricItem,
     pub network: NetworkMetric,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<MetricItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load: Option<MetricItem>,
 }
 
 // ── Tool Use (Anthropic API) ────────────────────────────────────────────
@@ -388,11 +505,84 @@ pub enum WsClientMessage {
         tools_enabled: Option<bool>,
         #[serde(default)]
         session_id: Option<String>,
+        #[serde(default)]
+        web_search: Option<bool>,
+        /// Read the response aloud as it streams (requires `CH_TTS_BACKEND`).
+        #[serde(default)]
+        speak: Option<bool>,
+        /// Images attached to the prompt (same format as `ChatRequest`).
+        #[serde(default)]
+        attachments: Vec<crate::ai_gateway::attachments::Attachment>,
     },
     /// Cancel the currently running execution.
     Cancel,
     /// Heartbeat ping — expects a `Pong` response.
     Ping,
+    /// Decide a tool call announced by `ApprovalRequired`.
+    ApprovalDecision {
+        id: String,
+        decision: crate::approvals::Verdict,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+}
+
+/// Whether a progress indicator can show how far along an operation is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressKind {
+    /// `current` / `total` are known — render a bar, announce "n of m".
+    Determinate,
+    /// Duration unknown — render a spinner, announce the phase only.
+    Indeterminate,
+}
+
+/// Machine-readable progress attached to long-running stream events, so the
+/// frontend can drive progress bars and screen-reader announcements the
+/// same way for every operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Progress {
+    pub kind: ProgressKind,
+    /// Stable phase id (e.g. `awaiting_model`, `running_tools`, `auto_fix`).
+    pub phase: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub current: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub total: Option<u32>,
+    /// 0-100, determinate only.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub percent: Option<u8>,
+    /// Short human-readable description suitable for a live region.
+    pub label: String,
+}
+
+impl Progress {
+    pub fn determinate(phase: &str, current: u32, total: u32, label: impl Into<String>) -> Self {
+        let percent = if total == 0 {
+            100
+        } else {
+            (u64::from(current.min(total)) * 100 / u64::from(total)) as u8
+        };
+        Self {
+            kind: ProgressKind::Determinate,
+            phase: phase.to_string(),
+            current: Some(current),
+            total: Some(total),
+            percent: Some(percent),
+            label: label.into(),
+        }
+    }
+
+    pub fn indeterminate(phase: &str, label: impl Into<String>) -> Self {
+        Self {
+            kind: ProgressKind::Indeterminate,
+            phase: phase.to_string(),
+            current: None,
+            total: None,
+            percent: None,
+            label: label.into(),
+        }
+    }
 }
 
 /// Messages sent from the backend to the frontend client via WebSocket.
@@ -407,9 +597,19 @@ pub enum WsServerMessage {
         files_loaded: Vec<String>,
     },
     /// A streamed text token.
-    Token { content: String },
+    Token {
+        content: String,
+        /// Canonical stream event fields (see `stream_event.rs`).
+        #[serde(flatten)]
+        stream: StreamEvent,
+    },
     /// Execution completed successfully.
-    Complete { duration_ms: u64 },
+    Complete {
+        duration_ms: u64,
+        /// Canonical stream event fields: usage and finish reason.
+        #[serde(flatten)]
+        stream: StreamEvent,
+    },
     /// A tool call has been initiated.
     ToolCall {
         name: String,
@@ -428,9 +628,17 @@ pub enum WsServerMessage {
         iteration: u32,
         tools_completed: u32,
         tools_total: u32,
+        progress: Progress,
     },
     /// Current iteration in the tool-use loop.
-    Iteration { number: u32, max: u32 },
+    Iteration {
+        number: u32,
+        max: u32,
+        progress: Progress,
+    },
+    /// The execution entered a new phase (waiting for the model, running
+    /// tools, auto-fix, ...).
+    Phase { progress: Progress },
     /// An error occurred during execution.
     Error {
         message: String,
@@ -452,6 +660,21 @@ pub enum WsServerMessage {
     ViewHint {
         views: Vec<String>,
     },
+    /// Web search results injected into the prompt (sent before the first
+    /// token so the frontend can render citations).
+    Sources {
+        sources: Vec<crate::web_search::WebSource>,
+    },
+    /// The safety guard flagged the prompt or the response without blocking
+    /// it (see `safety.rs`).
+    SafetyAlert { message: String },
+    /// A mutating tool call waits for a decision — `approval_decision` or
+    /// `POST /api/approvals/{id}` (`ask` mode, see `approvals.rs`).
+    ApprovalRequired {
+        id: String,
+        tool: String,
+        input: Value,
+    },
 }
 
 // ── Agent Config (DB-driven) ────────────────────────────────────────────
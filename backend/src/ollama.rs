@@ -0,0 +1,730 @@
+//! Ollama model residency — warm-up and keep-alive management — and native
+//! multi-turn chat.
+//!
+//! The first request to a model Ollama has not loaded yet pays the load time
+//! (often 10+ seconds for larger models). Warming a model issues an empty
+//! generation, which loads it without producing output; the keep-alive
+//! policy then decides how long Ollama keeps it resident after a request.
+//!
+//! Configuration via environment:
+//! - `CH_OLLAMA_KEEP_ALIVE` (`ollama.keep_alive` in the config file, also per
+//!   profile) — `keep_alive` sent with every Ollama request
+//!   (gateway chat, background prompts, compaction, warm-ups): a duration
+//!   (`30m`, `2h`, `1h30m`), seconds (`3600`), `-1` to keep models loaded
+//!   indefinitely or `0` to unload right after the request. Unset = Ollama's
+//!   default (5 minutes).
+//! - `CH_OLLAMA_WARMUP_MODELS` — comma-separated models warmed at startup.
+//!
+//! Chat goes through Ollama's `/api/chat` with the full message history, so
+//! multi-turn context is kept natively; tool definitions are passed through
+//! for tool-capable models and the model's `tool_calls` are returned as-is
+//! (the caller runs the tools and sends `role: "tool"` messages back).
+//!
+//! Batch generation runs prompts through `/api/generate` concurrently —
+//! at most `CH_OLLAMA_BATCH_CONCURRENCY` at a time (default 4, a request may
+//! ask for fewer or more up to 16) — emits `ollama-batch-progress` as each
+//! item finishes and returns the results in the order of the prompts.
+//!
+//! Chat turns and batch items run through the `ollama_queue` dispatcher:
+//! chat defaults to `high` priority, batches to `normal`; both accept a
+//! `request_id` (cancel via `DELETE /api/ollama/requests/{request_id}`) and
+//! a `priority`.
+//!
+//! - `POST /api/ollama/warmup` — `{ model }` → `{ model, elapsed_ms, load_ms }`
+//! - `GET  /api/ollama/loaded` — models resident in RAM/VRAM (Ollama `/api/ps`)
+//! - `POST /api/ollama/chat`   — `{ model, messages, tools?, request_id?, priority? }`
+//!   → `{ request_id, model, message, done_reason, usage }`
+//! - `POST /api/ollama/batch`  — `{ model, prompts, system?, concurrency?, request_id?, priority? }`
+//!   → `{ batch_id, results }`
+//! - `GET  /api/ollama/events` — SSE: batch progress and service watchdog events
+
+use std::convert::Infallible;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::broadcast;
+
+use crate::api_error::bad_request;
+use crate::app_log::Level;
+use crate::hydra_config::{Derived, text};
+use crate::ollama_queue::{CANCELLED, Priority};
+use crate::queue::OLLAMA_PREFIX;
+use crate::state::AppState;
+
+/// Loading a large model from disk can take a while.
+const WARMUP_TIMEOUT: Duration = Duration::from_secs(300);
+const PS_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BATCH_CONCURRENCY: usize = 16;
+const MAX_BATCH_PROMPTS: usize = 200;
+
+static KEEP_ALIVE: Derived<Option<Value>> = Derived::new(|config| {
+    let raw = text("CH_OLLAMA_KEEP_ALIVE", config.ollama.keep_alive.as_deref())?;
+    let policy = parse_keep_alive(&raw);
+    match &policy {
+        Some(value) => tracing::info!("ollama: keep_alive {}", value),
+        None => tracing::warn!("ollama: ignoring invalid keep_alive '{}'", raw),
+    }
+    policy
+});
+
+/// Ollama events (batch progress, service watchdog) for `/api/ollama/events`.
+static EVENTS: LazyLock<broadcast::Sender<Value>> = LazyLock::new(|| broadcast::channel(64).0);
+
+pub(crate) fn emit(event: &str, data: Value) {
+    let level = match event {
+        "ollama-batch-progress" => Level::Debug,
+        "ollama-status-changed" if data["running"] == false => Level::Warn,
+        "ollama-restart" if data["ok"] == false => Level::Error,
+        _ => Level::Info,
+    };
+    crate::app_log::record(level, "ollama", event, data.clone());
+    // No subscribers is fine — events are best effort.
+    let _ = EVENTS.send(json!({ "event": event, "data": data }));
+}
+
+/// `keep_alive` value for a policy string; `None` when it is not one Ollama accepts.
+pub(crate) fn parse_keep_alive(raw: &str) -> Option<Value> {
+    let raw = raw.trim();
+    if let Ok(secs) = raw.parse::<i64>() {
+        return (secs >= -1).then(|| json!(secs));
+    }
+    // Go duration syntax: one or more `<number><unit>` parts.
+    let mut rest = raw;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        if digits == 0 || rest[..digits].parse::<f64>().is_err() {
+            return None;
+        }
+        rest = &rest[digits..];
+        let unit = ["ms", "s", "m", "h"]
+            .into_iter()
+            .find(|u| rest.starts_with(u))?;
+        rest = &rest[unit.len()..];
+    }
+    (!raw.is_empty()).then(|| json!(raw))
+}
+
+/// Keep-alive policy (`CH_OLLAMA_KEEP_ALIVE` overrides the config).
+pub fn keep_alive() -> Option<Value> {
+    KEEP_ALIVE.get()
+}
+
+fn with_keep_alive(payload: &mut Value, policy: Option<&Value>) {
+    if let (Some(obj), Some(policy)) = (payload.as_object_mut(), policy) {
+        obj.entry("keep_alive").or_insert_with(|| policy.clone());
+    }
+}
+
+/// Add the keep-alive policy to an Ollama request body (unless it sets its own).
+pub fn apply_keep_alive(payload: &mut Value) {
+    with_keep_alive(payload, keep_alive().as_ref());
+}
+
+/// Ollama server base URL: `CH_OLLAMA_URL` / `ollama.url` in the config
+/// file when set, else the gateway's Ollama provider.
+pub(crate) fn base_url(state: &AppState) -> Result<String, String> {
+    use crate::ai_gateway::AiProvider;
+    if let Some(url) = crate::hydra_config::ollama_url() {
+        return Ok(url);
+    }
+    let upstream = state
+        .ai_gateway
+        .providers
+        .get(&AiProvider::Ollama)
+        .map(|c| c.upstream_url.clone())
+        .ok_or_else(|| "Ollama provider is not configured".to_string())?;
+    Ok(upstream
+        .trim_end_matches('/')
+        .trim_end_matches("/api/chat")
+        .to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WarmupResult {
+    pub model: String,
+    /// Round trip of the warm-up request.
+    pub elapsed_ms: u64,
+    /// Time Ollama spent loading the model (0 when it was already resident).
+    pub load_ms: Option<u64>,
+}
+
+/// Load `model` into memory with an empty generation.
+pub async fn ollama_warmup(state: &AppState, model: &str) -> Result<WarmupResult, String> {
+    let mut body = json!({ "model": model, "prompt": "", "stream": false });
+    apply_keep_alive(&mut body);
+    let started = Instant::now();
+    let resp = state
+        .http_client
+        .post(format!("{}/api/generate", base_url(state)?))
+        .timeout(WARMUP_TIMEOUT)
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama warm-up failed: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    if !status.is_success() {
+        let error = body["error"].as_str().unwrap_or("unknown error");
+        return Err(format!(
+            "Ollama returned HTTP {}: {}",
+            status.as_u16(),
+            error
+        ));
+    }
+    Ok(WarmupResult {
+        model: model.to_string(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        // Ollama reports durations in nanoseconds.
+        load_ms: body["load_duration"].as_u64().map(|ns| ns / 1_000_000),
+    })
+}
+
+/// A model resident in Ollama's memory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadedModel {
+    pub name: String,
+    pub size_bytes: u64,
+    /// Part of `size_bytes` held in VRAM.
+    pub vram_bytes: u64,
+    /// When Ollama unloads it unless it is used again.
+    pub expires_at: Option<String>,
+    pub parameter_size: Option<String>,
+    pub quantization: Option<String>,
+}
+
+/// Models listed in an `/api/ps` response.
+fn parse_ps(body: &Value) -> Vec<LoadedModel> {
+    body["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            let name = m["name"].as_str().or_else(|| m["model"].as_str())?;
+            Some(LoadedModel {
+                name: name.to_string(),
+                size_bytes: m["size"].as_u64().unwrap_or(0),
+                vram_bytes: m["size_vram"].as_u64().unwrap_or(0),
+                expires_at: m["expires_at"].as_str().map(str::to_string),
+                parameter_size: m["details"]["parameter_size"].as_str().map(str::to_string),
+                quantization: m["details"]["quantization_level"]
+                    .as_str()
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Request body of a non-streaming `/api/chat` call.
+fn chat_body(model: &str, messages: &[Value], tools: &[Value]) -> Value {
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "stream": false,
+    });
+    if !tools.is_empty() {
+        body["tools"] = json!(tools);
+    }
+    apply_keep_alive(&mut body);
+    body
+}
+
+/// One chat turn over `messages` (history + the new turn). Returns the full
+/// response: the reply `message` (with `tool_calls` when the model called
+/// tools), `done_reason` and timings.
+pub async fn ollama_chat(
+    state: &AppState,
+    model: &str,
+    messages: &[Value],
+    tools: &[Value],
+) -> Result<Value, String> {
+    let resp = state
+        .http_client
+        .post(format!("{}/api/chat", base_url(state)?))
+        .json(&chat_body(model, messages, tools))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama request failed: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    if !status.is_success() {
+        return Err(match body["error"].as_str() {
+            Some(error) => format!("Ollama returned HTTP {}: {}", status.as_u16(), error),
+            None => format!("Ollama returned HTTP {}", status.as_u16()),
+        });
+    }
+    Ok(body)
+}
+
+/// One non-streaming `/api/generate` call; returns the generated text.
+pub async fn ollama_generate(
+    state: &AppState,
+    model: &str,
+    prompt: &str,
+    system: Option<&str>,
+) -> Result<String, String> {
+    let mut body = json!({ "model": model, "prompt": prompt, "stream": false });
+    if let Some(system) = system {
+        body["system"] = json!(system);
+    }
+    apply_keep_alive(&mut body);
+    let resp = state
+        .http_client
+        .post(format!("{}/api/generate", base_url(state)?))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama request failed: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    if !status.is_success() {
+        return Err(match body["error"].as_str() {
+            Some(error) => format!("Ollama returned HTTP {}: {}", status.as_u16(), error),
+            None => format!("Ollama returned HTTP {}", status.as_u16()),
+        });
+    }
+    Ok(body["response"].as_str().unwrap_or_default().to_string())
+}
+
+/// Parallel generations per batch: the request's wish, else
+/// `CH_OLLAMA_BATCH_CONCURRENCY`, else 4.
+fn batch_concurrency(requested: Option<usize>) -> usize {
+    requested
+        .or_else(|| {
+            std::env::var("CH_OLLAMA_BATCH_CONCURRENCY")
+                .ok()
+                .and_then(|v| v.trim().parse().ok())
+        })
+        .unwrap_or(4)
+        .clamp(1, MAX_BATCH_CONCURRENCY)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItem {
+    pub index: usize,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Generate every prompt with at most `concurrency` requests in flight
+/// (each item also queues for a dispatcher slot under `batch_id`).
+/// Results keep the order of `prompts`; a failed item does not stop the rest.
+pub async fn ollama_batch_generate(
+    state: &AppState,
+    batch_id: &str,
+    model: &str,
+    prompts: &[String],
+    system: Option<&str>,
+    concurrency: usize,
+    priority: Priority,
+) -> Vec<BatchItem> {
+    let total = prompts.len();
+    let completed = AtomicUsize::new(0);
+    let completed = &completed;
+    futures_util::stream::iter(prompts.iter().enumerate())
+        .map(|(index, prompt)| async move {
+            let started = Instant::now();
+            let result = crate::ollama_queue::dispatch(
+                batch_id,
+                priority,
+                ollama_generate(state, model, prompt, system),
+            )
+            .await;
+            let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+            emit(
+                "ollama-batch-progress",
+                json!({
+                    "batch_id": batch_id,
+                    "index": index,
+                    "completed": done,
+                    "total": total,
+                    "ok": result.is_ok(),
+                }),
+            );
+            let (response, error) = match result {
+                Ok(text) => (Some(text), None),
+                Err(e) => (None, Some(e)),
+            };
+            BatchItem {
+                index,
+                response,
+                error,
+                elapsed_ms: started.elapsed().as_millis() as u64,
+            }
+        })
+        // `buffered` (not `buffer_unordered`) yields in input order.
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Token usage of an `/api/chat` or `/api/generate` response
+/// (`prompt_eval_count` / `eval_count`).
+pub fn usage(body: &Value) -> Option<crate::models::MessageUsage> {
+    Some(crate::models::MessageUsage {
+        prompt_tokens: body["prompt_eval_count"].as_u64()? as u32,
+        completion_tokens: body["eval_count"].as_u64().unwrap_or(0) as u32,
+        estimated: false,
+    })
+}
+
+/// Models Ollama currently holds in RAM/VRAM.
+pub async fn ollama_loaded_models(state: &AppState) -> Result<Vec<LoadedModel>, String> {
+    let resp = state
+        .http_client
+        .get(format!("{}/api/ps", base_url(state)?))
+        .timeout(PS_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Ollama returned HTTP {}", resp.status().as_u16()));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    Ok(parse_ps(&body))
+}
+
+/// Warm the models in `CH_OLLAMA_WARMUP_MODELS`, one after another.
+pub fn spawn(state: AppState) {
+    let models: Vec<String> = std::env::var("CH_OLLAMA_WARMUP_MODELS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+        .collect();
+    if models.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        for model in models {
+            match ollama_warmup(&state, &model).await {
+                Ok(r) => tracing::info!("ollama: warmed {} in {} ms", model, r.elapsed_ms),
+                Err(e) => tracing::warn!("ollama: warm-up of {} failed: {}", model, e),
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct WarmupRequest {
+    pub model: String,
+}
+
+/// `POST /api/ollama/warmup`
+pub async fn warmup(
+    State(state): State<AppState>,
+    Json(req): Json<WarmupRequest>,
+) -> Result<Json<WarmupResult>, (StatusCode, Json<Value>)> {
+    let model = req.model.trim();
+    let model = model.strip_prefix("ollama/").unwrap_or(model);
+    if model.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "model must not be empty" })),
+        ));
+    }
+    ollama_warmup(&state, model)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChatRequest {
+    pub model: String,
+    /// `{ role, content, images?, tool_calls? }` in Ollama's format, oldest first.
+    pub messages: Vec<Value>,
+    #[serde(default)]
+    pub tools: Vec<Value>,
+    /// Id to cancel the turn by; generated when absent.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+/// HTTP error for a failed generation: 409 when it was cancelled.
+fn generation_error(error: String) -> (StatusCode, Json<Value>) {
+    let status = if error == CANCELLED {
+        StatusCode::CONFLICT
+    } else {
+        StatusCode::BAD_GATEWAY
+    };
+    (status, Json(json!({ "error": error })))
+}
+
+/// `POST /api/ollama/chat`
+pub async fn chat(
+    State(state): State<AppState>,
+    Json(req): Json<ChatRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let model = req.model.trim();
+    let model = model.strip_prefix("ollama/").unwrap_or(model);
+    if model.is_empty() {
+        return Err(bad_request("model must not be empty"));
+    }
+    if req.messages.is_empty() || req.messages.iter().any(|m| !m["role"].is_string()) {
+        return Err(bad_request(
+            "messages must be a non-empty list of { role, content }",
+        ));
+    }
+    if !req.tools.is_empty() && !crate::ai_gateway::gateway_tools::is_ollama_tool_model(model) {
+        return Err(bad_request("model does not support tool calling"));
+    }
+    let request_id = req
+        .request_id
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let body = crate::ollama_queue::dispatch(
+        &request_id,
+        req.priority.unwrap_or(Priority::High),
+        ollama_chat(&state, model, &req.messages, &req.tools),
+    )
+    .await
+    .map_err(generation_error)?;
+    let usage = usage(&body);
+    crate::costs::schedule(
+        &state.db,
+        crate::costs::CostEvent {
+            model: format!("{}{}", OLLAMA_PREFIX, model),
+            session_id: None,
+            requests: 1,
+            input_tokens: usage.map_or(0, |u| u.prompt_tokens as i64),
+            output_tokens: usage.map_or(0, |u| u.completion_tokens as i64),
+            estimated: usage.is_none(),
+        },
+    );
+    Ok(Json(json!({
+        "request_id": request_id,
+        "model": model,
+        "message": body["message"],
+        "done_reason": body["done_reason"],
+        "usage": usage,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchRequest {
+    pub model: String,
+    pub prompts: Vec<String>,
+    #[serde(default)]
+    pub system: Option<String>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Batch id (shared by all items for cancelling); generated when absent.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    #[serde(default)]
+    pub priority: Option<Priority>,
+}
+
+/// `POST /api/ollama/batch`
+pub async fn batch(
+    State(state): State<AppState>,
+    Json(req): Json<BatchRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let model = req.model.trim();
+    let model = model.strip_prefix("ollama/").unwrap_or(model);
+    if model.is_empty() {
+        return Err(bad_request("model must not be empty".to_string()));
+    }
+    if req.prompts.is_empty() || req.prompts.len() > MAX_BATCH_PROMPTS {
+        return Err(bad_request(format!(
+            "prompts must contain 1 to {} entries",
+            MAX_BATCH_PROMPTS
+        )));
+    }
+    let batch_id = req
+        .request_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let concurrency = batch_concurrency(req.concurrency);
+    let started = Instant::now();
+    let results = ollama_batch_generate(
+        &state,
+        &batch_id,
+        model,
+        &req.prompts,
+        req.system.as_deref(),
+        concurrency,
+        req.priority.unwrap_or_default(),
+    )
+    .await;
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    let (input_tokens, output_tokens) = results
+        .iter()
+        .filter_map(|r| Some((&req.prompts[r.index], r.response.as_deref()?)))
+        .fold((0, 0), |(input, output), (prompt, response)| {
+            (
+                input + crate::compaction::estimate_tokens(prompt) as i64,
+                output + crate::compaction::estimate_tokens(response) as i64,
+            )
+        });
+    crate::costs::schedule(
+        &state.db,
+        crate::costs::CostEvent {
+            model: format!("{}{}", OLLAMA_PREFIX, model),
+            session_id: None,
+            requests: (results.len() - failed) as i64,
+            input_tokens,
+            output_tokens,
+            estimated: true,
+        },
+    );
+    Ok(Json(json!({
+        "batch_id": batch_id,
+        "model": model,
+        "concurrency": concurrency,
+        "failed": failed,
+        "elapsed_ms": started.elapsed().as_millis() as u64,
+        "results": results,
+    })))
+}
+
+/// `GET /api/ollama/events` — SSE stream of Ollama events.
+pub async fn events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut rx = EVENTS.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => {
+                    let name = msg["event"].as_str().unwrap_or("message").to_string();
+                    if let Ok(event) = Event::default().event(name).json_data(&msg["data"]) {
+                        yield Ok(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::new())
+}
+
+/// `GET /api/ollama/loaded`
+pub async fn loaded(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let models = ollama_loaded_models(&state)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))?;
+    Ok(Json(json!({
+        "models": models,
+        "keep_alive": keep_alive(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keep_alive_accepts_ollama_formats() {
+        assert_eq!(parse_keep_alive("30m"), Some(json!("30m")));
+        assert_eq!(parse_keep_alive(" 1h30m "), Some(json!("1h30m")));
+        assert_eq!(parse_keep_alive("1.5h"), Some(json!("1.5h")));
+        assert_eq!(parse_keep_alive("3600"), Some(json!(3600)));
+        assert_eq!(parse_keep_alive("-1"), Some(json!(-1)));
+        assert_eq!(parse_keep_alive("0"), Some(json!(0)));
+        for bad in ["", "forever", "10d", "m", "-5", "5 m"] {
+            assert_eq!(parse_keep_alive(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn keep_alive_does_not_override_the_request() {
+        let policy = json!("1h");
+        let mut body = json!({ "model": "llama3" });
+        with_keep_alive(&mut body, Some(&policy));
+        assert_eq!(body["keep_alive"], "1h");
+
+        let mut body = json!({ "model": "llama3", "keep_alive": 0 });
+        with_keep_alive(&mut body, Some(&policy));
+        assert_eq!(body["keep_alive"], 0);
+
+        let mut body = json!({ "model": "llama3" });
+        with_keep_alive(&mut body, None);
+        assert!(body.get("keep_alive").is_none());
+    }
+
+    #[test]
+    fn chat_body_passes_history_and_tools() {
+        let history = vec![
+            json!({ "role": "user", "content": "hi" }),
+            json!({ "role": "assistant", "content": "hello" }),
+            json!({ "role": "user", "content": "and now?" }),
+        ];
+        let body = chat_body("llama3.1:8b", &history, &[]);
+        assert_eq!(body["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(body["stream"], false);
+        assert!(body.get("tools").is_none());
+
+        let tool = json!({ "type": "function", "function": { "name": "read_file" } });
+        let body = chat_body("llama3.1:8b", &history, std::slice::from_ref(&tool));
+        assert_eq!(body["tools"], json!([tool]));
+    }
+
+    #[test]
+    fn batch_concurrency_is_bounded() {
+        assert_eq!(batch_concurrency(Some(8)), 8);
+        assert_eq!(batch_concurrency(Some(0)), 1);
+        assert_eq!(batch_concurrency(Some(500)), MAX_BATCH_CONCURRENCY);
+    }
+
+    #[test]
+    fn usage_comes_from_eval_counts() {
+        let body = json!({ "prompt_eval_count": 812, "eval_count": 64, "done": true });
+        let reported = usage(&body).unwrap();
+        assert_eq!(
+            (reported.prompt_tokens, reported.completion_tokens),
+            (812, 64)
+        );
+        assert!(!reported.estimated);
+        assert!(usage(&json!({ "done": true })).is_none());
+    }
+
+    #[test]
+    fn ps_response_is_parsed() {
+        let body = json!({
+            "models": [{
+                "name": "llama3.1:8b",
+                "model": "llama3.1:8b",
+                "size": 6_654_289_920u64,
+                "size_vram": 6_654_289_920u64,
+                "expires_at": "2026-10-16T12:30:00Z",
+                "details": { "parameter_size": "8.0B", "quantization_level": "Q4_K_M" }
+            }, { "size": 1 }]
+        });
+        let models = parse_ps(&body);
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].name, "llama3.1:8b");
+        assert_eq!(models[0].vram_bytes, 6_654_289_920);
+        assert_eq!(models[0].quantization.as_deref(), Some("Q4_K_M"));
+        assert!(parse_ps(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,527 @@
+//! Ollama model capability matcher — picks the best *installed* local model
+//! for a task instead of assuming a hard-coded one is present.
+//!
+//! A model is a candidate when it is not an embedding model and it fits the
+//! memory budget: already loaded, or its size (plus `FIT_HEADROOM`) is below
+//! the free VRAM (NVIDIA, see `gpu.rs`) or free system RAM, whichever is
+//! larger. Among candidates, models made for the task win (coder models for
+//! `code`, general models for `general`), then the largest one.
+//!
+//! When nothing fits, a default matching the budget is recommended; it is
+//! only downloaded after an explicit `POST /api/ollama/pull` with
+//! `confirm: true`.
+//!
+//! The Witcher router uses the matcher when `CH_WITCHER_LOCAL_MODEL=auto`.
+//!
+//! `context_length` reads a model's context window from `/api/show` (its
+//! `num_ctx` parameter, else the architecture's maximum) and caches it for
+//! the capability pre-check and context usage display (`capabilities.rs`).
+//!
+//! - `GET  /api/ollama/select` — `?task=code|general` → chosen model, budget, installed models, recommendation
+//! - `POST /api/ollama/pull`   — `{ model, confirm }` → starts the download (`202`)
+//! - `GET  /api/ollama/pulls`  — downloads started by this process and their status
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::state::AppState;
+
+const MB: f64 = 1_048_576.0;
+/// Room for the KV cache and runtime on top of the weights.
+const FIT_HEADROOM: f64 = 1.2;
+const TAGS_TIMEOUT: Duration = Duration::from_secs(10);
+const INSTALLED_TTL: Duration = Duration::from_secs(60);
+const PULL_TIMEOUT: Duration = Duration::from_secs(2 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskKind {
+    Code,
+    General,
+}
+
+impl TaskKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Code => "code",
+            Self::General => "general",
+        }
+    }
+}
+
+/// Defaults recommended per task, largest first: `(task, minimum budget MB, model)`.
+const RECOMMENDED: &[(TaskKind, f64, &str)] = &[
+    (TaskKind::Code, 6_000.0, "qwen2.5-coder:7b"),
+    (TaskKind::Code, 1_500.0, "qwen2.5-coder:1.5b"),
+    (TaskKind::General, 6_000.0, "llama3.1:8b"),
+    (TaskKind::General, 2_500.0, "llama3.2:3b"),
+    (TaskKind::General, 1_000.0, "llama3.2:1b"),
+];
+
+const CODE_HINTS: &[&str] = &[
+    "```",
+    "fn ",
+    "def ",
+    "class ",
+    "function",
+    "refactor",
+    "compile",
+    "stack trace",
+    "bug",
+    "unit test",
+    "implement",
+    "kod",
+    "funkcj",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstalledModel {
+    pub name: String,
+    pub size_bytes: u64,
+    pub family: Option<String>,
+    pub parameter_size: Option<String>,
+}
+
+impl InstalledModel {
+    fn size_mb(&self) -> f64 {
+        self.size_bytes as f64 / MB
+    }
+
+    fn is_embedding(&self) -> bool {
+        self.name.contains("embed") || self.family.as_deref().is_some_and(|f| f.contains("bert"))
+    }
+
+    fn is_coder(&self) -> bool {
+        let name = self.name.to_lowercase();
+        name.contains("coder") || name.contains("code")
+    }
+}
+
+/// Task kind of a prompt: `code` when it looks like programming work.
+pub fn task_kind(prompt: &str) -> TaskKind {
+    let lower = prompt.to_lowercase();
+    if CODE_HINTS.iter().any(|hint| lower.contains(hint)) {
+        TaskKind::Code
+    } else {
+        TaskKind::General
+    }
+}
+
+/// Best installed model for `kind` within `budget_mb`; loaded models always fit.
+fn select<'a>(
+    kind: TaskKind,
+    installed: &'a [InstalledModel],
+    budget_mb: f64,
+    loaded: &[String],
+) -> Option<&'a InstalledModel> {
+    installed
+        .iter()
+        .filter(|m| !m.is_embedding())
+        .filter(|m| loaded.contains(&m.name) || m.size_mb() * FIT_HEADROOM <= budget_mb)
+        .max_by(|a, b| {
+            let suits = |m: &InstalledModel| m.is_coder() == (kind == TaskKind::Code);
+            suits(a)
+                .cmp(&suits(b))
+                .then(a.size_bytes.cmp(&b.size_bytes))
+        })
+}
+
+/// Default model worth downloading for `kind` with `budget_mb` free.
+fn recommend(kind: TaskKind, budget_mb: f64) -> Option<&'static str> {
+    RECOMMENDED
+        .iter()
+        .find(|(task, min_mb, _)| *task == kind && budget_mb >= *min_mb)
+        .map(|(_, _, model)| *model)
+}
+
+/// Models in an Ollama `/api/tags` response.
+fn parse_tags(body: &Value) -> Vec<InstalledModel> {
+    body["models"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|m| {
+            Some(InstalledModel {
+                name: m["name"].as_str()?.to_string(),
+                size_bytes: m["size"].as_u64().unwrap_or(0),
+                family: m["details"]["family"].as_str().map(str::to_string),
+                parameter_size: m["details"]["parameter_size"].as_str().map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+static INSTALLED: LazyLock<Mutex<Option<(Instant, Vec<InstalledModel>)>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+/// Models installed in Ollama (cached for `INSTALLED_TTL`).
+pub async fn installed_models(state: &AppState) -> Result<Vec<InstalledModel>, String> {
+    let cached = INSTALLED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .filter(|(at, _)| at.elapsed() < INSTALLED_TTL)
+        .map(|(_, models)| models.clone());
+    if let Some(models) = cached {
+        return Ok(models);
+    }
+    let resp = state
+        .http_client
+        .get(format!("{}/api/tags", crate::ollama::base_url(state)?))
+        .timeout(TAGS_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama request failed: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Ollama returned HTTP {}", resp.status().as_u16()));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid Ollama response: {}", e))?;
+    let models = parse_tags(&body);
+    *INSTALLED.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), models.clone()));
+    Ok(models)
+}
+
+static CONTEXT_LENGTHS: LazyLock<Mutex<HashMap<String, u32>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Context window in an Ollama `/api/show` response: the `num_ctx`
+/// parameter when the Modelfile sets one, else `<arch>.context_length`.
+fn parse_context_length(body: &Value) -> Option<u32> {
+    let num_ctx = body["parameters"].as_str().and_then(|params| {
+        params.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            (parts.next() == Some("num_ctx")).then(|| parts.next()?.parse().ok())?
+        })
+    });
+    num_ctx.or_else(|| {
+        body["model_info"].as_object()?.iter().find_map(|(key, value)| {
+            key.ends_with(".context_length")
+                .then(|| value.as_u64().and_then(|n| u32::try_from(n).ok()))?
+        })
+    })
+}
+
+/// Context window of an installed model (`ollama/` prefix optional) as
+/// already looked up by `context_length`.
+pub fn cached_context_length(model: &str) -> Option<u32> {
+    let name = model.strip_prefix("ollama/").unwrap_or(model);
+    CONTEXT_LENGTHS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .copied()
+}
+
+/// Context window of an installed model from `/api/show` (cached).
+pub async fn context_length(state: &AppState, model: &str) -> Option<u32> {
+    if let Some(cached) = cached_context_length(model) {
+        return Some(cached);
+    }
+    let name = model.strip_prefix("ollama/").unwrap_or(model);
+    let body: Value = state
+        .http_client
+        .post(format!("{}/api/show", crate::ollama::base_url(state).ok()?))
+        .json(&json!({ "model": name }))
+        .timeout(TAGS_TIMEOUT)
+        .send()
+        .await
+        .ok()?
+        .error_for_status()
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    let length = parse_context_length(&body)?;
+    CONTEXT_LENGTHS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(name.to_string(), length);
+    Some(length)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryBudget {
+    pub free_vram_mb: Option<f64>,
+    pub free_ram_mb: f64,
+    /// The larger of the two.
+    pub budget_mb: f64,
+    /// Models already resident in memory.
+    pub loaded: Vec<String>,
+}
+
+async fn memory_budget(state: &AppState) -> MemoryBudget {
+    let gpu = crate::gpu::get_gpu_info(state).await;
+    let free_vram_mb = gpu
+        .vram_total_mb
+        .zip(gpu.vram_used_mb)
+        .map(|(total, used)| (total - used).max(0.0));
+    let free_ram_mb = {
+        let snapshot = state.system_monitor.read().await;
+        (snapshot.memory_total_mb - snapshot.memory_used_mb).max(0.0)
+    };
+    MemoryBudget {
+        free_vram_mb,
+        free_ram_mb,
+        budget_mb: free_vram_mb.unwrap_or(0.0).max(free_ram_mb),
+        loaded: gpu.models.into_iter().map(|m| m.name).collect(),
+    }
+}
+
+/// Best installed model for `kind` given current free memory.
+pub async fn best_model(state: &AppState, kind: TaskKind) -> Option<String> {
+    let installed = installed_models(state).await.ok()?;
+    let budget = memory_budget(state).await;
+    select(kind, &installed, budget.budget_mb, &budget.loaded).map(|m| m.name.clone())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PullStatus {
+    model: String,
+    /// `pulling`, `done` or `failed`
+    status: &'static str,
+    error: Option<String>,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+static PULLS: LazyLock<Mutex<HashMap<String, PullStatus>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+async fn pull(state: &AppState, model: &str) -> Result<(), String> {
+    let resp = state
+        .http_client
+        .post(format!("{}/api/pull", crate::ollama::base_url(state)?))
+        .timeout(PULL_TIMEOUT)
+        .json(&json!({ "model": model, "stream": false }))
+        .send()
+        .await
+        .map_err(|e| format!("Ollama pull failed: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp.json().await.unwrap_or_default();
+    if !status.is_success() || body["error"].is_string() {
+        return Err(body["error"]
+            .as_str()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("Ollama returned HTTP {}", status.as_u16())));
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct SelectQuery {
+    #[serde(default = "default_task")]
+    pub task: TaskKind,
+}
+
+fn default_task() -> TaskKind {
+    TaskKind::General
+}
+
+/// `GET /api/ollama/select`
+pub async fn select_model(
+    State(state): State<AppState>,
+    Query(q): Query<SelectQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let installed = installed_models(&state)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))?;
+    let budget = memory_budget(&state).await;
+    let chosen = select(q.task, &installed, budget.budget_mb, &budget.loaded);
+    let recommended = chosen
+        .is_none()
+        .then(|| recommend(q.task, budget.budget_mb))
+        .flatten();
+    Ok(Json(json!({
+        "task": q.task,
+        "model": chosen.map(|m| m.name.clone()),
+        "memory": budget,
+        "installed": installed,
+        // Downloading needs the user's consent: POST /api/ollama/pull with confirm=true.
+        "recommended": recommended,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PullRequest {
+    pub model: String,
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// `POST /api/ollama/pull`
+pub async fn pull_model(
+    State(state): State<AppState>,
+    Json(req): Json<PullRequest>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let model = req.model.trim().to_string();
+    if model.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "model must not be empty" })),
+        ));
+    }
+    if !req.confirm {
+        return Err((
+            StatusCode::PRECONDITION_REQUIRED,
+            Json(json!({
+                "error": "Downloading a model needs confirmation (confirm: true)",
+                "model": model,
+            })),
+        ));
+    }
+    {
+        let mut pulls = PULLS.lock().unwrap_or_else(|e| e.into_inner());
+        if pulls.get(&model).is_some_and(|p| p.status == "pulling") {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "Model is already being downloaded", "model": model })),
+            ));
+        }
+        pulls.insert(
+            model.clone(),
+            PullStatus {
+                model: model.clone(),
+                status: "pulling",
+                error: None,
+                started_at: Utc::now(),
+                finished_at: None,
+            },
+        );
+    }
+    tracing::info!("ollama_models: pulling {}", model);
+    let task_model = model.clone();
+    tokio::spawn(async move {
+        let outcome = pull(&state, &task_model).await;
+        // The next selection sees the new model.
+        *INSTALLED.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        if let Some(p) = PULLS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get_mut(&task_model)
+        {
+            p.finished_at = Some(Utc::now());
+            match outcome {
+                Ok(()) => p.status = "done",
+                Err(e) => {
+                    tracing::warn!("ollama_models: pull of {} failed: {}", task_model, e);
+                    p.status = "failed";
+                    p.error = Some(e);
+                }
+            }
+        }
+    });
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({ "model": model, "status": "pulling" })),
+    ))
+}
+
+/// `GET /api/ollama/pulls`
+pub async fn list_pulls() -> Json<Value> {
+    let mut pulls: Vec<PullStatus> = PULLS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+    pulls.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    Json(json!(pulls))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_length_prefers_num_ctx() {
+        let body = json!({
+            "parameters": "stop \"<|eot_id|>\"\nnum_ctx 32768",
+            "model_info": { "llama.context_length": 131072 },
+        });
+        assert_eq!(parse_context_length(&body), Some(32_768));
+        let body = json!({ "model_info": { "qwen2.context_length": 32768, "qwen2.block_count": 28 } });
+        assert_eq!(parse_context_length(&body), Some(32_768));
+        assert_eq!(parse_context_length(&json!({})), None);
+    }
+
+    fn model(name: &str, size_mb: u64) -> InstalledModel {
+        InstalledModel {
+            name: name.to_string(),
+            size_bytes: size_mb * 1_048_576,
+            family: None,
+            parameter_size: None,
+        }
+    }
+
+    #[test]
+    fn selection_prefers_task_models_that_fit() {
+        let installed = vec![
+            model("llama3.1:8b", 4_700),
+            model("llama3.2:3b", 2_000),
+            model("qwen2.5-coder:1.5b", 1_000),
+            model("nomic-embed-text:latest", 270),
+        ];
+        let pick = |kind, budget| select(kind, &installed, budget, &[]).map(|m| m.name.as_str());
+        assert_eq!(pick(TaskKind::General, 16_000.0), Some("llama3.1:8b"));
+        assert_eq!(pick(TaskKind::General, 3_000.0), Some("llama3.2:3b"));
+        assert_eq!(pick(TaskKind::Code, 16_000.0), Some("qwen2.5-coder:1.5b"));
+        // Only the coder fits: better than nothing for general prompts.
+        assert_eq!(pick(TaskKind::General, 1_500.0), Some("qwen2.5-coder:1.5b"));
+        assert_eq!(pick(TaskKind::General, 500.0), None);
+        // Resident models need no extra memory.
+        let loaded = vec!["llama3.1:8b".to_string()];
+        assert_eq!(
+            select(TaskKind::General, &installed, 500.0, &loaded).map(|m| m.name.as_str()),
+            Some("llama3.1:8b")
+        );
+    }
+
+    #[test]
+    fn recommendations_follow_the_budget() {
+        assert_eq!(recommend(TaskKind::General, 32_000.0), Some("llama3.1:8b"));
+        assert_eq!(recommend(TaskKind::General, 3_000.0), Some("llama3.2:3b"));
+        assert_eq!(
+            recommend(TaskKind::Code, 2_000.0),
+            Some("qwen2.5-coder:1.5b")
+        );
+        assert_eq!(recommend(TaskKind::Code, 1_000.0), None);
+    }
+
+    #[test]
+    fn task_kind_detects_code() {
+        assert_eq!(task_kind("Refactor this function"), TaskKind::Code);
+        assert_eq!(task_kind("```rust\nlet x = 1;\n```"), TaskKind::Code);
+        assert_eq!(task_kind("Summarize the meeting notes"), TaskKind::General);
+    }
+
+    #[test]
+    fn tags_response_is_parsed() {
+        let body = json!({ "models": [
+            { "name": "llama3.2:3b", "size": 2_019_393_189u64,
+              "details": { "family": "llama", "parameter_size": "3.2B" } },
+            { "name": "nomic-embed-text:latest", "size": 274_302_450u64,
+              "details": { "family": "nomic-bert" } },
+            { "size": 1 }
+        ]});
+        let models = parse_tags(&body);
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].parameter_size.as_deref(), Some("3.2B"));
+        assert!(models[1].is_embedding());
+        assert!(!models[0].is_embedding());
+    }
+}
@@ -0,0 +1,464 @@
+//! Ollama request dispatcher — generations share a bounded number of slots
+//! and wait in a priority queue; reads never queue.
+//!
+//! Ollama only runs a few generations at once, so a long batch would
+//! otherwise delay an interactive chat turn behind it. Generations (chat,
+//! generate, batch items, background prompts) go through `dispatch`, which
+//! admits at most `CH_OLLAMA_MAX_GENERATIONS` (default 2, or
+//! `ollama.max_generations` in the config file) at a time and
+//! queues the rest by priority — `high` (interactive chat), `normal` (API
+//! callers, batches), `low` (background prompts) — FIFO within a priority.
+//! Model listings, `/api/ps` and health checks go straight to Ollama.
+//!
+//! Every generation carries a `request_id` (a batch shares one across its
+//! items). Cancelling it drops queued entries and aborts in-flight ones —
+//! the HTTP request to Ollama is dropped, which stops the generation.
+//!
+//! - `GET    /api/ollama/queue`                 — running + queued generations
+//! - `DELETE /api/ollama/requests/{request_id}` — cancel
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use std::time::Instant;
+
+use axum::Json;
+use axum::extract::Path;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
+
+/// Error of a cancelled generation.
+pub const CANCELLED: &str = "cancelled";
+
+static DISPATCHER: LazyLock<Dispatcher> = LazyLock::new(|| Dispatcher::new(max_generations()));
+
+/// `CH_OLLAMA_MAX_GENERATIONS`, else the config file, else 2.
+fn max_generations() -> usize {
+    std::env::var("CH_OLLAMA_MAX_GENERATIONS")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .or(crate::hydra_config::current().ollama.max_generations)
+        .unwrap_or(2)
+        .clamp(1, 64)
+}
+
+/// Re-read the generation limit after a config change.
+pub fn apply_config() {
+    DISPATCHER.set_max_concurrent(max_generations());
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+struct Waiter {
+    seq: u64,
+    request_id: String,
+    priority: Priority,
+    enqueued: Instant,
+    cancel: CancellationToken,
+    grant: oneshot::Sender<()>,
+}
+
+// Max-heap: highest priority first, then the oldest entry.
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.seq == other.seq
+    }
+}
+
+impl Eq for Waiter {}
+
+struct Slot {
+    request_id: String,
+    priority: Priority,
+    started: Instant,
+    cancel: CancellationToken,
+}
+
+#[derive(Default)]
+struct Inner {
+    seq: u64,
+    running: HashMap<u64, Slot>,
+    queue: BinaryHeap<Waiter>,
+}
+
+pub struct Dispatcher {
+    max_concurrent: AtomicUsize,
+    inner: Mutex<Inner>,
+}
+
+/// A queued or running generation, as listed by `/api/ollama/queue`.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub request_id: String,
+    pub priority: Priority,
+    /// Time running, or time waiting for queued entries.
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSnapshot {
+    pub max_concurrent: usize,
+    pub running: Vec<QueueEntry>,
+    /// In the order they will start.
+    pub queued: Vec<QueueEntry>,
+}
+
+/// Frees the slot (or queue entry) of one generation, however it ends.
+struct Release<'a> {
+    dispatcher: &'a Dispatcher,
+    seq: u64,
+}
+
+impl Drop for Release<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.dispatcher.lock();
+        inner.queue.retain(|w| w.seq != self.seq);
+        inner.running.remove(&self.seq);
+        self.dispatcher.admit(&mut inner);
+    }
+}
+
+impl Dispatcher {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent: AtomicUsize::new(max_concurrent.max(1)),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn max_concurrent(&self) -> usize {
+        self.max_concurrent.load(AtomicOrdering::Relaxed)
+    }
+
+    /// Change the slot count. Growing admits waiting generations at once;
+    /// shrinking lets running ones finish.
+    pub fn set_max_concurrent(&self, max_concurrent: usize) {
+        self.max_concurrent
+            .store(max_concurrent.max(1), AtomicOrdering::Relaxed);
+        let mut inner = self.lock();
+        self.admit(&mut inner);
+    }
+
+    /// Start queued generations while slots are free.
+    fn admit(&self, inner: &mut Inner) {
+        while inner.running.len() < self.max_concurrent() {
+            let Some(waiter) = inner.queue.pop() else {
+                break;
+            };
+            // A waiter whose caller went away is skipped.
+            if waiter.grant.send(()).is_ok() {
+                inner.running.insert(
+                    waiter.seq,
+                    Slot {
+                        request_id: waiter.request_id,
+                        priority: waiter.priority,
+                        started: Instant::now(),
+                        cancel: waiter.cancel,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Run `generation` once a slot is free; `Err(CANCELLED)` when the
+    /// request is cancelled while queued or running.
+    pub async fn run<T, F>(
+        &self,
+        request_id: &str,
+        priority: Priority,
+        generation: F,
+    ) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        let cancel = CancellationToken::new();
+        let (seq, granted) = {
+            let mut inner = self.lock();
+            inner.seq += 1;
+            let seq = inner.seq;
+            if inner.running.len() < self.max_concurrent() && inner.queue.is_empty() {
+                inner.running.insert(
+                    seq,
+                    Slot {
+                        request_id: request_id.to_string(),
+                        priority,
+                        started: Instant::now(),
+                        cancel: cancel.clone(),
+                    },
+                );
+                (seq, None)
+            } else {
+                let (grant, granted) = oneshot::channel();
+                inner.queue.push(Waiter {
+                    seq,
+                    request_id: request_id.to_string(),
+                    priority,
+                    enqueued: Instant::now(),
+                    cancel: cancel.clone(),
+                    grant,
+                });
+                (seq, Some(granted))
+            }
+        };
+        let _release = Release {
+            dispatcher: self,
+            seq,
+        };
+        // Cancelling a queued entry drops its sender.
+        if let Some(granted) = granted
+            && granted.await.is_err()
+        {
+            return Err(CANCELLED.to_string());
+        }
+        tokio::select! {
+            result = generation => result,
+            _ = cancel.cancelled() => Err(CANCELLED.to_string()),
+        }
+    }
+
+    /// Cancel every queued and running generation of `request_id`; returns how many.
+    pub fn cancel(&self, request_id: &str) -> usize {
+        let mut inner = self.lock();
+        let queued = inner.queue.len();
+        inner.queue.retain(|w| w.request_id != request_id);
+        let mut cancelled = queued - inner.queue.len();
+        for slot in inner.running.values() {
+            if slot.request_id == request_id && !slot.cancel.is_cancelled() {
+                slot.cancel.cancel();
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let inner = self.lock();
+        let mut running: Vec<(u64, QueueEntry)> = inner
+            .running
+            .iter()
+            .map(|(seq, slot)| {
+                (
+                    *seq,
+                    QueueEntry {
+                        request_id: slot.request_id.clone(),
+                        priority: slot.priority,
+                        elapsed_ms: slot.started.elapsed().as_millis() as u64,
+                    },
+                )
+            })
+            .collect();
+        running.sort_by_key(|(seq, _)| *seq);
+        let mut queued: Vec<&Waiter> = inner.queue.iter().collect();
+        queued.sort_by(|a, b| b.cmp(a));
+        QueueSnapshot {
+            max_concurrent: self.max_concurrent(),
+            running: running.into_iter().map(|(_, entry)| entry).collect(),
+            queued: queued
+                .into_iter()
+                .map(|w| QueueEntry {
+                    request_id: w.request_id.clone(),
+                    priority: w.priority,
+                    elapsed_ms: w.enqueued.elapsed().as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Run an Ollama generation through the shared dispatcher.
+pub async fn dispatch<T, F>(
+    request_id: &str,
+    priority: Priority,
+    generation: F,
+) -> Result<T, String>
+where
+    F: Future<Output = Result<T, String>>,
+{
+    DISPATCHER.run(request_id, priority, generation).await
+}
+
+/// Running + queued generations of the shared dispatcher.
+pub fn snapshot() -> QueueSnapshot {
+    DISPATCHER.snapshot()
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/ollama/queue`
+pub async fn queue() -> Json<QueueSnapshot> {
+    Json(snapshot())
+}
+
+/// `DELETE /api/ollama/requests/{request_id}`
+pub async fn cancel_request(
+    Path(request_id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    match DISPATCHER.cancel(&request_id) {
+        0 => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No queued or running Ollama request with this id" })),
+        )),
+        cancelled => Ok(Json(json!({
+            "request_id": request_id,
+            "cancelled": cancelled,
+        }))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Poll until the dispatcher state passes `check`.
+    async fn until(d: &Dispatcher, check: impl Fn(&QueueSnapshot) -> bool) {
+        for _ in 0..200 {
+            if check(&d.snapshot()) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("dispatcher never reached the expected state");
+    }
+
+    #[tokio::test]
+    async fn queued_generations_start_by_priority() {
+        let d = Arc::new(Dispatcher::new(1));
+        let (release, hold) = oneshot::channel::<()>();
+        let blocker = {
+            let d = d.clone();
+            tokio::spawn(async move {
+                d.run("blocker", Priority::Normal, async {
+                    hold.await.ok();
+                    Ok(())
+                })
+                .await
+            })
+        };
+        until(&d, |s| s.running.len() == 1).await;
+
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let mut tasks = Vec::new();
+        for (id, priority) in [
+            ("low", Priority::Low),
+            ("normal", Priority::Normal),
+            ("high", Priority::High),
+        ] {
+            let (task_d, order) = (d.clone(), order.clone());
+            tasks.push(tokio::spawn(async move {
+                task_d
+                    .run(id, priority, async {
+                        order.lock().unwrap().push(id);
+                        Ok(())
+                    })
+                    .await
+            }));
+            let queued = tasks.len();
+            until(&d, |s| s.queued.len() == queued).await;
+        }
+        let queued: Vec<_> = d
+            .snapshot()
+            .queued
+            .into_iter()
+            .map(|e| e.request_id)
+            .collect();
+        assert_eq!(queued, ["high", "normal", "low"]);
+
+        release.send(()).unwrap();
+        blocker.await.unwrap().unwrap();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(*order.lock().unwrap(), ["high", "normal", "low"]);
+        assert!(d.snapshot().running.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cancel_aborts_running_and_queued_requests() {
+        let d = Arc::new(Dispatcher::new(1));
+        let spawn_pending = |id: &'static str| {
+            let d = d.clone();
+            tokio::spawn(async move {
+                d.run(
+                    id,
+                    Priority::Normal,
+                    std::future::pending::<Result<(), String>>(),
+                )
+                .await
+            })
+        };
+        let running = spawn_pending("a");
+        until(&d, |s| s.running.len() == 1).await;
+        let queued = spawn_pending("b");
+        until(&d, |s| s.queued.len() == 1).await;
+
+        assert_eq!(d.cancel("b"), 1);
+        assert_eq!(queued.await.unwrap(), Err(CANCELLED.to_string()));
+        assert_eq!(d.cancel("a"), 1);
+        assert_eq!(running.await.unwrap(), Err(CANCELLED.to_string()));
+        assert_eq!(d.cancel("a"), 0);
+
+        let snapshot = d.snapshot();
+        assert!(snapshot.running.is_empty() && snapshot.queued.is_empty());
+    }
+
+    #[tokio::test]
+    async fn raising_the_limit_admits_queued_generations() {
+        let d = Arc::new(Dispatcher::new(1));
+        let spawn_pending = |id: &'static str| {
+            let d = d.clone();
+            tokio::spawn(async move {
+                d.run(
+                    id,
+                    Priority::Normal,
+                    std::future::pending::<Result<(), String>>(),
+                )
+                .await
+            })
+        };
+        let _a = spawn_pending("a");
+        until(&d, |s| s.running.len() == 1).await;
+        let _b = spawn_pending("b");
+        until(&d, |s| s.queued.len() == 1).await;
+
+        d.set_max_concurrent(2);
+        until(&d, |s| s.running.len() == 2 && s.queued.is_empty()).await;
+        d.set_max_concurrent(0);
+        assert_eq!(d.snapshot().max_concurrent, 1);
+        assert_eq!(d.snapshot().running.len(), 2);
+        d.cancel("a");
+        d.cancel("b");
+    }
+}
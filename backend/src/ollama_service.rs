@@ -0,0 +1,536 @@
+//! Ollama service lifecycle — find the executable, start and stop the server
+//! on Windows, macOS and Linux.
+//!
+//! Executable discovery (`find_executable`) goes through `paths.rs`:
+//! `CH_OLLAMA_BIN`, then `PATH`, then the platform's install locations
+//! (`%LOCALAPPDATA%\Programs\Ollama`, Homebrew, the app bundle, snap, ...).
+//!
+//! Starting prefers the service manager: a systemd `ollama.service` (user
+//! unit, then system unit) is started with `systemctl`. Otherwise
+//! `ollama serve` is spawned detached — its own process group on Unix
+//! (setsid/nohup semantics: no terminal signals, output to
+//! `<data dir>/ollama.log`), a detached process group on Windows — so the
+//! server outlives the backend. `OLLAMA_HOST` is set from the gateway's
+//! Ollama URL. Stopping mirrors this: `systemctl stop`, else the process
+//! started here, else any `ollama` server process.
+//!
+//! Health watchdog (`spawn`): pings Ollama every `CH_OLLAMA_WATCHDOG_SECS`
+//! (default 30, `0` = off) and emits `ollama-status-changed` on every
+//! up/down transition. With `CH_OLLAMA_AUTO_RESTART=true`, a server that was
+//! up and then fails `CH_OLLAMA_RESTART_AFTER` pings in a row (default 3) is
+//! restarted via `stop_ollama` + `start_ollama`. Restarts back off
+//! exponentially from `CH_OLLAMA_RESTART_BACKOFF_SECS` (default 30, capped at
+//! 10 min) and stop after `CH_OLLAMA_MAX_RESTARTS` (default 5); the count
+//! resets once Ollama stays healthy for a while. Events go out on
+//! `/api/ollama/events` (`ollama-status-changed`, `ollama-restart`).
+//!
+//! - `GET  /api/ollama/service`       — running, version, executable, service manager, watchdog
+//! - `POST /api/ollama/service/start` — start (no-op when already running)
+//! - `POST /api/ollama/service/stop`  — stop
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::state::AppState;
+
+pub const JOB_OLLAMA_WATCHDOG: &str = "ollama_watchdog";
+const UNIT: &str = "ollama.service";
+const LOG_FILE: &str = "ollama.log";
+const VERSION_TIMEOUT: Duration = Duration::from_secs(3);
+/// How long a started server gets to answer.
+const START_TIMEOUT: Duration = Duration::from_secs(20);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// PID of the `ollama serve` process spawned by this backend.
+static STARTED_PID: Mutex<Option<u32>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceManager {
+    /// `systemctl --user` unit.
+    SystemdUser,
+    /// System-wide systemd unit (starting/stopping may need privileges).
+    SystemdSystem,
+    /// Plain `ollama serve` process.
+    Process,
+}
+
+/// Ollama executable (see `paths.rs`).
+pub fn find_executable() -> Option<PathBuf> {
+    crate::paths::find(&crate::paths::OLLAMA)
+}
+
+/// `OLLAMA_HOST` value (`host:port`) for a base URL.
+fn ollama_host(base_url: &str) -> String {
+    base_url
+        .trim_start_matches("http://")
+        .trim_start_matches("https://")
+        .trim_end_matches('/')
+        .to_string()
+}
+
+/// Run a short command; `Ok` with stdout on success.
+async fn run(program: &str, args: &[&str]) -> Result<String, String> {
+    let output = tokio::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, output)
+        .await
+        .map_err(|_| format!("{} timed out", program))?
+        .map_err(|e| format!("{}: {}", program, e))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+/// How Ollama is managed on this machine.
+pub async fn service_manager() -> ServiceManager {
+    if cfg!(target_os = "linux") {
+        if run("systemctl", &["--user", "cat", UNIT]).await.is_ok() {
+            return ServiceManager::SystemdUser;
+        }
+        if run("systemctl", &["cat", UNIT]).await.is_ok() {
+            return ServiceManager::SystemdSystem;
+        }
+    }
+    ServiceManager::Process
+}
+
+/// Server version when Ollama answers.
+pub async fn ollama_version(state: &AppState) -> Option<String> {
+    let base = crate::ollama::base_url(state).ok()?;
+    let resp = state
+        .http_client
+        .get(format!("{}/api/version", base))
+        .timeout(VERSION_TIMEOUT)
+        .send()
+        .await
+        .ok()
+        .filter(|r| r.status().is_success())?;
+    let body: Value = resp.json().await.ok()?;
+    Some(body["version"].as_str().unwrap_or("unknown").to_string())
+}
+
+/// Spawn `ollama serve` so that it outlives this process.
+fn spawn_detached(exe: &Path, host: &str) -> Result<u32, String> {
+    let log_dir = crate::metrics_snapshot::data_dir().clone();
+    std::fs::create_dir_all(&log_dir).map_err(|e| format!("{}: {}", log_dir.display(), e))?;
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_dir.join(LOG_FILE))
+        .map_err(|e| format!("{}: {}", LOG_FILE, e))?;
+    let log_err = log.try_clone().map_err(|e| e.to_string())?;
+
+    let mut cmd = tokio::process::Command::new(exe);
+    cmd.arg("serve")
+        .env("OLLAMA_HOST", host)
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err)
+        .kill_on_drop(false);
+    #[cfg(unix)]
+    {
+        // Own process group: no SIGINT/SIGHUP from the backend's terminal.
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+        cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    }
+    // Dropping the handle neither kills nor leaks the child (tokio reaps it).
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("cannot start {}: {}", exe.display(), e))?;
+    child
+        .id()
+        .ok_or_else(|| "ollama serve exited immediately".to_string())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartOutcome {
+    pub already_running: bool,
+    pub manager: ServiceManager,
+    pub pid: Option<u32>,
+    pub version: Option<String>,
+}
+
+/// Start the Ollama server unless it already answers, and wait until it does.
+pub async fn start_ollama(state: &AppState) -> Result<StartOutcome, String> {
+    let manager = service_manager().await;
+    if let Some(version) = ollama_version(state).await {
+        return Ok(StartOutcome {
+            already_running: true,
+            manager,
+            pid: None,
+            version: Some(version),
+        });
+    }
+    let pid = match manager {
+        ServiceManager::SystemdUser => {
+            run("systemctl", &["--user", "start", UNIT]).await?;
+            None
+        }
+        ServiceManager::SystemdSystem => {
+            run("systemctl", &["start", UNIT]).await?;
+            None
+        }
+        ServiceManager::Process => {
+            let exe = find_executable().ok_or_else(|| {
+                "Ollama executable not found (install Ollama or set CH_OLLAMA_BIN)".to_string()
+            })?;
+            let host = ollama_host(&crate::ollama::base_url(state)?);
+            let pid = spawn_detached(&exe, &host)?;
+            *STARTED_PID.lock().unwrap_or_else(|e| e.into_inner()) = Some(pid);
+            tracing::info!("ollama_service: started {} (pid {})", exe.display(), pid);
+            Some(pid)
+        }
+    };
+
+    let deadline = tokio::time::Instant::now() + START_TIMEOUT;
+    while tokio::time::Instant::now() < deadline {
+        if let Some(version) = ollama_version(state).await {
+            return Ok(StartOutcome {
+                already_running: false,
+                manager,
+                pid,
+                version: Some(version),
+            });
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    Err(format!(
+        "Ollama did not answer within {}s after starting",
+        START_TIMEOUT.as_secs()
+    ))
+}
+
+/// Stop the Ollama server.
+pub async fn stop_ollama() -> Result<ServiceManager, String> {
+    let manager = service_manager().await;
+    match manager {
+        ServiceManager::SystemdUser => {
+            run("systemctl", &["--user", "stop", UNIT]).await?;
+        }
+        ServiceManager::SystemdSystem => {
+            run("systemctl", &["stop", UNIT]).await?;
+        }
+        ServiceManager::Process => {
+            let pid = STARTED_PID.lock().unwrap_or_else(|e| e.into_inner()).take();
+            let pid = pid.map(|p| p.to_string());
+            let killed = match (&pid, cfg!(windows)) {
+                (Some(pid), true) => run("taskkill", &["/PID", pid, "/T", "/F"]).await,
+                (Some(pid), false) => run("kill", &["-TERM", pid]).await,
+                (None, true) => run("taskkill", &["/IM", "ollama.exe", "/F"]).await,
+                (None, false) => run("pkill", &["-x", "ollama"]).await,
+            };
+            killed.map_err(|e| format!("cannot stop Ollama: {}", e))?;
+        }
+    }
+    tracing::info!("ollama_service: stopped ({:?})", manager);
+    Ok(manager)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Health watchdog
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Healthy pings in a row after which the restart count and backoff reset.
+const STABLE_PINGS: u32 = 10;
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+static MONITOR: Mutex<Monitor> = Mutex::new(Monitor::new());
+
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// Ping interval; `None` disables the watchdog.
+    pub interval: Option<Duration>,
+    pub auto_restart: bool,
+    /// Consecutive failed pings before a restart.
+    pub restart_after: u32,
+    pub max_restarts: u32,
+    /// Delay before the second restart; doubles for every further one.
+    pub backoff: Duration,
+}
+
+impl WatchdogConfig {
+    fn from_env() -> Self {
+        let env_num = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        Self {
+            interval: match env_num("CH_OLLAMA_WATCHDOG_SECS").unwrap_or(30) {
+                0 => None,
+                secs => Some(Duration::from_secs(secs.max(5))),
+            },
+            auto_restart: std::env::var("CH_OLLAMA_AUTO_RESTART")
+                .is_ok_and(|v| matches!(v.trim(), "1" | "true" | "yes" | "on")),
+            restart_after: env_num("CH_OLLAMA_RESTART_AFTER")
+                .unwrap_or(3)
+                .clamp(1, 100) as u32,
+            max_restarts: env_num("CH_OLLAMA_MAX_RESTARTS").unwrap_or(5).min(100) as u32,
+            backoff: Duration::from_secs(env_num("CH_OLLAMA_RESTART_BACKOFF_SECS").unwrap_or(30)),
+        }
+    }
+}
+
+/// Outcome of one ping.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Observed {
+    /// Up/down differs from the previous ping (or this is the first one).
+    changed: bool,
+    restart: bool,
+}
+
+/// Watchdog state between pings.
+#[derive(Debug, Serialize)]
+struct Monitor {
+    running: Option<bool>,
+    /// Restarts only apply to a server that has been seen up.
+    #[serde(skip)]
+    seen_up: bool,
+    consecutive_failures: u32,
+    #[serde(skip)]
+    healthy_streak: u32,
+    restarts: u32,
+    #[serde(skip)]
+    next_restart: Option<Instant>,
+}
+
+impl Monitor {
+    const fn new() -> Self {
+        Self {
+            running: None,
+            seen_up: false,
+            consecutive_failures: 0,
+            healthy_streak: 0,
+            restarts: 0,
+            next_restart: None,
+        }
+    }
+
+    fn observe(&mut self, cfg: &WatchdogConfig, up: bool, now: Instant) -> Observed {
+        let changed = self.running != Some(up);
+        self.running = Some(up);
+        if up {
+            self.seen_up = true;
+            self.consecutive_failures = 0;
+            self.healthy_streak += 1;
+            if self.healthy_streak >= STABLE_PINGS {
+                self.restarts = 0;
+                self.next_restart = None;
+            }
+            return Observed {
+                changed,
+                restart: false,
+            };
+        }
+        self.healthy_streak = 0;
+        self.consecutive_failures += 1;
+        let restart = cfg.auto_restart
+            && self.seen_up
+            && self.consecutive_failures >= cfg.restart_after
+            && self.restarts < cfg.max_restarts
+            && self.next_restart.is_none_or(|at| now >= at);
+        if restart {
+            let backoff = cfg.backoff.saturating_mul(1 << self.restarts.min(16));
+            self.restarts += 1;
+            self.next_restart = Some(now + backoff.min(MAX_BACKOFF));
+        }
+        Observed { changed, restart }
+    }
+}
+
+fn monitor() -> std::sync::MutexGuard<'static, Monitor> {
+    MONITOR.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Ping Ollama periodically; emit status changes and restart when enabled.
+pub fn spawn(state: AppState) {
+    let cfg = WatchdogConfig::from_env();
+    let Some(interval) = cfg.interval else {
+        return;
+    };
+    state.job_schedule.start(JOB_OLLAMA_WATCHDOG, interval);
+    tokio::spawn(async move {
+        tracing::info!(
+            "ollama_service: watchdog started (interval={}s, auto_restart={})",
+            interval.as_secs(),
+            cfg.auto_restart
+        );
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_OLLAMA_WATCHDOG);
+            let version = ollama_version(&state).await;
+            let (observed, failures, restarts) = {
+                let mut m = monitor();
+                let observed = m.observe(&cfg, version.is_some(), Instant::now());
+                (observed, m.consecutive_failures, m.restarts)
+            };
+            if observed.changed {
+                tracing::info!(
+                    "ollama_service: Ollama is {}",
+                    if version.is_some() { "up" } else { "down" }
+                );
+                crate::ollama::emit(
+                    "ollama-status-changed",
+                    json!({
+                        "running": version.is_some(),
+                        "version": version,
+                        "consecutive_failures": failures,
+                    }),
+                );
+            }
+            if observed.restart {
+                tracing::warn!(
+                    "ollama_service: {} failed pings, restarting Ollama (attempt {}/{})",
+                    failures,
+                    restarts,
+                    cfg.max_restarts
+                );
+                // A hung server may still hold the port; a missing one is fine.
+                if let Err(e) = stop_ollama().await {
+                    tracing::debug!("ollama_service: stop before restart: {}", e);
+                }
+                let result = start_ollama(&state).await;
+                if let Err(e) = &result {
+                    tracing::warn!("ollama_service: restart failed: {}", e);
+                }
+                crate::ollama::emit(
+                    "ollama-restart",
+                    json!({
+                        "attempt": restarts,
+                        "max_restarts": cfg.max_restarts,
+                        "ok": result.is_ok(),
+                        "error": result.err(),
+                    }),
+                );
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/ollama/service`
+pub async fn service_status(State(state): State<AppState>) -> Json<Value> {
+    let (version, manager) = tokio::join!(ollama_version(&state), service_manager());
+    Json(json!({
+        "running": version.is_some(),
+        "version": version,
+        "executable": find_executable().map(|p| p.display().to_string()),
+        "manager": manager,
+        "started_pid": *STARTED_PID.lock().unwrap_or_else(|e| e.into_inner()),
+        "watchdog": &*monitor(),
+    }))
+}
+
+/// `POST /api/ollama/service/start`
+pub async fn start_service(
+    State(state): State<AppState>,
+) -> Result<Json<StartOutcome>, (StatusCode, Json<Value>)> {
+    start_ollama(&state)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))
+}
+
+/// `POST /api/ollama/service/stop`
+pub async fn stop_service() -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let manager = stop_ollama().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": e })),
+        )
+    })?;
+    Ok(Json(json!({ "stopped": true, "manager": manager })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(auto_restart: bool) -> WatchdogConfig {
+        WatchdogConfig {
+            interval: Some(Duration::from_secs(30)),
+            auto_restart,
+            restart_after: 2,
+            max_restarts: 2,
+            backoff: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn watchdog_reports_transitions_and_restarts_with_backoff() {
+        let cfg = policy(true);
+        let mut m = Monitor::new();
+        let t0 = Instant::now();
+        // Never seen up: status is reported, but nothing to restart.
+        assert!(m.observe(&cfg, false, t0).changed);
+        assert_eq!(m.observe(&cfg, false, t0), Observed::default());
+
+        assert!(m.observe(&cfg, true, t0).changed);
+        assert!(m.observe(&cfg, false, t0).changed);
+        let second = m.observe(&cfg, false, t0);
+        assert!(second.restart && !second.changed);
+        // Backoff: 60s before the second restart.
+        assert!(!m.observe(&cfg, false, t0 + Duration::from_secs(30)).restart);
+        assert!(m.observe(&cfg, false, t0 + Duration::from_secs(61)).restart);
+        // max_restarts reached.
+        assert!(
+            !m.observe(&cfg, false, t0 + Duration::from_secs(3600))
+                .restart
+        );
+        assert_eq!(m.restarts, 2);
+
+        // A stable recovery resets the budget.
+        for _ in 0..STABLE_PINGS {
+            m.observe(&cfg, true, t0);
+        }
+        assert_eq!(m.restarts, 0);
+        m.observe(&cfg, false, t0);
+        assert!(m.observe(&cfg, false, t0).restart);
+    }
+
+    #[test]
+    fn watchdog_without_auto_restart_only_reports() {
+        let cfg = policy(false);
+        let mut m = Monitor::new();
+        let t0 = Instant::now();
+        m.observe(&cfg, true, t0);
+        for _ in 0..5 {
+            assert!(!m.observe(&cfg, false, t0).restart);
+        }
+        assert_eq!(m.consecutive_failures, 5);
+    }
+
+    #[test]
+    fn ollama_host_drops_the_scheme() {
+        assert_eq!(ollama_host("http://localhost:11434"), "localhost:11434");
+        assert_eq!(ollama_host("https://gpu-box:8080/"), "gpu-box:8080");
+    }
+}
@@ -0,0 +1,230 @@
+//! Dashboard overview — one call for tabs, queue depth, provider health and
+//! recent completions.
+//!
+//! Backend-side state (open `/ws/chat` tabs, background and Ollama queues,
+//! provider counters and health, finished prompts) is read in-process.
+//! Out-of-process clients (the launcher, desktop GUI windows) report their
+//! own state over local HTTP: `PUT /api/overview/clients/{client}` with any
+//! JSON object, e.g. `{"tabs": [{"title": "…", "busy": true}]}`. Reports are
+//! kept in memory and dropped when a client has not reported for
+//! `CLIENT_TTL` — clients re-send on change and at least every 30 s.
+//!
+//! - `GET    /api/overview`
+//! - `PUT    /api/overview/clients/{client}`
+//! - `DELETE /api/overview/clients/{client}` — client shutting down
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::api_error::bad_request;
+use crate::live_updates::Topic;
+use crate::state::AppState;
+
+const CLIENT_TTL: Duration = Duration::from_secs(90);
+const MAX_CLIENTS: usize = 32;
+const MAX_REPORT_BYTES: usize = 64 * 1024;
+const RECENT_COMPLETIONS: i64 = 10;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientReport {
+    pub client: String,
+    pub reported_at: DateTime<Utc>,
+    pub state: Value,
+}
+
+static CLIENTS: LazyLock<Mutex<HashMap<String, ClientReport>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn valid_client_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Reports newer than `CLIENT_TTL` at `now`, sorted by client; stale ones are removed.
+fn live_clients(
+    clients: &mut HashMap<String, ClientReport>,
+    now: DateTime<Utc>,
+) -> Vec<ClientReport> {
+    let ttl = chrono::Duration::from_std(CLIENT_TTL).unwrap_or_default();
+    clients.retain(|_, report| now - report.reported_at < ttl);
+    let mut live: Vec<ClientReport> = clients.values().cloned().collect();
+    live.sort_by(|a, b| a.client.cmp(&b.client));
+    live
+}
+
+fn lock_clients() -> std::sync::MutexGuard<'static, HashMap<String, ClientReport>> {
+    CLIENTS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Finished background prompts and assistant replies, newest first.
+async fn recent_completions(state: &AppState) -> Result<Value, sqlx::Error> {
+    let background: Vec<(i64, Option<String>, String, Option<DateTime<Utc>>)> = sqlx::query_as(
+        "SELECT id, model, status, finished_at FROM ch_background_prompts \
+         WHERE finished_at IS NOT NULL ORDER BY finished_at DESC LIMIT $1",
+    )
+    .bind(RECENT_COMPLETIONS)
+    .fetch_all(&state.db)
+    .await?;
+    let chat: Vec<(uuid::Uuid, String, Option<String>, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT m.session_id, s.title, m.model, m.created_at \
+         FROM ch_messages m JOIN ch_sessions s ON s.id = m.session_id \
+         WHERE m.role = 'assistant' ORDER BY m.created_at DESC LIMIT $1",
+    )
+    .bind(RECENT_COMPLETIONS)
+    .fetch_all(&state.db)
+    .await?;
+    Ok(json!({
+        "background": background
+            .into_iter()
+            .map(|(id, model, status, finished_at)| json!({
+                "id": id,
+                "model": model,
+                "status": status,
+                "finished_at": finished_at,
+            }))
+            .collect::<Vec<_>>(),
+        "chat": chat
+            .into_iter()
+            .map(|(session_id, title, model, created_at)| json!({
+                "session_id": session_id,
+                "title": title,
+                "model": model,
+                "completed_at": created_at,
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/overview`
+pub async fn get_overview(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let internal = |e: String| {
+        tracing::warn!("overview: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to collect overview" })),
+        )
+    };
+    let metrics = crate::metrics_snapshot::collect(&state)
+        .await
+        .map_err(internal)?;
+    let completions = recent_completions(&state)
+        .await
+        .map_err(|e| internal(e.to_string()))?;
+    let health = crate::live_updates::sample(&state, Topic::Health).await;
+    let clients = live_clients(&mut lock_clients(), Utc::now());
+
+    Ok(Json(json!({
+        "generated_at": metrics.generated_at,
+        "tabs": {
+            "websocket": metrics.active_sessions,
+            "clients": clients,
+        },
+        "queue": {
+            "background": metrics.queue,
+            "ollama": crate::ollama_queue::snapshot(),
+        },
+        "providers": {
+            "health": health,
+            "usage_24h": metrics.providers,
+        },
+        "recent_completions": completions,
+    })))
+}
+
+/// `PUT /api/overview/clients/{client}`
+pub async fn report_client(
+    Path(client): Path<String>,
+    Json(body): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if !valid_client_name(&client) {
+        return Err(bad_request(
+            "client must be 1-64 characters of letters, digits, '-' or '_'",
+        ));
+    }
+    if !body.is_object() {
+        return Err(bad_request("report must be a JSON object"));
+    }
+    if body.to_string().len() > MAX_REPORT_BYTES {
+        return Err(bad_request("report is too large"));
+    }
+    let now = Utc::now();
+    let mut clients = lock_clients();
+    live_clients(&mut clients, now);
+    if !clients.contains_key(&client) && clients.len() >= MAX_CLIENTS {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({ "error": "Too many reporting clients" })),
+        ));
+    }
+    clients.insert(
+        client.clone(),
+        ClientReport {
+            client: client.clone(),
+            reported_at: now,
+            state: body,
+        },
+    );
+    Ok(Json(json!({ "client": client, "reported_at": now })))
+}
+
+/// `DELETE /api/overview/clients/{client}`
+pub async fn remove_client(Path(client): Path<String>) -> StatusCode {
+    match lock_clients().remove(&client) {
+        Some(_) => StatusCode::NO_CONTENT,
+        None => StatusCode::NOT_FOUND,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(client: &str, age_secs: i64, now: DateTime<Utc>) -> ClientReport {
+        ClientReport {
+            client: client.to_string(),
+            reported_at: now - chrono::Duration::seconds(age_secs),
+            state: json!({}),
+        }
+    }
+
+    #[test]
+    fn stale_client_reports_are_dropped() {
+        let now = Utc::now();
+        let mut clients = HashMap::new();
+        for (name, age) in [("launcher", 10), ("gui-2", 5), ("gui-1", 600)] {
+            clients.insert(name.to_string(), report(name, age, now));
+        }
+        let live: Vec<String> = live_clients(&mut clients, now)
+            .into_iter()
+            .map(|r| r.client)
+            .collect();
+        assert_eq!(live, ["gui-2", "launcher"]);
+        assert_eq!(clients.len(), 2);
+    }
+
+    #[test]
+    fn client_names_are_restricted() {
+        assert!(valid_client_name("hydra-launcher"));
+        assert!(valid_client_name("gui_1"));
+        assert!(!valid_client_name(""));
+        assert!(!valid_client_name("../etc"));
+        assert!(!valid_client_name(&"a".repeat(65)));
+    }
+}
@@ -0,0 +1,482 @@
+//! Executable discovery — one resolver for every external program the
+//! backend runs, on Windows, macOS and Linux.
+//!
+//! For each tool, the first hit wins:
+//! 1. an override: its env var, else `[paths]` in the config file (see
+//!    `hydra_config.rs`). A path is used as is; a bare name replaces the
+//!    program name searched for below.
+//! 2. the `PATH` directories. On Windows every `PATHEXT` extension is tried,
+//!    so npm shims such as `claude.cmd` are found.
+//! 3. the platform's install locations — Homebrew and `~/.local/bin` on
+//!    macOS / Linux, `%APPDATA%\npm`, `%LOCALAPPDATA%\Programs` and
+//!    `%ProgramFiles%` on Windows, plus tool-specific ones (the Claude
+//!    installer's `~/.claude/local`, the Ollama app bundle, NVSMI, ...).
+//!
+//! | tool         | program       | override              |
+//! |--------------|---------------|-----------------------|
+//! | `claude`     | `claude`      | `CH_CLAUDE_CLI_BIN`   |
+//! | `ollama`     | `ollama`      | `CH_OLLAMA_BIN`       |
+//! | `nvidia_smi` | `nvidia-smi`  | `CH_NVIDIA_SMI`       |
+//! | `piper`      | `piper`       | `CH_PIPER_BIN`        |
+//! | `edge_tts`   | `edge-tts`    | `CH_EDGE_TTS_BIN`     |
+//! | `whisper`    | `whisper-cli` | `CH_WHISPER_CPP_BIN`  |
+//!
+//! Results are cached; a miss is retried after `MISS_TTL`, and the cache is
+//! cleared when the config file changes.
+//!
+//! - `GET  /api/paths`         — resolved path and source per tool
+//! - `POST /api/paths/refresh` — drop the cache and resolve again
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// How long a tool that was not found stays "not found".
+const MISS_TTL: Duration = Duration::from_secs(60);
+/// `PATHEXT` when the variable is unset.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tool {
+    /// Name in `[paths]` and the API.
+    pub key: &'static str,
+    /// Program name without extension.
+    pub program: &'static str,
+    /// Env var overriding the location.
+    pub env: &'static str,
+}
+
+pub const CLAUDE: Tool = Tool {
+    key: "claude",
+    program: "claude",
+    env: "CH_CLAUDE_CLI_BIN",
+};
+pub const OLLAMA: Tool = Tool {
+    key: "ollama",
+    program: "ollama",
+    env: "CH_OLLAMA_BIN",
+};
+pub const NVIDIA_SMI: Tool = Tool {
+    key: "nvidia_smi",
+    program: "nvidia-smi",
+    env: "CH_NVIDIA_SMI",
+};
+pub const PIPER: Tool = Tool {
+    key: "piper",
+    program: "piper",
+    env: "CH_PIPER_BIN",
+};
+pub const EDGE_TTS: Tool = Tool {
+    key: "edge_tts",
+    program: "edge-tts",
+    env: "CH_EDGE_TTS_BIN",
+};
+pub const WHISPER: Tool = Tool {
+    key: "whisper",
+    program: "whisper-cli",
+    env: "CH_WHISPER_CPP_BIN",
+};
+
+pub const TOOLS: &[Tool] = &[CLAUDE, OLLAMA, NVIDIA_SMI, PIPER, EDGE_TTS, WHISPER];
+
+/// Where a resolved path came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Env,
+    Config,
+    Path,
+    InstallDir,
+}
+
+/// The parts of the environment discovery depends on.
+#[derive(Debug, Clone, Default)]
+struct Env {
+    os: String,
+    path: Vec<PathBuf>,
+    pathext: Vec<String>,
+    home: Option<PathBuf>,
+    app_data: Option<PathBuf>,
+    local_app_data: Option<PathBuf>,
+    program_files: Option<PathBuf>,
+    system_root: Option<PathBuf>,
+}
+
+impl Env {
+    fn current() -> Self {
+        let dir = |name: &str| {
+            std::env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            os: std::env::consts::OS.to_string(),
+            path: std::env::var_os("PATH")
+                .map(|p| std::env::split_paths(&p).collect())
+                .unwrap_or_default(),
+            pathext: std::env::var("PATHEXT")
+                .ok()
+                .filter(|v| !v.trim().is_empty())
+                .unwrap_or_else(|| DEFAULT_PATHEXT.to_string())
+                .split(';')
+                .map(|e| e.trim().to_lowercase())
+                .filter(|e| e.starts_with('.'))
+                .collect(),
+            home: dirs::home_dir(),
+            app_data: dir("APPDATA"),
+            local_app_data: dir("LOCALAPPDATA"),
+            program_files: dir("ProgramFiles"),
+            system_root: dir("SystemRoot"),
+        }
+    }
+
+    fn windows(&self) -> bool {
+        self.os == "windows"
+    }
+}
+
+/// File names `program` may have. On Windows a name without extension gets
+/// each of `exts`.
+fn file_names(program: &str, env: &Env, exts: &[String]) -> Vec<String> {
+    if !env.windows() || Path::new(program).extension().is_some() {
+        return vec![program.to_string()];
+    }
+    exts.iter()
+        .map(|ext| format!("{}{}", program, ext))
+        .collect()
+}
+
+/// Install directories of `tool` on `env.os`, most specific first.
+fn install_dirs(tool: &Tool, env: &Env) -> Vec<PathBuf> {
+    let home = env.home.as_deref();
+    let mut dirs = Vec::new();
+    let mut push = |dir: Option<PathBuf>| dirs.extend(dir);
+    match env.os.as_str() {
+        "windows" => match tool.key {
+            "claude" => {
+                push(home.map(|h| h.join(".local").join("bin")));
+                push(env.app_data.as_ref().map(|d| d.join("npm")));
+            }
+            "ollama" => {
+                push(
+                    env.local_app_data
+                        .as_ref()
+                        .map(|d| d.join("Programs").join("Ollama")),
+                );
+                push(env.program_files.as_ref().map(|d| d.join("Ollama")));
+            }
+            "nvidia_smi" => {
+                push(env.system_root.as_ref().map(|d| d.join("System32")));
+                push(
+                    env.program_files
+                        .as_ref()
+                        .map(|d| d.join("NVIDIA Corporation").join("NVSMI")),
+                );
+            }
+            _ => {
+                push(
+                    env.app_data
+                        .as_ref()
+                        .map(|d| d.join("Python").join("Scripts")),
+                );
+                push(home.map(|h| h.join(".local").join("bin")));
+            }
+        },
+        "macos" => {
+            push(Some(PathBuf::from("/opt/homebrew/bin")));
+            push(Some(PathBuf::from("/usr/local/bin")));
+            push(home.map(|h| h.join(".local/bin")));
+            match tool.key {
+                "claude" => {
+                    push(home.map(|h| h.join(".claude/local")));
+                    push(home.map(|h| h.join(".npm-global/bin")));
+                }
+                "ollama" => {
+                    push(Some(PathBuf::from(
+                        "/Applications/Ollama.app/Contents/Resources",
+                    )));
+                    push(home.map(|h| h.join("Applications/Ollama.app/Contents/Resources")));
+                }
+                _ => {}
+            }
+        }
+        _ => {
+            push(Some(PathBuf::from("/usr/local/bin")));
+            push(Some(PathBuf::from("/usr/bin")));
+            push(home.map(|h| h.join(".local/bin")));
+            push(Some(PathBuf::from("/home/linuxbrew/.linuxbrew/bin")));
+            push(Some(PathBuf::from("/snap/bin")));
+            if tool.key == "claude" {
+                push(home.map(|h| h.join(".claude/local")));
+                push(home.map(|h| h.join(".npm-global/bin")));
+            }
+        }
+    }
+    dirs
+}
+
+/// Where to look for `program` (the tool's, or an override name), in order.
+fn candidates(tool: &Tool, program: &str, env: &Env) -> Vec<(PathBuf, Source)> {
+    let on_path = file_names(program, env, &env.pathext);
+    // Installers ship executables or npm `.cmd` shims only.
+    let installed = file_names(program, env, &[".exe".to_string(), ".cmd".to_string()]);
+    let mut paths = Vec::new();
+    for dir in &env.path {
+        paths.extend(on_path.iter().map(|n| (dir.join(n), Source::Path)));
+    }
+    for dir in install_dirs(tool, env) {
+        paths.extend(installed.iter().map(|n| (dir.join(n), Source::InstallDir)));
+    }
+    paths
+}
+
+fn is_path(raw: &str) -> bool {
+    raw.contains('/') || raw.contains('\\') || Path::new(raw).is_absolute()
+}
+
+/// An override for `tool`: env var, else the config file.
+fn override_for(tool: &Tool) -> Option<(String, Source)> {
+    std::env::var(tool.env)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .map(|v| (v, Source::Env))
+        .or_else(|| {
+            crate::hydra_config::current()
+                .paths
+                .get(tool.key)
+                .map(|v| (v.trim().to_string(), Source::Config))
+                .filter(|(v, _)| !v.is_empty())
+        })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Resolved {
+    pub tool: &'static str,
+    pub path: Option<PathBuf>,
+    pub source: Option<Source>,
+    /// The override in effect, if any.
+    #[serde(rename = "override")]
+    pub override_value: Option<String>,
+}
+
+fn discover(
+    tool: &Tool,
+    over: Option<(String, Source)>,
+    env: &Env,
+    is_file: impl Fn(&Path) -> bool,
+) -> Resolved {
+    let mut resolved = Resolved {
+        tool: tool.key,
+        path: None,
+        source: None,
+        override_value: over.as_ref().map(|(v, _)| v.clone()),
+    };
+    let program = match &over {
+        Some((raw, source)) if is_path(raw) => {
+            let path = PathBuf::from(raw);
+            if is_file(&path) {
+                resolved.path = Some(path);
+                resolved.source = Some(*source);
+            }
+            return resolved;
+        }
+        Some((name, _)) => name.as_str(),
+        None => tool.program,
+    };
+    if let Some((path, source)) = candidates(tool, program, env)
+        .into_iter()
+        .find(|(p, _)| is_file(p))
+    {
+        resolved.path = Some(path);
+        resolved.source = Some(match (&over, source) {
+            (Some((_, over_source)), Source::Path) => *over_source,
+            (_, source) => source,
+        });
+    }
+    resolved
+}
+
+static CACHE: Mutex<Option<HashMap<&'static str, (Resolved, Instant)>>> = Mutex::new(None);
+
+/// Resolve `tool` (cached).
+pub fn resolve(tool: &Tool) -> Resolved {
+    if let Some((hit, at)) = CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .and_then(|c| c.get(tool.key))
+        && (hit.path.is_some() || at.elapsed() < MISS_TTL)
+    {
+        return hit.clone();
+    }
+    let resolved = discover(tool, override_for(tool), &Env::current(), Path::is_file);
+    if resolved.path.is_none() {
+        tracing::debug!("paths: {} not found", tool.program);
+    }
+    CACHE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(tool.key, (resolved.clone(), Instant::now()));
+    resolved
+}
+
+/// Path of `tool` if it was found.
+pub fn find(tool: &Tool) -> Option<PathBuf> {
+    resolve(tool).path
+}
+
+/// What to run for `tool`: the resolved path, else the override or program
+/// name (so spawn errors name what was looked for).
+pub fn program(tool: &Tool) -> PathBuf {
+    let resolved = resolve(tool);
+    resolved.path.unwrap_or_else(|| {
+        PathBuf::from(
+            resolved
+                .override_value
+                .unwrap_or_else(|| tool.program.to_string()),
+        )
+    })
+}
+
+/// Forget resolved paths, e.g. after the config file changed.
+pub fn clear_cache() {
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn all() -> Value {
+    let tools: Vec<Value> = TOOLS
+        .iter()
+        .map(|tool| {
+            let resolved = resolve(tool);
+            json!({
+                "tool": tool.key,
+                "program": tool.program,
+                "env": tool.env,
+                "path": resolved.path.map(|p| p.display().to_string()),
+                "source": resolved.source,
+                "override": resolved.override_value,
+            })
+        })
+        .collect();
+    json!({ "os": std::env::consts::OS, "tools": tools })
+}
+
+/// `GET /api/paths`
+pub async fn get_paths() -> Json<Value> {
+    Json(tokio::task::spawn_blocking(all).await.unwrap_or_default())
+}
+
+/// `POST /api/paths/refresh`
+pub async fn refresh() -> Json<Value> {
+    clear_cache();
+    get_paths().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(os: &str) -> Env {
+        Env {
+            os: os.to_string(),
+            pathext: vec![".com".into(), ".exe".into(), ".bat".into(), ".cmd".into()],
+            ..Env::default()
+        }
+    }
+
+    fn install_paths(tool: &Tool, env: &Env) -> Vec<PathBuf> {
+        candidates(tool, tool.program, env)
+            .into_iter()
+            .map(|(p, _)| p)
+            .collect()
+    }
+
+    #[test]
+    fn install_locations_cover_common_installs() {
+        let home = Path::new("/home/geralt");
+        let linux = install_paths(
+            &OLLAMA,
+            &Env {
+                home: Some(home.to_path_buf()),
+                ..env("linux")
+            },
+        );
+        assert!(linux.contains(&PathBuf::from("/usr/local/bin/ollama")));
+        assert!(linux.contains(&home.join(".local/bin/ollama")));
+        assert!(linux.contains(&PathBuf::from("/home/linuxbrew/.linuxbrew/bin/ollama")));
+
+        let macos = install_paths(
+            &OLLAMA,
+            &Env {
+                home: Some(home.to_path_buf()),
+                ..env("macos")
+            },
+        );
+        assert_eq!(macos[0], PathBuf::from("/opt/homebrew/bin/ollama"));
+        assert!(macos.contains(&PathBuf::from(
+            "/Applications/Ollama.app/Contents/Resources/ollama"
+        )));
+
+        let appdata = Path::new(r"C:\Users\geralt\AppData\Local");
+        let windows = install_paths(
+            &OLLAMA,
+            &Env {
+                local_app_data: Some(appdata.to_path_buf()),
+                ..env("windows")
+            },
+        );
+        assert_eq!(
+            windows[0],
+            appdata.join("Programs").join("Ollama").join("ollama.exe")
+        );
+    }
+
+    #[test]
+    fn windows_path_search_finds_cmd_shims() {
+        let npm = PathBuf::from(r"C:\Users\geralt\AppData\Roaming\npm");
+        let env = Env {
+            path: vec![PathBuf::from(r"C:\Windows"), npm.clone()],
+            ..env("windows")
+        };
+        let shim = npm.join("claude.cmd");
+        let found = discover(&CLAUDE, None, &env, |p| p == shim);
+        assert_eq!(found.path, Some(shim));
+        assert_eq!(found.source, Some(Source::Path));
+    }
+
+    #[test]
+    fn overrides_take_precedence() {
+        let env = Env {
+            path: vec![PathBuf::from("/usr/bin")],
+            ..env("linux")
+        };
+        let exists = |p: &Path| p.starts_with("/usr/bin") || p.starts_with("/opt");
+
+        // A path is used as is.
+        let over = Some(("/opt/claude/bin/claude".to_string(), Source::Config));
+        let found = discover(&CLAUDE, over, &env, exists);
+        assert_eq!(found.path, Some(PathBuf::from("/opt/claude/bin/claude")));
+        assert_eq!(found.source, Some(Source::Config));
+
+        // A bare name is searched for instead of the program name.
+        let over = Some(("claude-beta".to_string(), Source::Env));
+        let found = discover(&CLAUDE, over, &env, exists);
+        assert_eq!(found.path, Some(PathBuf::from("/usr/bin/claude-beta")));
+        assert_eq!(found.source, Some(Source::Env));
+
+        // A missing override path is not replaced by a search.
+        let over = Some(("/missing/claude".to_string(), Source::Env));
+        assert_eq!(discover(&CLAUDE, over, &env, exists).path, None);
+    }
+}
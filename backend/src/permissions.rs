@@ -0,0 +1,268 @@
+//! Per-session permission profiles for tool execution.
+//!
+//! Modes:
+//! - `yolo` — every tool runs unattended; the Claude CLI is started with
+//!   `--dangerously-skip-permissions`.
+//! - `ask` — read-only tools run as usual, mutating ones wait for the user's
+//!   approval (see `approvals.rs`); the Claude CLI keeps its default
+//!   permission prompts (non-interactive turns refuse gated actions).
+//! - `read_only` — only tools that cannot modify files, repositories or
+//!   deployments are offered and executed; the Claude CLI runs in `plan` mode.
+//!
+//! Sessions without an explicit mode inherit the active config profile's
+//! `permission_mode` (see `profiles.rs`), else `CH_PERMISSION_MODE`, else
+//! `[permissions] mode` in the config file (`hydra_config.rs`). With none
+//! set, tools run unattended as before permission modes existed: `yolo` on
+//! the WebSocket path, while the Claude CLI is started without permission
+//! flags. `ask` and `read_only` are opt-in, per session or through any of
+//! these settings. The mode is enforced on the WebSocket execution path and
+//! for persistent CLI sessions. Gateway tools (`ai_gateway/gateway_tools.rs`)
+//! only run when `yolo` is configured explicitly.
+//!
+//! - `GET /api/sessions/{id}/permission-mode` — effective mode
+//! - `PUT /api/sessions/{id}/permission-mode` — `{ mode }` (`null` = inherit)
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{db_error, parse_session_id};
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionMode {
+    Yolo,
+    Ask,
+    ReadOnly,
+}
+
+impl PermissionMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Yolo => "yolo",
+            Self::Ask => "ask",
+            Self::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "yolo" => Some(Self::Yolo),
+            "ask" => Some(Self::Ask),
+            "read_only" | "readonly" => Some(Self::ReadOnly),
+            _ => None,
+        }
+    }
+
+    /// Whether `tool` may run under this mode.
+    pub fn allows_tool(self, tool: &str) -> bool {
+        self != Self::ReadOnly || is_read_only_tool(tool)
+    }
+
+    /// Whether `tool` waits for the user's approval under this mode
+    /// (see `approvals.rs`).
+    pub fn needs_approval(self, tool: &str) -> bool {
+        self == Self::Ask && !is_read_only_tool(tool)
+    }
+
+    /// Permission flags for a Claude CLI invocation.
+    pub fn claude_cli_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Yolo => &["--dangerously-skip-permissions"],
+            Self::Ask => &["--permission-mode", "default"],
+            Self::ReadOnly => &["--permission-mode", "plan"],
+        }
+    }
+}
+
+/// Configured mode for sessions without an explicit one: the active
+/// profile's, else `CH_PERMISSION_MODE`, else the config file's. `None` when
+/// none is set.
+pub fn configured_mode() -> Option<PermissionMode> {
+    crate::profiles::permission_mode().or_else(|| {
+        crate::hydra_config::text(
+            "CH_PERMISSION_MODE",
+            crate::hydra_config::current().permissions.mode.as_deref(),
+        )
+        .and_then(|v| PermissionMode::parse(&v))
+    })
+}
+
+/// Mode for sessions without an explicit one; unattended unless configured.
+pub fn default_mode() -> PermissionMode {
+    mode_or_default(configured_mode())
+}
+
+fn mode_or_default(configured: Option<PermissionMode>) -> PermissionMode {
+    configured.unwrap_or(PermissionMode::Yolo)
+}
+
+/// Tools with no side effects on files, repositories or deployments.
+/// Unknown and MCP tools are treated as mutating.
+const READ_ONLY_TOOLS: &[&str] = &[
+    "read_file",
+    "list_directory",
+    "search_in_files",
+    "read_pdf",
+    "list_zip",
+    "analyze_image",
+    "ocr_document",
+    "git_status",
+    "git_log",
+    "git_diff",
+    "fetch_webpage",
+    "crawl_website",
+    "github_list_repos",
+    "github_get_repo",
+    "github_list_issues",
+    "github_get_issue",
+    "vercel_list_projects",
+    "vercel_get_deployment",
+    "fly_list_apps",
+    "fly_get_status",
+    "fly_get_logs",
+];
+
+pub fn is_read_only_tool(tool: &str) -> bool {
+    READ_ONLY_TOOLS.contains(&tool)
+}
+
+/// Mode set for a session (explicit, else the configured one); `None` when
+/// nothing is set and the session runs unattended.
+pub async fn session_mode_setting(
+    db: &sqlx::PgPool,
+    session_id: &uuid::Uuid,
+) -> Option<PermissionMode> {
+    sqlx::query_scalar::<_, Option<String>>("SELECT permission_mode FROM ch_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .flatten()
+        .and_then(|m| PermissionMode::parse(&m))
+        .or_else(configured_mode)
+}
+
+/// Effective mode of a session (explicit, else the default).
+pub async fn session_mode(db: &sqlx::PgPool, session_id: &uuid::Uuid) -> PermissionMode {
+    session_mode_setting(db, session_id)
+        .await
+        .unwrap_or(PermissionMode::Yolo)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/sessions/{id}/permission-mode`
+pub async fn get_permission_mode(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let explicit: Option<String> = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT permission_mode FROM ch_sessions WHERE id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("permissions"))?
+    .ok_or((
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Session not found" })),
+    ))?;
+    let explicit = explicit.and_then(|m| PermissionMode::parse(&m));
+    Ok(Json(json!({
+        "session_id": id,
+        "mode": explicit.unwrap_or_else(default_mode),
+        "inherited": explicit.is_none(),
+        "default": default_mode(),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPermissionModeRequest {
+    /// `None` resets the session to the default mode.
+    pub mode: Option<PermissionMode>,
+}
+
+/// `PUT /api/sessions/{id}/permission-mode`
+pub async fn set_permission_mode(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SetPermissionModeRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let updated = sqlx::query(
+        "UPDATE ch_sessions SET permission_mode = $1, updated_at = NOW() WHERE id = $2",
+    )
+    .bind(req.mode.map(PermissionMode::as_str))
+    .bind(session_id)
+    .execute(&state.db)
+    .await
+    .map_err(db_error("permissions"))?
+    .rows_affected();
+    if updated == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Session not found" })),
+        ));
+    }
+    let mode = req.mode.unwrap_or_else(default_mode);
+    tracing::info!("permissions: session {} → {}", session_id, mode.as_str());
+    // A running CLI process keeps its flags; restart it under the new mode.
+    crate::claude_cli::stop(session_id).await;
+    Ok(Json(json!({
+        "session_id": id,
+        "mode": mode,
+        "inherited": req.mode.is_none(),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_parse_leniently_and_round_trip() {
+        assert_eq!(
+            PermissionMode::parse("read-only"),
+            Some(PermissionMode::ReadOnly)
+        );
+        assert_eq!(PermissionMode::parse(" YOLO "), Some(PermissionMode::Yolo));
+        assert_eq!(PermissionMode::parse("sometimes"), None);
+        for mode in [
+            PermissionMode::Yolo,
+            PermissionMode::Ask,
+            PermissionMode::ReadOnly,
+        ] {
+            assert_eq!(PermissionMode::parse(mode.as_str()), Some(mode));
+            assert_eq!(json!(mode), json!(mode.as_str()));
+        }
+    }
+
+    #[test]
+    fn read_only_blocks_mutating_and_unknown_tools() {
+        let ro = PermissionMode::ReadOnly;
+        assert!(ro.allows_tool("read_file"));
+        assert!(ro.allows_tool("git_diff"));
+        assert!(!ro.allows_tool("write_file"));
+        assert!(!ro.allows_tool("git_commit"));
+        assert!(!ro.allows_tool("vercel_deploy"));
+        assert!(!ro.allows_tool("mcp_fs_delete"));
+        assert!(PermissionMode::Ask.allows_tool("write_file"));
+        assert!(PermissionMode::Ask.needs_approval("write_file"));
+        assert!(!PermissionMode::Ask.needs_approval("read_file"));
+        assert!(!PermissionMode::Yolo.needs_approval("write_file"));
+    }
+
+    #[test]
+    fn unconfigured_sessions_run_unattended() {
+        assert_eq!(mode_or_default(None), PermissionMode::Yolo);
+        assert_eq!(mode_or_default(Some(PermissionMode::Ask)), PermissionMode::Ask);
+    }
+}
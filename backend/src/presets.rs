@@ -0,0 +1,431 @@
+//! Session presets — named project configurations ("Rust refactor",
+//! "Security review", ...) that spin up a correctly configured session.
+//!
+//! A preset is a JSON file `<slug>.json` holding the model, extra system
+//! prompt, working directory, permission mode and pinned files. Presets live
+//! in `.hydra/presets/` under the global working directory (the process
+//! directory when none is set), or in `CH_PRESETS_DIR` when given, so they
+//! can be committed alongside the project they describe.
+//!
+//! Sessions keep their own copy of the preset values: the system prompt and
+//! pinned files are appended to the server-side system prompt on every turn
+//! (see `session_prompt_section`), so editing a preset later does not change
+//! sessions already created from it.
+//!
+//! Pinned files must lie inside the allowed directories (`ALLOWED_FILE_DIRS`):
+//! checked when a preset is saved and again whenever a file is read. Their
+//! contents pass the safety guard (`safety.rs`) before they are sent.
+//!
+//! - `GET    /api/presets`                — list presets
+//! - `GET    /api/presets/{name}`         — one preset
+//! - `PUT    /api/presets/{name}`         — create or replace
+//! - `DELETE /api/presets/{name}`         — delete
+//! - `POST   /api/presets/{name}/session` — new session from the preset (`{ title? }`)
+
+use std::path::{Path as FsPath, PathBuf};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::permissions::PermissionMode;
+use crate::state::AppState;
+use crate::tools::allowed_dirs_from_env;
+use crate::tools::fs_tools::validate_path;
+
+const PRESETS_SUBDIR: &str = ".hydra/presets";
+const MAX_PINNED_FILES: usize = 20;
+/// Per-file cap on pinned file contents included in the system prompt.
+const MAX_PINNED_FILE_CHARS: usize = 8_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Model id; its provider is implied (`claude-*`, `gemini-*`, ...).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    /// Empty inherits the global working directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_directory: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<PermissionMode>,
+    /// Paths relative to the working directory (or absolute), inside the
+    /// allowed directories.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned_files: Vec<String>,
+}
+
+/// File stem for a preset name: lowercase alphanumerics joined by `-`.
+pub(crate) fn slug(name: &str) -> Option<String> {
+    let slug = name
+        .trim()
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    (!slug.is_empty() && slug.len() <= 64).then_some(slug)
+}
+
+async fn presets_dir(db: &sqlx::PgPool) -> PathBuf {
+    if let Some(dir) = std::env::var("CH_PRESETS_DIR")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        return PathBuf::from(dir);
+    }
+    match global_working_directory(db).await {
+        Some(wd) => PathBuf::from(wd).join(PRESETS_SUBDIR),
+        None => PathBuf::from(PRESETS_SUBDIR),
+    }
+}
+
+async fn global_working_directory(db: &sqlx::PgPool) -> Option<String> {
+    sqlx::query_scalar("SELECT working_directory FROM ch_settings WHERE id = 1")
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .filter(|wd: &String| !wd.trim().is_empty())
+}
+
+async fn read_preset(path: &FsPath) -> Result<Preset, String> {
+    let raw = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    serde_json::from_str(&raw).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Resolve a pinned file against the session working directory; the result
+/// must lie inside the allowed directories (`ALLOWED_FILE_DIRS`), like every
+/// other file the backend reads on a client's behalf.
+pub(crate) fn pinned_path(working_directory: &str, file: &str) -> Result<PathBuf, String> {
+    pinned_path_in(working_directory, file, &allowed_dirs_from_env())
+}
+
+fn pinned_path_in(
+    working_directory: &str,
+    file: &str,
+    allowed_dirs: &[PathBuf],
+) -> Result<PathBuf, String> {
+    let path = FsPath::new(file);
+    let joined = if path.is_absolute() || working_directory.is_empty() {
+        path.to_path_buf()
+    } else {
+        FsPath::new(working_directory).join(path)
+    };
+    validate_path(&joined.to_string_lossy(), allowed_dirs)
+}
+
+/// Contents of one pinned file as it goes into the system prompt: capped,
+/// and screened by the safety guard since it is sent to the provider.
+async fn pinned_body(working_directory: &str, file: &str, allowed_dirs: &[PathBuf]) -> String {
+    let path = match pinned_path_in(working_directory, file, allowed_dirs) {
+        Ok(path) => path,
+        Err(e) => return format!("(not allowed: {})", e),
+    };
+    let mut text = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => crate::handlers::truncate_for_context_with_limit(&text, MAX_PINNED_FILE_CHARS),
+        Err(e) => return format!("(unreadable: {})", e),
+    };
+    match crate::safety::screen_prompt(&mut text, "pinned_file") {
+        Ok(_) => text,
+        Err(e) => format!("(withheld: {})", e),
+    }
+}
+
+/// Extra system prompt for a session: its own instructions followed by the
+/// current contents of its pinned files. Empty when neither is set.
+pub(crate) async fn session_prompt_section(
+    system_prompt: &str,
+    pinned_files: &[String],
+    working_directory: &str,
+) -> String {
+    prompt_section_in(
+        system_prompt,
+        pinned_files,
+        working_directory,
+        &allowed_dirs_from_env(),
+    )
+    .await
+}
+
+async fn prompt_section_in(
+    system_prompt: &str,
+    pinned_files: &[String],
+    working_directory: &str,
+    allowed_dirs: &[PathBuf],
+) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    if !system_prompt.trim().is_empty() {
+        lines.extend([
+            String::new(),
+            "## Session Instructions".to_string(),
+            system_prompt.trim().to_string(),
+        ]);
+    }
+    if !pinned_files.is_empty() {
+        lines.extend([String::new(), "## Pinned Files".to_string()]);
+        for file in pinned_files.iter().take(MAX_PINNED_FILES) {
+            let body = pinned_body(working_directory, file, allowed_dirs).await;
+            lines.push(format!("### `{}`\n```\n{}\n```", file, body.trim_end()));
+        }
+    }
+    lines.join("\n")
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn error(status: StatusCode, msg: impl Into<String>) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": msg.into() })))
+}
+
+fn preset_path(dir: &FsPath, name: &str) -> Result<PathBuf, (StatusCode, Json<Value>)> {
+    slug(name)
+        .map(|s| dir.join(format!("{}.json", s)))
+        .ok_or_else(|| error(StatusCode::BAD_REQUEST, "Invalid preset name"))
+}
+
+async fn load_named(state: &AppState, name: &str) -> Result<Preset, (StatusCode, Json<Value>)> {
+    let path = preset_path(&presets_dir(&state.db).await, name)?;
+    if !path.is_file() {
+        return Err(error(StatusCode::NOT_FOUND, "Preset not found"));
+    }
+    read_preset(&path)
+        .await
+        .map_err(|e| error(StatusCode::UNPROCESSABLE_ENTITY, e))
+}
+
+/// `GET /api/presets`
+pub async fn list_presets(State(state): State<AppState>) -> Json<Value> {
+    let dir = presets_dir(&state.db).await;
+    let mut presets = Vec::new();
+    let mut invalid = Vec::new();
+    if let Ok(mut entries) = tokio::fs::read_dir(&dir).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read_preset(&path).await {
+                Ok(preset) => presets.push(preset),
+                Err(e) => invalid.push(e),
+            }
+        }
+    }
+    presets.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    Json(json!({
+        "directory": dir.to_string_lossy(),
+        "presets": presets,
+        "invalid": invalid,
+    }))
+}
+
+/// `GET /api/presets/{name}`
+pub async fn get_preset(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Preset>, (StatusCode, Json<Value>)> {
+    load_named(&state, &name).await.map(Json)
+}
+
+/// `PUT /api/presets/{name}`
+pub async fn put_preset(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(mut preset): Json<Preset>,
+) -> Result<Json<Preset>, (StatusCode, Json<Value>)> {
+    let path = preset_path(&presets_dir(&state.db).await, &name)?;
+    preset.name = name.trim().to_string();
+    if preset.pinned_files.len() > MAX_PINNED_FILES {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            format!("At most {} pinned files", MAX_PINNED_FILES),
+        ));
+    }
+    let wd = match preset
+        .working_directory
+        .as_deref()
+        .filter(|wd| !wd.trim().is_empty())
+    {
+        Some(wd) => wd.to_string(),
+        None => global_working_directory(&state.db)
+            .await
+            .unwrap_or_default(),
+    };
+    for file in &preset.pinned_files {
+        pinned_path(&wd, file).map_err(|e| {
+            error(
+                StatusCode::BAD_REQUEST,
+                format!("Pinned file '{}': {}", file, e),
+            )
+        })?;
+    }
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    }
+    let body = serde_json::to_string_pretty(&preset)
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tokio::fs::write(&path, body + "\n")
+        .await
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tracing::info!("presets: saved '{}' to {}", preset.name, path.display());
+    Ok(Json(preset))
+}
+
+/// `DELETE /api/presets/{name}`
+pub async fn delete_preset(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let path = preset_path(&presets_dir(&state.db).await, &name)?;
+    match tokio::fs::remove_file(&path).await {
+        Ok(()) => Ok(StatusCode::NO_CONTENT),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(error(StatusCode::NOT_FOUND, "Preset not found"))
+        }
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionFromPresetRequest {
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+/// `POST /api/presets/{name}/session`
+pub async fn create_session_from_preset(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    body: Option<Json<SessionFromPresetRequest>>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let preset = load_named(&state, &name).await?;
+    let req = body.map(|Json(b)| b).unwrap_or_default();
+    let wd = crate::handlers::sessions::normalize_workspace(
+        preset.working_directory.as_deref().unwrap_or(""),
+    )
+    .map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+    let title = req
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .unwrap_or(&preset.name);
+
+    let session_id: uuid::Uuid = sqlx::query_scalar(
+        "INSERT INTO ch_sessions \
+            (title, working_directory, model, permission_mode, system_prompt, pinned_files) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+    )
+    .bind(title)
+    .bind(&wd)
+    .bind(
+        preset
+            .model
+            .as_deref()
+            .map(str::trim)
+            .filter(|m| !m.is_empty()),
+    )
+    .bind(preset.permission_mode.map(PermissionMode::as_str))
+    .bind(&preset.system_prompt)
+    .bind(&preset.pinned_files)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("presets: failed to create session: {}", e);
+        error(StatusCode::INTERNAL_SERVER_ERROR, "Database error")
+    })?;
+    tracing::info!("presets: session {} from '{}'", session_id, preset.name);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({
+            "id": session_id.to_string(),
+            "title": title,
+            "preset": preset.name,
+            "working_directory": wd,
+            "model": preset.model,
+            "permission_mode": preset.permission_mode.unwrap_or_else(crate::permissions::default_mode),
+            "pinned_files": preset.pinned_files,
+        })),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_slugify_to_safe_file_stems() {
+        assert_eq!(slug("Rust refactor").as_deref(), Some("rust-refactor"));
+        assert_eq!(
+            slug("  Security / Review! ").as_deref(),
+            Some("security-review")
+        );
+        assert_eq!(slug("../../etc/passwd").as_deref(), Some("etc-passwd"));
+        assert_eq!(slug(" -- "), None);
+    }
+
+    #[test]
+    fn preset_json_round_trips_with_optional_fields() {
+        let preset: Preset = serde_json::from_str(
+            r#"{"name":"Security review","model":"claude-opus-4-6",
+                "permission_mode":"read_only","pinned_files":["SECURITY.md"]}"#,
+        )
+        .unwrap();
+        assert_eq!(preset.permission_mode, Some(PermissionMode::ReadOnly));
+        assert_eq!(preset.working_directory, None);
+        let back: Preset = serde_json::from_str(&serde_json::to_string(&preset).unwrap()).unwrap();
+        assert_eq!(back, preset);
+    }
+
+    #[tokio::test]
+    async fn prompt_section_includes_instructions_and_pinned_files() {
+        assert_eq!(session_prompt_section("  ", &[], "").await, "");
+
+        let dir = std::env::temp_dir().join(format!("ch-presets-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("NOTES.md"), "Use thiserror.\n").unwrap();
+        let section = prompt_section_in(
+            "Focus on unsafe blocks.",
+            &["NOTES.md".to_string(), "missing.md".to_string()],
+            &dir.to_string_lossy(),
+            std::slice::from_ref(&dir),
+        )
+        .await;
+        assert!(section.contains("## Session Instructions\nFocus on unsafe blocks."));
+        assert!(section.contains("### `NOTES.md`\n```\nUse thiserror.\n```"));
+        assert!(section.contains("### `missing.md`\n```\n(unreadable:"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn pinned_files_outside_allowed_dirs_are_not_read() {
+        let root = std::env::temp_dir().join(format!("ch-presets-{}", uuid::Uuid::new_v4()));
+        let allowed = root.join("project");
+        std::fs::create_dir_all(&allowed).unwrap();
+        std::fs::write(root.join("secret.txt"), "hunter2\n").unwrap();
+        let allowed_dirs = std::slice::from_ref(&allowed);
+
+        assert!(pinned_path_in(&allowed.to_string_lossy(), "../secret.txt", allowed_dirs).is_err());
+        let outside = root.join("secret.txt").to_string_lossy().to_string();
+        assert!(pinned_path_in("", &outside, allowed_dirs).is_err());
+
+        let section =
+            prompt_section_in("", &[outside], &allowed.to_string_lossy(), allowed_dirs).await;
+        assert!(section.contains("(not allowed:"));
+        assert!(!section.contains("hunter2"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,384 @@
+//! Process inventory — HYDRA-related processes on this machine with PID,
+//! memory and uptime, plus kill / restart.
+//!
+//! Listed processes (scanned with `sysinfo` on every request):
+//! - `ollama` — the Ollama server and its model runners (any process named
+//!   `ollama` / `ollama app`)
+//! - `claude_cli` — persistent Claude CLI processes of chat sessions
+//!   (`claude_cli`), plus unmanaged children of this backend running the CLI
+//!   binary (`CH_CLAUDE_CLI_BIN`)
+//! - `mcp_server` — stdio MCP servers run by `mcp::supervisor`
+//!
+//! Control goes through the owning module so supervisors do not fight it:
+//!
+//! - `cli_session` — kill stops the session's process; restart kills it but
+//!   keeps the CLI session, so the next prompt respawns it with `--resume`
+//! - `mcp_supervisor` — kill stops supervising; restart is a supervisor restart
+//! - `ollama_service` — kill terminates that PID; restart is `stop_ollama` +
+//!   `start_ollama`
+//! - `none` — kill terminates that PID; restart is not supported (409)
+//!
+//! Only PIDs in the inventory can be controlled.
+//!
+//! - `GET  /api/processes`
+//! - `POST /api/processes/{pid}/kill`
+//! - `POST /api/processes/{pid}/restart`
+
+use std::collections::HashMap;
+use std::path::Path as FsPath;
+use std::process::Stdio;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Serialize;
+use serde_json::{Value, json};
+use sysinfo::{Pid, ProcessesToUpdate, Signal, System};
+
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessKind {
+    Ollama,
+    ClaudeCli,
+    McpServer,
+}
+
+/// Who manages a process, which decides how it is killed or restarted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "owner", rename_all = "snake_case")]
+pub enum Owner {
+    CliSession { session_id: uuid::Uuid },
+    McpSupervisor { server_id: String },
+    OllamaService,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub parent_pid: Option<u32>,
+    pub kind: ProcessKind,
+    #[serde(flatten)]
+    pub owner: Owner,
+    pub name: String,
+    pub command: String,
+    pub memory_bytes: u64,
+    pub uptime_secs: u64,
+}
+
+/// Processes this backend knows it owns.
+#[derive(Debug, Default)]
+struct Known {
+    backend_pid: u32,
+    cli: HashMap<u32, uuid::Uuid>,
+    mcp: HashMap<u32, String>,
+    /// File stem of `CH_CLAUDE_CLI_BIN`.
+    cli_stem: String,
+}
+
+fn file_stem(path: &str) -> String {
+    let name = FsPath::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name.strip_suffix(".exe").unwrap_or(&name).to_string()
+}
+
+/// Kind and owner of a process, `None` when it is not HYDRA-related.
+fn classify(
+    pid: u32,
+    parent_pid: Option<u32>,
+    name: &str,
+    argv: &[String],
+    known: &Known,
+) -> Option<(ProcessKind, Owner)> {
+    if let Some(session_id) = known.cli.get(&pid) {
+        return Some((
+            ProcessKind::ClaudeCli,
+            Owner::CliSession {
+                session_id: *session_id,
+            },
+        ));
+    }
+    if let Some(server_id) = known.mcp.get(&pid) {
+        return Some((
+            ProcessKind::McpServer,
+            Owner::McpSupervisor {
+                server_id: server_id.clone(),
+            },
+        ));
+    }
+    let stem = file_stem(name);
+    if stem == "ollama" || stem == "ollama app" {
+        return Some((ProcessKind::Ollama, Owner::OllamaService));
+    }
+    // The CLI may run as a script (`node …/claude`), so look at argv too.
+    let runs_cli = stem == known.cli_stem
+        || argv
+            .iter()
+            .take(2)
+            .any(|arg| file_stem(arg) == known.cli_stem);
+    (parent_pid == Some(known.backend_pid) && runs_cli)
+        .then_some((ProcessKind::ClaudeCli, Owner::None))
+}
+
+async fn known_processes() -> Known {
+    Known {
+        backend_pid: std::process::id(),
+        cli: crate::claude_cli::child_pids()
+            .await
+            .into_iter()
+            .map(|(session_id, pid)| (pid, session_id))
+            .collect(),
+        mcp: crate::mcp::supervisor::processes_snapshot()
+            .into_iter()
+            .filter_map(|p| Some((p.pid?, p.server_id)))
+            .collect(),
+        cli_stem: file_stem(&crate::paths::program(&crate::paths::CLAUDE).to_string_lossy()),
+    }
+}
+
+fn scan_blocking(known: &Known) -> Vec<ProcessEntry> {
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let mut entries: Vec<ProcessEntry> = system
+        .processes()
+        .values()
+        .filter_map(|process| {
+            let pid = process.pid().as_u32();
+            let parent_pid = process.parent().map(|p| p.as_u32());
+            let name = process.name().to_string_lossy().to_string();
+            let argv: Vec<String> = process
+                .cmd()
+                .iter()
+                .map(|a| a.to_string_lossy().to_string())
+                .collect();
+            let (kind, owner) = classify(pid, parent_pid, &name, &argv, known)?;
+            Some(ProcessEntry {
+                pid,
+                parent_pid,
+                kind,
+                owner,
+                name,
+                command: argv.join(" "),
+                memory_bytes: process.memory(),
+                uptime_secs: process.run_time(),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| (e.kind as u8, e.pid));
+    entries
+}
+
+/// Current HYDRA-related processes.
+pub async fn scan() -> Vec<ProcessEntry> {
+    let known = known_processes().await;
+    tokio::task::spawn_blocking(move || scan_blocking(&known))
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("processes: scan failed: {}", e);
+            Vec::new()
+        })
+}
+
+/// Send SIGTERM (a hard kill where signals are unsupported) to `pid`.
+fn terminate(pid: u32) -> bool {
+    let mut system = System::new();
+    let pid = Pid::from_u32(pid);
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system
+        .process(pid)
+        .is_some_and(|p| p.kill_with(Signal::Term).unwrap_or_else(|| p.kill()))
+}
+
+/// Run a command in its own process group, so a timeout can kill whatever it
+/// started with `kill_process_group`. (On Windows `taskkill /T` walks the
+/// process tree instead.)
+pub(crate) fn own_process_group(cmd: &mut tokio::process::Command) {
+    #[cfg(unix)]
+    cmd.process_group(0);
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Kill a timed-out child together with the processes it started, which
+/// `kill_on_drop` alone would leave running. Failures are logged under `target`.
+pub(crate) async fn kill_process_group(child: &mut tokio::process::Child, target: &str) {
+    if let Some(pid) = child.id() {
+        let mut kill = if cfg!(windows) {
+            let mut c = tokio::process::Command::new("taskkill");
+            c.args(["/PID", &pid.to_string(), "/T", "/F"]);
+            c
+        } else {
+            let mut c = tokio::process::Command::new("kill");
+            c.args(["-KILL", "--", &format!("-{}", pid)]);
+            c
+        };
+        let killed = kill
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .await;
+        if let Err(e) = killed {
+            tracing::warn!("{}: cannot kill process group {}: {}", target, pid, e);
+        }
+    }
+    let _ = child.kill().await;
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+type HandlerError = (StatusCode, Json<Value>);
+
+fn error(status: StatusCode, message: impl Into<String>) -> HandlerError {
+    (status, Json(json!({ "error": message.into() })))
+}
+
+async fn find(pid: u32) -> Result<ProcessEntry, HandlerError> {
+    scan()
+        .await
+        .into_iter()
+        .find(|e| e.pid == pid)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Not a HYDRA process"))
+}
+
+/// `GET /api/processes`
+pub async fn list_processes() -> Json<Value> {
+    let processes = scan().await;
+    let total_memory: u64 = processes.iter().map(|p| p.memory_bytes).sum();
+    Json(json!({
+        "processes": processes,
+        "total_memory_bytes": total_memory,
+    }))
+}
+
+/// `POST /api/processes/{pid}/kill`
+pub async fn kill_process(Path(pid): Path<u32>) -> Result<Json<Value>, HandlerError> {
+    let entry = find(pid).await?;
+    let killed = match &entry.owner {
+        Owner::CliSession { session_id } => crate::claude_cli::stop(*session_id).await,
+        Owner::McpSupervisor { server_id } => crate::mcp::supervisor::stop_process(server_id).await,
+        Owner::OllamaService | Owner::None => tokio::task::spawn_blocking(move || terminate(pid))
+            .await
+            .unwrap_or(false),
+    };
+    if !killed {
+        return Err(error(
+            StatusCode::CONFLICT,
+            "Process could not be stopped (already exited or not permitted)",
+        ));
+    }
+    tracing::info!("processes: killed {} ({:?})", pid, entry.kind);
+    Ok(Json(
+        json!({ "pid": pid, "kind": entry.kind, "killed": true }),
+    ))
+}
+
+/// `POST /api/processes/{pid}/restart`
+pub async fn restart_process(
+    State(state): State<AppState>,
+    Path(pid): Path<u32>,
+) -> Result<Json<Value>, HandlerError> {
+    let entry = find(pid).await?;
+    let outcome = match &entry.owner {
+        Owner::CliSession { session_id } => {
+            crate::claude_cli::recycle(*session_id).await;
+            json!({ "respawns_on_next_prompt": true })
+        }
+        Owner::McpSupervisor { server_id } => {
+            crate::mcp::supervisor::restart_process_handler(State(state), Path(server_id.clone()))
+                .await
+                .map_err(|status| error(status, "MCP server could not be restarted"))?
+                .0
+        }
+        Owner::OllamaService => {
+            crate::ollama_service::stop_ollama()
+                .await
+                .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))?;
+            let started = crate::ollama_service::start_ollama(&state)
+                .await
+                .map_err(|e| error(StatusCode::BAD_GATEWAY, e))?;
+            json!(started)
+        }
+        Owner::None => {
+            return Err(error(
+                StatusCode::CONFLICT,
+                "Process is not managed by the backend and cannot be restarted",
+            ));
+        }
+    };
+    tracing::info!("processes: restarted {} ({:?})", pid, entry.kind);
+    Ok(Json(json!({
+        "pid": pid,
+        "kind": entry.kind,
+        "restarted": true,
+        "result": outcome,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known() -> Known {
+        let session = uuid::Uuid::nil();
+        Known {
+            backend_pid: 100,
+            cli: HashMap::from([(200, session)]),
+            mcp: HashMap::from([(300, "fs".to_string())]),
+            cli_stem: "claude".to_string(),
+        }
+    }
+
+    #[test]
+    fn managed_processes_are_matched_by_pid() {
+        let known = known();
+        assert_eq!(
+            classify(200, Some(100), "node", &[], &known),
+            Some((
+                ProcessKind::ClaudeCli,
+                Owner::CliSession {
+                    session_id: uuid::Uuid::nil()
+                }
+            ))
+        );
+        assert_eq!(
+            classify(300, Some(100), "npx", &[], &known),
+            Some((
+                ProcessKind::McpServer,
+                Owner::McpSupervisor {
+                    server_id: "fs".to_string()
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn ollama_and_cli_children_are_recognised_by_name() {
+        let known = known();
+        for name in ["ollama", "ollama.exe", "Ollama App.exe"] {
+            assert_eq!(
+                classify(1, Some(1), name, &[], &known),
+                Some((ProcessKind::Ollama, Owner::OllamaService)),
+                "{}",
+                name
+            );
+        }
+        let argv = [
+            "node".to_string(),
+            "/usr/lib/node_modules/claude".to_string(),
+        ];
+        assert_eq!(
+            classify(400, Some(100), "node", &argv, &known),
+            Some((ProcessKind::ClaudeCli, Owner::None))
+        );
+        // Someone else's CLI, and unrelated children of the backend.
+        assert_eq!(classify(401, Some(5), "claude", &[], &known), None);
+        assert_eq!(classify(402, Some(100), "git", &[], &known), None);
+    }
+}
@@ -0,0 +1,495 @@
+//! Config profiles — named bundles of provider keys, endpoints, routing
+//! preferences and the default permission mode (e.g. `work`, `home`),
+//! switched at runtime.
+//!
+//! Profiles live in the config file (see `hydra_config.rs`):
+//!
+//! ```toml
+//! [profiles.work]
+//! keys = { anthropic = "WORK_ANTHROPIC_API_KEY" }  # provider = env var holding the key
+//! permission_mode = "ask"                           # yolo / ask / read_only
+//! ollama = { url = "http://gpu-box:11434" }
+//! routing = { witcher_local_model = "auto" }
+//!
+//! [profiles.home]
+//! permission_mode = "yolo"
+//! cloud = true                                      # false = local models only
+//! ```
+//!
+//! Keys are referenced by env var name so they never end up in the file.
+//! The profile's `ollama` / `routing` values replace the top-level ones while
+//! it is active (env vars still win over both).
+//!
+//! Switching re-initializes the providers: the runtime Anthropic and Google
+//! keys are set to the profile's (or, without one, to the keychain / env
+//! key), and queue, Ollama and routing settings are re-applied. The profile
+//! chosen by hand is kept in `<data dir>/profile` across restarts;
+//! `CH_PROFILE` picks the one to start with when there is none.
+//!
+//! `offline` always exists: cloud providers are disabled (chat requests to
+//! them fail with `503`, code `OFFLINE`) and only local models run. A
+//! `[profiles.offline]` section adds to it. With `CH_AUTO_OFFLINE` (default
+//! on) a TCP probe of `CH_CONNECTIVITY_PROBE` (default
+//! `api.anthropic.com:443`) runs every `PROBE_INTERVAL`; after
+//! `OFFLINE_AFTER` failed probes the backend switches to `offline`, and back
+//! to the previous profile once the network returns — unless the profile was
+//! changed by hand in the meantime.
+//!
+//! - `GET  /api/profiles`        — profiles, the active one, network status
+//! - `POST /api/profiles/switch` — `{ name }` (`null` = no profile)
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::hydra_config::{HydraConfig, OllamaConfig, RoutingConfig};
+use crate::permissions::PermissionMode;
+use crate::state::AppState;
+
+pub const JOB_OFFLINE_PROBE: &str = "offline_probe";
+/// Built-in profile that disables cloud providers.
+pub const OFFLINE: &str = "offline";
+const PROFILE_FILE: &str = "profile";
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Consecutive failed probes before switching to `offline`.
+const OFFLINE_AFTER: u32 = 2;
+/// Providers whose runtime key a profile can set.
+const KEYED_PROVIDERS: &[&str] = &["anthropic", "google"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Provider → name of the env var holding its key.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+    /// Permission mode of sessions without their own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub permission_mode: Option<String>,
+    /// `false` disables cloud providers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<bool>,
+    pub ollama: OllamaConfig,
+    pub routing: RoutingConfig,
+}
+
+impl Profile {
+    pub(crate) fn validate(&self, name: &str, errors: &mut Vec<String>) {
+        let prefix = format!("profiles.{}", name);
+        if !valid_name(name) {
+            errors.push(format!(
+                "{}: names may only use a-z, 0-9, '-' and '_'",
+                prefix
+            ));
+        }
+        for (provider, var) in &self.keys {
+            if !KEYED_PROVIDERS.contains(&provider.as_str()) {
+                errors.push(format!(
+                    "{}.keys: unknown provider '{}' (expected one of {})",
+                    prefix,
+                    provider,
+                    KEYED_PROVIDERS.join(", ")
+                ));
+            } else if var.trim().is_empty() {
+                errors.push(format!("{}.keys.{} must name an env var", prefix, provider));
+            }
+        }
+        if let Some(mode) = &self.permission_mode
+            && PermissionMode::parse(mode).is_none()
+        {
+            errors.push(format!(
+                "{}.permission_mode must be yolo, ask or read_only, got '{}'",
+                prefix, mode
+            ));
+        }
+        self.ollama.validate(&format!("{}.ollama", prefix), errors);
+        self.routing
+            .validate(&format!("{}.routing", prefix), errors);
+    }
+
+    /// Key for `provider`: the profile's env var, else keychain / env.
+    fn key(&self, provider: &str) -> Option<String> {
+        self.keys
+            .get(provider)
+            .and_then(|var| std::env::var(var.trim()).ok())
+            .filter(|k| !k.is_empty())
+            .or_else(|| crate::secrets::resolve_provider_key(provider))
+    }
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_')
+}
+
+fn env(name: &str) -> Option<String> {
+    std::env::var(name)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn profile_file() -> PathBuf {
+    crate::metrics_snapshot::data_dir().join(PROFILE_FILE)
+}
+
+/// Name of the active profile (`None` = plain config file).
+static ACTIVE: LazyLock<RwLock<Option<String>>> = LazyLock::new(|| {
+    let saved = std::fs::read_to_string(profile_file())
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    RwLock::new(saved.or_else(|| env("CH_PROFILE")))
+});
+
+pub fn active_name() -> Option<String> {
+    ACTIVE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// `name`'s profile in `file`; `offline` always resolves.
+fn lookup(file: &HydraConfig, name: &str) -> Option<Profile> {
+    let profile = file.profiles.get(name).cloned();
+    if name != OFFLINE {
+        return profile;
+    }
+    let mut offline = profile.unwrap_or_default();
+    offline.cloud = Some(false);
+    Some(offline)
+}
+
+/// The active profile as defined in `file` (`None` when no profile is
+/// active or it was removed from the file).
+pub(crate) fn active(file: &HydraConfig) -> Option<Profile> {
+    lookup(file, active_name().as_deref()?)
+}
+
+fn current() -> Option<Profile> {
+    active(&crate::hydra_config::file())
+}
+
+/// Permission mode set by the active profile.
+pub fn permission_mode() -> Option<PermissionMode> {
+    current()?
+        .permission_mode
+        .as_deref()
+        .and_then(PermissionMode::parse)
+}
+
+pub fn cloud_allowed() -> bool {
+    current().and_then(|p| p.cloud).unwrap_or(true)
+}
+
+/// Error for cloud requests while the active profile disables them.
+pub fn ensure_cloud() -> Result<(), (StatusCode, Json<Value>)> {
+    if cloud_allowed() {
+        return Ok(());
+    }
+    Err((
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "error": format!(
+                "Cloud providers are disabled by the '{}' profile — use a local model",
+                active_name().unwrap_or_default()
+            ),
+            "code": "OFFLINE",
+        })),
+    ))
+}
+
+/// Set the runtime provider keys from the active profile.
+async fn reinit_providers(state: &AppState) {
+    let profile = current().unwrap_or_default();
+    for provider in KEYED_PROVIDERS {
+        let key = profile.key(provider);
+        crate::secrets::sync_runtime_key(state, provider, key.as_deref()).await;
+    }
+}
+
+/// Make `name` the active profile and re-initialize the providers. Only
+/// switches made by hand are persisted.
+pub async fn switch_profile(
+    state: &AppState,
+    name: Option<String>,
+    persist: bool,
+) -> Result<(), String> {
+    if let Some(n) = &name
+        && lookup(&crate::hydra_config::file(), n).is_none()
+    {
+        return Err(format!("Unknown profile '{}'", n));
+    }
+    let previous = std::mem::replace(
+        &mut *ACTIVE.write().unwrap_or_else(|e| e.into_inner()),
+        name.clone(),
+    );
+    if persist {
+        let path = profile_file();
+        let saved = match &name {
+            Some(n) => match path.parent() {
+                Some(dir) => match tokio::fs::create_dir_all(dir).await {
+                    Ok(()) => tokio::fs::write(&path, n).await,
+                    Err(e) => Err(e),
+                },
+                None => tokio::fs::write(&path, n).await,
+            },
+            None => match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                other => other,
+            },
+        };
+        if let Err(e) = saved {
+            tracing::warn!("profiles: cannot save {}: {}", path.display(), e);
+        }
+    }
+    crate::hydra_config::refresh();
+    reinit_providers(state).await;
+    tracing::info!("profiles: {:?} -> {:?}", previous, name);
+    crate::app_log::record(
+        crate::app_log::Level::Info,
+        "config",
+        "profile-switched",
+        json!({ "from": previous, "to": name, "manual": persist }),
+    );
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Automatic offline profile
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Default)]
+struct Connectivity {
+    /// Result of the last probe.
+    online: Option<bool>,
+    failures: u32,
+    /// Profile to return to, set when the probe switched to `offline`.
+    resume: Option<Option<String>>,
+}
+
+static CONNECTIVITY: Mutex<Connectivity> = Mutex::new(Connectivity {
+    online: None,
+    failures: 0,
+    resume: None,
+});
+
+/// Record a probe result; returns the profile to switch to, if any.
+fn observe(
+    conn: &mut Connectivity,
+    reachable: bool,
+    active: Option<&str>,
+) -> Option<Option<String>> {
+    if reachable {
+        conn.failures = 0;
+        conn.online = Some(true);
+        let resume = conn.resume.take()?;
+        // A profile chosen by hand while offline stays.
+        return (active == Some(OFFLINE)).then_some(resume);
+    }
+    conn.failures += 1;
+    if conn.failures < OFFLINE_AFTER {
+        return None;
+    }
+    conn.online = Some(false);
+    if active == Some(OFFLINE) || conn.resume.is_some() {
+        return None;
+    }
+    conn.resume = Some(active.map(str::to_string));
+    Some(Some(OFFLINE.to_string()))
+}
+
+fn probe_target() -> String {
+    env("CH_CONNECTIVITY_PROBE").unwrap_or_else(|| "api.anthropic.com:443".to_string())
+}
+
+fn auto_offline() -> bool {
+    env("CH_AUTO_OFFLINE").is_none_or(|v| v != "0" && !v.eq_ignore_ascii_case("false"))
+}
+
+async fn probe(target: &str) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(target)).await,
+        Ok(Ok(_))
+    )
+}
+
+/// Apply the active profile's keys at startup and watch connectivity.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        if current().is_some() {
+            reinit_providers(&state).await;
+        }
+        if !auto_offline() {
+            return;
+        }
+        let target = probe_target();
+        state.job_schedule.start(JOB_OFFLINE_PROBE, PROBE_INTERVAL);
+        let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_OFFLINE_PROBE);
+            let reachable = probe(&target).await;
+            let switch = {
+                let mut conn = CONNECTIVITY.lock().unwrap_or_else(|e| e.into_inner());
+                observe(&mut conn, reachable, active_name().as_deref())
+            };
+            if let Some(name) = switch {
+                tracing::warn!(
+                    "profiles: {} {} — switching to {:?}",
+                    target,
+                    if reachable {
+                        "reachable"
+                    } else {
+                        "unreachable"
+                    },
+                    name
+                );
+                if let Err(e) = switch_profile(&state, name, false).await {
+                    tracing::warn!("profiles: {}", e);
+                }
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn profile_json(name: &str, profile: &Profile, builtin: bool) -> Value {
+    json!({
+        "name": name,
+        "builtin": builtin,
+        "cloud": profile.cloud.unwrap_or(true),
+        "permission_mode": profile.permission_mode,
+        "keys": profile.keys.keys().collect::<Vec<_>>(),
+        "ollama": profile.ollama,
+        "routing": profile.routing,
+    })
+}
+
+/// `GET /api/profiles`
+pub async fn list_profiles() -> Json<Value> {
+    let file = crate::hydra_config::file();
+    let mut profiles: Vec<Value> = file
+        .profiles
+        .iter()
+        .filter(|(name, _)| name.as_str() != OFFLINE)
+        .map(|(name, profile)| profile_json(name, profile, false))
+        .collect();
+    if let Some(offline) = lookup(&file, OFFLINE) {
+        profiles.push(profile_json(OFFLINE, &offline, true));
+    }
+    let (online, auto_switched) = {
+        let conn = CONNECTIVITY.lock().unwrap_or_else(|e| e.into_inner());
+        (conn.online, conn.resume.is_some())
+    };
+    Json(json!({
+        "active": active_name(),
+        "profiles": profiles,
+        "cloud_allowed": cloud_allowed(),
+        "network": {
+            "online": online,
+            "probe": probe_target(),
+            "auto_offline": auto_offline(),
+            "auto_switched": auto_switched,
+        },
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SwitchRequest {
+    pub name: Option<String>,
+}
+
+/// `POST /api/profiles/switch`
+pub async fn switch(
+    State(state): State<AppState>,
+    Json(body): Json<SwitchRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let name = body
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    switch_profile(&state, name, true)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, Json(json!({ "error": e }))))?;
+    // A manual choice ends any automatic offline switch.
+    CONNECTIVITY
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .resume = None;
+    Ok(list_profiles().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_parse_and_validate() {
+        let config = crate::hydra_config::parse(
+            r#"
+            [profiles.work]
+            keys = { anthropic = "WORK_ANTHROPIC_API_KEY" }
+            permission_mode = "ask"
+            ollama = { url = "http://gpu-box:11434" }
+
+            [profiles.offline]
+            routing = { witcher_local_model = "auto" }
+            "#,
+        )
+        .unwrap();
+        let work = &config.profiles["work"];
+        assert_eq!(work.keys["anthropic"], "WORK_ANTHROPIC_API_KEY");
+        assert_eq!(work.cloud, None);
+        assert_eq!(lookup(&config, OFFLINE).unwrap().cloud, Some(false));
+        assert!(lookup(&config, "home").is_none());
+
+        let err = crate::hydra_config::parse(
+            "[profiles.Home]\npermission_mode = \"maybe\"\nkeys = { slack = \"X\" }\n",
+        )
+        .unwrap_err();
+        assert!(err.contains("names may only use"), "{}", err);
+        assert!(err.contains("permission_mode"), "{}", err);
+        assert!(err.contains("unknown provider 'slack'"), "{}", err);
+    }
+
+    #[test]
+    fn lost_network_switches_to_offline_and_back() {
+        let mut conn = Connectivity::default();
+        assert_eq!(observe(&mut conn, false, Some("work")), None);
+        assert_eq!(
+            observe(&mut conn, false, Some("work")),
+            Some(Some(OFFLINE.to_string()))
+        );
+        assert_eq!(conn.online, Some(false));
+        assert_eq!(observe(&mut conn, false, Some(OFFLINE)), None);
+        assert_eq!(
+            observe(&mut conn, true, Some(OFFLINE)),
+            Some(Some("work".to_string()))
+        );
+        assert_eq!(observe(&mut conn, true, Some("work")), None);
+    }
+
+    #[test]
+    fn manual_switch_while_offline_is_kept() {
+        let mut conn = Connectivity::default();
+        observe(&mut conn, false, None);
+        assert_eq!(
+            observe(&mut conn, false, None),
+            Some(Some(OFFLINE.to_string()))
+        );
+        // The user picked `home` while the network was down.
+        assert_eq!(observe(&mut conn, false, Some("home")), None);
+        assert_eq!(observe(&mut conn, true, Some("home")), None);
+        assert!(conn.resume.is_none());
+    }
+}
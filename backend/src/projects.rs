@@ -0,0 +1,393 @@
+//! Projects — named workspaces the backend can switch between instead of
+//! being tied to a single directory.
+//!
+//! A project is a registry entry (`ch_projects`): name, directory, default
+//! provider and the presets offered for it. Switching makes its directory the
+//! global working directory, so tools, `.hydra/presets/`, affected-files
+//! inference and the file watcher follow it; with a default provider the
+//! default chat model becomes that provider's (`anthropic` → coordinator,
+//! `google` → Gemini Pro, `ollama` → the fixed Witcher local model).
+//!
+//! Work is scoped to the project active when it was created: new sessions and
+//! background prompts record it (a database trigger fills `project_id`),
+//! queued prompts only conflict over files with prompts of the same project
+//! (affected files are project-relative), and log entries carry the project
+//! name (`GET /api/logs?project=`).
+//!
+//! - `GET  /api/projects`                 — registry (recently used first), active project
+//! - `POST /api/projects`                 — add `{ name, path, default_provider?, presets? }`
+//! - `POST /api/projects/switch`          — `{ name }` (`null` = no project)
+//! - `GET  /api/projects/{name}/sessions` — the project's sessions, newest first
+
+use std::sync::{LazyLock, RwLock};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error};
+use crate::state::AppState;
+
+const MAX_NAME_LEN: usize = 60;
+const MAX_PRESETS: usize = 20;
+const MAX_SESSIONS: i64 = 500;
+/// Providers a project can default to.
+const PROVIDERS: &[&str] = &["anthropic", "google", "ollama"];
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ProjectRow {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub path: String,
+    pub default_provider: Option<String>,
+    /// Preset names (slugs) offered for the project.
+    pub presets: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+const PROJECT_COLUMNS: &str = "id, name, path, default_provider, presets, created_at, last_used_at";
+
+/// Name of the active project, for tagging log entries without a query.
+static ACTIVE: LazyLock<RwLock<Option<String>>> = LazyLock::new(|| RwLock::new(None));
+
+/// Name of the active project, if any.
+pub fn active_name() -> Option<String> {
+    ACTIVE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn set_active(name: Option<String>) {
+    *ACTIVE.write().unwrap_or_else(|e| e.into_inner()) = name;
+}
+
+/// Load the active project at startup.
+pub async fn load(db: &sqlx::PgPool) {
+    let name: Option<String> = sqlx::query_scalar(
+        "SELECT p.name FROM ch_settings g JOIN ch_projects p ON p.id = g.active_project_id \
+         WHERE g.id = 1",
+    )
+    .fetch_optional(db)
+    .await
+    .unwrap_or_else(|e| {
+        tracing::warn!("projects: cannot load the active project: {}", e);
+        None
+    });
+    if let Some(name) = &name {
+        tracing::info!("projects: active project '{}'", name);
+    }
+    set_active(name);
+}
+
+fn normalize_name(raw: &str) -> Result<String, String> {
+    let name = raw.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LEN || name.contains('/') {
+        return Err(format!(
+            "Project name must be 1-{} characters without '/'",
+            MAX_NAME_LEN
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn normalize_provider(raw: Option<&str>) -> Result<Option<String>, String> {
+    match raw
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+    {
+        Some(p) if PROVIDERS.contains(&p.as_str()) => Ok(Some(p)),
+        Some(p) => Err(format!(
+            "Unknown provider '{}' (expected {})",
+            p,
+            PROVIDERS.join(", ")
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Preset names as slugs, deduplicated in order.
+fn normalize_presets(raw: &[String]) -> Result<Vec<String>, String> {
+    if raw.len() > MAX_PRESETS {
+        return Err(format!("At most {} presets per project", MAX_PRESETS));
+    }
+    let mut presets: Vec<String> = Vec::new();
+    for name in raw {
+        let slug =
+            crate::presets::slug(name).ok_or_else(|| format!("Invalid preset name '{}'", name))?;
+        if !presets.contains(&slug) {
+            presets.push(slug);
+        }
+    }
+    Ok(presets)
+}
+
+/// Default chat model for a project's provider; `None` leaves it unchanged.
+async fn provider_model(state: &AppState, provider: &str) -> Option<String> {
+    match provider {
+        "anthropic" => Some(crate::model_registry::get_model_id(state, "coordinator").await),
+        "google" => Some(crate::model_registry::get_model_id(state, "gemini_pro").await),
+        "ollama" => crate::witcher_router::local_model()
+            .filter(|m| m != "auto")
+            .map(|m| format!("{}{}", crate::queue::OLLAMA_PREFIX, m)),
+        _ => None,
+    }
+}
+
+fn error(status: StatusCode, msg: &str) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": msg })))
+}
+
+async fn find(db: &sqlx::PgPool, name: &str) -> Result<ProjectRow, (StatusCode, Json<Value>)> {
+    sqlx::query_as::<_, ProjectRow>(&format!(
+        "SELECT {} FROM ch_projects WHERE name = $1",
+        PROJECT_COLUMNS
+    ))
+    .bind(name)
+    .fetch_optional(db)
+    .await
+    .map_err(db_error("projects"))?
+    .ok_or_else(|| {
+        error(
+            StatusCode::NOT_FOUND,
+            &format!("Project '{}' not found", name),
+        )
+    })
+}
+
+// ── GET /api/projects ───────────────────────────────────────────────────────
+
+pub async fn list_projects(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let projects = sqlx::query_as::<_, ProjectRow>(&format!(
+        "SELECT {} FROM ch_projects ORDER BY last_used_at DESC NULLS LAST, name ASC",
+        PROJECT_COLUMNS
+    ))
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("projects"))?;
+    let counts: std::collections::HashMap<uuid::Uuid, i64> = sqlx::query_as(
+        "SELECT project_id, COUNT(*) FROM ch_sessions WHERE project_id IS NOT NULL \
+         GROUP BY project_id",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("projects"))?
+    .into_iter()
+    .collect();
+
+    let active = active_name();
+    let projects: Vec<Value> = projects
+        .into_iter()
+        .map(|p| {
+            let mut entry = json!(p);
+            entry["session_count"] = json!(counts.get(&p.id).copied().unwrap_or(0));
+            entry["active"] = json!(active.as_deref() == Some(p.name.as_str()));
+            entry
+        })
+        .collect();
+    Ok(Json(json!({ "active": active, "projects": projects })))
+}
+
+// ── POST /api/projects ──────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct AddProjectRequest {
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub default_provider: Option<String>,
+    #[serde(default)]
+    pub presets: Vec<String>,
+}
+
+pub async fn add_project(
+    State(state): State<AppState>,
+    Json(req): Json<AddProjectRequest>,
+) -> Result<(StatusCode, Json<ProjectRow>), (StatusCode, Json<Value>)> {
+    let name = normalize_name(&req.name).map_err(bad_request)?;
+    if req.path.trim().is_empty() {
+        return Err(bad_request("Project path is required".to_string()));
+    }
+    let path = crate::handlers::sessions::normalize_workspace(&req.path).map_err(bad_request)?;
+    let provider = normalize_provider(req.default_provider.as_deref()).map_err(bad_request)?;
+    let presets = normalize_presets(&req.presets).map_err(bad_request)?;
+
+    let existing: Option<String> =
+        sqlx::query_scalar("SELECT name FROM ch_projects WHERE name = $1 OR path = $2 LIMIT 1")
+            .bind(&name)
+            .bind(&path)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(db_error("projects"))?;
+    if let Some(existing) = existing {
+        let msg = if existing == name {
+            format!("Project '{}' already exists", name)
+        } else {
+            format!("{} is already registered as project '{}'", path, existing)
+        };
+        return Err(error(StatusCode::CONFLICT, &msg));
+    }
+
+    let row = sqlx::query_as::<_, ProjectRow>(&format!(
+        "INSERT INTO ch_projects (name, path, default_provider, presets) \
+         VALUES ($1, $2, $3, $4) RETURNING {}",
+        PROJECT_COLUMNS
+    ))
+    .bind(&name)
+    .bind(&path)
+    .bind(&provider)
+    .bind(&presets)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error("projects"))?;
+    tracing::info!("projects: added '{}' at {}", row.name, row.path);
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+// ── POST /api/projects/switch ───────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct SwitchProjectRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+pub async fn switch_project(
+    State(state): State<AppState>,
+    Json(req): Json<SwitchProjectRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let name = req
+        .name
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    let Some(name) = name else {
+        sqlx::query(
+            "UPDATE ch_settings SET active_project_id = NULL, updated_at = NOW() WHERE id = 1",
+        )
+        .execute(&state.db)
+        .await
+        .map_err(db_error("projects"))?;
+        set_active(None);
+        tracing::info!("projects: no active project");
+        return Ok(Json(json!({ "active": null })));
+    };
+
+    let project = find(&state.db, &name).await?;
+    // The directory may have been moved or deleted since it was added.
+    crate::handlers::sessions::normalize_workspace(&project.path).map_err(|e| {
+        error(
+            StatusCode::CONFLICT,
+            &format!("Project directory unavailable: {}", e),
+        )
+    })?;
+    let model = match project.default_provider.as_deref() {
+        Some(provider) => provider_model(&state, provider).await,
+        None => None,
+    };
+
+    let mut tx = state.db.begin().await.map_err(db_error("projects"))?;
+    sqlx::query(
+        "UPDATE ch_settings SET active_project_id = $1, working_directory = $2, \
+         default_model = COALESCE($3, default_model), updated_at = NOW() WHERE id = 1",
+    )
+    .bind(project.id)
+    .bind(&project.path)
+    .bind(&model)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("projects"))?;
+    sqlx::query("UPDATE ch_projects SET last_used_at = NOW() WHERE id = $1")
+        .bind(project.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("projects"))?;
+    tx.commit().await.map_err(db_error("projects"))?;
+
+    set_active(Some(project.name.clone()));
+    tracing::info!(
+        "projects: switched to '{}' ({})",
+        project.name,
+        project.path
+    );
+    crate::app_log::record(
+        crate::app_log::Level::Info,
+        "projects",
+        &format!("Switched to project '{}'", project.name),
+        json!({ "path": project.path, "default_model": model }),
+    );
+    Ok(Json(json!({
+        "active": project.name,
+        "project": project,
+        "default_model": model,
+    })))
+}
+
+// ── GET /api/projects/{name}/sessions ───────────────────────────────────────
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ProjectSession {
+    id: uuid::Uuid,
+    title: String,
+    working_directory: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+pub async fn list_project_sessions(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let project = find(&state.db, name.trim()).await?;
+    let sessions = sqlx::query_as::<_, ProjectSession>(
+        "SELECT id, title, working_directory, created_at, updated_at FROM ch_sessions \
+         WHERE project_id = $1 ORDER BY updated_at DESC LIMIT $2",
+    )
+    .bind(project.id)
+    .bind(MAX_SESSIONS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("projects"))?;
+    Ok(Json(
+        json!({ "project": project.name, "sessions": sessions }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_and_providers_are_validated() {
+        assert_eq!(normalize_name("  Hydra backend ").unwrap(), "Hydra backend");
+        assert!(normalize_name("   ").is_err());
+        assert!(normalize_name("a/b").is_err());
+        assert!(normalize_name(&"x".repeat(MAX_NAME_LEN + 1)).is_err());
+
+        assert_eq!(
+            normalize_provider(Some(" Google ")).unwrap().as_deref(),
+            Some("google")
+        );
+        assert_eq!(normalize_provider(Some("")).unwrap(), None);
+        assert_eq!(normalize_provider(None).unwrap(), None);
+        assert!(
+            normalize_provider(Some("openai"))
+                .unwrap_err()
+                .contains("anthropic, google, ollama")
+        );
+    }
+
+    #[test]
+    fn presets_are_slugged_and_deduplicated() {
+        let presets = normalize_presets(&[
+            "Rust refactor".to_string(),
+            "rust-refactor".to_string(),
+            "Security review".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(presets, vec!["rust-refactor", "security-review"]);
+        assert!(normalize_presets(&[" -- ".to_string()]).is_err());
+        assert!(normalize_presets(&vec!["p".to_string(); MAX_PRESETS + 1]).is_err());
+    }
+}
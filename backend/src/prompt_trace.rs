@@ -0,0 +1,329 @@
+//! Per-prompt lifecycle tracing — reconstruct latency or routing oddities
+//! after the fact.
+//!
+//! A `PromptTrace` is created per WebSocket execution (keyed by the
+//! `execution_id` sent in `WsServerMessage::Start`) and collects ordered
+//! stage events in memory: received → context_resolved → provider_called →
+//! fallback / retries → first_token → tool calls → completed / failed.
+//! Streamed tokens are counted, not stored individually. `finish()` writes
+//! the events to `ch_prompt_traces` in a single batch insert.
+//!
+//! Background prompts trace under `bg-<prompt id>` (see `queue/`), one
+//! segment per step: enqueued (with the approval hold), conflict (first pass
+//! blocked by a file lock), conflicts_checked + dequeued, provider_called,
+//! then completed / failed / retrying. Each segment is appended after the
+//! events already stored, and its `elapsed_ms` counts from the first one.
+//!
+//! - `GET /api/prompts/{execution_id}/trace` — ordered events for one execution,
+//!   plus the post-response hook runs it triggered (see `hooks`)
+//! - `POST /api/prompts/{execution_id}/rating` — `{ rating: 1..=5 }`, appended
+//!   as a `rated` event (feeds the routing dataset export)
+
+use std::time::Instant;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sqlx::PgPool;
+
+use crate::state::AppState;
+
+/// A single lifecycle event.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TraceEvent {
+    pub seq: i32,
+    pub stage: String,
+    pub elapsed_ms: i64,
+    pub detail: Value,
+}
+
+/// In-flight trace for one prompt execution; stored by `finish()`.
+pub struct PromptTrace {
+    db: PgPool,
+    execution_id: String,
+    started: Instant,
+    started_at: DateTime<Utc>,
+    events: Vec<TraceEvent>,
+    token_chunks: u32,
+}
+
+impl PromptTrace {
+    pub fn new(db: PgPool, execution_id: &str) -> Self {
+        Self {
+            db,
+            execution_id: execution_id.to_string(),
+            started: Instant::now(),
+            started_at: Utc::now(),
+            events: Vec::new(),
+            token_chunks: 0,
+        }
+    }
+
+    /// Append a stage event with the elapsed time since the trace started.
+    pub fn record(&mut self, stage: &str, detail: Value) {
+        self.events.push(TraceEvent {
+            seq: self.events.len() as i32,
+            stage: stage.to_string(),
+            elapsed_ms: self.started.elapsed().as_millis() as i64,
+            detail,
+        });
+    }
+
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    /// Detail of the `failed` event, if the execution failed.
+    pub fn failure(&self) -> Option<&Value> {
+        self.events
+            .iter()
+            .rev()
+            .find(|e| e.stage == "failed")
+            .map(|e| &e.detail)
+    }
+
+    /// Count a streamed token chunk; the first one is recorded as `first_token`.
+    pub fn token(&mut self) {
+        if self.token_chunks == 0 {
+            self.record("first_token", Value::Null);
+        }
+        self.token_chunks += 1;
+    }
+
+    /// Recorded events, closed with the stream summary when tokens were counted.
+    fn into_events(mut self) -> Vec<TraceEvent> {
+        if self.token_chunks > 0 {
+            let chunks = self.token_chunks;
+            self.record("stream_summary", json!({ "token_chunks": chunks }));
+        }
+        self.events
+    }
+
+    /// Store the recorded events after any already stored for this execution.
+    pub async fn finish(self) {
+        let db = self.db.clone();
+        let execution_id = self.execution_id.clone();
+        let started_at = self.started_at;
+        let events = self.into_events();
+        if events.is_empty() {
+            return;
+        }
+        if let Err(e) = persist(&db, &execution_id, started_at, events).await {
+            tracing::warn!(
+                "prompt_trace: failed to persist trace {}: {}",
+                execution_id,
+                e
+            );
+        }
+    }
+}
+
+/// Record a single-stage trace segment for `execution_id`.
+pub async fn record_once(db: &PgPool, execution_id: &str, stage: &str, detail: Value) {
+    let mut trace = PromptTrace::new(db.clone(), execution_id);
+    trace.record(stage, detail);
+    trace.finish().await;
+}
+
+async fn persist(
+    db: &PgPool,
+    execution_id: &str,
+    started_at: DateTime<Utc>,
+    events: Vec<TraceEvent>,
+) -> Result<(), sqlx::Error> {
+    let mut seqs = Vec::with_capacity(events.len());
+    let mut stages = Vec::with_capacity(events.len());
+    let mut elapsed = Vec::with_capacity(events.len());
+    let mut details = Vec::with_capacity(events.len());
+    for ev in events {
+        seqs.push(ev.seq);
+        stages.push(ev.stage);
+        elapsed.push(ev.elapsed_ms);
+        details.push(ev.detail);
+    }
+
+    let mut tx = db.begin().await?;
+    // Same lock as ratings: segments of one execution append in turn.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('ch_prompt_traces:' || $1))")
+        .bind(execution_id)
+        .execute(&mut *tx)
+        .await?;
+    // `created_at` is the event time; an appended segment continues the seq
+    // and measures `elapsed_ms` from the first segment's start.
+    sqlx::query(
+        "INSERT INTO ch_prompt_traces (execution_id, seq, stage, elapsed_ms, detail, created_at) \
+         SELECT $1, prev.next_seq + ev.seq, ev.stage, prev.offset_ms + ev.elapsed, ev.detail, \
+                $6 + ev.elapsed * INTERVAL '1 millisecond' \
+         FROM UNNEST($2::int[], $3::text[], $4::bigint[], $5::jsonb[]) \
+                  AS ev(seq, stage, elapsed, detail), \
+              (SELECT COALESCE(MAX(seq) + 1, 0) AS next_seq, \
+                      COALESCE(EXTRACT(EPOCH FROM $6 - MIN(created_at \
+                          - elapsed_ms * INTERVAL '1 millisecond')) * 1000, 0)::BIGINT AS offset_ms \
+               FROM ch_prompt_traces WHERE execution_id = $1) prev \
+         ON CONFLICT (execution_id, seq) DO NOTHING",
+    )
+    .bind(execution_id)
+    .bind(&seqs)
+    .bind(&stages)
+    .bind(&elapsed)
+    .bind(&details)
+    .bind(started_at)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/prompts/{execution_id}/trace
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn get_prompt_trace(
+    State(state): State<AppState>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    let events: Vec<TraceEvent> = sqlx::query_as(
+        "SELECT seq, stage, elapsed_ms, detail FROM ch_prompt_traces \
+         WHERE execution_id = $1 ORDER BY seq",
+    )
+    .bind(&execution_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| {
+        tracing::error!("get_prompt_trace: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if events.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let hooks = crate::hooks::runs_for_execution(&state.db, &execution_id)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("get_prompt_trace: hook runs unavailable: {}", e);
+            Vec::new()
+        });
+
+    let total_ms = events.last().map(|e| e.elapsed_ms).unwrap_or(0);
+    Ok(Json(json!({
+        "execution_id": execution_id,
+        "total_ms": total_ms,
+        "events": events,
+        "hooks": hooks,
+    })))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  POST /api/prompts/{execution_id}/rating
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct RatePromptRequest {
+    pub rating: i32,
+}
+
+pub async fn rate_prompt(
+    State(state): State<AppState>,
+    Path(execution_id): Path<String>,
+    Json(req): Json<RatePromptRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if !(1..=5).contains(&req.rating) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "rating must be between 1 and 5" })),
+        ));
+    }
+
+    let db_error = |e: sqlx::Error| {
+        tracing::error!("rate_prompt: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "Failed to store rating" })),
+        )
+    };
+    let mut tx = state.db.begin().await.map_err(db_error)?;
+    // Serialize ratings of one execution so concurrent ones cannot take the same seq.
+    sqlx::query("SELECT pg_advisory_xact_lock(hashtext('ch_prompt_traces:' || $1))")
+        .bind(&execution_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    // Appended after the persisted trace; an unknown execution inserts nothing.
+    let inserted = sqlx::query(
+        "INSERT INTO ch_prompt_traces (execution_id, seq, stage, elapsed_ms, detail) \
+         SELECT $1, MAX(seq) + 1, 'rated', MAX(elapsed_ms), $2 FROM ch_prompt_traces \
+         WHERE execution_id = $1 HAVING COUNT(*) > 0",
+    )
+    .bind(&execution_id)
+    .bind(json!({ "rating": req.rating }))
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .rows_affected();
+    tx.commit().await.map_err(db_error)?;
+
+    if inserted == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Unknown execution" })),
+        ));
+    }
+    Ok(Json(json!({ "execution_id": execution_id, "rating": req.rating })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace() -> PromptTrace {
+        let db = PgPool::connect_lazy("postgres://test@localhost:19999/test").expect("lazy pool");
+        PromptTrace::new(db, "exec-1")
+    }
+
+    #[tokio::test]
+    async fn events_are_numbered_in_order() {
+        let mut t = trace();
+        t.record("received", Value::Null);
+        t.record("provider_called", json!({ "model": "claude-sonnet-4-6" }));
+        t.record("completed", Value::Null);
+        let stages: Vec<(i32, String)> = t
+            .into_events()
+            .into_iter()
+            .map(|e| (e.seq, e.stage))
+            .collect();
+        assert_eq!(
+            stages,
+            vec![
+                (0, "received".to_string()),
+                (1, "provider_called".to_string()),
+                (2, "completed".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn tokens_are_counted_and_summarised() {
+        let mut t = trace();
+        t.token();
+        t.token();
+        t.token();
+        let events = t.into_events();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].stage, "first_token");
+        assert_eq!(events[1].stage, "stream_summary");
+        assert_eq!(events[1].detail["token_chunks"], 3);
+    }
+
+    #[tokio::test]
+    async fn failure_is_the_last_failed_event() {
+        let mut t = trace();
+        assert!(t.failure().is_none());
+        t.record("failed", json!({ "reason": "offline" }));
+        t.record("failed", json!({ "reason": "timeout" }));
+        assert_eq!(t.failure().unwrap()["reason"], "timeout");
+        assert_eq!(t.execution_id(), "exec-1");
+    }
+}
@@ -0,0 +1,412 @@
+//! Claim and dispatch: worker slots, per-provider lanes and picking the next
+//! eligible prompt.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use serde_json::{Value, json};
+use tokio::sync::Semaphore;
+
+use super::{
+    BackgroundPrompt, OLLAMA_PREFIX, QUEUE_ORDER, config, emit, execute, locks, trace_id, wake,
+};
+use crate::prompt_trace::PromptTrace;
+use crate::state::AppState;
+
+/// Longest prompt (chars) eligible for the Ollama batch.
+const OLLAMA_BATCH_MAX_CHARS: i32 = 4_000;
+
+/// Worker slots: up to `max_concurrent` prompts plus `ollama_batch` short
+/// Ollama prompts at once, resized to the current settings on every pass.
+pub(crate) struct Dispatcher {
+    slots: Arc<Semaphore>,
+    batch_slots: Arc<Semaphore>,
+    size: (usize, usize),
+}
+
+impl Dispatcher {
+    pub(crate) fn new() -> Self {
+        let cfg = config();
+        Self {
+            slots: Arc::new(Semaphore::new(cfg.max_concurrent)),
+            batch_slots: Arc::new(Semaphore::new(cfg.ollama_batch)),
+            size: (cfg.max_concurrent, cfg.ollama_batch),
+        }
+    }
+
+    /// Claim eligible prompts into the free slots and run them; `low`
+    /// prompts only when `idle`.
+    pub(crate) async fn run(&mut self, state: &AppState, idle: bool) {
+        let cfg = config();
+        resize(&self.slots, &mut self.size.0, cfg.max_concurrent);
+        resize(&self.batch_slots, &mut self.size.1, cfg.ollama_batch);
+        dispatch(state, &self.slots, idle, false).await;
+        dispatch(state, &self.batch_slots, idle, true).await;
+    }
+}
+
+/// Move `slots` (currently `size` permits) towards `target`. Permits held by
+/// running prompts cannot be taken back; they are forgotten on later passes.
+fn resize(slots: &Semaphore, size: &mut usize, target: usize) {
+    if target > *size {
+        slots.add_permits(target - *size);
+        *size = target;
+    } else if target < *size {
+        *size -= slots.forget_permits(*size - target);
+    }
+}
+
+/// Claim prompts into the free permits of `slots` and run them. With
+/// `ollama_batch` only short Ollama prompts are considered.
+async fn dispatch(state: &AppState, slots: &Arc<Semaphore>, idle: bool, ollama_batch: bool) {
+    while let Ok(permit) = slots.clone().try_acquire_owned() {
+        // No new claims once shutdown has started.
+        let Some(in_flight) = crate::shutdown::track() else {
+            break;
+        };
+        let (job, lane) = match claim_next(state, idle, ollama_batch).await {
+            Ok(Some(claimed)) => claimed,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::warn!("queue: {}", e);
+                break;
+            }
+        };
+        let state = state.clone();
+        tokio::spawn(async move {
+            let correlation_id = job.correlation_id.clone();
+            let run = crate::correlation::scope(correlation_id, execute::run_job(&state, job));
+            if let Err(e) = run.await {
+                tracing::warn!("queue: {}", e);
+            }
+            drop(lane);
+            drop(in_flight);
+            drop(permit);
+            wake();
+        });
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Provider lanes
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Lanes shown in the queue snapshot.
+const LANES: &[&str] = &[
+    "anthropic",
+    "google",
+    "openrouter",
+    "groq",
+    "azure",
+    "ollama",
+    "other",
+];
+
+/// Prompts currently running per provider lane.
+static LANE_RUNNING: LazyLock<Mutex<HashMap<&'static str, usize>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Lane a prompt runs in: the provider `complete_prompt` sends it to.
+/// Prompts without a model use the default Claude model.
+pub(crate) fn lane_of(model: Option<&str>) -> &'static str {
+    match model {
+        Some(m) => crate::handlers::prompt::Backend::for_model(m).name(),
+        None => "anthropic",
+    }
+}
+
+/// Holds a place in a provider lane until dropped.
+struct LaneGuard(&'static str);
+
+impl Drop for LaneGuard {
+    fn drop(&mut self) {
+        let mut running = LANE_RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(n) = running.get_mut(self.0) {
+            *n = n.saturating_sub(1);
+        }
+    }
+}
+
+/// Take a place in `lane`, or `None` when it is at its configured limit.
+fn enter_lane(lane: &'static str, lanes: &HashMap<String, usize>) -> Option<LaneGuard> {
+    let mut running = LANE_RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let n = running.entry(lane).or_default();
+    if lanes.get(lane).is_some_and(|limit| *n >= *limit) {
+        return None;
+    }
+    *n += 1;
+    Some(LaneGuard(lane))
+}
+
+/// Per-lane `{ running, queued, limit }` for the queue snapshot.
+pub(super) async fn lane_stats(state: &AppState) -> Result<Value, sqlx::Error> {
+    let queued: Vec<(Option<String>, i64)> = sqlx::query_as(
+        "SELECT model, COUNT(*) FROM ch_background_prompts WHERE status = 'queued' GROUP BY model",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let mut queued_by_lane: HashMap<&'static str, i64> = HashMap::new();
+    for (model, count) in queued {
+        *queued_by_lane.entry(lane_of(model.as_deref())).or_default() += count;
+    }
+    let running = LANE_RUNNING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    let cfg = config();
+    let limits = &cfg.lanes;
+    let mut stats = serde_json::Map::new();
+    for lane in LANES {
+        stats.insert(
+            lane.to_string(),
+            json!({
+                "running": running.get(lane).copied().unwrap_or(0),
+                "queued": queued_by_lane.get(lane).copied().unwrap_or(0),
+                "limit": limits.get(lane),
+            }),
+        );
+    }
+    Ok(Value::Object(stats))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Claiming
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Blocked prompts whose `conflict` is already traced, so a prompt waiting
+/// through many passes is traced once; cleared when it is claimed.
+static CONFLICTS_TRACED: LazyLock<Mutex<HashSet<i64>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Whether `id` was newly marked as blocked (`true`) or already was.
+fn first_conflict(id: i64) -> bool {
+    CONFLICTS_TRACED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(id)
+}
+
+/// Claim the next eligible prompt whose provider lane has room and whose
+/// session has nothing running, if any. Queued prompts already past their
+/// deadline are failed first so they never take a slot.
+async fn claim_next(
+    state: &AppState,
+    idle: bool,
+    ollama_batch: bool,
+) -> Result<Option<(BackgroundPrompt, LaneGuard)>, String> {
+    let expired: Vec<i64> = sqlx::query_scalar(
+        "UPDATE ch_background_prompts \
+         SET status = 'failed', finished_at = NOW(), \
+             error = 'Timeout: deadline passed before the prompt could run' \
+         WHERE status = 'queued' AND deadline <= NOW() \
+         RETURNING id",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to expire prompts: {}", e))?;
+    for id in expired {
+        emit(
+            "prompt-completed",
+            json!({ "id": id, "status": "failed", "error": "Timeout", "duration_ms": 0 }),
+        );
+    }
+
+    // id, model, affected files, override_lock, witcher_mode, project
+    type Candidate = (
+        i64,
+        Option<String>,
+        Vec<String>,
+        bool,
+        bool,
+        Option<uuid::Uuid>,
+    );
+    let candidates: Vec<Candidate> = sqlx::query_as(&format!(
+        "SELECT id, model, affected_files, override_lock, \
+                COALESCE((SELECT s.witcher_mode FROM ch_sessions s \
+                          WHERE s.id = q.session_id), FALSE), \
+                project_id \
+         FROM ch_background_prompts q \
+         WHERE status = 'queued' AND (priority = 'normal' OR $1) \
+           AND (NOT $2 OR (model LIKE '{}%' AND char_length(prompt) <= $3)) \
+           AND NOT EXISTS (SELECT 1 FROM ch_background_prompts r \
+                           WHERE r.status = 'running' AND r.session_id = q.session_id) \
+           AND NOT EXISTS (SELECT 1 FROM ch_sessions p \
+                           WHERE p.id = q.session_id AND p.quota_exceeded_at IS NOT NULL) \
+         {} LIMIT 200",
+        OLLAMA_PREFIX, QUEUE_ORDER
+    ))
+    .bind(idle)
+    .bind(ollama_batch)
+    .bind(OLLAMA_BATCH_MAX_CHARS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to list queued prompts: {}", e))?;
+
+    let cfg = config();
+    let held = locks::held(state, cfg.file_locks).await?;
+    let lanes = &cfg.lanes;
+    for (id, model, affected, override_lock, witcher_mode, project_id) in candidates {
+        if locks::is_blocked(&held, project_id, &affected, override_lock) {
+            if first_conflict(id) {
+                let files = locks::blocking_files(&held, project_id, &affected);
+                crate::prompt_trace::record_once(
+                    &state.db,
+                    &trace_id(id),
+                    "conflict",
+                    json!({ "files": files }),
+                )
+                .await;
+            }
+            continue;
+        }
+        // Witcher mode: route prompts without an explicit model, so the lane
+        // is the routed provider's.
+        let decision = if witcher_mode && model.is_none() {
+            let prompt = prompt_text(state, id).await?;
+            Some(crate::witcher_router::WitcherRouter::route(state, &prompt).await)
+        } else {
+            None
+        };
+        let mut model = decision.as_ref().map(|d| d.model.clone()).or(model);
+        let mut budget_fallback = false;
+        // Provider over its budget cap: run on a free local model instead. With
+        // none available the run is refused by the provider's budget gate.
+        if let Some(over) = crate::budget::exceeded(&state.db, lane_of(model.as_deref())).await
+            && let Some(local) =
+                crate::budget::local_model(state, &prompt_text(state, id).await?).await
+        {
+            let reason = over.message();
+            tracing::info!(id, model = %local, "queue: {}, running locally", reason);
+            model = Some(local);
+            budget_fallback = true;
+        }
+        let Some(lane) = enter_lane(lane_of(model.as_deref()), lanes) else {
+            continue;
+        };
+        let claimed = sqlx::query_as::<_, BackgroundPrompt>(
+            "UPDATE ch_background_prompts \
+             SET status = 'running', started_at = NOW(), attempts = attempts + 1, \
+                 model = $2, route_decision = COALESCE($3, route_decision) \
+             WHERE id = $1 AND status = 'queued' \
+             RETURNING *",
+        )
+        .bind(id)
+        .bind(&model)
+        .bind(decision.as_ref().map(|d| json!(d)))
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| format!("Failed to claim prompt: {}", e))?;
+        // Cancelled in the meantime: the lane place is released on drop.
+        if let Some(job) = claimed {
+            if let Some(decision) = &decision {
+                crate::witcher_router::WitcherRouter::record(&state.db, job.id, decision).await;
+            }
+            CONFLICTS_TRACED
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&job.id);
+            let mut trace = PromptTrace::new(state.db.clone(), &trace_id(job.id));
+            trace.record(
+                "conflicts_checked",
+                json!({
+                    "file_locks": cfg.file_locks,
+                    "held_locks": held.len(),
+                    "override_lock": override_lock,
+                }),
+            );
+            trace.record(
+                "dequeued",
+                json!({
+                    "model": job.model,
+                    "lane": lane.0,
+                    "attempt": job.attempts,
+                    "routed": decision.is_some(),
+                    "budget_fallback": budget_fallback,
+                }),
+            );
+            trace.finish().await;
+            return Ok(Some((job, lane)));
+        }
+    }
+    Ok(None)
+}
+
+async fn prompt_text(state: &AppState, id: i64) -> Result<String, String> {
+    sqlx::query_scalar("SELECT prompt FROM ch_background_prompts WHERE id = $1")
+        .bind(id)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| format!("Failed to load prompt: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::parse_lanes;
+
+    #[test]
+    fn lanes_parse_and_cap_per_provider() {
+        let lanes = parse_lanes(" Google=1, other=2 ,bogus, openai=0, anthropic=x");
+        assert_eq!(lanes.len(), 2);
+        assert_eq!(lanes["google"], 1);
+        assert_eq!(lanes["other"], 2);
+
+        assert_eq!(lane_of(Some("gemini-2.5-pro")), "google");
+        assert_eq!(lane_of(Some("groq/llama-3.3-70b-versatile")), "groq");
+        assert_eq!(lane_of(Some("llama3")), "other");
+        assert_eq!(lane_of(None), "anthropic");
+        assert_eq!(lane_of(Some("ollama/llama3.1:8b")), "ollama");
+
+        let first = enter_lane("google", &lanes).expect("lane has room");
+        assert!(enter_lane("google", &lanes).is_none());
+        drop(first);
+        assert!(enter_lane("google", &lanes).is_some());
+        // Unlisted providers are only bound by the global limit.
+        let many: Vec<_> = (0..5)
+            .filter_map(|_| enter_lane("openai", &lanes))
+            .collect();
+        assert_eq!(many.len(), 5);
+    }
+
+    #[test]
+    fn google_lane_jobs_are_sent_to_google() {
+        use crate::handlers::prompt::Backend;
+        assert_eq!(lane_of(Some("gemini-2.5-pro")), "google");
+        assert_eq!(Backend::for_model("gemini-2.5-pro"), Backend::Google);
+        // Every lane is the provider its jobs are dispatched to.
+        for model in [
+            "claude-sonnet-4-6",
+            "gemini-2.5-flash",
+            "groq/llama-3.3-70b-versatile",
+            "openrouter/meta-llama/llama-3.1-8b-instruct",
+            "ollama/llama3.1:8b",
+        ] {
+            assert_eq!(
+                lane_of(Some(model)),
+                Backend::for_model(model).name(),
+                "{model}"
+            );
+        }
+        assert_eq!(Backend::for_model("llama3"), Backend::Unserved);
+    }
+
+    #[test]
+    fn conflicts_are_traced_once_per_prompt() {
+        assert!(first_conflict(-7));
+        assert!(!first_conflict(-7));
+        CONFLICTS_TRACED.lock().unwrap().remove(&-7);
+        assert!(first_conflict(-7));
+    }
+
+    #[test]
+    fn slots_resize_towards_the_configured_size() {
+        let slots = Semaphore::new(2);
+        let mut size = 2;
+        resize(&slots, &mut size, 4);
+        assert_eq!((size, slots.available_permits()), (4, 4));
+        let _held = slots.try_acquire_many(3).unwrap();
+        // Only the one free permit can be taken back now.
+        resize(&slots, &mut size, 1);
+        assert_eq!((size, slots.available_permits()), (3, 0));
+    }
+}
@@ -0,0 +1,58 @@
+//! Worker wake-ups and the `/api/background-prompts/events` SSE stream.
+
+use std::convert::Infallible;
+use std::sync::LazyLock;
+
+use axum::response::sse::{Event, KeepAlive, Sse};
+use futures_util::stream::Stream;
+use serde_json::{Value, json};
+use tokio::sync::{Notify, broadcast};
+
+use crate::app_log::Level;
+
+/// Wakes the worker early (new prompt queued, slot freed).
+static WAKE: Notify = Notify::const_new();
+
+/// `prompt-started` / `prompt-completed` events for `/api/background-prompts/events`.
+static EVENTS: LazyLock<broadcast::Sender<Value>> = LazyLock::new(|| broadcast::channel(64).0);
+
+/// Wake the worker now instead of on its next tick.
+pub fn wake() {
+    WAKE.notify_one();
+}
+
+/// Resolves once `wake` has been called (at once if it was called since the
+/// last wait).
+pub(crate) async fn woken() {
+    WAKE.notified().await;
+}
+
+pub(crate) fn emit(event: &str, data: Value) {
+    let level = match data["status"].as_str() {
+        Some("failed") => Level::Warn,
+        _ => Level::Info,
+    };
+    crate::app_log::record(level, "background", event, data.clone());
+    // No subscribers is fine — events are best effort.
+    let _ = EVENTS.send(json!({ "event": event, "data": data }));
+}
+
+/// `GET /api/background-prompts/events` — SSE stream of worker events.
+pub async fn events() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let mut rx = EVENTS.subscribe();
+    let stream = async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => {
+                    let name = msg["event"].as_str().unwrap_or("message").to_string();
+                    if let Ok(event) = Event::default().event(name).json_data(&msg["data"]) {
+                        yield Ok(event);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    };
+    Sse::new(stream).keep_alive(KeepAlive::new())
+}
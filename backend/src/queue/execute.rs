@@ -0,0 +1,292 @@
+//! Running a claimed prompt: the provider call under the prompt's time
+//! budget, the attempt record and where the outcome is stored.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+
+use super::{BackgroundPrompt, OLLAMA_PREFIX, config, emit, retry, trace_id};
+use crate::handlers::prompt::complete_prompt;
+use crate::hooks::{HookEvent, HookPayload};
+use crate::prompt_trace::PromptTrace;
+use crate::state::AppState;
+
+/// Time a run may take: the prompt's timeout (else `default`), shortened to
+/// its deadline. `None` when the deadline has already passed.
+fn execution_budget(
+    timeout_ms: Option<i32>,
+    default: Duration,
+    deadline: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    let timeout = timeout_ms
+        .map(|ms| Duration::from_millis(ms.max(1) as u64))
+        .unwrap_or(default);
+    match deadline {
+        Some(d) => (d - now).to_std().ok().map(|left| left.min(timeout)),
+        None => Some(timeout),
+    }
+}
+
+/// Execute a claimed prompt and store its outcome.
+pub(super) async fn run_job(state: &AppState, job: BackgroundPrompt) -> Result<(), String> {
+    tracing::info!(id = job.id, priority = %job.priority, "queue: executing background prompt");
+    emit(
+        "prompt-started",
+        json!({
+            "id": job.id,
+            "priority": job.priority,
+            "model": job.model,
+            "correlation_id": job.correlation_id,
+        }),
+    );
+    let mut trace = PromptTrace::new(state.db.clone(), &trace_id(job.id));
+    trace.record(
+        "provider_called",
+        json!({ "model": job.model, "attempt": job.attempts }),
+    );
+    let started = std::time::Instant::now();
+    let (status, result, error) = match execute(state, &job).await {
+        Ok(text) => ("done", Some(text), None),
+        Err(e) => ("failed", None, Some(e)),
+    };
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match &error {
+        None => trace.record("completed", json!({ "duration_ms": duration_ms })),
+        Some(e) => trace.record("failed", json!({ "error": e, "duration_ms": duration_ms })),
+    }
+    trace.finish().await;
+    sqlx::query(
+        "INSERT INTO ch_background_prompt_attempts \
+             (prompt_id, attempt, model, status, error, duration_ms) \
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(job.id)
+    .bind(job.attempts)
+    .bind(&job.model)
+    .bind(status)
+    .bind(&error)
+    .bind(duration_ms as i64)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to record attempt: {}", e))?;
+    if job.route_decision.is_some() {
+        let cost = match (&job.model, result.as_deref()) {
+            (Some(model), Some(text)) => {
+                let event = crate::costs::CostEvent::estimate(model, None, &job.prompt, text);
+                Some(crate::costs::cost_usd(
+                    &event.model,
+                    event.input_tokens,
+                    event.output_tokens,
+                ))
+            }
+            _ => None,
+        };
+        crate::witcher_router::WitcherRouter::record_outcome(
+            &state.db,
+            job.id,
+            status == "done",
+            duration_ms as i64,
+            cost,
+        )
+        .await;
+    }
+
+    if let Some(err) = error.as_deref()
+        && retry::requeue(state, &job, err, duration_ms).await?
+    {
+        return Ok(());
+    }
+
+    emit(
+        "prompt-completed",
+        json!({
+            "id": job.id,
+            "status": status,
+            "error": error,
+            "duration_ms": duration_ms,
+            "correlation_id": job.correlation_id,
+        }),
+    );
+    sqlx::query(
+        "UPDATE ch_background_prompts SET status = $2, result = $3, error = $4, finished_at = NOW() \
+         WHERE id = $1",
+    )
+    .bind(job.id)
+    .bind(status)
+    .bind(&result)
+    .bind(&error)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store result: {}", e))?;
+
+    // Scheduled prompts bound to a session land in its conversation.
+    let session_id: Option<uuid::Uuid> =
+        sqlx::query_scalar("SELECT session_id FROM ch_background_prompts WHERE id = $1")
+            .bind(job.id)
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| format!("Failed to load session binding: {}", e))?
+            .flatten();
+    // Prompts without a model run on the default Claude model.
+    let model = job.model.as_deref().unwrap_or("claude-default");
+    crate::hooks::dispatch(
+        state.db.clone(),
+        HookPayload {
+            event: if status == "done" {
+                HookEvent::Completed
+            } else {
+                HookEvent::Failed
+            },
+            execution_id: trace_id(job.id),
+            session_id,
+            model: model.to_string(),
+            working_directory: String::new(),
+            prompt: job.prompt.clone(),
+            response: result.clone().unwrap_or_default(),
+            detail: match &error {
+                Some(e) => json!({ "error": e, "prompt_id": job.id }),
+                None => Value::Null,
+            },
+        },
+    );
+    match (session_id, result.as_deref()) {
+        // Stored messages record the exchange's cost against the session.
+        (Some(sid), Some(text)) => {
+            crate::handlers::streaming::helpers::store_ws_messages(
+                state,
+                &sid,
+                Some(model),
+                &job.prompt,
+                text,
+            )
+            .await
+            .map_err(|e| format!("Failed to store session messages: {}", e))?;
+        }
+        (None, Some(text)) => crate::costs::schedule(
+            &state.db,
+            crate::costs::CostEvent::estimate(model, None, &job.prompt, text),
+        ),
+        _ => {}
+    }
+    Ok(())
+}
+
+async fn execute(state: &AppState, job: &BackgroundPrompt) -> Result<String, String> {
+    let Some(budget) = execution_budget(
+        job.timeout_ms,
+        config().execution_timeout,
+        job.deadline,
+        Utc::now(),
+    ) else {
+        return Err("Timeout: deadline passed before the prompt could run".to_string());
+    };
+    let local = job
+        .model
+        .as_deref()
+        .and_then(|m| m.strip_prefix(OLLAMA_PREFIX));
+    // Dropping the provider future on expiry aborts the HTTP call.
+    let secs = budget.as_secs().max(1);
+    let call = async {
+        match local {
+            Some(model) => {
+                // Session-bound prompts keep the conversation as native chat history.
+                let history = session_history(state, job.id).await;
+                complete_ollama(state, model, &history, &job.prompt).await
+            }
+            // Screens the prompt unless it stays on a local model.
+            None => {
+                complete_prompt(state, &job.prompt, job.model.clone(), secs, "background").await
+            }
+        }
+    };
+    let reply = match tokio::time::timeout(budget, call).await {
+        Ok(outcome) => outcome?,
+        Err(_) => {
+            return Err(format!(
+                "Timeout: no response within {} ms",
+                budget.as_millis()
+            ));
+        }
+    };
+    crate::safety::screen_response(&reply, "background").map_err(blocked)?;
+    Ok(reply)
+}
+
+pub(super) fn blocked(message: String) -> String {
+    format!("{}: {}", crate::safety::BLOCKED_CODE, message)
+}
+
+/// Single non-streaming chat turn against the local Ollama server, after
+/// `history` (earlier `{ role, content }` messages, oldest first). Queued at
+/// low priority behind interactive Ollama requests.
+pub(crate) async fn complete_ollama(
+    state: &AppState,
+    model: &str,
+    history: &[Value],
+    prompt: &str,
+) -> Result<String, String> {
+    let mut messages = history.to_vec();
+    messages.push(json!({ "role": "user", "content": prompt }));
+    let request_id = format!("background-{}", uuid::Uuid::new_v4());
+    let body = crate::ollama_queue::dispatch(
+        &request_id,
+        crate::ollama_queue::Priority::Low,
+        crate::ollama::ollama_chat(state, model, &messages, &[]),
+    )
+    .await?;
+    Ok(crate::ai_gateway::handlers::helpers::extract_content_text(
+        &crate::ai_gateway::AiProvider::Ollama,
+        &body,
+    ))
+}
+
+/// Conversation so far of the session a prompt is bound to (empty when unbound).
+async fn session_history(state: &AppState, prompt_id: i64) -> Vec<Value> {
+    let session_id: Option<uuid::Uuid> =
+        sqlx::query_scalar("SELECT session_id FROM ch_background_prompts WHERE id = $1")
+            .bind(prompt_id)
+            .fetch_optional(&state.db)
+            .await
+            .ok()
+            .flatten()
+            .flatten();
+    match session_id {
+        Some(sid) => {
+            crate::handlers::streaming::helpers::load_session_history(&state.db, &sid).await
+        }
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::EXECUTION_TIMEOUT_SECS;
+
+    #[test]
+    fn budget_is_timeout_capped_by_deadline() {
+        let now = Utc::now();
+        let default = Duration::from_secs(EXECUTION_TIMEOUT_SECS);
+        assert_eq!(execution_budget(None, default, None, now), Some(default));
+        assert_eq!(
+            execution_budget(Some(1_500), default, None, now),
+            Some(Duration::from_millis(1_500))
+        );
+        let soon = now + chrono::Duration::seconds(10);
+        assert_eq!(
+            execution_budget(None, default, Some(soon), now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            execution_budget(Some(2_000), default, Some(soon), now),
+            Some(Duration::from_secs(2))
+        );
+        let past = now - chrono::Duration::seconds(1);
+        assert_eq!(
+            execution_budget(Some(2_000), default, Some(past), now),
+            None
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! Queue endpoints: enqueue, list, the queue snapshot, run history and cancel.
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use super::{BackgroundPrompt, QUEUE_ORDER, claim, config, locks, not_queued, trace_id, wake};
+use crate::api_error::{bad_request, db_error};
+use crate::idle_scavenger::idle_status;
+use crate::state::AppState;
+
+const MAX_PROMPT_CHARS: usize = 100_000;
+/// Upper bound accepted for a per-prompt `timeout_ms` (1 hour).
+const MAX_TIMEOUT_MS: i32 = 3_600_000;
+/// How long an idempotency key deduplicates enqueue requests.
+const IDEMPOTENCY_WINDOW_SECS: i64 = 600;
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct EnqueueRequest {
+    pub prompt: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// `low` (default, idle-only) or `normal`
+    #[serde(default)]
+    pub priority: Option<String>,
+    /// Per-run limit in milliseconds (default 5 minutes, max 1 hour)
+    #[serde(default)]
+    pub timeout_ms: Option<i32>,
+    /// Fail the prompt if it has not finished by then
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Client-chosen key; retries with the same key do not enqueue twice
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    /// Files the prompt will touch; inferred from the prompt when omitted
+    #[serde(default)]
+    pub affected_files: Option<Vec<String>>,
+    /// Start even when another running prompt holds one of the files
+    #[serde(default)]
+    pub override_lock: bool,
+    /// Correlation id to run under; the request's id when omitted
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Trimmed idempotency key; blank keys count as absent.
+fn idempotency_key(raw: Option<&str>) -> Result<Option<&str>, ()> {
+    match raw.map(str::trim).filter(|k| !k.is_empty()) {
+        Some(k) if k.len() > MAX_IDEMPOTENCY_KEY_LEN => Err(()),
+        key => Ok(key),
+    }
+}
+
+/// `POST /api/background-prompts`
+pub async fn enqueue(
+    State(state): State<AppState>,
+    Json(req): Json<EnqueueRequest>,
+) -> Result<(StatusCode, Json<BackgroundPrompt>), (StatusCode, Json<Value>)> {
+    if req.prompt.trim().is_empty() {
+        return Err(bad_request("prompt must not be empty"));
+    }
+    if req.prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(bad_request("prompt is too long"));
+    }
+    let priority = req.priority.as_deref().unwrap_or("low");
+    if !matches!(priority, "low" | "normal") {
+        return Err(bad_request("priority must be 'low' or 'normal'"));
+    }
+    if req
+        .timeout_ms
+        .is_some_and(|ms| !(1..=MAX_TIMEOUT_MS).contains(&ms))
+    {
+        return Err(bad_request(&format!(
+            "timeout_ms must be between 1 and {}",
+            MAX_TIMEOUT_MS
+        )));
+    }
+    if req.deadline.is_some_and(|d| d <= Utc::now()) {
+        return Err(bad_request("deadline must be in the future"));
+    }
+    let key = idempotency_key(req.idempotency_key.as_deref())
+        .map_err(|_| bad_request("idempotency_key is too long"))?;
+    let correlation_id = match req.correlation_id.as_deref() {
+        Some(raw) => crate::correlation::accept(raw)
+            .ok_or_else(|| bad_request("correlation_id is invalid"))?
+            .to_string(),
+        None => crate::correlation::current().unwrap_or_else(crate::correlation::new_id),
+    };
+
+    // Inferred outside the transaction: it may ask a local model.
+    let workspace: String =
+        sqlx::query_scalar("SELECT COALESCE(working_directory, '') FROM ch_settings WHERE id = 1")
+            .fetch_optional(&state.db)
+            .await
+            .map_err(db_error("queue"))?
+            .unwrap_or_default();
+    let affected_files = match &req.affected_files {
+        Some(files) => {
+            crate::affected_files::normalize(std::path::Path::new(&workspace), files).await
+        }
+        None => crate::affected_files::infer(&state, &req.prompt, &workspace).await,
+    };
+
+    let mut tx = state.db.begin().await.map_err(db_error("queue"))?;
+    if let Some(key) = key {
+        // Serialize requests sharing a key so concurrent retries cannot both insert.
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext($1))")
+            .bind(key)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error("queue"))?;
+        let existing = sqlx::query_as::<_, BackgroundPrompt>(
+            "SELECT * FROM ch_background_prompts \
+             WHERE idempotency_key = $1 AND created_at > NOW() - make_interval(secs => $2) \
+             ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(key)
+        .bind(IDEMPOTENCY_WINDOW_SECS as f64)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(db_error("queue"))?;
+        if let Some(row) = existing {
+            return Ok((StatusCode::OK, Json(row)));
+        }
+    }
+    let mut row = sqlx::query_as::<_, BackgroundPrompt>(
+        "INSERT INTO ch_background_prompts \
+             (prompt, model, priority, timeout_ms, deadline, idempotency_key, affected_files, \
+              override_lock, correlation_id) \
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
+    )
+    .bind(&req.prompt)
+    .bind(&req.model)
+    .bind(priority)
+    .bind(req.timeout_ms)
+    .bind(req.deadline)
+    .bind(key)
+    .bind(&affected_files)
+    .bind(req.override_lock)
+    .bind(&correlation_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error("queue"))?;
+    let mode = crate::permissions::default_mode();
+    let held = crate::approvals::hold_prompt(&mut tx, mode, row.id, &req.prompt)
+        .await
+        .map_err(db_error("queue"))?;
+    tx.commit().await.map_err(db_error("queue"))?;
+    crate::prompt_trace::record_once(
+        &state.db,
+        &trace_id(row.id),
+        "enqueued",
+        json!({
+            "priority": priority,
+            "model": row.model,
+            "affected_files": row.affected_files.len(),
+            "awaiting_approval": held.is_some(),
+            "correlation_id": correlation_id,
+        }),
+    )
+    .await;
+    match held {
+        Some(approval) => {
+            row.status = "awaiting_approval".to_string();
+            crate::approvals::announce(&approval);
+        }
+        None => wake(),
+    }
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+/// `GET /api/background-prompts`
+pub async fn list(
+    State(state): State<AppState>,
+    Query(q): Query<ListQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let rows = sqlx::query_as::<_, BackgroundPrompt>(
+        "SELECT * FROM ch_background_prompts WHERE ($1::TEXT IS NULL OR status = $1) \
+         ORDER BY created_at DESC LIMIT $2",
+    )
+    .bind(&q.status)
+    .bind(q.limit.unwrap_or(100).clamp(1, 500))
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    Ok(Json(json!({
+        "idle": idle_status(&state).await,
+        "prompts": rows,
+    })))
+}
+
+/// `GET /api/background-prompts/queue` — what runs next, in order. `low`
+/// prompts are only picked up while `idle.idle` is true.
+pub async fn queue_snapshot(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let running = sqlx::query_as::<_, BackgroundPrompt>(
+        "SELECT * FROM ch_background_prompts WHERE status = 'running' ORDER BY started_at ASC",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    let queued = sqlx::query_as::<_, BackgroundPrompt>(&format!(
+        "SELECT * FROM ch_background_prompts WHERE status = 'queued' {}",
+        QUEUE_ORDER
+    ))
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    let sessions: std::collections::HashMap<i64, String> = sqlx::query_as::<_, (i64, String)>(
+        "SELECT id, session_id::TEXT FROM ch_background_prompts \
+         WHERE status IN ('queued', 'running') AND session_id IS NOT NULL",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue"))?
+    .into_iter()
+    .collect();
+    let active: Vec<&BackgroundPrompt> = running.iter().chain(queued.iter()).collect();
+    let entry = |p: &BackgroundPrompt| {
+        let overlaps: Vec<Value> = active
+            .iter()
+            .filter(|o| o.id != p.id && o.project_id == p.project_id)
+            .filter_map(|o| {
+                let shared = crate::affected_files::overlaps(&p.affected_files, &o.affected_files);
+                (!shared.is_empty()).then(|| json!({ "id": o.id, "overlap": shared }))
+            })
+            .collect();
+        let conflicts_with: Vec<&Value> = overlaps.iter().map(|o| &o["id"]).collect();
+        json!({
+            "id": p.id,
+            "session_id": sessions.get(&p.id),
+            "prompt": p.prompt.chars().take(200).collect::<String>(),
+            "model": p.model,
+            "priority": p.priority,
+            "created_at": p.created_at,
+            "deadline": p.deadline,
+            "affected_files": p.affected_files,
+            "conflicts_with": conflicts_with,
+            "conflicts": overlaps,
+            "override_lock": p.override_lock,
+            "blocked_by": if p.status == "queued" {
+                locks::lock_holders(p, &running, config().file_locks)
+            } else {
+                Vec::new()
+            },
+        })
+    };
+    Ok(Json(json!({
+        "idle": idle_status(&state).await,
+        "max_concurrent": config().max_concurrent,
+        "lanes": claim::lane_stats(&state).await.map_err(db_error("queue"))?,
+        "running": running.iter().map(entry).collect::<Vec<_>>(),
+        "queued": queued.iter().map(entry).collect::<Vec<_>>(),
+    })))
+}
+
+/// One recorded run of a background prompt.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PromptAttempt {
+    pub attempt: i32,
+    pub model: Option<String>,
+    pub status: String,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub finished_at: DateTime<Utc>,
+}
+
+/// `GET /api/background-prompts/{id}/attempts`
+pub async fn list_attempts(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let attempts = sqlx::query_as::<_, PromptAttempt>(
+        "SELECT attempt, model, status, error, duration_ms, finished_at \
+         FROM ch_background_prompt_attempts WHERE prompt_id = $1 ORDER BY id ASC",
+    )
+    .bind(id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    Ok(Json(json!({ "id": id, "attempts": attempts })))
+}
+
+/// `DELETE /api/background-prompts/{id}` — only queued prompts (or ones
+/// awaiting approval) can be cancelled.
+pub async fn cancel(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query(
+        "UPDATE ch_background_prompts SET status = 'cancelled', finished_at = NOW() \
+         WHERE id = $1 AND status IN ('queued', 'awaiting_approval')",
+    )
+    .bind(id)
+    .execute(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    if result.rows_affected() == 0 {
+        return Err(not_queued());
+    }
+    crate::approvals::withdraw_prompt(&state.db, id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idempotency_keys_are_trimmed_and_bounded() {
+        assert_eq!(idempotency_key(Some("  abc ")), Ok(Some("abc")));
+        assert_eq!(idempotency_key(Some("   ")), Ok(None));
+        assert_eq!(idempotency_key(None), Ok(None));
+        assert!(idempotency_key(Some(&"k".repeat(MAX_IDEMPOTENCY_KEY_LEN + 1))).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Advisory file locks: with `file_locks` on, a running prompt holds its
+//! `affected_files` and overlapping queued prompts of the same project wait
+//! unless they have `override_lock` set.
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{BackgroundPrompt, not_queued, wake};
+use crate::api_error::db_error;
+use crate::state::AppState;
+
+/// `(project, affected_files)` of a running prompt.
+pub(super) type HeldLock = (Option<uuid::Uuid>, Vec<String>);
+
+/// Files held by running prompts; none while file locks are off.
+pub(super) async fn held(state: &AppState, enabled: bool) -> Result<Vec<HeldLock>, String> {
+    if !enabled {
+        return Ok(Vec::new());
+    }
+    sqlx::query_as(
+        "SELECT project_id, affected_files FROM ch_background_prompts \
+         WHERE status = 'running' AND cardinality(affected_files) > 0",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to list file locks: {}", e))
+}
+
+/// Whether a queued prompt has to wait for one of the `held` locks. Checked
+/// here rather than in SQL since entries may be directory or glob patterns.
+pub(super) fn is_blocked(
+    held: &[HeldLock],
+    project_id: Option<uuid::Uuid>,
+    affected: &[String],
+    override_lock: bool,
+) -> bool {
+    !override_lock
+        && held.iter().any(|(project, files)| {
+            *project == project_id && crate::affected_files::would_conflict(affected, files)
+        })
+}
+
+/// Entries of `affected` overlapping a lock held in the same project.
+pub(super) fn blocking_files(
+    held: &[HeldLock],
+    project_id: Option<uuid::Uuid>,
+    affected: &[String],
+) -> Vec<String> {
+    let mut files: Vec<String> = held
+        .iter()
+        .filter(|(project, _)| *project == project_id)
+        .flat_map(|(_, files)| crate::affected_files::overlaps(affected, files))
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Running prompts holding a file lock that keeps `prompt` from starting.
+pub(super) fn lock_holders(
+    prompt: &BackgroundPrompt,
+    running: &[BackgroundPrompt],
+    locks: bool,
+) -> Vec<i64> {
+    if !locks || prompt.override_lock {
+        return Vec::new();
+    }
+    running
+        .iter()
+        .filter(|r| {
+            r.id != prompt.id
+                && r.project_id == prompt.project_id
+                && crate::affected_files::would_conflict(&prompt.affected_files, &r.affected_files)
+        })
+        .map(|r| r.id)
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OverrideLockRequest {
+    pub override_lock: bool,
+}
+
+/// `PUT /api/background-prompts/{id}/override-lock`
+pub async fn set_override_lock(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<OverrideLockRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let result = sqlx::query(
+        "UPDATE ch_background_prompts SET override_lock = $2 WHERE id = $1 AND status = 'queued'",
+    )
+    .bind(id)
+    .bind(req.override_lock)
+    .execute(&state.db)
+    .await
+    .map_err(db_error("queue"))?;
+    if result.rows_affected() == 0 {
+        return Err(not_queued());
+    }
+    // An unblocked prompt may be able to start right away.
+    wake();
+    Ok(Json(json!({
+        "id": id,
+        "override_lock": req.override_lock,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn prompt(id: i64, files: &[&str], override_lock: bool) -> BackgroundPrompt {
+        BackgroundPrompt {
+            id,
+            prompt: String::new(),
+            model: None,
+            priority: "normal".to_string(),
+            status: "queued".to_string(),
+            result: None,
+            error: None,
+            created_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            attempts: 0,
+            interrupted: 0,
+            timeout_ms: None,
+            deadline: None,
+            position: id,
+            retries: 0,
+            affected_files: files.iter().map(|f| f.to_string()).collect(),
+            override_lock,
+            route_decision: None,
+            correlation_id: String::new(),
+            project_id: None,
+        }
+    }
+
+    #[test]
+    fn file_locks_block_overlapping_prompts_unless_overridden() {
+        let running = vec![
+            prompt(1, &["src/a.rs"], false),
+            prompt(2, &["src/b.rs"], false),
+        ];
+        let queued = prompt(3, &["src/a.rs", "src/c.rs"], false);
+        assert_eq!(lock_holders(&queued, &running, true), vec![1]);
+        assert!(lock_holders(&queued, &running, false).is_empty());
+        let overridden = prompt(4, &["src/a.rs"], true);
+        assert!(lock_holders(&overridden, &running, true).is_empty());
+        // Affected files are project-relative: other projects never conflict.
+        let mut elsewhere = prompt(5, &["src/a.rs"], false);
+        elsewhere.project_id = Some(uuid::Uuid::new_v4());
+        assert!(lock_holders(&elsewhere, &running, true).is_empty());
+    }
+
+    #[test]
+    fn claims_skip_prompts_blocked_by_held_locks() {
+        let project = Some(uuid::Uuid::new_v4());
+        let held = vec![(project, vec!["src/a.rs".to_string()])];
+        let files = vec!["src/a.rs".to_string()];
+        assert!(is_blocked(&held, project, &files, false));
+        assert!(!is_blocked(&held, project, &files, true));
+        assert!(!is_blocked(&held, None, &files, false));
+        assert!(!is_blocked(
+            &held,
+            project,
+            &["src/b.rs".to_string()],
+            false
+        ));
+        assert!(!is_blocked(&[], project, &files, false));
+        assert_eq!(blocking_files(&held, project, &files), files);
+        assert!(blocking_files(&held, None, &files).is_empty());
+    }
+}
@@ -0,0 +1,311 @@
+//! Background prompt queue — queued prompts (documentation backlog, bulk
+//! summaries, ...) run by a worker inside the backend, so background work
+//! never competes with the user's active session. When the worker makes a
+//! pass is decided by `idle_scavenger.rs`; everything else lives here:
+//!
+//! - **claim**: picks the next eligible prompt into a free worker slot and
+//!   provider lane.
+//! - **execute**: runs a claimed prompt on its provider and stores the outcome.
+//! - **retry**: re-queues failed runs along the fallback chain and recovers
+//!   prompts interrupted by a crash or shutdown.
+//! - **locks**: advisory locks on the affected files of running prompts.
+//! - **positions**: execution order and moving queued prompts.
+//! - **events**: worker wake-ups and the SSE event stream.
+//! - **handlers**: enqueue, list, queue snapshot, attempts and cancel.
+//!
+//! Prompts are queued in `ch_background_prompts` with a priority:
+//! - `low` — runs only while the machine is idle: system CPU below
+//!   `CH_IDLE_CPU_THRESHOLD` percent (default 30) and no interactive prompt
+//!   for `CH_IDLE_MINUTES` (default 10).
+//! - `normal` — runs on the next worker pass regardless of activity.
+//!
+//! The worker keeps up to `CH_BACKGROUND_MAX_CONCURRENT` (default 2) prompts
+//! running (non-streaming, with the server-side system prompt, on the
+//! provider that serves the prompt's model — see `complete_prompt`).
+//!
+//! Within that limit each provider has its own lane: `CH_BACKGROUND_LANES`
+//! (e.g. `anthropic=1,google=1,openrouter=2`) caps how many prompts per provider
+//! run at once, so a slow provider cannot hold every slot. When the next
+//! prompt's lane is full the worker skips ahead to one whose lane has room.
+//! Providers not listed are limited only by the global cap.
+//!
+//! Prompts whose model is `ollama/<name>` go straight to the local Ollama
+//! server (plain prompt, no server-side system prompt) in the `ollama` lane.
+//! Ollama handles parallel requests well, so short ones (up to
+//! `OLLAMA_BATCH_MAX_CHARS`) are additionally dispatched in a batch of up to
+//! `CH_BACKGROUND_OLLAMA_BATCH` (default 4, `0` disables) that does not count
+//! against `CH_BACKGROUND_MAX_CONCURRENT`. A session never has more than one
+//! of its prompts running at a time.
+//!
+//! Prompts of a session paused by its token quota (see `tab_quota.rs`) stay
+//! queued until the quota is raised.
+//!
+//! A prompt whose provider has reached its budget cap (see `budget.rs`) is
+//! claimed with a free local model instead, when one is configured or
+//! installed.
+//!
+//! A failed run is re-queued up to `CH_BACKGROUND_MAX_RETRIES` times (default
+//! 1), unless a retry cannot help: prompts blocked by the safety guard or a
+//! budget cap and requests the provider rejects as invalid (4xx) fail at
+//! once. With `CH_BACKGROUND_FALLBACK_MODELS` set (comma-separated, e.g.
+//! `claude-sonnet-4-6,gemini-2.5-flash,ollama/llama3.1:8b`) each retry moves
+//! on to the next model in that chain, on whichever provider serves it.
+//! Every run is recorded in `ch_background_prompt_attempts`.
+//!
+//! The queue lives in Postgres, so it survives crashes. Prompts still marked
+//! `running` at startup (or when shutdown gives up on them) are requeued for
+//! retry, or failed once they have been claimed `CH_BACKGROUND_MAX_ATTEMPTS`
+//! times (default 3), so a prompt that crashes the backend cannot loop forever.
+//!
+//! Each run is bounded by the prompt's `timeout_ms` (default
+//! `CH_BACKGROUND_TIMEOUT_SECS`, 5 minutes unless set) and,
+//! if set, its `deadline`. Exceeding either aborts the provider call, fails
+//! the prompt with a `Timeout: ...` error and frees the worker slot; prompts
+//! whose deadline passes while still queued are failed without running.
+//!
+//! With `CH_BACKGROUND_FILE_LOCKS=1` a running prompt holds advisory locks on
+//! its `affected_files`: a queued prompt overlapping any of them (same file,
+//! or a file/subtree covered by a directory or glob entry) stays blocked
+//! (shown as `blocked_by` in the queue snapshot) until those prompts finish,
+//! unless it has `override_lock` set. Affected files are relative to the
+//! project active at enqueue time (see `projects.rs`), so only prompts of
+//! the same project conflict.
+//!
+//! Every `CH_*` setting above can also come from the `[queue]` section of the
+//! config file (see `hydra_config.rs`); the env var wins when both are set.
+//! Changes to the file apply on the worker's next pass.
+//!
+//! Prompts of a session in Witcher mode that have no explicit model are
+//! routed when claimed (see `witcher_router.rs`): the chosen model and the
+//! reasoning are stored on the prompt (`route_decision`).
+//!
+//! Each prompt keeps the correlation id of the request that enqueued it
+//! (`correlation_id`, see `correlation.rs`) and runs under it, so its events
+//! and log entries can be matched to that request.
+//!
+//! Each prompt's stages — enqueued, conflict (the first pass it is blocked by
+//! a file lock), conflicts checked, dequeued, provider called, then
+//! completed / failed / retrying — are traced under `bg-<id>` (see
+//! `prompt_trace.rs`), so `GET /api/prompts/bg-<id>/trace` shows where its
+//! time went together with the hook runs it triggered.
+//!
+//! Queued prompts run `normal` before `low`, then by `position` (enqueue
+//! order unless reordered). Moving a prompt across the `normal`/`low`
+//! boundary takes on the priority of the section it is dropped into.
+//!
+//! - `POST   /api/background-prompts`       — enqueue `{ prompt, model?, priority?, timeout_ms?, deadline?, idempotency_key?, affected_files?, correlation_id? }`
+//!   (`affected_files` is inferred from the prompt when omitted, see `affected_files.rs`)
+//!   (a key seen in the last `IDEMPOTENCY_WINDOW_SECS` returns that prompt with `200` instead of `201`)
+//! - `GET    /api/background-prompts`       — queue + idle status (`?status=`)
+//! - `GET    /api/background-prompts/events` — SSE: `prompt-started`, `prompt-completed`, `prompt-eta`
+//! - `GET    /api/background-prompts/queue` — running prompts + queued ones in execution order,
+//!   each with the ids of other queued/running prompts sharing an affected file (`conflicts_with`)
+//!   and the overlapping entry per prompt (`conflicts: [{ id, overlap }]`)
+//! - `PUT    /api/background-prompts/{id}/position` — move a queued prompt `{ position }`
+//! - `PUT    /api/background-prompts/{id}/override-lock` — `{ override_lock }`: ignore file locks
+//! - `GET    /api/background-prompts/{id}/attempts` — run history (model, outcome, duration)
+//! - `DELETE /api/background-prompts/{id}`  — cancel a queued prompt
+//!
+//! Outside YOLO mode, prompts asking for a system operation (see
+//! `approvals.rs`) are stored as `awaiting_approval` and join the queue
+//! only once approved via `POST /api/approvals/{id}`.
+
+mod claim;
+mod events;
+mod execute;
+mod handlers;
+mod locks;
+mod positions;
+mod retry;
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use axum::Json;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::hydra_config::HydraConfig;
+
+pub(crate) use claim::{Dispatcher, lane_of};
+pub(crate) use events::{emit, woken};
+pub use events::{events, wake};
+pub(crate) use execute::complete_ollama;
+pub use handlers::{
+    EnqueueRequest, ListQuery, PromptAttempt, cancel, enqueue, list, list_attempts, queue_snapshot,
+};
+pub use locks::{OverrideLockRequest, set_override_lock};
+pub(crate) use positions::QUEUE_ORDER;
+pub use positions::{MoveRequest, move_prompt};
+pub use retry::recover_interrupted;
+
+const EXECUTION_TIMEOUT_SECS: u64 = 300;
+/// Model prefix that routes a prompt to the local Ollama server.
+pub(crate) const OLLAMA_PREFIX: &str = "ollama/";
+
+#[derive(Debug, Clone)]
+pub struct QueueConfig {
+    pub cpu_threshold: f32,
+    pub idle_after: Duration,
+    pub max_concurrent: usize,
+    pub max_attempts: u32,
+    /// Per-provider concurrency limits (`model_registry::provider_for_model` names).
+    pub lanes: HashMap<String, usize>,
+    /// Extra slots for short Ollama prompts.
+    pub ollama_batch: usize,
+    /// Re-enqueues after a failed run.
+    pub max_retries: u32,
+    /// Models tried in turn when retrying; empty = retry on the same model.
+    pub fallback_models: Vec<String>,
+    /// Hold prompts whose affected files are in use by a running prompt.
+    pub file_locks: bool,
+    /// Run limit for prompts without their own `timeout_ms`.
+    pub execution_timeout: Duration,
+}
+
+static CONFIG: RwLock<Option<Arc<QueueConfig>>> = RwLock::new(None);
+
+/// Current queue settings (env, else the config file, else defaults).
+pub fn config() -> Arc<QueueConfig> {
+    if let Some(cfg) = CONFIG.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+        return cfg.clone();
+    }
+    reload_config()
+}
+
+/// Rebuild the settings, e.g. after the config file changed. The worker picks
+/// up new slot counts on its next pass.
+pub(crate) fn reload_config() -> Arc<QueueConfig> {
+    let cfg = Arc::new(build_config(&crate::hydra_config::current()));
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(cfg.clone());
+    cfg
+}
+
+fn build_config(file: &HydraConfig) -> QueueConfig {
+    let env_num = |name: &str| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+    };
+    let q = &file.queue;
+    QueueConfig {
+        cpu_threshold: env_num("CH_IDLE_CPU_THRESHOLD")
+            .or(q.cpu_threshold.map(f64::from))
+            .unwrap_or(30.0)
+            .clamp(1.0, 100.0) as f32,
+        idle_after: Duration::from_secs(
+            (env_num("CH_IDLE_MINUTES")
+                .or(q.idle_minutes)
+                .unwrap_or(10.0)
+                .max(0.0)
+                * 60.0) as u64,
+        ),
+        max_concurrent: env_num("CH_BACKGROUND_MAX_CONCURRENT")
+            .or(q.max_concurrent.map(|n| n as f64))
+            .unwrap_or(2.0)
+            .clamp(1.0, 16.0) as usize,
+        max_attempts: env_num("CH_BACKGROUND_MAX_ATTEMPTS")
+            .or(q.max_attempts.map(f64::from))
+            .unwrap_or(3.0)
+            .clamp(1.0, 100.0) as u32,
+        lanes: match std::env::var("CH_BACKGROUND_LANES") {
+            Ok(v) => parse_lanes(&v),
+            Err(_) => q
+                .lanes
+                .iter()
+                .filter(|(_, limit)| **limit > 0)
+                .map(|(name, limit)| (name.trim().to_lowercase(), *limit))
+                .collect(),
+        },
+        ollama_batch: env_num("CH_BACKGROUND_OLLAMA_BATCH")
+            .or(q.ollama_batch.map(|n| n as f64))
+            .unwrap_or(4.0)
+            .clamp(0.0, 32.0) as usize,
+        max_retries: env_num("CH_BACKGROUND_MAX_RETRIES")
+            .or(q.max_retries.map(f64::from))
+            .unwrap_or(1.0)
+            .clamp(0.0, 10.0) as u32,
+        fallback_models: match std::env::var("CH_BACKGROUND_FALLBACK_MODELS") {
+            Ok(v) => v
+                .split(',')
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .collect(),
+            Err(_) => file.routing.fallback_models.clone().unwrap_or_default(),
+        },
+        file_locks: match env_num("CH_BACKGROUND_FILE_LOCKS") {
+            Some(v) => v > 0.0,
+            None => q.file_locks.unwrap_or(false),
+        },
+        execution_timeout: Duration::from_secs(
+            env_num("CH_BACKGROUND_TIMEOUT_SECS")
+                .or(file.timeouts.background_prompt_secs.map(|s| s as f64))
+                .unwrap_or(EXECUTION_TIMEOUT_SECS as f64)
+                .clamp(1.0, 3600.0) as u64,
+        ),
+    }
+}
+
+/// Parse `provider=limit` pairs; malformed entries are ignored.
+fn parse_lanes(raw: &str) -> HashMap<String, usize> {
+    raw.split(',')
+        .filter_map(|pair| {
+            let (name, limit) = pair.split_once('=')?;
+            let name = name.trim().to_lowercase();
+            let limit: usize = limit.trim().parse().ok()?;
+            (!name.is_empty() && limit > 0).then_some((name, limit))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct BackgroundPrompt {
+    pub id: i64,
+    pub prompt: String,
+    pub model: Option<String>,
+    pub priority: String,
+    pub status: String,
+    pub result: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Times the prompt was claimed for execution.
+    pub attempts: i32,
+    /// Times a run was cut short by a crash or shutdown.
+    pub interrupted: i32,
+    /// Per-run limit; `EXECUTION_TIMEOUT_SECS` when unset.
+    pub timeout_ms: Option<i32>,
+    /// Absolute cut-off, whether queued or running.
+    pub deadline: Option<DateTime<Utc>>,
+    /// Execution order within a priority (lower runs first).
+    pub position: i64,
+    /// Times the prompt was re-queued after a failed run.
+    pub retries: i32,
+    /// Workspace-relative files the prompt is expected to touch.
+    pub affected_files: Vec<String>,
+    /// Start even when another running prompt holds one of the files.
+    pub override_lock: bool,
+    /// Witcher router decision, for prompts of sessions in Witcher mode.
+    pub route_decision: Option<Value>,
+    /// Correlation id of the enqueueing request.
+    pub correlation_id: String,
+    /// Project active when the prompt was enqueued; affected files are
+    /// relative to it, so only prompts of the same project conflict.
+    pub project_id: Option<uuid::Uuid>,
+}
+
+/// Execution id the prompt's trace and hook runs are stored under.
+pub(crate) fn trace_id(id: i64) -> String {
+    format!("bg-{}", id)
+}
+
+fn not_queued() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "No queued prompt with this id" })),
+    )
+}
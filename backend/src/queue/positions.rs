@@ -0,0 +1,103 @@
+//! Execution order: `normal` before `low`, then by `position` (enqueue order
+//! unless reordered).
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{not_queued, wake};
+use crate::api_error::db_error;
+use crate::state::AppState;
+
+pub(crate) const QUEUE_ORDER: &str = "ORDER BY (priority = 'normal') DESC, position ASC, id ASC";
+
+/// Move `id` to index `new_position` of the execution order (`(id, is_normal)`
+/// pairs, normal first). Returns the new id order and whether the prompt is
+/// `normal` afterwards: dropped below a `low` prompt it becomes `low`, above
+/// a `normal` one it becomes `normal`, otherwise it keeps its priority.
+fn reorder(order: &[(i64, bool)], id: i64, new_position: usize) -> Option<(Vec<i64>, bool)> {
+    let from = order.iter().position(|(i, _)| *i == id)?;
+    let mut rest: Vec<(i64, bool)> = order.to_vec();
+    let (_, mut normal) = rest.remove(from);
+    let at = new_position.min(rest.len());
+    let before = at.checked_sub(1).map(|i| rest[i].1);
+    let after = rest.get(at).map(|e| e.1);
+    if before == Some(false) {
+        normal = false;
+    } else if after == Some(true) {
+        normal = true;
+    }
+    rest.insert(at, (id, normal));
+    Some((rest.into_iter().map(|(i, _)| i).collect(), normal))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MoveRequest {
+    /// Zero-based index in the `queued` list of the queue snapshot.
+    pub position: usize,
+}
+
+/// `PUT /api/background-prompts/{id}/position`
+pub async fn move_prompt(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(req): Json<MoveRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut tx = state.db.begin().await.map_err(db_error("queue"))?;
+    let order: Vec<(i64, bool)> = sqlx::query_as(&format!(
+        "SELECT id, priority = 'normal' FROM ch_background_prompts \
+         WHERE status = 'queued' {} FOR UPDATE",
+        QUEUE_ORDER
+    ))
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(db_error("queue"))?;
+    let Some((ids, normal)) = reorder(&order, id, req.position) else {
+        return Err(not_queued());
+    };
+    sqlx::query(
+        "UPDATE ch_background_prompts SET position = ord.n - 1 \
+         FROM UNNEST($1::BIGINT[]) WITH ORDINALITY AS ord(id, n) \
+         WHERE ch_background_prompts.id = ord.id",
+    )
+    .bind(&ids)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("queue"))?;
+    let priority = if normal { "normal" } else { "low" };
+    sqlx::query("UPDATE ch_background_prompts SET priority = $2 WHERE id = $1")
+        .bind(id)
+        .bind(priority)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("queue"))?;
+    tx.commit().await.map_err(db_error("queue"))?;
+    // A prompt promoted to `normal` may be runnable right away.
+    wake();
+    Ok(Json(json!({
+        "id": id,
+        "position": ids.iter().position(|i| *i == id),
+        "priority": priority,
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reorder_moves_within_and_across_priorities() {
+        let order = [(1, true), (2, true), (3, false), (4, false)];
+        assert_eq!(reorder(&order, 2, 0), Some((vec![2, 1, 3, 4], true)));
+        // Dropped among low prompts: demoted.
+        assert_eq!(reorder(&order, 1, 2), Some((vec![2, 3, 1, 4], false)));
+        // Dropped above a normal prompt: promoted.
+        assert_eq!(reorder(&order, 4, 1), Some((vec![1, 4, 2, 3], true)));
+        // On the boundary it keeps its priority.
+        assert_eq!(reorder(&order, 3, 2), Some((vec![1, 2, 3, 4], false)));
+        assert_eq!(reorder(&order, 1, 99), Some((vec![2, 3, 4, 1], false)));
+        assert_eq!(reorder(&order, 9, 0), None);
+    }
+}
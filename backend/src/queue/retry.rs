@@ -0,0 +1,177 @@
+//! Retries: failed runs go back to the queue on the next model of the
+//! fallback chain unless a retry cannot help, and prompts left `running` by
+//! a crash or shutdown are requeued until they run out of attempts.
+
+use serde_json::json;
+
+use super::{BackgroundPrompt, config, emit, trace_id};
+use crate::state::AppState;
+
+/// Model for the next try after a failure on `current`: the entry after it
+/// in the fallback chain (wrapping around), the chain's first entry when
+/// `current` is not in it, or `current` itself without a chain.
+fn retry_model(current: Option<&str>, chain: &[String]) -> Option<String> {
+    if chain.is_empty() {
+        return current.map(str::to_string);
+    }
+    let next = match chain.iter().position(|m| Some(m.as_str()) == current) {
+        Some(i) => &chain[(i + 1) % chain.len()],
+        None => &chain[0],
+    };
+    Some(next.clone())
+}
+
+/// Failures a retry cannot fix: prompts refused by the safety guard or a
+/// budget cap, and requests the provider rejected as invalid (HTTP 4xx other
+/// than 408 / 429).
+fn is_permanent(error: &str) -> bool {
+    if error.starts_with(crate::safety::BLOCKED_CODE) || error.starts_with("BUDGET_EXCEEDED") {
+        return true;
+    }
+    error
+        .split_once("HTTP ")
+        .and_then(|(_, rest)| rest.get(..3))
+        .and_then(|code| code.parse::<u16>().ok())
+        .is_some_and(|status| (400..500).contains(&status) && status != 408 && status != 429)
+}
+
+/// Put a run that failed with `err` back in the queue, on the next model of
+/// the fallback chain. Returns `false` (leaving the prompt `running`) once
+/// its retries are used up or when a retry cannot help.
+pub(super) async fn requeue(
+    state: &AppState,
+    job: &BackgroundPrompt,
+    err: &str,
+    duration_ms: u64,
+) -> Result<bool, String> {
+    let cfg = config();
+    if job.retries >= cfg.max_retries as i32 || is_permanent(err) {
+        return Ok(false);
+    }
+    let model = retry_model(job.model.as_deref(), &cfg.fallback_models);
+    sqlx::query(
+        "UPDATE ch_background_prompts \
+         SET status = 'queued', model = $2, error = $3, retries = retries + 1, started_at = NULL \
+         WHERE id = $1 AND status = 'running'",
+    )
+    .bind(job.id)
+    .bind(&model)
+    .bind(err)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to requeue prompt: {}", e))?;
+    crate::prompt_trace::record_once(
+        &state.db,
+        &trace_id(job.id),
+        "retrying",
+        json!({ "retry_model": model, "retries": job.retries + 1 }),
+    )
+    .await;
+    emit(
+        "prompt-completed",
+        json!({
+            "id": job.id,
+            "status": "retrying",
+            "error": err,
+            "duration_ms": duration_ms,
+            "retry_model": model,
+            "correlation_id": job.correlation_id,
+        }),
+    );
+    Ok(true)
+}
+
+/// What happens to a prompt found `running` with no worker behind it:
+/// retried while it has attempts left, failed otherwise.
+fn recovered_status(attempts: i32, max_attempts: u32) -> &'static str {
+    if attempts >= max_attempts as i32 {
+        "failed"
+    } else {
+        "queued"
+    }
+}
+
+/// Recover prompts left `running` by a crashed or stopped process.
+/// Returns `(requeued, failed)`.
+pub async fn recover_interrupted(db: &sqlx::PgPool) -> Result<(u64, u64), sqlx::Error> {
+    let max_attempts = config().max_attempts;
+    let mut tx = db.begin().await?;
+    let rows: Vec<(i64, i32)> = sqlx::query_as(
+        "SELECT id, attempts FROM ch_background_prompts WHERE status = 'running' FOR UPDATE",
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let (mut requeued, mut failed) = (0, 0);
+    for (id, attempts) in rows {
+        let status = recovered_status(attempts, max_attempts);
+        if status == "failed" {
+            failed += 1;
+        } else {
+            requeued += 1;
+        }
+        sqlx::query(
+            "UPDATE ch_background_prompts SET status = $2, interrupted = interrupted + 1, \
+                 started_at = CASE WHEN $2 = 'queued' THEN NULL ELSE started_at END, \
+                 finished_at = CASE WHEN $2 = 'failed' THEN NOW() END, \
+                 error = CASE WHEN $2 = 'failed' \
+                     THEN 'Interrupted after ' || attempts || ' attempt(s)' END \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok((requeued, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::execute::blocked;
+
+    #[test]
+    fn interrupted_prompts_retry_until_attempts_run_out() {
+        assert_eq!(recovered_status(1, 3), "queued");
+        assert_eq!(recovered_status(2, 3), "queued");
+        assert_eq!(recovered_status(3, 3), "failed");
+        assert_eq!(recovered_status(1, 1), "failed");
+    }
+
+    #[test]
+    fn retries_walk_the_fallback_chain() {
+        let chain = vec!["claude-sonnet-4-6".to_string(), "ollama/llama3".to_string()];
+        assert_eq!(
+            retry_model(Some("claude-sonnet-4-6"), &chain).as_deref(),
+            Some("ollama/llama3")
+        );
+        assert_eq!(
+            retry_model(Some("ollama/llama3"), &chain).as_deref(),
+            Some("claude-sonnet-4-6")
+        );
+        assert_eq!(
+            retry_model(None, &chain).as_deref(),
+            Some("claude-sonnet-4-6")
+        );
+        assert_eq!(retry_model(Some("gpt-4o"), &[]).as_deref(), Some("gpt-4o"));
+        assert_eq!(retry_model(None, &[]), None);
+    }
+
+    #[test]
+    fn permanent_failures_are_not_retried() {
+        assert!(is_permanent(&blocked(
+            "Prompt blocked: secrets".to_string()
+        )));
+        assert!(is_permanent(
+            "BUDGET_EXCEEDED: The anthropic daily budget is used up"
+        ));
+        assert!(is_permanent("Provider returned HTTP 400"));
+        assert!(is_permanent("Provider returned HTTP 422: invalid model"));
+        assert!(!is_permanent("Provider returned HTTP 429"));
+        assert!(!is_permanent("Provider returned HTTP 503"));
+        assert!(!is_permanent("Provider returned HTTP 408"));
+        assert!(!is_permanent("Timeout: no response within 1000 ms"));
+        assert!(!is_permanent("AI provider request failed"));
+    }
+}
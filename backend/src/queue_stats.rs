@@ -0,0 +1,534 @@
+//! Background queue statistics — today's counters and a bucketed history for
+//! charts, computed from the persisted queue (`ch_background_prompts`,
+//! `ch_background_prompt_attempts`) so they survive restarts.
+//!
+//! "Today" and the bucket boundaries follow the server's local time zone:
+//! `completed_today` / `failed_today` roll over at local midnight.
+//!
+//! ETAs replay the queue in execution order against the worker's slots and
+//! provider lanes, using each lane's average run time over the last 7 days
+//! (`DEFAULT_RUN_MS` without history). The worker re-estimates after every
+//! wake and emits `prompt-eta` on `/api/background-prompts/events` when a
+//! prompt's position or ETA changes noticeably.
+//!
+//! - `GET /api/background-prompts/stats`   — live counts + today's totals
+//! - `GET /api/background-prompts/history` — `?range=24h` (hourly, default),
+//!   `7d` or `30d` (daily): enqueued / completed / failed / avg duration per bucket
+//! - `GET /api/background-prompts/{id}/eta` — `{ position, wait_ms, eta_ms, waiting_for_idle }`
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Local, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::db_error;
+use crate::queue::{QUEUE_ORDER, config, emit, lane_of};
+use crate::state::AppState;
+
+/// Assumed run time for a lane with no recent history.
+const DEFAULT_RUN_MS: u64 = 30_000;
+/// ETA drift below which no new `prompt-eta` event is sent.
+const ETA_EMIT_THRESHOLD_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HistoryRange {
+    Day,
+    Week,
+    Month,
+}
+
+impl HistoryRange {
+    fn parse(raw: Option<&str>) -> Option<Self> {
+        match raw.unwrap_or("24h") {
+            "24h" => Some(Self::Day),
+            "7d" => Some(Self::Week),
+            "30d" => Some(Self::Month),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Day => "24h",
+            Self::Week => "7d",
+            Self::Month => "30d",
+        }
+    }
+}
+
+/// Start of the local day containing `now`.
+fn local_midnight<Tz: TimeZone>(now: &DateTime<Tz>) -> DateTime<Utc> {
+    let midnight = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default();
+    now.timezone()
+        .from_local_datetime(&midnight)
+        .earliest()
+        // Midnight skipped by a DST change: the day starts an hour later.
+        .or_else(|| {
+            now.timezone()
+                .from_local_datetime(&(midnight + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| now.with_timezone(&Utc))
+}
+
+/// `[start, end)` bounds of the buckets in `range`, oldest first. The last
+/// bucket is the current (partial) hour or day.
+fn bucket_bounds<Tz: TimeZone>(
+    range: HistoryRange,
+    now: &DateTime<Tz>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    match range {
+        HistoryRange::Day => {
+            let hour = now
+                .with_minute(0)
+                .and_then(|t| t.with_second(0))
+                .and_then(|t| t.with_nanosecond(0))
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|| now.with_timezone(&Utc));
+            (0..24)
+                .rev()
+                .map(|i| {
+                    let start = hour - Duration::hours(i);
+                    (start, start + Duration::hours(1))
+                })
+                .collect()
+        }
+        HistoryRange::Week | HistoryRange::Month => {
+            let days = if range == HistoryRange::Week { 7 } else { 30 };
+            // Walk back day by day so DST days get their real length.
+            let mut starts = vec![local_midnight(now)];
+            for _ in 1..days {
+                let prev = starts[starts.len() - 1] - Duration::hours(12);
+                starts.push(local_midnight(&prev.with_timezone(&now.timezone())));
+            }
+            starts.reverse();
+            let mut bounds: Vec<_> = starts.windows(2).map(|w| (w[0], w[1])).collect();
+            let today = starts[starts.len() - 1];
+            bounds.push((today, today + Duration::days(1)));
+            bounds
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct HistoryBucket {
+    pub start: DateTime<Utc>,
+    pub enqueued: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub avg_duration_ms: Option<f64>,
+}
+
+/// `GET /api/background-prompts/stats`
+pub async fn queue_stats(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let since = local_midnight(&Local::now());
+    let (queued, running, completed_today, failed_today, cancelled_today): (
+        i64,
+        i64,
+        i64,
+        i64,
+        i64,
+    ) = sqlx::query_as(
+        "SELECT \
+             COUNT(*) FILTER (WHERE status = 'queued'), \
+             COUNT(*) FILTER (WHERE status = 'running'), \
+             COUNT(*) FILTER (WHERE status = 'done' AND finished_at >= $1), \
+             COUNT(*) FILTER (WHERE status = 'failed' AND finished_at >= $1), \
+             COUNT(*) FILTER (WHERE status = 'cancelled' AND finished_at >= $1) \
+         FROM ch_background_prompts",
+    )
+    .bind(since)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error("queue_stats"))?;
+    let (runs_today, avg_duration_ms): (i64, Option<f64>) = sqlx::query_as(
+        "SELECT COUNT(*), AVG(duration_ms)::FLOAT8 \
+         FROM ch_background_prompt_attempts WHERE finished_at >= $1",
+    )
+    .bind(since)
+    .fetch_one(&state.db)
+    .await
+    .map_err(db_error("queue_stats"))?;
+    Ok(Json(json!({
+        "queued": queued,
+        "running": running,
+        "completed_today": completed_today,
+        "failed_today": failed_today,
+        "cancelled_today": cancelled_today,
+        "runs_today": runs_today,
+        "avg_duration_ms_today": avg_duration_ms,
+        "day_started_at": since,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    pub range: Option<String>,
+}
+
+/// `GET /api/background-prompts/history`
+pub async fn queue_history(
+    State(state): State<AppState>,
+    Query(q): Query<HistoryQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let range = HistoryRange::parse(q.range.as_deref()).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "range must be '24h', '7d' or '30d'" })),
+        )
+    })?;
+    let (starts, ends): (Vec<_>, Vec<_>) = bucket_bounds(range, &Local::now()).into_iter().unzip();
+    let buckets = sqlx::query_as::<_, HistoryBucket>(
+        "SELECT b.start, \
+             (SELECT COUNT(*) FROM ch_background_prompts \
+              WHERE created_at >= b.start AND created_at < b.stop) AS enqueued, \
+             (SELECT COUNT(*) FROM ch_background_prompts WHERE status = 'done' \
+              AND finished_at >= b.start AND finished_at < b.stop) AS completed, \
+             (SELECT COUNT(*) FROM ch_background_prompts WHERE status = 'failed' \
+              AND finished_at >= b.start AND finished_at < b.stop) AS failed, \
+             (SELECT AVG(duration_ms)::FLOAT8 FROM ch_background_prompt_attempts \
+              WHERE finished_at >= b.start AND finished_at < b.stop) AS avg_duration_ms \
+         FROM UNNEST($1::TIMESTAMPTZ[], $2::TIMESTAMPTZ[]) AS b(start, stop) \
+         ORDER BY b.start",
+    )
+    .bind(&starts)
+    .bind(&ends)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("queue_stats"))?;
+    Ok(Json(json!({
+        "range": range.label(),
+        "buckets": buckets,
+    })))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  ETA
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PromptEta {
+    pub id: i64,
+    /// 0 = running, 1 = next to start, ...
+    pub position: usize,
+    /// Until the prompt starts.
+    pub wait_ms: u64,
+    /// Until the prompt is expected to finish.
+    pub eta_ms: u64,
+    /// `low` prompt that only starts once the machine is idle.
+    pub waiting_for_idle: bool,
+}
+
+/// Earliest-free slot in `slots`, as `(index, free_at)`.
+fn earliest(slots: &[u64]) -> (usize, u64) {
+    slots
+        .iter()
+        .copied()
+        .enumerate()
+        .min_by_key(|(_, t)| *t)
+        .unwrap_or((0, 0))
+}
+
+/// Replay the queue: `running` is `(id, lane, elapsed_ms)`, `queued` is
+/// `(id, lane, runnable)` in execution order. Prompts that are not runnable
+/// (low priority while busy) keep their place but get no slot.
+fn estimate_etas(
+    running: &[(i64, &'static str, u64)],
+    queued: &[(i64, &'static str, bool)],
+    avg_ms: &HashMap<&'static str, u64>,
+    max_concurrent: usize,
+    lane_limits: &HashMap<String, usize>,
+) -> Vec<PromptEta> {
+    let run_ms = |lane: &str| avg_ms.get(lane).copied().unwrap_or(DEFAULT_RUN_MS);
+    let mut slots = vec![0u64; max_concurrent.max(1)];
+    // Free-at times of each limited lane's places.
+    let mut lanes: HashMap<&str, Vec<u64>> = lane_limits
+        .iter()
+        .map(|(lane, limit)| (lane.as_str(), vec![0; *limit]))
+        .collect();
+
+    let mut etas = Vec::with_capacity(running.len() + queued.len());
+    for &(id, lane, elapsed) in running {
+        let left = run_ms(lane).saturating_sub(elapsed);
+        let (slot, _) = earliest(&slots);
+        slots[slot] = left;
+        if let Some(ls) = lanes.get_mut(lane) {
+            let (i, _) = earliest(ls);
+            ls[i] = left;
+        }
+        etas.push(PromptEta {
+            id,
+            position: 0,
+            wait_ms: 0,
+            eta_ms: left,
+            waiting_for_idle: false,
+        });
+    }
+    for (index, &(id, lane, runnable)) in queued.iter().enumerate() {
+        let position = index + 1;
+        if !runnable {
+            etas.push(PromptEta {
+                id,
+                position,
+                wait_ms: 0,
+                eta_ms: 0,
+                waiting_for_idle: true,
+            });
+            continue;
+        }
+        let (slot, slot_free) = earliest(&slots);
+        let lane_free = lanes.get(lane).map(|ls| earliest(ls));
+        let start = slot_free.max(lane_free.map_or(0, |(_, t)| t));
+        let end = start + run_ms(lane);
+        slots[slot] = end;
+        if let (Some(ls), Some((i, _))) = (lanes.get_mut(lane), lane_free) {
+            ls[i] = end;
+        }
+        etas.push(PromptEta {
+            id,
+            position,
+            wait_ms: start,
+            eta_ms: end,
+            waiting_for_idle: false,
+        });
+    }
+    etas
+}
+
+/// ETAs for every running and queued prompt.
+async fn current_etas(state: &AppState, idle: bool) -> Result<Vec<PromptEta>, sqlx::Error> {
+    let history: Vec<(Option<String>, i64, f64)> = sqlx::query_as(
+        "SELECT model, COUNT(*), AVG(duration_ms)::FLOAT8 FROM ch_background_prompt_attempts \
+         WHERE finished_at > NOW() - INTERVAL '7 days' GROUP BY model",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let mut totals: HashMap<&'static str, (f64, i64)> = HashMap::new();
+    for (model, count, avg) in history {
+        let t = totals.entry(lane_of(model.as_deref())).or_default();
+        t.0 += avg * count as f64;
+        t.1 += count;
+    }
+    let avg_ms: HashMap<&'static str, u64> = totals
+        .into_iter()
+        .map(|(lane, (sum, n))| (lane, (sum / n.max(1) as f64) as u64))
+        .collect();
+
+    let running: Vec<(i64, Option<String>, Option<DateTime<Utc>>)> = sqlx::query_as(
+        "SELECT id, model, started_at FROM ch_background_prompts \
+         WHERE status = 'running' ORDER BY started_at ASC",
+    )
+    .fetch_all(&state.db)
+    .await?;
+    let queued: Vec<(i64, Option<String>, String)> = sqlx::query_as(&format!(
+        "SELECT id, model, priority FROM ch_background_prompts WHERE status = 'queued' {}",
+        QUEUE_ORDER
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    let now = Utc::now();
+    let running: Vec<_> = running
+        .iter()
+        .map(|(id, model, started)| {
+            let elapsed = started
+                .and_then(|t| (now - t).to_std().ok())
+                .map_or(0, |d| d.as_millis() as u64);
+            (*id, lane_of(model.as_deref()), elapsed)
+        })
+        .collect();
+    let queued: Vec<_> = queued
+        .iter()
+        .map(|(id, model, priority)| (*id, lane_of(model.as_deref()), idle || priority == "normal"))
+        .collect();
+    let cfg = config();
+    Ok(estimate_etas(
+        &running,
+        &queued,
+        &avg_ms,
+        cfg.max_concurrent,
+        &cfg.lanes,
+    ))
+}
+
+/// Last ETA sent per prompt, to only emit meaningful changes.
+static LAST_EMITTED: LazyLock<Mutex<HashMap<i64, PromptEta>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn eta_changed(prev: Option<&PromptEta>, next: &PromptEta) -> bool {
+    prev.is_none_or(|p| {
+        p.position != next.position
+            || p.waiting_for_idle != next.waiting_for_idle
+            || p.eta_ms.abs_diff(next.eta_ms) >= ETA_EMIT_THRESHOLD_MS
+    })
+}
+
+/// Re-estimate and emit `prompt-eta` for prompts whose ETA moved.
+pub async fn publish_etas(state: &AppState, idle: bool) {
+    let etas = match current_etas(state, idle).await {
+        Ok(etas) => etas,
+        Err(e) => {
+            tracing::warn!("queue_stats: eta estimate failed: {}", e);
+            return;
+        }
+    };
+    let mut last = LAST_EMITTED.lock().unwrap_or_else(|e| e.into_inner());
+    let mut seen = HashMap::with_capacity(etas.len());
+    for eta in etas {
+        if eta_changed(last.get(&eta.id), &eta) {
+            emit("prompt-eta", json!(eta));
+        }
+        seen.insert(eta.id, eta);
+    }
+    *last = seen;
+}
+
+/// `GET /api/background-prompts/{id}/eta`
+pub async fn prompt_eta(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<PromptEta>, (StatusCode, Json<Value>)> {
+    let idle = crate::idle_scavenger::idle_status(&state).await.idle;
+    current_etas(&state, idle)
+        .await
+        .map_err(db_error("queue_stats"))?
+        .into_iter()
+        .find(|e| e.id == id)
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "No queued or running prompt with this id" })),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn etas_fill_slots_in_queue_order() {
+        let avg = HashMap::from([("anthropic", 10_000), ("google", 40_000)]);
+        let running = [(1, "anthropic", 4_000)];
+        let queued = [
+            (2, "anthropic", true),
+            (3, "anthropic", true),
+            (4, "anthropic", false),
+        ];
+        let etas = estimate_etas(&running, &queued, &avg, 2, &HashMap::new());
+        assert_eq!((etas[0].position, etas[0].eta_ms), (0, 6_000));
+        // Second slot is free right away.
+        assert_eq!((etas[1].wait_ms, etas[1].eta_ms), (0, 10_000));
+        // Then the slot of the running prompt frees up.
+        assert_eq!((etas[2].wait_ms, etas[2].eta_ms), (6_000, 16_000));
+        assert!(etas[3].waiting_for_idle);
+        assert_eq!(etas[3].position, 3);
+    }
+
+    #[test]
+    fn etas_respect_provider_lanes() {
+        let avg = HashMap::from([("google", 40_000)]);
+        let lanes = HashMap::from([("google".to_string(), 1)]);
+        let queued = [(1, "google", true), (2, "google", true), (3, "other", true)];
+        let etas = estimate_etas(&[], &queued, &avg, 4, &lanes);
+        assert_eq!(etas[1].wait_ms, 40_000);
+        // Unknown lane history falls back to the default run time.
+        assert_eq!(etas[2].eta_ms, DEFAULT_RUN_MS);
+    }
+
+    #[test]
+    fn eta_events_skip_small_drift() {
+        let base = PromptEta {
+            id: 1,
+            position: 2,
+            wait_ms: 1_000,
+            eta_ms: 20_000,
+            waiting_for_idle: false,
+        };
+        assert!(eta_changed(None, &base));
+        assert!(!eta_changed(
+            Some(&base),
+            &PromptEta {
+                eta_ms: 22_000,
+                ..base
+            }
+        ));
+        assert!(eta_changed(
+            Some(&base),
+            &PromptEta {
+                eta_ms: 30_000,
+                ..base
+            }
+        ));
+        assert!(eta_changed(
+            Some(&base),
+            &PromptEta {
+                position: 1,
+                ..base
+            }
+        ));
+    }
+
+    #[test]
+    fn ranges_parse_with_hourly_default() {
+        assert_eq!(HistoryRange::parse(None), Some(HistoryRange::Day));
+        assert_eq!(HistoryRange::parse(Some("7d")), Some(HistoryRange::Week));
+        assert_eq!(HistoryRange::parse(Some("30d")), Some(HistoryRange::Month));
+        assert_eq!(HistoryRange::parse(Some("1y")), None);
+    }
+
+    #[test]
+    fn midnight_follows_the_local_offset() {
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+        let now = tz.with_ymd_and_hms(2026, 3, 17, 1, 30, 0).unwrap();
+        assert_eq!(
+            local_midnight(&now),
+            Utc.with_ymd_and_hms(2026, 3, 16, 22, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn hourly_buckets_end_with_the_current_hour() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 17, 10, 42, 5).unwrap();
+        let bounds = bucket_bounds(HistoryRange::Day, &now);
+        assert_eq!(bounds.len(), 24);
+        assert_eq!(
+            bounds[23],
+            (
+                Utc.with_ymd_and_hms(2026, 3, 17, 10, 0, 0).unwrap(),
+                Utc.with_ymd_and_hms(2026, 3, 17, 11, 0, 0).unwrap()
+            )
+        );
+        assert_eq!(
+            bounds[0].0,
+            Utc.with_ymd_and_hms(2026, 3, 16, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn daily_buckets_are_contiguous_local_days() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 17, 10, 0, 0).unwrap();
+        let bounds = bucket_bounds(HistoryRange::Week, &now);
+        assert_eq!(bounds.len(), 7);
+        assert_eq!(
+            bounds[0].0,
+            Utc.with_ymd_and_hms(2026, 3, 11, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            bounds[6].1,
+            Utc.with_ymd_and_hms(2026, 3, 18, 0, 0, 0).unwrap()
+        );
+        assert!(bounds.windows(2).all(|w| w[0].1 == w[1].0));
+        assert_eq!(bucket_bounds(HistoryRange::Month, &now).len(), 30);
+    }
+}
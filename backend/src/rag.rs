@@ -0,0 +1,743 @@
+//! Local retrieval-augmented generation over the HYDRA project directory.
+//!
+//! Text files under the project root are split into overlapping line-based
+//! chunks, embedded with the configured backend (see `crate::embeddings`) and
+//! stored in `ch_rag_files` / `ch_rag_chunks`. Vectors are mirrored into an
+//! in-process index for search; it is rebuilt after every reindex pass.
+//! Search is exact (brute-force cosine) — a project tree yields a few
+//! thousand chunks, well below the size where an ANN index pays off.
+//!
+//! Reindexing is incremental: a pass rescans the tree, re-embeds only files
+//! whose content hash or embedding model changed and drops rows for deleted
+//! files. Every vector records its model; vectors from another model than the
+//! configured one are left out of search until they are re-embedded. Passes
+//! run at startup and whenever the file watcher (`file_watcher::watch_tree`)
+//! reports a change to an indexable file, after `REINDEX_DEBOUNCE` of quiet.
+//!
+//! Retrieval for WebSocket prompts is best effort: it is skipped while the
+//! index is empty, bounded by `RETRIEVAL_TIMEOUT`, paused for
+//! `RETRIEVAL_BACKOFF` after the embedding backend fails, and retrieved
+//! chunks pass the safety guard (`safety.rs`) before they are injected.
+//!
+//! Opt-in via environment or `[rag]` in the config file (`hydra_config.rs`):
+//! - `CH_RAG_DIR` — project root to index; RAG is disabled when unset.
+//! - `CH_RAG_REINDEX_SECS` — rescan interval when the file watcher is off
+//!   (`CH_FILE_WATCHER=0`; default 300, min 30).
+//! - `CH_RAG_TOP_K` — chunks injected into WebSocket prompts (default 4,
+//!   `0` disables automatic injection; `/api/rag/query` still works).
+//! - `CH_RAG_MIN_SCORE` — minimum cosine similarity to inject (default 0.35).
+//!
+//! - `POST /api/rag/query` — `{ prompt, top_k? }` → ranked chunks
+//! - `POST /api/rag/reindex` — trigger an incremental reindex now
+//! - `GET  /api/rag/status` — root, file/chunk counts, last reindex
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::embeddings::{cosine_similarity, embed_texts};
+use crate::hydra_config::{Derived, HydraConfig, number, text};
+use crate::state::AppState;
+
+pub const JOB_RAG_REINDEX: &str = "rag_reindex";
+/// Lines per chunk and overlap between consecutive chunks.
+const CHUNK_LINES: usize = 60;
+const CHUNK_OVERLAP: usize = 10;
+/// Chunks longer than this are cut (minified files, long lines).
+const MAX_CHUNK_CHARS: usize = 4_000;
+/// Files larger than this are skipped.
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+/// Texts per embedding call.
+const EMBED_BATCH: usize = 64;
+const MAX_TOP_K: usize = 20;
+/// Quiet period after the last file change before a reindex pass.
+const REINDEX_DEBOUNCE: Duration = Duration::from_secs(5);
+/// Upper bound on retrieval for a WebSocket prompt (query embedding + search).
+const RETRIEVAL_TIMEOUT: Duration = Duration::from_secs(2);
+/// Retrieval is skipped this long after the embedding backend failed.
+const RETRIEVAL_BACKOFF: Duration = Duration::from_secs(60);
+
+const SKIP_DIRS: &[&str] = &[
+    ".git", "node_modules", "target", "dist", "build", ".next", ".venv", "__pycache__",
+    "coverage",
+];
+
+const TEXT_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "mjs", "py", "go", "java", "kt", "c", "h", "cpp", "hpp",
+    "cs", "rb", "php", "swift", "sql", "sh", "ps1", "md", "mdx", "txt", "toml", "yaml", "yml",
+    "json", "html", "css", "scss", "vue", "svelte",
+];
+
+#[derive(Debug, Clone)]
+pub struct RagConfig {
+    pub root: PathBuf,
+    pub reindex_interval: Duration,
+    pub top_k: usize,
+    pub min_score: f32,
+}
+
+static CONFIG: Derived<Option<Arc<RagConfig>>> = Derived::new(build_config);
+
+fn build_config(config: &HydraConfig) -> Option<Arc<RagConfig>> {
+    let file = &config.rag;
+    let root = text("CH_RAG_DIR", file.dir.as_deref())?;
+    let cfg = RagConfig {
+        root: PathBuf::from(root),
+        reindex_interval: Duration::from_secs(
+            number("CH_RAG_REINDEX_SECS", file.reindex_secs)
+                .unwrap_or(300)
+                .max(30),
+        ),
+        top_k: number("CH_RAG_TOP_K", file.top_k)
+            .unwrap_or(4)
+            .min(MAX_TOP_K),
+        min_score: number("CH_RAG_MIN_SCORE", file.min_score).unwrap_or(0.35),
+    };
+    tracing::info!(
+        "rag: indexing {} every {}s (top_k {})",
+        cfg.root.display(),
+        cfg.reindex_interval.as_secs(),
+        cfg.top_k
+    );
+    Some(Arc::new(cfg))
+}
+
+/// RAG configuration (env overrides the file). `None` = RAG disabled.
+pub fn config() -> Option<Arc<RagConfig>> {
+    CONFIG.get()
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  In-process vector index
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+struct IndexedChunk {
+    path: String,
+    start_line: i32,
+    end_line: i32,
+    content: String,
+    embedding: Vec<f32>,
+}
+
+#[derive(Default)]
+struct RagIndex {
+    chunks: Vec<IndexedChunk>,
+    model: Option<String>,
+    last_reindex: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+static INDEX: OnceLock<RwLock<RagIndex>> = OnceLock::new();
+/// When retrieval last failed or timed out (see `RETRIEVAL_BACKOFF`).
+static LAST_RETRIEVAL_FAILURE: std::sync::Mutex<Option<Instant>> = std::sync::Mutex::new(None);
+/// Serializes reindex passes (background loop vs. manual trigger).
+static REINDEX_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn index() -> &'static RwLock<RagIndex> {
+    INDEX.get_or_init(|| RwLock::new(RagIndex::default()))
+}
+
+/// One retrieved chunk.
+#[derive(Debug, Clone, Serialize)]
+pub struct RagHit {
+    pub path: String,
+    pub start_line: i32,
+    pub end_line: i32,
+    pub score: f32,
+    pub content: String,
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Chunking + file discovery
+// ═══════════════════════════════════════════════════════════════════════
+
+/// A chunk before embedding. Lines are 1-based and inclusive.
+#[derive(Debug, Clone, PartialEq)]
+struct Chunk {
+    start_line: usize,
+    end_line: usize,
+    content: String,
+}
+
+fn chunk_text(text: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut chunks = Vec::new();
+    let step = CHUNK_LINES - CHUNK_OVERLAP;
+    let mut start = 0;
+    while start < lines.len() {
+        let end = (start + CHUNK_LINES).min(lines.len());
+        let mut content = lines[start..end].join("\n");
+        if content.len() > MAX_CHUNK_CHARS {
+            let mut cut = MAX_CHUNK_CHARS;
+            while !content.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            content.truncate(cut);
+        }
+        if !content.trim().is_empty() {
+            chunks.push(Chunk {
+                start_line: start + 1,
+                end_line: end,
+                content,
+            });
+        }
+        if end == lines.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+fn is_indexable(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| TEXT_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// Relative paths of indexable files under `root`, skipping build output,
+/// dependency folders and hidden directories.
+fn collect_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name().to_string_lossy().to_string();
+            if file_type.is_dir() {
+                if !name.starts_with('.') && !SKIP_DIRS.contains(&name.as_str()) {
+                    stack.push(path);
+                }
+            } else if file_type.is_file()
+                && is_indexable(&path)
+                && entry.metadata().is_ok_and(|m| m.len() <= MAX_FILE_BYTES)
+                && let Ok(rel) = path.strip_prefix(root)
+            {
+                files.push(rel.to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Whether a changed path affects the index: an indexable file under `root`
+/// outside skipped and hidden directories.
+fn affects_index(root: &Path, path: &Path) -> bool {
+    let Ok(rel) = path.strip_prefix(root) else {
+        return false;
+    };
+    let mut dirs = rel.parent().into_iter().flat_map(Path::components);
+    is_indexable(rel)
+        && !dirs.any(|c| {
+            let name = c.as_os_str().to_string_lossy();
+            name.starts_with('.') || SKIP_DIRS.contains(&name.as_ref())
+        })
+}
+
+fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Indexing
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReindexReport {
+    pub scanned: usize,
+    pub updated: usize,
+    pub removed: usize,
+    pub chunks_embedded: usize,
+    pub failed: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// Incremental reindex: re-embed changed files, drop deleted ones, then
+/// reload the in-process index.
+pub async fn reindex(state: &AppState, cfg: &RagConfig) -> Result<ReindexReport, String> {
+    let _guard = REINDEX_LOCK.lock().await;
+    let started = std::time::Instant::now();
+    let root = cfg.root.clone();
+
+    let files = tokio::task::spawn_blocking(move || collect_files(&root))
+        .await
+        .map_err(|e| format!("File scan failed: {}", e))?;
+
+    let backend = crate::embeddings::backend();
+    let model = backend.default_model.as_str();
+    let known: HashMap<String, (String, String)> = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT path, content_hash, model FROM ch_rag_files",
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load index state: {}", e))?
+    .into_iter()
+    .map(|(path, hash, model)| (path, (hash, model)))
+    .collect();
+
+    let mut report = ReindexReport {
+        scanned: files.len(),
+        ..Default::default()
+    };
+    let mut seen = std::collections::HashSet::with_capacity(files.len());
+
+    for rel in &files {
+        let key = rel.to_string_lossy().replace('\\', "/");
+        seen.insert(key.clone());
+        let Ok(text) = tokio::fs::read_to_string(cfg.root.join(rel)).await else {
+            // Binary or unreadable — treat as absent.
+            continue;
+        };
+        let hash = content_hash(&text);
+        // Unchanged and embedded with the current model.
+        if known
+            .get(&key)
+            .is_some_and(|(h, m)| *h == hash && m == model)
+        {
+            continue;
+        }
+        match index_file(state, &key, &hash, &text).await {
+            Ok(n) => {
+                report.updated += 1;
+                report.chunks_embedded += n;
+            }
+            Err(e) => {
+                tracing::warn!("rag: failed to index {}: {}", key, e);
+                report.failed.push(key);
+            }
+        }
+    }
+
+    let removed: Vec<String> = known.into_keys().filter(|p| !seen.contains(p)).collect();
+    if !removed.is_empty() {
+        sqlx::query("DELETE FROM ch_rag_files WHERE path = ANY($1)")
+            .bind(&removed)
+            .execute(&state.db)
+            .await
+            .map_err(|e| format!("Failed to drop deleted files: {}", e))?;
+        report.removed = removed.len();
+    }
+
+    reload_index(state).await?;
+    report.duration_ms = started.elapsed().as_millis() as u64;
+    Ok(report)
+}
+
+async fn index_file(state: &AppState, path: &str, hash: &str, text: &str) -> Result<usize, String> {
+    let chunks = chunk_text(text);
+    let mut vectors = Vec::with_capacity(chunks.len());
+    let mut model = String::new();
+    for batch in chunks.chunks(EMBED_BATCH) {
+        // Prefix the path so file names contribute to retrieval.
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|c| format!("{}\n{}", path, c.content))
+            .collect();
+        let embedded = embed_texts(&state.http_client, &texts, None).await?;
+        model = embedded.model;
+        vectors.extend(embedded.vectors);
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO ch_rag_files (path, content_hash, chunk_count, model, indexed_at) \
+         VALUES ($1, $2, $3, $4, NOW()) \
+         ON CONFLICT (path) DO UPDATE SET content_hash = EXCLUDED.content_hash, \
+         chunk_count = EXCLUDED.chunk_count, model = EXCLUDED.model, indexed_at = NOW()",
+    )
+    .bind(path)
+    .bind(hash)
+    .bind(chunks.len() as i32)
+    .bind(&model)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| e.to_string())?;
+    sqlx::query("DELETE FROM ch_rag_chunks WHERE path = $1")
+        .bind(path)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (i, (chunk, vector)) in chunks.iter().zip(vectors).enumerate() {
+        sqlx::query(
+            "INSERT INTO ch_rag_chunks (path, chunk_index, start_line, end_line, content, embedding, model) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(path)
+        .bind(i as i32)
+        .bind(chunk.start_line as i32)
+        .bind(chunk.end_line as i32)
+        .bind(&chunk.content)
+        .bind(&vector)
+        .bind(&model)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(chunks.len())
+}
+
+#[derive(sqlx::FromRow)]
+struct ChunkRow {
+    path: String,
+    start_line: i32,
+    end_line: i32,
+    content: String,
+    embedding: Vec<f32>,
+}
+
+/// Load the vectors of the configured embedding model into the index.
+async fn reload_index(state: &AppState) -> Result<(), String> {
+    let current = crate::embeddings::backend().default_model.clone();
+    let rows = sqlx::query_as::<_, ChunkRow>(
+        "SELECT path, start_line, end_line, content, embedding FROM ch_rag_chunks \
+         WHERE model = $1 ORDER BY path, chunk_index",
+    )
+    .bind(&current)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load chunks: {}", e))?;
+
+    let model = (!rows.is_empty()).then_some(current);
+    let chunks: Vec<IndexedChunk> = rows
+        .into_iter()
+        .map(|r| IndexedChunk {
+            path: r.path,
+            start_line: r.start_line,
+            end_line: r.end_line,
+            content: r.content,
+            embedding: r.embedding,
+        })
+        .collect();
+
+    let mut idx = index().write().await;
+    idx.chunks = chunks;
+    idx.model = model;
+    idx.last_reindex = Some(Utc::now());
+    idx.last_error = None;
+    Ok(())
+}
+
+async fn run_reindex(state: &AppState, cfg: &RagConfig) {
+    match reindex(state, cfg).await {
+        Ok(r) if r.updated > 0 || r.removed > 0 => tracing::info!(
+            "rag: reindexed {} file(s), removed {}, {} chunk(s) in {}ms",
+            r.updated,
+            r.removed,
+            r.chunks_embedded,
+            r.duration_ms
+        ),
+        Ok(_) => {}
+        Err(e) => {
+            tracing::warn!("rag: reindex failed: {}", e);
+            index().write().await.last_error = Some(e);
+        }
+    }
+}
+
+/// Background loop: load the persisted index, reindex once, then reindex
+/// when the file watcher reports changes (or on an interval without it).
+/// No-op when RAG is not configured.
+pub fn spawn_reindex_loop(state: AppState) {
+    let Some(cfg) = config() else {
+        return;
+    };
+    tokio::spawn(async move {
+        if let Err(e) = reload_index(&state).await {
+            tracing::warn!("rag: {}", e);
+        }
+        let Some(mut watch) = crate::file_watcher::watch_tree(&cfg.root) else {
+            tracing::info!(
+                "rag: file watcher unavailable, rescanning every {}s",
+                cfg.reindex_interval.as_secs()
+            );
+            state
+                .job_schedule
+                .start(JOB_RAG_REINDEX, cfg.reindex_interval);
+            let mut interval = tokio::time::interval(cfg.reindex_interval);
+            loop {
+                interval.tick().await;
+                state.job_schedule.record_run(JOB_RAG_REINDEX);
+                run_reindex(&state, &cfg).await;
+            }
+        };
+        run_reindex(&state, &cfg).await;
+        let mut due: Option<tokio::time::Instant> = None;
+        loop {
+            tokio::select! {
+                changed = watch.changes.recv() => match changed {
+                    Some(path) if affects_index(&cfg.root, &path) => {
+                        due = Some(tokio::time::Instant::now() + REINDEX_DEBOUNCE);
+                    }
+                    Some(_) => {}
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(due.unwrap_or_else(tokio::time::Instant::now)),
+                    if due.is_some() =>
+                {
+                    due = None;
+                    run_reindex(&state, &cfg).await;
+                }
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Retrieval
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Top-`top_k` chunks for `prompt` by cosine similarity.
+pub async fn rag_query(state: &AppState, prompt: &str, top_k: usize) -> Result<Vec<RagHit>, String> {
+    let model = {
+        let idx = index().read().await;
+        if idx.chunks.is_empty() {
+            return Ok(Vec::new());
+        }
+        idx.model.clone()
+    };
+    let Some(model) = model else {
+        return Ok(Vec::new());
+    };
+    let query = embed_texts(&state.http_client, &[prompt.to_string()], Some(&model)).await?;
+    let Some(query) = query.vectors.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+
+    let idx = index().read().await;
+    let mut scored: Vec<(f32, &IndexedChunk)> = idx
+        .chunks
+        .iter()
+        .map(|c| (cosine_similarity(&query, &c.embedding), c))
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(scored
+        .into_iter()
+        .take(top_k.clamp(1, MAX_TOP_K))
+        .map(|(score, c)| RagHit {
+            path: c.path.clone(),
+            start_line: c.start_line,
+            end_line: c.end_line,
+            score,
+            content: c.content.clone(),
+        })
+        .collect())
+}
+
+/// System prompt section for retrieved chunks.
+fn format_context(hits: &[RagHit]) -> String {
+    let mut out = String::from(
+        "\n\n## Retrieved project context\nExcerpts from the project that may be relevant to the request:\n",
+    );
+    for hit in hits {
+        out.push_str(&format!(
+            "\n### {} (lines {}-{})\n```\n{}\n```\n",
+            hit.path, hit.start_line, hit.end_line, hit.content
+        ));
+    }
+    out
+}
+
+fn retrieval_paused() -> bool {
+    LAST_RETRIEVAL_FAILURE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_some_and(|at| at.elapsed() < RETRIEVAL_BACKOFF)
+}
+
+fn retrieval_failed() {
+    *LAST_RETRIEVAL_FAILURE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+}
+
+/// Retrieved chunks that may go to the provider: secrets redacted (or the
+/// chunk dropped under a blocking policy) by the safety guard.
+fn screen_hits(hits: Vec<RagHit>) -> Vec<RagHit> {
+    hits.into_iter()
+        .filter_map(|mut hit| {
+            crate::safety::screen_prompt(&mut hit.content, "rag")
+                .ok()
+                .map(|_| hit)
+        })
+        .collect()
+}
+
+/// Append retrieved chunks to `system_prompt` before the request is routed.
+/// Returns the hits that were injected; retrieval errors and timeouts are
+/// logged and leave the prompt unchanged.
+pub async fn augment_system_prompt(
+    state: &AppState,
+    system_prompt: &mut String,
+    prompt: &str,
+) -> Vec<RagHit> {
+    let Some(cfg) = config() else {
+        return Vec::new();
+    };
+    if cfg.top_k == 0 || retrieval_paused() || index().read().await.chunks.is_empty() {
+        return Vec::new();
+    }
+    let hits: Vec<RagHit> =
+        match tokio::time::timeout(RETRIEVAL_TIMEOUT, rag_query(state, prompt, cfg.top_k)).await {
+            Ok(Ok(hits)) => {
+                screen_hits(hits.into_iter().filter(|h| h.score >= cfg.min_score).collect())
+            }
+            Ok(Err(e)) => {
+                tracing::warn!("rag: retrieval failed: {}", e);
+                retrieval_failed();
+                return Vec::new();
+            }
+            Err(_) => {
+                tracing::warn!("rag: retrieval timed out after {:?}", RETRIEVAL_TIMEOUT);
+                retrieval_failed();
+                return Vec::new();
+            }
+        };
+    if !hits.is_empty() {
+        system_prompt.push_str(&format_context(&hits));
+    }
+    hits
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn disabled() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "RAG is not configured (set CH_RAG_DIR)" })),
+    )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RagQueryRequest {
+    pub prompt: String,
+    #[serde(default)]
+    pub top_k: Option<usize>,
+}
+
+/// `POST /api/rag/query`
+pub async fn query(
+    State(state): State<AppState>,
+    Json(req): Json<RagQueryRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let cfg = config().ok_or_else(disabled)?;
+    if req.prompt.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "prompt must not be empty" })),
+        ));
+    }
+    let top_k = req.top_k.unwrap_or(cfg.top_k.max(1));
+    let hits = rag_query(&state, &req.prompt, top_k).await.map_err(|e| {
+        tracing::warn!("rag: {}", e);
+        (StatusCode::BAD_GATEWAY, Json(json!({ "error": e })))
+    })?;
+    Ok(Json(json!({ "results": hits })))
+}
+
+/// `POST /api/rag/reindex`
+pub async fn trigger_reindex(
+    State(state): State<AppState>,
+) -> Result<Json<ReindexReport>, (StatusCode, Json<Value>)> {
+    let cfg = config().ok_or_else(disabled)?;
+    reindex(&state, &cfg).await.map(Json).map_err(|e| {
+        tracing::warn!("rag: reindex failed: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e })))
+    })
+}
+
+/// `GET /api/rag/status`
+pub async fn status() -> Json<Value> {
+    let Some(cfg) = config() else {
+        return Json(json!({ "enabled": false }));
+    };
+    let idx = index().read().await;
+    let files = idx
+        .chunks
+        .iter()
+        .map(|c| c.path.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    Json(json!({
+        "enabled": true,
+        "root": cfg.root.display().to_string(),
+        "files": files,
+        "chunks": idx.chunks.len(),
+        "model": idx.model,
+        "top_k": cfg.top_k,
+        "reindex_interval_secs": cfg.reindex_interval.as_secs(),
+        "retrieval_paused": retrieval_paused(),
+        "last_reindex": idx.last_reindex.map(|t| t.to_rfc3339()),
+        "last_error": idx.last_error,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_overlap_and_cover_file() {
+        let text: String = (1..=130).map(|i| format!("line {}\n", i)).collect();
+        let chunks = chunk_text(&text);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 60));
+        assert_eq!((chunks[1].start_line, chunks[1].end_line), (51, 110));
+        assert_eq!((chunks[2].start_line, chunks[2].end_line), (101, 130));
+        assert!(chunks[2].content.ends_with("line 130"));
+    }
+
+    #[test]
+    fn blank_files_produce_no_chunks() {
+        assert!(chunk_text("").is_empty());
+        assert!(chunk_text("\n   \n\n").is_empty());
+    }
+
+    #[test]
+    fn long_chunks_are_cut_on_char_boundary() {
+        let text = "ż".repeat(MAX_CHUNK_CHARS);
+        let chunks = chunk_text(&text);
+        assert!(chunks[0].content.len() <= MAX_CHUNK_CHARS);
+    }
+
+    #[test]
+    fn changes_outside_indexed_files_are_ignored() {
+        let root = Path::new("/work/repo");
+        assert!(affects_index(root, Path::new("/work/repo/src/main.rs")));
+        assert!(!affects_index(root, Path::new("/work/repo/target/debug/build.rs")));
+        assert!(!affects_index(root, Path::new("/work/repo/.git/HEAD.md")));
+        assert!(!affects_index(root, Path::new("/work/repo/logo.png")));
+        assert!(!affects_index(root, Path::new("/elsewhere/main.rs")));
+    }
+
+    #[test]
+    fn only_text_files_are_indexable() {
+        assert!(is_indexable(Path::new("src/main.rs")));
+        assert!(is_indexable(Path::new("README.MD")));
+        assert!(!is_indexable(Path::new("logo.png")));
+        assert!(!is_indexable(Path::new("Makefile")));
+    }
+}
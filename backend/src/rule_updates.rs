@@ -0,0 +1,556 @@
+//! Signed update channel for routing rules, model recommendations and prices.
+//!
+//! Built-in tables (complexity keywords, view-hint keywords, tier → model
+//! routing, per-tier pricing) live in code. This module layers two sparse
+//! overlays on top of them, stored in `ch_rule_overlays`:
+//!
+//! 1. `remote` — the last verified manifest fetched from `CH_RULES_MANIFEST_URL`
+//! 2. `local`  — user overrides set via `PUT /api/rules/overrides`
+//!
+//! Lookups resolve local → remote → built-in, key by key, so local overrides
+//! always win and a remote update never clobbers them.
+//!
+//! The manifest is JSON `{ version, payload, signature }` where `payload` is
+//! base64 of a `RuleSet` and `signature` is the hex Ed25519 signature of
+//! `"{version}.{payload}"`. Clients only hold the pinned public key
+//! `CH_RULES_MANIFEST_PUBKEY`, so they cannot mint manifests themselves.
+//! Unsigned or mis-signed manifests are rejected, only a strictly newer
+//! `version` replaces the stored one (no downgrades), and remote model
+//! recommendations must name a tier or a model in the model registry.
+//!
+//! Opt-in via environment or `[rules]` in the config file (`hydra_config.rs`):
+//! - `CH_RULES_MANIFEST_URL` — manifest URL; remote updates are disabled when unset.
+//! - `CH_RULES_MANIFEST_PUBKEY` — hex Ed25519 public key (32 bytes); required
+//!   together with the URL.
+//! - `CH_RULES_CHECK_SECS` — automatic check interval (default 86400, min 600,
+//!   `0` = only on demand).
+//!
+//! - `GET  /api/rules` — effective overlay + remote/local versions
+//! - `POST /api/rules/check-updates` — `check_rule_updates`: fetch + verify now
+//! - `PUT  /api/rules/overrides` — replace the local override set
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use base64::Engine as _;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::hydra_config::{Derived, HydraConfig, number, text};
+use crate::state::AppState;
+
+pub const JOB_RULE_UPDATES: &str = "rule_updates";
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+/// Complexity classes understood by `prompt_complexity` / `resolve_chat_context`.
+const COMPLEXITY_CLASSES: &[&str] = &["simple", "complex"];
+/// Model tiers accepted as recommendation targets; anything else is a model id.
+pub const MODEL_TIERS: &[&str] = &["commander", "coordinator", "executor", "flash"];
+
+#[derive(Debug, Clone)]
+pub struct RulesConfig {
+    pub manifest_url: String,
+    key: VerifyingKey,
+    pub check_interval: Option<Duration>,
+}
+
+static CONFIG: Derived<Option<Arc<RulesConfig>>> = Derived::new(build_config);
+
+fn build_config(config: &HydraConfig) -> Option<Arc<RulesConfig>> {
+    let file = &config.rules;
+    let manifest_url = text("CH_RULES_MANIFEST_URL", file.manifest_url.as_deref())?;
+    let Some(raw_key) = text("CH_RULES_MANIFEST_PUBKEY", file.manifest_pubkey.as_deref()) else {
+        tracing::warn!("rule_updates: manifest URL set without a public key (CH_RULES_MANIFEST_PUBKEY) — disabled");
+        return None;
+    };
+    let key = match parse_public_key(&raw_key) {
+        Ok(key) => key,
+        Err(e) => {
+            tracing::warn!("rule_updates: manifest public key {} — disabled", e);
+            return None;
+        }
+    };
+    let secs = number("CH_RULES_CHECK_SECS", file.check_secs).unwrap_or(86_400);
+    let cfg = RulesConfig {
+        manifest_url,
+        key,
+        check_interval: (secs > 0).then(|| Duration::from_secs(secs.max(600))),
+    };
+    tracing::info!(
+        "rule_updates: manifest {} (auto-check {})",
+        cfg.manifest_url,
+        cfg.check_interval
+            .map(|d| format!("every {}s", d.as_secs()))
+            .unwrap_or_else(|| "off".to_string())
+    );
+    Some(Arc::new(cfg))
+}
+
+/// Remote manifest configuration (env overrides the file). `None` = remote
+/// updates disabled.
+pub fn config() -> Option<Arc<RulesConfig>> {
+    CONFIG.get()
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Rule set + effective overlay
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Sparse overrides for the built-in routing tables. Every map is keyed so
+/// that overlays merge entry by entry.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Complexity class (`simple` / `complex`) → keywords forcing that class.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub complexity_keywords: BTreeMap<String, Vec<String>>,
+    /// View id → additional keywords that suggest the view.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub view_hints: BTreeMap<String, Vec<String>>,
+    /// Complexity class → model tier (`commander`, ...) or explicit model id.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub model_recommendations: BTreeMap<String, String>,
+    /// Pricing tier (`opus` / `sonnet` / `haiku`) → `[input, output]` USD per 1M tokens.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pricing: BTreeMap<String, [f64; 2]>,
+}
+
+impl RuleSet {
+    /// `self` with every entry of `top` replacing the same key.
+    fn overlay(mut self, top: &RuleSet) -> RuleSet {
+        self.complexity_keywords
+            .extend(top.complexity_keywords.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.view_hints
+            .extend(top.view_hints.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.model_recommendations
+            .extend(top.model_recommendations.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.pricing.extend(top.pricing.iter().map(|(k, v)| (k.clone(), *v)));
+        self
+    }
+
+    /// Rejects entries the routing code could not use. Keywords are
+    /// normalised to lowercase since prompts are matched lowercased.
+    fn validate(mut self) -> Result<RuleSet, String> {
+        for class in self.complexity_keywords.keys().chain(self.model_recommendations.keys()) {
+            if !COMPLEXITY_CLASSES.contains(&class.as_str()) {
+                return Err(format!(
+                    "Unknown complexity class '{}' (expected one of {:?})",
+                    class, COMPLEXITY_CLASSES
+                ));
+            }
+        }
+        if let Some((class, _)) = self.model_recommendations.iter().find(|(_, m)| m.trim().is_empty()) {
+            return Err(format!("Empty model recommendation for '{}'", class));
+        }
+        if let Some((tier, _)) = self
+            .pricing
+            .iter()
+            .find(|(_, p)| p.iter().any(|v| !v.is_finite() || *v < 0.0))
+        {
+            return Err(format!("Invalid price for tier '{}'", tier));
+        }
+        for keywords in self.complexity_keywords.values_mut().chain(self.view_hints.values_mut()) {
+            keywords.retain(|k| !k.trim().is_empty());
+            for k in keywords.iter_mut() {
+                *k = k.trim().to_lowercase();
+            }
+        }
+        Ok(self)
+    }
+}
+
+static EFFECTIVE: LazyLock<RwLock<Arc<RuleSet>>> = LazyLock::new(Default::default);
+
+/// Current merged overlay (remote, then local on top). Empty until loaded.
+pub fn effective() -> Arc<RuleSet> {
+    EFFECTIVE.read().map(|r| r.clone()).unwrap_or_default()
+}
+
+fn set_effective(rules: RuleSet) {
+    if let Ok(mut guard) = EFFECTIVE.write() {
+        *guard = Arc::new(rules);
+    }
+}
+
+/// Complexity class forced by an overlay keyword. `complex` keywords are
+/// checked first so a prompt matching both lands in the stronger tier.
+pub fn complexity_override(prompt: &str) -> Option<&'static str> {
+    let rules = effective();
+    if rules.complexity_keywords.is_empty() {
+        return None;
+    }
+    let lower = crate::handlers::analysis_prefix(prompt, crate::handlers::PROMPT_ANALYSIS_PREFIX_BYTES)
+        .to_lowercase();
+    ["complex", "simple"].into_iter().find(|class| {
+        rules
+            .complexity_keywords
+            .get(*class)
+            .is_some_and(|kws| kws.iter().any(|kw| lower.contains(kw.as_str())))
+    })
+}
+
+/// Overlay model recommendation (tier name or model id) for a complexity class.
+pub fn recommended_model(class: &str) -> Option<String> {
+    effective().model_recommendations.get(class).cloned()
+}
+
+/// Overlay views whose keywords occur in the already-lowercased prompt.
+pub fn matching_views(lower: &str) -> Vec<String> {
+    effective()
+        .view_hints
+        .iter()
+        .filter(|(_, kws)| kws.iter().any(|kw| lower.contains(kw.as_str())))
+        .map(|(view, _)| view.clone())
+        .collect()
+}
+
+/// Overlay `(input, output)` price per 1M tokens for a pricing tier.
+pub fn price(tier: &str) -> Option<(f64, f64)> {
+    effective().pricing.get(tier).map(|p| (p[0], p[1]))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Persistence
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OverlayInfo {
+    pub version: i64,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub rules: RuleSet,
+}
+
+impl Default for OverlayInfo {
+    fn default() -> Self {
+        Self {
+            version: 0,
+            updated_at: None,
+            rules: RuleSet::default(),
+        }
+    }
+}
+
+async fn load_overlay(state: &AppState, source: &str) -> Result<OverlayInfo, String> {
+    let row: Option<(i64, Value, DateTime<Utc>)> = sqlx::query_as(
+        "SELECT version, rules, updated_at FROM ch_rule_overlays WHERE source = $1",
+    )
+    .bind(source)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load {} rules: {}", source, e))?;
+    let Some((version, rules, updated_at)) = row else {
+        return Ok(OverlayInfo::default());
+    };
+    let rules = serde_json::from_value(rules)
+        .map_err(|e| format!("Stored {} rules are malformed: {}", source, e))?;
+    Ok(OverlayInfo {
+        version,
+        updated_at: Some(updated_at),
+        rules,
+    })
+}
+
+async fn store_overlay(state: &AppState, source: &str, version: i64, rules: &RuleSet) -> Result<(), String> {
+    let rules = serde_json::to_value(rules).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO ch_rule_overlays (source, version, rules, updated_at) VALUES ($1, $2, $3, NOW()) \
+         ON CONFLICT (source) DO UPDATE SET version = EXCLUDED.version, rules = EXCLUDED.rules, updated_at = NOW()",
+    )
+    .bind(source)
+    .bind(version)
+    .bind(rules)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store {} rules: {}", source, e))?;
+    Ok(())
+}
+
+/// Rebuild the effective overlay from the database.
+pub async fn reload(state: &AppState) -> Result<(), String> {
+    let remote = load_overlay(state, "remote").await?;
+    let local = load_overlay(state, "local").await?;
+    set_effective(remote.rules.overlay(&local.rules));
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Manifest verification + update check
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    version: i64,
+    payload: String,
+    signature: String,
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim();
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Ed25519 public key from its hex encoding.
+pub(crate) fn parse_public_key(hex: &str) -> Result<VerifyingKey, String> {
+    let bytes: [u8; 32] = from_hex(hex)
+        .and_then(|b| b.try_into().ok())
+        .ok_or("is not 32 hex-encoded bytes")?;
+    VerifyingKey::from_bytes(&bytes).map_err(|_| "is not a valid Ed25519 key".to_string())
+}
+
+/// Check a hex Ed25519 signature of `message`.
+fn verify_signature(key: &VerifyingKey, message: &[u8], signature_hex: &str) -> Result<(), String> {
+    let bytes: [u8; 64] = from_hex(signature_hex)
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Manifest signature is not 64 hex-encoded bytes")?;
+    key.verify_strict(message, &Signature::from_bytes(&bytes))
+        .map_err(|_| "Manifest signature does not match".to_string())
+}
+
+/// Verify the manifest signature and decode its rule set.
+fn verify_manifest(manifest: &Manifest, key: &VerifyingKey) -> Result<RuleSet, String> {
+    let signed = format!("{}.{}", manifest.version, manifest.payload);
+    verify_signature(key, signed.as_bytes(), &manifest.signature)?;
+    let payload = base64::engine::general_purpose::STANDARD
+        .decode(manifest.payload.trim())
+        .map_err(|e| format!("Manifest payload is not base64: {}", e))?;
+    let rules: RuleSet = serde_json::from_slice(&payload)
+        .map_err(|e| format!("Manifest payload is not a rule set: {}", e))?;
+    rules.validate()
+}
+
+/// Remote recommendations may only name a tier or a model the registry knows.
+async fn check_recommendations(state: &AppState, rules: &RuleSet) -> Result<(), String> {
+    for (class, model) in &rules.model_recommendations {
+        if !MODEL_TIERS.contains(&model.as_str())
+            && !crate::model_registry::is_known_model(state, model).await
+        {
+            return Err(format!(
+                "Manifest recommends unknown model '{}' for '{}'",
+                model, class
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckOutcome {
+    pub updated: bool,
+    pub version: i64,
+    pub previous_version: i64,
+}
+
+/// Fetch the remote manifest and apply it when it is signed and newer than
+/// the stored one.
+pub async fn check_rule_updates(state: &AppState) -> Result<CheckOutcome, String> {
+    let cfg = config().ok_or("Remote rule updates are not configured (set CH_RULES_MANIFEST_URL)")?;
+
+    let resp = state
+        .http_client
+        .get(&cfg.manifest_url)
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch manifest: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("Manifest fetch returned {}", resp.status()));
+    }
+    let manifest: Manifest = resp
+        .json()
+        .await
+        .map_err(|e| format!("Manifest is not valid JSON: {}", e))?;
+
+    let previous_version = load_overlay(state, "remote").await?.version;
+    if manifest.version <= previous_version {
+        return Ok(CheckOutcome {
+            updated: false,
+            version: previous_version,
+            previous_version,
+        });
+    }
+
+    let rules = verify_manifest(&manifest, &cfg.key)?;
+    check_recommendations(state, &rules).await?;
+    store_overlay(state, "remote", manifest.version, &rules).await?;
+    reload(state).await?;
+    tracing::info!(
+        "rule_updates: applied manifest v{} (was v{})",
+        manifest.version,
+        previous_version
+    );
+    Ok(CheckOutcome {
+        updated: true,
+        version: manifest.version,
+        previous_version,
+    })
+}
+
+/// Load stored overlays and, when configured, check the manifest periodically.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        if let Err(e) = reload(&state).await {
+            tracing::warn!("rule_updates: {}", e);
+        }
+        let Some(interval) = config().and_then(|c| c.check_interval) else {
+            return;
+        };
+        state.job_schedule.start(JOB_RULE_UPDATES, interval);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_RULE_UPDATES);
+            if let Err(e) = check_rule_updates(&state).await {
+                tracing::warn!("rule_updates: {}", e);
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn internal(e: String) -> (StatusCode, Json<Value>) {
+    tracing::warn!("rule_updates: {}", e);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "error": e })))
+}
+
+/// `GET /api/rules`
+pub async fn get_rules(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let remote = load_overlay(&state, "remote").await.map_err(internal)?;
+    let local = load_overlay(&state, "local").await.map_err(internal)?;
+    Ok(Json(json!({
+        "manifest_url": config().map(|c| c.manifest_url.clone()),
+        "effective": effective().as_ref(),
+        "remote": remote,
+        "local": local,
+    })))
+}
+
+/// `POST /api/rules/check-updates`
+pub async fn check_updates(
+    State(state): State<AppState>,
+) -> Result<Json<CheckOutcome>, (StatusCode, Json<Value>)> {
+    if config().is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Remote rule updates are not configured (set CH_RULES_MANIFEST_URL)" })),
+        ));
+    }
+    check_rule_updates(&state).await.map(Json).map_err(|e| {
+        tracing::warn!("rule_updates: {}", e);
+        (StatusCode::BAD_GATEWAY, Json(json!({ "error": e })))
+    })
+}
+
+/// `PUT /api/rules/overrides` — replaces the whole local override set;
+/// send `{}` to clear it.
+pub async fn put_overrides(
+    State(state): State<AppState>,
+    Json(rules): Json<RuleSet>,
+) -> Result<Json<OverlayInfo>, (StatusCode, Json<Value>)> {
+    let rules = rules
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))))?;
+    let version = load_overlay(&state, "local").await.map_err(internal)?.version + 1;
+    store_overlay(&state, "local", version, &rules).await.map_err(internal)?;
+    reload(&state).await.map_err(internal)?;
+    load_overlay(&state, "local").await.map(Json).map_err(internal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn signing_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32])
+    }
+
+    fn signed(version: i64, rules: &Value, key: &SigningKey) -> Manifest {
+        let payload = base64::engine::general_purpose::STANDARD.encode(rules.to_string());
+        let signature = key.sign(format!("{}.{}", version, payload).as_bytes());
+        Manifest {
+            version,
+            payload,
+            signature: to_hex(&signature.to_bytes()),
+        }
+    }
+
+    fn to_hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn signature_check_matches_rfc8032_vector() {
+        // RFC 8032 §7.1, test 1 (empty message).
+        let key = parse_public_key("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap();
+        let signature = "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b";
+        assert!(verify_signature(&key, b"", signature).is_ok());
+        assert!(verify_signature(&key, b"x", signature).is_err());
+        assert!(parse_public_key("abcd").is_err());
+    }
+
+    #[test]
+    fn verify_accepts_signed_manifest() {
+        let key = signing_key();
+        let manifest = signed(3, &json!({ "pricing": { "opus": [10.0, 50.0] } }), &key);
+        let rules = verify_manifest(&manifest, &key.verifying_key()).unwrap();
+        assert_eq!(rules.pricing["opus"], [10.0, 50.0]);
+    }
+
+    #[test]
+    fn verify_rejects_wrong_key_and_tampered_version() {
+        let key = signing_key();
+        let manifest = signed(3, &json!({}), &key);
+        let other = SigningKey::from_bytes(&[8u8; 32]).verifying_key();
+        assert!(verify_manifest(&manifest, &other).is_err());
+        let tampered = Manifest { version: 4, ..manifest };
+        assert!(verify_manifest(&tampered, &key.verifying_key()).is_err());
+        let garbled = Manifest { signature: "zz".to_string(), ..signed(3, &json!({}), &key) };
+        assert!(verify_manifest(&garbled, &key.verifying_key()).is_err());
+    }
+
+    #[test]
+    fn validate_rejects_unknown_class_and_bad_price() {
+        let unknown: RuleSet =
+            serde_json::from_value(json!({ "model_recommendations": { "medium": "executor" } })).unwrap();
+        assert!(unknown.validate().is_err());
+        let negative: RuleSet = serde_json::from_value(json!({ "pricing": { "haiku": [-1.0, 1.0] } })).unwrap();
+        assert!(negative.validate().is_err());
+    }
+
+    #[test]
+    fn validate_lowercases_keywords() {
+        let rules: RuleSet =
+            serde_json::from_value(json!({ "complexity_keywords": { "complex": [" Refactor ", ""] } })).unwrap();
+        assert_eq!(rules.validate().unwrap().complexity_keywords["complex"], vec!["refactor"]);
+    }
+
+    #[test]
+    fn local_overlay_wins_per_key() {
+        let remote: RuleSet = serde_json::from_value(json!({
+            "pricing": { "opus": [10.0, 50.0], "haiku": [0.2, 1.0] },
+            "model_recommendations": { "simple": "executor" }
+        }))
+        .unwrap();
+        let local: RuleSet = serde_json::from_value(json!({ "pricing": { "opus": [12.0, 60.0] } })).unwrap();
+        let merged = remote.overlay(&local);
+        assert_eq!(merged.pricing["opus"], [12.0, 60.0]);
+        assert_eq!(merged.pricing["haiku"], [0.2, 1.0]);
+        assert_eq!(merged.model_recommendations["simple"], "executor");
+    }
+}
@@ -0,0 +1,296 @@
+//! Run history for the web dashboard — past swarm runs and background queue
+//! runs read straight from the persisted stores (`ch_swarm_tasks`,
+//! `ch_background_prompts`), so they are available after restarts and
+//! without a desktop client running.
+//!
+//! Both endpoints take `range` (`<n>m`, `<n>h` or `<n>d`, up to 90 days,
+//! default `7d`), an optional `status` filter and `limit` / `offset` paging
+//! (default 50, at most 500), and return the page of runs plus a summary of
+//! the whole filtered range: run / finished / failed counts, failure rate and
+//! average duration, overall and per pattern (swarm) or per model (queue).
+//!
+//! - `GET /api/swarm/runs?range=7d&status=failed&pattern=parallel`
+//! - `GET /api/queue/history?range=30d&model=claude-sonnet-4-6`
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::db_error;
+use crate::state::AppState;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+const PROMPT_PREVIEW_CHARS: i32 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct RunsQuery {
+    #[serde(default)]
+    pub range: Option<String>,
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Swarm pattern (`/api/swarm/runs` only).
+    #[serde(default)]
+    pub pattern: Option<String>,
+    /// Model (`/api/queue/history` only).
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub limit: Option<i64>,
+    #[serde(default)]
+    pub offset: Option<i64>,
+}
+
+/// Validated `range` in seconds and page bounds.
+fn page_of(q: &RunsQuery) -> Result<(i64, i64, i64), (StatusCode, Json<Value>)> {
+    let range = crate::metrics_history::parse_range(q.range.as_deref().unwrap_or("7d"))
+        .ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "range must be like 30m, 6h or 7d (at most 90d)" })),
+            )
+        })?;
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = q.offset.unwrap_or(0).max(0);
+    Ok((range, limit, offset))
+}
+
+/// Counters of one group (pattern or model) of runs.
+#[derive(Debug, Clone, Default, Serialize, sqlx::FromRow)]
+pub struct RunGroup {
+    pub key: Option<String>,
+    pub runs: i64,
+    pub finished: i64,
+    pub failed: i64,
+    /// Runs with a recorded duration (the weight of `avg_duration_ms`).
+    #[serde(skip)]
+    pub timed: i64,
+    pub avg_duration_ms: Option<f64>,
+    #[sqlx(skip)]
+    pub failure_rate: Option<f64>,
+}
+
+fn failure_rate(failed: i64, finished: i64) -> Option<f64> {
+    (finished > 0).then(|| failed as f64 / finished as f64)
+}
+
+/// Per-group rates plus the overall totals.
+fn summarize(mut groups: Vec<RunGroup>) -> (RunGroup, Vec<RunGroup>) {
+    let mut total = RunGroup::default();
+    let mut duration_sum = 0.0;
+    for group in &mut groups {
+        group.failure_rate = failure_rate(group.failed, group.finished);
+        total.runs += group.runs;
+        total.finished += group.finished;
+        total.failed += group.failed;
+        total.timed += group.timed;
+        duration_sum += group.avg_duration_ms.unwrap_or(0.0) * group.timed as f64;
+    }
+    total.failure_rate = failure_rate(total.failed, total.finished);
+    total.avg_duration_ms = (total.timed > 0).then(|| duration_sum / total.timed as f64);
+    groups.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.key.cmp(&b.key)));
+    (total, groups)
+}
+
+fn response(
+    runs: impl Serialize,
+    groups: Vec<RunGroup>,
+    group_by: &str,
+    range: &str,
+    limit: i64,
+    offset: i64,
+) -> Json<Value> {
+    let (total, groups) = summarize(groups);
+    Json(json!({
+        "range": range,
+        "runs": runs,
+        "total": total.runs,
+        "limit": limit,
+        "offset": offset,
+        "summary": {
+            "runs": total.runs,
+            "finished": total.finished,
+            "failed": total.failed,
+            "failure_rate": total.failure_rate,
+            "avg_duration_ms": total.avg_duration_ms,
+            group_by: groups,
+        },
+    }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Swarm runs
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct SwarmRun {
+    pub id: String,
+    pub pattern: String,
+    pub source_peer: String,
+    pub target_peers: Value,
+    pub status: String,
+    pub results_count: i64,
+    pub success_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+    pub prompt_preview: String,
+}
+
+const SWARM_FILTER: &str = "created_at >= NOW() - make_interval(secs => $1) \
+     AND ($2::TEXT IS NULL OR status = $2) AND ($3::TEXT IS NULL OR pattern = $3)";
+
+/// `GET /api/swarm/runs`
+pub async fn swarm_runs(
+    State(state): State<AppState>,
+    Query(q): Query<RunsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let (range_secs, limit, offset) = page_of(&q)?;
+    let runs = sqlx::query_as::<_, SwarmRun>(&format!(
+        "SELECT id, pattern, source_peer, target_peers, status, \
+             jsonb_array_length(results)::BIGINT AS results_count, \
+             (SELECT COUNT(*) FROM jsonb_array_elements(results) r \
+              WHERE r->>'status' = 'success') AS success_count, \
+             created_at, completed_at, duration_ms, error, \
+             LEFT(prompt, $6) AS prompt_preview \
+         FROM ch_swarm_tasks WHERE {SWARM_FILTER} \
+         ORDER BY created_at DESC LIMIT $4 OFFSET $5"
+    ))
+    .bind(range_secs as f64)
+    .bind(q.status.as_deref())
+    .bind(q.pattern.as_deref())
+    .bind(limit)
+    .bind(offset)
+    .bind(PROMPT_PREVIEW_CHARS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("run_history"))?;
+    let groups = sqlx::query_as::<_, RunGroup>(&format!(
+        "SELECT pattern AS key, COUNT(*) AS runs, \
+             COUNT(*) FILTER (WHERE completed_at IS NOT NULL) AS finished, \
+             COUNT(*) FILTER (WHERE status = 'failed') AS failed, \
+             COUNT(duration_ms) AS timed, AVG(duration_ms)::FLOAT8 AS avg_duration_ms \
+         FROM ch_swarm_tasks WHERE {SWARM_FILTER} GROUP BY pattern"
+    ))
+    .bind(range_secs as f64)
+    .bind(q.status.as_deref())
+    .bind(q.pattern.as_deref())
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("run_history"))?;
+    let range = q.range.as_deref().unwrap_or("7d");
+    Ok(response(runs, groups, "patterns", range, limit, offset))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Background queue runs
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct QueueRun {
+    pub id: i64,
+    pub model: Option<String>,
+    pub priority: String,
+    pub status: String,
+    pub attempts: i32,
+    pub retries: i32,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Wait in the queue before the last start.
+    pub wait_ms: Option<i64>,
+    pub duration_ms: Option<i64>,
+    pub error: Option<String>,
+    pub prompt_preview: String,
+}
+
+/// Finished (done / failed / cancelled) prompts only.
+const QUEUE_FILTER: &str = "finished_at IS NOT NULL \
+     AND finished_at >= NOW() - make_interval(secs => $1) \
+     AND ($2::TEXT IS NULL OR status = $2) AND ($3::TEXT IS NULL OR model = $3)";
+
+const QUEUE_DURATION: &str = "(EXTRACT(EPOCH FROM (finished_at - started_at)) * 1000)::BIGINT";
+
+/// `GET /api/queue/history`
+pub async fn queue_history(
+    State(state): State<AppState>,
+    Query(q): Query<RunsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let (range_secs, limit, offset) = page_of(&q)?;
+    let runs = sqlx::query_as::<_, QueueRun>(&format!(
+        "SELECT id, model, priority, status, attempts, retries, \
+             created_at, started_at, finished_at, \
+             (EXTRACT(EPOCH FROM (started_at - created_at)) * 1000)::BIGINT AS wait_ms, \
+             {QUEUE_DURATION} AS duration_ms, error, \
+             LEFT(prompt, $6) AS prompt_preview \
+         FROM ch_background_prompts WHERE {QUEUE_FILTER} \
+         ORDER BY finished_at DESC LIMIT $4 OFFSET $5"
+    ))
+    .bind(range_secs as f64)
+    .bind(q.status.as_deref())
+    .bind(q.model.as_deref())
+    .bind(limit)
+    .bind(offset)
+    .bind(PROMPT_PREVIEW_CHARS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("run_history"))?;
+    let groups = sqlx::query_as::<_, RunGroup>(&format!(
+        "SELECT model AS key, COUNT(*) AS runs, \
+             COUNT(*) FILTER (WHERE status IN ('done', 'failed')) AS finished, \
+             COUNT(*) FILTER (WHERE status = 'failed') AS failed, \
+             COUNT(started_at) AS timed, AVG({QUEUE_DURATION})::FLOAT8 AS avg_duration_ms \
+         FROM ch_background_prompts WHERE {QUEUE_FILTER} GROUP BY model"
+    ))
+    .bind(range_secs as f64)
+    .bind(q.status.as_deref())
+    .bind(q.model.as_deref())
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("run_history"))?;
+    let range = q.range.as_deref().unwrap_or("7d");
+    Ok(response(runs, groups, "models", range, limit, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(key: &str, runs: i64, failed: i64, timed: i64, avg: Option<f64>) -> RunGroup {
+        RunGroup {
+            key: Some(key.to_string()),
+            runs,
+            finished: runs,
+            failed,
+            timed,
+            avg_duration_ms: avg,
+            failure_rate: None,
+        }
+    }
+
+    #[test]
+    fn summary_weights_averages_by_timed_runs() {
+        let (total, groups) = summarize(vec![
+            group("parallel", 2, 1, 2, Some(1_000.0)),
+            group("chain", 8, 0, 6, Some(3_000.0)),
+        ]);
+        assert_eq!((total.runs, total.finished, total.failed), (10, 10, 1));
+        assert_eq!(total.failure_rate, Some(0.1));
+        assert_eq!(total.avg_duration_ms, Some(2_500.0));
+        assert_eq!(groups[0].key.as_deref(), Some("chain"));
+        assert_eq!(groups[1].failure_rate, Some(0.5));
+    }
+
+    #[test]
+    fn empty_history_has_no_rates() {
+        let (total, groups) = summarize(Vec::new());
+        assert!(groups.is_empty());
+        assert_eq!(total.runs, 0);
+        assert_eq!(total.failure_rate, None);
+        assert_eq!(total.avg_duration_ms, None);
+    }
+}
@@ -0,0 +1,564 @@
+// ClaudeHydra v4 — Background job schedule
+//
+// Catalogue of the recurring background loops spawned in main.rs with their
+// intervals and next-run times, followed by the user's enabled scheduled
+// prompts (`kind: "prompt"`, see `scheduled_prompts.rs`). Loops that own
+// `AppState` call `JobSchedule::record_run` on each tick, so their next run
+// is exact; the others are projected from process start (`estimated`).
+// Loops whose cadence comes from configuration report it with
+// `JobSchedule::start` and are listed only while running. One-shot startup
+// tasks (warm start, Ollama warm-up) and the shared sandbox cleanup loop,
+// whose cadence the sandbox crate does not expose, are not listed.
+//
+// Endpoints:
+// - `GET /api/schedule`      — upcoming runs as JSON
+// - `GET /api/schedule.ics`  — iCalendar (RFC 5545) export for calendar apps:
+//   one recurring VEVENT per background job, one VEVENT per upcoming run of
+//   a scheduled prompt
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Query, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use crate::state::AppState;
+
+/// Static description of a recurring job.
+struct JobDef {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    /// Fixed cadence; `None` when the loop reports it via `JobSchedule::start`.
+    interval: Option<Duration>,
+    /// `true` when the loop reports its ticks via `record_run`.
+    tracked: bool,
+}
+
+pub const JOB_WATCHDOG: &str = "watchdog";
+
+const JOBS: &[JobDef] = &[
+    JobDef {
+        id: JOB_WATCHDOG,
+        name: "Health watchdog",
+        description: "Anthropic API health check and in-memory queue compaction",
+        interval: Some(Duration::from_secs(60)),
+        tracked: true,
+    },
+    JobDef {
+        id: "swarm_discovery",
+        name: "Swarm peer discovery",
+        description: "Probe swarm peers and emit discovery events",
+        interval: Some(Duration::from_secs(30)),
+        tracked: false,
+    },
+    JobDef {
+        id: "semantic_cache_ttl",
+        name: "Semantic cache TTL cleanup",
+        description: "Evict expired semantic cache entries",
+        interval: Some(Duration::from_secs(5 * 60)),
+        tracked: false,
+    },
+    JobDef {
+        id: "memory_pruning",
+        name: "Memory pruning",
+        description: "Cluster and prune knowledge-graph memories",
+        interval: Some(Duration::from_secs(60 * 60)),
+        tracked: false,
+    },
+    JobDef {
+        id: crate::idle_scavenger::JOB_IDLE_SCAVENGER,
+        name: "Idle scavenger",
+        description: "Run queued background prompts while the machine is idle",
+        interval: Some(crate::idle_scavenger::TICK_INTERVAL),
+        tracked: true,
+    },
+    JobDef {
+        id: crate::scheduled_prompts::JOB_SCHEDULED_PROMPTS,
+        name: "Scheduled prompts",
+        description: "Enqueue due scheduled and recurring prompts",
+        interval: Some(crate::scheduled_prompts::TICK_INTERVAL),
+        tracked: true,
+    },
+    JobDef {
+        id: crate::gc::JOB_GC,
+        name: "Garbage collection",
+        description: "Apply per-store retention policies (age, count, size)",
+        interval: Some(crate::gc::GC_INTERVAL),
+        tracked: true,
+    },
+    JobDef {
+        id: "system_monitor",
+        name: "System monitor",
+        description: "Refresh CPU and memory statistics",
+        interval: Some(Duration::from_secs(5)),
+        tracked: false,
+    },
+    JobDef {
+        id: crate::file_watcher::JOB_FILE_WATCHER,
+        name: "File watcher refresh",
+        description: "Re-read the pinned and affected files to watch for external changes",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::rag::JOB_RAG_REINDEX,
+        name: "RAG rescan",
+        description: "Reindex the RAG directory (only without a file watcher; \
+                      otherwise it is reindexed on change)",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::ollama_service::JOB_OLLAMA_WATCHDOG,
+        name: "Ollama watchdog",
+        description: "Ping Ollama, emit status changes and restart it when enabled",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::witcher_router::JOB_SIGNS_RELOAD,
+        name: "Witcher signs reload",
+        description: "Reload custom Witcher signs from the sign file",
+        interval: Some(crate::witcher_router::SIGNS_RELOAD_INTERVAL),
+        tracked: true,
+    },
+    JobDef {
+        id: "config_reload",
+        name: "Config reload",
+        description: "Apply changes to the config file (hydra.toml)",
+        interval: Some(crate::hydra_config::RELOAD_INTERVAL),
+        tracked: false,
+    },
+    JobDef {
+        id: crate::profiles::JOB_OFFLINE_PROBE,
+        name: "Connectivity probe",
+        description: "Probe the provider endpoint and switch to the offline profile when unreachable",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::rule_updates::JOB_RULE_UPDATES,
+        name: "Routing rule updates",
+        description: "Check the signed routing rules manifest for updates",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::metrics_snapshot::JOB_METRICS_SNAPSHOT,
+        name: "Metrics snapshot",
+        description: "Write the metrics snapshot file for dashboards",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: crate::metrics_history::JOB_METRICS_HISTORY,
+        name: "Metrics history",
+        description: "Sample metrics for the dashboard charts",
+        interval: None,
+        tracked: true,
+    },
+    JobDef {
+        id: "claude_cli_reaper",
+        name: "Claude CLI reaper",
+        description: "Stop idle persistent Claude CLI processes",
+        interval: Some(crate::claude_cli::REAPER_INTERVAL),
+        tracked: false,
+    },
+];
+
+/// Default number of upcoming runs listed per job.
+const DEFAULT_UPCOMING: usize = 5;
+const MAX_UPCOMING: usize = 50;
+/// Upcoming runs of each scheduled prompt exported as calendar events.
+const ICS_PROMPT_RUNS: usize = 10;
+/// Scheduled prompts are left out when the database does not answer in time.
+const PROMPTS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Last-run bookkeeping shared through `AppState`.
+pub struct JobSchedule {
+    started_at: DateTime<Utc>,
+    last_runs: std::sync::RwLock<HashMap<&'static str, DateTime<Utc>>>,
+    intervals: std::sync::RwLock<HashMap<&'static str, Duration>>,
+}
+
+impl Default for JobSchedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobSchedule {
+    pub fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            last_runs: std::sync::RwLock::new(HashMap::new()),
+            intervals: std::sync::RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record that a loop with a configured cadence started, and its interval.
+    pub fn start(&self, job_id: &'static str, interval: Duration) {
+        if let Ok(mut intervals) = self.intervals.write() {
+            intervals.insert(job_id, interval);
+        }
+    }
+
+    /// Record a completed tick of a tracked job.
+    pub fn record_run(&self, job_id: &'static str) {
+        if let Ok(mut runs) = self.last_runs.write() {
+            runs.insert(job_id, Utc::now());
+        }
+    }
+
+    /// Background jobs with up to `upcoming` future run times each.
+    fn snapshot_at(&self, upcoming: usize, now: DateTime<Utc>) -> Vec<ScheduledJob> {
+        let runs = self.last_runs.read().map(|r| r.clone()).unwrap_or_default();
+        let started = self.intervals.read().map(|i| i.clone()).unwrap_or_default();
+
+        JOBS.iter()
+            .filter_map(|job| {
+                let every = job.interval.or_else(|| started.get(job.id).copied())?;
+                let interval = chrono::Duration::from_std(every).unwrap_or_default();
+                let last_run = runs.get(job.id).copied();
+                let next = next_run(last_run.unwrap_or(self.started_at), interval, now);
+                let next_runs = (0..upcoming)
+                    .map(|i| (next + interval * i as i32).to_rfc3339())
+                    .collect();
+
+                Some(ScheduledJob {
+                    id: job.id.to_string(),
+                    name: job.name.to_string(),
+                    description: job.description.to_string(),
+                    kind: "maintenance".to_string(),
+                    interval_secs: Some(every.as_secs()),
+                    estimated: !job.tracked || last_run.is_none(),
+                    last_run: last_run.map(|t| t.to_rfc3339()),
+                    next_run: next.to_rfc3339(),
+                    next_runs,
+                    prompt_id: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A scheduled prompt as a job of kind `prompt`.
+fn prompt_job(schedule: crate::scheduled_prompts::UpcomingRuns) -> ScheduledJob {
+    let description = match &schedule.cron {
+        Some(cron) => format!("Scheduled prompt (cron `{}`)", cron),
+        None => "One-time scheduled prompt".to_string(),
+    };
+    let next_runs: Vec<String> = schedule.runs.iter().map(|t| t.to_rfc3339()).collect();
+    ScheduledJob {
+        id: format!("prompt-{}", schedule.id),
+        name: schedule.name,
+        description,
+        kind: "prompt".to_string(),
+        interval_secs: None,
+        estimated: false,
+        last_run: schedule.last_run_at.map(|t| t.to_rfc3339()),
+        next_run: next_runs[0].clone(),
+        next_runs,
+        prompt_id: Some(schedule.id),
+    }
+}
+
+/// Background jobs followed by the enabled scheduled prompts, each prompt
+/// with up to `prompt_runs` upcoming runs. When the database is unavailable
+/// only the background jobs are listed.
+async fn schedule(state: &AppState, upcoming: usize, prompt_runs: usize) -> Vec<ScheduledJob> {
+    let now = Utc::now();
+    let mut jobs = state.job_schedule.snapshot_at(upcoming, now);
+    let prompts = tokio::time::timeout(
+        PROMPTS_TIMEOUT,
+        crate::scheduled_prompts::next_runs(&state.db, now, prompt_runs),
+    )
+    .await;
+    match prompts {
+        Ok(Ok(schedules)) => jobs.extend(schedules.into_iter().map(prompt_job)),
+        Ok(Err(e)) => tracing::warn!("schedule: scheduled prompts unavailable: {}", e),
+        Err(_) => tracing::warn!("schedule: scheduled prompts timed out"),
+    }
+    jobs
+}
+
+/// First run after `anchor` on the job's cadence. A run that should already
+/// have happened (missed ticks, untracked loops) is projected forward to the
+/// first slot that is not in the past.
+fn next_run(
+    anchor: DateTime<Utc>,
+    interval: chrono::Duration,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let next = anchor + interval;
+    if next >= now || interval <= chrono::Duration::zero() {
+        return next;
+    }
+    let behind = (now - anchor).num_seconds() / interval.num_seconds().max(1);
+    anchor + interval * (behind as i32 + 1)
+}
+
+/// One recurring job with its upcoming run times.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScheduledJob {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// `maintenance` (background loop) or `prompt` (scheduled prompt).
+    pub kind: String,
+    /// `None` for scheduled prompts (cron or one-time).
+    pub interval_secs: Option<u64>,
+    /// `true` when next-run times are projected rather than observed.
+    pub estimated: bool,
+    pub last_run: Option<String>,
+    pub next_run: String,
+    pub next_runs: Vec<String>,
+    /// Schedule id of a `prompt` job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleQuery {
+    pub upcoming: Option<usize>,
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/schedule
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn get_schedule(
+    State(state): State<AppState>,
+    Query(q): Query<ScheduleQuery>,
+) -> Json<Value> {
+    let upcoming = q.upcoming.unwrap_or(DEFAULT_UPCOMING).clamp(1, MAX_UPCOMING);
+    let jobs = schedule(&state, upcoming, upcoming).await;
+    Json(json!({
+        "generated_at": Utc::now().to_rfc3339(),
+        "jobs": jobs,
+    }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/schedule.ics
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn export_schedule_ics(State(state): State<AppState>) -> impl IntoResponse {
+    let ics = render_ics(&schedule(&state, 1, ICS_PROMPT_RUNS).await, Utc::now());
+    (
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"claudehydra-schedule.ics\"",
+            ),
+        ],
+        ics,
+    )
+}
+
+/// Render jobs as an iCalendar document (CRLF line endings per RFC 5545):
+/// background jobs recur by RRULE, scheduled prompts list each upcoming run.
+fn render_ics(jobs: &[ScheduledJob], now: DateTime<Utc>) -> String {
+    let stamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//ClaudeHydra//Schedule//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:ClaudeHydra schedule".to_string(),
+    ];
+
+    for job in jobs {
+        let occurrences: Vec<(String, &String)> = match job.interval_secs {
+            Some(_) => vec![(job.id.clone(), &job.next_run)],
+            None => job
+                .next_runs
+                .iter()
+                .enumerate()
+                .map(|(i, run)| (format!("{}-{}", job.id, i), run))
+                .collect(),
+        };
+        for (uid, run) in occurrences {
+            let Ok(start) = DateTime::parse_from_rfc3339(run) else {
+                continue;
+            };
+            let start = start
+                .with_timezone(&Utc)
+                .format("%Y%m%dT%H%M%SZ")
+                .to_string();
+            lines.push("BEGIN:VEVENT".to_string());
+            lines.push(format!("UID:{}@claudehydra", uid));
+            lines.push(format!("DTSTAMP:{}", stamp));
+            lines.push(format!("DTSTART:{}", start));
+            lines.push("DURATION:PT1M".to_string());
+            if let Some(secs) = job.interval_secs {
+                lines.push(rrule(secs));
+            }
+            lines.push(format!("SUMMARY:{}", ics_escape(&job.name)));
+            lines.push(format!("DESCRIPTION:{}", ics_escape(&job.description)));
+            lines.push(format!("CATEGORIES:{}", job.kind.to_uppercase()));
+            lines.push("TRANSP:TRANSPARENT".to_string());
+            lines.push("END:VEVENT".to_string());
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+    let mut out = lines.join("\r\n");
+    out.push_str("\r\n");
+    out
+}
+
+/// Recurrence rule for a fixed interval, using the coarsest exact unit.
+fn rrule(interval_secs: u64) -> String {
+    match interval_secs {
+        s if s >= 86_400 && s % 86_400 == 0 => format!("RRULE:FREQ=DAILY;INTERVAL={}", s / 86_400),
+        s if s >= 3_600 && s % 3_600 == 0 => format!("RRULE:FREQ=HOURLY;INTERVAL={}", s / 3_600),
+        s if s >= 60 && s % 60 == 0 => format!("RRULE:FREQ=MINUTELY;INTERVAL={}", s / 60),
+        s => format!("RRULE:FREQ=SECONDLY;INTERVAL={}", s.max(1)),
+    }
+}
+
+fn ics_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn fixed_jobs() -> usize {
+        JOBS.iter().filter(|j| j.interval.is_some()).count()
+    }
+
+    #[test]
+    fn next_run_follows_the_last_run() {
+        let minute = chrono::Duration::seconds(60);
+        let anchor = at("2026-01-01T10:00:00Z");
+        assert_eq!(
+            next_run(anchor, minute, at("2026-01-01T10:00:20Z")),
+            at("2026-01-01T10:01:00Z")
+        );
+        // Due right now is still the next run.
+        assert_eq!(
+            next_run(anchor, minute, at("2026-01-01T10:01:00Z")),
+            at("2026-01-01T10:01:00Z")
+        );
+    }
+
+    #[test]
+    fn missed_runs_catch_up_to_the_next_slot() {
+        let minute = chrono::Duration::seconds(60);
+        let anchor = at("2026-01-01T10:00:00Z");
+        assert_eq!(
+            next_run(anchor, minute, at("2026-01-01T10:05:30Z")),
+            at("2026-01-01T10:06:00Z")
+        );
+        // Exactly on a slot: the following one, never a time in the past.
+        assert_eq!(
+            next_run(anchor, minute, at("2026-01-01T10:05:00Z")),
+            at("2026-01-01T10:06:00Z")
+        );
+        let hour = chrono::Duration::seconds(3_600);
+        assert_eq!(
+            next_run(anchor, hour, at("2026-01-03T07:59:59Z")),
+            at("2026-01-03T08:00:00Z")
+        );
+    }
+
+    #[test]
+    fn snapshot_projects_untracked_jobs_from_start() {
+        let schedule = JobSchedule::new();
+        let started = schedule.started_at;
+        schedule.record_run(JOB_WATCHDOG);
+        let now = started + chrono::Duration::seconds(95);
+        let jobs = schedule.snapshot_at(3, now);
+        assert_eq!(jobs.len(), fixed_jobs());
+
+        let watchdog = jobs.iter().find(|j| j.id == JOB_WATCHDOG).unwrap();
+        assert!(!watchdog.estimated);
+        assert_eq!(watchdog.next_runs.len(), 3);
+        let next = at(&watchdog.next_run);
+        assert!(next > now);
+        assert_eq!(at(&watchdog.next_runs[1]) - next, chrono::Duration::seconds(60));
+
+        // Every 30 s from start: 30, 60, 90, then 120 s is the next one.
+        let swarm = jobs.iter().find(|j| j.id == "swarm_discovery").unwrap();
+        assert!(swarm.estimated && swarm.last_run.is_none());
+        assert_eq!(at(&swarm.next_run), started + chrono::Duration::seconds(120));
+    }
+
+    #[test]
+    fn configured_jobs_are_listed_once_started() {
+        let schedule = JobSchedule::new();
+        let now = schedule.started_at;
+        let history = crate::metrics_history::JOB_METRICS_HISTORY;
+        assert!(!schedule.snapshot_at(1, now).iter().any(|j| j.id == history));
+
+        schedule.start(history, Duration::from_secs(120));
+        let jobs = schedule.snapshot_at(1, now);
+        assert_eq!(jobs.len(), fixed_jobs() + 1);
+        let job = jobs.iter().find(|j| j.id == history).unwrap();
+        assert_eq!(job.interval_secs, Some(120));
+    }
+
+    #[test]
+    fn scheduled_prompts_are_listed_run_by_run() {
+        let runs = vec![at("2026-01-01T18:00:00Z"), at("2026-01-02T18:00:00Z")];
+        let job = prompt_job(crate::scheduled_prompts::UpcomingRuns {
+            id: 7,
+            name: "Summarize today's git log".to_string(),
+            cron: Some("0 18 * * *".to_string()),
+            last_run_at: None,
+            runs,
+        });
+        assert_eq!(job.kind, "prompt");
+        assert_eq!(job.prompt_id, Some(7));
+        assert_eq!(job.interval_secs, None);
+        assert_eq!(at(&job.next_run), at("2026-01-01T18:00:00Z"));
+
+        let json = serde_json::to_value(&job).unwrap();
+        assert_eq!(json["id"], "prompt-7");
+        assert_eq!(json["prompt_id"], 7);
+
+        let ics = render_ics(&[job], at("2026-01-01T12:00:00Z"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains(
+            "UID:prompt-7-0@claudehydra\r\nDTSTAMP:20260101T120000Z\r\nDTSTART:20260101T180000Z\r\n"
+        ));
+        assert!(ics.contains("UID:prompt-7-1@claudehydra\r\n"));
+        assert!(ics.contains("SUMMARY:Summarize today's git log\r\n"));
+        assert!(!ics.contains("RRULE"));
+    }
+
+    #[test]
+    fn ics_uses_the_coarsest_exact_recurrence() {
+        assert_eq!(rrule(86_400 * 2), "RRULE:FREQ=DAILY;INTERVAL=2");
+        assert_eq!(rrule(3_600), "RRULE:FREQ=HOURLY;INTERVAL=1");
+        assert_eq!(rrule(5_400), "RRULE:FREQ=MINUTELY;INTERVAL=90");
+        assert_eq!(rrule(30), "RRULE:FREQ=SECONDLY;INTERVAL=30");
+        assert_eq!(ics_escape("a, b; c\\d\n"), "a\\, b\\; c\\\\d\\n");
+
+        let schedule = JobSchedule::new();
+        let now = schedule.started_at;
+        let ics = render_ics(&schedule.snapshot_at(1, now), now);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), fixed_jobs());
+        assert!(ics.contains("UID:watchdog@claudehydra\r\n"));
+    }
+}
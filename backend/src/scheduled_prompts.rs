@@ -0,0 +1,666 @@
+//! Scheduled and recurring prompts — e.g. a nightly "summarize today's git
+//! log" — queued as background prompts at the appointed time.
+//!
+//! A schedule's `when` is one of:
+//! - a delay: `30m`, `in 2h`, `1d` (units `m`, `h`, `d`) — runs once;
+//! - an RFC 3339 timestamp — runs once;
+//! - a 5-field cron expression (`minute hour day-of-month month day-of-week`,
+//!   local time, with `*`, lists, ranges and `/step`) — recurs.
+//!
+//! Schedules live in `ch_scheduled_prompts`. A loop checks them every 30
+//! seconds and enqueues due runs into `ch_background_prompts` with `normal`
+//! priority (see `queue/`); runs bound to a session store the prompt
+//! and reply in it. Runs missed while the backend was down follow the
+//! schedule's catch-up policy:
+//! - `skip` — drop missed runs, only fire when on time;
+//! - `once` (default) — fire a single run for everything that was missed;
+//! - `all` — fire every missed run (at most 10).
+//!
+//! Runs asking for a system operation wait for approval outside YOLO mode
+//! (see `approvals.rs`).
+//!
+//! - `GET    /api/scheduled-prompts`       — list schedules
+//! - `POST   /api/scheduled-prompts`       — `{ prompt, when, session_id?, model?, catch_up? }`
+//! - `DELETE /api/scheduled-prompts/{id}`  — cancel a schedule
+
+use std::time::Duration;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error};
+use crate::state::AppState;
+
+pub const JOB_SCHEDULED_PROMPTS: &str = "scheduled_prompts";
+pub const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+const MAX_PROMPT_CHARS: usize = 100_000;
+/// Runs due within this window count as on time (not "missed").
+const ON_TIME_GRACE_SECS: i64 = 120;
+const MAX_CATCH_UP_RUNS: usize = 10;
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Cron expressions
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Parsed 5-field cron expression; each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (
+                r,
+                s.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step in '{}'", part))?,
+            ),
+            None => (part, 1),
+        };
+        let num = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("'{}' is out of range {}-{}", s, min, max))
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            r => match r.split_once('-') {
+                Some((a, b)) => (num(a)?, num(b)?),
+                // `5/15` means "from 5, every 15".
+                None if step > 1 => (num(r)?, max),
+                None => (num(r)?, num(r)?),
+            },
+        };
+        if lo > hi {
+            return Err(format!("empty range '{}'", range));
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [min, hour, dom, month, dow] = fields[..] else {
+            return Err("cron expression needs 5 fields".to_string());
+        };
+        // Day-of-week accepts 0-7 with 7 meaning Sunday.
+        let dow_mask = parse_field(dow, 0, 7)?;
+        Ok(Self {
+            minutes: parse_field(min, 0, 59)?,
+            hours: parse_field(hour, 0, 23)? as u32,
+            days_of_month: parse_field(dom, 1, 31)? as u32,
+            months: parse_field(month, 1, 12)? as u16,
+            days_of_week: ((dow_mask | (dow_mask >> 7)) & 0x7f) as u8,
+            dom_restricted: dom != "*",
+            dow_restricted: dow != "*",
+        })
+    }
+
+    fn day_matches(&self, t: &NaiveDateTime) -> bool {
+        let dom = self.days_of_month & (1 << t.day()) != 0;
+        let dow = self.days_of_week & (1 << t.weekday().num_days_from_sunday()) != 0;
+        // Standard cron: when both are restricted, either one may match.
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            _ => dom && dow,
+        }
+    }
+
+    /// First matching minute strictly after `after` (naive local time).
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut t = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        // Jumps skip whole months/days/hours, so this bound covers 4+ years.
+        for _ in 0..100_000 {
+            if self.months & (1 << t.month()) == 0 {
+                let (y, m) = if t.month() == 12 {
+                    (t.year() + 1, 1)
+                } else {
+                    (t.year(), t.month() + 1)
+                };
+                t = chrono::NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(&t) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)? + chrono::Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += chrono::Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+
+    /// Next run after `after`, in local time (skipping DST gaps).
+    fn next_run(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut naive = after.with_timezone(&Local).naive_local();
+        for _ in 0..8 {
+            naive = self.next_after(naive)?;
+            if let Some(t) = Local.from_local_datetime(&naive).earliest() {
+                return Some(t.with_timezone(&Utc));
+            }
+        }
+        None
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Schedule specs and catch-up policy
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, PartialEq)]
+enum When {
+    Once(DateTime<Utc>),
+    Cron(CronExpr),
+}
+
+fn parse_delay(raw: &str) -> Option<chrono::Duration> {
+    let s = raw.trim().strip_prefix("in ").unwrap_or(raw.trim()).trim();
+    let unit = s.chars().last()?;
+    let n: i64 = s[..s.len() - unit.len_utf8()].trim().parse().ok()?;
+    // Up to ~10 years in minutes; keeps chrono arithmetic in range.
+    if n <= 0 || n > 5_000_000 {
+        return None;
+    }
+    match unit {
+        'm' => Some(chrono::Duration::minutes(n)),
+        'h' => Some(chrono::Duration::hours(n)),
+        'd' => Some(chrono::Duration::days(n)),
+        _ => None,
+    }
+}
+
+fn parse_when(raw: &str, now: DateTime<Utc>) -> Result<When, String> {
+    if let Some(delay) = parse_delay(raw) {
+        return Ok(When::Once(now + delay));
+    }
+    if let Ok(at) = DateTime::parse_from_rfc3339(raw.trim()) {
+        return Ok(When::Once(at.with_timezone(&Utc)));
+    }
+    CronExpr::parse(raw).map(When::Cron).map_err(|e| {
+        format!(
+            "'{}' is not a delay, timestamp or cron expression: {}",
+            raw, e
+        )
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUp {
+    Skip,
+    Once,
+    All,
+}
+
+impl CatchUp {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::Once => "once",
+            Self::All => "all",
+        }
+    }
+
+    fn parse(raw: &str) -> Self {
+        match raw {
+            "skip" => Self::Skip,
+            "all" => Self::All,
+            _ => Self::Once,
+        }
+    }
+}
+
+/// Number of runs to enqueue for the due occurrences `due` (oldest first).
+fn runs_to_enqueue(policy: CatchUp, due: &[DateTime<Utc>], now: DateTime<Utc>) -> usize {
+    let Some(latest) = due.last() else {
+        return 0;
+    };
+    match policy {
+        CatchUp::Skip => usize::from((now - *latest).num_seconds() <= ON_TIME_GRACE_SECS),
+        CatchUp::Once => 1,
+        CatchUp::All => due.len().min(MAX_CATCH_UP_RUNS),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Scheduler loop
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Column list for `ScheduledPrompt` (session id rendered as text).
+const COLUMNS: &str = "id, session_id::TEXT AS session_id, prompt, model, cron, catch_up, \
+     next_run_at, last_run_at, run_count, enabled, created_at";
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct ScheduledPrompt {
+    pub id: i64,
+    pub session_id: Option<String>,
+    pub prompt: String,
+    pub model: Option<String>,
+    pub cron: Option<String>,
+    pub catch_up: String,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub run_count: i32,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Spawn the scheduler loop.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+            state.job_schedule.record_run(JOB_SCHEDULED_PROMPTS);
+            if crate::shutdown::is_draining() {
+                continue;
+            }
+            if let Err(e) = fire_due(&state).await {
+                tracing::warn!("scheduled_prompts: {}", e);
+            }
+        }
+    });
+}
+
+async fn fire_due(state: &AppState) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    let due = sqlx::query_as::<_, ScheduledPrompt>(&format!(
+        "SELECT {} FROM ch_scheduled_prompts \
+         WHERE enabled AND next_run_at <= $1 ORDER BY next_run_at ASC",
+        COLUMNS
+    ))
+    .bind(now)
+    .fetch_all(&state.db)
+    .await?;
+
+    let mut enqueued = 0;
+    for schedule in due {
+        let Some(first) = schedule.next_run_at else {
+            continue;
+        };
+        let cron = schedule
+            .cron
+            .as_deref()
+            .and_then(|c| CronExpr::parse(c).ok());
+        // Every occurrence between the stored next run and now.
+        let mut occurrences = vec![first];
+        let mut next = None;
+        if let Some(cron) = &cron {
+            let mut t = first;
+            loop {
+                match cron.next_run(t) {
+                    Some(n) if n <= now && occurrences.len() < 1_000 => {
+                        occurrences.push(n);
+                        t = n;
+                    }
+                    Some(n) if n <= now => t = n,
+                    other => {
+                        next = other;
+                        break;
+                    }
+                }
+            }
+        }
+        let runs = runs_to_enqueue(CatchUp::parse(&schedule.catch_up), &occurrences, now);
+
+        let mode = match schedule.session_id.as_deref().and_then(|s| s.parse().ok()) {
+            Some(session_id) => crate::permissions::session_mode(&state.db, &session_id).await,
+            None => crate::permissions::default_mode(),
+        };
+        let mut held = Vec::new();
+        let mut tx = state.db.begin().await?;
+        for _ in 0..runs {
+            let prompt_id: i64 = sqlx::query_scalar(
+                "INSERT INTO ch_background_prompts \
+                    (prompt, model, priority, session_id, scheduled_prompt_id) \
+                 VALUES ($1, $2, 'normal', $3::UUID, $4) RETURNING id",
+            )
+            .bind(&schedule.prompt)
+            .bind(&schedule.model)
+            .bind(&schedule.session_id)
+            .bind(schedule.id)
+            .fetch_one(&mut *tx)
+            .await?;
+            held.extend(
+                crate::approvals::hold_prompt(&mut tx, mode, prompt_id, &schedule.prompt).await?,
+            );
+        }
+        sqlx::query(
+            "UPDATE ch_scheduled_prompts SET next_run_at = $2, enabled = $3, \
+                 run_count = run_count + $4, \
+                 last_run_at = CASE WHEN $4 > 0 THEN $5 ELSE last_run_at END \
+             WHERE id = $1",
+        )
+        .bind(schedule.id)
+        .bind(next)
+        .bind(next.is_some())
+        .bind(runs as i32)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        held.iter().for_each(crate::approvals::announce);
+
+        if runs == 0 {
+            tracing::info!(
+                "scheduled_prompts: #{} skipped {} missed run(s)",
+                schedule.id,
+                occurrences.len()
+            );
+        }
+        enqueued += runs;
+    }
+    if enqueued > 0 {
+        tracing::info!("scheduled_prompts: enqueued {} run(s)", enqueued);
+        crate::queue::wake();
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Upcoming runs (listed by `/api/schedule`)
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Longest schedule name, in characters.
+const NAME_CHARS: usize = 60;
+
+/// Upcoming runs of one enabled schedule.
+#[derive(Debug, Clone)]
+pub struct UpcomingRuns {
+    pub id: i64,
+    /// First line of the prompt — schedules have no name of their own.
+    pub name: String,
+    pub cron: Option<String>,
+    pub last_run_at: Option<DateTime<Utc>>,
+    /// Oldest first; never empty.
+    pub runs: Vec<DateTime<Utc>>,
+}
+
+fn schedule_name(prompt: &str) -> String {
+    let line = prompt
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("");
+    if line.chars().count() <= NAME_CHARS {
+        return line.to_string();
+    }
+    let mut name: String = line.chars().take(NAME_CHARS - 1).collect();
+    name.push('…');
+    name
+}
+
+/// Up to `count` runs after `now`: the cron occurrences of a recurring
+/// schedule, or the once-time of a one-shot (listed even when it is due and
+/// waits for the next tick).
+fn upcoming(
+    cron: Option<&CronExpr>,
+    once: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    count: usize,
+) -> Vec<DateTime<Utc>> {
+    match cron {
+        Some(cron) => std::iter::successors(cron.next_run(now), |t| cron.next_run(*t))
+            .take(count)
+            .collect(),
+        None => once.into_iter().take(count).collect(),
+    }
+}
+
+/// Next `count` runs of every enabled schedule, soonest schedule first.
+pub async fn next_runs(
+    db: &sqlx::PgPool,
+    now: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<UpcomingRuns>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ScheduledPrompt>(&format!(
+        "SELECT {} FROM ch_scheduled_prompts WHERE enabled ORDER BY next_run_at ASC, id ASC",
+        COLUMNS
+    ))
+    .fetch_all(db)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let cron = row.cron.as_deref().and_then(|c| CronExpr::parse(c).ok());
+            let runs = upcoming(cron.as_ref(), row.next_run_at, now, count);
+            (!runs.is_empty()).then(|| UpcomingRuns {
+                id: row.id,
+                name: schedule_name(&row.prompt),
+                cron: row.cron,
+                last_run_at: row.last_run_at,
+                runs,
+            })
+        })
+        .collect())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRequest {
+    pub prompt: String,
+    /// Delay (`30m`), RFC 3339 timestamp, or 5-field cron expression.
+    pub when: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub catch_up: Option<CatchUp>,
+}
+
+/// `POST /api/scheduled-prompts`
+pub async fn schedule_prompt(
+    State(state): State<AppState>,
+    Json(req): Json<ScheduleRequest>,
+) -> Result<(StatusCode, Json<ScheduledPrompt>), (StatusCode, Json<Value>)> {
+    if req.prompt.trim().is_empty() {
+        return Err(bad_request("prompt must not be empty"));
+    }
+    if req.prompt.chars().count() > MAX_PROMPT_CHARS {
+        return Err(bad_request("prompt is too long"));
+    }
+    let session_id: Option<uuid::Uuid> = req
+        .session_id
+        .as_deref()
+        .map(|s| s.parse().map_err(|_| bad_request("Invalid session id")))
+        .transpose()?;
+    let now = Utc::now();
+    let (cron, next_run_at) = match parse_when(&req.when, now).map_err(|e| bad_request(&e))? {
+        When::Once(at) => (None, at),
+        When::Cron(expr) => (
+            Some(req.when.split_whitespace().collect::<Vec<_>>().join(" ")),
+            expr.next_run(now)
+                .ok_or_else(|| bad_request("cron expression never fires"))?,
+        ),
+    };
+    let row = sqlx::query_as::<_, ScheduledPrompt>(&format!(
+        "INSERT INTO ch_scheduled_prompts (session_id, prompt, model, cron, catch_up, next_run_at) \
+         VALUES ($1, $2, $3, $4, $5, $6) RETURNING {}",
+        COLUMNS
+    ))
+    .bind(session_id)
+    .bind(&req.prompt)
+    .bind(&req.model)
+    .bind(&cron)
+    .bind(req.catch_up.unwrap_or(CatchUp::Once).as_str())
+    .bind(next_run_at)
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| match &e {
+        sqlx::Error::Database(db) if db.is_foreign_key_violation() => {
+            bad_request("session does not exist")
+        }
+        _ => db_error("scheduled_prompts")(e),
+    })?;
+    Ok((StatusCode::CREATED, Json(row)))
+}
+
+/// `GET /api/scheduled-prompts`
+pub async fn list_scheduled(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let rows = sqlx::query_as::<_, ScheduledPrompt>(&format!(
+        "SELECT {} FROM ch_scheduled_prompts \
+         ORDER BY enabled DESC, next_run_at ASC NULLS LAST, created_at DESC",
+        COLUMNS
+    ))
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("scheduled_prompts"))?;
+    Ok(Json(json!({ "schedules": rows })))
+}
+
+/// `DELETE /api/scheduled-prompts/{id}` — runs already queued are kept.
+pub async fn cancel_scheduled(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    let result = sqlx::query("DELETE FROM ch_scheduled_prompts WHERE id = $1")
+        .bind(id)
+        .execute(&state.db)
+        .await
+        .map_err(db_error("scheduled_prompts"))?;
+    if result.rows_affected() == 0 {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No schedule with this id" })),
+        ));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn cron_fields_support_lists_ranges_and_steps() {
+        let cron = CronExpr::parse("*/15 9-17 * * 1-5").unwrap();
+        // Friday 17:50 → Monday 09:00
+        assert_eq!(
+            cron.next_after(at("2026-03-13 17:50")),
+            Some(at("2026-03-16 09:00"))
+        );
+        assert_eq!(
+            cron.next_after(at("2026-03-16 09:00")),
+            Some(at("2026-03-16 09:15"))
+        );
+        assert!(CronExpr::parse("* * *").is_err());
+        assert!(CronExpr::parse("61 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+    }
+
+    #[test]
+    fn nightly_and_monthly_schedules() {
+        let nightly = CronExpr::parse("30 23 * * *").unwrap();
+        assert_eq!(
+            nightly.next_after(at("2026-12-31 23:30")),
+            Some(at("2027-01-01 23:30"))
+        );
+        let monthly = CronExpr::parse("0 0 31 * *").unwrap();
+        assert_eq!(
+            monthly.next_after(at("2026-04-01 00:00")),
+            Some(at("2026-05-31 00:00"))
+        );
+        // Sunday as 7, restricted together with day-of-month (either matches).
+        let either = CronExpr::parse("0 12 1 * 7").unwrap();
+        assert_eq!(
+            either.next_after(at("2026-03-02 00:00")),
+            Some(at("2026-03-08 12:00"))
+        );
+    }
+
+    #[test]
+    fn when_accepts_delays_timestamps_and_cron() {
+        let now = Utc::now();
+        assert_eq!(
+            parse_when("in 30m", now),
+            Ok(When::Once(now + chrono::Duration::minutes(30)))
+        );
+        assert_eq!(
+            parse_when("2d", now),
+            Ok(When::Once(now + chrono::Duration::days(2)))
+        );
+        assert_eq!(
+            parse_when("2026-03-20T08:00:00Z", now),
+            Ok(When::Once(
+                Utc.with_ymd_and_hms(2026, 3, 20, 8, 0, 0).unwrap()
+            ))
+        );
+        assert!(matches!(parse_when("0 2 * * *", now), Ok(When::Cron(_))));
+        assert!(parse_when("tomorrow-ish", now).is_err());
+        assert!(parse_delay("0m").is_none());
+    }
+
+    #[test]
+    fn catch_up_policy_decides_missed_runs() {
+        let now = Utc::now();
+        let on_time = [now - chrono::Duration::seconds(20)];
+        let missed: Vec<_> = (1..=14)
+            .rev()
+            .map(|d| now - chrono::Duration::days(d))
+            .collect();
+        assert_eq!(runs_to_enqueue(CatchUp::Skip, &on_time, now), 1);
+        assert_eq!(runs_to_enqueue(CatchUp::Skip, &missed, now), 0);
+        assert_eq!(runs_to_enqueue(CatchUp::Once, &missed, now), 1);
+        assert_eq!(
+            runs_to_enqueue(CatchUp::All, &missed, now),
+            MAX_CATCH_UP_RUNS
+        );
+        assert_eq!(runs_to_enqueue(CatchUp::All, &[], now), 0);
+    }
+
+    #[test]
+    fn upcoming_runs_follow_cron_or_once_time() {
+        let now = Utc.with_ymd_and_hms(2026, 3, 13, 10, 7, 0).unwrap();
+        let quarter = CronExpr::parse("*/15 * * * *").unwrap();
+        let runs = upcoming(Some(&quarter), None, now, 3);
+        assert_eq!(runs.len(), 3);
+        assert!(runs[0] > now && runs[0] <= now + chrono::Duration::minutes(15));
+        assert_eq!(runs[1] - runs[0], chrono::Duration::minutes(15));
+        assert_eq!(runs[2] - runs[1], chrono::Duration::minutes(15));
+
+        // A one-shot lists its time once, even when already due.
+        let due = now - chrono::Duration::seconds(10);
+        assert_eq!(upcoming(None, Some(due), now, 3), vec![due]);
+        assert!(upcoming(None, None, now, 3).is_empty());
+
+        assert_eq!(
+            schedule_name("\n  Summarize today's git log\nthen post it"),
+            "Summarize today's git log"
+        );
+        let long = schedule_name(&"x".repeat(200));
+        assert_eq!(long.chars().count(), NAME_CHARS);
+        assert!(long.ends_with('…'));
+    }
+}
@@ -0,0 +1,404 @@
+// ClaudeHydra v4 — Provider API keys in the OS credential store
+//
+// Stores per-provider API keys in Windows Credential Manager / macOS
+// Keychain / Secret Service (cargo feature `keychain`, via the `keyring`
+// crate) instead of passing them from the frontend or keeping them in `.env`.
+// Keys are read into an in-memory cache at startup (`preload`); lookups are
+// cheap and never block on the OS store — a cache miss loads the key on the
+// blocking pool and serves it from the next lookup on.
+//
+// Resolution order for a provider key, everywhere: keychain → env var →
+// Vault (callers fall back to Vault themselves). At startup, keychain keys
+// for `anthropic` / `google` replace env keys in `runtime.api_keys`.
+//
+// Endpoints:
+// - `GET    /api/secrets/providers`             — masked status per provider
+// - `PUT    /api/secrets/providers/{provider}`  — store a key `{ "key": "..." }`
+// - `DELETE /api/secrets/providers/{provider}`  — remove a stored key
+
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::state::AppState;
+
+/// Service name under which entries are stored in the OS credential store.
+#[cfg(feature = "keychain")]
+const KEYRING_SERVICE: &str = "ClaudeHydra";
+
+/// Providers whose keys can be managed here, with their env var fallback.
+const KEYED_PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("google", "GOOGLE_API_KEY"),
+    ("openai", "OPENAI_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+    ("groq", "GROQ_API_KEY"),
+    ("azure", "AZURE_OPENAI_API_KEY"),
+    ("brave", "BRAVE_API_KEY"),
+];
+
+#[derive(Debug)]
+pub enum SecretsError {
+    /// Built without the `keychain` feature.
+    Unavailable,
+    UnknownProvider,
+    Backend(String),
+}
+
+impl std::fmt::Display for SecretsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "OS keychain support is not enabled in this build"),
+            Self::UnknownProvider => write!(f, "unknown provider"),
+            Self::Backend(e) => write!(f, "keychain error: {}", e),
+        }
+    }
+}
+
+/// provider → key (`None` = looked up, not stored).
+static CACHE: OnceLock<RwLock<HashMap<String, Option<String>>>> = OnceLock::new();
+
+fn cache() -> &'static RwLock<HashMap<String, Option<String>>> {
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn env_var_for(provider: &str) -> Option<&'static str> {
+    KEYED_PROVIDERS
+        .iter()
+        .find(|(p, _)| *p == provider)
+        .map(|(_, env)| *env)
+}
+
+/// Whether this build can talk to the OS credential store.
+pub fn keychain_available() -> bool {
+    cfg!(feature = "keychain")
+}
+
+fn cached(provider: &str) -> Option<Option<String>> {
+    cache().read().ok().and_then(|c| c.get(provider).cloned())
+}
+
+/// Read `provider`'s key from the OS store into the cache (blocking).
+fn load(provider: &str) -> Option<String> {
+    let loaded = keychain_read(provider);
+    if let Ok(mut c) = cache().write() {
+        c.insert(provider.to_string(), loaded.clone());
+    }
+    loaded
+}
+
+/// Cached key, else a read of the OS store (blocking).
+fn lookup_blocking(provider: &str) -> Option<String> {
+    cached(provider).unwrap_or_else(|| load(provider))
+}
+
+/// Key stored in the OS keychain for `provider`, if any. Served from the
+/// cache only: on a miss the key is loaded on the blocking pool and this
+/// returns `None` until it lands.
+pub fn get_provider_key(provider: &str) -> Option<String> {
+    if let Some(hit) = cached(provider) {
+        return hit;
+    }
+    if keychain_available()
+        && env_var_for(provider).is_some()
+        && let Ok(rt) = tokio::runtime::Handle::try_current()
+    {
+        let provider = provider.to_string();
+        rt.spawn_blocking(move || load(&provider));
+    }
+    None
+}
+
+/// Read every managed provider's key into the cache (blocking — call once
+/// at startup from `spawn_blocking`).
+pub fn preload() {
+    if !keychain_available() {
+        return;
+    }
+    let stored = KEYED_PROVIDERS
+        .iter()
+        .filter(|(p, _)| lookup_blocking(p).is_some())
+        .count();
+    tracing::info!("secrets: {} provider key(s) loaded from OS keychain", stored);
+}
+
+/// Which key wins: the keychain's over the env var's.
+fn pick_key(stored: Option<String>, env: Option<String>) -> (&'static str, Option<String>) {
+    match (stored, env) {
+        (Some(k), _) => ("keychain", Some(k)),
+        (None, Some(k)) => ("env", Some(k)),
+        (None, None) => ("none", None),
+    }
+}
+
+fn env_key(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|k| !k.is_empty())
+}
+
+/// Keychain key first, then the provider's env var.
+pub fn resolve_provider_key(provider: &str) -> Option<String> {
+    let env = env_var_for(provider).and_then(env_key);
+    pick_key(get_provider_key(provider), env).1
+}
+
+pub fn set_provider_key(provider: &str, key: &str) -> Result<(), SecretsError> {
+    if env_var_for(provider).is_none() {
+        return Err(SecretsError::UnknownProvider);
+    }
+    keychain_write(provider, Some(key))?;
+    if let Ok(mut c) = cache().write() {
+        c.insert(provider.to_string(), Some(key.to_string()));
+    }
+    Ok(())
+}
+
+pub fn delete_provider_key(provider: &str) -> Result<(), SecretsError> {
+    if env_var_for(provider).is_none() {
+        return Err(SecretsError::UnknownProvider);
+    }
+    keychain_write(provider, None)?;
+    if let Ok(mut c) = cache().write() {
+        c.insert(provider.to_string(), None);
+    }
+    Ok(())
+}
+
+/// `…wxyz` — the last 4 characters, enough to tell keys apart; short keys
+/// are masked entirely.
+pub fn mask_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "•".repeat(chars.len());
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("…{}", tail)
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_read(provider: &str) -> Option<String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider).ok()?;
+    match entry.get_password() {
+        Ok(k) if !k.is_empty() => Some(k),
+        Ok(_) | Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            tracing::warn!("secrets: keychain read for {} failed: {}", provider, e);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_read(_provider: &str) -> Option<String> {
+    None
+}
+
+#[cfg(feature = "keychain")]
+fn keychain_write(provider: &str, key: Option<&str>) -> Result<(), SecretsError> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider)
+        .map_err(|e| SecretsError::Backend(e.to_string()))?;
+    let result = match key {
+        Some(k) => entry.set_password(k),
+        None => match entry.delete_credential() {
+            Err(keyring::Error::NoEntry) => Ok(()),
+            other => other,
+        },
+    };
+    result.map_err(|e| SecretsError::Backend(e.to_string()))
+}
+
+#[cfg(not(feature = "keychain"))]
+fn keychain_write(_provider: &str, _key: Option<&str>) -> Result<(), SecretsError> {
+    Err(SecretsError::Unavailable)
+}
+
+fn secrets_error_response(provider: &str, err: SecretsError) -> (StatusCode, Json<Value>) {
+    let status = match err {
+        SecretsError::Unavailable => StatusCode::NOT_IMPLEMENTED,
+        SecretsError::UnknownProvider => StatusCode::NOT_FOUND,
+        SecretsError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    tracing::warn!("secrets: {} — {}", provider, err);
+    (
+        status,
+        Json(json!({ "error": err.to_string(), "provider": provider })),
+    )
+}
+
+/// Mirror a key change into `runtime.api_keys` for providers the chat path
+/// reads from there (same legacy key names as `AppState::new`).
+pub(crate) async fn sync_runtime_key(state: &AppState, provider: &str, key: Option<&str>) {
+    let legacy = match provider {
+        "anthropic" => "ANTHROPIC_API_KEY",
+        "google" => "GOOGLE_API_KEY",
+        _ => return,
+    };
+    let mut rt = state.runtime.write().await;
+    match key {
+        Some(k) => {
+            rt.api_keys.insert(provider.to_string(), k.to_string());
+            rt.api_keys.insert(legacy.to_string(), k.to_string());
+        }
+        // Fall back to the env key, if any, like a fresh start would.
+        None => match std::env::var(legacy).ok().filter(|k| !k.is_empty()) {
+            Some(k) => {
+                rt.api_keys.insert(provider.to_string(), k.clone());
+                rt.api_keys.insert(legacy.to_string(), k);
+            }
+            None => {
+                rt.api_keys.remove(provider);
+                rt.api_keys.remove(legacy);
+            }
+        },
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  GET /api/secrets/providers
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn get_provider_key_status() -> Json<Value> {
+    let providers = tokio::task::spawn_blocking(|| {
+        KEYED_PROVIDERS
+            .iter()
+            .map(|(provider, env)| {
+                let (source, key) = pick_key(lookup_blocking(provider), env_key(env));
+                json!({
+                    "provider": provider,
+                    "stored": source == "keychain",
+                    "source": source,
+                    "masked_key": key.as_deref().map(mask_key),
+                    "env_var": env,
+                })
+            })
+            .collect::<Vec<_>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    Json(json!({
+        "keychain_available": keychain_available(),
+        "providers": providers,
+    }))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  PUT /api/secrets/providers/{provider}
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct SetProviderKeyRequest {
+    pub key: String,
+}
+
+pub async fn set_provider_key_handler(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Json(req): Json<SetProviderKeyRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let key = req.key.trim().to_string();
+    if key.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "key must not be empty" })),
+        ));
+    }
+
+    let p = provider.clone();
+    let k = key.clone();
+    tokio::task::spawn_blocking(move || set_provider_key(&p, &k))
+        .await
+        .map_err(|e| secrets_error_response(&provider, SecretsError::Backend(e.to_string())))?
+        .map_err(|e| secrets_error_response(&provider, e))?;
+
+    sync_runtime_key(&state, &provider, Some(&key)).await;
+    crate::audit::log_audit(
+        &state.db,
+        "provider_key_set",
+        json!({ "provider": provider }),
+        None,
+    )
+    .await;
+
+    Ok(Json(json!({
+        "status": "ok",
+        "provider": provider,
+        "masked_key": mask_key(&key),
+    })))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  DELETE /api/secrets/providers/{provider}
+// ═══════════════════════════════════════════════════════════════════════
+
+pub async fn delete_provider_key_handler(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let p = provider.clone();
+    tokio::task::spawn_blocking(move || delete_provider_key(&p))
+        .await
+        .map_err(|e| secrets_error_response(&provider, SecretsError::Backend(e.to_string())))?
+        .map_err(|e| secrets_error_response(&provider, e))?;
+
+    sync_runtime_key(&state, &provider, None).await;
+    crate::audit::log_audit(
+        &state.db,
+        "provider_key_deleted",
+        json!({ "provider": provider }),
+        None,
+    )
+    .await;
+
+    Ok(Json(json!({ "status": "ok", "provider": provider })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_show_only_the_last_four_characters() {
+        assert_eq!(mask_key("sk-ant-api03-abcdwxyz"), "…wxyz");
+        assert_eq!(mask_key("123456789"), "…6789");
+        assert_eq!(mask_key("short"), "•••••");
+        assert_eq!(mask_key(""), "");
+    }
+
+    #[test]
+    fn keychain_keys_win_over_env_keys() {
+        let key = |k: &str| Some(k.to_string());
+        assert_eq!(pick_key(key("kc"), key("env")), ("keychain", key("kc")));
+        assert_eq!(pick_key(None, key("env")), ("env", key("env")));
+        assert_eq!(pick_key(None, None), ("none", None));
+    }
+
+    #[test]
+    fn unknown_providers_are_rejected_before_the_store() {
+        assert!(matches!(
+            set_provider_key("not-a-provider", "k"),
+            Err(SecretsError::UnknownProvider)
+        ));
+        assert!(matches!(
+            delete_provider_key("not-a-provider"),
+            Err(SecretsError::UnknownProvider)
+        ));
+        assert_eq!(env_var_for("anthropic"), Some("ANTHROPIC_API_KEY"));
+    }
+
+    #[test]
+    fn lookups_are_served_from_the_cache() {
+        cache()
+            .write()
+            .unwrap()
+            .insert("test-cached".to_string(), Some("cached-key".to_string()));
+        assert_eq!(get_provider_key("test-cached").as_deref(), Some("cached-key"));
+        // Unmanaged providers never reach the OS store.
+        assert_eq!(get_provider_key("test-unknown"), None);
+        assert_eq!(cached("test-unknown"), None);
+    }
+}
@@ -0,0 +1,401 @@
+//! Long-term conversation memory per session (tab), backed by embeddings.
+//!
+//! Completed exchanges (prompt + reply) are embedded with the configured
+//! backend (see `crate::embeddings`) and stored in `ch_session_memory`. On
+//! a new WebSocket prompt the most similar past exchanges are added to the
+//! system prompt — only ones the history window no longer carries (older
+//! than the session's last `HISTORY_EXCHANGES`), and with `all` scope also
+//! exchanges from prior sessions. Search is exact (brute-force cosine) over
+//! the newest `MAX_CANDIDATES` exchanges.
+//!
+//! Opt-in via environment or `[session_memory]` in the config file
+//! (`hydra_config.rs`):
+//! - `CH_SESSION_MEMORY` — `session` (the tab's own exchanges) or `all`
+//!   (prior sessions too); memory is disabled when unset.
+//! - `CH_SESSION_MEMORY_TOP_K` — exchanges injected per prompt (default 3).
+//! - `CH_SESSION_MEMORY_MIN_SCORE` — minimum cosine similarity (default 0.5).
+//!
+//! - `GET    /api/sessions/{id}/memory`        — stored exchanges (without vectors)
+//! - `POST   /api/sessions/{id}/memory/search` — `{ prompt, top_k?, all_sessions? }` → ranked exchanges
+//! - `DELETE /api/sessions/{id}/memory`        — forget the session's exchanges
+
+use std::sync::Arc;
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{db_error, parse_session_id};
+use crate::embeddings::{cosine_similarity, embed_texts};
+use crate::hydra_config::{Derived, HydraConfig, number, text};
+use crate::state::AppState;
+
+/// Exchanges still in the model's history window (`load_session_history`
+/// sends the last 20 messages) — recalling them would only repeat them.
+const HISTORY_EXCHANGES: i64 = 10;
+/// Newest exchanges scored per recall.
+const MAX_CANDIDATES: i64 = 2_000;
+/// Characters of an exchange that are embedded / injected.
+const MAX_EXCHANGE_CHARS: usize = 4_000;
+const MAX_TOP_K: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryScope {
+    Session,
+    All,
+}
+
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    pub scope: MemoryScope,
+    pub top_k: usize,
+    pub min_score: f32,
+}
+
+static CONFIG: Derived<Option<Arc<MemoryConfig>>> = Derived::new(build_config);
+
+pub(crate) fn parse_scope(raw: &str) -> Option<MemoryScope> {
+    match raw.trim().to_lowercase().as_str() {
+        "session" | "tab" | "1" | "true" | "on" => Some(MemoryScope::Session),
+        "all" | "global" => Some(MemoryScope::All),
+        _ => None,
+    }
+}
+
+fn build_config(config: &HydraConfig) -> Option<Arc<MemoryConfig>> {
+    let file = &config.session_memory;
+    let scope = parse_scope(&text("CH_SESSION_MEMORY", file.scope.as_deref())?)?;
+    let cfg = MemoryConfig {
+        scope,
+        top_k: number("CH_SESSION_MEMORY_TOP_K", file.top_k)
+            .unwrap_or(3)
+            .min(MAX_TOP_K),
+        min_score: number("CH_SESSION_MEMORY_MIN_SCORE", file.min_score).unwrap_or(0.5),
+    };
+    tracing::info!(
+        "session_memory: enabled (scope {:?}, top_k {}, min_score {})",
+        cfg.scope,
+        cfg.top_k,
+        cfg.min_score
+    );
+    Some(Arc::new(cfg))
+}
+
+/// Memory settings (env overrides the file); `None` when disabled.
+pub fn config() -> Option<Arc<MemoryConfig>> {
+    CONFIG.get()
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Text an exchange is embedded as.
+fn exchange_text(prompt: &str, response: &str) -> String {
+    truncate_chars(
+        &format!("User: {}\n\nAssistant: {}", prompt.trim(), response.trim()),
+        MAX_EXCHANGE_CHARS,
+    )
+}
+
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct MemoryEntry {
+    pub id: i64,
+    pub session_id: uuid::Uuid,
+    pub prompt: String,
+    pub response: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryHit {
+    #[serde(flatten)]
+    pub entry: MemoryEntry,
+    pub score: f32,
+}
+
+/// Best `top_k` candidates by similarity to `query`.
+fn rank(query: &[f32], candidates: Vec<(MemoryEntry, Vec<f32>)>, top_k: usize) -> Vec<MemoryHit> {
+    let mut hits: Vec<MemoryHit> = candidates
+        .into_iter()
+        .map(|(entry, embedding)| MemoryHit {
+            score: cosine_similarity(query, &embedding),
+            entry,
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(top_k);
+    hits
+}
+
+/// Embed and store a completed exchange.
+pub async fn remember(
+    state: &AppState,
+    session_id: uuid::Uuid,
+    prompt: &str,
+    response: &str,
+) -> Result<(), String> {
+    let embedded =
+        embed_texts(&state.http_client, &[exchange_text(prompt, response)], None).await?;
+    let Some(vector) = embedded.vectors.into_iter().next() else {
+        return Err("Embedding backend returned no vector".to_string());
+    };
+    sqlx::query(
+        "INSERT INTO ch_session_memory (session_id, prompt, response, model, embedding) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(session_id)
+    .bind(prompt)
+    .bind(response)
+    .bind(&embedded.model)
+    .bind(&vector)
+    .execute(&state.db)
+    .await
+    .map_err(|e| format!("Failed to store memory: {}", e))?;
+    Ok(())
+}
+
+/// Store an exchange in the background when memory is enabled — call after
+/// persisting a prompt and its reply.
+pub fn schedule_remember(state: &AppState, session_id: uuid::Uuid, prompt: &str, response: &str) {
+    if config().is_none() || prompt.trim().is_empty() || response.trim().is_empty() {
+        return;
+    }
+    let (state, prompt, response) = (state.clone(), prompt.to_string(), response.to_string());
+    tokio::spawn(async move {
+        if let Err(e) = remember(&state, session_id, &prompt, &response).await {
+            tracing::warn!("session_memory: {}", e);
+        }
+    });
+}
+
+/// Past exchanges most similar to `prompt`, excluding the ones still in the
+/// session's history window.
+pub async fn recall(
+    state: &AppState,
+    session_id: uuid::Uuid,
+    prompt: &str,
+    scope: MemoryScope,
+    top_k: usize,
+) -> Result<Vec<MemoryHit>, String> {
+    let embedded = embed_texts(&state.http_client, &[prompt.to_string()], None).await?;
+    let Some(query) = embedded.vectors.into_iter().next() else {
+        return Ok(Vec::new());
+    };
+    let rows: Vec<(i64, uuid::Uuid, String, String, DateTime<Utc>, Vec<f32>)> = sqlx::query_as(
+        "SELECT id, session_id, prompt, response, created_at, embedding FROM ch_session_memory \
+         WHERE model = $2 AND (session_id = $1 OR $3) \
+           AND id NOT IN (SELECT id FROM ch_session_memory WHERE session_id = $1 \
+                          ORDER BY created_at DESC LIMIT $4) \
+         ORDER BY created_at DESC LIMIT $5",
+    )
+    .bind(session_id)
+    .bind(&embedded.model)
+    .bind(scope == MemoryScope::All)
+    .bind(HISTORY_EXCHANGES)
+    .bind(MAX_CANDIDATES)
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| format!("Failed to load memory: {}", e))?;
+    let candidates = rows
+        .into_iter()
+        .map(
+            |(id, session_id, prompt, response, created_at, embedding)| {
+                (
+                    MemoryEntry {
+                        id,
+                        session_id,
+                        prompt,
+                        response,
+                        created_at,
+                    },
+                    embedding,
+                )
+            },
+        )
+        .collect();
+    Ok(rank(&query, candidates, top_k.clamp(1, MAX_TOP_K)))
+}
+
+/// System prompt section for recalled exchanges.
+fn format_context(hits: &[MemoryHit], session_id: uuid::Uuid) -> String {
+    let mut out = String::from(
+        "\n\n## Recalled conversation memory\nEarlier exchanges that may be relevant to the request:\n",
+    );
+    for hit in hits {
+        let origin = if hit.entry.session_id == session_id {
+            "this conversation"
+        } else {
+            "an earlier conversation"
+        };
+        out.push_str(&format!(
+            "\n### {} ({})\n{}\n",
+            hit.entry.created_at.format("%Y-%m-%d %H:%M"),
+            origin,
+            exchange_text(&hit.entry.prompt, &hit.entry.response)
+        ));
+    }
+    out
+}
+
+/// Append recalled exchanges to `system_prompt`. Returns the injected hits;
+/// recall errors are logged and leave the prompt unchanged.
+pub async fn augment_system_prompt(
+    state: &AppState,
+    session_id: uuid::Uuid,
+    system_prompt: &mut String,
+    prompt: &str,
+) -> Vec<MemoryHit> {
+    let Some(cfg) = config() else {
+        return Vec::new();
+    };
+    if cfg.top_k == 0 {
+        return Vec::new();
+    }
+    let hits: Vec<MemoryHit> = match recall(state, session_id, prompt, cfg.scope, cfg.top_k).await {
+        Ok(hits) => hits
+            .into_iter()
+            .filter(|h| h.score >= cfg.min_score)
+            .collect(),
+        Err(e) => {
+            tracing::warn!("session_memory: recall failed: {}", e);
+            return Vec::new();
+        }
+    };
+    if !hits.is_empty() {
+        system_prompt.push_str(&format_context(&hits, session_id));
+    }
+    hits
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+/// `GET /api/sessions/{id}/memory`
+pub async fn list_memory(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let entries = sqlx::query_as::<_, MemoryEntry>(
+        "SELECT id, session_id, prompt, response, created_at FROM ch_session_memory \
+         WHERE session_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(session_id)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("session_memory"))?;
+    Ok(Json(json!({
+        "session_id": session_id,
+        "enabled": config().is_some(),
+        "scope": config().map(|c| c.scope),
+        "count": entries.len(),
+        "entries": entries,
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MemorySearchRequest {
+    pub prompt: String,
+    #[serde(default)]
+    pub top_k: Option<usize>,
+    /// Search prior sessions too (default: the configured scope).
+    #[serde(default)]
+    pub all_sessions: Option<bool>,
+}
+
+/// `POST /api/sessions/{id}/memory/search`
+pub async fn search_memory(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<MemorySearchRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    if req.prompt.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "prompt must not be empty" })),
+        ));
+    }
+    let scope = match req.all_sessions {
+        Some(true) => MemoryScope::All,
+        Some(false) => MemoryScope::Session,
+        None => config().map_or(MemoryScope::Session, |c| c.scope),
+    };
+    let top_k = req.top_k.or(config().map(|c| c.top_k)).unwrap_or(3);
+    let hits = recall(&state, session_id, &req.prompt, scope, top_k)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, Json(json!({ "error": e }))))?;
+    Ok(Json(json!({ "scope": scope, "hits": hits })))
+}
+
+/// `DELETE /api/sessions/{id}/memory`
+pub async fn clear_memory(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let deleted = sqlx::query("DELETE FROM ch_session_memory WHERE session_id = $1")
+        .bind(session_id)
+        .execute(&state.db)
+        .await
+        .map_err(db_error("session_memory"))?
+        .rows_affected();
+    Ok(Json(
+        json!({ "session_id": session_id, "deleted": deleted }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: i64) -> MemoryEntry {
+        MemoryEntry {
+            id,
+            session_id: uuid::Uuid::nil(),
+            prompt: format!("prompt {}", id),
+            response: format!("response {}", id),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn rank_orders_by_similarity() {
+        let candidates = vec![
+            (entry(1), vec![0.0, 1.0]),
+            (entry(2), vec![1.0, 0.1]),
+            (entry(3), vec![0.7, 0.7]),
+        ];
+        let hits = rank(&[1.0, 0.0], candidates, 2);
+        let ids: Vec<i64> = hits.iter().map(|h| h.entry.id).collect();
+        assert_eq!(ids, [2, 3]);
+        assert!(hits[0].score > hits[1].score);
+    }
+
+    #[test]
+    fn exchange_text_is_bounded() {
+        assert_eq!(
+            exchange_text(" hi ", "hello\n"),
+            "User: hi\n\nAssistant: hello"
+        );
+        let long = exchange_text(&"ą".repeat(5_000), "ok");
+        assert_eq!(long.chars().count(), MAX_EXCHANGE_CHARS + 1);
+        assert!(long.ends_with('…'));
+    }
+
+    #[test]
+    fn scope_values() {
+        assert_eq!(parse_scope("session"), Some(MemoryScope::Session));
+        assert_eq!(parse_scope(" ALL "), Some(MemoryScope::All));
+        assert_eq!(parse_scope("off"), None);
+    }
+}
@@ -0,0 +1,167 @@
+//! Graceful shutdown — stop taking new background work, let in-flight work
+//! finish, then clean up child processes and persisted queue state.
+//!
+//! Sequence (local entry point, `main.rs`):
+//! 1. On Ctrl+C / SIGTERM `begin_drain()` is called: the idle scavenger stops
+//!    claiming prompts and new persistent CLI turns are refused, while axum
+//!    finishes the HTTP requests already in progress.
+//! 2. Once the server has stopped, `finish()` waits up to
+//!    `CH_SHUTDOWN_DRAIN_SECS` (default 30) for tracked work (background
+//!    prompts, CLI turns) to complete.
+//! 3. All persistent Claude CLI children and supervised MCP servers are
+//!    killed, and background prompts still marked `running` are requeued for
+//!    retry (see `queue::recover_interrupted`) instead of being lost.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::state::AppState;
+
+/// Drain flag and in-flight counter. The process uses `DRAIN`; tests use
+/// their own instance so they never flip the real flag under other tests.
+struct Drain {
+    draining: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+impl Drain {
+    const fn new() -> Self {
+        Self {
+            draining: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    /// Start draining; `false` if it had already started.
+    fn begin(&self) -> bool {
+        !self.draining.swap(true, Ordering::SeqCst)
+    }
+
+    fn track(&'static self) -> Option<InFlight> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlight(self);
+        // Checked after registering so `finish()` never misses a late starter.
+        (!self.is_draining()).then_some(guard)
+    }
+
+    fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+}
+
+static DRAIN: Drain = Drain::new();
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+fn drain_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("CH_SHUTDOWN_DRAIN_SECS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(30),
+    )
+}
+
+/// Whether shutdown has started; queue consumers must not pick up new work.
+pub fn is_draining() -> bool {
+    DRAIN.is_draining()
+}
+
+pub fn begin_drain() {
+    if DRAIN.begin() {
+        tracing::info!(
+            "shutdown: draining ({} task(s) in flight)",
+            DRAIN.in_flight()
+        );
+    }
+}
+
+/// Marks one unit of work as in flight until dropped.
+pub struct InFlight(&'static Drain);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Track a unit of work, or `None` when shutdown has already started.
+pub fn track() -> Option<InFlight> {
+    DRAIN.track()
+}
+
+pub fn in_flight() -> usize {
+    DRAIN.in_flight()
+}
+
+/// Wait for tracked work, then stop CLI children and requeue interrupted
+/// background prompts. Idempotent.
+pub async fn finish(state: &AppState) {
+    begin_drain();
+
+    let timeout = drain_timeout();
+    let waited = tokio::time::timeout(timeout, async {
+        while in_flight() > 0 {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    })
+    .await;
+    if waited.is_err() {
+        tracing::warn!(
+            "shutdown: {} task(s) still running after {}s, abandoning them",
+            in_flight(),
+            timeout.as_secs()
+        );
+    }
+
+    let stopped = crate::claude_cli::stop_all().await;
+    if stopped > 0 {
+        tracing::info!("shutdown: stopped {} Claude CLI process(es)", stopped);
+    }
+    let stopped = crate::mcp::supervisor::stop_all().await;
+    if stopped > 0 {
+        tracing::info!("shutdown: stopped {} MCP server process(es)", stopped);
+    }
+
+    match crate::queue::recover_interrupted(&state.db).await {
+        Ok((0, 0)) => {}
+        Ok((requeued, failed)) => tracing::info!(
+            "shutdown: interrupted background prompts: {} requeued, {} failed",
+            requeued,
+            failed
+        ),
+        Err(e) => tracing::warn!("shutdown: failed to requeue background prompts: {}", e),
+    }
+
+    state.db.close().await;
+    tracing::info!("shutdown: complete");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracking_counts_until_dropped_and_stops_once_draining() {
+        static DRAIN: Drain = Drain::new();
+        let guard = DRAIN.track().expect("not draining yet");
+        assert_eq!(DRAIN.in_flight(), 1);
+        drop(guard);
+        assert_eq!(DRAIN.in_flight(), 0);
+
+        let running = DRAIN.track().expect("not draining yet");
+        assert!(DRAIN.begin());
+        assert!(!DRAIN.begin());
+        assert!(DRAIN.track().is_none());
+        // Work started before the drain is still waited for.
+        assert_eq!(DRAIN.in_flight(), 1);
+        drop(running);
+        assert_eq!(DRAIN.in_flight(), 0);
+        // The process-wide state is untouched.
+        assert!(!is_draining());
+    }
+}
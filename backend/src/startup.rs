@@ -0,0 +1,219 @@
+// ClaudeHydra v4 — Eager startup warm-up
+//
+// Moves first-request costs (model registry sync, prompt cache, Anthropic /
+// Vault reachability, MCP connections) to process start. Everything runs in
+// a background task so the listener binds immediately; the result is kept as
+// a `StartupReport` served at `GET /api/health/startup`. Both entry points
+// (shuttle and local) start it.
+//
+// `mark_ready()` waits for the model sync (at most `READY_MODEL_SYNC_WAIT`),
+// the Anthropic probe and the prompt cache. Every probe has a timeout. Vault
+// and MCP never gate readiness: `vault_reachable` / `mcp_connected` are
+// `null` in the report until their checks finish, and `model_sync_*` are
+// filled in once a sync still retrying at readiness ends. MCP servers get
+// as long as they need to connect; one still connecting after
+// `MCP_CONNECT_TIMEOUT` is only logged.
+
+use std::time::{Duration, Instant};
+
+use crate::ai_gateway::vault_bridge::HasVaultBridge;
+use crate::handlers;
+use crate::model_registry;
+use crate::models::StartupReport;
+use crate::state::AppState;
+
+/// Retry delays for the model registry sync (after the first attempt).
+const MODEL_SYNC_RETRY_DELAYS: [Duration; 3] = [
+    Duration::from_secs(5),
+    Duration::from_secs(15),
+    Duration::from_secs(30),
+];
+const MODEL_SYNC_TIMEOUT: Duration = Duration::from_secs(90);
+/// Longest readiness waits for the model sync before serving fallback models.
+const READY_MODEL_SYNC_WAIT: Duration = Duration::from_secs(20);
+/// Per-probe limit for the Anthropic and Vault reachability checks.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+/// When a still-running MCP connect is logged as slow (it is not cancelled).
+const MCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Spawn the writers every entry point needs (shuttle and local): the audit
+/// trail writer, which drains the queue `audit_trail::command` /
+/// `file_change` fill.
+pub fn spawn_background_writers(state: &AppState) {
+    crate::audit_trail::spawn(state.clone());
+}
+
+/// Spawn the warm-up task. Returns immediately.
+pub fn spawn_warm_start(state: AppState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let started = Instant::now();
+
+        // Vault and MCP are reported but never gate readiness.
+        let vault = tokio::spawn({
+            let state = state.clone();
+            async move { within(PROBE_TIMEOUT, "Vault probe", probe_vault(&state)).await }
+        });
+        // No timeout: cancelling would drop connections still being set up.
+        let mut mcp = tokio::spawn({
+            let state = state.clone();
+            async move { connect_mcp(&state).await }
+        });
+        let mut model_sync = tokio::spawn({
+            let state = state.clone();
+            async move { sync_models_with_retry(&state).await }
+        });
+
+        // Readiness waits for the model sync only so long; retries go on and
+        // the fallback models serve until one succeeds.
+        let (synced_in_time, anthropic_reachable) = tokio::join!(
+            tokio::time::timeout(READY_MODEL_SYNC_WAIT, &mut model_sync),
+            within(
+                PROBE_TIMEOUT,
+                "Anthropic API probe",
+                crate::watchdog::check_anthropic_api(&state)
+            ),
+        );
+        let synced = synced_in_time
+            .ok()
+            .map(|joined| joined.unwrap_or((false, 0)));
+        let (model_sync_ok, model_sync_attempts) = synced.unwrap_or((false, 1));
+
+        handlers::warm_prompt_cache(&state).await;
+
+        let report = StartupReport {
+            ready: true,
+            model_sync_ok,
+            model_sync_attempts,
+            prompt_cache_warmed: true,
+            anthropic_reachable,
+            vault_reachable: None,
+            mcp_connected: None,
+            agents_loaded: state.agents.read().await.len(),
+            tools_available: state.tool_executor.tool_definitions().len(),
+            warmup_ms: started.elapsed().as_millis() as u64,
+            completed_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        tracing::info!(
+            warmup_ms = report.warmup_ms,
+            model_sync_ok,
+            anthropic_reachable,
+            "startup: backend ready"
+        );
+
+        *state.startup_report.write().await = Some(report);
+        state.mark_ready();
+
+        // Fill in what finished after readiness.
+        let vault_reachable = vault.await.unwrap_or(false);
+        if let Some(report) = state.startup_report.write().await.as_mut() {
+            report.vault_reachable = Some(vault_reachable);
+        }
+        tracing::info!(vault_reachable, "startup: Vault checked");
+        let mcp_connected = match tokio::time::timeout(MCP_CONNECT_TIMEOUT, &mut mcp).await {
+            Ok(joined) => joined.unwrap_or(false),
+            Err(_) => {
+                tracing::warn!(
+                    "startup: MCP servers still connecting after {}s",
+                    MCP_CONNECT_TIMEOUT.as_secs()
+                );
+                mcp.await.unwrap_or(false)
+            }
+        };
+        if let Some(report) = state.startup_report.write().await.as_mut() {
+            report.mcp_connected = Some(mcp_connected);
+        }
+        tracing::info!(mcp_connected, "startup: MCP connect finished");
+        if synced.is_none() {
+            let (ok, attempts) = model_sync.await.unwrap_or((false, 0));
+            if let Some(report) = state.startup_report.write().await.as_mut() {
+                report.model_sync_ok = ok;
+                report.model_sync_attempts = attempts;
+            }
+        }
+    })
+}
+
+/// `probe`'s result, or `false` when it takes longer than `limit`.
+async fn within(limit: Duration, what: &str, probe: impl Future<Output = bool>) -> bool {
+    tokio::time::timeout(limit, probe)
+        .await
+        .unwrap_or_else(|_| {
+            tracing::warn!("startup: {} timed out after {}s", what, limit.as_secs());
+            false
+        })
+}
+
+/// Model registry sync with retry/backoff. Returns `(synced, attempts)`.
+async fn sync_models_with_retry(state: &AppState) -> (bool, u32) {
+    for (attempt, delay) in std::iter::once(&Duration::ZERO)
+        .chain(MODEL_SYNC_RETRY_DELAYS.iter())
+        .enumerate()
+    {
+        if attempt > 0 {
+            tracing::warn!(
+                "startup: model registry sync retry {}/{} after {}s",
+                attempt,
+                MODEL_SYNC_RETRY_DELAYS.len(),
+                delay.as_secs()
+            );
+            tokio::time::sleep(*delay).await;
+        }
+
+        match tokio::time::timeout(MODEL_SYNC_TIMEOUT, model_registry::startup_sync(state)).await {
+            Ok(()) => {
+                tracing::info!(
+                    "startup: model registry sync complete (attempt {})",
+                    attempt + 1
+                );
+                return (true, attempt as u32 + 1);
+            }
+            Err(_) => {
+                tracing::error!(
+                    "startup: model registry sync timed out after {}s (attempt {})",
+                    MODEL_SYNC_TIMEOUT.as_secs(),
+                    attempt + 1
+                );
+            }
+        }
+    }
+
+    let attempts = MODEL_SYNC_RETRY_DELAYS.len() as u32 + 1;
+    tracing::error!(
+        "startup: model registry sync failed after {} attempts — using fallback models",
+        attempts
+    );
+    (false, attempts)
+}
+
+/// Vault reachability (the health endpoint, not a credential read).
+async fn probe_vault(state: &AppState) -> bool {
+    let status = state.vault_client().health().await;
+    serde_json::to_value(status)
+        .ok()
+        .and_then(|v| v.get("online").and_then(|o| o.as_bool()))
+        .unwrap_or(false)
+}
+
+/// Connect to enabled MCP servers (previously spawned separately in main).
+async fn connect_mcp(state: &AppState) -> bool {
+    match state.mcp_client.startup_connect().await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!("MCP startup_connect failed: {}", e);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slow_probes_count_as_unreachable() {
+        let hung = std::future::pending::<bool>();
+        assert!(!within(Duration::from_millis(10), "hung probe", hung).await);
+        assert!(within(Duration::from_secs(1), "quick probe", async { true }).await);
+    }
+}
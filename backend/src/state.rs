@@ -82,6 +82,13 @@ pub struct AppState {
     pub sandbox: SandboxState,
     // ── Memory Pruning (Self-Reflection & Knowledge Graph cleanup) ──────
     pub memory_pruning: Arc<MemoryPruningState>,
+    // ── Startup warm-up ──────────────────────────────────────────────────
+    /// Readiness report written once by `startup::spawn_warm_start`.
+    pub startup_report: Arc<RwLock<Option<crate::models::StartupReport>>>,
+
+    // ── Background job schedule ──────────────────────────────────────────
+    /// Last-run times of recurring jobs (`GET /api/schedule`).
+    pub job_schedule: Arc<crate::schedule::JobSchedule>,
 }
 
 impl Deref for AppState {
@@ -106,11 +113,20 @@ impl AppState {
             mcp_tools_table: "ch_mcp_discovered_tools",
         }).await;
 
+        // ── OS keychain keys (feature `keychain`) ───────────────────
+        let _ = tokio::task::spawn_blocking(crate::secrets::preload).await;
+
         // ── Inject legacy key names for backward compatibility ──────
         // BaseHydraState inserts as "anthropic" / "google", but CH handlers
         // look up "ANTHROPIC_API_KEY" / "GOOGLE_API_KEY" in runtime.api_keys.
+        // Keychain keys take precedence over env keys (see `secrets.rs`).
         {
             let mut rt = base.runtime.write().await;
+            for provider in ["anthropic", "google"] {
+                if let Some(key) = crate::secrets::get_provider_key(provider) {
+                    rt.api_keys.insert(provider.to_string(), key);
+                }
+            }
             if let Some(key) = rt.api_keys.get("anthropic").cloned() {
                 rt.api_keys.insert("ANTHROPIC_API_KEY".to_string(), key);
             }
@@ -143,6 +159,7 @@ impl AppState {
             providers: ai_gateway::default_provider_configs(),
             vault_client,
             oauth_manager,
+            http_client: base.client.clone(),
         });
 
         // ── Backward-compat field aliases ───────────────────────────
@@ -179,6 +196,8 @@ impl AppState {
             semantic_cache,
             sandbox,
             memory_pruning: Arc::new(MemoryPruningState::new(&db).await),
+            startup_report: Arc::new(RwLock::new(None)),
+            job_schedule: Arc::new(crate::schedule::JobSchedule::new()),
         }
     }
 
@@ -202,6 +221,13 @@ impl AppState {
     /// Test-only constructor — uses `connect_lazy` so no real DB is needed.
     #[doc(hidden)]
     pub fn new_test() -> Self {
+        let db = PgPool::connect_lazy("postgres://test@localhost:19999/test").expect("lazy pool");
+        Self::new_test_with_db(db)
+    }
+
+    /// Test-only constructor over a real (migrated) database.
+    #[doc(hidden)]
+    pub fn new_test_with_db(db: PgPool) -> Self {
         let agents = Arc::new(RwLock::new(init_witcher_agents()));
 
         let http_client = reqwest::Client::builder()
@@ -209,7 +235,6 @@ impl AppState {
             .build()
             .expect("Failed to build HTTP client");
 
-        let db = PgPool::connect_lazy("postgres://test@localhost:19999/test").expect("lazy pool");
         let mcp_client = Arc::new(jaskier_hydra_state::McpClientManager::with_tables(
             db.clone(), http_client.clone(), "ch_mcp_servers", "ch_mcp_discovered_tools",
         ));
@@ -258,6 +283,7 @@ impl AppState {
             providers: ai_gateway::default_provider_configs(),
             vault_client: VaultClient::with_url("http://localhost:19999"), // non-existent in tests
             oauth_manager: ai_gateway::OAuthFlowManager::new(http_client.clone()),
+            http_client: http_client.clone(),
         });
 
         Self {
@@ -277,6 +303,8 @@ impl AppState {
             semantic_cache: Arc::new(SemanticCacheState::new_test()),
             sandbox: SandboxState::new(),
             memory_pruning: Arc::new(MemoryPruningState::new_test()),
+            startup_report: Arc::new(RwLock::new(None)),
+            job_schedule: Arc::new(crate::schedule::JobSchedule::new()),
         }
     }
 }
@@ -347,6 +375,10 @@ impl HasAiGateway for AppState {
     fn ai_gateway(&self) -> &AiGatewayState {
         &self.ai_gateway
     }
+
+    fn gateway_db(&self) -> &sqlx::PgPool {
+        &self.base.db
+    }
 }
 
 // ── HasVaultBridge — Jaskier Vault client access ─────────────────────────────
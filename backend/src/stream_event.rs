@@ -0,0 +1,337 @@
+//! Canonical stream event — one shape for streamed output across providers,
+//! so frontends can share their rendering code.
+//!
+//! Every streaming path adds these fields to the frames it already sends
+//! (existing fields are kept for older clients):
+//! - `request_id` — correlation id of the request (`correlation.rs`), the
+//!   same for all frames of one response
+//! - `provider` — `anthropic`, `google`, `ollama`, a gateway provider id, ...
+//! - `delta` — text added by this frame, empty on the final one
+//! - `usage` — `{ input_tokens, output_tokens }`, on the final frame when the
+//!   provider reported it
+//! - `finish_reason` — on the final frame only: `stop`, `length`, `tool_use`,
+//!   `content_filter`, `cancelled` or `error` (`finish_reason`)
+//!
+//! Paths: the NDJSON chat stream (`handlers/streaming/anthropic.rs`,
+//! `gemini.rs`; lines of the shared `jaskier_core` tools handler are
+//! converted with `from_ndjson_line`), WebSocket `token` / `complete`
+//! messages, and the AI gateway SSE `token` / `stream_end` events
+//! (`ai_gateway/handlers/proxy.rs`, `compat_providers.rs`). Frames are built
+//! with the helpers below so the field set stays the same everywhere.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+/// Token usage of one response.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamEvent {
+    pub request_id: String,
+    pub provider: String,
+    #[serde(default)]
+    pub delta: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<String>,
+}
+
+impl StreamEvent {
+    /// A frame carrying `text`.
+    pub fn delta(request_id: &str, provider: &str, text: &str) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            provider: provider.to_string(),
+            delta: text.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// The final frame; `reason` is the provider's own stop reason.
+    pub fn finish(request_id: &str, provider: &str, reason: &str, usage: Option<Usage>) -> Self {
+        Self {
+            request_id: request_id.to_string(),
+            provider: provider.to_string(),
+            usage,
+            finish_reason: Some(finish_reason(reason)),
+            ..Self::default()
+        }
+    }
+
+    /// `legacy` (a JSON object) with the canonical fields added.
+    pub fn merged(&self, mut legacy: Value) -> Value {
+        if let (Some(obj), Value::Object(fields)) = (
+            legacy.as_object_mut(),
+            serde_json::to_value(self).unwrap_or_default(),
+        ) {
+            obj.extend(fields);
+        }
+        legacy
+    }
+}
+
+/// Correlation id of the current request — the `request_id` of its frames —
+/// or a fresh id outside a request scope.
+pub fn current_request_id() -> String {
+    crate::correlation::current().unwrap_or_else(crate::correlation::new_id)
+}
+
+/// NDJSON chat line carrying `text` (legacy `token` / `done`).
+pub fn ndjson_token(request_id: &str, provider: &str, text: &str) -> Value {
+    StreamEvent::delta(request_id, provider, text).merged(json!({ "token": text, "done": false }))
+}
+
+/// Final NDJSON chat line; `legacy` holds `model`, `total_tokens`, ...
+pub fn ndjson_done(event: &StreamEvent, token: &str, mut legacy: Value) -> Value {
+    if let Some(obj) = legacy.as_object_mut() {
+        obj.insert("token".to_string(), json!(token));
+        obj.insert("done".to_string(), json!(true));
+    }
+    event.merged(legacy)
+}
+
+/// Data of a gateway SSE `token` event.
+pub fn sse_token(request_id: &str, provider: &str, text: &str) -> Value {
+    StreamEvent::delta(request_id, provider, text).merged(json!({ "text": text }))
+}
+
+/// Data of a gateway SSE `stream_end` event.
+pub fn sse_end(event: &StreamEvent, model: &str, latency_ms: u64) -> Value {
+    event.merged(json!({ "model": model, "latency_ms": latency_ms }))
+}
+
+/// Canonical fields for a line of the shared Anthropic handler (legacy
+/// `token` / `done`, tool and fallback events), which reports no usage.
+pub fn from_ndjson_line(frame: &Value, request_id: &str, provider: &str) -> StreamEvent {
+    if frame.get("done").and_then(Value::as_bool) == Some(true) {
+        let reason = frame
+            .get("stop_reason")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        StreamEvent::finish(request_id, provider, reason, None)
+    } else {
+        let text = frame
+            .get("token")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        StreamEvent::delta(request_id, provider, text)
+    }
+}
+
+/// Canonical finish reason for a provider stop reason (Anthropic
+/// `stop_reason`, OpenAI `finish_reason`, Gemini `finishReason`, Ollama
+/// `done_reason`). Unknown reasons are passed through in lowercase.
+pub fn finish_reason(raw: &str) -> String {
+    let raw = raw.trim().to_lowercase();
+    match raw.as_str() {
+        "" | "end_turn" | "stop" | "stop_sequence" | "finish_reason_unspecified" => "stop",
+        "max_tokens" | "length" => "length",
+        "tool_use" | "tool_calls" | "function_call" => "tool_use",
+        "safety" | "recitation" | "blocklist" | "prohibited_content" | "spii"
+        | "content_filter" | "refusal" => "content_filter",
+        "cancelled" | "canceled" => "cancelled",
+        _ => return raw,
+    }
+    .to_string()
+}
+
+fn tokens(body: &Value, pointer: &str) -> Option<u64> {
+    body.pointer(pointer).and_then(Value::as_u64)
+}
+
+/// Usage reported in a complete (non-streamed) response body of any
+/// supported provider.
+pub fn response_usage(body: &Value) -> Option<Usage> {
+    [
+        ("/usage/input_tokens", "/usage/output_tokens"),
+        ("/usage/prompt_tokens", "/usage/completion_tokens"),
+        (
+            "/usageMetadata/promptTokenCount",
+            "/usageMetadata/candidatesTokenCount",
+        ),
+        ("/prompt_eval_count", "/eval_count"),
+    ]
+    .into_iter()
+    .find_map(|(input, output)| {
+        let (input, output) = (tokens(body, input), tokens(body, output));
+        (input.is_some() || output.is_some()).then(|| Usage {
+            input_tokens: input.unwrap_or(0),
+            output_tokens: output.unwrap_or(0),
+        })
+    })
+}
+
+/// Stop reason in a complete response body of any supported provider.
+pub fn response_finish_reason(body: &Value) -> Option<&str> {
+    [
+        "/stop_reason",
+        "/choices/0/finish_reason",
+        "/candidates/0/finishReason",
+        "/done_reason",
+    ]
+    .into_iter()
+    .find_map(|pointer| body.pointer(pointer).and_then(Value::as_str))
+}
+
+/// Fold an Anthropic streaming event into `usage`: `message_start` carries
+/// the input tokens, `message_delta` the output tokens so far.
+pub fn anthropic_usage(event: &Value, usage: &mut Usage) {
+    match event.get("type").and_then(Value::as_str) {
+        Some("message_start") => {
+            if let Some(input) = tokens(event, "/message/usage/input_tokens") {
+                usage.input_tokens = input;
+            }
+        }
+        Some("message_delta") => {
+            if let Some(output) = tokens(event, "/usage/output_tokens") {
+                usage.output_tokens = output;
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::WsServerMessage;
+
+    const CANONICAL: [&str; 5] = ["request_id", "provider", "delta", "usage", "finish_reason"];
+
+    fn canonical_keys(frame: &Value) -> Vec<&'static str> {
+        CANONICAL
+            .into_iter()
+            .filter(|k| frame.get(k).is_some())
+            .collect()
+    }
+
+    #[test]
+    fn provider_stop_reasons_map_to_canonical_ones() {
+        assert_eq!(finish_reason("end_turn"), "stop");
+        assert_eq!(finish_reason("STOP"), "stop");
+        assert_eq!(finish_reason("MAX_TOKENS"), "length");
+        assert_eq!(finish_reason("tool_calls"), "tool_use");
+        assert_eq!(finish_reason("SAFETY"), "content_filter");
+        assert_eq!(finish_reason("pause_turn"), "pause_turn");
+    }
+
+    #[test]
+    fn usage_and_reason_are_read_from_each_provider_shape() {
+        let anthropic = json!({
+            "stop_reason": "max_tokens",
+            "usage": { "input_tokens": 12, "output_tokens": 34 }
+        });
+        let openai = json!({
+            "choices": [{ "finish_reason": "stop" }],
+            "usage": { "prompt_tokens": 5, "completion_tokens": 7 }
+        });
+        let gemini = json!({
+            "candidates": [{ "finishReason": "STOP" }],
+            "usageMetadata": { "promptTokenCount": 3, "candidatesTokenCount": 4 }
+        });
+        let ollama = json!({ "done_reason": "length", "prompt_eval_count": 8, "eval_count": 9 });
+        let usage = |i, o| {
+            Some(Usage {
+                input_tokens: i,
+                output_tokens: o,
+            })
+        };
+        assert_eq!(response_usage(&anthropic), usage(12, 34));
+        assert_eq!(response_usage(&openai), usage(5, 7));
+        assert_eq!(response_usage(&gemini), usage(3, 4));
+        assert_eq!(response_usage(&ollama), usage(8, 9));
+        assert_eq!(response_usage(&json!({ "content": "hi" })), None);
+        assert_eq!(response_finish_reason(&anthropic), Some("max_tokens"));
+        assert_eq!(response_finish_reason(&gemini), Some("STOP"));
+        assert_eq!(response_finish_reason(&ollama), Some("length"));
+    }
+
+    #[test]
+    fn anthropic_stream_usage_accumulates_and_frames_keep_legacy_fields() {
+        let mut usage = Usage::default();
+        let start =
+            json!({ "type": "message_start", "message": { "usage": { "input_tokens": 40 } } });
+        anthropic_usage(&start, &mut usage);
+        anthropic_usage(
+            &json!({ "type": "message_delta", "usage": { "output_tokens": 9 } }),
+            &mut usage,
+        );
+        assert_eq!(
+            usage,
+            Usage {
+                input_tokens: 40,
+                output_tokens: 9
+            }
+        );
+
+        let frame = StreamEvent::delta("req-1", "google", "Hi").merged(json!({ "token": "Hi" }));
+        assert_eq!(frame["token"], "Hi");
+        assert_eq!(frame["delta"], "Hi");
+        assert!(frame.get("finish_reason").is_none());
+        let done = StreamEvent::finish("req-1", "google", "STOP", Some(usage)).merged(json!({}));
+        assert_eq!(done["finish_reason"], "stop");
+        assert_eq!(done["delta"], "");
+        assert_eq!(done["usage"]["output_tokens"], 9);
+    }
+
+    #[test]
+    fn every_producer_emits_the_same_fields() {
+        let ws = |msg: WsServerMessage| serde_json::to_value(msg).unwrap();
+        let deltas = [
+            ndjson_token("req-1", "google", "Hi"),
+            sse_token("req-1", "openrouter", "Hi"),
+            ws(WsServerMessage::Token {
+                content: "Hi".to_string(),
+                stream: StreamEvent::delta("req-1", "anthropic", "Hi"),
+            }),
+        ];
+        for frame in &deltas {
+            assert_eq!(canonical_keys(frame), ["request_id", "provider", "delta"]);
+            assert_eq!(frame["request_id"], "req-1");
+            assert_eq!(frame["delta"], "Hi");
+        }
+
+        let usage = Some(Usage {
+            input_tokens: 3,
+            output_tokens: 4,
+        });
+        let end = StreamEvent::finish("req-1", "anthropic", "end_turn", usage);
+        let finals = [
+            ndjson_done(&end, "", json!({ "model": "claude-sonnet-4-6" })),
+            sse_end(&end, "claude-sonnet-4-6", 12),
+            ws(WsServerMessage::Complete {
+                duration_ms: 12,
+                stream: end.clone(),
+            }),
+        ];
+        for frame in &finals {
+            assert_eq!(canonical_keys(frame), CANONICAL);
+            assert_eq!(frame["finish_reason"], "stop");
+            assert_eq!(frame["usage"]["output_tokens"], 4);
+        }
+    }
+
+    #[test]
+    fn shared_handler_lines_get_the_canonical_fields() {
+        let token = json!({ "token": "Hi", "done": false });
+        let event = from_ndjson_line(&token, "req-1", "anthropic");
+        assert_eq!(
+            canonical_keys(&event.merged(token)),
+            ["request_id", "provider", "delta"]
+        );
+        assert_eq!(event.delta, "Hi");
+
+        let tool = json!({ "type": "tool_call", "tool_name": "read_file" });
+        assert_eq!(from_ndjson_line(&tool, "req-1", "anthropic").delta, "");
+
+        let done = json!({ "token": "", "done": true, "model": "m", "total_tokens": 7 });
+        let frame = from_ndjson_line(&done, "req-1", "anthropic").merged(done);
+        assert_eq!(frame["finish_reason"], "stop");
+        assert_eq!(frame["total_tokens"], 7);
+    }
+}
@@ -1,18 +1,89 @@
 // ClaudeHydra Swarm IPC integration
 //
 // Wires jaskier-swarm into ClaudeHydra's AppState and router.
+//
+// CH-specific result paging (large swarm runs can return megabytes of
+// results; these let the frontend fetch a summary first and pull results
+// individually or in pages):
+// - GET /api/swarm/tasks/{id}/summary          — counts + per-result refs (no content)
+// - GET /api/swarm/tasks/{id}/results          — paged results (?offset=&limit=)
+// - GET /api/swarm/tasks/{id}/results/{index}  — single result
+// Running tasks are read from the in-memory map; finished ones from
+// `ch_swarm_tasks`, paged in the database. Results are redacted before they
+// are served (see src/redaction.rs).
+//
+// Dry-run planning (nothing is sent to peers until a plan is approved):
+// - POST /api/swarm/plan — per-step peers, estimated tokens / cost / time and
+//   files the prompt is likely to touch, plus the `/api/swarm/delegate` body
+//   to submit once the user approves (or edits) the plan. The plan is stored
+//   in `ch_swarm_plans` under its `plan_id`.
+// - GET  /api/swarm/plans/{id}          — stored plan and its status
+// - POST /api/swarm/plans/{id}/approve  — submit the delegate body, with
+//   optional `{ prompt?, pattern?, targets?, timeout_secs? }` edits applied
+// - POST /api/swarm/plans/{id}/reject   — drop a pending plan
+// Costs are estimated with the same prices as cost accounting (see
+// src/costs.rs) for the model each peer runs.
+//
+// Witcher sign commands: a plan prompt starting with
+// `/witcher <sign> [--providers=a,b] [--judge=x] [--timeout=secs] <prompt>`
+// picks the pattern from the sign and the peers from the options. Only the
+// explicit command routes — sign names elsewhere in a prompt are plain text.
+// Besides the built-in signs, user-defined ones from the Witcher router's
+// sign file (see src/witcher_router.rs) bring their own provider chain and
+// system prompt.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+use axum::Json;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{Request, StatusCode, header};
+use axum::routing::{get, post};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use tokio::sync::{RwLock, broadcast};
+use tower::ServiceExt;
 
 use jaskier_swarm::{
     SwarmEvent, SwarmOrchestrator, SwarmRegistry, SwarmTask,
     handlers::HasSwarmHub,
 };
 
+use crate::api_error::db_error;
+
+/// How long finished tasks stay in the in-memory map before compaction.
+/// Final task state is persisted to `swarm_db`, so the map only needs to
+/// hold finished entries long enough for pollers to observe completion.
+const FINISHED_TASK_RETENTION: Duration = Duration::from_secs(15 * 60);
+
+/// Default / maximum page size for `/api/swarm/tasks/{id}/results`.
+const DEFAULT_RESULTS_PAGE: usize = 5;
+const MAX_RESULTS_PAGE: usize = 50;
+
+/// Dry-run estimates: chars per token, assumed completion size, per-call overhead.
+const PLAN_CHARS_PER_TOKEN: usize = 4;
+const PLAN_OUTPUT_TOKENS: u64 = 1500;
+const PLAN_CALL_OVERHEAD_SECS: f64 = 2.0;
+const PLAN_DEFAULT_TIMEOUT_SECS: u64 = 120;
+
+/// Largest delegate response read back when a plan is approved.
+const MAX_DELEGATE_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// Peer id → (provider, model the peer runs, output tokens/sec). Prices come
+/// from `costs::cost_usd` for that model.
+const PEER_PROFILES: &[(&str, &str, &str, f64)] = &[
+    ("claudehydra", "anthropic", "claude-sonnet-4-6", 60.0),
+    ("geminihydra", "google", "gemini-2.5-flash", 120.0),
+    ("grokhydra", "xai", "grok-3", 60.0),
+    ("openaihydra", "openai", "gpt-4o", 80.0),
+    ("deepseekhydra", "deepseek", "deepseek-chat", 40.0),
+];
+/// Profile used for peers missing from `PEER_PROFILES`.
+const UNKNOWN_PEER_PROFILE: (&str, &str, f64) = ("unknown", "unknown", 50.0);
+
 /// Swarm state embedded in AppState.
 #[derive(Clone)]
 pub struct SwarmState {
@@ -42,6 +113,22 @@ impl SwarmState {
         }
     }
 
+    /// Remove finished tasks older than `FINISHED_TASK_RETENTION` from the
+    /// in-memory map. Returns the number of entries removed.
+    pub async fn compact_tasks(&self) -> usize {
+        let cutoff = chrono::Utc::now()
+            - chrono::Duration::from_std(FINISHED_TASK_RETENTION).unwrap_or_default();
+        let mut tasks = self.tasks.write().await;
+        let before = tasks.len();
+        tasks.retain(|_, t| t.completed_at.is_none_or(|at| at > cutoff));
+        before - tasks.len()
+    }
+
+    /// Number of tasks currently held in memory (for diagnostics).
+    pub async fn task_count(&self) -> usize {
+        self.tasks.read().await.len()
+    }
+
     /// Start background discovery loop (probes peers every 30s).
     pub fn start_discovery(&self) {
         let registry = self.registry.clone();
@@ -104,6 +191,255 @@ impl HasSwarmHub for crate::state::AppState {
     }
 }
 
+// ── Result paging ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct ResultsPageQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+/// CH-only swarm routes (result paging, dry-run planning). Merged next to `swarm_router`.
+pub fn swarm_results_router() -> Router<crate::state::AppState> {
+    Router::new()
+        .route("/api/swarm/plan", post(plan_swarm_task))
+        .route("/api/swarm/plans/{id}", get(get_plan))
+        .route("/api/swarm/plans/{id}/approve", post(approve_plan))
+        .route("/api/swarm/plans/{id}/reject", post(reject_plan))
+        .route("/api/swarm/tasks/{id}/summary", get(task_summary))
+        .route("/api/swarm/tasks/{id}/results", get(task_results_page))
+        .route("/api/swarm/tasks/{id}/results/{index}", get(task_result))
+}
+
+fn task_not_found(id: &str) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "task_not_found", "task_id": id })),
+    )
+}
+
+/// Runs `view` on a task in the in-memory map. Unless `finished` is set,
+/// only tasks still running qualify: finished ones are read from
+/// `ch_swarm_tasks`, where results are paged by the database.
+async fn memory_task<T>(
+    state: &crate::state::AppState,
+    id: &str,
+    finished: bool,
+    view: impl FnOnce(&SwarmTask) -> serde_json::Result<T>,
+) -> Result<Option<T>, (StatusCode, Json<Value>)> {
+    let tasks = state.swarm.tasks.read().await;
+    let Some(task) = tasks.get(id).filter(|t| finished || t.completed_at.is_none()) else {
+        return Ok(None);
+    };
+    view(task).map(Some).map_err(|e| {
+        tracing::error!("swarm: failed to serialize task {}: {}", id, e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({ "error": "serialization failed" })),
+        )
+    })
+}
+
+/// A lookup in `ch_swarm_tasks`; a failed query falls through to the
+/// in-memory map.
+fn persisted<T>(id: &str, row: Result<Option<T>, sqlx::Error>) -> Option<T> {
+    row.unwrap_or_else(|e| {
+        tracing::error!("swarm: failed to load task {}: {}", id, e);
+        None
+    })
+}
+
+/// Lightweight reference to one result — everything except the content body.
+fn result_ref(index: usize, result: &Value) -> Value {
+    json!({
+        "index": index,
+        "peer_id": result.get("peer_id"),
+        "status": result.get("status"),
+        "duration_ms": result.get("duration_ms"),
+        "content_bytes": result.get("content").and_then(|c| c.as_str()).map(str::len).unwrap_or(0),
+    })
+}
+
+/// Summary body: status fields plus the per-result refs.
+fn summary_json(id: &str, status: Value, pattern: Value, duration_ms: Value, refs: Vec<Value>) -> Value {
+    let success_count = refs
+        .iter()
+        .filter(|r| r.get("status").and_then(|s| s.as_str()) == Some("success"))
+        .count();
+    json!({
+        "task_id": id,
+        "status": status,
+        "pattern": pattern,
+        "duration_ms": duration_ms,
+        "results_count": refs.len(),
+        "success_count": success_count,
+        "results": refs,
+    })
+}
+
+/// Clamped (offset, limit) of a results page request.
+fn page_window(q: &ResultsPageQuery) -> (usize, usize) {
+    (
+        q.offset.unwrap_or(0).min(i32::MAX as usize),
+        q.limit.unwrap_or(DEFAULT_RESULTS_PAGE).clamp(1, MAX_RESULTS_PAGE),
+    )
+}
+
+/// Page body for `results`, the (redacted) entries from `offset` on.
+fn page_json(id: &str, offset: usize, limit: usize, total: usize, mut results: Vec<Value>) -> Value {
+    let offset = offset.min(total);
+    results.truncate(limit);
+    for result in &mut results {
+        crate::redaction::redact_value(result);
+    }
+    let end = offset + results.len();
+    json!({
+        "task_id": id,
+        "offset": offset,
+        "limit": limit,
+        "total": total,
+        "next_offset": if end < total { Some(end) } else { None },
+        "results": results,
+    })
+}
+
+async fn task_summary(
+    State(state): State<crate::state::AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let from_memory = |task: &SwarmTask| -> serde_json::Result<Value> {
+        let refs = task
+            .results
+            .iter()
+            .enumerate()
+            .map(|(i, r)| serde_json::to_value(r).map(|r| result_ref(i, &r)))
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        Ok(summary_json(
+            &id,
+            serde_json::to_value(&task.status)?,
+            serde_json::to_value(&task.pattern)?,
+            serde_json::to_value(task.duration_ms)?,
+            refs,
+        ))
+    };
+    if let Some(summary) = memory_task(&state, &id, false, from_memory).await? {
+        return Ok(Json(summary));
+    }
+
+    // Refs are built in the database, so result bodies never leave it.
+    let row: Option<(String, String, Option<i64>, Value)> = persisted(
+        &id,
+        sqlx::query_as(
+            "SELECT status, pattern, duration_ms, \
+                (SELECT COALESCE(jsonb_agg(jsonb_build_object( \
+                    'index', e.i - 1, \
+                    'peer_id', e.r->'peer_id', \
+                    'status', e.r->'status', \
+                    'duration_ms', e.r->'duration_ms', \
+                    'content_bytes', COALESCE(octet_length(e.r->>'content'), 0) \
+                 ) ORDER BY e.i), '[]'::jsonb) \
+                 FROM jsonb_array_elements(results) WITH ORDINALITY AS e(r, i)) \
+             FROM ch_swarm_tasks WHERE id = $1",
+        )
+        .bind(&id)
+        .fetch_optional(&state.db)
+        .await,
+    );
+    if let Some((status, pattern, duration_ms, refs)) = row {
+        let refs = match refs {
+            Value::Array(refs) => refs,
+            _ => Vec::new(),
+        };
+        return Ok(Json(summary_json(&id, json!(status), json!(pattern), json!(duration_ms), refs)));
+    }
+
+    memory_task(&state, &id, true, from_memory)
+        .await?
+        .map(Json)
+        .ok_or_else(|| task_not_found(&id))
+}
+
+async fn task_results_page(
+    State(state): State<crate::state::AppState>,
+    Path(id): Path<String>,
+    Query(q): Query<ResultsPageQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let (offset, limit) = page_window(&q);
+    // Only the requested slice is serialized.
+    let from_memory = |task: &SwarmTask| -> serde_json::Result<(usize, Vec<Value>)> {
+        let total = task.results.len();
+        let page = &task.results[offset.min(total)..(offset + limit).min(total)];
+        Ok((total, page.iter().map(serde_json::to_value).collect::<serde_json::Result<_>>()?))
+    };
+    if let Some((total, results)) = memory_task(&state, &id, false, from_memory).await? {
+        return Ok(Json(page_json(&id, offset, limit, total, results)));
+    }
+
+    let row: Option<(i32, Value)> = persisted(
+        &id,
+        sqlx::query_as(
+            "SELECT jsonb_array_length(results), \
+                (SELECT COALESCE(jsonb_agg(e.r ORDER BY e.i), '[]'::jsonb) \
+                 FROM jsonb_array_elements(results) WITH ORDINALITY AS e(r, i) \
+                 WHERE e.i > $2 AND e.i <= $2 + $3) \
+             FROM ch_swarm_tasks WHERE id = $1",
+        )
+        .bind(&id)
+        .bind(offset as i64)
+        .bind(limit as i64)
+        .fetch_optional(&state.db)
+        .await,
+    );
+    if let Some((total, results)) = row {
+        let results = match results {
+            Value::Array(results) => results,
+            _ => Vec::new(),
+        };
+        return Ok(Json(page_json(&id, offset, limit, total.max(0) as usize, results)));
+    }
+
+    memory_task(&state, &id, true, from_memory)
+        .await?
+        .map(|(total, results)| Json(page_json(&id, offset, limit, total, results)))
+        .ok_or_else(|| task_not_found(&id))
+}
+
+async fn task_result(
+    State(state): State<crate::state::AppState>,
+    Path((id, index)): Path<(String, usize)>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let from_memory = |task: &SwarmTask| -> serde_json::Result<Option<Value>> {
+        task.results.get(index).map(serde_json::to_value).transpose()
+    };
+    let result = match memory_task(&state, &id, false, from_memory).await? {
+        Some(result) => result,
+        None => {
+            let row: Option<(Option<Value>,)> = persisted(
+                &id,
+                sqlx::query_as("SELECT results -> $2 FROM ch_swarm_tasks WHERE id = $1")
+                    .bind(&id)
+                    .bind(i32::try_from(index).unwrap_or(i32::MAX))
+                    .fetch_optional(&state.db)
+                    .await,
+            );
+            match row {
+                Some((result,)) => result,
+                None => memory_task(&state, &id, true, from_memory)
+                    .await?
+                    .ok_or_else(|| task_not_found(&id))?,
+            }
+        }
+    };
+    let mut result = result.ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "result_not_found", "task_id": id, "index": index })),
+        )
+    })?;
+    crate::redaction::redact_value(&mut result);
+    Ok(Json(result))
+}
+
 /// MCP tool definition for `swarm_delegate_task`.
 ///
 /// Returns the tool definition JSON for inclusion in CH's tool executor.
@@ -150,3 +486,714 @@ pub fn swarm_delegate_tool_def() -> serde_json::Value {
         }
     })
 }
+
+// ── Witcher sign commands ────────────────────────────────────────────────
+
+/// `/witcher <sign>` → orchestration pattern.
+pub(crate) const WITCHER_SIGNS: &[(&str, &str)] = &[
+    ("aard", "parallel"),
+    ("igni", "fan_out"),
+    ("quen", "sequential"),
+    ("axii", "review"),
+];
+
+const WITCHER_USAGE: &str =
+    "usage: /witcher <aard|igni|quen|axii|custom sign> [--providers=a,b] [--judge=x] [--timeout=secs] <prompt>";
+
+/// A parsed `/witcher` command.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WitcherCommand {
+    pub sign: String,
+    pub pattern: &'static str,
+    /// Peer ids from `--providers` (plus the judge for axii); empty = all online peers.
+    pub targets: Vec<String>,
+    /// Reviewer peer from `--judge` (axii only).
+    pub judge: Option<String>,
+    pub timeout_secs: Option<u64>,
+    /// System prompt of a custom sign, put in front of the prompt.
+    pub system_prompt: Option<String>,
+    /// Prompt text after the command and its options.
+    pub prompt: String,
+}
+
+/// `gemini` → `geminihydra`; `hydra` / `claude` → `claudehydra`.
+pub(crate) fn witcher_peer_id(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    match name.as_str() {
+        "hydra" | "claude" => "claudehydra".to_string(),
+        n if n.ends_with("hydra") => name,
+        _ => format!("{}hydra", name),
+    }
+}
+
+/// First whitespace-separated token of `s` and the text after it.
+fn split_token(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    s.split_once(char::is_whitespace).unwrap_or((s, ""))
+}
+
+/// Parse an explicit `/witcher` command. `None` when `input` is not one.
+pub(crate) fn parse_witcher_command(input: &str) -> Option<Result<WitcherCommand, String>> {
+    let rest = input.trim_start().strip_prefix("/witcher")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(parse_witcher_args(rest))
+}
+
+fn parse_witcher_args(rest: &str) -> Result<WitcherCommand, String> {
+    let (sign, mut rest) = split_token(rest);
+    if sign.is_empty() {
+        return Err(WITCHER_USAGE.to_string());
+    }
+    let sign = sign.to_lowercase();
+    let builtin = WITCHER_SIGNS
+        .iter()
+        .find(|(name, _)| *name == sign)
+        .map(|(_, pattern)| *pattern);
+    let (pattern, targets, system_prompt) = match builtin {
+        Some(pattern) => (pattern, Vec::new(), None),
+        None => {
+            let custom = crate::witcher_router::WitcherRouter::custom_sign(&sign)
+                .ok_or_else(|| format!("unknown sign '{}' — {}", sign, WITCHER_USAGE))?;
+            (custom.pattern, custom.providers, custom.system_prompt)
+        }
+    };
+    let mut cmd = WitcherCommand {
+        sign,
+        pattern,
+        targets,
+        judge: None,
+        timeout_secs: None,
+        system_prompt,
+        prompt: String::new(),
+    };
+
+    // Options come first; the first non-option token (or `--`) starts the prompt.
+    loop {
+        let (token, after) = split_token(rest);
+        if token == "--" {
+            rest = after;
+            break;
+        }
+        let Some(option) = token.strip_prefix("--") else {
+            break;
+        };
+        let (key, value, after) = match option.split_once('=') {
+            Some((key, value)) => (key, value, after),
+            None => {
+                let (value, after) = split_token(after);
+                (option, value, after)
+            }
+        };
+        if value.is_empty() {
+            return Err(format!("--{} needs a value", key));
+        }
+        match key {
+            "providers" => {
+                cmd.targets = value
+                    .split(',')
+                    .filter(|p| !p.trim().is_empty())
+                    .map(witcher_peer_id)
+                    .collect();
+            }
+            "judge" => cmd.judge = Some(witcher_peer_id(value)),
+            "timeout" => {
+                cmd.timeout_secs = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("--timeout must be a number of seconds, got '{}'", value))?,
+                );
+            }
+            _ => return Err(format!("unknown option --{} — {}", key, WITCHER_USAGE)),
+        }
+        rest = after;
+    }
+
+    cmd.prompt = rest.trim().to_string();
+    if cmd.prompt.is_empty() {
+        return Err(format!("missing prompt — {}", WITCHER_USAGE));
+    }
+    if let Some(judge) = &cmd.judge {
+        if pattern != "review" {
+            return Err("--judge only applies to axii".to_string());
+        }
+        // review = first target works, second reviews.
+        if cmd.targets.len() != 1 {
+            return Err("axii with --judge needs exactly one provider".to_string());
+        }
+        cmd.targets.push(judge.clone());
+    }
+    Ok(cmd)
+}
+
+// ── Dry-run planning ─────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct SwarmPlanRequest {
+    pub prompt: String,
+    #[serde(default = "default_plan_pattern")]
+    pub pattern: String,
+    #[serde(default)]
+    pub targets: Vec<String>,
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub attachments: Vec<Value>,
+}
+
+fn default_plan_pattern() -> String {
+    "parallel".to_string()
+}
+
+/// One planned peer call.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanStep {
+    pub step: usize,
+    pub peer_id: String,
+    pub provider: String,
+    pub model_hint: String,
+    /// `worker` or `reviewer`.
+    pub role: String,
+    /// Steps whose output this step consumes.
+    pub depends_on: Vec<usize>,
+    pub est_input_tokens: u64,
+    pub est_output_tokens: u64,
+    pub est_cost_usd: f64,
+    pub est_seconds: f64,
+}
+
+fn peer_profile(peer_id: &str) -> (&'static str, &'static str, f64) {
+    PEER_PROFILES
+        .iter()
+        .find(|(id, ..)| id.eq_ignore_ascii_case(peer_id))
+        .map(|(_, provider, model, tps)| (*provider, *model, *tps))
+        .unwrap_or(UNKNOWN_PEER_PROFILE)
+}
+
+/// Build the step list for `pattern` over `peers` — pure, no I/O.
+pub(crate) fn build_plan_steps(pattern: &str, peers: &[String], prompt_tokens: u64, timeout_secs: u64) -> Vec<PlanStep> {
+    let step_for = |step: usize, peer: &str, role: &str, depends_on: Vec<usize>, input: u64| {
+        let (provider, model, tps) = peer_profile(peer);
+        let est_seconds = (PLAN_CALL_OVERHEAD_SECS + PLAN_OUTPUT_TOKENS as f64 / tps).min(timeout_secs as f64);
+        let cost = crate::costs::cost_usd(model, input as i64, PLAN_OUTPUT_TOKENS as i64);
+        PlanStep {
+            step,
+            peer_id: peer.to_string(),
+            provider: provider.to_string(),
+            model_hint: model.to_string(),
+            role: role.to_string(),
+            depends_on,
+            est_input_tokens: input,
+            est_output_tokens: PLAN_OUTPUT_TOKENS,
+            est_cost_usd: (cost * 10_000.0).round() / 10_000.0,
+            est_seconds: (est_seconds * 10.0).round() / 10.0,
+        }
+    };
+
+    match pattern {
+        // Each step receives the previous step's output as extra input.
+        "sequential" => peers
+            .iter()
+            .enumerate()
+            .map(|(i, peer)| {
+                let input = prompt_tokens + if i == 0 { 0 } else { PLAN_OUTPUT_TOKENS };
+                let deps = if i == 0 { vec![] } else { vec![i - 1] };
+                step_for(i, peer, "worker", deps, input)
+            })
+            .collect(),
+        // First target works, second (or the same one) reviews.
+        "review" => {
+            let Some(worker) = peers.first() else {
+                return Vec::new();
+            };
+            let reviewer = peers.get(1).unwrap_or(worker);
+            vec![
+                step_for(0, worker, "worker", vec![], prompt_tokens),
+                step_for(1, reviewer, "reviewer", vec![0], prompt_tokens + PLAN_OUTPUT_TOKENS),
+            ]
+        }
+        // parallel / fan_out: independent calls.
+        _ => peers
+            .iter()
+            .enumerate()
+            .map(|(i, peer)| step_for(i, peer, "worker", vec![], prompt_tokens))
+            .collect(),
+    }
+}
+
+/// Wall-clock estimate: parallel steps overlap, dependent steps add up.
+fn plan_total_seconds(steps: &[PlanStep]) -> f64 {
+    let mut finish = vec![0.0_f64; steps.len()];
+    for s in steps {
+        let start = s
+            .depends_on
+            .iter()
+            .filter_map(|d| finish.get(*d).copied())
+            .fold(0.0, f64::max);
+        finish[s.step] = start + s.est_seconds;
+    }
+    finish.into_iter().fold(0.0, f64::max)
+}
+
+/// File paths mentioned in the prompt — what the run is likely to read or edit.
+fn files_mentioned(prompt: &str) -> Vec<String> {
+    static FILE_RE: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let re = FILE_RE.get_or_init(|| {
+        regex::Regex::new(
+            r"(?:[A-Za-z]:)?[\w./\\-]*[\w-]+\.(?:rs|ts|tsx|js|jsx|py|go|java|kt|md|json|toml|ya?ml|sql|css|scss|html|sh|ps1)\b",
+        )
+        .expect("valid file regex")
+    });
+    let mut files: Vec<String> = re.find_iter(prompt).map(|m| m.as_str().to_string()).collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+async fn plan_swarm_task(
+    State(state): State<crate::state::AppState>,
+    Json(mut req): Json<SwarmPlanRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let witcher = match parse_witcher_command(&req.prompt) {
+        Some(Ok(cmd)) => {
+            req.prompt = match &cmd.system_prompt {
+                Some(system) => format!("{}\n\n{}", system, cmd.prompt),
+                None => cmd.prompt.clone(),
+            };
+            req.pattern = cmd.pattern.to_string();
+            if !cmd.targets.is_empty() {
+                req.targets = cmd.targets.clone();
+            }
+            req.timeout_secs = cmd.timeout_secs.or(req.timeout_secs);
+            Some(cmd)
+        }
+        Some(Err(e)) => {
+            return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": e }))));
+        }
+        None => None,
+    };
+    if req.prompt.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "prompt must not be empty" })),
+        ));
+    }
+    let pattern = req.pattern.to_lowercase();
+    if !matches!(pattern.as_str(), "parallel" | "sequential" | "review" | "fan_out") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "unknown pattern", "pattern": req.pattern })),
+        ));
+    }
+
+    let mut warnings = Vec::new();
+    let peers: Vec<String> = if req.targets.is_empty() {
+        state
+            .swarm
+            .registry
+            .discover()
+            .await
+            .into_iter()
+            .filter(|p| p.status == jaskier_swarm::PeerStatus::Online && p.id != "claudehydra")
+            .map(|p| p.id)
+            .collect()
+    } else {
+        req.targets.clone()
+    };
+    if peers.is_empty() {
+        warnings.push("no online peers — nothing would run".to_string());
+    }
+    for peer in &peers {
+        if !PEER_PROFILES.iter().any(|(id, ..)| id.eq_ignore_ascii_case(peer)) {
+            warnings.push(format!("{}: unknown peer, using default cost/latency profile", peer));
+        }
+    }
+
+    let timeout_secs = req.timeout_secs.unwrap_or(PLAN_DEFAULT_TIMEOUT_SECS);
+    let prompt_tokens = (req.prompt.len() / PLAN_CHARS_PER_TOKEN) as u64;
+    let steps = build_plan_steps(&pattern, &peers, prompt_tokens, timeout_secs);
+
+    let total_cost: f64 = steps.iter().map(|s| s.est_cost_usd).sum();
+    let total_tokens: u64 = steps.iter().map(|s| s.est_input_tokens + s.est_output_tokens).sum();
+
+    let plan_id = uuid::Uuid::new_v4();
+    let execute = json!({
+        "prompt": req.prompt,
+        "pattern": pattern,
+        "targets": peers,
+        "timeout_secs": timeout_secs,
+        "attachments": req.attachments,
+    });
+    let mut plan = json!({
+        "dry_run": true,
+        "plan_id": plan_id.to_string(),
+        "pattern": pattern,
+        "witcher": witcher,
+        "steps": steps,
+        "totals": {
+            "steps": steps.len(),
+            "est_tokens": total_tokens,
+            "est_cost_usd": (total_cost * 10_000.0).round() / 10_000.0,
+            "est_seconds": (plan_total_seconds(&steps) * 10.0).round() / 10.0,
+        },
+        "files_likely_touched": files_mentioned(&req.prompt),
+        "attachments": req.attachments.len(),
+        "warnings": warnings,
+        // Approve = `POST /api/swarm/plans/{plan_id}/approve`, or POST this
+        // body as-is (or edited) to the shared delegate endpoint.
+        "execute": {
+            "method": "POST",
+            "path": "/api/swarm/delegate",
+            "body": execute,
+        },
+    });
+
+    let stored = sqlx::query("INSERT INTO ch_swarm_plans (id, plan, request) VALUES ($1, $2, $3)")
+        .bind(plan_id)
+        .bind(&plan)
+        .bind(&execute)
+        .execute(&state.db)
+        .await;
+    if let Err(e) = stored {
+        tracing::warn!("swarm: failed to store plan {}: {}", plan_id, e);
+        plan["warnings"]
+            .as_array_mut()
+            .expect("warnings is an array")
+            .push(json!("plan could not be stored — submit `execute` directly"));
+    }
+    Ok(Json(plan))
+}
+
+fn plan_not_found(id: &uuid::Uuid) -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "plan_not_found", "plan_id": id })),
+    )
+}
+
+/// `GET /api/swarm/plans/{id}`
+async fn get_plan(
+    State(state): State<crate::state::AppState>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    type PlanRow = (String, Value, Value, Option<Value>, chrono::DateTime<chrono::Utc>, Option<chrono::DateTime<chrono::Utc>>);
+    let row: Option<PlanRow> = sqlx::query_as(
+        "SELECT status, plan, request, delegate_response, created_at, decided_at \
+         FROM ch_swarm_plans WHERE id = $1",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("swarm: plan store"))?;
+    let Some((status, plan, request, delegate_response, created_at, decided_at)) = row else {
+        return Err(plan_not_found(&id));
+    };
+    Ok(Json(json!({
+        "plan_id": id,
+        "status": status,
+        "plan": plan,
+        "request": request,
+        "delegate_response": delegate_response,
+        "created_at": created_at,
+        "decided_at": decided_at,
+    })))
+}
+
+/// Changes applied to a stored plan's delegate body on approval.
+#[derive(Debug, Default, Deserialize)]
+pub struct PlanEdits {
+    pub prompt: Option<String>,
+    pub pattern: Option<String>,
+    pub targets: Option<Vec<String>>,
+    pub timeout_secs: Option<u64>,
+}
+
+/// `request` with `edits` applied, or the reason the edits are invalid.
+fn apply_plan_edits(mut request: Value, edits: PlanEdits) -> Result<Value, String> {
+    if let Some(prompt) = edits.prompt {
+        if prompt.trim().is_empty() {
+            return Err("prompt must not be empty".to_string());
+        }
+        request["prompt"] = json!(prompt);
+    }
+    if let Some(pattern) = edits.pattern {
+        let pattern = pattern.to_lowercase();
+        if !matches!(pattern.as_str(), "parallel" | "sequential" | "review" | "fan_out") {
+            return Err(format!("unknown pattern: {}", pattern));
+        }
+        request["pattern"] = json!(pattern);
+    }
+    if let Some(targets) = edits.targets {
+        request["targets"] = json!(targets);
+    }
+    if let Some(timeout_secs) = edits.timeout_secs {
+        request["timeout_secs"] = json!(timeout_secs);
+    }
+    Ok(request)
+}
+
+/// Submit `body` to the shared `/api/swarm/delegate` handler in-process.
+async fn delegate(state: &crate::state::AppState, body: &Value) -> Result<(StatusCode, Value), String> {
+    let request = Request::post("/api/swarm/delegate")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .map_err(|e| e.to_string())?;
+    let response = jaskier_swarm::swarm_router::<crate::state::AppState>()
+        .with_state(state.clone())
+        .oneshot(request)
+        .await
+        .map_err(|e| e.to_string())?;
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), MAX_DELEGATE_RESPONSE_BYTES)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok((status, serde_json::from_slice(&bytes).unwrap_or(Value::Null)))
+}
+
+/// Put an approved plan back to `pending` after its delegation failed.
+async fn reopen_plan(db: &sqlx::PgPool, id: uuid::Uuid) {
+    let reopened = sqlx::query(
+        "UPDATE ch_swarm_plans SET status = 'pending', decided_at = NULL WHERE id = $1",
+    )
+    .bind(id)
+    .execute(db)
+    .await;
+    if let Err(e) = reopened {
+        tracing::warn!("swarm: failed to reopen plan {}: {}", id, e);
+    }
+}
+
+/// `POST /api/swarm/plans/{id}/approve` — body: optional `PlanEdits`.
+async fn approve_plan(
+    State(state): State<crate::state::AppState>,
+    Path(id): Path<uuid::Uuid>,
+    body: Option<Json<PlanEdits>>,
+) -> Result<(StatusCode, Json<Value>), (StatusCode, Json<Value>)> {
+    let edits = body.map(|Json(e)| e).unwrap_or_default();
+    // Claimed atomically so concurrent approvals cannot both delegate.
+    let request: Option<Value> = sqlx::query_scalar(
+        "UPDATE ch_swarm_plans SET status = 'approved', decided_at = NOW() \
+         WHERE id = $1 AND status = 'pending' RETURNING request",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("swarm: plan store"))?;
+    let Some(request) = request else {
+        return Err(plan_not_found(&id));
+    };
+    let request = match apply_plan_edits(request, edits) {
+        Ok(request) => request,
+        Err(e) => {
+            reopen_plan(&state.db, id).await;
+            return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": e }))));
+        }
+    };
+    let (status, response) = match delegate(&state, &request).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            reopen_plan(&state.db, id).await;
+            tracing::error!("swarm: delegating plan {} failed: {}", id, e);
+            return Err((
+                StatusCode::BAD_GATEWAY,
+                Json(json!({ "error": "delegate failed", "plan_id": id })),
+            ));
+        }
+    };
+    if !status.is_success() {
+        // Left pending so the plan can be fixed and approved again.
+        reopen_plan(&state.db, id).await;
+        return Ok((status, Json(response)));
+    }
+    sqlx::query("UPDATE ch_swarm_plans SET request = $2, delegate_response = $3 WHERE id = $1")
+        .bind(id)
+        .bind(&request)
+        .bind(&response)
+        .execute(&state.db)
+        .await
+        .map_err(db_error("swarm: plan store"))?;
+    Ok((status, Json(json!({ "plan_id": id, "status": "approved", "delegate": response }))))
+}
+
+/// `POST /api/swarm/plans/{id}/reject`
+async fn reject_plan(
+    State(state): State<crate::state::AppState>,
+    Path(id): Path<uuid::Uuid>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let result = sqlx::query(
+        "UPDATE ch_swarm_plans SET status = 'rejected', decided_at = NOW() \
+         WHERE id = $1 AND status = 'pending'",
+    )
+    .bind(id)
+    .execute(&state.db)
+    .await
+    .map_err(db_error("swarm: plan store"))?;
+    if result.rows_affected() == 0 {
+        return Err(plan_not_found(&id));
+    }
+    Ok(Json(json!({ "plan_id": id, "status": "rejected" })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer_result(peer: &str, status: &str, content: &str) -> Value {
+        json!({ "peer_id": peer, "status": status, "content": content, "duration_ms": 1200 })
+    }
+
+    #[test]
+    fn results_are_paged_with_a_next_offset() {
+        let results: Vec<Value> = (0..7)
+            .map(|i| peer_result(&format!("peer{}", i), "success", &format!("answer {}", i)))
+            .collect();
+        let q = ResultsPageQuery { offset: Some(2), limit: Some(3) };
+        let (offset, limit) = page_window(&q);
+        let page = page_json("t1", offset, limit, results.len(), results[offset..offset + limit].to_vec());
+        assert_eq!(page["total"], 7);
+        assert_eq!(page["next_offset"], 5);
+        let contents: Vec<&str> = page["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(contents, ["answer 2", "answer 3", "answer 4"]);
+
+        let last = page_json("t1", 5, limit, results.len(), results[5..].to_vec());
+        assert_eq!(last["results"].as_array().unwrap().len(), 2);
+        assert!(last["next_offset"].is_null());
+
+        let past_end = page_json("t1", 40, limit, results.len(), Vec::new());
+        assert_eq!(past_end["offset"], 7);
+        assert!(past_end["results"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn page_size_is_clamped() {
+        assert_eq!(page_window(&ResultsPageQuery { offset: None, limit: None }), (0, DEFAULT_RESULTS_PAGE));
+        assert_eq!(page_window(&ResultsPageQuery { offset: None, limit: Some(0) }).1, 1);
+        assert_eq!(page_window(&ResultsPageQuery { offset: None, limit: Some(500) }).1, MAX_RESULTS_PAGE);
+    }
+
+    #[test]
+    fn summary_counts_successes_without_content() {
+        let refs: Vec<Value> = [
+            peer_result("geminihydra", "success", "four bytes"),
+            peer_result("grokhydra", "error", ""),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, r)| result_ref(i, r))
+        .collect();
+        let summary = summary_json("t1", json!("completed"), json!("parallel"), json!(2400), refs);
+        assert_eq!(summary["results_count"], 2);
+        assert_eq!(summary["success_count"], 1);
+        assert_eq!(summary["results"][0]["content_bytes"], 10);
+        assert!(summary["results"][0].get("content").is_none());
+    }
+
+    #[test]
+    fn plan_costs_use_the_cost_accounting_prices() {
+        let peers = vec!["claudehydra".to_string(), "somehydra".to_string()];
+        let steps = build_plan_steps("parallel", &peers, 2_000, 120);
+        let expected = crate::costs::cost_usd("claude-sonnet-4-6", 2_000, PLAN_OUTPUT_TOKENS as i64);
+        assert_eq!(steps[0].model_hint, "claude-sonnet-4-6");
+        assert_eq!(steps[0].est_cost_usd, (expected * 10_000.0).round() / 10_000.0);
+        // Unknown peers still get an estimate.
+        assert_eq!(steps[1].provider, "unknown");
+        assert!(steps[1].est_cost_usd > 0.0);
+    }
+
+    #[test]
+    fn plan_edits_replace_only_what_they_set() {
+        let request = json!({
+            "prompt": "do it",
+            "pattern": "parallel",
+            "targets": ["geminihydra"],
+            "timeout_secs": 120,
+            "attachments": [],
+        });
+        let edits = PlanEdits {
+            pattern: Some("Review".to_string()),
+            targets: Some(vec!["geminihydra".to_string(), "grokhydra".to_string()]),
+            ..Default::default()
+        };
+        let edited = apply_plan_edits(request.clone(), edits).unwrap();
+        assert_eq!(edited["prompt"], "do it");
+        assert_eq!(edited["pattern"], "review");
+        assert_eq!(edited["targets"][1], "grokhydra");
+        assert_eq!(edited["timeout_secs"], 120);
+
+        let bad = PlanEdits { pattern: Some("chaos".to_string()), ..Default::default() };
+        assert!(apply_plan_edits(request.clone(), bad).is_err());
+        let empty = PlanEdits { prompt: Some("  ".to_string()), ..Default::default() };
+        assert!(apply_plan_edits(request, empty).is_err());
+    }
+
+    #[test]
+    fn only_the_explicit_command_routes() {
+        assert!(parse_witcher_command("use igni to burn through the aard tests").is_none());
+        assert!(parse_witcher_command("/witchers igni do it").is_none());
+        assert!(parse_witcher_command("/witcher").unwrap().is_err());
+    }
+
+    #[test]
+    fn witcher_options_are_parsed() {
+        let cmd = parse_witcher_command(
+            "/witcher AXII --providers=gemini --judge hydra --timeout=90 review src/main.rs",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(cmd.sign, "axii");
+        assert_eq!(cmd.pattern, "review");
+        assert_eq!(cmd.targets, vec!["geminihydra", "claudehydra"]);
+        assert_eq!(cmd.judge.as_deref(), Some("claudehydra"));
+        assert_eq!(cmd.timeout_secs, Some(90));
+        assert_eq!(cmd.prompt, "review src/main.rs");
+
+        let cmd = parse_witcher_command("/witcher aard --providers=gemini,deepseekhydra -- --flag in prompt")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cmd.pattern, "parallel");
+        assert_eq!(cmd.targets, vec!["geminihydra", "deepseekhydra"]);
+        assert_eq!(cmd.prompt, "--flag in prompt");
+    }
+
+    #[test]
+    fn invalid_witcher_commands_are_rejected() {
+        for bad in [
+            "/witcher yrden do it",
+            "/witcher igni --judge=hydra do it",
+            "/witcher axii --providers=gemini,deepseek --judge=hydra do it",
+            "/witcher quen --timeout=soon do it",
+            "/witcher quen --color=red do it",
+            "/witcher quen --providers=gemini",
+        ] {
+            assert!(parse_witcher_command(bad).unwrap().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn custom_signs_bring_their_providers_and_system_prompt() {
+        crate::witcher_router::set_custom_signs(vec![crate::witcher_router::CustomSign {
+            name: "Doc generator".to_string(),
+            trigger: "docgen".to_string(),
+            pattern: "sequential",
+            providers: vec!["geminihydra".to_string(), "claudehydra".to_string()],
+            system_prompt: Some("Write rustdoc.".to_string()),
+        }]);
+        let cmd = parse_witcher_command("/witcher docgen src/lib.rs")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cmd.pattern, "sequential");
+        assert_eq!(cmd.targets, vec!["geminihydra", "claudehydra"]);
+        assert_eq!(cmd.system_prompt.as_deref(), Some("Write rustdoc."));
+        assert_eq!(cmd.prompt, "src/lib.rs");
+
+        let cmd = parse_witcher_command("/witcher docgen --providers=deepseek src/lib.rs")
+            .unwrap()
+            .unwrap();
+        assert_eq!(cmd.targets, vec!["deepseekhydra"]);
+    }
+}
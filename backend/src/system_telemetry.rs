@@ -0,0 +1,232 @@
+//! Disk, network and load telemetry for `/api/system/stats` and
+//! `/api/system/metrics` — the part of "why is local inference slow" that
+//! CPU and memory do not show (a full data disk, a saturated link while a
+//! model downloads, a long run queue).
+//!
+//! - Disk: usage of the filesystem holding the data directory
+//!   (`CH_DATA_DIR`, see `metrics_snapshot`)
+//! - Network: bytes/s received and sent over all interfaces, measured between
+//!   calls over a window of at least `MIN_WINDOW` (`null` on the first call)
+//! - Load average: 1 / 5 / 15 minutes (`null` on Windows)
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sysinfo::{Disks, Networks, System};
+use utoipa::ToSchema;
+
+/// Shorter windows are dominated by counter granularity; callers in between
+/// get the previous rate.
+const MIN_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct DiskUsage {
+    /// Directory the usage is reported for.
+    pub path: String,
+    pub mount_point: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub used_percent: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct NetworkThroughput {
+    pub rx_bytes_per_sec: Option<f64>,
+    pub tx_bytes_per_sec: Option<f64>,
+    pub rx_total_bytes: u64,
+    pub tx_total_bytes: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct LoadAverage {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Telemetry {
+    pub disk: Option<DiskUsage>,
+    pub network: NetworkThroughput,
+    pub load_average: Option<LoadAverage>,
+}
+
+/// Mount whose point is the longest prefix of `path`.
+fn containing_mount<'a>(
+    path: &Path,
+    mounts: &'a [(PathBuf, u64, u64)],
+) -> Option<&'a (PathBuf, u64, u64)> {
+    mounts
+        .iter()
+        .filter(|(mount, ..)| path.starts_with(mount))
+        .max_by_key(|(mount, ..)| mount.components().count())
+}
+
+fn disk_usage(dir: &Path) -> Option<DiskUsage> {
+    // The data dir may not exist yet; its nearest existing ancestor is on
+    // the same filesystem.
+    let resolved = dir
+        .ancestors()
+        .find_map(|p| std::fs::canonicalize(p).ok())?;
+    let disks = Disks::new_with_refreshed_list();
+    let mounts: Vec<(PathBuf, u64, u64)> = disks
+        .list()
+        .iter()
+        .map(|d| {
+            (
+                d.mount_point().to_path_buf(),
+                d.total_space(),
+                d.available_space(),
+            )
+        })
+        .collect();
+    let (mount, total, available) = containing_mount(&resolved, &mounts)?;
+    let used = total.saturating_sub(*available);
+    Some(DiskUsage {
+        path: dir.display().to_string(),
+        mount_point: mount.display().to_string(),
+        total_bytes: *total,
+        available_bytes: *available,
+        used_percent: if *total > 0 {
+            used as f64 / *total as f64 * 100.0
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Counter totals at one point in time, with the rate measured up to it.
+#[derive(Debug, Clone, Copy)]
+struct NetSample {
+    at: Instant,
+    rx: u64,
+    tx: u64,
+    rate: Option<(f64, f64)>,
+}
+
+/// Next sample after reading `rx` / `tx` at `now`. Counter resets (an
+/// interface going away) yield no rate rather than a negative one.
+fn next_sample(prev: Option<NetSample>, now: Instant, rx: u64, tx: u64) -> NetSample {
+    let Some(prev) = prev else {
+        return NetSample {
+            at: now,
+            rx,
+            tx,
+            rate: None,
+        };
+    };
+    let elapsed = now.duration_since(prev.at);
+    if elapsed < MIN_WINDOW {
+        return prev;
+    }
+    let secs = elapsed.as_secs_f64();
+    let rate = (rx >= prev.rx && tx >= prev.tx)
+        .then(|| ((rx - prev.rx) as f64 / secs, (tx - prev.tx) as f64 / secs));
+    NetSample {
+        at: now,
+        rx,
+        tx,
+        rate,
+    }
+}
+
+static NETWORK: Mutex<Option<(Networks, NetSample)>> = Mutex::new(None);
+
+fn network_throughput() -> NetworkThroughput {
+    let mut guard = NETWORK.lock().unwrap_or_else(|e| e.into_inner());
+    let (networks, prev) = match guard.take() {
+        Some((mut networks, prev)) => {
+            networks.refresh(true);
+            (networks, Some(prev))
+        }
+        None => (Networks::new_with_refreshed_list(), None),
+    };
+    let (rx, tx) = networks
+        .list()
+        .values()
+        .fold((0u64, 0u64), |(rx, tx), data| {
+            (rx + data.total_received(), tx + data.total_transmitted())
+        });
+    let sample = next_sample(prev, Instant::now(), rx, tx);
+    *guard = Some((networks, sample));
+    NetworkThroughput {
+        rx_bytes_per_sec: sample.rate.map(|(rx, _)| rx),
+        tx_bytes_per_sec: sample.rate.map(|(_, tx)| tx),
+        rx_total_bytes: rx,
+        tx_total_bytes: tx,
+    }
+}
+
+fn load_average() -> Option<LoadAverage> {
+    if cfg!(windows) {
+        return None;
+    }
+    let load = System::load_average();
+    Some(LoadAverage {
+        one: load.one,
+        five: load.five,
+        fifteen: load.fifteen,
+    })
+}
+
+/// Current telemetry. Blocking (reads disk and interface lists).
+pub fn collect_blocking() -> Telemetry {
+    Telemetry {
+        disk: disk_usage(crate::metrics_snapshot::data_dir()),
+        network: network_throughput(),
+        load_average: load_average(),
+    }
+}
+
+/// Current telemetry, read off the async runtime.
+pub async fn collect() -> Telemetry {
+    tokio::task::spawn_blocking(collect_blocking)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::warn!("system_telemetry: {}", e);
+            Telemetry {
+                disk: None,
+                network: NetworkThroughput::default(),
+                load_average: None,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_dir_maps_to_the_deepest_mount() {
+        let mounts = vec![
+            (PathBuf::from("/"), 100, 50),
+            (PathBuf::from("/home"), 200, 20),
+            (PathBuf::from("/home/user/data"), 300, 30),
+        ];
+        let mount = |p: &str| containing_mount(Path::new(p), &mounts).map(|m| m.1);
+        assert_eq!(mount("/home/user/.claudehydra"), Some(200));
+        assert_eq!(mount("/home/user/data/hydra"), Some(300));
+        assert_eq!(mount("/var/lib"), Some(100));
+        // `/home/user/database` is not under `/home/user/data`.
+        assert_eq!(mount("/home/user/database"), Some(200));
+    }
+
+    #[test]
+    fn throughput_needs_a_window_and_ignores_resets() {
+        let t0 = Instant::now();
+        let first = next_sample(None, t0, 1_000, 500);
+        assert_eq!(first.rate, None);
+
+        let early = next_sample(Some(first), t0 + Duration::from_millis(200), 9_000, 900);
+        assert_eq!((early.rx, early.rate), (1_000, None));
+
+        let second = next_sample(Some(first), t0 + Duration::from_secs(2), 5_000, 1_500);
+        assert_eq!(second.rate, Some((2_000.0, 500.0)));
+
+        let reset = next_sample(Some(second), t0 + Duration::from_secs(4), 100, 1_600);
+        assert_eq!(reset.rate, None);
+        assert_eq!(reset.rx, 100);
+    }
+}
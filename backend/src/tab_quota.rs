@@ -0,0 +1,300 @@
+//! Tab quota — an optional token budget per session (tab), e.g. to bound an
+//! experiment.
+//!
+//! Every generation recorded against the session by cost accounting (see
+//! `costs.rs`) counts its input + output tokens towards the quota. When the
+//! count reaches the quota the tab is paused (`quota_exceeded`): the queue
+//! executor leaves its background prompts queued, and a `tab-quota-exceeded`
+//! event goes out on `/api/background-prompts/events`. A paused tab resumes
+//! only through an explicit raise (`raise_tab_quota`) or by removing the
+//! quota; setting a quota on a tab that is not paused restarts the count.
+//!
+//! Token counts are partly estimates and a running prompt is not interrupted,
+//! so the quota may be overshot by the prompt that crosses it.
+//!
+//! - `GET /api/sessions/{id}/quota`        — quota, used, remaining and state
+//! - `PUT /api/sessions/{id}/quota`        — `{ quota }` (tokens, `null` removes it)
+//! - `POST /api/sessions/{id}/quota/raise` — `{ by }` or `{ to }`: raise a quota
+//!   above the tokens used and resume the tab
+
+use axum::Json;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::api_error::{bad_request, db_error, parse_session_id};
+use crate::state::AppState;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+struct QuotaRow {
+    token_quota: Option<i64>,
+    quota_used_tokens: i64,
+    quota_exceeded_at: Option<DateTime<Utc>>,
+}
+
+/// `unlimited`, `active` or `quota_exceeded`.
+fn quota_state(row: &QuotaRow) -> &'static str {
+    match (row.token_quota, row.quota_exceeded_at) {
+        (None, _) => "unlimited",
+        (Some(_), Some(_)) => "quota_exceeded",
+        (Some(_), None) => "active",
+    }
+}
+
+fn quota_json(session_id: uuid::Uuid, row: &QuotaRow) -> Value {
+    json!({
+        "session_id": session_id,
+        "quota": row.token_quota,
+        "used": row.quota_used_tokens,
+        "remaining": row.token_quota.map(|q| (q - row.quota_used_tokens).max(0)),
+        "state": quota_state(row),
+        "exceeded_at": row.quota_exceeded_at,
+    })
+}
+
+/// Count `tokens` against the session's quota, pausing the tab when they
+/// use it up. Sessions without a quota are left alone.
+pub async fn consume(
+    db: &sqlx::PgPool,
+    session_id: uuid::Uuid,
+    tokens: i64,
+) -> Result<(), sqlx::Error> {
+    if tokens <= 0 {
+        return Ok(());
+    }
+    // Paused by this update: the count crossed the quota.
+    let paused: Option<(i64, i64)> = sqlx::query_as(
+        "UPDATE ch_sessions \
+         SET quota_used_tokens = quota_used_tokens + $2, \
+             quota_exceeded_at = CASE \
+                 WHEN quota_used_tokens + $2 >= token_quota \
+                 THEN COALESCE(quota_exceeded_at, NOW()) \
+                 ELSE quota_exceeded_at END \
+         WHERE id = $1 AND token_quota IS NOT NULL \
+         RETURNING token_quota, quota_used_tokens",
+    )
+    .bind(session_id)
+    .bind(tokens)
+    .fetch_optional(db)
+    .await?
+    .filter(|(quota, used)| used - tokens < *quota && used >= quota);
+    if let Some((quota, used)) = paused {
+        tracing::warn!(
+            "tab_quota: session {} used {} of {} tokens, paused",
+            session_id,
+            used,
+            quota
+        );
+        crate::queue::emit(
+            "tab-quota-exceeded",
+            json!({ "session_id": session_id, "quota": quota, "used": used }),
+        );
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn session_not_found() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Session not found" })),
+    )
+}
+
+async fn load(
+    db: &sqlx::PgPool,
+    session_id: uuid::Uuid,
+) -> Result<QuotaRow, (StatusCode, Json<Value>)> {
+    sqlx::query_as(
+        "SELECT token_quota, quota_used_tokens, quota_exceeded_at \
+         FROM ch_sessions WHERE id = $1",
+    )
+    .bind(session_id)
+    .fetch_optional(db)
+    .await
+    .map_err(db_error("tab_quota"))?
+    .ok_or_else(session_not_found)
+}
+
+/// `GET /api/sessions/{id}/quota`
+pub async fn get_tab_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let row = load(&state.db, session_id).await?;
+    Ok(Json(quota_json(session_id, &row)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetQuotaRequest {
+    /// Tokens; `None` removes the quota.
+    pub quota: Option<i64>,
+}
+
+/// `PUT /api/sessions/{id}/quota` — set or remove the quota and restart the
+/// count. A paused tab keeps its quota until it is raised or removed.
+pub async fn set_tab_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SetQuotaRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    if req.quota.is_some_and(|q| q <= 0) {
+        return Err(bad_request("quota must be a positive number of tokens"));
+    }
+    if req.quota.is_some()
+        && load(&state.db, session_id)
+            .await?
+            .quota_exceeded_at
+            .is_some()
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Tab is paused by its token quota — raise or remove the quota",
+                "code": "QUOTA_EXCEEDED",
+            })),
+        ));
+    }
+    let row: QuotaRow = sqlx::query_as(
+        "UPDATE ch_sessions \
+         SET token_quota = $2, quota_used_tokens = 0, quota_exceeded_at = NULL, \
+             updated_at = NOW() \
+         WHERE id = $1 \
+         RETURNING token_quota, quota_used_tokens, quota_exceeded_at",
+    )
+    .bind(session_id)
+    .bind(req.quota)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("tab_quota"))?
+    .ok_or_else(session_not_found)?;
+    tracing::info!("tab_quota: session {} quota → {:?}", session_id, req.quota);
+    crate::queue::wake();
+    Ok(Json(quota_json(session_id, &row)))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RaiseQuotaRequest {
+    /// Tokens to add to the current quota.
+    pub by: Option<i64>,
+    /// New quota.
+    pub to: Option<i64>,
+}
+
+/// New quota for `req`; it must leave room above the tokens already used.
+fn raised_quota(current: i64, used: i64, req: &RaiseQuotaRequest) -> Result<i64, String> {
+    let target = match (req.by, req.to) {
+        (Some(by), None) if by > 0 => current.saturating_add(by),
+        (None, Some(to)) if to > 0 => to,
+        (Some(_), None) | (None, Some(_)) => return Err("Amount must be positive".into()),
+        _ => return Err("Give exactly one of 'by' and 'to'".into()),
+    };
+    if target <= used {
+        return Err(format!(
+            "The new quota ({}) must exceed the {} tokens already used",
+            target, used
+        ));
+    }
+    Ok(target)
+}
+
+/// `POST /api/sessions/{id}/quota/raise` — `raise_tab_quota`: the only way
+/// to resume a tab paused by its quota.
+pub async fn raise_tab_quota(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<RaiseQuotaRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let current = load(&state.db, session_id).await?;
+    let Some(quota) = current.token_quota else {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Session has no token quota" })),
+        ));
+    };
+    let target = raised_quota(quota, current.quota_used_tokens, &req).map_err(bad_request)?;
+    let row: QuotaRow = sqlx::query_as(
+        "UPDATE ch_sessions \
+         SET token_quota = $3, quota_exceeded_at = NULL, updated_at = NOW() \
+         WHERE id = $1 AND token_quota = $2 AND quota_used_tokens < $3 \
+         RETURNING token_quota, quota_used_tokens, quota_exceeded_at",
+    )
+    .bind(session_id)
+    .bind(quota)
+    .bind(target)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(db_error("tab_quota"))?
+    .ok_or_else(|| {
+        (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Quota changed in the meantime, try again" })),
+        )
+    })?;
+    tracing::info!(
+        "tab_quota: session {} quota raised {} → {}",
+        session_id,
+        quota,
+        target
+    );
+    crate::queue::wake();
+    Ok(Json(quota_json(session_id, &row)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn raise_needs_one_positive_amount_above_usage() {
+        let by = |n| RaiseQuotaRequest {
+            by: Some(n),
+            to: None,
+        };
+        let to = |n| RaiseQuotaRequest {
+            by: None,
+            to: Some(n),
+        };
+        assert_eq!(raised_quota(1000, 1200, &by(500)), Ok(1500));
+        assert_eq!(raised_quota(1000, 1200, &to(5000)), Ok(5000));
+        assert!(raised_quota(1000, 1200, &by(100)).is_err());
+        assert!(raised_quota(1000, 1200, &to(1200)).is_err());
+        assert!(raised_quota(1000, 0, &by(0)).is_err());
+        assert!(raised_quota(1000, 0, &RaiseQuotaRequest::default()).is_err());
+        let both = RaiseQuotaRequest {
+            by: Some(1),
+            to: Some(2000),
+        };
+        assert!(raised_quota(1000, 0, &both).is_err());
+    }
+
+    #[test]
+    fn state_follows_quota_and_pause() {
+        let mut row = QuotaRow {
+            token_quota: None,
+            quota_used_tokens: 10,
+            quota_exceeded_at: None,
+        };
+        assert_eq!(quota_state(&row), "unlimited");
+        row.token_quota = Some(100);
+        assert_eq!(quota_state(&row), "active");
+        row.quota_exceeded_at = Some(Utc::now());
+        assert_eq!(quota_state(&row), "quota_exceeded");
+        let body = quota_json(
+            uuid::Uuid::nil(),
+            &QuotaRow {
+                quota_used_tokens: 140,
+                ..row
+            },
+        );
+        assert_eq!(body["remaining"], 0);
+    }
+}
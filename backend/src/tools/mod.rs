@@ -38,6 +38,23 @@ pub struct ToolExecutor {
     pub api_keys: HashMap<String, String>,
 }
 
+/// Directories filesystem access is limited to: `ALLOWED_FILE_DIRS`
+/// (`;`-separated), or the Desktop when unset.
+pub fn allowed_dirs_from_env() -> Vec<PathBuf> {
+    let dirs_str = std::env::var("ALLOWED_FILE_DIRS").unwrap_or_else(|_| {
+        dirs::desktop_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .to_string_lossy()
+            .to_string()
+    });
+
+    dirs_str
+        .split(';')
+        .filter(|s| !s.is_empty())
+        .map(|s| PathBuf::from(s.trim()))
+        .collect()
+}
+
 impl Default for ToolExecutor {
     fn default() -> Self {
         Self::new(reqwest::Client::new(), HashMap::new())
@@ -46,18 +63,7 @@ impl Default for ToolExecutor {
 
 impl ToolExecutor {
     pub fn new(http_client: reqwest::Client, api_keys: HashMap<String, String>) -> Self {
-        let dirs_str = std::env::var("ALLOWED_FILE_DIRS").unwrap_or_else(|_| {
-            dirs::desktop_dir()
-                .unwrap_or_else(|| PathBuf::from("."))
-                .to_string_lossy()
-                .to_string()
-        });
-
-        let allowed_dirs: Vec<PathBuf> = dirs_str
-            .split(';')
-            .filter(|s| !s.is_empty())
-            .map(|s| PathBuf::from(s.trim()))
-            .collect();
+        let allowed_dirs = allowed_dirs_from_env();
 
         tracing::info!("ToolExecutor: allowed_dirs = {:?}", allowed_dirs);
 
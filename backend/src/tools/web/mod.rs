@@ -151,12 +151,18 @@ pub fn tool_definitions() -> Vec<ToolDefinition> {
 //  Dispatcher
 // ═══════════════════════════════════════════════════════════════════════════
 
+/// `fetch_webpage` with a caller-supplied client, for callers outside the
+/// agent tool executor (gateway tools).
+pub async fn fetch_webpage(input: &Value, client: &reqwest::Client) -> (String, bool) {
+    match fetch::tool_fetch_webpage(input, client).await {
+        Ok(text) => (text, false),
+        Err(e) => (format!("TOOL_ERROR: {}", e), true),
+    }
+}
+
 pub async fn execute(tool_name: &str, input: &Value, state: &AppState) -> (String, bool) {
     match tool_name {
-        "fetch_webpage" => match fetch::tool_fetch_webpage(input, &state.http_client).await {
-            Ok(text) => (text, false),
-            Err(e) => (format!("TOOL_ERROR: {}", e), true),
-        },
+        "fetch_webpage" => fetch_webpage(input, &state.http_client).await,
         "crawl_website" => match crawl::tool_crawl_website(input, &state.http_client).await {
             Ok(text) => (text, false),
             Err(e) => (format!("TOOL_ERROR: {}", e), true),
@@ -0,0 +1,418 @@
+//! Speech-to-text for push-to-talk prompting.
+//!
+//! `transcribe_audio` accepts either a file path (checked against the
+//! allowed directories like every other file tool) or raw audio bytes and
+//! returns the transcript. Two backends:
+//! - `openai` — any OpenAI-compatible `/audio/transcriptions` endpoint
+//!   (OpenAI, Groq, a local faster-whisper server, ...).
+//! - `whisper_cpp` — the local whisper.cpp CLI, fully offline.
+//!
+//! With `query: true` the transcript is sent on as a prompt (same path as
+//! background prompts: server-side system prompt + auto-tier routing) and
+//! the reply is returned alongside it.
+//!
+//! Opt-in via environment or `[transcription]` in the config file
+//! (`hydra_config.rs`):
+//! - `CH_STT_BACKEND` — `openai` or `whisper_cpp`; transcription is disabled when unset.
+//! - `CH_STT_URL` — OpenAI-compatible base URL (default `https://api.openai.com/v1`).
+//! - `CH_STT_API_KEY` — bearer key (falls back to `OPENAI_API_KEY`; env only).
+//! - `CH_STT_MODEL` — model name for the endpoint (default `whisper-1`).
+//! - `CH_WHISPER_CPP_BIN` — whisper.cpp CLI (default `whisper-cli`, found via `paths.rs`).
+//! - `CH_WHISPER_CPP_MODEL` — ggml model file; required for `whisper_cpp`.
+//!
+//! - `POST /api/transcribe` — `{ path? | audio_base64 + filename?, language?, query?, model? }`
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::extract::State;
+use axum::http::StatusCode;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::bad_request;
+use crate::handlers::prompt::complete_prompt;
+use crate::hydra_config::{Derived, HydraConfig, env, text};
+use crate::state::AppState;
+use crate::tools::allowed_dirs_from_env;
+use crate::tools::fs_tools::validate_path;
+
+/// Upload limit of the OpenAI transcription endpoint; applied to both backends.
+pub const MAX_AUDIO_BYTES: usize = 25 * 1024 * 1024;
+/// Request body limit for `/api/transcribe` (base64 inflates by 4/3).
+pub const MAX_REQUEST_BYTES: usize = MAX_AUDIO_BYTES / 3 * 4 + 64 * 1024;
+const TRANSCRIBE_TIMEOUT: Duration = Duration::from_secs(300);
+const QUERY_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SttBackendKind {
+    OpenAiCompatible,
+    WhisperCpp,
+}
+
+#[derive(Debug, Clone)]
+pub struct SttConfig {
+    pub kind: SttBackendKind,
+    pub base_url: String,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub whisper_model: Option<PathBuf>,
+}
+
+static CONFIG: Derived<Option<Arc<SttConfig>>> = Derived::new(build_config);
+
+pub(crate) fn parse_backend(raw: &str) -> Option<SttBackendKind> {
+    match raw.trim().to_lowercase().as_str() {
+        "openai" => Some(SttBackendKind::OpenAiCompatible),
+        "whisper_cpp" | "whisper.cpp" => Some(SttBackendKind::WhisperCpp),
+        _ => None,
+    }
+}
+
+fn build_config(config: &HydraConfig) -> Option<Arc<SttConfig>> {
+    let file = &config.transcription;
+    let raw = text("CH_STT_BACKEND", file.backend.as_deref())?;
+    let Some(kind) = parse_backend(&raw) else {
+        tracing::warn!("transcription: unknown backend '{}' — disabled", raw);
+        return None;
+    };
+    let cfg = SttConfig {
+        kind,
+        base_url: text("CH_STT_URL", file.url.as_deref())
+            .unwrap_or_else(|| "https://api.openai.com/v1".to_string())
+            .trim_end_matches('/')
+            .to_string(),
+        api_key: env("CH_STT_API_KEY").or_else(|| env("OPENAI_API_KEY")),
+        model: text("CH_STT_MODEL", file.model.as_deref())
+            .unwrap_or_else(|| "whisper-1".to_string()),
+        whisper_model: text("CH_WHISPER_CPP_MODEL", file.whisper_model.as_deref())
+            .map(PathBuf::from),
+    };
+    if kind == SttBackendKind::WhisperCpp && cfg.whisper_model.is_none() {
+        tracing::warn!("transcription: whisper_cpp requires a model file (CH_WHISPER_CPP_MODEL) — disabled");
+        return None;
+    }
+    tracing::info!("transcription: {:?} backend enabled", kind);
+    Some(Arc::new(cfg))
+}
+
+/// Speech-to-text configuration (env overrides the file). `None` = disabled.
+pub fn config() -> Option<Arc<SttConfig>> {
+    CONFIG.get()
+}
+
+/// Audio to transcribe: a path on disk or an in-memory upload.
+#[derive(Debug, Clone)]
+pub enum AudioInput {
+    Path(String),
+    Bytes { data: Vec<u8>, filename: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    pub text: String,
+    pub backend: SttBackendKind,
+    pub duration_ms: u64,
+}
+
+/// Transcribe `input` with the configured backend. `language` is an
+/// ISO-639-1 hint (`en`, `pl`, ...); the backend auto-detects when omitted.
+pub async fn transcribe_audio(
+    client: &reqwest::Client,
+    input: AudioInput,
+    language: Option<&str>,
+) -> Result<Transcript, String> {
+    let cfg = config().ok_or("Transcription is not configured (set CH_STT_BACKEND)")?;
+    let started = Instant::now();
+
+    let text = match cfg.kind {
+        SttBackendKind::OpenAiCompatible => {
+            let (data, filename) = match input {
+                AudioInput::Path(raw) => {
+                    let path = checked_path(&raw).await?;
+                    let data = tokio::fs::read(&path)
+                        .await
+                        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+                    (data, file_name(&path))
+                }
+                AudioInput::Bytes { data, filename } => (data, filename),
+            };
+            check_size(data.len())?;
+            transcribe_openai(client, &cfg, data, filename, language).await?
+        }
+        SttBackendKind::WhisperCpp => match input {
+            AudioInput::Path(raw) => {
+                let path = checked_path(&raw).await?;
+                transcribe_whisper_cpp(&cfg, &path, language).await?
+            }
+            AudioInput::Bytes { data, filename } => {
+                check_size(data.len())?;
+                // The CLI only reads files; spool the upload to a temp file.
+                let tmp = std::env::temp_dir().join(format!(
+                    "ch-stt-{}.{}",
+                    uuid::Uuid::new_v4(),
+                    extension(&filename)
+                ));
+                tokio::fs::write(&tmp, &data)
+                    .await
+                    .map_err(|e| format!("Cannot spool audio: {}", e))?;
+                let result = transcribe_whisper_cpp(&cfg, &tmp, language).await;
+                let _ = tokio::fs::remove_file(&tmp).await;
+                result?
+            }
+        },
+    };
+
+    Ok(Transcript {
+        text,
+        backend: cfg.kind,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+async fn checked_path(raw: &str) -> Result<PathBuf, String> {
+    let path = validate_path(raw, &allowed_dirs_from_env())?;
+    let meta = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    check_size(meta.len() as usize)?;
+    Ok(path)
+}
+
+fn check_size(len: usize) -> Result<(), String> {
+    if len == 0 {
+        return Err("Audio is empty".to_string());
+    }
+    if len > MAX_AUDIO_BYTES {
+        return Err(format!(
+            "Audio is too large: {} bytes (max {})",
+            len, MAX_AUDIO_BYTES
+        ));
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "audio.wav".to_string())
+}
+
+/// Lowercase extension of an upload name (`wav` when missing or odd).
+fn extension(filename: &str) -> String {
+    Path::new(filename)
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .filter(|e| !e.is_empty() && e.len() <= 5 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .unwrap_or_else(|| "wav".to_string())
+}
+
+async fn transcribe_openai(
+    client: &reqwest::Client,
+    cfg: &SttConfig,
+    data: Vec<u8>,
+    filename: String,
+    language: Option<&str>,
+) -> Result<String, String> {
+    let mut form = reqwest::multipart::Form::new()
+        .part("file", reqwest::multipart::Part::bytes(data).file_name(filename))
+        .text("model", cfg.model.clone())
+        .text("response_format", "json");
+    if let Some(lang) = language {
+        form = form.text("language", lang.to_string());
+    }
+
+    let mut req = client
+        .post(format!("{}/audio/transcriptions", cfg.base_url))
+        .multipart(form)
+        .timeout(TRANSCRIBE_TIMEOUT);
+    if let Some(ref key) = cfg.api_key {
+        req = req.bearer_auth(key);
+    }
+    let resp = req
+        .send()
+        .await
+        .map_err(|e| format!("Transcription request failed: {}", e))?;
+    let status = resp.status();
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Invalid transcription response: {}", e))?;
+    if !status.is_success() {
+        let message = body
+            .pointer("/error/message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        return Err(format!("Transcription endpoint returned HTTP {}: {}", status.as_u16(), message));
+    }
+    body.get("text")
+        .and_then(|t| t.as_str())
+        .map(|t| t.trim().to_string())
+        .ok_or_else(|| "Transcription response has no text".to_string())
+}
+
+/// whisper.cpp CLI arguments: plain text on stdout, no timestamps or progress.
+fn whisper_args(model: &Path, audio: &Path, language: Option<&str>) -> Vec<String> {
+    let mut args = vec![
+        "-m".to_string(),
+        model.display().to_string(),
+        "-f".to_string(),
+        audio.display().to_string(),
+        "-nt".to_string(),
+        "-np".to_string(),
+    ];
+    if let Some(lang) = language {
+        args.extend(["-l".to_string(), lang.to_string()]);
+    }
+    args
+}
+
+async fn transcribe_whisper_cpp(cfg: &SttConfig, audio: &Path, language: Option<&str>) -> Result<String, String> {
+    let model = cfg
+        .whisper_model
+        .as_deref()
+        .ok_or("CH_WHISPER_CPP_MODEL is not set")?;
+    let bin = crate::paths::program(&crate::paths::WHISPER);
+    let child = tokio::process::Command::new(&bin)
+        .args(whisper_args(model, audio, language))
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(TRANSCRIBE_TIMEOUT, child)
+        .await
+        .map_err(|_| format!("whisper.cpp timed out after {}s", TRANSCRIBE_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run {}: {}", bin.display(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "whisper.cpp failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" "))
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handler
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct TranscribeRequest {
+    /// Audio file path (must be inside the allowed directories).
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Base64 audio bytes (alternative to `path`).
+    #[serde(default)]
+    pub audio_base64: Option<String>,
+    /// Upload name; its extension tells the backend the container format.
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Send the transcript on as a prompt and return the reply too.
+    #[serde(default)]
+    pub query: bool,
+    /// Model for the follow-up prompt (auto-tier routing when omitted).
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscribeResponse {
+    #[serde(flatten)]
+    pub transcript: Transcript,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<String>,
+}
+
+/// `POST /api/transcribe`
+pub async fn transcribe(
+    State(state): State<AppState>,
+    Json(req): Json<TranscribeRequest>,
+) -> Result<Json<TranscribeResponse>, (StatusCode, Json<Value>)> {
+    if config().is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Transcription is not configured (set CH_STT_BACKEND)" })),
+        ));
+    }
+    let input = match (req.path, req.audio_base64) {
+        (Some(path), None) => AudioInput::Path(path),
+        (None, Some(b64)) => AudioInput::Bytes {
+            data: base64::engine::general_purpose::STANDARD
+                .decode(b64.trim())
+                .map_err(|e| bad_request(format!("audio_base64 is not valid base64: {}", e)))?,
+            filename: req.filename.unwrap_or_else(|| "audio.wav".to_string()),
+        },
+        _ => return Err(bad_request("Provide exactly one of path or audio_base64")),
+    };
+
+    let transcript = transcribe_audio(&state.http_client, input, req.language.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::warn!("transcription: {}", e);
+            (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e })))
+        })?;
+
+    let response = if req.query && !transcript.text.is_empty() {
+        crate::idle_scavenger::mark_interactive();
+        let reply = complete_prompt(
+            &state,
+            &transcript.text,
+            req.model,
+            QUERY_TIMEOUT_SECS,
+            "transcription",
+        )
+        .await
+        .map_err(|e| {
+            // Transcripts refused by the safety guard are the caller's input, not a provider failure.
+            let (status, code) = match e.strip_prefix(crate::safety::BLOCKED_CODE) {
+                Some(_) => (StatusCode::UNPROCESSABLE_ENTITY, Some(crate::safety::BLOCKED_CODE)),
+                None => (StatusCode::BAD_GATEWAY, None),
+            };
+            (
+                status,
+                Json(json!({ "error": e, "code": code, "transcript": transcript.text })),
+            )
+        })?;
+        Some(reply)
+    } else {
+        None
+    };
+
+    Ok(Json(TranscribeResponse { transcript, response }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whisper_args_disable_timestamps_and_pass_language() {
+        let args = whisper_args(Path::new("/m/ggml-base.bin"), Path::new("/tmp/a.wav"), Some("pl"));
+        assert_eq!(args[..4], ["-m", "/m/ggml-base.bin", "-f", "/tmp/a.wav"]);
+        assert!(args.contains(&"-nt".to_string()));
+        assert_eq!(args[args.len() - 2..], ["-l", "pl"]);
+    }
+
+    #[test]
+    fn extension_falls_back_to_wav() {
+        assert_eq!(extension("clip.WEBM"), "webm");
+        assert_eq!(extension("noext"), "wav");
+        assert_eq!(extension("weird.a/b"), "wav");
+    }
+
+    #[test]
+    fn size_limits() {
+        assert!(check_size(0).is_err());
+        assert!(check_size(1024).is_ok());
+        assert!(check_size(MAX_AUDIO_BYTES + 1).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+//! Text-to-speech for assistant responses, played on the local machine.
+//!
+//! Pluggable backends (all external programs — nothing is linked in):
+//! - `sapi` — Windows SAPI via PowerShell `System.Speech`; speaks directly.
+//! - `edge_tts` — the `edge-tts` CLI (Microsoft neural voices, needs network).
+//! - `piper` — the `piper` CLI, fully offline; `voice` is the `.onnx` model.
+//!
+//! `edge_tts` and `piper` render to a temp file that is played with
+//! `CH_TTS_PLAYER`. Utterances never overlap: a global lock serialises them.
+//!
+//! Streaming mode (`StreamingSpeaker`) buffers token deltas and speaks each
+//! completed sentence while the model is still generating, so playback
+//! starts with the first sentence. Fenced code blocks are never read aloud.
+//!
+//! Opt-in via environment or `[tts]` in the config file (`hydra_config.rs`):
+//! - `CH_TTS_BACKEND` — `sapi`, `edge_tts` or `piper`; TTS is disabled when unset.
+//! - `CH_TTS_VOICE` — default voice (SAPI voice name, edge-tts short name,
+//!   or piper model path; `CH_PIPER_MODEL` is accepted as an alias).
+//! - `CH_TTS_PLAYER` — audio player command, file path appended
+//!   (default `ffplay -nodisp -autoexit -loglevel quiet`).
+//! - `CH_EDGE_TTS_BIN` / `CH_PIPER_BIN` — CLI paths (default: found via `paths.rs`).
+//!
+//! - `POST /api/tts/speak` — `{ text, voice? }`; returns when playback ends
+//! - WebSocket `execute` with `speak: true` — streaming mode
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use axum::Json;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, mpsc};
+
+use crate::hydra_config::{Derived, HydraConfig, env, text};
+
+const UTTERANCE_TIMEOUT: Duration = Duration::from_secs(120);
+const MAX_TEXT_CHARS: usize = 20_000;
+const DEFAULT_EDGE_VOICE: &str = "en-US-AriaNeural";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TtsBackend {
+    Sapi,
+    EdgeTts,
+    Piper,
+}
+
+#[derive(Debug, Clone)]
+pub struct TtsConfig {
+    pub backend: TtsBackend,
+    pub default_voice: Option<String>,
+    pub player: Vec<String>,
+}
+
+static CONFIG: Derived<Option<Arc<TtsConfig>>> = Derived::new(build_config);
+
+pub(crate) fn parse_backend(raw: &str) -> Option<TtsBackend> {
+    match raw.trim().to_lowercase().as_str() {
+        "sapi" => Some(TtsBackend::Sapi),
+        "edge_tts" | "edge-tts" => Some(TtsBackend::EdgeTts),
+        "piper" => Some(TtsBackend::Piper),
+        _ => None,
+    }
+}
+
+fn build_config(config: &HydraConfig) -> Option<Arc<TtsConfig>> {
+    let file = &config.tts;
+    let raw = text("CH_TTS_BACKEND", file.backend.as_deref())?;
+    let Some(backend) = parse_backend(&raw) else {
+        tracing::warn!("tts: unknown backend '{}' — disabled", raw);
+        return None;
+    };
+    let cfg = TtsConfig {
+        backend,
+        default_voice: env("CH_TTS_VOICE")
+            .or_else(|| env("CH_PIPER_MODEL"))
+            .or_else(|| file.voice.clone().filter(|v| !v.trim().is_empty())),
+        player: text("CH_TTS_PLAYER", file.player.as_deref())
+            .unwrap_or_else(|| "ffplay -nodisp -autoexit -loglevel quiet".to_string())
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+    };
+    if backend == TtsBackend::Piper && cfg.default_voice.is_none() {
+        tracing::warn!("tts: piper without a voice (CH_TTS_VOICE) — requests must pass a voice model");
+    }
+    tracing::info!("tts: {:?} backend enabled", backend);
+    Some(Arc::new(cfg))
+}
+
+/// TTS configuration (env overrides the file). `None` = TTS disabled.
+pub fn config() -> Option<Arc<TtsConfig>> {
+    CONFIG.get()
+}
+
+/// Serialises utterances so overlapping requests queue instead of talking
+/// over each other.
+static SPEAK_LOCK: LazyLock<Mutex<()>> = LazyLock::new(|| Mutex::new(()));
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Text preparation
+// ═══════════════════════════════════════════════════════════════════════
+
+static CODE_FENCE_RE: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"(?s)```.*?(```|$)").expect("valid regex"));
+static LINK_RE: LazyLock<regex::Regex> =
+    LazyLock::new(|| regex::Regex::new(r"\[([^\]]*)\]\([^)]*\)").expect("valid regex"));
+
+/// Markdown → plain speech: drops fenced code, keeps link text, strips
+/// emphasis / heading / quote markers and collapses whitespace.
+pub fn clean_for_speech(text: &str) -> String {
+    let text = CODE_FENCE_RE.replace_all(text, " ");
+    let text = LINK_RE.replace_all(&text, "$1");
+    text.chars()
+        .filter(|c| !matches!(c, '*' | '_' | '#' | '`' | '>' | '|'))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Removes and returns the longest prefix of `buf` that ends on a sentence
+/// boundary outside a code fence (`.`, `!`, `?` followed by whitespace, or a
+/// newline). `None` when no complete sentence is buffered yet.
+fn take_ready(buf: &mut String) -> Option<String> {
+    let bytes = buf.as_bytes();
+    let mut cut = None;
+    let mut in_fence = false;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i..].starts_with(b"```") {
+            in_fence = !in_fence;
+            i += 3;
+            continue;
+        }
+        if !in_fence {
+            let boundary = match bytes[i] {
+                b'\n' => true,
+                b'.' | b'!' | b'?' => bytes.get(i + 1).is_some_and(|n| n.is_ascii_whitespace()),
+                _ => false,
+            };
+            if boundary {
+                cut = Some(i + 1);
+            }
+        }
+        i += 1;
+    }
+    let cut = cut?;
+    let ready: String = buf.drain(..cut).collect();
+    Some(ready)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Synthesis + playback
+// ═══════════════════════════════════════════════════════════════════════
+
+async fn run(mut cmd: tokio::process::Command, stdin_text: Option<&str>, what: &str) -> Result<(), String> {
+    cmd.kill_on_drop(true)
+        .stdin(if stdin_text.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    let mut child = cmd.spawn().map_err(|e| format!("Failed to start {}: {}", what, e))?;
+    if let (Some(text), Some(mut stdin)) = (stdin_text, child.stdin.take()) {
+        stdin
+            .write_all(text.as_bytes())
+            .await
+            .map_err(|e| format!("Failed to feed {}: {}", what, e))?;
+    }
+    let output = tokio::time::timeout(UTTERANCE_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("{} timed out after {}s", what, UTTERANCE_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("{} failed: {}", what, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+async fn play(cfg: &TtsConfig, file: &Path) -> Result<(), String> {
+    let (program, args) = cfg.player.split_first().ok_or("CH_TTS_PLAYER is empty")?;
+    let mut cmd = tokio::process::Command::new(program);
+    cmd.args(args).arg(file);
+    run(cmd, None, "audio player").await
+}
+
+/// Speak one utterance with the configured backend (blocking until done).
+async fn speak_with(cfg: &TtsConfig, text: &str, voice: Option<&str>) -> Result<(), String> {
+    let voice = voice.or(cfg.default_voice.as_deref());
+    match cfg.backend {
+        TtsBackend::Sapi => {
+            // Text goes through stdin and the voice through an env var, so
+            // neither is ever interpolated into the PowerShell script.
+            let mut cmd = tokio::process::Command::new("powershell.exe");
+            cmd.args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "Add-Type -AssemblyName System.Speech; \
+                 $s = New-Object System.Speech.Synthesis.SpeechSynthesizer; \
+                 if ($env:CH_TTS_SAPI_VOICE) { $s.SelectVoice($env:CH_TTS_SAPI_VOICE) }; \
+                 $s.Speak([Console]::In.ReadToEnd())",
+            ])
+            .env("CH_TTS_SAPI_VOICE", voice.unwrap_or(""));
+            run(cmd, Some(text), "SAPI").await
+        }
+        TtsBackend::EdgeTts => {
+            let file = temp_audio("mp3");
+            let bin = crate::paths::program(&crate::paths::EDGE_TTS);
+            let mut cmd = tokio::process::Command::new(bin);
+            cmd.args(["--voice", voice.unwrap_or(DEFAULT_EDGE_VOICE), "--text", text, "--write-media"])
+                .arg(&file);
+            let result = match run(cmd, None, "edge-tts").await {
+                Ok(()) => play(cfg, &file).await,
+                Err(e) => Err(e),
+            };
+            let _ = tokio::fs::remove_file(&file).await;
+            result
+        }
+        TtsBackend::Piper => {
+            let model = voice.ok_or("piper needs a voice model (CH_TTS_VOICE or `voice`)")?;
+            let file = temp_audio("wav");
+            let mut cmd = tokio::process::Command::new(crate::paths::program(&crate::paths::PIPER));
+            cmd.args(["--model", model, "--output_file"]).arg(&file);
+            let result = match run(cmd, Some(text), "piper").await {
+                Ok(()) => play(cfg, &file).await,
+                Err(e) => Err(e),
+            };
+            let _ = tokio::fs::remove_file(&file).await;
+            result
+        }
+    }
+}
+
+fn temp_audio(ext: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ch-tts-{}.{}", uuid::Uuid::new_v4(), ext))
+}
+
+/// Speak `text` (markdown allowed) with an optional voice override.
+pub async fn speak_response(text: &str, voice: Option<&str>) -> Result<(), String> {
+    let cfg = config().ok_or("TTS is not configured (set CH_TTS_BACKEND)")?;
+    let speech = clean_for_speech(text);
+    if speech.is_empty() {
+        return Ok(());
+    }
+    let _guard = SPEAK_LOCK.lock().await;
+    speak_with(&cfg, &speech, voice).await
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  Streaming mode
+// ═══════════════════════════════════════════════════════════════════════
+
+/// Speaks a response sentence by sentence while it streams in.
+///
+/// Dropping the speaker without `finish()` (cancelled execution) aborts the
+/// playback task; the player process is killed with it.
+pub struct StreamingSpeaker {
+    buffer: String,
+    tx: mpsc::UnboundedSender<String>,
+    task: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl StreamingSpeaker {
+    /// `None` when TTS is not configured.
+    pub fn start(voice: Option<String>) -> Option<Self> {
+        let cfg = config()?;
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let task = tokio::spawn(async move {
+            let _guard = SPEAK_LOCK.lock().await;
+            while let Some(chunk) = rx.recv().await {
+                let speech = clean_for_speech(&chunk);
+                if speech.is_empty() {
+                    continue;
+                }
+                if let Err(e) = speak_with(&cfg, &speech, voice.as_deref()).await {
+                    tracing::warn!("tts: {}", e);
+                    break;
+                }
+            }
+        });
+        Some(Self {
+            buffer: String::new(),
+            tx,
+            task: Some(task),
+        })
+    }
+
+    /// Feed a token delta; completed sentences are queued for playback.
+    pub fn push(&mut self, delta: &str) {
+        self.buffer.push_str(delta);
+        if let Some(ready) = take_ready(&mut self.buffer) {
+            let _ = self.tx.send(ready);
+        }
+    }
+
+    /// Queue the remaining text and let playback run to completion.
+    pub fn finish(mut self) {
+        let rest = std::mem::take(&mut self.buffer);
+        if !rest.trim().is_empty() {
+            let _ = self.tx.send(rest);
+        }
+        // Detach: the task ends once the queue drains and `tx` is dropped.
+        self.task.take();
+    }
+}
+
+impl Drop for StreamingSpeaker {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handler
+// ═══════════════════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct SpeakRequest {
+    pub text: String,
+    #[serde(default)]
+    pub voice: Option<String>,
+}
+
+/// `POST /api/tts/speak`
+pub async fn speak(Json(req): Json<SpeakRequest>) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    if config().is_none() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "TTS is not configured (set CH_TTS_BACKEND)" })),
+        ));
+    }
+    if req.text.chars().count() > MAX_TEXT_CHARS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": format!("text is too long (max {} chars)", MAX_TEXT_CHARS) })),
+        ));
+    }
+    let started = Instant::now();
+    speak_response(&req.text, req.voice.as_deref()).await.map_err(|e| {
+        tracing::warn!("tts: {}", e);
+        (StatusCode::UNPROCESSABLE_ENTITY, Json(json!({ "error": e })))
+    })?;
+    Ok(Json(json!({ "spoken": true, "duration_ms": started.elapsed().as_millis() as u64 })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn take_ready_waits_for_sentence_boundary() {
+        let mut buf = "Hello there. How are".to_string();
+        assert_eq!(take_ready(&mut buf).as_deref(), Some("Hello there."));
+        assert_eq!(buf, " How are");
+        assert_eq!(take_ready(&mut buf), None);
+        // A decimal point is not a boundary.
+        let mut buf = "Version 4.0 is".to_string();
+        assert_eq!(take_ready(&mut buf), None);
+    }
+
+    #[test]
+    fn take_ready_holds_open_code_fences() {
+        let mut buf = "Run this:\n```sh\nmake. all\n".to_string();
+        assert_eq!(take_ready(&mut buf).as_deref(), Some("Run this:\n"));
+        assert!(take_ready(&mut buf).is_none());
+        buf.push_str("```\nDone.\n");
+        assert!(take_ready(&mut buf).unwrap().ends_with("Done.\n"));
+    }
+
+    #[test]
+    fn clean_for_speech_strips_markdown() {
+        assert_eq!(
+            clean_for_speech("## Title\n**Bold** and [docs](https://x.y).\n```rs\nfn main() {}\n```\nEnd"),
+            "Title Bold and docs. End"
+        );
+    }
+}
@@ -27,16 +27,39 @@ pub fn spawn(state: AppState) -> tokio::task::JoinHandle<()> {
             if !api_ok {
                 tracing::warn!("watchdog: Anthropic API check failed");
             }
+            compact_in_memory_queues(&state).await;
+            state.job_schedule.record_run(crate::schedule::JOB_WATCHDOG);
         }
     });
 
     shared_handle
 }
 
+/// Drop ghost entries from in-memory maps that are otherwise only skipped
+/// on lookup: cancelled A2A tokens, finished swarm tasks and expired PKCE states.
+async fn compact_in_memory_queues(state: &AppState) {
+    let cancelled = {
+        let mut tokens = state.a2a_cancel_tokens.write().await;
+        let before = tokens.len();
+        tokens.retain(|_, t| !t.is_cancelled());
+        before - tokens.len()
+    };
+    let swarm_removed = state.swarm.compact_tasks().await;
+    state.ai_gateway.oauth_manager.cleanup_expired_states().await;
+
+    if cancelled > 0 || swarm_removed > 0 {
+        tracing::debug!(
+            cancelled_tokens = cancelled,
+            swarm_tasks = swarm_removed,
+            "watchdog: compacted in-memory queues"
+        );
+    }
+}
+
 /// Check Anthropic API reachability.
 /// Uses a lightweight HEAD request to api.anthropic.com (no tokens consumed).
 /// Skips if no credential is available (Vault, OAuth token, or API key).
-async fn check_anthropic_api(state: &AppState) -> bool {
+pub(crate) async fn check_anthropic_api(state: &AppState) -> bool {
     // Check if we have a credential configured from ANY source:
     // 1. Vault (ai_providers/anthropic_max)
     let has_vault = match state.vault_client().get("ai_providers", "anthropic_max").await {
@@ -0,0 +1,314 @@
+//! Web search augmentation — an optional pre-processing step that fetches
+//! fresh results for prompts that need current information and injects them
+//! into the system prompt. The sources are returned alongside the answer
+//! (`ChatResponse.sources`, WebSocket `sources` event) for citation display.
+//!
+//! Opt-in via environment or `[web_search]` in the config file
+//! (`hydra_config.rs`):
+//! - `CH_WEB_SEARCH_PROVIDER` — `searxng`, `brave` or `serper`; disabled
+//!   when unset.
+//! - `CH_WEB_SEARCH_URL` — SearxNG instance base URL (required for
+//!   `searxng`, e.g. `http://localhost:8888`).
+//! - `CH_WEB_SEARCH_API_KEY` — API key for Brave / Serper (env only).
+//! - `CH_WEB_SEARCH_MAX_RESULTS` — results injected per prompt (default 5).
+//! - `CH_WEB_SEARCH_AUTO` — `1` to also search prompts without the flag when
+//!   `needs_fresh_information` matches (default off).
+//!
+//! Whether a prompt is searched: the request's `web_search` flag wins when
+//! set; without it only auto mode searches, on whole-word time markers.
+//!
+//! Wired into `POST /api/claude/chat` and the WebSocket execute path. The
+//! NDJSON stream (`POST /api/claude/chat/stream`) does not search: it has no
+//! frame to return the sources in yet.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use utoipa::ToSchema;
+
+use crate::hydra_config::{Derived, HydraConfig, env, flag, number, text};
+
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_SNIPPET_CHARS: usize = 500;
+const MAX_QUERY_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchProvider {
+    SearxNg,
+    Brave,
+    Serper,
+}
+
+#[derive(Debug, Clone)]
+pub struct WebSearchConfig {
+    pub provider: SearchProvider,
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub max_results: usize,
+    /// Search unflagged prompts that look time-sensitive.
+    pub auto: bool,
+}
+
+static CONFIG: Derived<Option<Arc<WebSearchConfig>>> = Derived::new(build_config);
+
+pub(crate) fn parse_provider(raw: &str) -> Option<SearchProvider> {
+    match raw.trim().to_lowercase().as_str() {
+        "searxng" => Some(SearchProvider::SearxNg),
+        "brave" => Some(SearchProvider::Brave),
+        "serper" => Some(SearchProvider::Serper),
+        _ => None,
+    }
+}
+
+fn build_config(config: &HydraConfig) -> Option<Arc<WebSearchConfig>> {
+    let file = &config.web_search;
+    let raw = text("CH_WEB_SEARCH_PROVIDER", file.provider.as_deref())?;
+    let Some(provider) = parse_provider(&raw) else {
+        tracing::warn!("web_search: unknown provider '{}' — disabled", raw);
+        return None;
+    };
+    let cfg = WebSearchConfig {
+        provider,
+        base_url: text("CH_WEB_SEARCH_URL", file.url.as_deref())
+            .map(|u| u.trim_end_matches('/').to_string()),
+        api_key: env("CH_WEB_SEARCH_API_KEY"),
+        max_results: number("CH_WEB_SEARCH_MAX_RESULTS", file.max_results)
+            .unwrap_or(5)
+            .clamp(1, 10),
+        auto: flag("CH_WEB_SEARCH_AUTO", file.auto).unwrap_or(false),
+    };
+    if provider == SearchProvider::SearxNg && cfg.base_url.is_none() {
+        tracing::warn!("web_search: searxng requires a URL (CH_WEB_SEARCH_URL) — disabled");
+        return None;
+    }
+    if provider != SearchProvider::SearxNg && cfg.api_key.is_none() {
+        tracing::warn!("web_search: {:?} requires CH_WEB_SEARCH_API_KEY — disabled", provider);
+        return None;
+    }
+    tracing::info!(
+        "web_search: {:?} enabled ({} results, auto {})",
+        provider,
+        cfg.max_results,
+        if cfg.auto { "on" } else { "off" }
+    );
+    Some(Arc::new(cfg))
+}
+
+/// Search configuration (env overrides the file). `None` = augmentation disabled.
+pub fn config() -> Option<Arc<WebSearchConfig>> {
+    CONFIG.get()
+}
+
+/// A search result cited by a response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebSource {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+}
+
+/// Whether `needle` occurs in `haystack` as whole words; a trailing `*`
+/// makes it a word prefix (Polish stems).
+fn contains_words(haystack: &str, needle: &str) -> bool {
+    let (needle, prefix) = match needle.strip_suffix('*') {
+        Some(stem) => (stem, true),
+        None => (needle, false),
+    };
+    haystack.match_indices(needle).any(|(at, _)| {
+        let before = haystack[..at].chars().next_back();
+        let after = haystack[at + needle.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric)
+            && (prefix || !after.is_some_and(char::is_alphanumeric))
+    })
+}
+
+/// Heuristic: does the prompt ask about something recent or time-sensitive?
+pub fn needs_fresh_information(prompt: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "latest", "newest", "today", "yesterday", "this week", "this month", "this year",
+        "right now", "currently", "current version", "recent", "news", "release notes",
+        "just released", "announced", "price of", "stock price", "weather", "score",
+        "najnowsz*", "dzisiaj", "aktualn*", "wiadomości",
+    ];
+    let lower = prompt.to_lowercase();
+    if MARKERS.iter().any(|m| contains_words(&lower, m)) {
+        return true;
+    }
+    // Mentions of the current or previous year.
+    let year = chrono::Utc::now().format("%Y").to_string();
+    let prev_year = (year.parse::<i32>().unwrap_or(0) - 1).to_string();
+    contains_words(&lower, &year) || contains_words(&lower, &prev_year)
+}
+
+/// Whether to search for this prompt: the explicit flag, else the heuristic
+/// when auto mode is on.
+pub fn should_search(prompt: &str, flag: Option<bool>) -> bool {
+    config().is_some_and(|cfg| flag.unwrap_or_else(|| cfg.auto && needs_fresh_information(prompt)))
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_string(),
+    }
+}
+
+/// Run a search with the configured provider.
+pub async fn search(
+    client: &reqwest::Client,
+    cfg: &WebSearchConfig,
+    query: &str,
+) -> Result<Vec<WebSource>, String> {
+    let query = truncate_chars(query.trim(), MAX_QUERY_CHARS);
+    let req = match cfg.provider {
+        SearchProvider::SearxNg => client
+            .get(format!("{}/search", cfg.base_url.as_deref().unwrap_or_default()))
+            .query(&[("q", query.as_str()), ("format", "json")]),
+        SearchProvider::Brave => client
+            .get("https://api.search.brave.com/res/v1/web/search")
+            .query(&[("q", query.as_str()), ("count", &cfg.max_results.to_string())])
+            .header("X-Subscription-Token", cfg.api_key.as_deref().unwrap_or_default())
+            .header("Accept", "application/json"),
+        SearchProvider::Serper => client
+            .post("https://google.serper.dev/search")
+            .header("X-API-KEY", cfg.api_key.as_deref().unwrap_or_default())
+            .json(&json!({ "q": query, "num": cfg.max_results })),
+    };
+    let resp = req
+        .timeout(SEARCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Search request failed: {}", e))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("Search provider returned HTTP {}", status.as_u16()));
+    }
+    let body: Value = resp
+        .json()
+        .await
+        .map_err(|e| format!("Search provider returned invalid JSON: {}", e))?;
+    let mut sources = parse_results(cfg.provider, &body);
+    sources.truncate(cfg.max_results);
+    Ok(sources)
+}
+
+/// Normalize a provider response into `WebSource`s.
+fn parse_results(provider: SearchProvider, body: &Value) -> Vec<WebSource> {
+    let (items, url_key, snippet_key) = match provider {
+        SearchProvider::SearxNg => (body.get("results"), "url", "content"),
+        SearchProvider::Brave => (body.pointer("/web/results"), "url", "description"),
+        SearchProvider::Serper => (body.get("organic"), "link", "snippet"),
+    };
+    items
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| {
+                    let url = item.get(url_key)?.as_str()?.to_string();
+                    let title = item.get("title").and_then(|t| t.as_str()).unwrap_or(&url);
+                    let snippet = item.get(snippet_key).and_then(|s| s.as_str()).unwrap_or("");
+                    Some(WebSource {
+                        title: title.to_string(),
+                        url,
+                        snippet: truncate_chars(snippet, MAX_SNIPPET_CHARS),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// System prompt section listing numbered sources for citation.
+pub fn format_context(sources: &[WebSource]) -> String {
+    let mut out = format!(
+        "\n\n## Web search results ({})\nUse these results for up-to-date facts and cite them as [n]:\n",
+        chrono::Utc::now().format("%Y-%m-%d")
+    );
+    for (i, s) in sources.iter().enumerate() {
+        out.push_str(&format!("\n[{}] {} — {}\n{}\n", i + 1, s.title, s.url, s.snippet));
+    }
+    out
+}
+
+/// Search for `prompt` when warranted and append the results to
+/// `system_prompt`. Returns the injected sources; search failures are
+/// logged and leave the prompt unchanged.
+pub async fn augment_system_prompt(
+    client: &reqwest::Client,
+    system_prompt: &mut String,
+    prompt: &str,
+    flag: Option<bool>,
+) -> Vec<WebSource> {
+    let Some(cfg) = config() else {
+        return Vec::new();
+    };
+    if !should_search(prompt, flag) {
+        return Vec::new();
+    }
+    match search(client, &cfg, prompt).await {
+        Ok(sources) => {
+            if !sources.is_empty() {
+                system_prompt.push_str(&format_context(&sources));
+            }
+            sources
+        }
+        Err(e) => {
+            tracing::warn!("web_search: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_time_sensitive_prompts() {
+        assert!(needs_fresh_information("What is the latest Rust release?"));
+        assert!(needs_fresh_information("Any news about Tokio?"));
+        let year = chrono::Utc::now().format("%Y").to_string();
+        assert!(needs_fresh_information(&format!("Best laptops of {}", year)));
+        assert!(!needs_fresh_information("Refactor this function to use iterators"));
+    }
+
+    #[test]
+    fn markers_match_whole_words_only() {
+        assert!(!needs_fresh_information("Rename underscore_case fields"));
+        assert!(!needs_fresh_information("Fix the newsletter template"));
+        assert!(!needs_fresh_information("Port 20251 is taken"));
+        assert!(needs_fresh_information("What's the score?"));
+        assert!(needs_fresh_information("Jakie są najnowsze wersje?"));
+    }
+
+    #[test]
+    fn parses_each_provider() {
+        let searx = json!({ "results": [{ "title": "A", "url": "https://a", "content": "aa" }] });
+        let brave = json!({ "web": { "results": [{ "title": "B", "url": "https://b", "description": "bb" }] } });
+        let serper = json!({ "organic": [{ "title": "C", "link": "https://c", "snippet": "cc" }] });
+        assert_eq!(parse_results(SearchProvider::SearxNg, &searx)[0].snippet, "aa");
+        assert_eq!(parse_results(SearchProvider::Brave, &brave)[0].url, "https://b");
+        assert_eq!(parse_results(SearchProvider::Serper, &serper)[0].url, "https://c");
+        assert!(parse_results(SearchProvider::Serper, &searx).is_empty());
+    }
+
+    #[test]
+    fn results_without_url_are_dropped() {
+        let body = json!({ "results": [{ "title": "no url" }, { "url": "https://x" }] });
+        let sources = parse_results(SearchProvider::SearxNg, &body);
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].title, "https://x");
+    }
+
+    #[test]
+    fn context_numbers_sources() {
+        let ctx = format_context(&[
+            WebSource { title: "A".into(), url: "https://a".into(), snippet: String::new() },
+            WebSource { title: "B".into(), url: "https://b".into(), snippet: String::new() },
+        ]);
+        assert!(ctx.contains("[1] A — https://a"));
+        assert!(ctx.contains("[2] B — https://b"));
+    }
+}
@@ -0,0 +1,676 @@
+//! Witcher router — per-prompt provider/model routing for sessions in
+//! "Witcher mode".
+//!
+//! A session with `witcher_mode` set does not use a fixed model for its
+//! background prompts: when the queue executor claims a prompt of such a
+//! session that has no explicit model, `WitcherRouter::route` picks one from
+//! the prompt itself:
+//! - task type — `code` or `general` (`ollama_models::task_kind`)
+//! - complexity — `prompt_complexity` (keyword classification of the
+//!   prompt's instructions)
+//! - `simple` prompts go to the local Ollama model in `CH_WITCHER_LOCAL_MODEL`
+//!   (or `routing.witcher_local_model` in the config file, see
+//!   `hydra_config.rs`) when set (`auto` = best installed model that fits
+//!   in memory, see `ollama_models.rs`); everything else gets the auto-tier
+//!   model (`auto_tier_model`)
+//! - a prompt whose provider has reached its budget cap (`budget.rs`) goes to
+//!   the local model instead, when one is configured or installed
+//!
+//! The decision is stored on the prompt (`route_decision`) and in the routing
+//! history (`ch_witcher_routing`). When the run finishes the history row gets
+//! its latency, success and estimated cost, priced like every other request
+//! by `costs.rs` (local Ollama runs are free).
+//!
+//! Custom signs for the `/witcher <sign>` command (see `swarm.rs`) are
+//! declared in `<working dir>/.hydra/witcher-signs.toml` (or the file in
+//! `CH_WITCHER_SIGNS_FILE`):
+//!
+//! ```toml
+//! [signs.docgen]
+//! name = "Doc generator"           # display name, defaults to the key
+//! trigger = "docgen"               # `/witcher docgen ...`, defaults to the key
+//! providers = ["gemini", "hydra"]  # provider chain (peers), in order
+//! pattern = "sequential"           # default; or parallel / fan_out / review
+//! system_prompt = "Write rustdoc for the given files."
+//! ```
+//!
+//! The file is loaded at startup and re-read whenever it changes (checked
+//! every `SIGNS_RELOAD_INTERVAL`). A file that fails to parse keeps the
+//! previously loaded signs; triggers of built-in signs cannot be redefined.
+//!
+//! - `GET /api/sessions/{id}/witcher-mode`    — `{ enabled }`
+//! - `PUT /api/sessions/{id}/witcher-mode`    — `{ enabled }`
+//! - `GET /api/witcher/routing-stats`         — decisions per provider and task type, plus recent ones
+//! - `GET /api/witcher/routing-analytics`     — `?range=24h|7d|30d`: success rate, latency and cost
+//!   per provider / task type
+//! - `GET /api/witcher/signs`                 — built-in and custom signs (plus sign file errors)
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, SystemTime};
+
+use axum::Json;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::api_error::{db_error, parse_session_id};
+use crate::state::AppState;
+
+const RECENT_IN_STATS: i64 = 20;
+const SIGNS_FILE: &str = ".hydra/witcher-signs.toml";
+pub const JOB_SIGNS_RELOAD: &str = "witcher_signs_reload";
+pub const SIGNS_RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where one prompt was routed and why.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDecision {
+    /// Provider lane (`anthropic`, `ollama`, ...).
+    pub provider: String,
+    pub model: String,
+    pub task_type: String,
+    pub complexity: String,
+    pub reason: String,
+}
+
+/// A user-defined `/witcher` sign.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CustomSign {
+    pub name: String,
+    /// Word after `/witcher` (lowercase).
+    pub trigger: String,
+    pub pattern: &'static str,
+    /// Peer ids, in chain order; empty = all online peers.
+    pub providers: Vec<String>,
+    pub system_prompt: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct SignsState {
+    signs: Vec<CustomSign>,
+    /// File and modification time the signs were loaded from.
+    loaded: Option<(PathBuf, Option<SystemTime>)>,
+    error: Option<String>,
+}
+
+static SIGNS: LazyLock<RwLock<SignsState>> = LazyLock::new(|| RwLock::new(SignsState::default()));
+
+#[derive(Debug, Deserialize)]
+struct SignsToml {
+    #[serde(default)]
+    signs: BTreeMap<String, RawSign>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSign {
+    name: Option<String>,
+    trigger: Option<String>,
+    #[serde(default)]
+    providers: Vec<String>,
+    pattern: Option<String>,
+    system_prompt: Option<String>,
+}
+
+/// Custom signs declared in a sign file. Errors name the offending sign.
+fn parse_signs(text: &str) -> Result<Vec<CustomSign>, String> {
+    let file: SignsToml = toml::from_str(text).map_err(|e| e.to_string())?;
+    let mut signs: Vec<CustomSign> = Vec::new();
+    for (key, raw) in file.signs {
+        let trigger = raw.trigger.as_deref().unwrap_or(&key).trim().to_lowercase();
+        if trigger.is_empty()
+            || !trigger
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("sign '{}': invalid trigger '{}'", key, trigger));
+        }
+        if crate::swarm::WITCHER_SIGNS
+            .iter()
+            .any(|(name, _)| *name == trigger)
+        {
+            return Err(format!("sign '{}': '{}' is a built-in sign", key, trigger));
+        }
+        if signs.iter().any(|s| s.trigger == trigger) {
+            return Err(format!(
+                "sign '{}': trigger '{}' is used twice",
+                key, trigger
+            ));
+        }
+        let pattern_name = raw
+            .pattern
+            .as_deref()
+            .unwrap_or("sequential")
+            .to_lowercase();
+        let pattern = crate::swarm::WITCHER_SIGNS
+            .iter()
+            .map(|(_, pattern)| *pattern)
+            .find(|p| *p == pattern_name)
+            .ok_or_else(|| format!("sign '{}': unknown pattern '{}'", key, pattern_name))?;
+        signs.push(CustomSign {
+            name: raw.name.unwrap_or_else(|| key.clone()),
+            trigger,
+            pattern,
+            providers: raw
+                .providers
+                .iter()
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| crate::swarm::witcher_peer_id(p))
+                .collect(),
+            system_prompt: raw.system_prompt.filter(|p| !p.trim().is_empty()),
+        });
+    }
+    Ok(signs)
+}
+
+/// Replace the loaded custom signs.
+pub(crate) fn set_custom_signs(signs: Vec<CustomSign>) {
+    SIGNS.write().unwrap_or_else(|e| e.into_inner()).signs = signs;
+}
+
+async fn signs_path(db: &sqlx::PgPool) -> PathBuf {
+    if let Some(file) = std::env::var("CH_WITCHER_SIGNS_FILE")
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+    {
+        return PathBuf::from(file);
+    }
+    let global_wd: Option<String> =
+        sqlx::query_scalar("SELECT working_directory FROM ch_settings WHERE id = 1")
+            .fetch_optional(db)
+            .await
+            .ok()
+            .flatten()
+            .filter(|wd: &String| !wd.trim().is_empty());
+    match global_wd {
+        Some(wd) => PathBuf::from(wd).join(SIGNS_FILE),
+        None => PathBuf::from(SIGNS_FILE),
+    }
+}
+
+/// Re-read the sign file when it moved or changed since the last load.
+async fn reload_signs(db: &sqlx::PgPool) {
+    let path = signs_path(db).await;
+    let modified = tokio::fs::metadata(&path)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok());
+    let current = Some((path.clone(), modified));
+    if SIGNS.read().unwrap_or_else(|e| e.into_inner()).loaded == current {
+        return;
+    }
+    let parsed = match modified {
+        None => Ok(Vec::new()),
+        Some(_) => match tokio::fs::read_to_string(&path).await {
+            Ok(text) => parse_signs(&text),
+            Err(e) => Err(e.to_string()),
+        },
+    };
+    let mut state = SIGNS.write().unwrap_or_else(|e| e.into_inner());
+    state.loaded = current;
+    match parsed {
+        Ok(signs) => {
+            if modified.is_some() {
+                tracing::info!(
+                    "witcher_router: loaded {} custom sign(s) from {}",
+                    signs.len(),
+                    path.display()
+                );
+            }
+            state.signs = signs;
+            state.error = None;
+        }
+        Err(e) => {
+            tracing::warn!(
+                "witcher_router: {}: {} — keeping previous signs",
+                path.display(),
+                e
+            );
+            state.error = Some(format!("{}: {}", path.display(), e));
+        }
+    }
+}
+
+/// Load custom signs and keep them in sync with the sign file.
+pub fn spawn(state: AppState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SIGNS_RELOAD_INTERVAL);
+        loop {
+            ticker.tick().await;
+            state.job_schedule.record_run(JOB_SIGNS_RELOAD);
+            reload_signs(&state.db).await;
+        }
+    });
+}
+
+pub(crate) fn local_model() -> Option<String> {
+    std::env::var("CH_WITCHER_LOCAL_MODEL")
+        .ok()
+        .or_else(|| {
+            crate::hydra_config::current()
+                .routing
+                .witcher_local_model
+                .clone()
+        })
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty())
+}
+
+pub struct WitcherRouter;
+
+impl WitcherRouter {
+    /// Pick provider and model for `prompt`.
+    pub async fn route(state: &AppState, prompt: &str) -> RouteDecision {
+        let kind = crate::ollama_models::task_kind(prompt);
+        let task_type = kind.as_str().to_string();
+        let complexity = crate::handlers::prompt::prompt_complexity(prompt);
+        let local = match local_model() {
+            Some(local) if complexity == "simple" && local == "auto" => {
+                crate::ollama_models::best_model(state, kind)
+                    .await
+                    .map(|m| {
+                        let reason = format!(
+                            "simple {} prompt, best installed local model",
+                            kind.as_str()
+                        );
+                        (m, reason)
+                    })
+            }
+            Some(local) if complexity == "simple" => {
+                Some((local, "simple prompt, local model configured".to_string()))
+            }
+            _ => None,
+        };
+        let (mut model, mut reason) = match local {
+            Some((local, reason)) => (format!("{}{}", crate::queue::OLLAMA_PREFIX, local), reason),
+            None => (
+                crate::handlers::prompt::auto_tier_model(state, complexity).await,
+                format!("auto-tier model for {} prompts", complexity),
+            ),
+        };
+        // Budget cap reached: shift to a free local model
+        let provider = crate::queue::lane_of(Some(&model));
+        if let Some(over) = crate::budget::exceeded(&state.db, provider).await
+            && let Some(local) = crate::budget::local_model(state, prompt).await
+        {
+            reason = format!("{}, free local model", over.message());
+            model = local;
+        }
+        RouteDecision {
+            provider: crate::queue::lane_of(Some(&model)).to_string(),
+            model,
+            task_type,
+            complexity: complexity.to_string(),
+            reason,
+        }
+    }
+
+    /// Custom sign with this trigger.
+    pub fn custom_sign(trigger: &str) -> Option<CustomSign> {
+        SIGNS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .signs
+            .iter()
+            .find(|s| s.trigger == trigger)
+            .cloned()
+    }
+
+    /// Store a decision that was applied to `prompt_id` in the routing history.
+    pub async fn record(db: &sqlx::PgPool, prompt_id: i64, decision: &RouteDecision) {
+        let inserted = sqlx::query(
+            "INSERT INTO ch_witcher_routing \
+                 (prompt_id, provider, model, task_type, complexity, reason) \
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(prompt_id)
+        .bind(&decision.provider)
+        .bind(&decision.model)
+        .bind(&decision.task_type)
+        .bind(&decision.complexity)
+        .bind(&decision.reason)
+        .execute(db)
+        .await;
+        if let Err(e) = inserted {
+            tracing::warn!(
+                "witcher_router: failed to record decision for {}: {}",
+                prompt_id,
+                e
+            );
+        }
+    }
+
+    /// Complete the open history row of `prompt_id` with the run's outcome.
+    pub async fn record_outcome(
+        db: &sqlx::PgPool,
+        prompt_id: i64,
+        success: bool,
+        latency_ms: i64,
+        cost_usd: Option<f64>,
+    ) {
+        let updated = sqlx::query(
+            "UPDATE ch_witcher_routing SET success = $2, latency_ms = $3, cost_usd = $4 \
+             WHERE id = (SELECT id FROM ch_witcher_routing \
+                         WHERE prompt_id = $1 AND success IS NULL \
+                         ORDER BY decided_at DESC LIMIT 1)",
+        )
+        .bind(prompt_id)
+        .bind(success)
+        .bind(latency_ms)
+        .bind(cost_usd)
+        .execute(db)
+        .await;
+        if let Err(e) = updated {
+            tracing::warn!(
+                "witcher_router: failed to record outcome for {}: {}",
+                prompt_id,
+                e
+            );
+        }
+    }
+}
+
+/// Look-back window of an analytics `range`, in hours.
+fn range_hours(raw: Option<&str>) -> Option<(&'static str, i32)> {
+    match raw.unwrap_or("24h") {
+        "24h" => Some(("24h", 24)),
+        "7d" => Some(("7d", 24 * 7)),
+        "30d" => Some(("30d", 24 * 30)),
+        _ => None,
+    }
+}
+
+/// Share of finished runs that succeeded; `None` before any finished.
+fn success_rate(succeeded: i64, finished: i64) -> Option<f64> {
+    (finished > 0).then(|| succeeded as f64 / finished as f64)
+}
+
+/// Whether the session routes its prompts through the Witcher router.
+pub async fn session_enabled(db: &sqlx::PgPool, session_id: uuid::Uuid) -> bool {
+    sqlx::query_scalar("SELECT witcher_mode FROM ch_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(db)
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+}
+
+// ═══════════════════════════════════════════════════════════════════════
+//  HTTP handlers
+// ═══════════════════════════════════════════════════════════════════════
+
+fn session_not_found() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Session not found" })),
+    )
+}
+
+/// `GET /api/sessions/{id}/witcher-mode`
+pub async fn get_witcher_mode(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let enabled: bool = sqlx::query_scalar("SELECT witcher_mode FROM ch_sessions WHERE id = $1")
+        .bind(session_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(db_error("witcher_router"))?
+        .ok_or_else(session_not_found)?;
+    Ok(Json(json!({ "session_id": id, "enabled": enabled })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetWitcherModeRequest {
+    pub enabled: bool,
+}
+
+/// `PUT /api/sessions/{id}/witcher-mode`
+pub async fn set_witcher_mode(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<SetWitcherModeRequest>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let session_id = parse_session_id(&id)?;
+    let updated =
+        sqlx::query("UPDATE ch_sessions SET witcher_mode = $1, updated_at = NOW() WHERE id = $2")
+            .bind(req.enabled)
+            .bind(session_id)
+            .execute(&state.db)
+            .await
+            .map_err(db_error("witcher_router"))?
+            .rows_affected();
+    if updated == 0 {
+        return Err(session_not_found());
+    }
+    tracing::info!(
+        "witcher_router: session {} witcher mode → {}",
+        session_id,
+        req.enabled
+    );
+    Ok(Json(json!({ "session_id": id, "enabled": req.enabled })))
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct CountRow {
+    key: String,
+    count: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct HistoryRow {
+    prompt_id: Option<i64>,
+    provider: String,
+    model: String,
+    task_type: String,
+    complexity: String,
+    reason: String,
+    decided_at: DateTime<Utc>,
+    latency_ms: Option<i64>,
+    success: Option<bool>,
+    cost_usd: Option<f64>,
+}
+
+/// `GET /api/witcher/routing-stats`
+pub async fn routing_stats(
+    State(state): State<AppState>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let count_by = |column: &str| {
+        format!(
+            "SELECT {0} AS key, COUNT(*) AS count FROM ch_witcher_routing \
+             GROUP BY {0} ORDER BY {0}",
+            column
+        )
+    };
+    let to_map = |rows: Vec<CountRow>| {
+        rows.into_iter()
+            .map(|r| (r.key, json!(r.count)))
+            .collect::<serde_json::Map<_, _>>()
+    };
+    let by_provider: Vec<CountRow> = sqlx::query_as(&count_by("provider"))
+        .fetch_all(&state.db)
+        .await
+        .map_err(db_error("witcher_router"))?;
+    let by_task_type: Vec<CountRow> = sqlx::query_as(&count_by("task_type"))
+        .fetch_all(&state.db)
+        .await
+        .map_err(db_error("witcher_router"))?;
+    let recent: Vec<HistoryRow> = sqlx::query_as(
+        "SELECT prompt_id, provider, model, task_type, complexity, reason, decided_at, \
+                latency_ms, success, cost_usd \
+         FROM ch_witcher_routing ORDER BY decided_at DESC LIMIT $1",
+    )
+    .bind(RECENT_IN_STATS)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("witcher_router"))?;
+    Ok(Json(json!({
+        "total": by_provider.iter().map(|r| r.count).sum::<i64>(),
+        "by_provider": to_map(by_provider),
+        "by_task_type": to_map(by_task_type),
+        "recent": recent,
+    })))
+}
+
+/// `GET /api/witcher/signs`
+pub async fn list_signs() -> Json<Value> {
+    let builtin: Vec<Value> = crate::swarm::WITCHER_SIGNS
+        .iter()
+        .map(|(trigger, pattern)| json!({ "trigger": trigger, "pattern": pattern }))
+        .collect();
+    let state = SIGNS.read().unwrap_or_else(|e| e.into_inner());
+    Json(json!({
+        "builtin": builtin,
+        "custom": state.signs,
+        "source": state.loaded.as_ref().map(|(path, _)| path.display().to_string()),
+        "error": state.error,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsQuery {
+    pub range: Option<String>,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AnalyticsRow {
+    provider: String,
+    task_type: String,
+    decisions: i64,
+    finished: i64,
+    succeeded: i64,
+    avg_latency_ms: Option<f64>,
+    p95_latency_ms: Option<f64>,
+    cost_usd: Option<f64>,
+}
+
+/// `GET /api/witcher/routing-analytics` — per provider / task type over `range`
+pub async fn get_routing_analytics(
+    State(state): State<AppState>,
+    Query(q): Query<AnalyticsQuery>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let (label, hours) = range_hours(q.range.as_deref()).ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "range must be '24h', '7d' or '30d'" })),
+        )
+    })?;
+    let rows: Vec<AnalyticsRow> = sqlx::query_as(
+        "SELECT provider, task_type, \
+                COUNT(*) AS decisions, \
+                COUNT(success) AS finished, \
+                COUNT(*) FILTER (WHERE success) AS succeeded, \
+                AVG(latency_ms)::FLOAT8 AS avg_latency_ms, \
+                PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY latency_ms) AS p95_latency_ms, \
+                SUM(cost_usd) AS cost_usd \
+         FROM ch_witcher_routing \
+         WHERE decided_at >= NOW() - make_interval(hours => $1) \
+         GROUP BY provider, task_type \
+         ORDER BY provider, task_type",
+    )
+    .bind(hours)
+    .fetch_all(&state.db)
+    .await
+    .map_err(db_error("witcher_router"))?;
+    let groups: Vec<Value> = rows
+        .into_iter()
+        .map(|r| {
+            json!({
+                "provider": r.provider,
+                "task_type": r.task_type,
+                "decisions": r.decisions,
+                "finished": r.finished,
+                "success_rate": success_rate(r.succeeded, r.finished),
+                "avg_latency_ms": r.avg_latency_ms,
+                "p95_latency_ms": r.p95_latency_ms,
+                "cost_usd": r.cost_usd,
+            })
+        })
+        .collect();
+    Ok(Json(json!({ "range": label, "groups": groups })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decisions_round_trip_through_json() {
+        let d = RouteDecision {
+            provider: "ollama".to_string(),
+            model: "ollama/llama3".to_string(),
+            task_type: "code".to_string(),
+            complexity: "simple".to_string(),
+            reason: String::new(),
+        };
+        let back: RouteDecision = serde_json::from_value(json!(d)).unwrap();
+        assert_eq!(back, d);
+    }
+
+    #[test]
+    fn custom_signs_are_parsed_with_defaults() {
+        let signs = parse_signs(
+            r#"
+            [signs.docgen]
+            providers = ["gemini", "hydra"]
+            system_prompt = "Write rustdoc."
+
+            [signs.second-opinion]
+            name = "Second opinion"
+            trigger = "Second"
+            pattern = "review"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(signs.len(), 2);
+        assert_eq!(signs[0].name, "docgen");
+        assert_eq!(signs[0].trigger, "docgen");
+        assert_eq!(signs[0].pattern, "sequential");
+        assert_eq!(signs[0].providers, vec!["geminihydra", "claudehydra"]);
+        assert_eq!(signs[0].system_prompt.as_deref(), Some("Write rustdoc."));
+        assert_eq!(signs[1].trigger, "second");
+        assert_eq!(signs[1].pattern, "review");
+        assert!(signs[1].providers.is_empty());
+    }
+
+    #[test]
+    fn invalid_custom_signs_are_rejected() {
+        for bad in [
+            "[signs.x]\ntrigger = \"igni\"",
+            "[signs.x]\ntrigger = \"two words\"",
+            "[signs.x]\npattern = \"swirl\"",
+            "[signs.x]\nmodel = \"gpt\"",
+            "[signs.a]\ntrigger = \"dup\"\n[signs.b]\ntrigger = \"dup\"",
+        ] {
+            assert!(parse_signs(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn ranges_parse_with_daily_default() {
+        assert_eq!(range_hours(None), Some(("24h", 24)));
+        assert_eq!(range_hours(Some("7d")), Some(("7d", 168)));
+        assert_eq!(range_hours(Some("30d")), Some(("30d", 720)));
+        assert_eq!(range_hours(Some("1y")), None);
+    }
+
+    #[test]
+    fn success_rate_needs_finished_runs() {
+        assert_eq!(success_rate(0, 0), None);
+        assert_eq!(success_rate(3, 4), Some(0.75));
+    }
+
+    #[test]
+    fn local_runs_are_free() {
+        use crate::costs::cost_usd;
+        assert_eq!(cost_usd("ollama/llama3", 1_000, 1_000), 0.0);
+        assert!(
+            cost_usd("claude-opus-4-6", 1_000, 1_000) > cost_usd("claude-haiku-4-5", 1_000, 1_000)
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use axum::http::StatusCode;
-use jaskier_core::testing::{body_json, get, post_json};
+use jaskier_core::testing::{body_json, delete, get, post_json};
 use tower::ServiceExt;
 
 use claudehydra_backend::state::AppState;
@@ -139,6 +139,150 @@ async fn set_api_key_returns_200() {
     assert_eq!(json["provider"], "anthropic");
 }
 
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/swarm/tasks/{id}/summary + /results
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn swarm_task_summary_unknown_returns_404() {
+    let response = app()
+        .oneshot(get("/api/swarm/tasks/does-not-exist/summary"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let json = body_json(response).await;
+    assert_eq!(json["error"], "task_not_found");
+}
+
+#[tokio::test]
+async fn swarm_task_results_unknown_returns_404() {
+    let response = app()
+        .oneshot(get("/api/swarm/tasks/does-not-exist/results?offset=0&limit=2"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  GET /api/schedule + /api/schedule.ics
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn schedule_lists_jobs_with_next_runs() {
+    let response = app().oneshot(get("/api/schedule?upcoming=3")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = body_json(response).await;
+    let jobs = json["jobs"].as_array().expect("jobs should be an array");
+    assert!(!jobs.is_empty());
+    assert_eq!(jobs[0]["next_runs"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn schedule_ics_is_a_calendar() {
+    let response = app().oneshot(get("/api/schedule.ics")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .starts_with("text/calendar")
+    );
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let ics = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.contains("RRULE:FREQ=MINUTELY;INTERVAL=1"));
+}
+
+/// Router over a migrated database; `None` (test skipped) unless
+/// `TEST_DATABASE_URL` points at a scratch Postgres.
+async fn db_app() -> Option<axum::Router> {
+    let url = std::env::var("TEST_DATABASE_URL").ok()?;
+    let db = sqlx::PgPool::connect(&url)
+        .await
+        .expect("connect TEST_DATABASE_URL");
+    sqlx::migrate!("./migrations")
+        .run(&db)
+        .await
+        .expect("migrations");
+    let state = AppState::new_test_with_db(db);
+    Some(claudehydra_backend::create_test_router(state))
+}
+
+#[tokio::test]
+async fn schedule_lists_scheduled_prompts() {
+    let Some(app) = db_app().await else {
+        return;
+    };
+    let response = app
+        .clone()
+        .oneshot(post_json(
+            "/api/scheduled-prompts",
+            serde_json::json!({ "prompt": "Nightly report\nwith details", "when": "0 3 * * *" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let id = body_json(response).await["id"].as_i64().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(get("/api/schedule?upcoming=2"))
+        .await
+        .unwrap();
+    let json = body_json(response).await;
+    let prompt = json["jobs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|j| j["prompt_id"] == id)
+        .expect("scheduled prompt should be listed");
+    assert_eq!(prompt["kind"], "prompt");
+    assert_eq!(prompt["id"], format!("prompt-{}", id));
+    assert_eq!(prompt["name"], "Nightly report");
+    assert_eq!(prompt["next_runs"].as_array().unwrap().len(), 2);
+
+    let response = app.clone().oneshot(get("/api/schedule.ics")).await.unwrap();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let ics = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(ics.contains(&format!("UID:prompt-{}-0@claudehydra", id)));
+    assert!(ics.contains("SUMMARY:Nightly report"));
+
+    let response = app
+        .oneshot(delete(&format!("/api/scheduled-prompts/{}", id)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+// ═══════════════════════════════════════════════════════════════════════════
+//  POST /api/swarm/plan (dry run)
+// ═══════════════════════════════════════════════════════════════════════════
+
+#[tokio::test]
+async fn swarm_plan_review_is_sequential_and_not_executed() {
+    let body = serde_json::json!({
+        "prompt": "Refactor src/state.rs and update tests/api_tests.rs",
+        "pattern": "review",
+        "targets": ["geminihydra", "grokhydra"],
+    });
+    let response = app().oneshot(post_json("/api/swarm/plan", body)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = body_json(response).await;
+    assert_eq!(json["dry_run"], true);
+    let steps = json["steps"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1]["role"], "reviewer");
+    assert_eq!(steps[1]["depends_on"][0], 0);
+    assert_eq!(json["files_likely_touched"].as_array().unwrap().len(), 2);
+    assert_eq!(json["execute"]["path"], "/api/swarm/delegate");
+}
+
 // ═══════════════════════════════════════════════════════════════════════════
 //  404 for unknown routes
 // ═══════════════════════════════════════════════════════════════════════════
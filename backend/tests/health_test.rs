@@ -55,6 +55,47 @@ async fn readiness_endpoint_exists() {
     assert_ne!(response.status(), StatusCode::NOT_FOUND);
 }
 
+#[tokio::test]
+async fn startup_report_is_503_before_warm_start() {
+    let response = test_app()
+        .oneshot(get("/api/health/startup"))
+        .await
+        .unwrap();
+    // new_test() never runs the warm-up, so no report is stored.
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let json = body_json(response).await;
+    assert_eq!(json["ready"], false);
+}
+
+#[tokio::test]
+async fn startup_report_leaves_unfinished_checks_null() {
+    let state = AppState::new_test();
+    *state.startup_report.write().await = Some(claudehydra_backend::models::StartupReport {
+        ready: true,
+        model_sync_ok: true,
+        model_sync_attempts: 1,
+        prompt_cache_warmed: true,
+        anthropic_reachable: true,
+        vault_reachable: Some(false),
+        mcp_connected: None,
+        agents_loaded: 0,
+        tools_available: 0,
+        warmup_ms: 1,
+        completed_at: chrono::Utc::now().to_rfc3339(),
+    });
+    let response = claudehydra_backend::create_test_router(state)
+        .oneshot(get("/api/health/startup"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json = body_json(response).await;
+    assert_eq!(json["vault_reachable"], false);
+    // MCP still connecting: unknown rather than reported as down.
+    assert!(json["mcp_connected"].is_null());
+}
+
 #[tokio::test]
 async fn nonexistent_route_returns_404() {
     let response = test_app()
@@ -5,6 +5,7 @@
  * and enable reuse / unit testing of transport logic.
  */
 
+import type { StreamEvent } from '@/shared/api/schemas';
 import { env } from '@/shared/config/env';
 
 // ---------------------------------------------------------------------------
@@ -12,7 +13,7 @@ import { env } from '@/shared/config/env';
 // ---------------------------------------------------------------------------
 
 /** Extended NDJSON chunk — may be a text token, tool_call, tool_result, or fallback notification. */
-interface NdjsonEvent {
+interface NdjsonEvent extends Partial<StreamEvent> {
   // Text token (backward-compatible)
   token?: string;
   done?: boolean;
@@ -0,0 +1,58 @@
+import { act, fireEvent, render, screen, waitFor } from '@testing-library/react';
+import { describe, expect, it, vi } from 'vitest';
+import { useApprovals } from '@/shared/hooks/useApprovals';
+import { parseServerMessage } from '@/shared/hooks/useWebSocketChat';
+import { ApprovalPrompt } from './ApprovalPrompt';
+
+const { mockApiPost } = vi.hoisted(() => ({ mockApiPost: vi.fn() }));
+
+vi.mock('@jaskier/chat-module', () => ({
+  MAX_RECONNECT_ATTEMPTS: 5,
+  useWebSocketChat: vi.fn(),
+}));
+
+vi.mock('@/shared/api/client', () => ({
+  ApiError: class ApiError extends Error {
+    status = 500;
+  },
+  apiPost: mockApiPost,
+}));
+
+function Harness() {
+  const { pending, decide } = useApprovals();
+  return <ApprovalPrompt pending={pending} onDecide={(id, decision) => void decide(id, decision)} />;
+}
+
+describe('ApprovalPrompt', () => {
+  it('shows a gated tool call from the WebSocket and sends the decision', async () => {
+    mockApiPost.mockResolvedValue({ id: 'appr-1', status: 'approved' });
+    render(<Harness />);
+
+    act(() => {
+      const msg = parseServerMessage({
+        type: 'approval_required',
+        id: 'appr-1',
+        tool: 'write_file',
+        input: { path: 'src/main.rs' },
+      });
+      expect(msg?.type).toBe('approval_required');
+    });
+
+    expect(screen.getByRole('alertdialog', { name: /write_file/ })).toBeInTheDocument();
+    expect(screen.getByText(/src\/main\.rs/)).toBeInTheDocument();
+
+    fireEvent.click(screen.getByRole('button', { name: /Zatwierdź/ }));
+    await waitFor(() => expect(mockApiPost).toHaveBeenCalledWith('/api/approvals/appr-1', { decision: 'approve' }));
+  });
+
+  it('sends a denial', async () => {
+    mockApiPost.mockResolvedValue({ id: 'appr-2', status: 'denied' });
+    render(<Harness />);
+
+    act(() => {
+      parseServerMessage({ type: 'approval_required', id: 'appr-2', tool: 'git_commit', input: {} });
+    });
+    fireEvent.click(screen.getByRole('button', { name: /Odrzuć/ }));
+    await waitFor(() => expect(mockApiPost).toHaveBeenCalledWith('/api/approvals/appr-2', { decision: 'deny' }));
+  });
+});
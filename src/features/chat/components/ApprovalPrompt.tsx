@@ -0,0 +1,69 @@
+/**
+ * ApprovalPrompt — tool calls waiting for the user's approval (`ask` mode).
+ *
+ * Lists each pending call with its input and Approve / Deny buttons. The
+ * model's turn stays paused until every call is decided or expires.
+ */
+
+import { Check, ShieldQuestion, X } from 'lucide-react';
+import { AnimatePresence, motion } from 'motion/react';
+import type { ApprovalDecision, PendingApproval } from '@/shared/hooks/useApprovals';
+
+interface ApprovalPromptProps {
+  pending: PendingApproval[];
+  onDecide: (id: string, decision: ApprovalDecision) => void;
+}
+
+function formatInput(input: unknown): string {
+  const text = typeof input === 'string' ? input : JSON.stringify(input, null, 2);
+  return text.length > 500 ? `${text.slice(0, 500)}…` : text;
+}
+
+export function ApprovalPrompt({ pending, onDecide }: ApprovalPromptProps) {
+  return (
+    <AnimatePresence>
+      {pending.map((approval) => (
+        <motion.div
+          key={approval.id}
+          initial={{ opacity: 0, y: -20, height: 0 }}
+          animate={{ opacity: 1, y: 0, height: 'auto' }}
+          exit={{ opacity: 0, y: -20, height: 0 }}
+          transition={{ duration: 0.3, ease: 'easeOut' }}
+          className="overflow-hidden"
+        >
+          <div
+            role="alertdialog"
+            aria-label={`Zatwierdź narzędzie ${approval.tool}`}
+            className="flex items-start gap-2 px-3 py-2 rounded-lg bg-sky-500/15 border border-sky-500/30 text-sky-100 text-sm font-mono mb-2"
+          >
+            <ShieldQuestion size={16} className="text-sky-400 shrink-0 mt-0.5" />
+            <div className="flex-1 min-w-0">
+              <div>
+                Narzędzie <strong>{approval.tool}</strong> czeka na zgodę
+              </div>
+              <pre className="mt-1 max-h-32 overflow-auto whitespace-pre-wrap break-all text-xs opacity-80">
+                {formatInput(approval.input)}
+              </pre>
+            </div>
+            <button
+              type="button"
+              onClick={() => onDecide(approval.id, 'approve')}
+              className="flex items-center gap-1 px-2 py-1 rounded bg-emerald-500/20 hover:bg-emerald-500/30 transition-colors shrink-0"
+            >
+              <Check size={14} />
+              Zatwierdź
+            </button>
+            <button
+              type="button"
+              onClick={() => onDecide(approval.id, 'deny')}
+              className="flex items-center gap-1 px-2 py-1 rounded bg-red-500/20 hover:bg-red-500/30 transition-colors shrink-0"
+            >
+              <X size={14} />
+              Odrzuć
+            </button>
+          </div>
+        </motion.div>
+      ))}
+    </AnimatePresence>
+  );
+}
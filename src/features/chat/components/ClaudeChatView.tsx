@@ -24,17 +24,21 @@ import type { ModelOption } from '@/components/molecules/ModelSelector';
 import { useAutoScroll } from '@/features/chat/hooks/useAutoScroll';
 import { type ClaudeModel, FALLBACK_CLAUDE_MODELS, useClaudeModels } from '@/features/chat/hooks/useClaudeModels';
 import { useSessionSync } from '@/features/chat/hooks/useSessionSync';
+import { useApprovals } from '@/shared/hooks/useApprovals';
+import { useSafetyAlerts } from '@/shared/hooks/useSafetyAlerts';
 import { useSettingsQuery } from '@/shared/hooks/useSettings';
 import { useWebSocketChat } from '@/shared/hooks/useWebSocketChat';
 import { useViewStore } from '@/stores/viewStore';
 import { claudeHealthCheck, DEFAULT_MODEL } from '../api/claudeStream';
 import { useChatMessages } from '../hooks/useChatMessages';
 import { type FallbackInfo, useChatStreaming } from '../hooks/useChatStreaming';
+import { ApprovalPrompt } from './ApprovalPrompt';
 import { ChatHeader } from './ChatHeader';
 import { type Attachment, ChatInput, type ChatInputHandle } from './ChatInput';
 import { CompletionFeedback } from './CompletionFeedback';
 import { FallbackBanner, type FallbackBannerData } from './FallbackBanner';
 import type { ChatMessage } from './MessageBubble';
+import { SafetyAlertBanner } from './SafetyAlertBanner';
 import { StreamingIndicator } from './StreamingIndicator';
 import { VirtualizedMessageArea } from './VirtualizedMessageArea';
 
@@ -197,6 +201,12 @@ export function ClaudeChatView() {
     setFallbackBanner(null);
   }, []);
 
+  // Tool calls waiting for approval (`ask` permission mode)
+  const { pending: pendingApprovals, decide: decideApproval } = useApprovals();
+
+  // Safety guard warnings (secrets in the prompt, injection in the response)
+  const { alert: safetyAlert, dismiss: dismissSafetyAlert } = useSafetyAlerts();
+
   const flushTokenBatch = useCallback(() => {
     const batch = tokenBatchRef.current;
     if (!batch) return;
@@ -506,6 +516,12 @@ export function ClaudeChatView() {
       {/* Model fallback notification */}
       <FallbackBanner data={fallbackBanner} onDismiss={dismissFallbackBanner} />
 
+      {/* Safety guard warning */}
+      <SafetyAlertBanner message={safetyAlert} onDismiss={dismissSafetyAlert} />
+
+      {/* Tool calls waiting for approval */}
+      <ApprovalPrompt pending={pendingApprovals} onDecide={(id, decision) => void decideApproval(id, decision)} />
+
       {/* #1 Virtualized message area */}
       <div className="flex-1 min-h-0 flex relative overflow-hidden gap-2">
         <VirtualizedMessageArea
@@ -0,0 +1,29 @@
+import { act, fireEvent, render, screen } from '@testing-library/react';
+import { describe, expect, it, vi } from 'vitest';
+import { useSafetyAlerts } from '@/shared/hooks/useSafetyAlerts';
+import { parseServerMessage } from '@/shared/hooks/useWebSocketChat';
+import { SafetyAlertBanner } from './SafetyAlertBanner';
+
+vi.mock('@jaskier/chat-module', () => ({
+  MAX_RECONNECT_ATTEMPTS: 5,
+  useWebSocketChat: vi.fn(),
+}));
+
+function Harness() {
+  const { alert, dismiss } = useSafetyAlerts();
+  return <SafetyAlertBanner message={alert} onDismiss={dismiss} />;
+}
+
+describe('SafetyAlertBanner', () => {
+  it('shows a safety alert received over the WebSocket', () => {
+    render(<Harness />);
+    expect(screen.queryByRole('alert')).not.toBeInTheDocument();
+
+    act(() => {
+      parseServerMessage({ type: 'safety_alert', message: 'The response contains a prompt injection pattern' });
+    });
+
+    expect(screen.getByRole('alert')).toHaveTextContent('prompt injection pattern');
+    fireEvent.click(screen.getByRole('button', { name: 'Zamknij' }));
+  });
+});
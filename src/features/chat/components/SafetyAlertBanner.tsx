@@ -0,0 +1,46 @@
+/**
+ * SafetyAlertBanner — Red notification when the safety guard flags the
+ * prompt or the response without blocking it.
+ *
+ * Stays until closed — the user should read it before relying on the reply.
+ */
+
+import { ShieldAlert, X } from 'lucide-react';
+import { AnimatePresence, motion } from 'motion/react';
+
+interface SafetyAlertBannerProps {
+  message: string | null;
+  onDismiss: () => void;
+}
+
+export function SafetyAlertBanner({ message, onDismiss }: SafetyAlertBannerProps) {
+  return (
+    <AnimatePresence>
+      {message && (
+        <motion.div
+          initial={{ opacity: 0, y: -20, height: 0 }}
+          animate={{ opacity: 1, y: 0, height: 'auto' }}
+          exit={{ opacity: 0, y: -20, height: 0 }}
+          transition={{ duration: 0.3, ease: 'easeOut' }}
+          className="overflow-hidden"
+        >
+          <div
+            role="alert"
+            className="flex items-center gap-2 px-3 py-2 rounded-lg bg-red-500/15 border border-red-500/30 text-red-200 text-sm font-mono mb-2"
+          >
+            <ShieldAlert size={16} className="text-red-400 shrink-0" />
+            <span className="flex-1">{message}</span>
+            <button
+              type="button"
+              onClick={onDismiss}
+              className="p-0.5 rounded hover:bg-red-500/20 transition-colors shrink-0"
+              aria-label="Zamknij"
+            >
+              <X size={14} />
+            </button>
+          </div>
+        </motion.div>
+      )}
+    </AnimatePresence>
+  );
+}
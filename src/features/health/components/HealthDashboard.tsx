@@ -4,13 +4,14 @@
  * ClaudeHydra v4 - Health Dashboard
  * ===================================
  * Compact grid of stat cards showing backend status, auth mode,
- * system resources, model cache size, and uptime.
+ * system resources, model cache size, uptime, and the background queue,
+ * A2A tasks and open tabs from the backend's metrics snapshot.
  */
 
 import { useViewTheme } from '@jaskier/chat-module';
 import { QueryError } from '@jaskier/hydra-app/components/molecules';
 import { BaseMetricsDashboard, Card, cn } from '@jaskier/ui';
-import { Clock, RefreshCw, Shield } from 'lucide-react';
+import { Clock, Layers, ListTodo, MonitorSmartphone, RefreshCw, Shield } from 'lucide-react';
 import { memo, type ReactNode, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { useHealthDashboard } from '../hooks/useHealthDashboard';
@@ -174,6 +175,31 @@ export const HealthDashboard = memo(() => {
           label={t('health.uptime', 'Uptime')}
           value={data.uptimeSeconds !== null ? formatUptime(data.uptimeSeconds) : '--'}
         />
+
+        {/* Background queue (metrics snapshot) */}
+        <StatCard
+          icon={<ListTodo size={16} />}
+          label={t('health.backgroundQueue', 'Queue (Running / Queued)')}
+          value={
+            data.metrics
+              ? `${String(data.metrics.queue.background_running)} / ${String(data.metrics.queue.background_queued)}`
+              : '--'
+          }
+        />
+
+        {/* In-flight A2A delegations */}
+        <StatCard
+          icon={<Layers size={16} />}
+          label={t('health.a2aInFlight', 'A2A Tasks')}
+          value={data.metrics ? String(data.metrics.queue.a2a_in_flight) : '--'}
+        />
+
+        {/* Open frontend tabs */}
+        <StatCard
+          icon={<MonitorSmartphone size={16} />}
+          label={t('health.activeSessions', 'Open Tabs')}
+          value={data.metrics ? String(data.metrics.active_sessions) : '--'}
+        />
       </div>
     </div>
   );
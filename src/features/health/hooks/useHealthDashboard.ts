@@ -3,8 +3,9 @@
 /**
  * ClaudeHydra v4 - Health Dashboard Hook
  * ========================================
- * Aggregates health, auth mode, system stats, and model count
- * for the HealthDashboard component. Reuses existing TanStack Query hooks
+ * Aggregates health, auth mode, system stats, model count and the backend's
+ * periodic metrics snapshot (queue, providers, open tabs) for the
+ * HealthDashboard component. Reuses existing TanStack Query hooks
  * where available and adds new lightweight queries.
  */
 
@@ -29,6 +30,28 @@ const ModelsResponseSchema = z
   .passthrough();
 type ModelsResponse = z.infer<typeof ModelsResponseSchema>;
 
+/** `GET /api/metrics/snapshot` — the backend's `metrics.json`, refreshed every ~15 s. */
+const MetricsSnapshotSchema = z.object({
+  generated_at: z.string(),
+  queue: z.object({
+    background_queued: z.number(),
+    background_running: z.number(),
+    background_done_24h: z.number(),
+    background_failed_24h: z.number(),
+    a2a_in_flight: z.number(),
+  }),
+  providers: z.array(
+    z.object({
+      provider: z.string(),
+      requests_24h: z.number(),
+      failures_24h: z.number(),
+      tokens_24h: z.number(),
+    }),
+  ),
+  active_sessions: z.number(),
+});
+export type MetricsSnapshot = z.infer<typeof MetricsSnapshotSchema>;
+
 interface HealthDashboardData {
   backendOnline: boolean;
   uptimeSeconds: number | null;
@@ -37,7 +60,7 @@ interface HealthDashboardData {
   memoryUsedMb: number | null;
   memoryTotalMb: number | null;
   modelCount: number | null;
-  metrics: unknown;
+  metrics: MetricsSnapshot | null;
   audit: unknown;
   loading: boolean;
   error: boolean;
@@ -78,10 +101,13 @@ export function useHealthDashboard(): HealthDashboardData {
     enabled: backendOnline, // don't poll when backend is down
   });
 
-  const metricsQuery = useQuery<unknown>({
-    queryKey: ['system', 'metrics'],
-    queryFn: () => apiGetPolling<unknown>('/api/system/metrics'),
-    refetchInterval: 60_000,
+  const metricsQuery = useQuery<MetricsSnapshot>({
+    queryKey: ['metrics', 'snapshot'],
+    queryFn: async () => {
+      const data = await apiGetPolling<unknown>('/api/metrics/snapshot');
+      return MetricsSnapshotSchema.parse(data);
+    },
+    refetchInterval: 15_000,
     retry: (failureCount) => failureCount < 3,
     retryDelay: (attemptIndex) => Math.min(1000 * 2 ** attemptIndex, 30000),
     enabled: backendOnline,
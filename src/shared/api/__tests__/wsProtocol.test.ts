@@ -38,6 +38,33 @@ describe('wsServerMessageSchema — WebSocket protocol validation', () => {
       const result = wsServerMessageSchema.safeParse({ type: 'token', content: '' });
       expect(result.success).toBe(true);
     });
+
+    it('should keep canonical stream event fields', () => {
+      const token = wsServerMessageSchema.parse({
+        type: 'token',
+        content: 'Hi',
+        request_id: 'req-1',
+        provider: 'anthropic',
+        delta: 'Hi',
+      });
+      if (token.type === 'token') {
+        expect(token.delta).toBe('Hi');
+        expect(token.request_id).toBe('req-1');
+      }
+      const complete = wsServerMessageSchema.parse({
+        type: 'complete',
+        duration_ms: 10,
+        request_id: 'req-1',
+        provider: 'anthropic',
+        delta: '',
+        usage: { input_tokens: 12, output_tokens: 3 },
+        finish_reason: 'stop',
+      });
+      if (complete.type === 'complete') {
+        expect(complete.finish_reason).toBe('stop');
+        expect(complete.usage?.output_tokens).toBe(3);
+      }
+    });
   });
 
   describe('tool lifecycle messages', () => {
@@ -82,6 +109,39 @@ describe('wsServerMessageSchema — WebSocket protocol validation', () => {
         expect(result.tools_total).toBe(5);
       }
     });
+
+    it('should parse tool_progress with structured progress', () => {
+      const msg = {
+        type: 'tool_progress',
+        iteration: 1,
+        tools_completed: 1,
+        tools_total: 4,
+        progress: {
+          kind: 'determinate',
+          phase: 'running_tools',
+          current: 1,
+          total: 4,
+          percent: 25,
+          label: '1 of 4 tools finished',
+        },
+      };
+      const result = wsServerMessageSchema.parse(msg);
+      if (result.type === 'tool_progress') {
+        expect(result.progress?.percent).toBe(25);
+      }
+    });
+
+    it('should parse indeterminate phase message', () => {
+      const msg = {
+        type: 'phase',
+        progress: { kind: 'indeterminate', phase: 'awaiting_model', label: 'Waiting for claude-sonnet-4-6' },
+      };
+      const result = wsServerMessageSchema.parse(msg);
+      if (result.type === 'phase') {
+        expect(result.progress.kind).toBe('indeterminate');
+        expect(result.progress.current).toBeUndefined();
+      }
+    });
   });
 
   describe('control messages', () => {
@@ -160,6 +220,50 @@ describe('wsServerMessageSchema — WebSocket protocol validation', () => {
     });
   });
 
+  describe('sources messages', () => {
+    it('should parse sources with citation fields', () => {
+      const result = wsServerMessageSchema.parse({
+        type: 'sources',
+        sources: [{ title: 'Rust 1.90', url: 'https://blog.rust-lang.org', snippet: 'Released today' }],
+      });
+      if (result.type === 'sources') {
+        expect(result.sources[0]?.url).toBe('https://blog.rust-lang.org');
+      }
+    });
+
+    it('should reject a source without url', () => {
+      const msg = { type: 'sources', sources: [{ title: 'x', snippet: '' }] };
+      expect(wsServerMessageSchema.safeParse(msg).success).toBe(false);
+    });
+  });
+
+  describe('approval_required messages', () => {
+    it('should parse approval_required with any input', () => {
+      const result = wsServerMessageSchema.parse({
+        type: 'approval_required',
+        id: '7d0c3c1e-0000-4000-8000-000000000001',
+        tool: 'write_file',
+        input: { path: 'a.ts' },
+      });
+      if (result.type === 'approval_required') {
+        expect(result.tool).toBe('write_file');
+      }
+    });
+
+    it('should reject approval_required without id', () => {
+      expect(wsServerMessageSchema.safeParse({ type: 'approval_required', tool: 'x', input: {} }).success).toBe(false);
+    });
+  });
+
+  describe('safety_alert messages', () => {
+    it('should parse safety_alert', () => {
+      const result = wsServerMessageSchema.parse({ type: 'safety_alert', message: 'Prompt contains an API key' });
+      if (result.type === 'safety_alert') {
+        expect(result.message).toBe('Prompt contains an API key');
+      }
+    });
+  });
+
   describe('rejection of invalid messages', () => {
     it('should reject unknown type', () => {
       expect(wsServerMessageSchema.safeParse({ type: 'unknown' }).success).toBe(false);
@@ -206,4 +310,9 @@ describe('WsClientMessage type', () => {
     const msg: WsClientMessage = { type: 'ping' };
     expect(msg.type).toBe('ping');
   });
+
+  it('should accept approval_decision message', () => {
+    const msg: WsClientMessage = { type: 'approval_decision', id: 'appr-1', decision: 'deny', reason: 'wrong file' };
+    expect(msg.type).toBe('approval_decision');
+  });
 });
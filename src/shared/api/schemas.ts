@@ -84,6 +84,17 @@ const usageSchema = z.object({
 
 export type Usage = z.infer<typeof usageSchema>;
 
+/** Canonical stream event fields sent by every streaming path (backend `stream_event.rs`). */
+export const streamEventSchema = z.object({
+  request_id: z.string(),
+  provider: z.string(),
+  delta: z.string(),
+  usage: usageSchema.optional(),
+  finish_reason: z.string().optional(),
+});
+
+export type StreamEvent = z.infer<typeof streamEventSchema>;
+
 export const claudeChatResponseSchema = z.object({
   content: z.string(),
   model: z.string(),
@@ -216,6 +227,17 @@ export type OcrResponse = z.infer<typeof ocrResponseSchema>;
 // WebSocket Protocol
 // ---------------------------------------------------------------------------
 
+const progressSchema = z.object({
+  kind: z.enum(['determinate', 'indeterminate']),
+  phase: z.string(),
+  current: z.number().optional(),
+  total: z.number().optional(),
+  percent: z.number().optional(),
+  label: z.string(),
+});
+
+export type Progress = z.infer<typeof progressSchema>;
+
 const wsStartSchema = z.object({
   type: z.literal('start'),
   id: z.string(),
@@ -223,12 +245,12 @@ const wsStartSchema = z.object({
   files_loaded: z.array(z.string()).optional().default([]),
 });
 
-const wsTokenSchema = z.object({
+const wsTokenSchema = streamEventSchema.partial().extend({
   type: z.literal('token'),
   content: z.string(),
 });
 
-const wsCompleteSchema = z.object({
+const wsCompleteSchema = streamEventSchema.partial().extend({
   type: z.literal('complete'),
   duration_ms: z.number(),
 });
@@ -259,12 +281,19 @@ const wsToolProgressSchema = z.object({
   iteration: z.number(),
   tools_completed: z.number(),
   tools_total: z.number(),
+  progress: progressSchema.optional(),
 });
 
 const wsIterationSchema = z.object({
   type: z.literal('iteration'),
   number: z.number(),
   max: z.number(),
+  progress: progressSchema.optional(),
+});
+
+const wsPhaseSchema = z.object({
+  type: z.literal('phase'),
+  progress: progressSchema,
 });
 
 const wsPongSchema = z.object({
@@ -287,6 +316,29 @@ const wsViewHintSchema = z.object({
   views: z.array(z.string()),
 });
 
+const wsSourcesSchema = z.object({
+  type: z.literal('sources'),
+  sources: z.array(
+    z.object({
+      title: z.string(),
+      url: z.string(),
+      snippet: z.string(),
+    }),
+  ),
+});
+
+const wsApprovalRequiredSchema = z.object({
+  type: z.literal('approval_required'),
+  id: z.string(),
+  tool: z.string(),
+  input: z.unknown(),
+});
+
+const wsSafetyAlertSchema = z.object({
+  type: z.literal('safety_alert'),
+  message: z.string(),
+});
+
 export const wsServerMessageSchema = z.discriminatedUnion('type', [
   wsStartSchema,
   wsTokenSchema,
@@ -296,10 +348,14 @@ export const wsServerMessageSchema = z.discriminatedUnion('type', [
   wsToolResultSchema,
   wsToolProgressSchema,
   wsIterationSchema,
+  wsPhaseSchema,
   wsPongSchema,
   wsHeartbeatSchema,
   wsFallbackSchema,
   wsViewHintSchema,
+  wsSourcesSchema,
+  wsApprovalRequiredSchema,
+  wsSafetyAlertSchema,
 ]);
 
 export type WsServerMessage = z.infer<typeof wsServerMessageSchema>;
@@ -309,10 +365,23 @@ export type WsToolCallMessage = z.infer<typeof wsToolCallSchema>;
 export type WsToolResultMessage = z.infer<typeof wsToolResultSchema>;
 export type WsToolProgressMessage = z.infer<typeof wsToolProgressSchema>;
 export type WsIterationMessage = z.infer<typeof wsIterationSchema>;
+export type WsPhaseMessage = z.infer<typeof wsPhaseSchema>;
 export type WsFallbackMessage = z.infer<typeof wsFallbackSchema>;
 export type WsViewHintMessage = z.infer<typeof wsViewHintSchema>;
+export type WsSourcesMessage = z.infer<typeof wsSourcesSchema>;
+export type WsApprovalRequiredMessage = z.infer<typeof wsApprovalRequiredSchema>;
+export type WsSafetyAlertMessage = z.infer<typeof wsSafetyAlertSchema>;
 
 export type WsClientMessage =
-  | { type: 'execute'; prompt: string; model?: string; tools_enabled?: boolean; session_id?: string }
+  | {
+      type: 'execute';
+      prompt: string;
+      model?: string;
+      tools_enabled?: boolean;
+      session_id?: string;
+      web_search?: boolean;
+      speak?: boolean;
+    }
   | { type: 'cancel' }
-  | { type: 'ping' };
+  | { type: 'ping' }
+  | { type: 'approval_decision'; id: string; decision: 'approve' | 'deny'; reason?: string };
@@ -0,0 +1,58 @@
+/**
+ * useApprovals — tool calls waiting for the user's approval (`ask` mode).
+ *
+ * The WS message parser dispatches every `approval_required` message as an
+ * `approvalrequired` DOM event; this hook collects them until decided.
+ * Decisions go to POST /api/approvals/:id — the shared WS hook has no raw
+ * send, and the backend resolves the waiting tool call either way.
+ */
+
+import { useCallback, useEffect, useState } from 'react';
+import { toast } from 'sonner';
+import { ApiError, apiPost } from '@/shared/api/client';
+import type { WsApprovalRequiredMessage } from '@/shared/api/schemas';
+
+export type ApprovalDecision = 'approve' | 'deny';
+
+export interface PendingApproval {
+  id: string;
+  tool: string;
+  input: unknown;
+}
+
+/** Call this from the WS message parser when `approval_required` arrives. */
+export function dispatchApprovalRequired(msg: WsApprovalRequiredMessage): void {
+  window.dispatchEvent(
+    new CustomEvent<PendingApproval>('approvalrequired', {
+      detail: { id: msg.id, tool: msg.tool, input: msg.input },
+    }),
+  );
+}
+
+export function useApprovals() {
+  const [pending, setPending] = useState<PendingApproval[]>([]);
+
+  useEffect(() => {
+    function handleApprovalRequired(e: Event) {
+      const approval = (e as CustomEvent<PendingApproval>).detail;
+      setPending((prev) => (prev.some((p) => p.id === approval.id) ? prev : [...prev, approval]));
+    }
+    window.addEventListener('approvalrequired', handleApprovalRequired);
+    return () => window.removeEventListener('approvalrequired', handleApprovalRequired);
+  }, []);
+
+  const decide = useCallback(async (id: string, decision: ApprovalDecision) => {
+    try {
+      await apiPost(`/api/approvals/${id}`, { decision });
+    } catch (err) {
+      // 404: already decided or expired — nothing is waiting any more.
+      if (!(err instanceof ApiError && err.status === 404)) {
+        toast.error('Nie udało się zapisać decyzji');
+        return;
+      }
+    }
+    setPending((prev) => prev.filter((p) => p.id !== id));
+  }, []);
+
+  return { pending, decide };
+}
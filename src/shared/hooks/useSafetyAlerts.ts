@@ -0,0 +1,31 @@
+/**
+ * useSafetyAlerts — warnings from the backend safety guard.
+ *
+ * The guard flags secrets in prompts and injection patterns in responses
+ * without blocking them; the WS message parser dispatches each
+ * `safety_alert` message as a `safetyalert` DOM event.
+ */
+
+import { useCallback, useEffect, useState } from 'react';
+
+/** Call this from the WS message parser when `safety_alert` arrives. */
+export function dispatchSafetyAlert(message: string): void {
+  window.dispatchEvent(new CustomEvent<string>('safetyalert', { detail: message }));
+}
+
+/** The latest safety alert, until dismissed. */
+export function useSafetyAlerts() {
+  const [alert, setAlert] = useState<string | null>(null);
+
+  useEffect(() => {
+    function handleSafetyAlert(e: Event) {
+      setAlert((e as CustomEvent<string>).detail);
+    }
+    window.addEventListener('safetyalert', handleSafetyAlert);
+    return () => window.removeEventListener('safetyalert', handleSafetyAlert);
+  }, []);
+
+  const dismiss = useCallback(() => setAlert(null), []);
+
+  return { alert, dismiss };
+}
@@ -28,7 +28,9 @@ import type {
 } from '@/shared/api/schemas';
 import { wsServerMessageSchema } from '@/shared/api/schemas';
 import { env } from '@/shared/config/env';
+import { dispatchApprovalRequired } from '@/shared/hooks/useApprovals';
 import { dispatchViewHint } from '@/shared/hooks/usePredictivePrefetch';
+import { dispatchSafetyAlert } from '@/shared/hooks/useSafetyAlerts';
 
 export type { WsStatus } from '@jaskier/chat-module';
 // Re-export shared constants and types for backward compatibility
@@ -75,7 +77,7 @@ function getWsUrl(): string {
 // MESSAGE PARSER (Zod validation)
 // ============================================================================
 
-function parseServerMessage(
+export function parseServerMessage(
   raw: unknown,
 ): { type: string; content?: string; message?: string; [key: string]: unknown } | null {
   const parsed = wsServerMessageSchema.safeParse(raw);
@@ -87,6 +89,16 @@ function parseServerMessage(
     dispatchViewHint(msg.views as string[]);
   }
 
+  // Tool calls waiting for approval are rendered by useApprovals consumers
+  if (msg.type === 'approval_required') {
+    dispatchApprovalRequired(msg);
+  }
+
+  // Safety guard warnings are rendered by useSafetyAlerts consumers
+  if (msg.type === 'safety_alert') {
+    dispatchSafetyAlert(msg.message);
+  }
+
   return msg;
 }
 